@@ -4,19 +4,27 @@ use std::collections::HashMap;
 use std::sync::LazyLock;
 
 use axum::extract::multipart::MultipartError;
-use axum::http::StatusCode;
+use axum::http::{HeaderValue, StatusCode, header};
 use axum::response::{IntoResponse, Response};
-use chrono::{NaiveDateTime, NaiveTime};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use diesel::result::DatabaseErrorKind;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
 use thiserror::Error;
-use tokio::sync::mpsc;
+use utoipa::ToSchema;
 
 /// Top level application error, can be converted into a [`Response`]
 #[derive(Debug, Error)]
 pub enum Error {
-	/// Duplicate resource created
+	/// The request can not be handled in the current state
+	#[error("{0}")]
+	BadRequest(String),
+	/// The request conflicts with the current state of a resource
 	#[error("{0}")]
-	Duplicate(String),
+	Conflict(String),
+	/// Duplicate resource created
+	#[error("{message}")]
+	Duplicate { message: String, resource_id: Option<i32> },
 	/// Request/operation forbidden
 	#[error("forbidden")]
 	Forbidden,
@@ -29,18 +37,42 @@ pub enum Error {
 	/// Decoding an image failed somehow
 	#[error("{0}")]
 	InvalidImage(String),
+	/// An uploaded image was refused before decoding it
+	#[error(transparent)]
+	ImageUploadError(#[from] ImageUploadError),
 	/// The request attempted to set invalid permissions
 	#[error("invalid permissions")]
 	InvalidRolePermissions,
+	/// An institution can not be deleted while authorities are still linked
+	/// to it
+	#[error("institution is still linked to authorities {authority_ids:?}")]
+	InstitutionInUse { authority_ids: Vec<i32> },
+	/// The last administrator of a location or authority can not lose their
+	/// administrator role
+	#[error("the last administrator can not be removed")]
+	LastAdministrator,
 	/// Resource not found
 	#[error("not found - {0}")]
 	NotFound(String),
 	/// Any error related to logging in
 	#[error(transparent)]
 	LoginError(#[from] LoginError),
+	/// The request asked to include related records that do not exist
+	#[error("unknown includes {keys:?}")]
+	UnknownIncludes { keys: Vec<String>, valid: Vec<String> },
 	/// Some data in the request was missing
 	#[error("{0}")]
 	MissingRequestData(String),
+	/// The location already has opening times on some of the given days
+	#[error("location already has opening times on {days:?}")]
+	OpeningTimeCollision { days: Vec<NaiveDate> },
+	/// A role can not be deleted while members still hold it
+	#[error("role is still assigned to {member_count} member(s)")]
+	RoleInUse { member_count: i64 },
+	/// A tag can not be deleted while locations still use it, unless the
+	/// deletion is forced
+	#[error("tag is still used by {location_count} location(s)")]
+	TagInUse { location_count: i64 },
 	/// Any error related to deserializing multipart data
 	#[error(transparent)]
 	MultipartSerializationError(#[from] MultipartError),
@@ -59,25 +91,127 @@ pub enum Error {
 	/// Any error related to creating a reservation
 	#[error(transparent)]
 	CreateReservationError(#[from] CreateReservationError),
+	/// Any error related to changing the state of a reservation
+	#[error(transparent)]
+	ReservationTransitionError(#[from] ReservationTransitionError),
+	/// The profile has not visited the location it is trying to review
+	#[error("only profiles that visited a location can review it")]
+	ReviewNotAllowed,
+	/// The client made too many requests in a short amount of time
+	#[error("too many requests, retry after {retry_after} seconds")]
+	TooManyRequests { retry_after: u64 },
 	/// Resource could not be validated
-	#[error("{0}")]
-	ValidationError(String),
+	#[error("{message}")]
+	ValidationError { message: String, fields: HashMap<String, String> },
+}
+
+/// Machine-readable category of an [`Error`]
+///
+/// Every error response carries one of these codes, more specific
+/// information about the error is placed in the `details` field
+#[derive(
+	Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, ToSchema,
+)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+	BadRequest,
+	Conflict,
+	Forbidden,
+	InternalServerError,
+	InvalidImage,
+	LoginFailed,
+	NotFound,
+	ReservationRejected,
+	TokenExpired,
+	TooManyRequests,
+	Unauthorized,
+	ValidationFailed,
+}
+
+/// Serialized body of an error response
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct ErrorResponse {
+	pub code:    ErrorCode,
+	pub message: String,
+	#[schema(value_type = Object)]
+	pub details: Map<String, Value>,
 }
 
 impl Error {
-	/// Return a unique identifying code for this error
+	/// Return the machine-readable category of this error
+	#[must_use]
+	pub fn error_code(&self) -> ErrorCode {
+		match self {
+			Self::Conflict(_)
+			| Self::Duplicate { .. }
+			| Self::InstitutionInUse { .. }
+			| Self::LastAdministrator
+			| Self::OpeningTimeCollision { .. }
+			| Self::ReservationTransitionError(_)
+			| Self::RoleInUse { .. }
+			| Self::TagInUse { .. } => ErrorCode::Conflict,
+			Self::Forbidden | Self::ReviewNotAllowed => ErrorCode::Forbidden,
+			Self::Infallible(_) | Self::InternalServerError => {
+				ErrorCode::InternalServerError
+			},
+			Self::InvalidImage(_) | Self::ImageUploadError(_) => {
+				ErrorCode::InvalidImage
+			},
+			Self::InvalidRolePermissions | Self::ValidationError { .. } => {
+				ErrorCode::ValidationFailed
+			},
+			Self::NotFound(_) => ErrorCode::NotFound,
+			Self::LoginError(_) | Self::OAuthError(_) => ErrorCode::LoginFailed,
+			Self::BadRequest(_)
+			| Self::MissingRequestData(_)
+			| Self::MultipartSerializationError(_)
+			| Self::MultipartParseError(_)
+			| Self::PaginationError(_)
+			| Self::UnknownIncludes { .. } => ErrorCode::BadRequest,
+			Self::TokenError(e) => {
+				match e {
+					TokenError::MissingAccessToken
+					| TokenError::MissingSession => ErrorCode::Unauthorized,
+					TokenError::ExpiredEmailToken
+					| TokenError::ExpiredPasswordToken
+					| TokenError::ExpiredRevertToken => ErrorCode::TokenExpired,
+				}
+			},
+			Self::CreateReservationError(_) => ErrorCode::ReservationRejected,
+			Self::TooManyRequests { .. } => ErrorCode::TooManyRequests,
+		}
+	}
+
+	/// Return a unique identifying reason for this error
 	///
-	/// An error code should never be reused once its assigned to avoid
+	/// A reason should never be reused once its assigned to avoid
 	/// unexpectedly breaking the frontend
-	fn code(&self) -> &'static str {
+	fn reason(&self) -> &'static str {
 		match self {
-			Self::Duplicate(_) => "duplicate",
+			Self::BadRequest(_) => "bad_request",
+			Self::Conflict(_) => "conflict",
+			Self::Duplicate { .. } => "duplicate",
 			Self::Forbidden => "forbidden",
 			Self::Infallible(_) => "infallible",
 			Self::InternalServerError => "internal_server_error",
 			Self::InvalidImage(_) => "invalid_image",
+			Self::ImageUploadError(e) => {
+				match e {
+					ImageUploadError::TooLarge { .. } => "image_too_large",
+					ImageUploadError::UnrecognizedFormat => {
+						"unrecognized_image_format"
+					},
+					ImageUploadError::UnsupportedFormat(_) => {
+						"unsupported_image_format"
+					},
+					ImageUploadError::DimensionsTooLarge { .. } => {
+						"image_dimensions_too_large"
+					},
+				}
+			},
 			Self::InvalidRolePermissions => "invalid_role_permissions",
 			Self::NotFound(_) => "not_found",
+			Self::OpeningTimeCollision { .. } => "opening_time_collision",
 			Self::LoginError(e) => {
 				match e {
 					LoginError::UnknownProfile => "unknown_profile",
@@ -89,6 +223,9 @@ impl Error {
 			},
 			Self::OAuthError(e) => {
 				match e {
+					OAuthError::AccountExistsUnverified => {
+						"account_exists_unverified"
+					},
 					OAuthError::InvalidCSRFToken => "invalid_csrf_token",
 					OAuthError::MissingCSRFTokenCookie => {
 						"missing_csrf_token_cookie"
@@ -123,6 +260,7 @@ impl Error {
 					TokenError::ExpiredPasswordToken => {
 						"expired_password_token"
 					},
+					TokenError::ExpiredRevertToken => "expired_revert_token",
 				}
 			},
 			Self::CreateReservationError(e) => {
@@ -136,6 +274,9 @@ impl Error {
 					CreateReservationError::NotReservableAnymore(_) => {
 						"not_reservable_anymore"
 					},
+					CreateReservationError::OpeningTimeEnded(_) => {
+						"opening_time_ended"
+					},
 					CreateReservationError::ReservationTooShort(_) => {
 						"reservation_too_short"
 					},
@@ -143,89 +284,157 @@ impl Error {
 						"reservation_too_long"
 					},
 					CreateReservationError::Full(_) => "full",
+					CreateReservationError::NotExtendable => "not_extendable",
+					CreateReservationError::InvalidBlockRange { .. } => {
+						"invalid_block_range"
+					},
+					CreateReservationError::Overlap(_) => "overlap",
+				}
+			},
+			Self::ReservationTransitionError(e) => {
+				match e {
+					ReservationTransitionError::AlreadyCancelled => {
+						"already_cancelled"
+					},
+					ReservationTransitionError::AlreadyConfirmed => {
+						"already_confirmed"
+					},
+					ReservationTransitionError::AlreadyStarted(_) => {
+						"already_started"
+					},
 				}
 			},
-			Self::ValidationError(_) => "validation_error",
+			Self::ValidationError { .. } => "validation_error",
 			Self::PaginationError(e) => {
 				match e {
 					PaginationError::OffsetTooLarge => "offset_too_large",
 				}
 			},
 			Self::MissingRequestData(_) => "missing_request_data",
+			Self::RoleInUse { .. } => "role_in_use",
+			Self::InstitutionInUse { .. } => "institution_in_use",
+			Self::TagInUse { .. } => "tag_in_use",
+			Self::LastAdministrator => "last_administrator",
+			Self::UnknownIncludes { .. } => "unknown_includes",
+			Self::ReviewNotAllowed => "review_not_allowed",
+			Self::TooManyRequests { .. } => "too_many_requests",
 		}
 	}
 
-	/// Return additional information about the error
-	fn info(&self) -> Option<String> {
-		match self {
-			Self::Duplicate(m)
-			| Self::InvalidImage(m)
-			| Self::NotFound(m)
-			| Self::ValidationError(m) => Some(m.to_owned()),
+	/// Return structured information about the error
+	///
+	/// The reason for the error is always included, any other fields depend
+	/// on the kind of error
+	fn details(&self) -> Map<String, Value> {
+		let extra = match self {
+			Self::Duplicate { resource_id, .. } => {
+				json!({ "resourceId": resource_id })
+			},
+			Self::ValidationError { fields, .. } => json!({ "fields": fields }),
+			Self::OpeningTimeCollision { days } => json!({ "days": days }),
+			Self::RoleInUse { member_count } => {
+				json!({ "memberCount": member_count })
+			},
+			Self::InstitutionInUse { authority_ids } => {
+				json!({ "authorityIds": authority_ids })
+			},
+			Self::TagInUse { location_count } => {
+				json!({ "locationCount": location_count })
+			},
+			Self::UnknownIncludes { keys, valid } => {
+				json!({ "unknownKeys": keys, "validKeys": valid })
+			},
+			Self::TooManyRequests { retry_after } => {
+				json!({ "retryAfter": retry_after })
+			},
 			Self::CreateReservationError(e) => {
 				match e {
 					CreateReservationError::OutOfBounds { start, end } => {
-						Some(
-							serde_json::json!({"start": start, "end": end})
-								.to_string(),
-						)
+						json!({ "start": start, "end": end })
 					},
 					CreateReservationError::NotReservableYet(from) => {
-						Some(serde_json::json!({"from": from}).to_string())
+						json!({ "from": from })
 					},
 					CreateReservationError::NotReservableAnymore(until) => {
-						Some(serde_json::json!({"until": until}).to_string())
+						json!({ "until": until })
+					},
+					CreateReservationError::OpeningTimeEnded(end) => {
+						json!({ "end": end })
 					},
 					CreateReservationError::ReservationTooShort(min) => {
-						Some(serde_json::json!({"min": min}).to_string())
+						json!({ "min": min })
 					},
 					CreateReservationError::ReservationTooLong(max) => {
-						Some(serde_json::json!({"max": max}).to_string())
+						json!({ "max": max })
 					},
 					CreateReservationError::Full(blocks) => {
-						Some(serde_json::json!({"blocks": blocks}).to_string())
+						json!({ "blocks": blocks })
+					},
+					CreateReservationError::NotExtendable => json!({}),
+					CreateReservationError::InvalidBlockRange { min, max } => {
+						json!({ "min": min, "max": max })
+					},
+					CreateReservationError::Overlap(reservation_id) => {
+						json!({ "reservationId": reservation_id })
 					},
 				}
 			},
+			Self::ReservationTransitionError(
+				ReservationTransitionError::AlreadyStarted(start),
+			) => json!({ "start": start }),
 			Self::OAuthError(OAuthError::UnknownProvider(p)) => {
-				Some(serde_json::json!({"provider": p}).to_string())
+				json!({ "provider": p })
+			},
+			Self::ImageUploadError(e) => {
+				match e {
+					ImageUploadError::TooLarge { size, max } => {
+						json!({ "size": size, "max": max })
+					},
+					ImageUploadError::UnrecognizedFormat => json!({}),
+					ImageUploadError::UnsupportedFormat(format) => {
+						json!({ "format": format })
+					},
+					ImageUploadError::DimensionsTooLarge {
+						width,
+						height,
+						max,
+					} => {
+						json!({ "width": width, "height": height, "max": max })
+					},
+				}
 			},
 			Self::MultipartParseError(e) => {
 				match e {
 					MultipartParseError::MissingField { expected_field } => {
-						Some(
-							serde_json::json!({
-								"expected_field": expected_field,
-							})
-							.to_string(),
-						)
+						json!({ "expectedField": expected_field })
 					},
 					MultipartParseError::UnknownField { field_name } => {
-						Some(
-							serde_json::json!({
-								"field_name": field_name,
-							})
-							.to_string(),
-						)
+						json!({ "fieldName": field_name })
 					},
 					MultipartParseError::WrongFieldType {
 						field_name,
 						expected_ty,
 						..
 					} => {
-						Some(
-							serde_json::json!({
-								"field_name": field_name,
-								"expected_type": expected_ty,
-							})
-							.to_string(),
-						)
-					},
-					MultipartParseError::NamelessField => None,
+						json!({
+							"fieldName": field_name,
+							"expectedType": expected_ty,
+						})
+					},
+					MultipartParseError::NamelessField => json!({}),
 				}
 			},
-			_ => None,
+			_ => json!({}),
+		};
+
+		let mut details = Map::new();
+		details.insert("reason".to_string(), self.reason().into());
+
+		if let Value::Object(extra) = extra {
+			details.extend(extra);
 		}
+
+		details
 	}
 }
 
@@ -235,16 +444,33 @@ impl IntoResponse for Error {
 	fn into_response(self) -> Response {
 		error!("{self:?}");
 
-		let message = self.to_string();
+		let data = ErrorResponse {
+			code:    self.error_code(),
+			message: self.to_string(),
+			details: self.details(),
+		};
 
-		let data = serde_json::json!({
-			"message": message,
-			"code": self.code(),
-			"info": self.info(),
-		});
+		let retry_after = match self {
+			Self::TooManyRequests { retry_after } => Some(retry_after),
+			_ => None,
+		};
 
 		let status = match self {
-			Self::Duplicate(_) => StatusCode::CONFLICT,
+			Self::Conflict(_)
+			| Self::Duplicate { .. }
+			| Self::OpeningTimeCollision { .. }
+			| Self::RoleInUse { .. }
+			| Self::InstitutionInUse { .. }
+			| Self::TagInUse { .. }
+			| Self::LastAdministrator
+			| Self::ReservationTransitionError(_)
+			| Self::CreateReservationError(
+				CreateReservationError::Full(_)
+				| CreateReservationError::Overlap(_),
+			)
+			| Self::OAuthError(OAuthError::AccountExistsUnverified) => {
+				StatusCode::CONFLICT
+			},
 			Self::InternalServerError | Self::Infallible(_) => {
 				StatusCode::INTERNAL_SERVER_ERROR
 			},
@@ -254,13 +480,16 @@ impl IntoResponse for Error {
 			Self::NotFound(_)
 			| Self::LoginError(LoginError::UnknownProfile) => StatusCode::NOT_FOUND,
 			Self::Forbidden
+			| Self::ReviewNotAllowed
 			| Self::LoginError(_)
 			| Self::OAuthError(OAuthError::InvalidCSRFToken)
 			| Self::TokenError(_) => StatusCode::FORBIDDEN,
-			Self::MultipartSerializationError(_)
+			Self::BadRequest(_)
+			| Self::MultipartSerializationError(_)
 			| Self::InvalidImage(_)
 			| Self::CreateReservationError(_)
 			| Self::PaginationError(_)
+			| Self::UnknownIncludes { .. }
 			| Self::OAuthError(
 				OAuthError::MissingCSRFTokenCookie
 				| OAuthError::MissingEmailField
@@ -268,14 +497,31 @@ impl IntoResponse for Error {
 				| OAuthError::UnknownProvider(_),
 			) => StatusCode::BAD_REQUEST,
 			Self::InvalidRolePermissions
-			| Self::ValidationError(_)
+			| Self::ValidationError { .. }
 			| Self::MissingRequestData(_)
-			| Self::MultipartParseError(_) => {
+			| Self::MultipartParseError(_)
+			| Self::ImageUploadError(
+				ImageUploadError::UnrecognizedFormat
+				| ImageUploadError::UnsupportedFormat(_)
+				| ImageUploadError::DimensionsTooLarge { .. },
+			) => {
 				StatusCode::UNPROCESSABLE_ENTITY
 			},
+			Self::ImageUploadError(ImageUploadError::TooLarge { .. }) => {
+				StatusCode::PAYLOAD_TOO_LARGE
+			},
+			Self::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
 		};
 
-		(status, axum::Json(data)).into_response()
+		let mut response = (status, axum::Json(data)).into_response();
+
+		if let Some(retry_after) = retry_after {
+			response
+				.headers_mut()
+				.insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+		}
+
+		response
 	}
 }
 
@@ -293,6 +539,11 @@ pub enum LoginError {
 /// Any error related to OAuth login
 #[derive(Debug, Error)]
 pub enum OAuthError {
+	#[error(
+		"a profile with this email already exists but its email was never \
+		 confirmed, confirm it before logging in with SSO"
+	)]
+	AccountExistsUnverified,
 	#[error("invalid CSRF token provided")]
 	InvalidCSRFToken,
 	#[error("missing CSRF token cookie")]
@@ -317,6 +568,8 @@ pub enum TokenError {
 	ExpiredEmailToken,
 	#[error("password reset token has expired")]
 	ExpiredPasswordToken,
+	#[error("email revert token has expired")]
+	ExpiredRevertToken,
 }
 
 #[derive(Debug, Error)]
@@ -333,6 +586,26 @@ pub enum MultipartParseError {
 	NamelessField,
 }
 
+/// Any error related to checking an uploaded image before it is decoded
+#[derive(Debug, Error)]
+pub enum ImageUploadError {
+	/// The upload is larger than the configured maximum amount of bytes
+	#[error("image is {size} bytes, at most {max} bytes are allowed")]
+	TooLarge { size: usize, max: usize },
+	/// The contents of the upload do not match any known image format
+	#[error("file is not a recognized image")]
+	UnrecognizedFormat,
+	/// The upload is an image, but not in a format that is accepted
+	#[error("images of type {0} are not supported")]
+	UnsupportedFormat(String),
+	/// The image is wider or higher than the configured maximum
+	#[error(
+		"image is {width}x{height} pixels, at most {max}x{max} pixels are \
+		 allowed"
+	)]
+	DimensionsTooLarge { width: u32, height: u32, max: u32 },
+}
+
 #[derive(Debug, Error)]
 pub enum CreateReservationError {
 	/// The request was out of bounds for the given opening time
@@ -344,11 +617,14 @@ pub enum CreateReservationError {
 	/// The request was made after the timeslot was reservable
 	#[error("this timeslot is not reservable anymore")]
 	NotReservableAnymore(NaiveDateTime),
+	/// The opening time of the timeslot has already ended
+	#[error("this opening time has already ended")]
+	OpeningTimeEnded(NaiveDateTime),
 	/// The amount of blocks reserved was less than the minimum reservation
 	/// length
 	#[error("the reserved amount of time was too short")]
 	ReservationTooShort(i32),
-	/// The amount of blocks reserved was more than the maximum reservation
+	/// The amount of minutes reserved was more than the maximum reservation
 	/// length
 	#[error("the reserved amount of time was too long")]
 	ReservationTooLong(i32),
@@ -356,6 +632,30 @@ pub enum CreateReservationError {
 	/// blocks
 	#[error("the reservation would overoccupy some blocks")]
 	Full(Vec<i32>),
+	/// The reservation has already been confirmed or cancelled and can no
+	/// longer be extended
+	#[error("this reservation can no longer be extended")]
+	NotExtendable,
+	/// The reserved blocks do not fall within the blocks of the opening time
+	#[error("the reserved blocks fall outside of the opening time")]
+	InvalidBlockRange { min: i32, max: i32 },
+	/// The profile already has a reservation overlapping the requested blocks
+	#[error("this reservation overlaps with an existing reservation")]
+	Overlap(i32),
+}
+
+/// Any error related to changing the state of a reservation
+#[derive(Debug, Error)]
+pub enum ReservationTransitionError {
+	/// Cancelled reservations can not move to any other state
+	#[error("this reservation has already been cancelled")]
+	AlreadyCancelled,
+	/// The presence of the profile has already been recorded
+	#[error("this reservation has already been confirmed")]
+	AlreadyConfirmed,
+	/// Reservations can not be cancelled once they started
+	#[error("this reservation has already started")]
+	AlreadyStarted(NaiveDateTime),
 }
 
 #[derive(Debug, Error)]
@@ -393,15 +693,15 @@ pub enum InternalServerError {
 	/// Malformed email
 	#[error("invalid email -- {0:?}")]
 	InvalidEmail(lettre::address::AddressError),
-	/// Mailer stopped unexpectedly
-	#[error("mailer stopped -- {0:?}")]
-	MailerStopped(mpsc::error::SendError<lettre::Message>),
 	/// Mail queue is full
-	#[error("mail queue full -- {0:?}")]
-	MailQueueFull(mpsc::error::TrySendError<lettre::Message>),
+	#[error("mail queue full -- {0} mails queued")]
+	MailQueueFull(usize),
 	/// Generic mailer error
 	#[error("mail error -- {0:?}")]
 	MailError(lettre::error::Error),
+	/// Error delivering some mail over SMTP
+	#[error("SMTP error -- {0:?}")]
+	SmtpError(lettre::transport::smtp::Error),
 	/// Error acquiring database pool connection
 	#[error("database pool error -- {0:?}")]
 	PoolError(deadpool_diesel::PoolError),
@@ -432,19 +732,24 @@ impl From<InternalServerError> for Error {
 /// Map validation errors to application errors
 impl From<validator::ValidationErrors> for Error {
 	fn from(err: validator::ValidationErrors) -> Self {
-		let errs = err.field_errors();
-		let repr = errs
-			.values()
-			.map(|v| {
-				v.iter()
+		let fields = err
+			.field_errors()
+			.into_iter()
+			.map(|(field, errs)| {
+				let repr = errs
+					.iter()
 					.map(ToString::to_string)
 					.collect::<Vec<String>>()
-					.join("\n")
+					.join("\n");
+
+				(field.to_string(), repr)
 			})
-			.collect::<Vec<String>>()
-			.join("\n");
+			.collect::<HashMap<String, String>>();
+
+		let message =
+			fields.values().cloned().collect::<Vec<String>>().join("\n");
 
-		Self::ValidationError(repr)
+		Self::ValidationError { message, fields }
 	}
 }
 
@@ -494,7 +799,10 @@ impl From<diesel::result::Error> for Error {
 
 				match CONSTRAINT_TO_COLUMN.get(constraint_name) {
 					Some(field) => {
-						Self::Duplicate(format!("{field} is already in use"))
+						Self::Duplicate {
+							message:     format!("{field} is already in use"),
+							resource_id: None,
+						}
 					},
 					None => InternalServerError::DatabaseError(err).into(),
 				}
@@ -503,7 +811,17 @@ impl From<diesel::result::Error> for Error {
 			diesel::result::Error::DatabaseError(
 				DatabaseErrorKind::ForeignKeyViolation,
 				info,
-			) => Error::ValidationError(info.message().to_string()),
+			) => {
+				let fields = info
+					.column_name()
+					.map(|c| HashMap::from([(c.to_string(), "invalid".into())]))
+					.unwrap_or_default();
+
+				Error::ValidationError {
+					message: info.message().to_string(),
+					fields,
+				}
+			},
 			_ => InternalServerError::DatabaseError(err).into(),
 		}
 	}
@@ -521,24 +839,18 @@ impl From<lettre::address::AddressError> for Error {
 	}
 }
 
-impl From<mpsc::error::SendError<lettre::Message>> for Error {
-	fn from(err: mpsc::error::SendError<lettre::Message>) -> Self {
-		InternalServerError::MailerStopped(err).into()
-	}
-}
-
-impl From<mpsc::error::TrySendError<lettre::Message>> for Error {
-	fn from(err: mpsc::error::TrySendError<lettre::Message>) -> Self {
-		InternalServerError::MailQueueFull(err).into()
-	}
-}
-
 impl From<lettre::error::Error> for Error {
 	fn from(err: lettre::error::Error) -> Self {
 		InternalServerError::MailError(err).into()
 	}
 }
 
+impl From<lettre::transport::smtp::Error> for Error {
+	fn from(err: lettre::transport::smtp::Error) -> Self {
+		InternalServerError::SmtpError(err).into()
+	}
+}
+
 impl From<serde_json::Error> for Error {
 	fn from(err: serde_json::Error) -> Self {
 		InternalServerError::SerdeJsonError(err).into()
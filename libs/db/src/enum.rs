@@ -1,5 +1,6 @@
 use diesel_derive_enum::DbEnum;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 #[derive(
 	Clone, Copy, DbEnum, Debug, Default, Deserialize, PartialEq, Eq, Serialize,
@@ -26,12 +27,21 @@ pub enum InstitutionCategory {
 impl InstitutionCategory {
 	#[must_use]
 	pub fn get_variants() -> [&'static str; 3] {
-		["education", "organisation", "government"]
+		["Education", "Organisation", "Government"]
 	}
 }
 
 #[derive(
-	Clone, Copy, DbEnum, Debug, Default, Deserialize, PartialEq, Eq, Serialize,
+	Clone,
+	Copy,
+	DbEnum,
+	Debug,
+	Default,
+	Deserialize,
+	PartialEq,
+	Eq,
+	Serialize,
+	ToSchema,
 )]
 #[ExistingTypePath = "crate::sql_types::ReservationState"]
 pub enum ReservationState {
@@ -41,3 +51,26 @@ pub enum ReservationState {
 	Absent,
 	Present,
 }
+
+/// A privileged mutation that is recorded in the audit log
+#[derive(Clone, Copy, DbEnum, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[ExistingTypePath = "crate::sql_types::AuditAction"]
+pub enum AuditAction {
+	LocationApproved,
+	LocationRejected,
+	ProfileDisabled,
+	ProfileActivated,
+	MemberRoleChanged,
+	AuthorityDeleted,
+	InstitutionDeleted,
+}
+
+/// The kind of resource an audit log entry is about
+#[derive(Clone, Copy, DbEnum, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[ExistingTypePath = "crate::sql_types::AuditTarget"]
+pub enum AuditTarget {
+	Location,
+	Profile,
+	Authority,
+	Institution,
+}
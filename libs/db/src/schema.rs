@@ -1,6 +1,14 @@
 // @generated automatically by Diesel CLI.
 
 pub mod sql_types {
+	#[derive(diesel::sql_types::SqlType)]
+	#[diesel(postgres_type(name = "audit_action"))]
+	pub struct AuditAction;
+
+	#[derive(diesel::sql_types::SqlType)]
+	#[diesel(postgres_type(name = "audit_target"))]
+	pub struct AuditTarget;
+
 	#[derive(diesel::sql_types::SqlType)]
 	#[diesel(postgres_type(name = "institution_category"))]
 	pub struct InstitutionCategory;
@@ -14,6 +22,22 @@ pub mod sql_types {
 	pub struct ReservationState;
 }
 
+diesel::table! {
+	use diesel::sql_types::*;
+	use super::sql_types::AuditAction;
+	use super::sql_types::AuditTarget;
+
+	audit_log (id) {
+		id -> Int4,
+		actor_id -> Nullable<Int4>,
+		action -> AuditAction,
+		target_type -> AuditTarget,
+		target_id -> Int4,
+		detail -> Jsonb,
+		created_at -> Timestamp,
+	}
+}
+
 diesel::table! {
 	authority (id) {
 		id -> Int4,
@@ -60,6 +84,7 @@ diesel::table! {
 		image_url -> Nullable<Text>,
 		uploaded_at -> Timestamp,
 		uploaded_by -> Nullable<Int4>,
+		content_hash -> Nullable<Text>,
 	}
 }
 
@@ -143,6 +168,15 @@ diesel::table! {
 		created_by -> Nullable<Int4>,
 		updated_at -> Timestamp,
 		updated_by -> Nullable<Int4>,
+		public_id -> Uuid,
+	}
+}
+
+diesel::table! {
+	location_favourite (profile_id, location_id) {
+		profile_id -> Int4,
+		location_id -> Int4,
+		created_at -> Timestamp,
 	}
 }
 
@@ -153,6 +187,7 @@ diesel::table! {
 		index -> Int4,
 		approved_at -> Nullable<Timestamp>,
 		approved_by -> Nullable<Int4>,
+		is_cover -> Bool,
 	}
 }
 
@@ -179,6 +214,7 @@ diesel::table! {
 		created_by -> Nullable<Int4>,
 		updated_at -> Timestamp,
 		updated_by -> Nullable<Int4>,
+		is_default -> Bool,
 	}
 }
 
@@ -225,12 +261,16 @@ diesel::table! {
 		email_confirmation_token -> Nullable<Text>,
 		email_confirmation_token_expiry -> Nullable<Timestamp>,
 		is_admin -> Bool,
-		block_reason -> Nullable<Text>,
+		deactivation_reason -> Nullable<Text>,
 		state -> ProfileState,
 		created_at -> Timestamp,
 		updated_at -> Timestamp,
 		updated_by -> Nullable<Int4>,
 		last_login_at -> Timestamp,
+		deactivated_by -> Nullable<Int4>,
+		email_revert_token -> Nullable<Text>,
+		email_revert_token_expiry -> Nullable<Timestamp>,
+		previous_email -> Nullable<Text>,
 	}
 }
 
@@ -249,6 +289,7 @@ diesel::table! {
 		updated_at -> Timestamp,
 		confirmed_at -> Nullable<Timestamp>,
 		confirmed_by -> Nullable<Int4>,
+		reminded_at -> Nullable<Timestamp>,
 	}
 }
 
@@ -266,6 +307,16 @@ diesel::table! {
 	}
 }
 
+diesel::table! {
+	review_flag (id) {
+		id -> Int4,
+		review_id -> Int4,
+		flagged_by -> Int4,
+		reason -> Text,
+		created_at -> Timestamp,
+	}
+}
+
 diesel::table! {
 	tag (id) {
 		id -> Int4,
@@ -291,6 +342,25 @@ diesel::table! {
 	}
 }
 
+diesel::table! {
+	webhook (id) {
+		id -> Int4,
+		authority_id -> Int4,
+		url -> Text,
+		secret -> Text,
+		enabled -> Bool,
+		events -> Int8,
+		last_delivery_at -> Nullable<Timestamp>,
+		last_delivery_status -> Nullable<Int4>,
+		last_delivery_error -> Nullable<Text>,
+		created_at -> Timestamp,
+		created_by -> Nullable<Int4>,
+		updated_at -> Timestamp,
+		updated_by -> Nullable<Int4>,
+	}
+}
+
+diesel::joinable!(audit_log -> profile (actor_id));
 diesel::joinable!(authority -> institution (institution_id));
 diesel::joinable!(authority_member -> authority (authority_id));
 diesel::joinable!(authority_member -> authority_role (authority_role_id));
@@ -300,6 +370,8 @@ diesel::joinable!(institution_member -> institution (institution_id));
 diesel::joinable!(institution_member -> institution_role (institution_role_id));
 diesel::joinable!(institution_role -> institution (institution_id));
 diesel::joinable!(location -> authority (authority_id));
+diesel::joinable!(location_favourite -> location (location_id));
+diesel::joinable!(location_favourite -> profile (profile_id));
 diesel::joinable!(location_image -> image (image_id));
 diesel::joinable!(location_image -> location (location_id));
 diesel::joinable!(location_image -> profile (approved_by));
@@ -309,11 +381,17 @@ diesel::joinable!(location_role -> location (location_id));
 diesel::joinable!(location_tag -> location (location_id));
 diesel::joinable!(location_tag -> tag (tag_id));
 diesel::joinable!(opening_time -> location (location_id));
+diesel::joinable!(opening_time_exception -> opening_time (opening_time_id));
+diesel::joinable!(opening_time_exception -> profile (created_by));
 diesel::joinable!(reservation -> opening_time (opening_time_id));
 diesel::joinable!(review -> location (location_id));
+diesel::joinable!(review_flag -> profile (flagged_by));
+diesel::joinable!(review_flag -> review (review_id));
 diesel::joinable!(tag -> translation (name_translation_id));
+diesel::joinable!(webhook -> authority (authority_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+	audit_log,
 	authority,
 	authority_member,
 	authority_role,
@@ -322,14 +400,18 @@ diesel::allow_tables_to_appear_in_same_query!(
 	institution_member,
 	institution_role,
 	location,
+	location_favourite,
 	location_image,
 	location_member,
 	location_role,
 	location_tag,
 	opening_time,
+	opening_time_exception,
 	profile,
 	reservation,
 	review,
+	review_flag,
 	tag,
 	translation,
+	webhook,
 );
@@ -0,0 +1,185 @@
+#[macro_use]
+extern crate tracing;
+
+use base::{BoxedCondition, PaginatedData, PaginationConfig, ToFilter};
+use common::{DbConn, Error};
+use db::{AuditAction, AuditTarget, audit_log, profile};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::sql_types::Bool;
+use primitives::{PrimitiveAuditLog, PrimitiveProfile};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogFilter {
+	pub actor_id:    Option<i32>,
+	pub action:      Option<AuditAction>,
+	pub target_type: Option<AuditTarget>,
+	pub target_id:   Option<i32>,
+}
+
+impl<S> ToFilter<S> for AuditLogFilter
+where
+	S: 'static,
+	audit_log::actor_id: SelectableExpression<S>,
+	audit_log::action: SelectableExpression<S>,
+	audit_log::target_type: SelectableExpression<S>,
+	audit_log::target_id: SelectableExpression<S>,
+{
+	type SqlType = Bool;
+
+	fn to_filter(&self) -> BoxedCondition<S, Self::SqlType> {
+		let mut filter: BoxedCondition<S, Self::SqlType> =
+			Box::new(true.into_sql::<Bool>());
+
+		if let Some(actor_id) = self.actor_id {
+			filter = Box::new(
+				filter.and(audit_log::actor_id.is_not_distinct_from(actor_id)),
+			);
+		}
+
+		if let Some(action) = self.action {
+			filter = Box::new(filter.and(audit_log::action.eq(action)));
+		}
+
+		if let Some(target_type) = self.target_type {
+			filter =
+				Box::new(filter.and(audit_log::target_type.eq(target_type)));
+		}
+
+		if let Some(target_id) = self.target_id {
+			filter = Box::new(filter.and(audit_log::target_id.eq(target_id)));
+		}
+
+		filter
+	}
+}
+
+/// A record of a privileged mutation, such as approving a location or
+/// disabling a profile
+#[derive(Clone, Debug, Deserialize, Queryable, Selectable, Serialize)]
+#[diesel(check_for_backend(Pg))]
+pub struct AuditLog {
+	#[diesel(embed)]
+	pub primitive: PrimitiveAuditLog,
+	/// The profile that made the change, unless it was deleted since
+	#[diesel(embed)]
+	pub actor:     Option<PrimitiveProfile>,
+}
+
+impl AuditLog {
+	/// Build a query with all required joins to select a full audit log
+	/// data tuple
+	#[diesel::dsl::auto_type(no_type_alias)]
+	fn query() -> _ {
+		audit_log::table.left_join(
+			profile::table.on(audit_log::actor_id.eq(profile::id.nullable())),
+		)
+	}
+
+	/// Record a privileged mutation
+	///
+	/// The audit log should never get in the way of the change it describes,
+	/// so failures are logged instead of returned
+	#[instrument(skip(conn))]
+	pub async fn record(
+		actor_id: i32,
+		action: AuditAction,
+		target_type: AuditTarget,
+		target_id: i32,
+		detail: serde_json::Value,
+		conn: &DbConn,
+	) {
+		let entry =
+			NewAuditLog { actor_id, action, target_type, target_id, detail };
+
+		if let Err(e) = entry.insert(conn).await {
+			error!(
+				"could not record {action:?} of {target_type:?} {target_id} \
+				 by profile {actor_id}: {e:?}"
+			);
+		}
+	}
+
+	/// Get a page of [`AuditLog`] entries, newest first
+	#[instrument(skip(conn))]
+	pub async fn get_all(
+		filter: AuditLogFilter,
+		p_cfg: PaginationConfig,
+		conn: &DbConn,
+	) -> Result<PaginatedData<Vec<Self>>, Error> {
+		let count_filter = filter.to_filter();
+		let filter = filter.to_filter();
+		let count_query = Self::query();
+		let query = Self::query();
+
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.offset as i64;
+
+		let (total, entries) = conn
+			.interact(move |conn| {
+				let total = count_query
+					.filter(count_filter)
+					.count()
+					.get_result::<i64>(conn)?;
+
+				let entries = query
+					.filter(filter)
+					.order((audit_log::created_at.desc(), audit_log::id.desc()))
+					.limit(limit)
+					.offset(offset)
+					.select(Self::as_select())
+					.get_results(conn)?;
+
+				Ok::<_, Error>((total, entries))
+			})
+			.await??;
+
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		p_cfg.check_offset(total)?;
+
+		Ok((total, false, entries))
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Insertable, Serialize)]
+#[diesel(table_name = audit_log)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewAuditLog {
+	pub actor_id:    i32,
+	pub action:      AuditAction,
+	pub target_type: AuditTarget,
+	pub target_id:   i32,
+	pub detail:      serde_json::Value,
+}
+
+impl NewAuditLog {
+	/// Insert this [`NewAuditLog`]
+	#[instrument(skip(conn))]
+	pub async fn insert(self, conn: &DbConn) -> Result<AuditLog, Error> {
+		let entry = conn
+			.interact(move |conn| {
+				conn.transaction::<_, Error, _>(|conn| {
+					let entry_id = diesel::insert_into(audit_log::table)
+						.values(self)
+						.returning(audit_log::id)
+						.get_result::<i32>(conn)?;
+
+					let entry = AuditLog::query()
+						.filter(audit_log::id.eq(entry_id))
+						.select(AuditLog::as_select())
+						.get_result(conn)?;
+
+					Ok(entry)
+				})
+			})
+			.await??;
+
+		Ok(entry)
+	}
+}
@@ -1,8 +1,11 @@
 #[macro_use]
 extern crate tracing;
 
+use std::collections::HashMap;
+
 use ::role::NewAuthorityRole;
-use common::{DbConn, Error};
+use base::{PaginatedData, PaginationConfig};
+use common::{DbConn, Error, PaginationError};
 use db::{
 	CreatorAlias,
 	UpdaterAlias,
@@ -14,26 +17,30 @@ use db::{
 	profile,
 	updater,
 };
-use diesel::dsl::{AliasedFields, Nullable};
+use diesel::dsl::{AliasedFields, Nullable, exists};
 use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::sql_types::Bool;
 use permissions::AuthorityPermissions;
 use primitives::{PrimitiveAuthority, PrimitiveInstitution, PrimitiveProfile};
 use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
 
 mod member;
+mod statistics;
 
 pub use member::*;
+pub use statistics::*;
 
-#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, IntoParams, Serialize)]
+#[into_params(parameter_in = Query)]
+#[serde(default)]
 pub struct AuthorityIncludes {
-	#[serde(default)]
 	pub created_by:  bool,
-	#[serde(default)]
 	pub updated_by:  bool,
-	#[serde(default)]
 	pub institution: bool,
+	/// Include the [`AuthorityCounts`], only used when listing authorities
+	pub counts:      bool,
 }
 
 #[derive(Clone, Debug, Deserialize, Queryable, Selectable, Serialize)]
@@ -110,21 +117,141 @@ impl Authority {
 	}
 
 	/// Get all [`Authorities`]s from the database, optionally including related
-	/// profiles.
+	/// profiles and their [`AuthorityCounts`].
 	#[instrument(skip(conn))]
 	pub async fn get_all(
 		includes: AuthorityIncludes,
 		conn: &DbConn,
-	) -> Result<Vec<Self>, Error> {
+	) -> Result<Vec<(Self, Option<AuthorityCounts>)>, Error> {
+		let query = Self::query(includes);
+
+		let authorities = conn
+			.interact(move |c| query.select(Self::as_select()).load::<Self>(c))
+			.await??;
+
+		let mut counts = if includes.counts {
+			let auth_ids = authorities.iter().map(|a| a.primitive.id).collect();
+
+			Self::get_counts(auth_ids, conn).await?
+		} else {
+			HashMap::new()
+		};
+
+		let authorities = authorities
+			.into_iter()
+			.map(|a| {
+				let a_counts = counts.remove(&a.primitive.id);
+
+				(a, a_counts)
+			})
+			.collect();
+
+		Ok(authorities)
+	}
+
+	/// Get a page of the [`Authority`]s created by a given profile
+	#[instrument(skip(conn))]
+	pub async fn created_by_profile(
+		p_id: i32,
+		includes: AuthorityIncludes,
+		p_cfg: PaginationConfig,
+		conn: &DbConn,
+	) -> Result<PaginatedData<Vec<Self>>, Error> {
 		let query = Self::query(includes);
 
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.offset as i64;
+
+		let (total, authorities) = conn
+			.interact(move |conn| {
+				let total = authority::table
+					.filter(authority::created_by.eq(p_id))
+					.count()
+					.get_result::<i64>(conn)?;
+
+				let authorities = query
+					.filter(authority::created_by.eq(p_id))
+					.order(authority::id.asc())
+					.select(Self::as_select())
+					.limit(limit)
+					.offset(offset)
+					.get_results(conn)?;
+
+				Ok::<_, Error>((total, authorities))
+			})
+			.await??;
+
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		if total > 0 && p_cfg.offset >= total {
+			return Err(PaginationError::OffsetTooLarge.into());
+		}
+
+		Ok((total, false, authorities))
+	}
+
+	/// Get all [`Authority`]s whose name contains the given query
+	///
+	/// Queries shorter than two characters never match anything and at most
+	/// twenty results are returned
+	#[instrument(skip(conn))]
+	pub async fn get_by_name(
+		name_query: &str,
+		limit: usize,
+		conn: &DbConn,
+	) -> Result<Vec<Self>, Error> {
+		if name_query.chars().count() < 2 {
+			return Ok(vec![]);
+		}
+
+		let pattern = format!(
+			"%{}%",
+			name_query
+				.replace('\\', "\\\\")
+				.replace('%', "\\%")
+				.replace('_', "\\_")
+		);
+		let limit = i64::try_from(limit.min(20)).unwrap_or(20);
+
+		let query = Self::query(AuthorityIncludes::default());
+
 		let authorities = conn
-			.interact(move |c| query.select(Self::as_select()).load(c))
+			.interact(move |conn| {
+				query
+					.filter(authority::name.ilike(pattern))
+					.order(authority::name)
+					.limit(limit)
+					.select(Self::as_select())
+					.get_results(conn)
+			})
 			.await??;
 
 		Ok(authorities)
 	}
 
+	/// Check if no [`Authority`] with the given name exists yet
+	#[instrument(skip(conn))]
+	pub async fn name_is_available(
+		name: &str,
+		conn: &DbConn,
+	) -> Result<bool, Error> {
+		let name = name.to_string();
+
+		let taken = conn
+			.interact(move |conn| {
+				diesel::select(exists(
+					authority::table.filter(authority::name.eq(name)),
+				))
+				.get_result::<bool>(conn)
+			})
+			.await??;
+
+		Ok(!taken)
+	}
+
 	/// Delete an [`Authority`] given its id
 	#[instrument(skip(conn))]
 	pub async fn delete_by_id(
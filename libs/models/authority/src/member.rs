@@ -1,11 +1,69 @@
+use std::collections::{HashMap, HashSet};
+
 use ::profile::Profile;
+use ::role::AuthorityRole;
 use common::{DbConn, Error};
-use db::{authority, authority_member, image, profile};
+use db::{authority, authority_member, authority_role, image, profile};
 use diesel::prelude::*;
+use permissions::AuthorityPermissions;
+use primitives::PrimitiveAuthorityRole;
 use serde::{Deserialize, Serialize};
 
 use crate::{Authority, AuthorityIncludes};
 
+/// Make sure an authority keeps an administrator once the member `prof_id`
+/// holds the role `new_role_id` instead of their current one
+///
+/// This should always be called from inside a transaction
+fn check_administrator_remains(
+	auth_id: i32,
+	prof_id: i32,
+	new_role_id: Option<i32>,
+	conn: &mut PgConnection,
+) -> Result<(), Error> {
+	let is_admin = |perms: Option<i64>| {
+		perms.is_some_and(|p| {
+			AuthorityPermissions::from_bits_truncate(p)
+				.contains(AuthorityPermissions::Administrator)
+		})
+	};
+
+	let members: Vec<(i32, Option<i64>)> = authority_member::table
+		.left_join(authority_role::table)
+		.filter(authority_member::authority_id.eq(auth_id))
+		.select((
+			authority_member::profile_id,
+			authority_role::permissions.nullable(),
+		))
+		.get_results(conn)?;
+
+	let was_admin =
+		members.iter().any(|(id, perms)| *id == prof_id && is_admin(*perms));
+
+	if !was_admin {
+		return Ok(());
+	}
+
+	let new_perms = new_role_id
+		.map(|role_id| {
+			authority_role::table
+				.find(role_id)
+				.filter(authority_role::authority_id.eq(auth_id))
+				.select(authority_role::permissions)
+				.get_result(conn)
+		})
+		.transpose()?;
+
+	let other_admin =
+		members.iter().any(|(id, perms)| *id != prof_id && is_admin(*perms));
+
+	if is_admin(new_perms) || other_admin {
+		return Ok(());
+	}
+
+	Err(Error::LastAdministrator)
+}
+
 impl Authority {
 	/// Get all [members](Profile) of this [`Authority`]
 	#[instrument(skip(conn))]
@@ -35,6 +93,8 @@ impl Authority {
 	}
 
 	/// Delete a member from this authority
+	///
+	/// The last administrator of an authority can not be deleted
 	#[instrument(skip(conn))]
 	pub async fn delete_member(
 		auth_id: i32,
@@ -42,14 +102,18 @@ impl Authority {
 		conn: &DbConn,
 	) -> Result<(), Error> {
 		conn.interact(move |conn| {
-			use self::authority_member::dsl::*;
+			conn.transaction::<_, Error, _>(|conn| {
+				use self::authority_member::dsl::*;
+
+				check_administrator_remains(auth_id, prof_id, None, conn)?;
 
-			diesel::delete(
-				authority_member.filter(
+				diesel::delete(authority_member.filter(
 					authority_id.eq(auth_id).and(profile_id.eq(prof_id)),
-				),
-			)
-			.execute(conn)
+				))
+				.execute(conn)?;
+
+				Ok(())
+			})
 		})
 		.await??;
 
@@ -58,6 +122,124 @@ impl Authority {
 		Ok(())
 	}
 
+	/// Add members to this authority in bulk
+	///
+	/// Rows are matched to profiles by email and to roles of this authority by
+	/// name, rows that cannot be matched are reported instead of added. All
+	/// matched rows are inserted in a single transaction
+	#[instrument(skip(rows, conn))]
+	pub async fn import_members(
+		auth_id: i32,
+		rows: Vec<MemberImportRow>,
+		added_by: i32,
+		conn: &DbConn,
+	) -> Result<MemberImportSummary, Error> {
+		let summary = conn
+			.interact(move |conn| {
+				conn.transaction::<_, Error, _>(|conn| {
+					Self::import_rows(auth_id, &rows, added_by, conn)
+				})
+			})
+			.await??;
+
+		info!("imported {} members into authority {auth_id}", summary.added);
+
+		Ok(summary)
+	}
+
+	/// Match the rows of a member import and insert the ones that are valid
+	fn import_rows(
+		auth_id: i32,
+		rows: &[MemberImportRow],
+		added_by: i32,
+		conn: &mut PgConnection,
+	) -> Result<MemberImportSummary, Error> {
+		let emails: Vec<&str> = rows.iter().map(|r| r.email.as_str()).collect();
+
+		let profiles: HashMap<String, i32> = profile::table
+			.filter(profile::email.eq_any(emails))
+			.select((profile::email.assume_not_null(), profile::id))
+			.get_results(conn)?
+			.into_iter()
+			.collect();
+
+		let roles: HashMap<String, i32> = authority_role::table
+			.filter(authority_role::authority_id.eq(auth_id))
+			.select((authority_role::name, authority_role::id))
+			.get_results(conn)?
+			.into_iter()
+			.collect();
+
+		let existing: HashSet<i32> = authority_member::table
+			.filter(authority_member::authority_id.eq(auth_id))
+			.select(authority_member::profile_id)
+			.get_results(conn)?
+			.into_iter()
+			.collect();
+
+		let mut summary = MemberImportSummary::default();
+		let mut seen = HashSet::new();
+		let mut new_members = vec![];
+
+		for row in rows {
+			let issue = |reason| {
+				MemberImportIssue {
+					line: row.line,
+					email: row.email.clone(),
+					reason,
+				}
+			};
+
+			let Some(&prof_id) = profiles.get(&row.email) else {
+				summary
+					.errors
+					.push(issue(MemberImportIssueReason::UnknownEmail));
+				continue;
+			};
+
+			let role_id = match &row.role {
+				Some(role) => {
+					let Some(&role_id) = roles.get(role) else {
+						summary
+							.errors
+							.push(issue(MemberImportIssueReason::UnknownRole));
+						continue;
+					};
+
+					Some(role_id)
+				},
+				None => None,
+			};
+
+			if existing.contains(&prof_id) {
+				summary
+					.skipped
+					.push(issue(MemberImportIssueReason::AlreadyMember));
+				continue;
+			}
+
+			if !seen.insert(prof_id) {
+				summary
+					.skipped
+					.push(issue(MemberImportIssueReason::DuplicateRow));
+				continue;
+			}
+
+			new_members.push(NewAuthorityMember {
+				authority_id: auth_id,
+				profile_id: prof_id,
+				authority_role_id: role_id,
+				added_by,
+			});
+		}
+
+		summary.added = diesel::insert_into(authority_member::table)
+			.values(&new_members)
+			.execute(conn)?;
+
+		Ok(summary)
+	}
+
 	/// Get all [`Authorities`](Authority) for a given profile
 	#[instrument(skip(conn))]
 	pub async fn for_profile(
@@ -81,6 +263,54 @@ impl Authority {
 
 		Ok(authorities)
 	}
+
+	/// Get all [`Authorities`](Authority) for a given profile, together with
+	/// the [`AuthorityRole`] the profile has in each of them
+	///
+	/// The roles are returned without their `created_by` and `updated_by`
+	/// profiles
+	#[instrument(skip(conn))]
+	pub async fn for_profile_with_role(
+		p_id: i32,
+		includes: AuthorityIncludes,
+		conn: &DbConn,
+	) -> Result<Vec<(Self, Option<AuthorityRole>)>, Error> {
+		let query = Self::query(includes);
+
+		let memberships = conn
+			.interact(move |conn| {
+				use self::authority_member::dsl::*;
+
+				authority_member
+					.filter(profile_id.eq(p_id))
+					.inner_join(query.on(authority_id.eq(authority::id)))
+					.left_join(authority_role::table.on(
+						authority_role_id.eq(authority_role::id.nullable()),
+					))
+					.order_by(authority::id)
+					.select((
+						Self::as_select(),
+						Option::<PrimitiveAuthorityRole>::as_select(),
+					))
+					.get_results(conn)
+			})
+			.await??
+			.into_iter()
+			.map(|(authority, role)| {
+				let role = role.map(|primitive| {
+					AuthorityRole {
+						primitive,
+						created_by: None,
+						updated_by: None,
+					}
+				});
+
+				(authority, role)
+			})
+			.collect();
+
+		Ok(memberships)
+	}
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Insertable, Serialize)]
@@ -142,12 +372,16 @@ impl NewAuthorityMember {
 #[diesel(table_name = authority_member)]
 #[diesel(check_for_backend(Pg))]
 pub struct AuthorityMemberUpdate {
+	#[diesel(treat_none_as_null = true)]
 	pub authority_role_id: Option<i32>,
 	pub updated_by:        i32,
 }
 
 impl AuthorityMemberUpdate {
 	/// Update this [`AuthorityMember`] in the database.
+	///
+	/// A role of `None` clears the role of the member, the last
+	/// administrator of an authority can not lose their administrator role
 	#[instrument(skip(conn))]
 	pub async fn apply_to(
 		self,
@@ -156,15 +390,24 @@ impl AuthorityMemberUpdate {
 		conn: &DbConn,
 	) -> Result<Profile, Error> {
 		conn.interact(move |conn| {
-			use self::authority_member::dsl::*;
+			conn.transaction::<_, Error, _>(|conn| {
+				use self::authority_member::dsl::*;
 
-			diesel::update(
-				authority_member.filter(
+				check_administrator_remains(
+					auth_id,
+					prof_id,
+					self.authority_role_id,
+					conn,
+				)?;
+
+				diesel::update(authority_member.filter(
 					authority_id.eq(auth_id).and(profile_id.eq(prof_id)),
-				),
-			)
-			.set(self)
-			.execute(conn)
+				))
+				.set(self)
+				.execute(conn)?;
+
+				Ok(())
+			})
 		})
 		.await??;
 
@@ -173,3 +416,47 @@ impl AuthorityMemberUpdate {
 		Ok(profile)
 	}
 }
+
+/// A single row of a member import
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MemberImportRow {
+	/// The line of this row in the imported file
+	pub line:  usize,
+	pub email: String,
+	pub role:  Option<String>,
+}
+
+/// Why a row of a member import was not added
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemberImportIssueReason {
+	/// The profile is already a member of the authority
+	AlreadyMember,
+	/// The profile was already added by an earlier row
+	DuplicateRow,
+	/// The row does not have the expected columns
+	MalformedRow,
+	/// No profile has the given email
+	UnknownEmail,
+	/// The authority has no role with the given name
+	UnknownRole,
+}
+
+/// A row of a member import that was not added
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MemberImportIssue {
+	pub line:   usize,
+	pub email:  String,
+	pub reason: MemberImportIssueReason,
+}
+
+/// The outcome of a member import
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct MemberImportSummary {
+	/// The amount of members that were added
+	pub added:   usize,
+	/// Rows that were left out as they would not change anything
+	pub skipped: Vec<MemberImportIssue>,
+	/// Rows that could not be imported
+	pub errors:  Vec<MemberImportIssue>,
+}
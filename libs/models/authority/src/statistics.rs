@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use common::{DbConn, Error};
+use db::{authority, authority_member, location, location_member};
+use diesel::dsl::{count_distinct, count_star};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::Authority;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct AuthorityStatistics {
+	pub location_count:               i64,
+	pub member_count:                 i64,
+	/// Amount of distinct profiles that are a member of at least one of the
+	/// authority's locations
+	pub unique_location_member_count: i64,
+}
+
+/// Amount of members and locations of an [`Authority`]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct AuthorityCounts {
+	pub member_count:   i64,
+	pub location_count: i64,
+}
+
+impl Authority {
+	/// Get the [`AuthorityCounts`] for a list of authorities, keyed by id
+	///
+	/// Every count is a single grouped query over all given authorities,
+	/// authorities without members or locations get a count of zero
+	#[instrument(skip(conn))]
+	pub async fn get_counts(
+		auth_ids: Vec<i32>,
+		conn: &DbConn,
+	) -> Result<HashMap<i32, AuthorityCounts>, Error> {
+		let mut counts: HashMap<i32, AuthorityCounts> = auth_ids
+			.iter()
+			.map(|a_id| (*a_id, AuthorityCounts::default()))
+			.collect();
+
+		let (member_counts, location_counts) = conn
+			.interact(move |conn| {
+				let location_counts = location::table
+					.inner_join(authority::table)
+					.filter(authority::id.eq_any(auth_ids.clone()))
+					.group_by(authority::id)
+					.select((authority::id, count_star()))
+					.get_results::<(i32, i64)>(conn)?;
+
+				let member_counts = authority_member::table
+					.filter(authority_member::authority_id.eq_any(auth_ids))
+					.group_by(authority_member::authority_id)
+					.select((authority_member::authority_id, count_star()))
+					.get_results::<(i32, i64)>(conn)?;
+
+				Ok::<_, Error>((member_counts, location_counts))
+			})
+			.await??;
+
+		for (a_id, member_count) in member_counts {
+			counts.entry(a_id).or_default().member_count = member_count;
+		}
+
+		for (a_id, location_count) in location_counts {
+			counts.entry(a_id).or_default().location_count = location_count;
+		}
+
+		Ok(counts)
+	}
+
+	/// Count the distinct profiles that are a member of any location of a
+	/// given [`Authority`]
+	#[instrument(skip(conn))]
+	pub async fn get_location_member_count(
+		auth_id: i32,
+		conn: &DbConn,
+	) -> Result<i64, Error> {
+		let count = conn
+			.interact(move |conn| {
+				location_member::table
+					.inner_join(
+						location::table
+							.on(location::id.eq(location_member::location_id)),
+					)
+					.filter(location::authority_id.eq(auth_id))
+					.select(count_distinct(location_member::profile_id))
+					.get_result(conn)
+			})
+			.await??;
+
+		Ok(count)
+	}
+
+	/// Get the dashboard statistics of a given [`Authority`]
+	#[instrument(skip(conn))]
+	pub async fn get_statistics(
+		auth_id: i32,
+		conn: &DbConn,
+	) -> Result<AuthorityStatistics, Error> {
+		let (location_count, member_count) = conn
+			.interact(move |conn| {
+				let location_count = location::table
+					.filter(location::authority_id.eq(auth_id))
+					.count()
+					.get_result::<i64>(conn)?;
+
+				let member_count = authority_member::table
+					.filter(authority_member::authority_id.eq(auth_id))
+					.count()
+					.get_result::<i64>(conn)?;
+
+				Ok::<_, Error>((location_count, member_count))
+			})
+			.await??;
+
+		let unique_location_member_count =
+			Self::get_location_member_count(auth_id, conn).await?;
+
+		Ok(AuthorityStatistics {
+			location_count,
+			member_count,
+			unique_location_member_count,
+		})
+	}
+}
@@ -2,6 +2,8 @@ use common::{Error, PaginationError};
 use diesel::BoxableExpression;
 use diesel::pg::Pg;
 use diesel::sql_types::{Bool, Nullable};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 pub const QUERY_HARD_LIMIT: i64 = 100;
 pub const RESERVATION_BLOCK_SIZE_MINUTES: i32 = 5;
@@ -17,10 +19,55 @@ pub trait ToFilter<S> {
 	fn to_filter(&self) -> BoxedCondition<S, Self::SqlType>;
 }
 
+#[derive(
+	Clone,
+	Copy,
+	Debug,
+	Default,
+	Deserialize,
+	Eq,
+	PartialEq,
+	Serialize,
+	ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+	Asc,
+	#[default]
+	Desc,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct PaginationConfig {
-	pub limit:  usize,
-	pub offset: usize,
+	pub limit:    usize,
+	pub offset:   usize,
+	/// Only return items with an id larger than this one, the offset is
+	/// ignored if this is set
+	///
+	/// This is only supported by queries that are paginated by the database
+	/// and ordered by id
+	pub after_id: Option<i32>,
+}
+
+impl PaginationConfig {
+	/// The SQL OFFSET of this config, which is always zero when paginating
+	/// with a cursor
+	#[must_use]
+	pub fn sql_offset(&self) -> usize {
+		if self.after_id.is_some() { 0 } else { self.offset }
+	}
+
+	/// Check that the offset of this config does not lie past the given
+	/// total amount of items
+	///
+	/// Cursors can never be out of range, they just yield an empty page
+	pub fn check_offset(&self, total: usize) -> Result<(), Error> {
+		if self.after_id.is_none() && total > 0 && self.offset >= total {
+			return Err(PaginationError::OffsetTooLarge.into());
+		}
+
+		Ok(())
+	}
 }
 
 #[inline]
@@ -1,8 +1,9 @@
 #[macro_use]
 extern crate tracing;
 
-use chrono::NaiveDateTime;
-use common::{DbConn, Error};
+use base::{PaginatedData, PaginationConfig};
+use chrono::{NaiveDateTime, Utc};
+use common::{DbConn, Error, PaginationError};
 use db::{image, location, location_image, profile};
 use diesel::pg::Pg;
 use diesel::prelude::*;
@@ -10,8 +11,10 @@ use diesel::sql_types::Bool;
 use diesel::{Identifiable, Queryable, Selectable};
 use primitives::{PrimitiveImage, PrimitiveProfile};
 use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
 
-#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, IntoParams, Serialize)]
+#[into_params(parameter_in = Query)]
 pub struct ImageIncludes {
 	#[serde(default)]
 	pub uploaded_by: bool,
@@ -28,8 +31,16 @@ pub struct Image {
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct OrderedImage {
-	pub image: Image,
-	pub index: i32,
+	pub image:    Image,
+	pub index:    i32,
+	pub is_cover: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ImageWithLocation {
+	pub image:         Image,
+	pub location_id:   Option<i32>,
+	pub location_name: Option<String>,
 }
 
 impl Image {
@@ -57,7 +68,10 @@ impl Image {
 
 		let img = conn
 			.interact(move |conn| {
-				query.select(Self::as_select()).get_result(conn)
+				query
+					.filter(image::id.eq(i_id))
+					.select(Self::as_select())
+					.get_result(conn)
 			})
 			.await??;
 
@@ -83,7 +97,60 @@ impl Image {
 		Ok(image)
 	}
 
-	/// Get all [`Image`]s for a location with the given id
+	/// Delete the avatar [`Image`] of a profile with the given id, clearing
+	/// the profile's reference to it
+	///
+	/// Returns the path of the stored image file, if any, so the caller can
+	/// remove it from disk
+	#[instrument(skip(conn))]
+	pub async fn delete_avatar_for_profile(
+		p_id: i32,
+		conn: &DbConn,
+	) -> Result<Option<String>, Error> {
+		let file_path = conn
+			.interact(move |conn| {
+				conn.transaction::<_, Error, _>(|conn| {
+					Self::delete_avatar_for_profile_with_conn(p_id, conn)
+				})
+			})
+			.await??;
+
+		Ok(file_path)
+	}
+
+	/// Delete the avatar [`Image`] of a profile using an existing connection
+	///
+	/// This should always be called from inside a transaction
+	fn delete_avatar_for_profile_with_conn(
+		p_id: i32,
+		conn: &mut PgConnection,
+	) -> Result<Option<String>, Error> {
+		use self::profile::dsl::*;
+
+		let avatar_id = profile
+			.find(p_id)
+			.select(avatar_image_id)
+			.for_update()
+			.get_result::<Option<i32>>(conn)?;
+
+		let Some(avatar_id) = avatar_id else {
+			return Ok(None);
+		};
+
+		diesel::update(profile.find(p_id))
+			.set(avatar_image_id.eq(None::<i32>))
+			.execute(conn)?;
+
+		let file_path = diesel::delete(image::table.find(avatar_id))
+			.returning(image::file_path)
+			.get_result(conn)?;
+
+		info!("deleted avatar image {avatar_id} of profile {p_id}");
+
+		Ok(file_path)
+	}
+
+	/// Get all approved [`Image`]s for a location with the given id
 	#[instrument(skip(conn))]
 	pub async fn get_for_location(
 		l_id: i32,
@@ -99,20 +166,56 @@ impl Image {
 
 				location_image
 					.filter(location_id.eq(l_id))
+					.filter(approved_at.is_not_null())
+					.inner_join(query.on(image_id.eq(id)))
+					.order((is_cover.desc(), index.asc()))
+					.select((Self::as_select(), index, is_cover))
+					.get_results(conn)
+			})
+			.await??
+			.into_iter()
+			.map(|(image, index, is_cover)| {
+				OrderedImage { image, index, is_cover }
+			})
+			.collect();
+
+		Ok(imgs)
+	}
+
+	/// Get all [`Image`]s for a location with the given id that have not
+	/// been approved yet
+	#[instrument(skip(conn))]
+	pub async fn get_pending_for_location(
+		l_id: i32,
+		includes: ImageIncludes,
+		conn: &DbConn,
+	) -> Result<Vec<OrderedImage>, Error> {
+		let query = Self::query(includes);
+
+		let imgs = conn
+			.interact(move |conn| {
+				use self::image::dsl::*;
+				use self::location_image::dsl::*;
+
+				location_image
+					.filter(location_id.eq(l_id))
+					.filter(approved_at.is_null())
 					.inner_join(query.on(image_id.eq(id)))
-					.order(index.asc())
-					.select((Self::as_select(), index))
+					.order((is_cover.desc(), index.asc()))
+					.select((Self::as_select(), index, is_cover))
 					.get_results(conn)
 			})
 			.await??
 			.into_iter()
-			.map(|(image, index)| OrderedImage { image, index })
+			.map(|(image, index, is_cover)| {
+				OrderedImage { image, index, is_cover }
+			})
 			.collect();
 
 		Ok(imgs)
 	}
 
-	/// Get all [`Image`]s for the locations with the given ids
+	/// Get all approved [`Image`]s for the locations with the given ids
 	#[instrument(skip(l_ids, conn))]
 	pub async fn get_for_locations(
 		l_ids: Vec<i32>,
@@ -130,21 +233,172 @@ impl Image {
 				location::table
 					.filter(location::id.eq_any(l_ids))
 					.inner_join(location_image.on(location_id.eq(location::id)))
+					.filter(approved_at.is_not_null())
 					.inner_join(query.on(image_id.eq(id)))
-					.select((location::id, Self::as_select(), index))
+					.select((location::id, Self::as_select(), index, is_cover))
 					.get_results(conn)
 			})
 			.await??
 			.into_iter()
-			.map(|(id, image, index)| (id, OrderedImage { image, index }))
+			.map(|(id, image, index, is_cover)| {
+				(id, OrderedImage { image, index, is_cover })
+			})
 			.collect();
 
 		Ok(imgs)
 	}
 
+	/// Get a page of the [`Image`]s uploaded by a given profile, newest
+	/// first, along with the location each image belongs to (if any)
+	#[instrument(skip(conn))]
+	pub async fn get_by_uploaded_by(
+		p_id: i32,
+		includes: ImageIncludes,
+		p_cfg: PaginationConfig,
+		conn: &DbConn,
+	) -> Result<PaginatedData<Vec<ImageWithLocation>>, Error> {
+		let query = Self::query(includes);
+
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.offset as i64;
+
+		let (total, imgs) = conn
+			.interact(move |conn| {
+				let total = image::table
+					.filter(image::uploaded_by.eq(p_id))
+					.count()
+					.get_result::<i64>(conn)?;
+
+				let imgs = query
+					.left_join(
+						location_image::table
+							.on(location_image::image_id.eq(image::id)),
+					)
+					.left_join(
+						location::table
+							.on(location::id.eq(location_image::location_id)),
+					)
+					.filter(image::uploaded_by.eq(p_id))
+					.order((image::uploaded_at.desc(), image::id.desc()))
+					.select((
+						Self::as_select(),
+						location::id.nullable(),
+						location::name.nullable(),
+					))
+					.limit(limit)
+					.offset(offset)
+					.get_results(conn)?;
+
+				Ok::<_, Error>((total, imgs))
+			})
+			.await??;
+
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		if total > 0 && p_cfg.offset >= total {
+			return Err(PaginationError::OffsetTooLarge.into());
+		}
+
+		let imgs = imgs
+			.into_iter()
+			.map(|(image, location_id, location_name)| {
+				ImageWithLocation { image, location_id, location_name }
+			})
+			.collect();
+
+		Ok((total, false, imgs))
+	}
+
+	/// Get the [`OrderedImage`] of a [`Location`](crate::Location) with the
+	/// given content hash, if it exists
+	#[instrument(skip(conn))]
+	pub async fn detect_duplicate_for_location(
+		loc_id: i32,
+		content_hash: &str,
+		conn: &DbConn,
+	) -> Result<Option<OrderedImage>, Error> {
+		let hash = content_hash.to_string();
+
+		let duplicate = conn
+			.interact(move |conn| {
+				location_image::table
+					.inner_join(
+						image::table.on(location_image::image_id.eq(image::id)),
+					)
+					.filter(location_image::location_id.eq(loc_id))
+					.filter(image::content_hash.eq(hash))
+					.select((
+						image::id,
+						location_image::index,
+						location_image::is_cover,
+					))
+					.first::<(i32, i32, bool)>(conn)
+					.optional()
+			})
+			.await??;
+
+		let Some((img_id, index, is_cover)) = duplicate else {
+			return Ok(None);
+		};
+
+		let image =
+			Self::get_by_id(img_id, ImageIncludes::default(), conn).await?;
+
+		Ok(Some(OrderedImage { image, index, is_cover }))
+	}
+
+	/// Mark the [`Image`] with the given id as the cover image of the
+	/// [`Location`](crate::Location) with the given id, moving it to the front
+	/// of the gallery
+	#[instrument(skip(conn))]
+	pub async fn set_as_cover(
+		loc_id: i32,
+		img_id: i32,
+		conn: &DbConn,
+	) -> Result<(), Error> {
+		conn.interact(move |conn| {
+			conn.transaction::<_, Error, _>(|conn| {
+				use self::location_image::dsl::*;
+
+				let old_index = location_image
+					.find((loc_id, img_id))
+					.select(index)
+					.get_result::<i32>(conn)?;
+
+				diesel::update(location_image.filter(location_id.eq(loc_id)))
+					.set(is_cover.eq(false))
+					.execute(conn)?;
+
+				diesel::update(
+					location_image
+						.filter(location_id.eq(loc_id))
+						.filter(index.lt(old_index)),
+				)
+				.set(index.eq(index + 1))
+				.execute(conn)?;
+
+				diesel::update(location_image.find((loc_id, img_id)))
+					.set((is_cover.eq(true), index.eq(0)))
+					.execute(conn)?;
+
+				Ok(())
+			})
+		})
+		.await??;
+
+		info!("set image {img_id} as cover for location {loc_id}");
+
+		Ok(())
+	}
+
 	/// Reorder the images for the [`Location`](crate::Location) with the given
 	/// id
 	///
+	/// Images keep their approval when they are reordered
+	///
 	/// # Warning
 	/// This overwrites the entire list of `location_image`s for the location,
 	/// and so may hide/delete images if the input doesn't refer to all images
@@ -155,8 +409,6 @@ impl Image {
 		includes: ImageIncludes,
 		conn: &DbConn,
 	) -> Result<Vec<OrderedImage>, Error> {
-		// TODO: reordered images should be approved
-
 		let query = Self::query(includes);
 
 		let images = conn
@@ -165,6 +417,14 @@ impl Image {
 					use self::image::dsl::*;
 					use self::location_image::dsl::*;
 
+					let approvals = location_image
+						.filter(location_id.eq(l_id))
+						.filter(approved_at.is_not_null())
+						.select((image_id, approved_at, approved_by))
+						.load::<(i32, Option<NaiveDateTime>, Option<i32>)>(
+							conn,
+						)?;
+
 					diesel::delete(location_image.filter(location_id.eq(l_id)))
 						.execute(conn)?;
 
@@ -172,33 +432,172 @@ impl Image {
 						.values(new_order)
 						.execute(conn)?;
 
+					for (img_id, at, by) in approvals {
+						diesel::update(location_image.find((l_id, img_id)))
+							.set((approved_at.eq(at), approved_by.eq(by)))
+							.execute(conn)?;
+					}
+
 					location_image
 						.filter(location_id.eq(l_id))
 						.inner_join(query.on(image_id.eq(id)))
-						.order(index.asc())
-						.select((Self::as_select(), index))
+						.order((is_cover.desc(), index.asc()))
+						.select((Self::as_select(), index, is_cover))
 						.get_results(conn)
 						.map_err(Into::into)
 				})
 			})
 			.await??
 			.into_iter()
-			.map(|(image, index)| OrderedImage { image, index })
+			.map(|(image, index, is_cover)| {
+				OrderedImage { image, index, is_cover }
+			})
 			.collect();
 
 		Ok(images)
 	}
+
+	/// Approve the [`Image`] with the given id for the
+	/// [`Location`](crate::Location) with the given id
+	#[instrument(skip(conn))]
+	pub async fn approve_for_location(
+		loc_id: i32,
+		img_id: i32,
+		approver_id: i32,
+		conn: &DbConn,
+	) -> Result<(), Error> {
+		let count = conn
+			.interact(move |conn| {
+				use self::location_image::dsl::*;
+
+				diesel::update(location_image.find((loc_id, img_id)))
+					.set((
+						approved_at.eq(Utc::now().naive_utc()),
+						approved_by.eq(approver_id),
+					))
+					.execute(conn)
+			})
+			.await??;
+
+		if count == 0 {
+			return Err(Error::NotFound(format!(
+				"image with id {img_id} for location {loc_id}"
+			)));
+		}
+
+		info!("approved image {img_id} for location {loc_id}");
+
+		Ok(())
+	}
+
+	/// Reject the [`Image`] with the given id for the
+	/// [`Location`](crate::Location) with the given id, deleting it
+	///
+	/// Returns the path of the stored image file, if any, so the caller can
+	/// remove it from disk
+	#[instrument(skip(conn))]
+	pub async fn reject_for_location(
+		loc_id: i32,
+		img_id: i32,
+		conn: &DbConn,
+	) -> Result<Option<String>, Error> {
+		let file_path = conn
+			.interact(move |conn| {
+				conn.transaction::<_, Error, _>(|conn| {
+					// Fails if the image does not belong to this location
+					diesel::delete(
+						location_image::table.find((loc_id, img_id)),
+					)
+					.returning(location_image::image_id)
+					.get_result::<i32>(conn)?;
+
+					let file_path = diesel::delete(image::table.find(img_id))
+						.returning(image::file_path)
+						.get_result(conn)?;
+
+					Ok(file_path)
+				})
+			})
+			.await??;
+
+		info!("rejected image {img_id} for location {loc_id}");
+
+		Ok(file_path)
+	}
+
+	/// Approve all images of the [`Location`](crate::Location) with the given
+	/// id that have not been approved yet, returning the amount of approved
+	/// images
+	#[instrument(skip(conn))]
+	pub async fn approve_all_pending_for_location(
+		loc_id: i32,
+		approver_id: i32,
+		conn: &DbConn,
+	) -> Result<usize, Error> {
+		let count = conn
+			.interact(move |conn| {
+				use self::location_image::dsl::*;
+
+				diesel::update(
+					location_image
+						.filter(location_id.eq(loc_id))
+						.filter(approved_at.is_null()),
+				)
+				.set((
+					approved_at.eq(Utc::now().naive_utc()),
+					approved_by.eq(approver_id),
+				))
+				.execute(conn)
+			})
+			.await??;
+
+		info!("approved {count} pending images for location {loc_id}");
+
+		Ok(count)
+	}
 }
 
 #[derive(Clone, Debug, Deserialize, Insertable, Serialize)]
 #[diesel(table_name = image)]
 pub struct NewImage {
-	pub file_path:   Option<String>,
-	pub uploaded_by: i32,
-	pub image_url:   Option<String>,
+	pub file_path:    Option<String>,
+	pub uploaded_by:  i32,
+	pub image_url:    Option<String>,
+	pub content_hash: Option<String>,
 }
 
 impl NewImage {
+	/// Insert a [`NewImage`] with an index for a specific [`Location`] using
+	/// an existing connection
+	///
+	/// This should always be called from inside a transaction
+	pub fn insert_for_location_with_conn(
+		self,
+		loc_id: i32,
+		image_index: i32,
+		conn: &mut PgConnection,
+	) -> Result<PrimitiveImage, Error> {
+		use self::image::dsl::*;
+		use self::location_image::dsl::*;
+
+		let inserted_image = diesel::insert_into(image)
+			.values(self)
+			.returning(PrimitiveImage::as_returning())
+			.get_result(conn)?;
+
+		let new_location_image = NewLocationImage {
+			location_id: loc_id,
+			image_id:    inserted_image.id,
+			index:       image_index,
+		};
+
+		diesel::insert_into(location_image)
+			.values(new_location_image)
+			.execute(conn)?;
+
+		Ok(inserted_image)
+	}
+
 	/// Insert a [`NewImage`] with an index for a specific [`Location`]
 	#[instrument(skip(conn))]
 	pub async fn insert_for_location(
@@ -210,25 +609,11 @@ impl NewImage {
 		let primitive = conn
 			.interact(move |conn| {
 				conn.transaction::<_, Error, _>(|conn| {
-					use self::image::dsl::*;
-					use self::location_image::dsl::*;
-
-					let inserted_image = diesel::insert_into(image)
-						.values(self)
-						.returning(PrimitiveImage::as_returning())
-						.get_result(conn)?;
-
-					let new_location_image = NewLocationImage {
-						location_id: loc_id,
-						image_id:    inserted_image.id,
-						index:       image_index,
-					};
-
-					diesel::insert_into(location_image)
-						.values(new_location_image)
-						.execute(conn)?;
-
-					Ok(inserted_image)
+					self.insert_for_location_with_conn(
+						loc_id,
+						image_index,
+						conn,
+					)
 				})
 			})
 			.await??;
@@ -237,33 +622,39 @@ impl NewImage {
 			Image::get_by_id(primitive.id, ImageIncludes::default(), conn)
 				.await?;
 
-		let ordered_image = OrderedImage { image, index: image_index };
+		let ordered_image =
+			OrderedImage { image, index: image_index, is_cover: false };
 
 		Ok(ordered_image)
 	}
 
-	/// Insert a [`NewImage`] for a specific [`Profile`]
+	/// Insert a [`NewImage`] as the avatar of a specific [`Profile`]
+	///
+	/// The previous avatar of the profile is deleted in the same transaction,
+	/// the path of its stored file is returned alongside the new image so the
+	/// caller can remove it from disk
 	#[instrument(skip(conn))]
 	pub async fn insert_for_profile(
 		self,
 		p_id: i32,
 		conn: &DbConn,
-	) -> Result<Image, Error> {
-		let primitive = conn
+	) -> Result<(Image, Option<String>), Error> {
+		let (primitive, replaced) = conn
 			.interact(move |conn| {
 				conn.transaction::<_, Error, _>(|conn| {
-					use self::profile::dsl::*;
+					let replaced =
+						Image::delete_avatar_for_profile_with_conn(p_id, conn)?;
 
 					let image_record = diesel::insert_into(image::table)
 						.values(self)
 						.returning(PrimitiveImage::as_returning())
 						.get_result(conn)?;
 
-					diesel::update(profile.find(p_id))
-						.set(avatar_image_id.eq(image_record.id))
+					diesel::update(profile::table.find(p_id))
+						.set(profile::avatar_image_id.eq(image_record.id))
 						.execute(conn)?;
 
-					Ok(image_record)
+					Ok((image_record, replaced))
 				})
 			})
 			.await??;
@@ -272,7 +663,7 @@ impl NewImage {
 			Image::get_by_id(primitive.id, ImageIncludes::default(), conn)
 				.await?;
 
-		Ok(image)
+		Ok((image, replaced))
 	}
 }
 
@@ -288,6 +679,7 @@ pub struct LocationImage {
 	pub approved_at: Option<NaiveDateTime>,
 	pub approved_by: Option<i32>,
 	pub index:       i32,
+	pub is_cover:    bool,
 }
 
 #[derive(Clone, Debug, Deserialize, Insertable, Serialize)]
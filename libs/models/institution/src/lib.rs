@@ -1,14 +1,17 @@
 #[macro_use]
 extern crate tracing;
 
+use std::collections::HashMap;
+
 use ::role::NewInstitutionRole;
-use ::translation::NewTranslation;
-use base::{PaginatedData, PaginationConfig, manual_pagination};
+use ::translation::{Language, NewTranslation, TranslationUpdate};
+use base::{BoxedCondition, PaginatedData, PaginationConfig, ToFilter};
 use common::{DbConn, Error};
 use db::{
 	CreatorAlias,
 	InstitutionCategory,
 	UpdaterAlias,
+	authority,
 	creator,
 	institution,
 	institution_member,
@@ -17,7 +20,7 @@ use db::{
 	translation,
 	updater,
 };
-use diesel::dsl::{AliasedFields, Nullable};
+use diesel::dsl::{AliasedFields, Nullable, exists};
 use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::sql_types::Bool;
@@ -30,8 +33,10 @@ use primitives::{
 use serde::{Deserialize, Serialize};
 
 mod member;
+mod statistics;
 
 pub use member::*;
+pub use statistics::*;
 
 #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
 #[allow(clippy::struct_excessive_bools)]
@@ -41,6 +46,38 @@ pub struct InstitutionIncludes {
 	pub created_by: bool,
 	#[serde(default)]
 	pub updated_by: bool,
+	/// Include the [`InstitutionCounts`], only used when listing
+	/// institutions
+	#[serde(default)]
+	pub counts:     bool,
+	/// Resolve translations to a single [`Language`] in responses
+	#[serde(default)]
+	pub lang:       Option<Language>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstitutionFilter {
+	pub category: Option<InstitutionCategory>,
+}
+
+impl<S> ToFilter<S> for InstitutionFilter
+where
+	S: 'static,
+	institution::category: SelectableExpression<S>,
+{
+	type SqlType = Bool;
+
+	fn to_filter(&self) -> BoxedCondition<S, Self::SqlType> {
+		let mut filter: BoxedCondition<S, Self::SqlType> =
+			Box::new(true.into_sql::<Bool>());
+
+		if let Some(category) = self.category {
+			filter = Box::new(filter.and(institution::category.eq(category)));
+		}
+
+		filter
+	}
 }
 
 #[derive(Clone, Debug, Deserialize, Queryable, Selectable, Serialize)]
@@ -99,21 +136,118 @@ impl Institution {
 			)
 	}
 
+	/// Get a page of [`Institution`]s, optionally including their
+	/// [`InstitutionCounts`]
 	#[instrument(skip(conn))]
 	pub async fn get_all(
+		filter: InstitutionFilter,
 		includes: InstitutionIncludes,
 		p_cfg: PaginationConfig,
 		conn: &DbConn,
-	) -> Result<PaginatedData<Vec<Self>>, Error> {
+	) -> Result<PaginatedData<Vec<(Self, Option<InstitutionCounts>)>>, Error> {
+		let count_filter = filter.to_filter();
+		let filter = filter.to_filter();
+		let count_query = Self::query(includes);
 		let query = Self::query(includes);
 
-		let institutions = conn
+		let after_id = p_cfg.after_id.unwrap_or(i32::MIN);
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.sql_offset() as i64;
+
+		let (total, institutions) = conn
+			.interact(move |conn| {
+				let total = count_query
+					.filter(count_filter)
+					.count()
+					.get_result::<i64>(conn)?;
+
+				let institutions = query
+					.filter(filter)
+					.filter(institution::id.gt(after_id))
+					.order(institution::id)
+					.limit(limit)
+					.offset(offset)
+					.select(Self::as_select())
+					.get_results::<Self>(conn)?;
+
+				Ok::<_, Error>((total, institutions))
+			})
+			.await??;
+
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		p_cfg.check_offset(total)?;
+
+		let mut counts = if includes.counts {
+			let inst_ids =
+				institutions.iter().map(|i| i.primitive.id).collect();
+
+			Self::get_counts(inst_ids, conn).await?
+		} else {
+			HashMap::new()
+		};
+
+		let institutions = institutions
+			.into_iter()
+			.map(|i| {
+				let i_counts = counts.remove(&i.primitive.id);
+
+				(i, i_counts)
+			})
+			.collect();
+
+		Ok((total, false, institutions))
+	}
+
+	/// Get all [`Institution`]s belonging to a given [`InstitutionCategory`]
+	#[instrument(skip(conn))]
+	pub async fn get_by_category(
+		category: InstitutionCategory,
+		includes: InstitutionIncludes,
+		p_cfg: PaginationConfig,
+		conn: &DbConn,
+	) -> Result<PaginatedData<Vec<(Self, Option<InstitutionCounts>)>>, Error> {
+		let filter = InstitutionFilter { category: Some(category) };
+
+		Self::get_all(filter, includes, p_cfg, conn).await
+	}
+
+	/// Check if no [`Institution`] in the given [`InstitutionCategory`] uses
+	/// the given name in any language yet
+	#[instrument(skip(conn))]
+	pub async fn name_is_available(
+		name: &str,
+		category: InstitutionCategory,
+		conn: &DbConn,
+	) -> Result<bool, Error> {
+		let name = name.to_string();
+
+		let taken = conn
 			.interact(move |conn| {
-				query.select(Self::as_select()).get_results(conn)
+				let name_matches = translation::nl
+					.eq(name.clone())
+					.or(translation::en.eq(name.clone()))
+					.or(translation::fr.eq(name.clone()))
+					.or(translation::de.eq(name));
+
+				diesel::select(exists(
+					institution::table
+						.inner_join(
+							translation::table
+								.on(institution::name_translation_id
+									.eq(translation::id)),
+						)
+						.filter(institution::category.eq(category))
+						.filter(name_matches),
+				))
+				.get_result::<bool>(conn)
 			})
 			.await??;
 
-		manual_pagination(institutions, p_cfg)
+		Ok(!taken)
 	}
 
 	/// Get an [`Institution`] given its id
@@ -138,6 +272,43 @@ impl Institution {
 
 		Ok(institution)
 	}
+
+	/// Delete an [`Institution`] given its id, together with its name
+	/// translation
+	///
+	/// Fails with [`Error::InstitutionInUse`] if any authorities are still
+	/// linked to the institution, these should be unlinked or removed first
+	#[instrument(skip(conn))]
+	pub async fn delete_by_id(i_id: i32, conn: &DbConn) -> Result<(), Error> {
+		conn.interact(move |conn| {
+			conn.transaction::<_, Error, _>(|conn| {
+				let authority_ids = authority::table
+					.filter(authority::institution_id.eq(i_id))
+					.select(authority::id)
+					.order(authority::id)
+					.get_results::<i32>(conn)?;
+
+				if !authority_ids.is_empty() {
+					return Err(Error::InstitutionInUse { authority_ids });
+				}
+
+				let name_translation_id =
+					diesel::delete(institution::table.find(i_id))
+						.returning(institution::name_translation_id)
+						.get_result::<i32>(conn)?;
+
+				diesel::delete(translation::table.find(name_translation_id))
+					.execute(conn)?;
+
+				Ok(())
+			})
+		})
+		.await??;
+
+		info!("deleted institution with id {i_id}");
+
+		Ok(())
+	}
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -250,3 +421,88 @@ impl NewInstitution {
 		Ok(institution)
 	}
 }
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct InstitutionUpdate {
+	pub name_translation: Option<TranslationUpdate>,
+	pub email:            Option<String>,
+	pub phone_number:     Option<String>,
+	pub street:           Option<String>,
+	pub number:           Option<String>,
+	pub zip:              Option<String>,
+	pub city:             Option<String>,
+	pub province:         Option<String>,
+	pub country:          Option<String>,
+	pub category:         Option<InstitutionCategory>,
+	pub slug:             Option<String>,
+	pub updated_by:       i32,
+}
+
+#[derive(AsChangeset, Clone, Debug, Deserialize, Serialize)]
+#[diesel(table_name = institution)]
+#[diesel(check_for_backend(Pg))]
+struct InsertableInstitutionUpdate {
+	email:        Option<String>,
+	phone_number: Option<String>,
+	street:       Option<String>,
+	number:       Option<String>,
+	zip:          Option<String>,
+	city:         Option<String>,
+	province:     Option<String>,
+	country:      Option<String>,
+	category:     Option<InstitutionCategory>,
+	slug:         Option<String>,
+	updated_by:   i32,
+}
+
+impl InstitutionUpdate {
+	/// Apply this update to the [`Institution`] with the given id
+	#[instrument(skip(conn))]
+	pub async fn apply_to(
+		self,
+		i_id: i32,
+		includes: InstitutionIncludes,
+		conn: &DbConn,
+	) -> Result<Institution, Error> {
+		conn.interact(move |conn| {
+			conn.transaction::<_, Error, _>(|conn| {
+				let institution_update = InsertableInstitutionUpdate {
+					email:        self.email,
+					phone_number: self.phone_number,
+					street:       self.street,
+					number:       self.number,
+					zip:          self.zip,
+					city:         self.city,
+					province:     self.province,
+					country:      self.country,
+					category:     self.category,
+					slug:         self.slug,
+					updated_by:   self.updated_by,
+				};
+
+				let name_translation_id: i32 =
+					diesel::update(institution::table.find(i_id))
+						.set(institution_update)
+						.returning(institution::name_translation_id)
+						.get_result(conn)?;
+
+				if let Some(name) = self.name_translation {
+					diesel::update(
+						translation::table.find(name_translation_id),
+					)
+					.set(name)
+					.execute(conn)?;
+				}
+
+				Ok(())
+			})
+		})
+		.await??;
+
+		let institution = Institution::get_by_id(i_id, includes, conn).await?;
+
+		info!("updated institution {institution:?}");
+
+		Ok(institution)
+	}
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use common::{DbConn, Error};
+use db::{
+	authority,
+	authority_member,
+	institution_member,
+	location,
+	opening_time,
+	reservation,
+};
+use diesel::dsl::{count_distinct, count_star};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::Institution;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct InstitutionStatistics {
+	pub authority_count:         i64,
+	pub total_location_count:    i64,
+	/// Amount of locations that have been neither approved nor rejected yet
+	pub pending_location_count:  i64,
+	/// Amount of distinct profiles that are a member of at least one of the
+	/// institution's authorities
+	pub total_member_count:      i64,
+	pub total_reservation_count: i64,
+}
+
+/// Amount of members and locations of an [`Institution`]
+///
+/// Locations are counted over all authorities of the institution
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct InstitutionCounts {
+	pub member_count:   i64,
+	pub location_count: i64,
+}
+
+impl Institution {
+	/// Get the [`InstitutionCounts`] for a list of institutions, keyed by id
+	///
+	/// Every count is a single grouped query over all given institutions,
+	/// institutions without members or locations get a count of zero
+	#[instrument(skip(conn))]
+	pub async fn get_counts(
+		inst_ids: Vec<i32>,
+		conn: &DbConn,
+	) -> Result<HashMap<i32, InstitutionCounts>, Error> {
+		let mut counts: HashMap<i32, InstitutionCounts> = inst_ids
+			.iter()
+			.map(|i_id| (*i_id, InstitutionCounts::default()))
+			.collect();
+
+		let (member_counts, location_counts) = conn
+			.interact(move |conn| {
+				let location_counts = location::table
+					.inner_join(authority::table)
+					.filter(authority::institution_id.eq_any(inst_ids.clone()))
+					.group_by(authority::institution_id)
+					.select((authority::institution_id, count_star()))
+					.get_results::<(Option<i32>, i64)>(conn)?;
+
+				let member_counts = institution_member::table
+					.filter(institution_member::institution_id.eq_any(inst_ids))
+					.group_by(institution_member::institution_id)
+					.select((institution_member::institution_id, count_star()))
+					.get_results::<(i32, i64)>(conn)?;
+
+				Ok::<_, Error>((member_counts, location_counts))
+			})
+			.await??;
+
+		for (i_id, member_count) in member_counts {
+			counts.entry(i_id).or_default().member_count = member_count;
+		}
+
+		for (i_id, location_count) in location_counts {
+			let Some(i_id) = i_id else { continue };
+
+			counts.entry(i_id).or_default().location_count = location_count;
+		}
+
+		Ok(counts)
+	}
+
+	/// Get the dashboard statistics of a given [`Institution`], rolled up
+	/// over all of its authorities
+	#[instrument(skip(conn))]
+	pub async fn get_statistics(
+		inst_id: i32,
+		conn: &DbConn,
+	) -> Result<InstitutionStatistics, Error> {
+		let (authorities, locations, pending, members, reservations) =
+			tokio::join!(
+				conn.interact(move |conn| {
+					authority::table
+						.filter(authority::institution_id.eq(inst_id))
+						.count()
+						.get_result::<i64>(conn)
+				}),
+				conn.interact(move |conn| {
+					location::table
+						.inner_join(authority::table)
+						.filter(authority::institution_id.eq(inst_id))
+						.count()
+						.get_result::<i64>(conn)
+				}),
+				conn.interact(move |conn| {
+					location::table
+						.inner_join(authority::table)
+						.filter(authority::institution_id.eq(inst_id))
+						.filter(location::approved_at.is_null())
+						.filter(location::rejected_at.is_null())
+						.count()
+						.get_result::<i64>(conn)
+				}),
+				conn.interact(move |conn| {
+					authority_member::table
+						.inner_join(authority::table)
+						.filter(authority::institution_id.eq(inst_id))
+						.select(count_distinct(authority_member::profile_id))
+						.get_result::<i64>(conn)
+				}),
+				conn.interact(move |conn| {
+					reservation::table
+						.inner_join(opening_time::table.inner_join(
+							location::table.inner_join(authority::table),
+						))
+						.filter(authority::institution_id.eq(inst_id))
+						.count()
+						.get_result::<i64>(conn)
+				}),
+			);
+
+		Ok(InstitutionStatistics {
+			authority_count:         authorities??,
+			total_location_count:    locations??,
+			pending_location_count:  pending??,
+			total_member_count:      members??,
+			total_reservation_count: reservations??,
+		})
+	}
+}
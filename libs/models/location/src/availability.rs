@@ -0,0 +1,229 @@
+use ::reservation::Reservation;
+use base::RESERVATION_BLOCK_SIZE_MINUTES;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use common::{DbConn, Error};
+use db::{location, opening_time};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, Date, Integer, Time};
+use primitives::PrimitiveOpeningTime;
+use serde::{Deserialize, Serialize};
+
+use crate::Location;
+
+/// Whether a [`Location`] can be reserved at some point in time
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct Availability {
+	pub available:       bool,
+	/// The opening time covering the requested point in time, if any
+	pub opening_time_id: Option<i32>,
+}
+
+/// The amount of days, starting today, to look ahead for upcoming
+/// availability
+pub const UPCOMING_AVAILABILITY_DAYS: i32 = 14;
+
+/// Summary of the first upcoming opening time of a [`Location`] that still
+/// has free blocks
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct LocationAvailabilitySummary {
+	pub location_id:     i32,
+	pub next_date:       Option<NaiveDate>,
+	pub next_start_time: Option<NaiveTime>,
+	pub free_blocks:     i32,
+}
+
+impl LocationAvailabilitySummary {
+	/// A summary for a location without any upcoming free blocks
+	#[must_use]
+	pub fn unavailable(location_id: i32) -> Self {
+		Self {
+			location_id,
+			next_date: None,
+			next_start_time: None,
+			free_blocks: 0,
+		}
+	}
+}
+
+#[derive(Debug, QueryableByName)]
+#[diesel(check_for_backend(Pg))]
+struct UpcomingAvailabilityRow {
+	#[diesel(sql_type = Integer)]
+	location_id: i32,
+	#[diesel(sql_type = Date)]
+	day:         NaiveDate,
+	#[diesel(sql_type = Time)]
+	start_time:  NaiveTime,
+	#[diesel(sql_type = Integer)]
+	free_blocks: i32,
+}
+
+/// Find the first opening time with free blocks within the upcoming window
+/// for every location
+///
+/// Free blocks are counted the same way as for the next available opening
+/// times, by comparing the non-cancelled reservations occupying every block
+/// against the seat count
+const UPCOMING_AVAILABILITY_QUERY: &str = r"
+SELECT DISTINCT ON (location.id)
+	location.id AS location_id,
+	opening_time.day,
+	opening_time.start_time,
+	availability.free_blocks::INTEGER AS free_blocks
+FROM opening_time
+INNER JOIN location ON location.id = opening_time.location_id
+CROSS JOIN LATERAL (
+	SELECT COUNT(*) AS free_blocks
+	FROM generate_series(
+		0,
+		(
+			EXTRACT(EPOCH FROM opening_time.end_time - opening_time.start_time)
+			/ 60 / $2
+		)::INTEGER - 1
+	) AS block
+	WHERE (
+		SELECT COUNT(*)
+		FROM reservation
+		WHERE reservation.opening_time_id = opening_time.id
+			AND reservation.state <> 'cancelled'
+			AND reservation.base_block_index <= block
+			AND block < reservation.base_block_index + reservation.block_count
+	) < COALESCE(opening_time.seat_count, location.seat_count)
+) AS availability
+WHERE location.id = ANY($1)
+	AND opening_time.day >= CURRENT_DATE
+	AND opening_time.day < CURRENT_DATE + $3
+	AND availability.free_blocks > 0
+ORDER BY
+	location.id,
+	opening_time.day ASC,
+	opening_time.start_time ASC
+";
+
+impl Location {
+	/// Get the [`Availability`] of a [`Location`] at a given point in time
+	///
+	/// A location is available if one of its opening times covers the given
+	/// time and the block containing it could be reserved right now, as
+	/// checked by [`Reservation::validate_blocks`]. Points in time that have
+	/// already passed are never available
+	#[instrument(skip(conn))]
+	pub async fn get_availability_at(
+		loc_id: i32,
+		at: NaiveDateTime,
+		conn: &DbConn,
+	) -> Result<Availability, Error> {
+		if at < Utc::now().naive_utc() {
+			return Ok(Availability::default());
+		}
+
+		let availability = conn
+			.interact(move |conn| {
+				let times = opening_time::table
+					.inner_join(
+						location::table
+							.on(opening_time::location_id.eq(location::id)),
+					)
+					.filter(location::id.eq(loc_id))
+					.filter(opening_time::day.eq(at.date()))
+					.filter(opening_time::start_time.le(at.time()))
+					.filter(opening_time::end_time.gt(at.time()))
+					.select((
+						PrimitiveOpeningTime::as_select(),
+						location::seat_count,
+					))
+					.get_results::<(PrimitiveOpeningTime, i32)>(conn)?;
+
+				let block_size = i64::from(RESERVATION_BLOCK_SIZE_MINUTES);
+
+				let mut availability = Availability::default();
+
+				for (time, loc_seats) in times {
+					let offset = (at.time() - time.start_time).num_minutes();
+					#[allow(clippy::cast_possible_truncation)]
+					let block = (offset / block_size) as i32;
+
+					availability.opening_time_id = Some(time.id);
+
+					match Reservation::validate_blocks(
+						&time,
+						time.seat_count.unwrap_or(loc_seats),
+						None,
+						block..block + 1,
+						None,
+						conn,
+					) {
+						Ok(()) => {
+							availability.available = true;
+							break;
+						},
+						Err(Error::CreateReservationError(_)) => {},
+						Err(e) => return Err(e),
+					}
+				}
+
+				Ok::<_, Error>(availability)
+			})
+			.await??;
+
+		Ok(availability)
+	}
+
+	/// Check if a [`Location`] can be reserved at a given point in time
+	///
+	/// This uses the same checks as creating a reservation, except for the
+	/// overlap with reservations of the requesting profile
+	#[instrument(skip(conn))]
+	pub async fn is_available_at(
+		loc_id: i32,
+		at: NaiveDateTime,
+		conn: &DbConn,
+	) -> Result<bool, Error> {
+		let availability = Self::get_availability_at(loc_id, at, conn).await?;
+
+		Ok(availability.available)
+	}
+
+	/// Get a [`LocationAvailabilitySummary`] for each of the given locations
+	///
+	/// Only opening times within the next [`UPCOMING_AVAILABILITY_DAYS`] days
+	/// are considered, locations without free blocks in that window get an
+	/// empty summary
+	#[instrument(skip(conn))]
+	pub async fn get_upcoming_availability_summary_for_ids(
+		loc_ids: Vec<i32>,
+		conn: &DbConn,
+	) -> Result<Vec<LocationAvailabilitySummary>, Error> {
+		let query_ids = loc_ids.clone();
+
+		let rows = conn
+			.interact(move |conn| {
+				diesel::sql_query(UPCOMING_AVAILABILITY_QUERY)
+					.bind::<Array<Integer>, _>(query_ids)
+					.bind::<Integer, _>(RESERVATION_BLOCK_SIZE_MINUTES)
+					.bind::<Integer, _>(UPCOMING_AVAILABILITY_DAYS)
+					.load::<UpcomingAvailabilityRow>(conn)
+			})
+			.await??;
+
+		let summaries = loc_ids
+			.into_iter()
+			.map(|id| {
+				let Some(row) = rows.iter().find(|r| r.location_id == id)
+				else {
+					return LocationAvailabilitySummary::unavailable(id);
+				};
+
+				LocationAvailabilitySummary {
+					location_id:     id,
+					next_date:       Some(row.day),
+					next_start_time: Some(row.start_time),
+					free_blocks:     row.free_blocks,
+				}
+			})
+			.collect();
+
+		Ok(summaries)
+	}
+}
@@ -0,0 +1,136 @@
+use common::{DbConn, Error};
+use db::{
+	image,
+	location,
+	location_image,
+	location_member,
+	location_role,
+	location_tag,
+	opening_time,
+	reservation,
+	review,
+	translation,
+};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::Location;
+
+/// The records that were removed when permanently deleting a [`Location`]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LocationDeletion {
+	pub opening_times: i64,
+	pub reservations:  i64,
+	pub images:        i64,
+	pub tags:          i64,
+	pub members:       i64,
+	pub roles:         i64,
+	pub reviews:       i64,
+	pub translations:  i64,
+	/// Paths of the image files that belonged to the location, these still
+	/// have to be removed from disk
+	pub image_files:   Vec<String>,
+}
+
+impl Location {
+	/// Permanently delete a [`Location`] together with all of its related
+	/// records
+	///
+	/// Image files are not removed from disk, their paths are returned so the
+	/// caller can clean them up once the deletion succeeded
+	#[instrument(skip(conn))]
+	pub async fn hard_delete(
+		loc_id: i32,
+		conn: &DbConn,
+	) -> Result<LocationDeletion, Error> {
+		let deletion = conn
+			.interact(move |conn| {
+				conn.transaction::<_, Error, _>(|conn| {
+					let (description_id, excerpt_id) = location::table
+						.find(loc_id)
+						.select((
+							location::description_id,
+							location::excerpt_id,
+						))
+						.for_update()
+						.get_result::<(i32, i32)>(conn)?;
+
+					let opening_times = opening_time::table
+						.filter(opening_time::location_id.eq(loc_id))
+						.count()
+						.get_result(conn)?;
+
+					let reservations = reservation::table
+						.inner_join(opening_time::table.on(
+							reservation::opening_time_id.eq(opening_time::id),
+						))
+						.filter(opening_time::location_id.eq(loc_id))
+						.count()
+						.get_result(conn)?;
+
+					let tags = location_tag::table
+						.filter(location_tag::location_id.eq(loc_id))
+						.count()
+						.get_result(conn)?;
+
+					let members = location_member::table
+						.filter(location_member::location_id.eq(loc_id))
+						.count()
+						.get_result(conn)?;
+
+					let roles = location_role::table
+						.filter(location_role::location_id.eq(loc_id))
+						.count()
+						.get_result(conn)?;
+
+					let reviews = review::table
+						.filter(review::location_id.eq(loc_id))
+						.count()
+						.get_result(conn)?;
+
+					let image_ids = location_image::table
+						.filter(location_image::location_id.eq(loc_id))
+						.select(location_image::image_id);
+
+					// Images are not owned by the location table, so they have
+					// to be removed explicitly
+					let image_files: Vec<Option<String>> =
+						diesel::delete(image::table)
+							.filter(image::id.eq_any(image_ids))
+							.returning(image::file_path)
+							.get_results(conn)?;
+
+					// Deleting the translations cascades to the location
+					// itself, and from there to all its remaining records
+					let translation_ids = [description_id, excerpt_id];
+					let translations = diesel::delete(translation::table)
+						.filter(translation::id.eq_any(translation_ids))
+						.execute(conn)?;
+
+					#[allow(clippy::cast_possible_wrap)]
+					let images = image_files.len() as i64;
+					#[allow(clippy::cast_possible_wrap)]
+					let translations = translations as i64;
+					let image_files =
+						image_files.into_iter().flatten().collect();
+
+					Ok(LocationDeletion {
+						opening_times,
+						reservations,
+						images,
+						tags,
+						members,
+						roles,
+						reviews,
+						translations,
+						image_files,
+					})
+				})
+			})
+			.await??;
+
+		info!("permanently deleted location {loc_id}: {deletion:?}");
+
+		Ok(deletion)
+	}
+}
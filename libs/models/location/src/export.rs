@@ -0,0 +1,135 @@
+use chrono::NaiveDate;
+use common::{DbConn, Error};
+use diesel::pg::{Pg, PgRowByRowLoadingMode};
+use diesel::prelude::*;
+use diesel::sql_types::{Array, BigInt, Bool, Date, Integer, Nullable, Text};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::Sender;
+
+/// A single [`Location`](crate::Location) of an authority as it is exported,
+/// together with its tags and reservation totals
+#[derive(Clone, Debug, Deserialize, QueryableByName, Serialize)]
+#[diesel(check_for_backend(Pg))]
+pub struct LocationExportRow {
+	#[diesel(sql_type = Integer)]
+	pub id:                i32,
+	#[diesel(sql_type = Text)]
+	pub name:              String,
+	#[diesel(sql_type = Text)]
+	pub street:            String,
+	#[diesel(sql_type = Text)]
+	pub number:            String,
+	#[diesel(sql_type = Text)]
+	pub zip:               String,
+	#[diesel(sql_type = Text)]
+	pub city:              String,
+	#[diesel(sql_type = Text)]
+	pub province:          String,
+	#[diesel(sql_type = Text)]
+	pub country:           String,
+	#[diesel(sql_type = Integer)]
+	pub seat_count:        i32,
+	/// One of `approved`, `rejected` or `pending`
+	#[diesel(sql_type = Text)]
+	pub approval_state:    String,
+	#[diesel(sql_type = Bool)]
+	pub is_reservable:     bool,
+	/// The names of all tags of the location, in English where possible
+	#[diesel(sql_type = Array<Text>)]
+	pub tag_names:         Vec<String>,
+	/// Amount of reservations in the requested range that were not cancelled
+	#[diesel(sql_type = BigInt)]
+	pub reservation_count: i64,
+}
+
+/// Select every location of an authority with its aggregates joined in
+///
+/// Tag names follow the fallback order of `Language::resolve` for English,
+/// both ends of the reservation range are optional and inclusive
+const LOCATION_EXPORT_QUERY: &str = r"
+SELECT
+	location.id,
+	location.name,
+	location.street,
+	location.number,
+	location.zip,
+	location.city,
+	location.province,
+	location.country,
+	location.seat_count,
+	CASE
+		WHEN location.approved_at IS NOT NULL THEN 'approved'
+		WHEN location.rejected_at IS NOT NULL THEN 'rejected'
+		ELSE 'pending'
+	END AS approval_state,
+	location.is_reservable,
+	tags.tag_names,
+	reservations.reservation_count
+FROM location
+CROSS JOIN LATERAL (
+	SELECT
+		COALESCE(
+			ARRAY_AGG(
+				COALESCE(
+					translation.en,
+					translation.nl,
+					translation.fr,
+					translation.de
+				)
+				ORDER BY tag.id
+			),
+			ARRAY[]::TEXT[]
+		) AS tag_names
+	FROM location_tag
+	INNER JOIN tag ON tag.id = location_tag.tag_id
+	INNER JOIN translation ON translation.id = tag.name_translation_id
+	WHERE location_tag.location_id = location.id
+) AS tags
+CROSS JOIN LATERAL (
+	SELECT COUNT(*) AS reservation_count
+	FROM reservation
+	INNER JOIN opening_time ON opening_time.id = reservation.opening_time_id
+	WHERE opening_time.location_id = location.id
+		AND reservation.state <> 'cancelled'
+		AND ($2::DATE IS NULL OR opening_time.day >= $2)
+		AND ($3::DATE IS NULL OR opening_time.day <= $3)
+) AS reservations
+WHERE location.authority_id = $1
+ORDER BY location.id
+";
+
+impl LocationExportRow {
+	/// Send the export rows of all locations of an authority to `sender`,
+	/// one row at a time
+	///
+	/// Rows are read from the database as they are sent, so the export is
+	/// never held in memory as a whole. Stops early once the receiving end
+	/// is dropped, errors are returned rather than sent
+	#[instrument(skip(conn, sender))]
+	pub async fn stream_for_authority(
+		auth_id: i32,
+		from: Option<NaiveDate>,
+		to: Option<NaiveDate>,
+		sender: Sender<Result<Self, Error>>,
+		conn: &DbConn,
+	) -> Result<(), Error> {
+		conn.interact(move |conn| {
+			let rows = diesel::sql_query(LOCATION_EXPORT_QUERY)
+				.bind::<Integer, _>(auth_id)
+				.bind::<Nullable<Date>, _>(from)
+				.bind::<Nullable<Date>, _>(to)
+				.load_iter::<Self, PgRowByRowLoadingMode>(conn)?;
+
+			for row in rows {
+				if sender.blocking_send(Ok(row?)).is_err() {
+					break;
+				}
+			}
+
+			Ok::<_, Error>(())
+		})
+		.await??;
+
+		Ok(())
+	}
+}
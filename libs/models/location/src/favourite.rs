@@ -0,0 +1,126 @@
+use base::{PaginatedData, PaginationConfig};
+use common::{DbConn, Error};
+use db::location_favourite;
+use diesel::prelude::*;
+
+use crate::{FullLocationData, Location, LocationIncludes};
+
+/// A [`Location`] saved by a profile for later
+pub struct LocationFavourite;
+
+impl LocationFavourite {
+	/// Add a location to the favourites of a profile
+	///
+	/// Favouriting a location that is already a favourite does nothing
+	#[instrument(skip(conn))]
+	pub async fn add(l_id: i32, p_id: i32, conn: &DbConn) -> Result<(), Error> {
+		conn.interact(move |conn| {
+			use self::location_favourite::dsl::*;
+
+			diesel::insert_into(location_favourite)
+				.values((location_id.eq(l_id), profile_id.eq(p_id)))
+				.on_conflict_do_nothing()
+				.execute(conn)
+		})
+		.await??;
+
+		info!("profile {p_id} favourited location {l_id}");
+
+		Ok(())
+	}
+
+	/// Remove a location from the favourites of a profile
+	///
+	/// Removing a location that is not a favourite does nothing
+	#[instrument(skip(conn))]
+	pub async fn remove(
+		l_id: i32,
+		p_id: i32,
+		conn: &DbConn,
+	) -> Result<(), Error> {
+		conn.interact(move |conn| {
+			use self::location_favourite::dsl::*;
+
+			diesel::delete(
+				location_favourite
+					.filter(location_id.eq(l_id).and(profile_id.eq(p_id))),
+			)
+			.execute(conn)
+		})
+		.await??;
+
+		info!("profile {p_id} unfavourited location {l_id}");
+
+		Ok(())
+	}
+
+	/// Get a page of the favourite locations of a profile, most recently
+	/// favourited first
+	#[instrument(skip(conn))]
+	pub async fn list_for_profile(
+		p_id: i32,
+		includes: LocationIncludes,
+		p_cfg: PaginationConfig,
+		conn: &DbConn,
+	) -> Result<PaginatedData<Vec<FullLocationData>>, Error> {
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.offset as i64;
+
+		let (total, l_ids): (i64, Vec<i32>) = conn
+			.interact(move |conn| {
+				use self::location_favourite::dsl::*;
+
+				let total = location_favourite
+					.filter(profile_id.eq(p_id))
+					.count()
+					.get_result::<i64>(conn)?;
+
+				let l_ids = location_favourite
+					.filter(profile_id.eq(p_id))
+					.order((created_at.desc(), location_id))
+					.select(location_id)
+					.limit(limit)
+					.offset(offset)
+					.load(conn)?;
+
+				Ok::<_, Error>((total, l_ids))
+			})
+			.await??;
+
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		p_cfg.check_offset(total)?;
+
+		let mut locations =
+			Location::get_by_ids(l_ids.clone(), includes, conn).await?;
+
+		locations.sort_by_key(|(l, _)| {
+			l_ids.iter().position(|id| *id == l.primitive.id)
+		});
+
+		Ok((total, false, locations))
+	}
+
+	/// Get the amount of profiles that favourited a location
+	#[instrument(skip(conn))]
+	pub async fn count_for_location(
+		l_id: i32,
+		conn: &DbConn,
+	) -> Result<i64, Error> {
+		let count = conn
+			.interact(move |conn| {
+				use self::location_favourite::dsl::*;
+
+				location_favourite
+					.filter(location_id.eq(l_id))
+					.count()
+					.get_result(conn)
+			})
+			.await??;
+
+		Ok(count)
+	}
+}
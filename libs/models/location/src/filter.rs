@@ -9,7 +9,7 @@ use base::{
 	ToFilter,
 	manual_pagination,
 };
-use common::{DbConn, Error};
+use common::{DbConn, Error, PaginationError};
 use db::{location, opening_time};
 use diesel::dsl::sql;
 use diesel::pg::Pg;
@@ -17,6 +17,9 @@ use diesel::prelude::*;
 use diesel::sql_types::{Bool, Nullable, Text};
 use serde::{Deserialize, Serialize};
 use serde_with::DisplayFromStr;
+use utoipa::IntoParams;
+use utoipa::openapi::Required;
+use utoipa::openapi::path::{Parameter, ParameterIn};
 
 use crate::{Location, LocationIncludes};
 
@@ -38,23 +41,113 @@ pub struct LocationFilter {
 	reservable: Option<ReservableFilter>,
 	#[serde(flatten)]
 	bounds:     Option<BoundsFilter>,
+	#[serde(flatten)]
+	newest:     Option<NewestFilter>,
+	#[serde(flatten)]
+	zip:        Option<ZipFilter>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+impl LocationFilter {
+	/// Whether the results of this filter should be sorted by their approval
+	/// date, newest first, instead of by id
+	#[must_use]
+	pub fn sorts_by_newest(&self) -> bool {
+		self.newest.is_some_and(|n| n.newest)
+	}
+
+	/// Whether this filter limits the results to a geographic area
+	#[must_use]
+	pub fn has_bounds(&self) -> bool { self.bounds.is_some() }
+
+	/// Whether this filter only allows reservable locations
+	#[must_use]
+	pub fn requires_reservable(&self) -> bool {
+		self.reservable.is_some_and(|r| r.is_reservable)
+	}
+
+	/// An expression that is true for locations whose name matches the
+	/// search query, used to rank name matches before description and
+	/// excerpt matches
+	///
+	/// Returns [`None`] if this filter has no search query
+	#[must_use]
+	pub fn name_rank<S>(&self) -> Option<BoxedCondition<S, Bool>> {
+		self.query.as_ref().map(QueryFilter::name_filter)
+	}
+}
+
+/// The parameters of every part of the filter, none of which are required
+impl IntoParams for LocationFilter {
+	fn into_params(
+		parameter_in_provider: impl Fn() -> Option<ParameterIn>,
+	) -> Vec<Parameter> {
+		[
+			QueryFilter::into_params(&parameter_in_provider),
+			ReservableFilter::into_params(&parameter_in_provider),
+			BoundsFilter::into_params(&parameter_in_provider),
+			NewestFilter::into_params(&parameter_in_provider),
+			ZipFilter::into_params(&parameter_in_provider),
+		]
+		.into_iter()
+		.flatten()
+		.map(|mut param| {
+			param.required = Required::False;
+			param
+		})
+		.collect()
+	}
+}
+
+/// Case-insensitive search through the name of a location and all
+/// translations of its description and excerpt
+#[derive(Clone, Debug, Deserialize, IntoParams, Serialize)]
+#[into_params(parameter_in = Query)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryFilter {
-	pub language: String,
-	pub query:    String,
+	pub query: String,
+}
+
+impl QueryFilter {
+	/// The `ILIKE` pattern matching any text containing the query, with the
+	/// `LIKE` wildcards in the query itself escaped
+	fn pattern(&self) -> String {
+		let mut pattern = String::with_capacity(self.query.len() + 2);
+		pattern.push('%');
+
+		for c in self.query.chars() {
+			if matches!(c, '\\' | '%' | '_') {
+				pattern.push('\\');
+			}
+
+			pattern.push(c);
+		}
+
+		pattern.push('%');
+		pattern
+	}
+
+	fn name_filter<S>(&self) -> BoxedCondition<S, Bool> {
+		Box::new(
+			sql::<Bool>("")
+				.bind::<Text, _>(location::name)
+				.sql(" ILIKE ")
+				.bind::<Text, _>(self.pattern()),
+		)
+	}
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, IntoParams, Serialize)]
+#[into_params(parameter_in = Query)]
 #[serde(rename_all = "camelCase")]
 pub struct ReservableFilter {
 	pub is_reservable: bool,
 }
 
+/// Only locations within a bounding box, ignored unless all four of its
+/// corners are given
 #[serde_as]
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, IntoParams, Serialize)]
+#[into_params(parameter_in = Query)]
 #[serde(rename_all = "camelCase")]
 pub struct BoundsFilter {
 	#[serde_as(as = "DisplayFromStr")]
@@ -67,6 +160,29 @@ pub struct BoundsFilter {
 	pub south_west_lng: f64,
 }
 
+#[serde_as]
+#[derive(Clone, Copy, Debug, Deserialize, IntoParams, Serialize)]
+#[into_params(parameter_in = Query)]
+#[serde(rename_all = "camelCase")]
+pub struct NewestFilter {
+	#[serde_as(as = "DisplayFromStr")]
+	pub newest: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, IntoParams, Serialize)]
+#[into_params(parameter_in = Query)]
+#[serde(rename_all = "camelCase")]
+pub struct ZipFilter {
+	pub zip: String,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, IntoParams, Serialize)]
+#[into_params(parameter_in = Query)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingFilter {
+	pub authority_id: Option<i32>,
+}
+
 impl<S> ToFilter<S> for LocationFilter
 where
 	S: 'static,
@@ -74,6 +190,7 @@ where
 	location::latitude: SelectableExpression<S>,
 	location::longitude: SelectableExpression<S>,
 	location::is_reservable: SelectableExpression<S>,
+	location::zip: SelectableExpression<S>,
 {
 	type SqlType = Nullable<Bool>;
 
@@ -93,6 +210,10 @@ where
 			filter = Box::new(filter.and(bounds.to_filter()));
 		}
 
+		if let Some(zip) = self.zip.clone() {
+			filter = Box::new(filter.and(zip.to_filter()));
+		}
+
 		filter
 	}
 }
@@ -101,27 +222,23 @@ impl<S> ToFilter<S> for QueryFilter {
 	type SqlType = Bool;
 
 	fn to_filter(&self) -> BoxedCondition<S, Self::SqlType> {
-		let language = self.language.clone().to_ascii_lowercase();
+		let pattern = self.pattern();
+		let mut filter = self.name_filter();
 
-		let dyn_description = diesel_dynamic_schema::table("description");
-		let dyn_excerpt = diesel_dynamic_schema::table("excerpt");
+		for table in ["description", "excerpt"] {
+			let dyn_table = diesel_dynamic_schema::table(table);
 
-		let name_filter = sql::<Bool>("")
-			.bind::<Text, _>(location::name)
-			.sql(" % ")
-			.bind::<Text, _>(self.query.clone());
+			for language in ["nl", "en", "fr", "de"] {
+				let translation_filter = sql::<Bool>("")
+					.bind::<Text, _>(dyn_table.column(language))
+					.sql(" ILIKE ")
+					.bind::<Text, _>(pattern.clone());
 
-		let desc_filter = sql::<Bool>("")
-			.bind::<Text, _>(dyn_description.column(language.clone()))
-			.sql(" % ")
-			.bind::<Text, _>(self.query.clone());
-
-		let exc_filter = sql::<Bool>("")
-			.bind::<Text, _>(dyn_excerpt.column(language))
-			.sql(" % ")
-			.bind::<Text, _>(self.query.clone());
+				filter = Box::new(filter.or(translation_filter));
+			}
+		}
 
-		Box::new(name_filter.or(desc_filter).or(exc_filter))
+		filter
 	}
 }
 
@@ -155,8 +272,40 @@ where
 	}
 }
 
+impl<S> ToFilter<S> for ZipFilter
+where
+	location::zip: SelectableExpression<S>,
+{
+	type SqlType = Bool;
+
+	fn to_filter(&self) -> BoxedCondition<S, Self::SqlType> {
+		Box::new(location::zip.eq(self.zip.clone()))
+	}
+}
+
+/// The filter a location needs at least one matching opening time for
+///
+/// When only reservable locations are requested, the opening time must also
+/// be reservable at the requested moment
+///
+/// Locations are not required to have any opening times if the
+/// [`TimeFilter`] is empty
+fn opening_time_filter(
+	loc_filter: &LocationFilter,
+	time_filter: TimeFilter,
+) -> BoxedCondition<opening_time::table, Bool> {
+	if loc_filter.requires_reservable() {
+		time_filter.to_reservable_filter()
+	} else {
+		time_filter.to_filter()
+	}
+}
+
 impl Location {
 	/// Search through all [`Location`]s with a given [`LocationFilter`]
+	///
+	/// If the filter [sorts by newest](LocationFilter::sorts_by_newest), only
+	/// approved locations are returned, most recently approved first
 	#[instrument(skip(conn))]
 	pub async fn search(
 		loc_filter: LocationFilter,
@@ -165,30 +314,141 @@ impl Location {
 		p_cfg: PaginationConfig,
 		conn: &DbConn,
 	) -> Result<PaginatedData<Vec<Self>>, Error> {
+		let newest = loc_filter.sorts_by_newest();
+		let rank = loc_filter.name_rank();
 		let filter = loc_filter.to_filter();
 		let query = Self::query(includes);
 
-		let time_filter = time_filter.to_filter();
+		let any_time = time_filter.is_empty();
+		let time_filter = opening_time_filter(&loc_filter, time_filter);
 
 		let locations = conn
 			.interact(move |conn| {
 				use self::location::dsl::*;
 
-				query
+				let matching = query
 					.filter(filter)
-					.filter(diesel::dsl::exists(
-						opening_time::table
-							.filter(time_filter)
-							.filter(opening_time::location_id.eq(id))
-							.select(opening_time::id),
-					))
-					.select(Self::as_select())
-					.order(id)
-					.limit(QUERY_HARD_LIMIT)
-					.get_results(conn)
+					.filter(
+						any_time.into_sql::<Bool>().or(diesel::dsl::exists(
+							opening_time::table
+								.filter(time_filter)
+								.filter(opening_time::location_id.eq(id))
+								.select(opening_time::id),
+						)),
+					)
+					.select(Self::as_select());
+
+				if newest {
+					matching
+						.filter(approved_at.is_not_null())
+						.order((approved_at.desc(), id))
+						.limit(QUERY_HARD_LIMIT)
+						.get_results(conn)
+				} else if let Some(rank) = rank {
+					matching
+						.order((rank.desc(), id))
+						.limit(QUERY_HARD_LIMIT)
+						.get_results(conn)
+				} else {
+					matching.order(id).limit(QUERY_HARD_LIMIT).get_results(conn)
+				}
 			})
 			.await??;
 
 		manual_pagination(locations, p_cfg)
 	}
+
+	/// Get a page of the visible and approved [`Location`]s matching a given
+	/// [`LocationFilter`]
+	///
+	/// Unlike [`Location::search`], the results are paginated by the database
+	/// itself, so this is not limited to the first [`QUERY_HARD_LIMIT`]
+	/// matches
+	#[instrument(skip(conn))]
+	pub async fn get_visible(
+		loc_filter: LocationFilter,
+		time_filter: TimeFilter,
+		includes: LocationIncludes,
+		p_cfg: PaginationConfig,
+		conn: &DbConn,
+	) -> Result<PaginatedData<Vec<Self>>, Error> {
+		let newest = loc_filter.sorts_by_newest();
+		let rank = loc_filter.name_rank();
+		let count_filter = loc_filter.to_filter();
+		let filter = loc_filter.to_filter();
+		let any_time = time_filter.is_empty();
+		let count_time_filter = opening_time_filter(&loc_filter, time_filter);
+		let time_filter = opening_time_filter(&loc_filter, time_filter);
+		let count_query = Self::query(includes);
+		let query = Self::query(includes);
+
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.offset as i64;
+
+		let (total, locations) = conn
+			.interact(move |conn| {
+				use self::location::dsl::*;
+
+				let total = count_query
+					.filter(count_filter)
+					.filter(approved_at.is_not_null())
+					.filter(
+						any_time.into_sql::<Bool>().or(diesel::dsl::exists(
+							opening_time::table
+								.filter(count_time_filter)
+								.filter(opening_time::location_id.eq(id))
+								.select(opening_time::id),
+						)),
+					)
+					.count()
+					.get_result::<i64>(conn)?;
+
+				let matching = query
+					.filter(filter)
+					.filter(approved_at.is_not_null())
+					.filter(
+						any_time.into_sql::<Bool>().or(diesel::dsl::exists(
+							opening_time::table
+								.filter(time_filter)
+								.filter(opening_time::location_id.eq(id))
+								.select(opening_time::id),
+						)),
+					)
+					.select(Self::as_select());
+
+				let locations = if newest {
+					matching
+						.order((approved_at.desc(), id))
+						.limit(limit)
+						.offset(offset)
+						.get_results(conn)?
+				} else if let Some(rank) = rank {
+					matching
+						.order((rank.desc(), id))
+						.limit(limit)
+						.offset(offset)
+						.get_results(conn)?
+				} else {
+					matching
+						.order(id)
+						.limit(limit)
+						.offset(offset)
+						.get_results(conn)?
+				};
+
+				Ok::<_, Error>((total, locations))
+			})
+			.await??;
+
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		if total > 0 && p_cfg.offset >= total {
+			return Err(PaginationError::OffsetTooLarge.into());
+		}
+
+		Ok((total, false, locations))
+	}
 }
@@ -5,13 +5,15 @@ extern crate tracing;
 
 use std::hash::Hash;
 
-use ::image::{Image, OrderedImage};
+use ::image::{Image, NewImage, OrderedImage};
 use ::opening_time::{OpeningTime, OpeningTimeIncludes, TimeBoundsFilter};
+use ::review::Review;
 use ::role::NewLocationRole;
 use ::tag::Tag;
-use ::translation::NewTranslation;
+use ::translation::{Language, NewTranslation, TranslationUpdate};
+use base::{PaginatedData, PaginationConfig};
 use chrono::{NaiveDateTime, Utc};
-use common::{DbConn, Error};
+use common::{DbConn, Error, PaginationError};
 use db::{
 	ApproverAlias,
 	CreatorAlias,
@@ -33,10 +35,11 @@ use db::{
 	translation,
 	updater,
 };
-use diesel::dsl::{AliasedFields, Nullable, sql};
+use diesel::dsl::{AliasedFields, Nullable, count_star, sql};
+use diesel::expression::SqlLiteral;
 use diesel::pg::Pg;
 use diesel::prelude::*;
-use diesel::sql_types::{Bool, Double};
+use diesel::sql_types::{BigInt, Bool, Double};
 use image::ImageIncludes;
 use permissions::LocationPermissions;
 use primitives::{
@@ -49,12 +52,32 @@ use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_with::DisplayFromStr;
 use tag::TagIncludes;
+use utoipa::IntoParams;
+use uuid::Uuid;
 
+mod availability;
+mod deletion;
+mod export;
+mod favourite;
 mod filter;
 mod member;
-
+mod merge;
+mod popularity;
+mod stats;
+mod version;
+
+pub use availability::*;
+pub use deletion::*;
+pub use export::*;
+pub use favourite::*;
 pub use filter::*;
 pub use member::*;
+pub use merge::*;
+pub use stats::*;
+pub use version::*;
+
+/// The maximum amount of locations returned by [`Location::get_nearby`]
+pub const NEARBY_HARD_LIMIT: usize = 50;
 
 pub type JoinedLocationData = (
 	PrimitiveLocation,
@@ -67,26 +90,32 @@ pub type JoinedLocationData = (
 	Option<PrimitiveProfile>,
 );
 
-pub type FullLocationData =
-	(Location, (Vec<OpeningTime>, Vec<Tag>, Vec<OrderedImage>));
+/// The average rating and amount of reviews of a location
+pub type ReviewAggregate = (Option<f64>, i64);
+
+pub type FullLocationData = (
+	Location,
+	(Vec<OpeningTime>, Vec<Tag>, Vec<OrderedImage>, ReviewAggregate),
+);
 
-#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, IntoParams, Serialize)]
 #[allow(clippy::struct_excessive_bools)]
+#[into_params(parameter_in = Query)]
+#[serde(default)]
 pub struct LocationIncludes {
-	#[serde(default)]
 	pub authority:   bool,
-	#[serde(default)]
 	pub approved_by: bool,
-	#[serde(default)]
 	pub rejected_by: bool,
-	#[serde(default)]
 	pub created_by:  bool,
-	#[serde(default)]
 	pub updated_by:  bool,
+	/// Resolve translations to a single [`Language`] in responses
+	#[param(inline)]
+	pub lang:        Option<Language>,
 }
 
 #[serde_as]
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, IntoParams, Serialize)]
+#[into_params(parameter_in = Query)]
 #[serde(rename_all = "camelCase")]
 pub struct Point {
 	#[serde_as(as = "DisplayFromStr")]
@@ -99,21 +128,23 @@ pub struct Point {
 #[diesel(check_for_backend(Pg))]
 pub struct Location {
 	#[diesel(embed)]
-	pub primitive:   PrimitiveLocation,
+	pub primitive:           PrimitiveLocation,
 	#[diesel(embed)]
-	pub authority:   Option<PrimitiveAuthority>,
+	pub authority:           Option<PrimitiveAuthority>,
 	#[diesel(select_expression = description_fragment())]
-	pub description: PrimitiveTranslation,
+	pub description:         PrimitiveTranslation,
 	#[diesel(select_expression = excerpt_fragment())]
-	pub excerpt:     PrimitiveTranslation,
+	pub excerpt:             PrimitiveTranslation,
 	#[diesel(select_expression = approved_by_fragment())]
-	pub approved_by: Option<PrimitiveProfile>,
+	pub approved_by:         Option<PrimitiveProfile>,
 	#[diesel(select_expression = rejected_by_fragment())]
-	pub rejected_by: Option<PrimitiveProfile>,
+	pub rejected_by:         Option<PrimitiveProfile>,
 	#[diesel(select_expression = created_by_fragment())]
-	pub created_by:  Option<PrimitiveProfile>,
+	pub created_by:          Option<PrimitiveProfile>,
 	#[diesel(select_expression = updated_by_fragment())]
-	pub updated_by:  Option<PrimitiveProfile>,
+	pub updated_by:          Option<PrimitiveProfile>,
+	#[diesel(select_expression = pending_image_count_fragment())]
+	pub pending_image_count: i64,
 }
 
 #[allow(non_camel_case_types)]
@@ -162,6 +193,16 @@ fn updated_by_fragment() -> updated_by_fragment {
 	updater.fields(profile::all_columns).nullable()
 }
 
+#[allow(non_camel_case_types)]
+type pending_image_count_fragment = SqlLiteral<BigInt>;
+fn pending_image_count_fragment() -> pending_image_count_fragment {
+	sql::<BigInt>(
+		"(SELECT COUNT(*) FROM location_image
+		WHERE location_image.location_id = location.id
+		AND location_image.approved_at IS NULL)",
+	)
+}
+
 impl Hash for Location {
 	fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
 		self.primitive.id.hash(state);
@@ -243,6 +284,7 @@ impl Location {
 		times: &[(i32, OpeningTime)],
 		tags: &[(i32, Tag)],
 		imgs: &[(i32, OrderedImage)],
+		ratings: &[(i32, Option<f64>, i64)],
 	) -> Vec<FullLocationData> {
 		locs.into_par_iter()
 			.map(|l| {
@@ -263,8 +305,12 @@ impl Location {
 					.filter(|(i, _)| *i == l_id)
 					.map(|(_, d)| d.to_owned())
 					.collect();
+				let rating = ratings
+					.iter()
+					.find(|(i, ..)| *i == l_id)
+					.map_or((None, 0), |(_, avg, count)| (*avg, *count));
 
-				(l, (times, tags, imgs))
+				(l, (times, tags, imgs, rating))
 			})
 			.collect()
 	}
@@ -314,7 +360,7 @@ impl Location {
 
 		let l_id = location.primitive.id;
 
-		let (times, tags, imgs) = tokio::join!(
+		let (times, tags, ratings, imgs) = tokio::join!(
 			OpeningTime::get_for_location(
 				l_id,
 				TimeBoundsFilter::default(),
@@ -322,14 +368,60 @@ impl Location {
 				conn
 			),
 			Tag::get_for_location(l_id, TagIncludes::default(), conn),
+			Review::get_aggregates_for_locations(vec![l_id], conn),
 			Image::get_for_location(l_id, ImageIncludes::default(), conn),
 		);
 
 		let times = times?;
 		let tags = tags?;
 		let imgs = imgs?;
+		let rating = ratings?
+			.into_iter()
+			.next()
+			.map_or((None, 0), |(_, avg, count)| (avg, count));
+
+		Ok((location, (times, tags, imgs, rating)))
+	}
+
+	/// Get a [`Location`] by its public id
+	#[instrument(skip(conn))]
+	pub async fn get_by_public_id(
+		p_id: Uuid,
+		includes: LocationIncludes,
+		conn: &DbConn,
+	) -> Result<FullLocationData, Error> {
+		let l_id = conn
+			.interact(move |conn| {
+				use self::location::dsl::*;
+
+				location.filter(public_id.eq(p_id)).select(id).get_result(conn)
+			})
+			.await??;
 
-		Ok((location, (times, tags, imgs)))
+		Self::get_by_id(l_id, includes, conn).await
+	}
+
+	/// Get the [`Location`] an [`OpeningTime`] belongs to
+	#[instrument(skip(conn))]
+	pub async fn get_by_opening_time_id(
+		t_id: i32,
+		includes: LocationIncludes,
+		conn: &DbConn,
+	) -> Result<FullLocationData, Error> {
+		let l_id = conn
+			.interact(move |conn| {
+				location::table
+					.inner_join(
+						opening_time::table
+							.on(opening_time::location_id.eq(location::id)),
+					)
+					.filter(opening_time::id.eq(t_id))
+					.select(location::id)
+					.get_result(conn)
+			})
+			.await??;
+
+		Self::get_by_id(l_id, includes, conn).await
 	}
 
 	/// Get a list of [`Location`]s given a list of IDs
@@ -355,61 +447,258 @@ impl Location {
 		let l_ids: Vec<i32> =
 			locations.iter().map(|l| l.primitive.id).collect();
 
-		let (times, tags, imgs) = tokio::join!(
+		let (times, tags, ratings, imgs) = tokio::join!(
 			OpeningTime::get_for_locations(
 				l_ids.clone(),
 				OpeningTimeIncludes::default(),
 				conn
 			),
 			Tag::get_for_locations(l_ids.clone(), TagIncludes::default(), conn),
+			Review::get_aggregates_for_locations(l_ids.clone(), conn),
 			Image::get_for_locations(l_ids, ImageIncludes::default(), conn),
 		);
 
 		let times = times?;
 		let tags = tags?;
+		let ratings = ratings?;
 		let imgs = imgs?;
 
-		Ok(Self::group(locations, &times, &tags, &imgs))
+		Ok(Self::group(locations, &times, &tags, &imgs, &ratings))
 	}
 
-	/// Get all locations created by a given profile
+	/// Get a page of the locations created by a given profile
 	#[instrument(skip(conn))]
-	pub async fn get_by_profile_id(
-		profile_id: i32,
+	pub async fn created_by_profile_paginated(
+		p_id: i32,
 		includes: LocationIncludes,
+		p_cfg: PaginationConfig,
 		conn: &DbConn,
-	) -> Result<Vec<FullLocationData>, Error> {
+	) -> Result<PaginatedData<Vec<FullLocationData>>, Error> {
 		let query = Self::query(includes);
 
-		let locations: Vec<_> = conn
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.offset as i64;
+
+		let (total, locations): (i64, Vec<_>) = conn
 			.interact(move |conn| {
 				use self::location::dsl::*;
 
-				query
-					.filter(created_by.eq(profile_id))
+				let total = location
+					.filter(created_by.eq(p_id))
+					.count()
+					.get_result::<i64>(conn)?;
+
+				let locations = query
+					.filter(created_by.eq(p_id))
+					.order(id.asc())
 					.select(Self::as_select())
-					.load(conn)
+					.limit(limit)
+					.offset(offset)
+					.load(conn)?;
+
+				Ok::<_, Error>((total, locations))
 			})
 			.await??;
 
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		if total > 0 && p_cfg.offset >= total {
+			return Err(PaginationError::OffsetTooLarge.into());
+		}
+
+		let l_ids: Vec<i32> =
+			locations.iter().map(|l| l.primitive.id).collect();
+
+		let (times, tags, ratings, imgs) = tokio::join!(
+			OpeningTime::get_for_locations(
+				l_ids.clone(),
+				OpeningTimeIncludes::default(),
+				conn
+			),
+			Tag::get_for_locations(l_ids.clone(), TagIncludes::default(), conn),
+			Review::get_aggregates_for_locations(l_ids.clone(), conn),
+			Image::get_for_locations(l_ids, ImageIncludes::default(), conn),
+		);
+
+		let times = times?;
+		let tags = tags?;
+		let ratings = ratings?;
+		let imgs = imgs?;
+
+		let locations = Self::group(locations, &times, &tags, &imgs, &ratings);
+
+		Ok((total, false, locations))
+	}
+
+	/// Get a page of the locations that are neither approved nor rejected,
+	/// optionally limited to a single authority
+	#[instrument(skip(conn))]
+	pub async fn get_pending(
+		filter: PendingFilter,
+		includes: LocationIncludes,
+		p_cfg: PaginationConfig,
+		conn: &DbConn,
+	) -> Result<PaginatedData<Vec<FullLocationData>>, Error> {
+		let query = Self::query(includes);
+
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.offset as i64;
+
+		let (total, locations): (i64, Vec<_>) = conn
+			.interact(move |conn| {
+				use self::location::dsl::*;
+
+				let mut count_query = location
+					.filter(approved_at.is_null())
+					.filter(rejected_at.is_null())
+					.into_boxed();
+				let mut page_query = query
+					.filter(approved_at.is_null())
+					.filter(rejected_at.is_null())
+					.into_boxed();
+
+				if let Some(auth_id) = filter.authority_id {
+					count_query = count_query.filter(authority_id.eq(auth_id));
+					page_query = page_query.filter(authority_id.eq(auth_id));
+				}
+
+				let total = count_query.count().get_result::<i64>(conn)?;
+
+				let locations = page_query
+					.order(id.asc())
+					.select(Self::as_select())
+					.limit(limit)
+					.offset(offset)
+					.load(conn)?;
+
+				Ok::<_, Error>((total, locations))
+			})
+			.await??;
+
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		p_cfg.check_offset(total)?;
+
+		let l_ids: Vec<i32> =
+			locations.iter().map(|l| l.primitive.id).collect();
+
+		let (times, tags, ratings, imgs) = tokio::join!(
+			OpeningTime::get_for_locations(
+				l_ids.clone(),
+				OpeningTimeIncludes::default(),
+				conn
+			),
+			Tag::get_for_locations(l_ids.clone(), TagIncludes::default(), conn),
+			Review::get_aggregates_for_locations(l_ids.clone(), conn),
+			Image::get_for_locations(l_ids, ImageIncludes::default(), conn),
+		);
+
+		let times = times?;
+		let tags = tags?;
+		let ratings = ratings?;
+		let imgs = imgs?;
+
+		let locations = Self::group(locations, &times, &tags, &imgs, &ratings);
+
+		Ok((total, false, locations))
+	}
+
+	/// Get a page of the visible locations with a given postcode
+	#[instrument(skip(conn))]
+	pub async fn get_by_zip(
+		zip_code: &str,
+		includes: LocationIncludes,
+		p_cfg: PaginationConfig,
+		conn: &DbConn,
+	) -> Result<PaginatedData<Vec<FullLocationData>>, Error> {
+		let query = Self::query(includes);
+		let zip_code = zip_code.to_string();
+
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.offset as i64;
+
+		let (total, locations): (i64, Vec<_>) = conn
+			.interact(move |conn| {
+				use self::location::dsl::*;
+
+				let total = location
+					.filter(zip.eq(&zip_code))
+					.filter(is_visible.eq(true))
+					.count()
+					.get_result::<i64>(conn)?;
+
+				let locations = query
+					.filter(zip.eq(zip_code))
+					.filter(is_visible.eq(true))
+					.order(id.asc())
+					.select(Self::as_select())
+					.limit(limit)
+					.offset(offset)
+					.load(conn)?;
+
+				Ok::<_, Error>((total, locations))
+			})
+			.await??;
+
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		if total > 0 && p_cfg.offset >= total {
+			return Err(PaginationError::OffsetTooLarge.into());
+		}
+
 		let l_ids: Vec<i32> =
 			locations.iter().map(|l| l.primitive.id).collect();
 
-		let (times, tags, imgs) = tokio::join!(
+		let (times, tags, ratings, imgs) = tokio::join!(
 			OpeningTime::get_for_locations(
 				l_ids.clone(),
 				OpeningTimeIncludes::default(),
 				conn
 			),
 			Tag::get_for_locations(l_ids.clone(), TagIncludes::default(), conn),
+			Review::get_aggregates_for_locations(l_ids.clone(), conn),
 			Image::get_for_locations(l_ids, ImageIncludes::default(), conn),
 		);
 
 		let times = times?;
 		let tags = tags?;
+		let ratings = ratings?;
 		let imgs = imgs?;
 
-		Ok(Self::group(locations, &times, &tags, &imgs))
+		let locations = Self::group(locations, &times, &tags, &imgs, &ratings);
+
+		Ok((total, false, locations))
+	}
+
+	/// Get the amount of locations per postcode, most common postcode first
+	#[instrument(skip(conn))]
+	pub async fn get_count_by_zip(
+		conn: &DbConn,
+	) -> Result<Vec<(String, i64)>, Error> {
+		let counts = conn
+			.interact(|conn| {
+				use self::location::dsl::*;
+
+				let location_count = count_star();
+
+				location
+					.group_by(zip)
+					.select((zip, location_count))
+					.order((location_count.desc(), zip))
+					.get_results(conn)
+			})
+			.await??;
+
+		Ok(counts)
 	}
 
 	/// Get the location nearest to the given point
@@ -440,6 +729,74 @@ impl Location {
 		Ok(loc_info)
 	}
 
+	/// Get the visible and approved locations closest to the given point,
+	/// together with their distance to it in kilometers
+	///
+	/// At most [`NEARBY_HARD_LIMIT`] locations are returned
+	#[instrument(skip(conn))]
+	pub async fn get_nearby(
+		center: Point,
+		limit: usize,
+		includes: LocationIncludes,
+		conn: &DbConn,
+	) -> Result<Vec<(FullLocationData, f64)>, Error> {
+		let query = Self::query(includes);
+
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = limit.min(NEARBY_HARD_LIMIT) as i64;
+
+		let located: Vec<(Self, f64)> = conn
+			.interact(move |conn| {
+				// Great-circle distance using the Haversine formula
+				let distance = || {
+					sql::<Double>("6371 * 2 * asin(sqrt(power(sin(radians(")
+						.sql("location.latitude - ")
+						.bind::<Double, _>(center.center_lat)
+						.sql(") / 2), 2) + cos(radians(")
+						.bind::<Double, _>(center.center_lat)
+						.sql(")) * cos(radians(location.latitude)) * ")
+						.sql("power(sin(radians(location.longitude - ")
+						.bind::<Double, _>(center.center_lng)
+						.sql(") / 2), 2)))")
+				};
+
+				query
+					.filter(location::is_visible.eq(true))
+					.filter(location::approved_at.is_not_null())
+					.order(distance().asc())
+					.select((Self::as_select(), distance()))
+					.limit(limit)
+					.load(conn)
+			})
+			.await??;
+
+		let (locations, distances): (Vec<_>, Vec<_>) =
+			located.into_iter().unzip();
+
+		let l_ids: Vec<i32> =
+			locations.iter().map(|l| l.primitive.id).collect();
+
+		let (times, tags, ratings, imgs) = tokio::join!(
+			OpeningTime::get_for_locations(
+				l_ids.clone(),
+				OpeningTimeIncludes::default(),
+				conn
+			),
+			Tag::get_for_locations(l_ids.clone(), TagIncludes::default(), conn),
+			Review::get_aggregates_for_locations(l_ids.clone(), conn),
+			Image::get_for_locations(l_ids, ImageIncludes::default(), conn),
+		);
+
+		let times = times?;
+		let tags = tags?;
+		let ratings = ratings?;
+		let imgs = imgs?;
+
+		let locations = Self::group(locations, &times, &tags, &imgs, &ratings);
+
+		Ok(locations.into_iter().zip(distances).collect())
+	}
+
 	/// Get all simple locations belonging to an authority
 	#[instrument(skip(conn))]
 	pub async fn get_simple_by_authority_id(
@@ -487,21 +844,23 @@ impl Location {
 		let l_ids: Vec<i32> =
 			locations.iter().map(|l| l.primitive.id).collect();
 
-		let (times, tags, imgs) = tokio::join!(
+		let (times, tags, ratings, imgs) = tokio::join!(
 			OpeningTime::get_for_locations(
 				l_ids.clone(),
 				OpeningTimeIncludes::default(),
 				conn
 			),
 			Tag::get_for_locations(l_ids.clone(), TagIncludes::default(), conn),
+			Review::get_aggregates_for_locations(l_ids.clone(), conn),
 			Image::get_for_locations(l_ids, ImageIncludes::default(), conn),
 		);
 
 		let times = times?;
 		let tags = tags?;
+		let ratings = ratings?;
 		let imgs = imgs?;
 
-		Ok(Self::group(locations, &times, &tags, &imgs))
+		Ok(Self::group(locations, &times, &tags, &imgs, &ratings))
 	}
 
 	/// Delete a [`Location`] by its id
@@ -517,6 +876,94 @@ impl Location {
 		Ok(())
 	}
 
+	/// Update the description translation of a [`Location`]
+	#[instrument(skip(conn))]
+	pub async fn update_description(
+		loc_id: i32,
+		update: TranslationUpdate,
+		updated_by: i32,
+		conn: &DbConn,
+	) -> Result<(), Error> {
+		conn.interact(move |conn| {
+			conn.transaction::<_, Error, _>(|conn| {
+				let desc_id = location::table
+					.find(loc_id)
+					.select(location::description_id)
+					.get_result::<i32>(conn)?;
+
+				diesel::update(translation::table.find(desc_id))
+					.set(update)
+					.execute(conn)?;
+
+				diesel::update(location::table.find(loc_id))
+					.set(location::updated_by.eq(updated_by))
+					.execute(conn)?;
+
+				Ok(())
+			})
+		})
+		.await??;
+
+		info!("updated description of location with id {loc_id}");
+
+		Ok(())
+	}
+
+	/// Update the excerpt translation of a [`Location`]
+	#[instrument(skip(conn))]
+	pub async fn update_excerpt(
+		loc_id: i32,
+		update: TranslationUpdate,
+		updated_by: i32,
+		conn: &DbConn,
+	) -> Result<(), Error> {
+		conn.interact(move |conn| {
+			conn.transaction::<_, Error, _>(|conn| {
+				let exc_id = location::table
+					.find(loc_id)
+					.select(location::excerpt_id)
+					.get_result::<i32>(conn)?;
+
+				diesel::update(translation::table.find(exc_id))
+					.set(update)
+					.execute(conn)?;
+
+				diesel::update(location::table.find(loc_id))
+					.set(location::updated_by.eq(updated_by))
+					.execute(conn)?;
+
+				Ok(())
+			})
+		})
+		.await??;
+
+		info!("updated excerpt of location with id {loc_id}");
+
+		Ok(())
+	}
+
+	/// Set the visibility of a [`Location`]
+	#[instrument(skip(conn))]
+	pub async fn set_visibility(
+		loc_id: i32,
+		visible: bool,
+		updated_by_id: i32,
+		conn: &DbConn,
+	) -> Result<(), Error> {
+		conn.interact(move |conn| {
+			use self::location::dsl::*;
+
+			diesel::update(location.find(loc_id))
+				.set((is_visible.eq(visible), updated_by.eq(updated_by_id)))
+				.execute(conn)
+		})
+		.await??;
+
+		info!("set visibility of location with id {loc_id} to {visible}");
+
+		Ok(())
+	}
+
 	/// Approve a [`Location`] by its id and profile id
 	#[instrument(skip(conn))]
 	pub async fn approve_by(
@@ -613,6 +1060,78 @@ pub struct InsertableNewLocation {
 }
 
 impl NewLocation {
+	/// Insert the [`Location`] together with its translations and owner role
+	///
+	/// This should always be called from inside a transaction
+	fn insert_with_conn(
+		self,
+		conn: &mut PgConnection,
+	) -> Result<PrimitiveLocation, Error> {
+		use self::location::dsl::location;
+		use self::translation::dsl::translation;
+
+		let desc = diesel::insert_into(translation)
+			.values(self.description)
+			.returning(PrimitiveTranslation::as_returning())
+			.get_result(conn)?;
+
+		let exc = diesel::insert_into(translation)
+			.values(self.excerpt)
+			.returning(PrimitiveTranslation::as_returning())
+			.get_result(conn)?;
+
+		let new_location = InsertableNewLocation {
+			name:                   self.name,
+			authority_id:           self.authority_id,
+			description_id:         desc.id,
+			excerpt_id:             exc.id,
+			seat_count:             self.seat_count,
+			is_reservable:          self.is_reservable,
+			max_reservation_length: self.max_reservation_length,
+			is_visible:             self.is_visible,
+			street:                 self.street,
+			number:                 self.number,
+			zip:                    self.zip,
+			city:                   self.city,
+			country:                self.country,
+			province:               self.province,
+			latitude:               self.latitude,
+			longitude:              self.longitude,
+			created_by:             self.created_by,
+		};
+
+		let loc = diesel::insert_into(location)
+			.values(new_location)
+			.returning(PrimitiveLocation::as_returning())
+			.get_result(conn)?;
+
+		let new_role = NewLocationRole {
+			location_id: loc.id,
+			name:        "owner".into(),
+			colour:      None,
+			permissions: LocationPermissions::Administrator.bits(),
+			created_by:  self.created_by,
+		};
+
+		let role_id = diesel::insert_into(location_role::table)
+			.values(new_role)
+			.returning(location_role::id)
+			.get_result(conn)?;
+
+		let member = NewLocationMember {
+			location_id:      loc.id,
+			profile_id:       self.created_by,
+			location_role_id: Some(role_id),
+			added_by:         self.created_by,
+		};
+
+		diesel::insert_into(location_member::table)
+			.values(member)
+			.execute(conn)?;
+
+		Ok(loc)
+	}
+
 	/// Create a new [`Location`]
 	#[instrument(skip(conn))]
 	pub async fn insert(
@@ -623,67 +1142,45 @@ impl NewLocation {
 		let location = conn
 			.interact(move |conn| {
 				conn.transaction::<_, Error, _>(|conn| {
-					use self::location::dsl::location;
-					use self::translation::dsl::translation;
-
-					let desc = diesel::insert_into(translation)
-						.values(self.description)
-						.returning(PrimitiveTranslation::as_returning())
-						.get_result(conn)?;
-
-					let exc = diesel::insert_into(translation)
-						.values(self.excerpt)
-						.returning(PrimitiveTranslation::as_returning())
-						.get_result(conn)?;
-
-					let new_location = InsertableNewLocation {
-						name:                   self.name,
-						authority_id:           self.authority_id,
-						description_id:         desc.id,
-						excerpt_id:             exc.id,
-						seat_count:             self.seat_count,
-						is_reservable:          self.is_reservable,
-						max_reservation_length: self.max_reservation_length,
-						is_visible:             self.is_visible,
-						street:                 self.street,
-						number:                 self.number,
-						zip:                    self.zip,
-						city:                   self.city,
-						country:                self.country,
-						province:               self.province,
-						latitude:               self.latitude,
-						longitude:              self.longitude,
-						created_by:             self.created_by,
-					};
-
-					let loc = diesel::insert_into(location)
-						.values(new_location)
-						.returning(PrimitiveLocation::as_returning())
-						.get_result(conn)?;
-
-					let new_role = NewLocationRole {
-						location_id: loc.id,
-						name:        "owner".into(),
-						colour:      None,
-						permissions: LocationPermissions::Administrator.bits(),
-						created_by:  self.created_by,
-					};
-
-					let role_id = diesel::insert_into(location_role::table)
-						.values(new_role)
-						.returning(location_role::id)
-						.get_result(conn)?;
-
-					let member = NewLocationMember {
-						location_id:      loc.id,
-						profile_id:       self.created_by,
-						location_role_id: Some(role_id),
-						added_by:         self.created_by,
-					};
-
-					diesel::insert_into(location_member::table)
-						.values(member)
-						.execute(conn)?;
+					self.insert_with_conn(conn)
+				})
+			})
+			.await??;
+
+		let location = Location::get_by_id(location.id, includes, conn).await?;
+
+		info!("inserted new location {location:?}");
+
+		Ok(location)
+	}
+
+	/// Create a new [`Location`] together with a set of images
+	///
+	/// The images are only built once the id of the new location is known,
+	/// if building or inserting any of them fails the location is not created
+	/// either
+	#[instrument(skip(conn, build_images))]
+	pub async fn insert_with_images<F>(
+		self,
+		build_images: F,
+		includes: LocationIncludes,
+		conn: &DbConn,
+	) -> Result<FullLocationData, Error>
+	where
+		F: FnOnce(i32) -> Result<Vec<NewImage>, Error> + Send + 'static,
+	{
+		let location = conn
+			.interact(move |conn| {
+				conn.transaction::<_, Error, _>(|conn| {
+					let loc = self.insert_with_conn(conn)?;
+
+					let new_images = build_images(loc.id)?;
+
+					for (index, new_image) in (0..).zip(new_images) {
+						new_image.insert_for_location_with_conn(
+							loc.id, index, conn,
+						)?;
+					}
 
 					Ok(loc)
 				})
@@ -692,7 +1189,7 @@ impl NewLocation {
 
 		let location = Location::get_by_id(location.id, includes, conn).await?;
 
-		info!("inserted new location {location:?}");
+		info!("inserted new location with images {location:?}");
 
 		Ok(location)
 	}
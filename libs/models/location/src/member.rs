@@ -1,11 +1,65 @@
 use ::profile::Profile;
 use common::{DbConn, Error};
-use db::{image, location_member, profile};
+use db::{image, location_member, location_role, profile};
 use diesel::prelude::*;
+use permissions::LocationPermissions;
 use serde::{Deserialize, Serialize};
 
 use crate::Location;
 
+/// Make sure a location keeps an administrator once the member `prof_id`
+/// holds the role `new_role_id` instead of their current one
+///
+/// This should always be called from inside a transaction
+fn check_administrator_remains(
+	loc_id: i32,
+	prof_id: i32,
+	new_role_id: Option<i32>,
+	conn: &mut PgConnection,
+) -> Result<(), Error> {
+	let is_admin = |perms: Option<i64>| {
+		perms.is_some_and(|p| {
+			LocationPermissions::from_bits_truncate(p)
+				.contains(LocationPermissions::Administrator)
+		})
+	};
+
+	let members: Vec<(i32, Option<i64>)> = location_member::table
+		.left_join(location_role::table)
+		.filter(location_member::location_id.eq(loc_id))
+		.select((
+			location_member::profile_id,
+			location_role::permissions.nullable(),
+		))
+		.get_results(conn)?;
+
+	let was_admin =
+		members.iter().any(|(id, perms)| *id == prof_id && is_admin(*perms));
+
+	if !was_admin {
+		return Ok(());
+	}
+
+	let new_perms = new_role_id
+		.map(|role_id| {
+			location_role::table
+				.find(role_id)
+				.filter(location_role::location_id.eq(loc_id))
+				.select(location_role::permissions)
+				.get_result(conn)
+		})
+		.transpose()?;
+
+	let other_admin =
+		members.iter().any(|(id, perms)| *id != prof_id && is_admin(*perms));
+
+	if is_admin(new_perms) || other_admin {
+		return Ok(());
+	}
+
+	Err(Error::LastAdministrator)
+}
+
 impl Location {
 	/// Get all the members of this location
 	#[instrument(skip(conn))]
@@ -35,6 +89,8 @@ impl Location {
 	}
 
 	/// Delete a member from this location
+	///
+	/// The last administrator of a location can not be deleted
 	#[instrument(skip(conn))]
 	pub async fn delete_member(
 		loc_id: i32,
@@ -42,13 +98,18 @@ impl Location {
 		conn: &DbConn,
 	) -> Result<(), Error> {
 		conn.interact(move |conn| {
-			use self::location_member::dsl::*;
+			conn.transaction::<_, Error, _>(|conn| {
+				use self::location_member::dsl::*;
+
+				check_administrator_remains(loc_id, prof_id, None, conn)?;
+
+				diesel::delete(location_member.filter(
+					location_id.eq(loc_id).and(profile_id.eq(prof_id)),
+				))
+				.execute(conn)?;
 
-			diesel::delete(
-				location_member
-					.filter(location_id.eq(loc_id).and(profile_id.eq(prof_id))),
-			)
-			.execute(conn)
+				Ok(())
+			})
 		})
 		.await??;
 
@@ -94,12 +155,16 @@ impl NewLocationMember {
 #[diesel(table_name = location_member)]
 #[diesel(check_for_backend(Pg))]
 pub struct LocationMemberUpdate {
+	#[diesel(treat_none_as_null = true)]
 	pub location_role_id: Option<i32>,
 	pub updated_by:       i32,
 }
 
 impl LocationMemberUpdate {
 	/// Update this [`LocationMember`] in the database.
+	///
+	/// A role of `None` clears the role of the member, the last
+	/// administrator of a location can not lose their administrator role
 	#[instrument(skip(conn))]
 	pub async fn apply_to(
 		self,
@@ -108,14 +173,24 @@ impl LocationMemberUpdate {
 		conn: &DbConn,
 	) -> Result<Profile, Error> {
 		conn.interact(move |conn| {
-			use self::location_member::dsl::*;
-
-			diesel::update(
-				location_member
-					.filter(location_id.eq(loc_id).and(profile_id.eq(prof_id))),
-			)
-			.set(self)
-			.execute(conn)
+			conn.transaction::<_, Error, _>(|conn| {
+				use self::location_member::dsl::*;
+
+				check_administrator_remains(
+					loc_id,
+					prof_id,
+					self.location_role_id,
+					conn,
+				)?;
+
+				diesel::update(location_member.filter(
+					location_id.eq(loc_id).and(profile_id.eq(prof_id)),
+				))
+				.set(self)
+				.execute(conn)?;
+
+				Ok(())
+			})
 		})
 		.await??;
 
@@ -0,0 +1,262 @@
+use common::{DbConn, Error};
+use db::{
+	location,
+	location_favourite,
+	location_image,
+	location_member,
+	location_role,
+	location_tag,
+	opening_time,
+	reservation,
+	review,
+	translation,
+};
+use diesel::dsl::{exists, max};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::Location;
+
+/// Amounts of records related to a location, per kind of record
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct MergedRecords {
+	pub opening_times: i64,
+	/// Reservations move along with the opening times they belong to
+	pub reservations:  i64,
+	pub reviews:       i64,
+	pub images:        i64,
+	pub tags:          i64,
+	pub members:       i64,
+	pub favourites:    i64,
+}
+
+/// Convert an amount of affected rows to the type used for counts
+#[allow(clippy::cast_possible_wrap)]
+fn affected(rows: usize) -> i64 { rows as i64 }
+
+/// The outcome of merging one [`Location`] into another
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LocationMerge {
+	pub source_id:    i32,
+	pub target_id:    i32,
+	/// Records that now belong to the target location
+	pub moved:        MergedRecords,
+	/// Records that were dropped because the target location already had an
+	/// equivalent one, e.g. a review by the same profile
+	pub skipped:      MergedRecords,
+	/// Translations of the source location that were deleted
+	pub translations: i64,
+}
+
+impl Location {
+	/// Merge a duplicate [`Location`] into another one
+	///
+	/// Everything related to the source location is moved to the target,
+	/// unless the target already has an equivalent record. The source
+	/// location and its translations are deleted afterwards
+	#[instrument(skip(conn))]
+	pub async fn merge(
+		source_id: i32,
+		target_id: i32,
+		conn: &DbConn,
+	) -> Result<LocationMerge, Error> {
+		if source_id == target_id {
+			return Err(Error::BadRequest(
+				"a location can not be merged into itself".to_string(),
+			));
+		}
+
+		let merge = conn
+			.interact(move |conn| {
+				conn.transaction::<_, Error, _>(|conn| {
+					location::table
+						.find(target_id)
+						.select(location::id)
+						.for_update()
+						.get_result::<i32>(conn)
+						.optional()?
+						.ok_or(Error::NotFound(format!(
+							"location with id {target_id}"
+						)))?;
+
+					let (description_id, excerpt_id) = location::table
+						.find(source_id)
+						.select((
+							location::description_id,
+							location::excerpt_id,
+						))
+						.for_update()
+						.get_result::<(i32, i32)>(conn)
+						.optional()?
+						.ok_or(Error::NotFound(format!(
+							"location with id {source_id}"
+						)))?;
+
+					let mut moved = MergedRecords::default();
+					let mut skipped = MergedRecords::default();
+
+					// Opening times carry their reservations along
+					moved.reservations = reservation::table
+						.inner_join(opening_time::table.on(
+							reservation::opening_time_id.eq(opening_time::id),
+						))
+						.filter(opening_time::location_id.eq(source_id))
+						.count()
+						.get_result(conn)?;
+
+					moved.opening_times = affected(
+						diesel::update(opening_time::table)
+							.filter(opening_time::location_id.eq(source_id))
+							.set(opening_time::location_id.eq(target_id))
+							.execute(conn)?,
+					);
+
+					// A profile can only review a location once
+					let reviewers = review::table
+						.filter(review::location_id.eq(target_id))
+						.select(review::profile_id)
+						.load::<i32>(conn)?;
+
+					moved.reviews = affected(
+						diesel::update(review::table)
+							.filter(review::location_id.eq(source_id))
+							.filter(review::profile_id.ne_all(reviewers))
+							.set(review::location_id.eq(target_id))
+							.execute(conn)?,
+					);
+
+					skipped.reviews = review::table
+						.filter(review::location_id.eq(source_id))
+						.count()
+						.get_result(conn)?;
+
+					let tags = location_tag::table
+						.filter(location_tag::location_id.eq(target_id))
+						.select(location_tag::tag_id)
+						.load::<i32>(conn)?;
+
+					moved.tags = affected(
+						diesel::update(location_tag::table)
+							.filter(location_tag::location_id.eq(source_id))
+							.filter(location_tag::tag_id.ne_all(tags))
+							.set(location_tag::location_id.eq(target_id))
+							.execute(conn)?,
+					);
+
+					skipped.tags = location_tag::table
+						.filter(location_tag::location_id.eq(source_id))
+						.count()
+						.get_result(conn)?;
+
+					// Roles stay behind with the source location, so moved
+					// members get the default role of the target instead
+					let default_role = location_role::table
+						.filter(location_role::location_id.eq(target_id))
+						.filter(location_role::is_default.eq(true))
+						.select(location_role::id)
+						.first::<i32>(conn)
+						.optional()?;
+
+					let members = location_member::table
+						.filter(location_member::location_id.eq(target_id))
+						.select(location_member::profile_id)
+						.load::<i32>(conn)?;
+
+					moved.members = affected(
+						diesel::update(location_member::table)
+							.filter(location_member::location_id.eq(source_id))
+							.filter(location_member::profile_id.ne_all(members))
+							.set((
+								location_member::location_id.eq(target_id),
+								location_member::location_role_id
+									.eq(default_role),
+							))
+							.execute(conn)?,
+					);
+
+					skipped.members = location_member::table
+						.filter(location_member::location_id.eq(source_id))
+						.count()
+						.get_result(conn)?;
+
+					let profiles = location_favourite::table
+						.filter(location_favourite::location_id.eq(target_id))
+						.select(location_favourite::profile_id)
+						.load::<i32>(conn)?;
+
+					moved.favourites = affected(
+						diesel::update(location_favourite::table)
+							.filter(
+								location_favourite::location_id.eq(source_id),
+							)
+							.filter(
+								location_favourite::profile_id.ne_all(profiles),
+							)
+							.set(location_favourite::location_id.eq(target_id))
+							.execute(conn)?,
+					);
+
+					skipped.favourites = location_favourite::table
+						.filter(location_favourite::location_id.eq(source_id))
+						.count()
+						.get_result(conn)?;
+
+					// Moved images are placed after the images of the target
+					// and the target keeps its own cover image
+					let last_index = location_image::table
+						.filter(location_image::location_id.eq(target_id))
+						.select(max(location_image::index))
+						.get_result::<Option<i32>>(conn)?;
+
+					let offset = last_index.map_or(0, |i| i + 1);
+
+					let has_cover = diesel::select(exists(
+						location_image::table
+							.filter(location_image::location_id.eq(target_id))
+							.filter(location_image::is_cover.eq(true)),
+					))
+					.get_result::<bool>(conn)?;
+
+					if has_cover {
+						diesel::update(location_image::table)
+							.filter(location_image::location_id.eq(source_id))
+							.set(location_image::is_cover.eq(false))
+							.execute(conn)?;
+					}
+
+					moved.images = affected(
+						diesel::update(location_image::table)
+							.filter(location_image::location_id.eq(source_id))
+							.set((
+								location_image::location_id.eq(target_id),
+								location_image::index
+									.eq(location_image::index + offset),
+							))
+							.execute(conn)?,
+					);
+
+					// Deleting the translations cascades to the source
+					// location and the records that were skipped
+					let translation_ids = [description_id, excerpt_id];
+					let translations = affected(
+						diesel::delete(translation::table)
+							.filter(translation::id.eq_any(translation_ids))
+							.execute(conn)?,
+					);
+
+					Ok(LocationMerge {
+						source_id,
+						target_id,
+						moved,
+						skipped,
+						translations,
+					})
+				})
+			})
+			.await??;
+
+		info!("merged location {source_id} into {target_id}: {merge:?}");
+
+		Ok(merge)
+	}
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use base::{
+	PaginatedData,
+	PaginationConfig,
+	QUERY_HARD_LIMIT,
+	SortDirection,
+	manual_pagination,
+};
+use common::{DbConn, Error};
+use db::{ReservationState, location, opening_time, reservation};
+use diesel::dsl::count;
+use diesel::prelude::*;
+
+use crate::{Location, LocationIncludes};
+
+impl Location {
+	/// Get all [`Location`]s together with their amount of non-cancelled
+	/// reservations, sorted by that amount
+	#[instrument(skip(conn))]
+	pub async fn get_by_reservation_count(
+		order: SortDirection,
+		p_cfg: PaginationConfig,
+		conn: &DbConn,
+	) -> Result<PaginatedData<Vec<(Self, i64)>>, Error> {
+		let counts = conn
+			.interact(move |conn| {
+				let reservation_count = count(reservation::id.nullable());
+
+				let query = location::table
+					.left_join(
+						opening_time::table
+							.on(opening_time::location_id.eq(location::id)),
+					)
+					.left_join(reservation::table.on(
+						reservation::opening_time_id.eq(opening_time::id).and(
+							reservation::state.ne(ReservationState::Cancelled),
+						),
+					))
+					.group_by(location::id)
+					.select((location::id, reservation_count))
+					.limit(QUERY_HARD_LIMIT);
+
+				match order {
+					SortDirection::Asc => {
+						query
+							.order((reservation_count.asc(), location::id))
+							.get_results::<(i32, i64)>(conn)
+					},
+					SortDirection::Desc => {
+						query
+							.order((reservation_count.desc(), location::id))
+							.get_results::<(i32, i64)>(conn)
+					},
+				}
+			})
+			.await??;
+
+		let (total, truncated, counts) = manual_pagination(counts, p_cfg)?;
+
+		let l_ids: Vec<i32> = counts.iter().map(|(id, _)| *id).collect();
+		let query = Self::query(LocationIncludes::default());
+
+		let locations: Vec<Self> = conn
+			.interact(move |conn| {
+				query
+					.filter(location::id.eq_any(l_ids))
+					.select(Self::as_select())
+					.get_results(conn)
+			})
+			.await??;
+
+		let mut locations: HashMap<i32, Self> =
+			locations.into_iter().map(|l| (l.primitive.id, l)).collect();
+
+		// Restore the ordering of the counts
+		let locations = counts
+			.into_iter()
+			.filter_map(|(id, count)| locations.remove(&id).map(|l| (l, count)))
+			.collect();
+
+		Ok((total, truncated, locations))
+	}
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use base::RESERVATION_BLOCK_SIZE_MINUTES;
+use chrono::NaiveDate;
+use common::{DbConn, Error};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Date, Integer};
+use serde::{Deserialize, Serialize};
+
+/// The longest range of days [`LocationStats`] can be requested for
+pub const MAX_STATS_RANGE_DAYS: i64 = 366;
+
+/// Usage numbers of a [`Location`](crate::Location) on a single day
+#[derive(Clone, Copy, Debug, Deserialize, QueryableByName, Serialize)]
+#[diesel(check_for_backend(Pg))]
+pub struct LocationDayStats {
+	#[diesel(sql_type = Date)]
+	pub day:               NaiveDate,
+	/// Amount of reservations that were not cancelled
+	#[diesel(sql_type = BigInt)]
+	pub reservation_count: i64,
+	#[diesel(sql_type = BigInt)]
+	pub cancelled_count:   i64,
+	/// Seat minutes taken up by reservations that were not cancelled
+	#[diesel(sql_type = BigInt)]
+	pub reserved_minutes:  i64,
+	/// Seat minutes offered by the opening times of the day
+	#[diesel(sql_type = BigInt)]
+	pub capacity_minutes:  i64,
+}
+
+/// Usage numbers of a [`Location`](crate::Location) over a range of days
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LocationStats {
+	pub from:            NaiveDate,
+	pub to:              NaiveDate,
+	/// One entry for every day in the range, including days without
+	/// reservations
+	pub days:            Vec<LocationDayStats>,
+	/// Amount of distinct profiles with a reservation that was not cancelled
+	pub unique_visitors: i64,
+}
+
+/// Aggregate the opening times and reservations of a location per day
+///
+/// Days are generated in SQL so days without any opening time or reservation
+/// are still part of the result
+const LOCATION_DAY_STATS_QUERY: &str = r"
+SELECT
+	series.day::DATE AS day,
+	reservations.reservation_count,
+	reservations.cancelled_count,
+	reservations.reserved_minutes,
+	times.capacity_minutes
+FROM generate_series($2::DATE, $3::DATE, INTERVAL '1 day') AS series (day)
+CROSS JOIN LATERAL (
+	SELECT
+		COALESCE(
+			SUM(
+				EXTRACT(
+					EPOCH FROM opening_time.end_time - opening_time.start_time
+				) / 60 * COALESCE(opening_time.seat_count, location.seat_count)
+			),
+			0
+		)::BIGINT AS capacity_minutes
+	FROM opening_time
+	INNER JOIN location ON location.id = opening_time.location_id
+	WHERE opening_time.location_id = $1
+		AND opening_time.day = series.day::DATE
+) AS times
+CROSS JOIN LATERAL (
+	SELECT
+		COUNT(*) FILTER (
+			WHERE reservation.state <> 'cancelled'
+		) AS reservation_count,
+		COUNT(*) FILTER (
+			WHERE reservation.state = 'cancelled'
+		) AS cancelled_count,
+		COALESCE(
+			SUM(reservation.block_count) FILTER (
+				WHERE reservation.state <> 'cancelled'
+			),
+			0
+		) * $4 AS reserved_minutes
+	FROM reservation
+	INNER JOIN opening_time ON opening_time.id = reservation.opening_time_id
+	WHERE opening_time.location_id = $1
+		AND opening_time.day = series.day::DATE
+) AS reservations
+ORDER BY series.day
+";
+
+#[derive(Debug, QueryableByName)]
+#[diesel(check_for_backend(Pg))]
+struct UniqueVisitorsRow {
+	#[diesel(sql_type = BigInt)]
+	unique_visitors: i64,
+}
+
+const UNIQUE_VISITORS_QUERY: &str = r"
+SELECT COUNT(DISTINCT reservation.profile_id) AS unique_visitors
+FROM reservation
+INNER JOIN opening_time ON opening_time.id = reservation.opening_time_id
+WHERE opening_time.location_id = $1
+	AND opening_time.day BETWEEN $2 AND $3
+	AND reservation.state <> 'cancelled'
+";
+
+/// Check whether a range of days is valid for [`LocationStats`]
+fn check_range(from: NaiveDate, to: NaiveDate) -> Result<(), Error> {
+	let range_days = (to - from).num_days() + 1;
+
+	let message = if range_days < 1 {
+		"to can not lie before from".to_string()
+	} else if range_days > MAX_STATS_RANGE_DAYS {
+		format!("range can not span more than {MAX_STATS_RANGE_DAYS} days")
+	} else {
+		return Ok(());
+	};
+
+	Err(Error::ValidationError {
+		fields: HashMap::from([("to".to_string(), message.clone())]),
+		message,
+	})
+}
+
+impl LocationStats {
+	/// Get the [`LocationStats`] of a location between two days, both
+	/// inclusive
+	///
+	/// Fails if `to` lies before `from` or if the range spans more than
+	/// [`MAX_STATS_RANGE_DAYS`] days
+	#[instrument(skip(conn))]
+	pub async fn for_location(
+		l_id: i32,
+		from: NaiveDate,
+		to: NaiveDate,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		check_range(from, to)?;
+
+		let (days, unique_visitors) = conn
+			.interact(move |conn| {
+				let days = diesel::sql_query(LOCATION_DAY_STATS_QUERY)
+					.bind::<Integer, _>(l_id)
+					.bind::<Date, _>(from)
+					.bind::<Date, _>(to)
+					.bind::<Integer, _>(RESERVATION_BLOCK_SIZE_MINUTES)
+					.get_results::<LocationDayStats>(conn)?;
+
+				let visitors = diesel::sql_query(UNIQUE_VISITORS_QUERY)
+					.bind::<Integer, _>(l_id)
+					.bind::<Date, _>(from)
+					.bind::<Date, _>(to)
+					.get_result::<UniqueVisitorsRow>(conn)?;
+
+				Ok::<_, Error>((days, visitors.unique_visitors))
+			})
+			.await??;
+
+		Ok(Self { from, to, days, unique_visitors })
+	}
+}
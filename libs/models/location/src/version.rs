@@ -0,0 +1,136 @@
+use chrono::NaiveDateTime;
+use common::{DbConn, Error};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Integer, Nullable, Text, Timestamp};
+
+use crate::Location;
+
+/// Summary of everything that makes up the responses of a [`Location`]
+///
+/// Any change to the location or its related records changes this summary,
+/// counts are included to catch related records being removed
+#[derive(Clone, Debug, Hash, PartialEq, Eq, QueryableByName)]
+#[diesel(check_for_backend(Pg))]
+pub struct LocationVersion {
+	#[diesel(sql_type = Timestamp)]
+	pub updated_at:               NaiveDateTime,
+	#[diesel(sql_type = Timestamp)]
+	pub translations_updated_at:  NaiveDateTime,
+	#[diesel(sql_type = Nullable<Timestamp>)]
+	pub authority_updated_at:     Option<NaiveDateTime>,
+	#[diesel(sql_type = Nullable<Timestamp>)]
+	pub opening_times_updated_at: Option<NaiveDateTime>,
+	#[diesel(sql_type = BigInt)]
+	pub opening_time_count:       i64,
+	#[diesel(sql_type = Nullable<Timestamp>)]
+	pub tags_updated_at:          Option<NaiveDateTime>,
+	#[diesel(sql_type = BigInt)]
+	pub tag_count:                i64,
+	#[diesel(sql_type = Nullable<Timestamp>)]
+	pub images_updated_at:        Option<NaiveDateTime>,
+	/// The ids, order and cover flags of the images, as reordering does not
+	/// leave a timestamp behind
+	#[diesel(sql_type = Text)]
+	pub image_order:              String,
+	#[diesel(sql_type = Nullable<Timestamp>)]
+	pub reviews_updated_at:       Option<NaiveDateTime>,
+	#[diesel(sql_type = BigInt)]
+	pub review_count:             i64,
+	#[diesel(sql_type = BigInt)]
+	pub favourite_count:          i64,
+}
+
+const LOCATION_VERSION_QUERY: &str = r"
+SELECT
+	location.updated_at,
+	GREATEST(description.updated_at, excerpt.updated_at)
+		AS translations_updated_at,
+	authority.updated_at AS authority_updated_at,
+	times.updated_at AS opening_times_updated_at,
+	times.count AS opening_time_count,
+	tags.updated_at AS tags_updated_at,
+	tags.count AS tag_count,
+	images.updated_at AS images_updated_at,
+	images.image_order,
+	reviews.updated_at AS reviews_updated_at,
+	reviews.count AS review_count,
+	favourites.count AS favourite_count
+FROM location
+INNER JOIN translation AS description
+	ON description.id = location.description_id
+INNER JOIN translation AS excerpt
+	ON excerpt.id = location.excerpt_id
+LEFT JOIN authority ON authority.id = location.authority_id
+CROSS JOIN LATERAL (
+	SELECT MAX(opening_time.updated_at) AS updated_at, COUNT(*) AS count
+	FROM opening_time
+	WHERE opening_time.location_id = location.id
+) AS times
+CROSS JOIN LATERAL (
+	SELECT
+		MAX(GREATEST(tag.updated_at, tag_name.updated_at)) AS updated_at,
+		COUNT(*) AS count
+	FROM location_tag
+	INNER JOIN tag ON tag.id = location_tag.tag_id
+	INNER JOIN translation AS tag_name
+		ON tag_name.id = tag.name_translation_id
+	WHERE location_tag.location_id = location.id
+) AS tags
+CROSS JOIN LATERAL (
+	SELECT
+		MAX(GREATEST(image.uploaded_at, location_image.approved_at))
+			AS updated_at,
+		COALESCE(
+			STRING_AGG(
+				CONCAT_WS(
+					':',
+					location_image.image_id,
+					location_image.index,
+					location_image.is_cover,
+					location_image.approved_at IS NOT NULL
+				),
+				',' ORDER BY location_image.image_id
+			),
+			''
+		) AS image_order
+	FROM location_image
+	INNER JOIN image ON image.id = location_image.image_id
+	WHERE location_image.location_id = location.id
+) AS images
+CROSS JOIN LATERAL (
+	SELECT
+		MAX(review.updated_at) AS updated_at,
+		COUNT(*) FILTER (WHERE review.hidden_at IS NULL) AS count
+	FROM review
+	WHERE review.location_id = location.id
+) AS reviews
+CROSS JOIN LATERAL (
+	SELECT COUNT(*) AS count
+	FROM location_favourite
+	WHERE location_favourite.location_id = location.id
+) AS favourites
+WHERE location.id = $1
+";
+
+impl Location {
+	/// Get the current [`LocationVersion`] of a [`Location`]
+	///
+	/// This is a single aggregate query, meant to be much cheaper than
+	/// loading the location itself
+	#[instrument(skip(conn))]
+	pub async fn get_version(
+		loc_id: i32,
+		conn: &DbConn,
+	) -> Result<LocationVersion, Error> {
+		let version = conn
+			.interact(move |conn| {
+				diesel::sql_query(LOCATION_VERSION_QUERY)
+					.bind::<Integer, _>(loc_id)
+					.get_result::<LocationVersion>(conn)
+			})
+			.await??;
+
+		Ok(version)
+	}
+}
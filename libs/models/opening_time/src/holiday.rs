@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Embedded public holiday dataset, keyed by country code and year
+static HOLIDAYS: LazyLock<HashMap<String, HashMap<i32, Vec<PublicHoliday>>>> =
+	LazyLock::new(|| {
+		serde_json::from_str(include_str!("../data/holidays.json"))
+			.expect("COULD NOT PARSE EMBEDDED HOLIDAY DATASET")
+	});
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PublicHoliday {
+	pub date: NaiveDate,
+	pub name: String,
+}
+
+pub struct PublicHolidayService;
+
+impl PublicHolidayService {
+	/// Whether the embedded dataset holds any holidays for a given country
+	#[must_use]
+	pub fn has_country(country_code: &str) -> bool {
+		HOLIDAYS.contains_key(&country_code.to_uppercase())
+	}
+
+	/// Get all known [`PublicHoliday`]s for a given country in a given year
+	///
+	/// Unknown countries or years yield an empty list, use
+	/// [`PublicHolidayService::has_country`] to tell them apart
+	#[must_use]
+	pub fn get_holidays(country_code: &str, year: i32) -> Vec<PublicHoliday> {
+		HOLIDAYS
+			.get(&country_code.to_uppercase())
+			.and_then(|years| years.get(&year))
+			.cloned()
+			.unwrap_or_default()
+	}
+}
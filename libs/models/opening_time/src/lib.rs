@@ -1,32 +1,106 @@
 #[macro_use]
 extern crate tracing;
 
-use base::{BoxedCondition, ToFilter};
+use std::collections::HashMap;
+
+use ::reservation::Reservation;
+use base::{BoxedCondition, RESERVATION_BLOCK_SIZE_MINUTES, ToFilter};
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday};
 use common::{DbConn, Error};
-use db::{CreatorAlias, UpdaterAlias, creator, opening_time, profile, updater};
+use db::{
+	CreatorAlias,
+	UpdaterAlias,
+	creator,
+	opening_time,
+	opening_time_exception,
+	profile,
+	updater,
+};
 use diesel::dsl::{AliasedFields, Nullable};
 use diesel::pg::Pg;
 use diesel::prelude::*;
-use diesel::sql_types::{Bool, Date, Time};
-use primitives::{PrimitiveOpeningTime, PrimitiveProfile};
+use diesel::sql_types::{Array, BigInt, Bool, Date, Integer};
+use primitives::{
+	PrimitiveOpeningTime,
+	PrimitiveOpeningTimeException,
+	PrimitiveProfile,
+	PrimitiveReservation,
+};
 use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+mod holiday;
+mod series;
+
+pub use holiday::*;
+pub use series::*;
 
 pub type JoinedOpeningTimeData =
 	(PrimitiveOpeningTime, Option<PrimitiveProfile>, Option<PrimitiveProfile>);
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Serialize)]
+#[into_params(parameter_in = Query)]
 #[serde(rename_all = "camelCase")]
 pub struct TimeBoundsFilter {
 	pub start_date: Option<NaiveDate>,
 	pub end_date:   Option<NaiveDate>,
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+/// Filter for opening times covering a given day and/or time of day
+#[derive(Clone, Copy, Debug, Deserialize, IntoParams, Serialize)]
+#[into_params(parameter_in = Query)]
 #[serde(rename_all = "camelCase")]
 pub struct TimeFilter {
-	pub open_on_day:  Option<NaiveDate>,
-	pub open_on_time: Option<NaiveTime>,
+	#[serde(alias = "openOnDay")]
+	pub open_on: Option<NaiveDate>,
+	#[serde(alias = "openOnTime")]
+	pub open_at: Option<NaiveTime>,
+}
+
+impl TimeFilter {
+	/// Whether this filter puts no restrictions on opening times at all
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.open_on.is_none() && self.open_at.is_none()
+	}
+
+	/// The requested point in time, if both a day and a time are given
+	#[must_use]
+	pub fn moment(&self) -> Option<NaiveDateTime> {
+		Some(self.open_on?.and_time(self.open_at?))
+	}
+
+	/// Like [`ToFilter::to_filter`], but only matching opening times that
+	/// can be reserved at the requested [moment](TimeFilter::moment)
+	///
+	/// A missing `reservable_from` or `reservable_until` leaves that side of
+	/// the reservation window open
+	pub fn to_reservable_filter<S>(&self) -> BoxedCondition<S, Bool>
+	where
+		S: 'static,
+		opening_time::id: SelectableExpression<S>,
+		opening_time::day: SelectableExpression<S>,
+		opening_time::start_time: SelectableExpression<S>,
+		opening_time::end_time: SelectableExpression<S>,
+		opening_time::reservable_from: SelectableExpression<S>,
+		opening_time::reservable_until: SelectableExpression<S>,
+	{
+		let filter = self.to_filter();
+
+		let Some(moment) = self.moment() else {
+			return filter;
+		};
+
+		Box::new(
+			filter
+				.and(opening_time::reservable_from.is_null().or(
+					opening_time::reservable_from.le(moment).assume_not_null(),
+				))
+				.and(opening_time::reservable_until.is_null().or(
+					opening_time::reservable_until.ge(moment).assume_not_null(),
+				)),
+		)
+	}
 }
 
 impl<S> ToFilter<S> for TimeBoundsFilter
@@ -70,30 +144,35 @@ where
 
 	fn to_filter(&self) -> BoxedCondition<S, Self::SqlType> {
 		let mut filter: BoxedCondition<S, Self::SqlType> =
-			if let Some(open_on_day) = self.open_on_day {
-				Box::new(open_on_day.into_sql::<Date>().eq(opening_time::day))
+			if let Some(open_on) = self.open_on {
+				Box::new(open_on.into_sql::<Date>().eq(opening_time::day))
 			} else {
 				Box::new(true.into_sql::<Bool>())
 			};
 
-		if let Some(open_on_time) = self.open_on_time {
-			filter =
-				Box::new(filter.and(open_on_time.into_sql::<Time>().between(
-					opening_time::start_time,
-					opening_time::end_time,
-				)));
+		// An opening time no longer covers the moment it ends
+		if let Some(open_at) = self.open_at {
+			filter = Box::new(
+				filter
+					.and(opening_time::start_time.le(open_at))
+					.and(opening_time::end_time.gt(open_at)),
+			);
 		}
 
 		filter
 	}
 }
 
-#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, IntoParams, Serialize)]
+#[into_params(parameter_in = Query)]
 pub struct OpeningTimeIncludes {
 	#[serde(default)]
-	pub created_by: bool,
+	pub created_by:   bool,
+	#[serde(default)]
+	pub updated_by:   bool,
+	/// Include the amount of reserved blocks of every opening time
 	#[serde(default)]
-	pub updated_by: bool,
+	pub reservations: bool,
 }
 
 #[derive(Clone, Debug, Deserialize, Queryable, Selectable, Serialize)]
@@ -123,6 +202,54 @@ fn updated_by_fragment() -> updated_by_fragment {
 	updater.fields(profile::all_columns).nullable()
 }
 
+/// An upcoming [`OpeningTime`] together with the amount of blocks that still
+/// have at least one free seat
+#[derive(Clone, Debug, Deserialize, QueryableByName, Serialize)]
+#[diesel(check_for_backend(Pg))]
+pub struct OpeningTimeWithAvailability {
+	#[diesel(embed)]
+	pub opening_time: PrimitiveOpeningTime,
+	#[diesel(sql_type = BigInt)]
+	pub free_blocks:  i64,
+}
+
+/// Find the first upcoming opening time with free blocks for every location
+///
+/// The amount of free blocks is computed by counting, for every block in the
+/// opening time, the non-cancelled reservations that occupy it
+const NEXT_AVAILABLE_QUERY: &str = r"
+SELECT DISTINCT ON (opening_time.location_id)
+	opening_time.*,
+	availability.free_blocks
+FROM opening_time
+INNER JOIN location ON location.id = opening_time.location_id
+CROSS JOIN LATERAL (
+	SELECT COUNT(*) AS free_blocks
+	FROM generate_series(
+		0,
+		(
+			EXTRACT(EPOCH FROM opening_time.end_time - opening_time.start_time)
+			/ 60 / $2
+		)::INTEGER - 1
+	) AS block
+	WHERE (
+		SELECT COUNT(*)
+		FROM reservation
+		WHERE reservation.opening_time_id = opening_time.id
+			AND reservation.state <> 'cancelled'
+			AND reservation.base_block_index <= block
+			AND block < reservation.base_block_index + reservation.block_count
+	) < COALESCE(opening_time.seat_count, location.seat_count)
+) AS availability
+WHERE opening_time.location_id = ANY($1)
+	AND opening_time.day >= CURRENT_DATE
+	AND availability.free_blocks > 0
+ORDER BY
+	opening_time.location_id,
+	opening_time.day ASC,
+	opening_time.start_time ASC
+";
+
 impl OpeningTime {
 	/// Build a query with all required (dynamic) joins to select a full
 	/// location data tuple
@@ -167,6 +294,30 @@ impl OpeningTime {
 		Ok(time)
 	}
 
+	/// Get an [`OpeningTime`] given its id, only if it belongs to the given
+	/// location
+	#[instrument(skip(conn))]
+	pub async fn get_by_id_and_location(
+		t_id: i32,
+		loc_id: i32,
+		includes: OpeningTimeIncludes,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		let query = Self::query(includes);
+
+		let time = conn
+			.interact(move |conn| {
+				query
+					.filter(opening_time::id.eq(t_id))
+					.filter(opening_time::location_id.eq(loc_id))
+					.select(Self::as_select())
+					.get_result(conn)
+			})
+			.await??;
+
+		Ok(time)
+	}
+
 	/// Get all the [`OpeningTimes`] for a specific location
 	#[instrument(skip(conn))]
 	pub async fn get_for_location(
@@ -193,6 +344,35 @@ impl OpeningTime {
 		Ok(times)
 	}
 
+	/// Get all the [`OpeningTime`]s for a specific location together with
+	/// the total amount of blocks reserved for each of them
+	#[instrument(skip(conn))]
+	pub async fn get_with_availability_for_location(
+		loc_id: i32,
+		time_filter: TimeBoundsFilter,
+		includes: OpeningTimeIncludes,
+		conn: &DbConn,
+	) -> Result<Vec<(Self, i64)>, Error> {
+		let times =
+			Self::get_for_location(loc_id, time_filter, includes, conn).await?;
+
+		let t_ids = times.iter().map(|t| t.primitive.id).collect();
+		let booked =
+			Reservation::get_total_blocks_for_opening_times(t_ids, conn)
+				.await?;
+
+		let availability = times
+			.into_iter()
+			.map(|t| {
+				let blocks = booked.get(&t.primitive.id).copied().unwrap_or(0);
+
+				(t, blocks)
+			})
+			.collect();
+
+		Ok(availability)
+	}
+
 	/// Get all the [`OpeningTime`]s for a list of location IDs
 	#[instrument(skip(conn))]
 	pub async fn get_for_locations(
@@ -216,6 +396,36 @@ impl OpeningTime {
 		Ok(times)
 	}
 
+	/// Get the first upcoming [`OpeningTime`] with free blocks for each of
+	/// the given locations
+	///
+	/// Locations without any such opening time are mapped to [`None`]
+	#[instrument(skip(conn))]
+	pub async fn get_next_available_for_locations(
+		l_ids: Vec<i32>,
+		conn: &DbConn,
+	) -> Result<HashMap<i32, Option<OpeningTimeWithAvailability>>, Error> {
+		let query_ids = l_ids.clone();
+
+		let times = conn
+			.interact(move |conn| {
+				diesel::sql_query(NEXT_AVAILABLE_QUERY)
+					.bind::<Array<Integer>, _>(query_ids)
+					.bind::<Integer, _>(RESERVATION_BLOCK_SIZE_MINUTES)
+					.load::<OpeningTimeWithAvailability>(conn)
+			})
+			.await??;
+
+		let mut next_available: HashMap<i32, Option<_>> =
+			l_ids.into_iter().map(|id| (id, None)).collect();
+
+		for time in times {
+			next_available.insert(time.opening_time.location_id, Some(time));
+		}
+
+		Ok(next_available)
+	}
+
 	/// Search through all [`OpeningTime`]s
 	///
 	/// # Panics
@@ -227,8 +437,8 @@ impl OpeningTime {
 	) -> Result<Vec<PrimitiveOpeningTime>, Error> {
 		let filter = time_filter.to_filter();
 
-		let bounds_filter = if let Some(open_on_day) = time_filter.open_on_day {
-			let week = open_on_day.week(Weekday::Mon);
+		let bounds_filter = if let Some(open_on) = time_filter.open_on {
+			let week = open_on.week(Weekday::Mon);
 			// I don't think blokmap will still be used in 264.000 AD so unwrap
 			// should be safe
 			let week_start = week.checked_first_day().unwrap();
@@ -270,19 +480,102 @@ impl OpeningTime {
 		Ok(times)
 	}
 
+	/// Create an exception for every [`OpeningTime`] of a location that falls
+	/// on one of the given [`PublicHoliday`]s
+	///
+	/// Opening times that already have an exception are skipped, only the
+	/// newly created exceptions are returned alongside the day they apply to
+	#[instrument(skip(conn))]
+	pub async fn generate_exceptions_from_public_holidays(
+		loc_id: i32,
+		holidays: Vec<PublicHoliday>,
+		created_by: i32,
+		conn: &DbConn,
+	) -> Result<Vec<(NaiveDate, PrimitiveOpeningTimeException)>, Error> {
+		let holiday_names: HashMap<NaiveDate, String> =
+			holidays.into_iter().map(|h| (h.date, h.name)).collect();
+
+		let exceptions = conn
+			.interact(move |conn| {
+				conn.transaction::<_, Error, _>(|conn| {
+					let dates: Vec<NaiveDate> =
+						holiday_names.keys().copied().collect();
+
+					let times: HashMap<i32, NaiveDate> = opening_time::table
+						.filter(opening_time::location_id.eq(loc_id))
+						.filter(opening_time::day.eq_any(dates))
+						.select((opening_time::id, opening_time::day))
+						.get_results::<(i32, NaiveDate)>(conn)?
+						.into_iter()
+						.collect();
+
+					let new_exceptions: Vec<_> = times
+						.iter()
+						.map(|(t_id, day)| {
+							NewOpeningTimeException {
+								opening_time_id: *t_id,
+								reason: holiday_names[day].clone(),
+								created_by,
+							}
+						})
+						.collect();
+
+					let exceptions =
+						diesel::insert_into(opening_time_exception::table)
+							.values(new_exceptions)
+							.on_conflict(
+								opening_time_exception::opening_time_id,
+							)
+							.do_nothing()
+							.returning(
+								PrimitiveOpeningTimeException::as_returning(),
+							)
+							.get_results(conn)?
+							.into_iter()
+							.map(|e| (times[&e.opening_time_id], e))
+							.collect();
+
+					Ok(exceptions)
+				})
+			})
+			.await??;
+
+		info!(
+			"generated public holiday exceptions for location with id {loc_id}"
+		);
+
+		Ok(exceptions)
+	}
+
 	/// Delete an [`OpeningTime`] given its id
+	///
+	/// Its reservations are removed along with it, the ones that were still
+	/// active are returned so their profiles can be notified
 	#[instrument(skip(conn))]
-	pub async fn delete_by_id(t_id: i32, conn: &DbConn) -> Result<(), Error> {
-		conn.interact(move |conn| {
-			use self::opening_time::dsl::*;
+	pub async fn delete_by_id(
+		t_id: i32,
+		conn: &DbConn,
+	) -> Result<Vec<PrimitiveReservation>, Error> {
+		let cancelled = conn
+			.interact(move |conn| {
+				conn.transaction::<_, Error, _>(|conn| {
+					let cancelled =
+						Reservation::lock_active_for_opening_time(t_id, conn)?;
+
+					diesel::delete(opening_time::table.find(t_id))
+						.execute(conn)?;
 
-			diesel::delete(opening_time.find(t_id)).execute(conn)
-		})
-		.await??;
+					Ok(cancelled)
+				})
+			})
+			.await??;
 
-		info!("deleted opening_time with id {t_id}");
+		info!(
+			"deleted opening_time with id {t_id}, cancelling {} reservations",
+			cancelled.len()
+		);
 
-		Ok(())
+		Ok(cancelled)
 	}
 }
 
@@ -323,6 +616,15 @@ impl NewOpeningTime {
 	}
 }
 
+#[derive(Clone, Debug, Deserialize, Insertable, Serialize)]
+#[diesel(table_name = opening_time_exception)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewOpeningTimeException {
+	pub opening_time_id: i32,
+	pub reason:          String,
+	pub created_by:      i32,
+}
+
 #[derive(AsChangeset, Clone, Debug, Deserialize, Serialize)]
 #[diesel(table_name = opening_time)]
 #[diesel(check_for_backend(Pg))]
@@ -338,24 +640,54 @@ pub struct OpeningTimeUpdate {
 
 impl OpeningTimeUpdate {
 	/// Apply this update to the [`OpeningTime`] with the given id
+	///
+	/// Active reservations that no longer fit within the updated opening time
+	/// are cancelled and returned alongside it
 	#[instrument(skip(conn))]
 	pub async fn apply_to(
 		self,
 		t_id: i32,
 		includes: OpeningTimeIncludes,
 		conn: &DbConn,
-	) -> Result<OpeningTime, Error> {
-		conn.interact(move |conn| {
-			use self::opening_time::dsl::*;
-
-			diesel::update(opening_time.find(t_id)).set(self).execute(conn)
-		})
-		.await??;
+	) -> Result<(OpeningTime, Vec<PrimitiveReservation>), Error> {
+		let cancelled = conn
+			.interact(move |conn| {
+				conn.transaction::<_, Error, _>(|conn| {
+					use self::opening_time::dsl::*;
+
+					let (old_start, old_end) = opening_time
+						.find(t_id)
+						.select((start_time, end_time))
+						.for_update()
+						.get_result::<(NaiveTime, NaiveTime)>(conn)?;
+
+					let new_start = self.start_time.unwrap_or(old_start);
+					let new_end = self.end_time.unwrap_or(old_end);
+
+					let cancelled =
+						Reservation::cancel_outside_of_opening_time(
+							t_id, old_start, new_start, new_end, conn,
+						)?;
+
+					// Reservation blocks are relative to the start time, so
+					// they have to move along with it
+					Reservation::recalculate_for_opening_time(
+						t_id, old_start, new_start, new_end, conn,
+					)?;
+
+					diesel::update(opening_time.find(t_id))
+						.set(self)
+						.execute(conn)?;
+
+					Ok(cancelled)
+				})
+			})
+			.await??;
 
 		let time = OpeningTime::get_by_id(t_id, includes, conn).await?;
 
 		info!("updated opening_time {time:?}");
 
-		Ok(time)
+		Ok((time, cancelled))
 	}
 }
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use common::{DbConn, Error};
+use db::opening_time;
+use diesel::prelude::*;
+use primitives::PrimitiveOpeningTime;
+use serde::{Deserialize, Serialize};
+
+use crate::NewOpeningTime;
+
+/// Maximum amount of opening times a single series may expand into
+pub const MAX_SERIES_LENGTH: usize = 366;
+
+/// A recurring opening time on a set of weekdays between two dates
+///
+/// Opening times are stored as local dates and times, so every occurrence
+/// keeps the same start and end time across daylight saving changes
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NewOpeningTimeSeries {
+	pub location_id:      i32,
+	pub start_date:       NaiveDate,
+	pub end_date:         NaiveDate,
+	/// Bitmask of weekdays, Monday being the least significant bit
+	pub weekdays:         u8,
+	pub start_time:       NaiveTime,
+	pub end_time:         NaiveTime,
+	pub seat_count:       Option<i32>,
+	pub reservable_from:  Option<NaiveDateTime>,
+	pub reservable_until: Option<NaiveDateTime>,
+	pub created_by:       i32,
+}
+
+impl NewOpeningTimeSeries {
+	/// Build a weekday mask from a list of [`Weekday`]s
+	#[must_use]
+	pub fn weekday_mask(days: &[Weekday]) -> u8 {
+		days.iter().fold(0, |mask, d| mask | (1 << d.num_days_from_monday()))
+	}
+
+	/// Check if this series recurs on the given [`Weekday`]
+	#[must_use]
+	pub fn includes_weekday(&self, day: Weekday) -> bool {
+		self.weekdays & (1 << day.num_days_from_monday()) != 0
+	}
+
+	/// Expand this series into a [`NewOpeningTime`] for every matching day
+	pub fn expand(&self) -> Result<Vec<NewOpeningTime>, Error> {
+		let times: Vec<NewOpeningTime> = self
+			.start_date
+			.iter_days()
+			.take_while(|d| *d <= self.end_date)
+			.filter(|d| self.includes_weekday(d.weekday()))
+			.take(MAX_SERIES_LENGTH + 1)
+			.map(|day| {
+				NewOpeningTime {
+					location_id: self.location_id,
+					day,
+					start_time: self.start_time,
+					end_time: self.end_time,
+					seat_count: self.seat_count,
+					reservable_from: self.reservable_from,
+					reservable_until: self.reservable_until,
+					created_by: self.created_by,
+				}
+			})
+			.collect();
+
+		let message = if times.is_empty() {
+			"series does not contain any days".to_string()
+		} else if times.len() > MAX_SERIES_LENGTH {
+			format!("series can not contain more than {MAX_SERIES_LENGTH} days")
+		} else {
+			return Ok(times);
+		};
+
+		Err(Error::ValidationError {
+			fields: HashMap::from([("end_date".to_string(), message.clone())]),
+			message,
+		})
+	}
+
+	/// Insert every opening time of this series in a single transaction
+	///
+	/// Nothing is inserted if the location already has an opening time on
+	/// any of the days in the series, the colliding days are returned in
+	/// the error instead
+	#[instrument(skip(conn))]
+	pub async fn insert(
+		self,
+		conn: &DbConn,
+	) -> Result<Vec<PrimitiveOpeningTime>, Error> {
+		let loc_id = self.location_id;
+		let new_times = self.expand()?;
+
+		let times = conn
+			.interact(move |conn| {
+				conn.transaction::<_, Error, _>(|conn| {
+					let days: Vec<NaiveDate> =
+						new_times.iter().map(|t| t.day).collect();
+
+					let collisions = opening_time::table
+						.filter(opening_time::location_id.eq(loc_id))
+						.filter(opening_time::day.eq_any(days))
+						.select(opening_time::day)
+						.distinct()
+						.order(opening_time::day)
+						.get_results::<NaiveDate>(conn)?;
+
+					if !collisions.is_empty() {
+						return Err(Error::OpeningTimeCollision {
+							days: collisions,
+						});
+					}
+
+					let times = diesel::insert_into(opening_time::table)
+						.values(new_times)
+						.returning(PrimitiveOpeningTime::as_returning())
+						.get_results(conn)?;
+
+					Ok(times)
+				})
+			})
+			.await??;
+
+		info!(
+			"created series of {} opening times for location {loc_id}",
+			times.len()
+		);
+
+		Ok(times)
+	}
+}
@@ -1,5 +1,11 @@
-use common::{DbPool, Error};
-use db::{authority, authority_member, authority_role};
+use common::{DbConn, DbPool, Error};
+use db::{
+	authority,
+	authority_member,
+	authority_role,
+	institution_member,
+	institution_role,
+};
 use diesel::prelude::*;
 use serde::Serialize;
 
@@ -26,71 +32,89 @@ bitflags! {
 }
 
 impl AuthorityPermissions {
+	/// Get the names of all the permissions that are set
+	#[must_use]
+	pub fn permissions_labels(&self) -> Vec<String> {
+		self.iter_names().map(|(name, _)| name.to_string()).collect()
+	}
+
+	/// Get the permissions of a profile for an authority, together with those
+	/// for the institution the authority is part of
+	///
+	/// Both levels are fetched in a single query, levels the profile has no
+	/// role on are empty
 	#[instrument(skip(pool))]
 	pub(crate) async fn get_for_authority_member(
 		auth_id: i32,
 		prof_id: i32,
 		pool: &DbPool,
 	) -> Result<(InstitutionPermissions, Self), Error> {
-		let inst_conn = pool.get().await?;
-		let inst_perms_future = async {
-			let inst_id = inst_conn
-				.interact(move |conn| {
-					use self::authority::dsl::*;
-
-					authority
-						.find(auth_id)
-						.select(institution_id)
-						.get_result(conn)
-				})
-				.await??;
-
-			let Some(inst_id) = inst_id else {
-				return Ok::<_, Error>(InstitutionPermissions::empty());
-			};
-
-			let inst_perms =
-				InstitutionPermissions::get_for_institution_member(
-					inst_id, prof_id, &inst_conn,
-				)
-				.await?;
+		let conn = pool.get().await?;
 
-			Ok(inst_perms)
-		};
+		let (inst_perms, auth_perms) = conn
+			.interact(move |conn| {
+				authority::table
+					.left_join(
+						authority_member::table.on(
+							authority_member::authority_id
+								.eq(authority::id)
+								.and(authority_member::profile_id.eq(prof_id)),
+						),
+					)
+					.left_join(
+						authority_role::table
+							.on(authority_member::authority_role_id
+								.eq(authority_role::id.nullable())),
+					)
+					.left_join(
+						institution_member::table.on(authority::institution_id
+							.eq(institution_member::institution_id.nullable())
+							.and(institution_member::profile_id.eq(prof_id))),
+					)
+					.left_join(
+						institution_role::table
+							.on(institution_member::institution_role_id
+								.eq(institution_role::id.nullable())),
+					)
+					.filter(authority::id.eq(auth_id))
+					.select((
+						institution_role::permissions.nullable(),
+						authority_role::permissions.nullable(),
+					))
+					.get_result::<(Option<i64>, Option<i64>)>(conn)
+			})
+			.await??;
 
-		let auth_conn = pool.get().await?;
-		let auth_perms_future = async {
-			let auth_perms = auth_conn
-				.interact(move |conn| {
-					use self::authority_member::dsl::*;
+		let inst_perms = InstitutionPermissions::from_bits_truncate(
+			inst_perms.unwrap_or_default(),
+		);
+		let auth_perms =
+			Self::from_bits_truncate(auth_perms.unwrap_or_default());
 
-					authority_member
-						.filter(
-							authority_id
-								.eq(auth_id)
-								.and(profile_id.eq(prof_id)),
-						)
-						.inner_join(authority_role::table.on(
-							authority_role_id.eq(authority_role::id.nullable()),
-						))
-						.select(authority_role::permissions)
-						.get_result(conn)
-						.optional()
-				})
-				.await??;
-
-			let auth_perms = auth_perms.unwrap_or_default();
-			let auth_perms = Self::from_bits_truncate(auth_perms);
+		Ok((inst_perms, auth_perms))
+	}
 
-			Ok::<_, Error>(auth_perms)
-		};
+	/// Get the permissions granted by a role of the given authority
+	#[instrument(skip(conn))]
+	pub(crate) async fn get_for_authority_role(
+		auth_id: i32,
+		role_id: i32,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		let perms = conn
+			.interact(move |conn| {
+				use self::authority_role::dsl::*;
 
-		let (inst_perms, auth_perms) =
-			tokio::join!(inst_perms_future, auth_perms_future,);
+				authority_role
+					.find(role_id)
+					.filter(authority_id.eq(auth_id))
+					.select(permissions)
+					.get_result(conn)
+			})
+			.await??;
 
-		let inst_perms = inst_perms?;
-		let auth_perms = auth_perms?;
+		let perms = Self::from_bits_truncate(perms);
 
-		Ok((inst_perms, auth_perms))
+		Ok(perms)
 	}
 }
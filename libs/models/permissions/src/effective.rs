@@ -0,0 +1,97 @@
+use common::{DbConn, DbPool, Error};
+use serde::Serialize;
+
+use crate::{
+	AuthorityPermissions,
+	InstitutionPermissions,
+	LocationPermissions,
+};
+
+/// The permissions a profile holds on a location, authority or institution,
+/// together with those on every level above it
+///
+/// Levels below the one the permissions were fetched for are always empty
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub struct EffectivePermissions {
+	pub institution: InstitutionPermissions,
+	pub authority:   AuthorityPermissions,
+	pub location:    LocationPermissions,
+}
+
+impl EffectivePermissions {
+	/// Get the effective permissions of a profile for a location
+	#[instrument(skip(pool))]
+	pub async fn for_location(
+		loc_id: i32,
+		prof_id: i32,
+		pool: &DbPool,
+	) -> Result<Self, Error> {
+		let (institution, authority, location) =
+			LocationPermissions::get_for_location_member(loc_id, prof_id, pool)
+				.await?;
+
+		Ok(Self { institution, authority, location })
+	}
+
+	/// Get the effective permissions of a profile for an authority
+	#[instrument(skip(pool))]
+	pub async fn for_authority(
+		auth_id: i32,
+		prof_id: i32,
+		pool: &DbPool,
+	) -> Result<Self, Error> {
+		let (institution, authority) =
+			AuthorityPermissions::get_for_authority_member(
+				auth_id, prof_id, pool,
+			)
+			.await?;
+
+		Ok(Self {
+			institution,
+			authority,
+			location: LocationPermissions::empty(),
+		})
+	}
+
+	/// Get the effective permissions of a profile for an institution
+	#[instrument(skip(conn))]
+	pub async fn for_institution(
+		inst_id: i32,
+		prof_id: i32,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		let institution = InstitutionPermissions::get_for_institution_member(
+			inst_id, prof_id, conn,
+		)
+		.await?;
+
+		Ok(Self {
+			institution,
+			authority: AuthorityPermissions::empty(),
+			location: LocationPermissions::empty(),
+		})
+	}
+
+	/// Check whether *any* of the given permissions are held on their level
+	#[must_use]
+	pub fn intersects(
+		&self,
+		loc_perms: LocationPermissions,
+		auth_perms: AuthorityPermissions,
+		inst_perms: InstitutionPermissions,
+	) -> bool {
+		self.location.intersects(loc_perms)
+			| self.authority.intersects(auth_perms)
+			| self.institution.intersects(inst_perms)
+	}
+
+	/// Check whether the profile is an administrator on any level
+	#[must_use]
+	pub fn is_administrator(&self) -> bool {
+		self.intersects(
+			LocationPermissions::Administrator,
+			AuthorityPermissions::Administrator,
+			InstitutionPermissions::Administrator,
+		)
+	}
+}
@@ -22,6 +22,12 @@ bitflags! {
 }
 
 impl InstitutionPermissions {
+	/// Get the names of all the permissions that are set
+	#[must_use]
+	pub fn permissions_labels(&self) -> Vec<String> {
+		self.iter_names().map(|(name, _)| name.to_string()).collect()
+	}
+
 	#[instrument(skip(conn))]
 	pub(crate) async fn get_for_institution_member(
 		inst_id: i32,
@@ -50,4 +56,28 @@ impl InstitutionPermissions {
 
 		Ok(perms)
 	}
+
+	/// Get the permissions granted by a role of the given institution
+	#[instrument(skip(conn))]
+	pub(crate) async fn get_for_institution_role(
+		inst_id: i32,
+		role_id: i32,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		let perms = conn
+			.interact(move |conn| {
+				use self::institution_role::dsl::*;
+
+				institution_role
+					.find(role_id)
+					.filter(institution_id.eq(inst_id))
+					.select(permissions)
+					.get_result(conn)
+			})
+			.await??;
+
+		let perms = Self::from_bits_truncate(perms);
+
+		Ok(perms)
+	}
 }
@@ -6,10 +6,12 @@ extern crate tracing;
 use common::{DbConn, DbPool, Error};
 
 mod authority;
+mod effective;
 mod institution;
 mod location;
 
 pub use authority::*;
+pub use effective::*;
 pub use institution::*;
 pub use location::*;
 
@@ -22,12 +24,46 @@ pub async fn check_institution_perms(
 	perms: InstitutionPermissions,
 	conn: &DbConn,
 ) -> Result<(), Error> {
+	let effective =
+		EffectivePermissions::for_institution(inst_id, prof_id, conn).await?;
+
+	if effective.institution.intersects(perms) {
+		return Ok(());
+	}
+
+	Err(Error::Forbidden)
+}
+
+/// Checks whether the given profile is allowed to grant the given role to a
+/// member of the given institution
+///
+/// Administrators can grant any role, other members can only grant roles that
+/// do not exceed their own permissions
+#[instrument(skip(conn))]
+pub async fn check_institution_role_grant(
+	inst_id: i32,
+	prof_id: i32,
+	role_id: Option<i32>,
+	conn: &DbConn,
+) -> Result<(), Error> {
+	// Clearing a role never grants any permissions
+	let Some(role_id) = role_id else {
+		return Ok(());
+	};
+
+	let role_perms = InstitutionPermissions::get_for_institution_role(
+		inst_id, role_id, conn,
+	)
+	.await?;
+
 	let inst_perms = InstitutionPermissions::get_for_institution_member(
 		inst_id, prof_id, conn,
 	)
 	.await?;
 
-	if inst_perms.intersects(perms) {
+	if inst_perms.contains(InstitutionPermissions::Administrator)
+		| inst_perms.contains(role_perms)
+	{
 		return Ok(());
 	}
 
@@ -44,12 +80,49 @@ pub async fn check_authority_perms(
 	inst_perms: InstitutionPermissions,
 	pool: &DbPool,
 ) -> Result<(), Error> {
+	let effective =
+		EffectivePermissions::for_authority(auth_id, prof_id, pool).await?;
+
+	if effective.intersects(
+		LocationPermissions::empty(),
+		auth_perms,
+		inst_perms,
+	) {
+		return Ok(());
+	}
+
+	Err(Error::Forbidden)
+}
+
+/// Checks whether the given profile is allowed to grant the given role to a
+/// member of the given authority
+///
+/// Administrators can grant any role, other members can only grant roles that
+/// do not exceed their own permissions
+#[instrument(skip(pool))]
+pub async fn check_authority_role_grant(
+	auth_id: i32,
+	prof_id: i32,
+	role_id: Option<i32>,
+	pool: &DbPool,
+) -> Result<(), Error> {
+	// Clearing a role never grants any permissions
+	let Some(role_id) = role_id else {
+		return Ok(());
+	};
+
+	let conn = pool.get().await?;
+	let role_perms =
+		AuthorityPermissions::get_for_authority_role(auth_id, role_id, &conn)
+			.await?;
+
 	let (db_inst_perms, db_auth_perms) =
 		AuthorityPermissions::get_for_authority_member(auth_id, prof_id, pool)
 			.await?;
 
-	if db_inst_perms.intersects(inst_perms)
-		| db_auth_perms.intersects(auth_perms)
+	if db_inst_perms.contains(InstitutionPermissions::Administrator)
+		| db_auth_perms.contains(AuthorityPermissions::Administrator)
+		| db_auth_perms.contains(role_perms)
 	{
 		return Ok(());
 	}
@@ -67,14 +140,78 @@ pub async fn check_location_perms(
 	auth_perms: AuthorityPermissions,
 	inst_perms: InstitutionPermissions,
 	pool: &DbPool,
+) -> Result<(), Error> {
+	let effective =
+		EffectivePermissions::for_location(loc_id, prof_id, pool).await?;
+
+	if effective.intersects(loc_perms, auth_perms, inst_perms) {
+		return Ok(());
+	}
+
+	Err(Error::Forbidden)
+}
+
+/// Checks whether the given profile is allowed to grant the given role to a
+/// member of the given location
+///
+/// Administrators can grant any role, other members can only grant roles that
+/// do not exceed their own permissions
+#[instrument(skip(pool))]
+pub async fn check_location_role_grant(
+	loc_id: i32,
+	prof_id: i32,
+	role_id: Option<i32>,
+	pool: &DbPool,
+) -> Result<(), Error> {
+	// Clearing a role never grants any permissions
+	let Some(role_id) = role_id else {
+		return Ok(());
+	};
+
+	let conn = pool.get().await?;
+	let role_perms =
+		LocationPermissions::get_for_location_role(loc_id, role_id, &conn)
+			.await?;
+
+	let (db_inst_perms, db_auth_perms, db_loc_perms) =
+		LocationPermissions::get_for_location_member(loc_id, prof_id, pool)
+			.await?;
+
+	if db_inst_perms.contains(InstitutionPermissions::Administrator)
+		| db_auth_perms.contains(AuthorityPermissions::Administrator)
+		| db_loc_perms.contains(LocationPermissions::Administrator)
+		| db_loc_perms.contains(role_perms)
+	{
+		return Ok(());
+	}
+
+	Err(Error::Forbidden)
+}
+
+/// Checks whether the given profile is allowed to change the role of another
+/// member of the given location
+///
+/// Administrators can change any member, other members can only change
+/// members whose current permissions do not exceed their own
+#[instrument(skip(pool))]
+pub async fn check_location_member_change(
+	loc_id: i32,
+	prof_id: i32,
+	member_id: i32,
+	pool: &DbPool,
 ) -> Result<(), Error> {
 	let (db_inst_perms, db_auth_perms, db_loc_perms) =
 		LocationPermissions::get_for_location_member(loc_id, prof_id, pool)
 			.await?;
 
-	if db_inst_perms.intersects(inst_perms)
-		| db_auth_perms.intersects(auth_perms)
-		| db_loc_perms.intersects(loc_perms)
+	let (_, _, member_perms) =
+		LocationPermissions::get_for_location_member(loc_id, member_id, pool)
+			.await?;
+
+	if db_inst_perms.contains(InstitutionPermissions::Administrator)
+		| db_auth_perms.contains(AuthorityPermissions::Administrator)
+		| db_loc_perms.contains(LocationPermissions::Administrator)
+		| db_loc_perms.contains(member_perms)
 	{
 		return Ok(());
 	}
@@ -1,5 +1,14 @@
-use common::{DbPool, Error};
-use db::{location, location_member, location_role};
+use common::{DbConn, DbPool, Error};
+use db::{
+	authority,
+	authority_member,
+	authority_role,
+	institution_member,
+	institution_role,
+	location,
+	location_member,
+	location_role,
+};
 use diesel::prelude::*;
 use serde::Serialize;
 
@@ -32,68 +41,103 @@ bitflags! {
 }
 
 impl LocationPermissions {
+	/// Get the names of all the permissions that are set
+	#[must_use]
+	pub fn permissions_labels(&self) -> Vec<String> {
+		self.iter_names().map(|(name, _)| name.to_string()).collect()
+	}
+
+	/// Get the permissions of a profile for a location, together with those
+	/// for the authority and institution the location is part of
+	///
+	/// All three levels are fetched in a single query, levels the profile has
+	/// no role on are empty
 	#[instrument(skip(pool))]
 	pub(crate) async fn get_for_location_member(
 		loc_id: i32,
 		prof_id: i32,
 		pool: &DbPool,
 	) -> Result<(InstitutionPermissions, AuthorityPermissions, Self), Error> {
-		let ia_conn = pool.get().await?;
-		let ia_perms_future = async {
-			let auth_id = ia_conn
-				.interact(move |conn| {
-					use self::location::dsl::*;
-
-					location.find(loc_id).select(authority_id).get_result(conn)
-				})
-				.await??;
-
-			let Some(auth_id) = auth_id else {
-				return Ok::<_, Error>((
-					InstitutionPermissions::empty(),
-					AuthorityPermissions::empty(),
-				));
-			};
-
-			let ia_perms = AuthorityPermissions::get_for_authority_member(
-				auth_id, prof_id, pool,
-			)
-			.await?;
+		let conn = pool.get().await?;
 
-			Ok(ia_perms)
-		};
+		let (inst_perms, auth_perms, loc_perms) = conn
+			.interact(move |conn| {
+				location::table
+					.left_join(
+						location_member::table.on(location_member::location_id
+							.eq(location::id)
+							.and(location_member::profile_id.eq(prof_id))),
+					)
+					.left_join(
+						location_role::table
+							.on(location_member::location_role_id
+								.eq(location_role::id.nullable())),
+					)
+					.left_join(
+						authority_member::table.on(location::authority_id
+							.eq(authority_member::authority_id.nullable())
+							.and(authority_member::profile_id.eq(prof_id))),
+					)
+					.left_join(
+						authority_role::table
+							.on(authority_member::authority_role_id
+								.eq(authority_role::id.nullable())),
+					)
+					.left_join(authority::table.on(
+						location::authority_id.eq(authority::id.nullable()),
+					))
+					.left_join(
+						institution_member::table.on(authority::institution_id
+							.eq(institution_member::institution_id.nullable())
+							.and(institution_member::profile_id.eq(prof_id))),
+					)
+					.left_join(
+						institution_role::table
+							.on(institution_member::institution_role_id
+								.eq(institution_role::id.nullable())),
+					)
+					.filter(location::id.eq(loc_id))
+					.select((
+						institution_role::permissions.nullable(),
+						authority_role::permissions.nullable(),
+						location_role::permissions.nullable(),
+					))
+					.get_result::<(Option<i64>, Option<i64>, Option<i64>)>(conn)
+			})
+			.await??;
 
-		let loc_conn = pool.get().await?;
-		let loc_perms_future = async {
-			let loc_perms = loc_conn
-				.interact(move |conn| {
-					use self::location_member::dsl::*;
+		let inst_perms = InstitutionPermissions::from_bits_truncate(
+			inst_perms.unwrap_or_default(),
+		);
+		let auth_perms = AuthorityPermissions::from_bits_truncate(
+			auth_perms.unwrap_or_default(),
+		);
+		let loc_perms = Self::from_bits_truncate(loc_perms.unwrap_or_default());
 
-					location_member
-						.filter(
-							location_id.eq(loc_id).and(profile_id.eq(prof_id)),
-						)
-						.inner_join(location_role::table.on(
-							location_role_id.eq(location_role::id.nullable()),
-						))
-						.select(location_role::permissions)
-						.get_result(conn)
-						.optional()
-				})
-				.await??;
-
-			let loc_perms = loc_perms.unwrap_or_default();
-			let loc_perms = Self::from_bits_truncate(loc_perms);
+		Ok((inst_perms, auth_perms, loc_perms))
+	}
 
-			Ok::<_, Error>(loc_perms)
-		};
+	/// Get the permissions granted by a role of the given location
+	#[instrument(skip(conn))]
+	pub(crate) async fn get_for_location_role(
+		loc_id: i32,
+		role_id: i32,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		let perms = conn
+			.interact(move |conn| {
+				use self::location_role::dsl::*;
 
-		let (ia_perms, loc_perms) =
-			tokio::join!(ia_perms_future, loc_perms_future,);
+				location_role
+					.find(role_id)
+					.filter(location_id.eq(loc_id))
+					.select(permissions)
+					.get_result(conn)
+			})
+			.await??;
 
-		let (inst_perms, auth_perms) = ia_perms?;
-		let loc_perms = loc_perms?;
+		let perms = Self::from_bits_truncate(perms);
 
-		Ok((inst_perms, auth_perms, loc_perms))
+		Ok(perms)
 	}
 }
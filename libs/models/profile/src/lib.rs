@@ -13,23 +13,34 @@ use base::{
 	manual_pagination,
 };
 use chrono::{NaiveDateTime, TimeDelta, Utc};
-use common::{DbConn, Error};
-use db::{
-	ProfileState,
-	ReservationState,
-	image,
-	opening_time,
-	profile,
-	reservation,
-};
+use common::{DbConn, Error, OAuthError};
+use db::{ProfileState, image, profile};
 use diesel::pg::Pg;
 use diesel::prelude::*;
+use diesel::result::DatabaseErrorKind;
+use diesel::sql_types::{BigInt, Integer, Timestamp};
 use lettre::message::Mailbox;
 use primitives::{PrimitiveImage, PrimitiveProfile};
 use rand::Rng;
 use rand::distr::Alphabetic;
 use serde::{Deserialize, Serialize};
 
+/// Maximum amount of usernames tried when creating a [`Profile`] from login
+/// claims
+pub const SSO_USERNAME_ATTEMPTS: usize = 5;
+
+/// Name of the unique constraint on profile usernames
+const USERNAME_CONSTRAINT: &str = "profile_username_key";
+
+/// Generate a username from a prefix followed by a random suffix
+fn random_username(prefix: &str) -> String {
+	let mut rng = rand::rng();
+	let suffix: String =
+		(0..5).map(|_| rng.sample(Alphabetic) as char).collect();
+
+	format!("{prefix}.{suffix}")
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProfileClaims {
@@ -125,6 +136,31 @@ impl Profile {
 		Ok(profile)
 	}
 
+	/// Disable this [`Profile`], storing who deactivated it and why
+	#[instrument(skip(conn))]
+	pub async fn deactivate_with_reason(
+		mut self,
+		reason: Option<String>,
+		deactivated_by: i32,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		self.primitive.state = ProfileState::Disabled;
+		self.primitive.deactivation_reason = reason;
+		self.primitive.deactivated_by = Some(deactivated_by);
+
+		self.update(conn).await
+	}
+
+	/// Re-activate this [`Profile`], clearing any deactivation info
+	#[instrument(skip(conn))]
+	pub async fn activate(mut self, conn: &DbConn) -> Result<Self, Error> {
+		self.primitive.state = ProfileState::Active;
+		self.primitive.deactivation_reason = None;
+		self.primitive.deactivated_by = None;
+
+		self.update(conn).await
+	}
+
 	/// Get a list of all [`Profile`]s
 	#[instrument(skip(conn))]
 	pub async fn get_all(
@@ -133,12 +169,54 @@ impl Profile {
 	) -> Result<PaginatedData<Vec<Self>>, Error> {
 		let query = Self::query();
 
+		let after_id = p_cfg.after_id.unwrap_or(i32::MIN);
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.sql_offset() as i64;
+
+		let (total, profiles) = conn
+			.interact(move |conn| {
+				use self::profile::dsl::*;
+
+				let total = profile.count().get_result::<i64>(conn)?;
+
+				let profiles = query
+					.filter(id.gt(after_id))
+					.order_by(id)
+					.limit(limit)
+					.offset(offset)
+					.select(Self::as_select())
+					.get_results(conn)?;
+
+				Ok::<_, Error>((total, profiles))
+			})
+			.await??;
+
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		p_cfg.check_offset(total)?;
+
+		Ok((total, false, profiles))
+	}
+
+	/// Get a list of all [`Profile`]s that have not confirmed their email
+	/// yet, oldest first
+	#[instrument(skip(conn))]
+	pub async fn get_pending_email_verification(
+		p_cfg: PaginationConfig,
+		conn: &DbConn,
+	) -> Result<PaginatedData<Vec<Self>>, Error> {
+		let query = Self::query();
+
 		let profiles = conn
 			.interact(move |conn| {
 				use self::profile::dsl::*;
 
 				query
-					.order_by(id)
+					.filter(state.eq(ProfileState::PendingEmailVerification))
+					.order_by(created_at.asc())
 					.limit(QUERY_HARD_LIMIT)
 					.select(Self::as_select())
 					.get_results(conn)
@@ -148,6 +226,58 @@ impl Profile {
 		manual_pagination(profiles, p_cfg)
 	}
 
+	/// Get all [`Profile`]s that are still awaiting email verification but
+	/// whose confirmation token has already expired
+	#[instrument(skip(conn))]
+	pub async fn get_expired_pending_verification(
+		conn: &DbConn,
+	) -> Result<Vec<Self>, Error> {
+		let query = Self::query();
+		let now = Utc::now().naive_utc();
+
+		let profiles = conn
+			.interact(move |conn| {
+				use self::profile::dsl::*;
+
+				query
+					.filter(state.eq(ProfileState::PendingEmailVerification))
+					.filter(email_confirmation_token_expiry.lt(now))
+					.order_by(id)
+					.select(Self::as_select())
+					.get_results(conn)
+			})
+			.await??;
+
+		Ok(profiles)
+	}
+
+	/// Delete all [`Profile`]s that are still awaiting email verification
+	/// but whose confirmation token has already expired, returning the
+	/// amount of deleted profiles
+	///
+	/// Unverified profiles cannot own any data yet, so they can be removed
+	/// without cascading anything
+	#[instrument(skip(conn))]
+	pub async fn cleanup_expired_pending(
+		conn: &DbConn,
+	) -> Result<usize, Error> {
+		let now = Utc::now().naive_utc();
+
+		let count = conn
+			.interact(move |conn| {
+				use self::profile::dsl::*;
+
+				let expired = profile
+					.filter(state.eq(ProfileState::PendingEmailVerification))
+					.filter(email_confirmation_token_expiry.lt(now));
+
+				diesel::delete(expired).execute(conn)
+			})
+			.await??;
+
+		Ok(count)
+	}
+
 	/// Check if a [`Profile`] with a given id exists
 	#[instrument(skip(conn))]
 	pub async fn exists(query_id: i32, conn: &DbConn) -> Result<bool, Error> {
@@ -251,6 +381,28 @@ impl Profile {
 		Ok(profile)
 	}
 
+	/// Get a profile given its email revert token
+	#[instrument(skip(token, conn))]
+	pub async fn get_by_email_revert_token(
+		token: String,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		let query = Self::query();
+
+		let profile = conn
+			.interact(move |conn| {
+				use self::profile::dsl::*;
+
+				query
+					.filter(email_revert_token.eq(token))
+					.select(Self::as_select())
+					.first(conn)
+			})
+			.await??;
+
+		Ok(profile)
+	}
+
 	/// Confirm the pending email for a [`Profile`]
 	///
 	/// # Panics
@@ -280,6 +432,45 @@ impl Profile {
 		Ok(profile)
 	}
 
+	/// Undo an email change of a [`Profile`]
+	///
+	/// The email from before the change is restored, even if the new one was
+	/// already confirmed, and the pending email is dropped along with its
+	/// confirmation and revert tokens
+	#[instrument(skip(conn))]
+	pub async fn revert_pending_email(
+		&self,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		let self_id = self.primitive.id;
+		let restored = self
+			.primitive
+			.previous_email
+			.clone()
+			.or_else(|| self.primitive.email.clone());
+
+		conn.interact(move |conn| {
+			use self::profile::dsl::*;
+
+			diesel::update(profile.find(self_id))
+				.set((
+					email.eq(restored),
+					previous_email.eq(None::<String>),
+					pending_email.eq(None::<String>),
+					email_confirmation_token.eq(None::<String>),
+					email_confirmation_token_expiry.eq(None::<NaiveDateTime>),
+					email_revert_token.eq(None::<String>),
+					email_revert_token_expiry.eq(None::<NaiveDateTime>),
+				))
+				.execute(conn)
+		})
+		.await??;
+
+		let profile = Self::get(self_id, conn).await?;
+
+		Ok(profile)
+	}
+
 	/// Set a new email confirmation token and expiry for a [`Profile`]
 	#[instrument(skip(token, conn))]
 	pub async fn set_email_confirmation_token(
@@ -314,6 +505,27 @@ impl Profile {
 		self.update(conn).await
 	}
 
+	/// Set a new email revert token and expiry for a [`Profile`]
+	///
+	/// The current email is kept so reverting can restore it after the new
+	/// email has been confirmed
+	#[instrument(skip(token, conn))]
+	pub async fn set_email_revert_token(
+		mut self,
+		token: &str,
+		lifetime: TimeDelta,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		let email_revert_token_expiry = Utc::now().naive_utc() + lifetime;
+
+		self.primitive.email_revert_token = Some(token.to_string());
+		self.primitive.email_revert_token_expiry =
+			Some(email_revert_token_expiry);
+		self.primitive.previous_email = self.primitive.email.clone();
+
+		self.update(conn).await
+	}
+
 	/// Hash a password using Argon2
 	pub fn hash_password(password: &str) -> Result<String, Error> {
 		let salt = SaltString::generate(&mut OsRng);
@@ -352,6 +564,62 @@ impl Profile {
 		Ok(profile)
 	}
 
+	/// Switch an SSO-linked [`Profile`] over to password authentication
+	///
+	/// The externally hosted avatar is detached from the profile and its url
+	/// is cleared, the image record itself is kept for reference
+	#[instrument(skip(new_password, conn))]
+	pub async fn unlink_sso(
+		&self,
+		new_password: &str,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		if !self.primitive.password_hash.is_empty() {
+			return Err(Error::BadRequest(
+				"profile already uses password authentication".to_string(),
+			));
+		}
+
+		if self.primitive.email.is_none() {
+			return Err(Error::BadRequest(
+				"profile has no confirmed email".to_string(),
+			));
+		}
+
+		let self_id = self.primitive.id;
+		let avatar_id = self.primitive.avatar_image_id;
+		let new_password_hash = Self::hash_password(new_password)?;
+
+		conn.interact(move |conn| {
+			conn.transaction::<_, Error, _>(|conn| {
+				use self::profile::dsl::*;
+
+				diesel::update(profile.find(self_id))
+					.set((
+						password_hash.eq(new_password_hash),
+						avatar_image_id.eq(None::<i32>),
+						state.eq(ProfileState::Active),
+					))
+					.execute(conn)?;
+
+				if let Some(avatar_id) = avatar_id {
+					diesel::update(image::table.find(avatar_id))
+						.set(image::image_url.eq(None::<String>))
+						.execute(conn)?;
+				}
+
+				Ok(())
+			})
+		})
+		.await??;
+
+		let profile = Self::get(self_id, conn).await?;
+
+		info!("unlinked SSO for profile {self_id}");
+
+		Ok(profile)
+	}
+
 	/// Set the `last_login_at` field to the current datetime for the given
 	/// [`Profile`]
 	#[instrument(skip(conn))]
@@ -365,6 +633,12 @@ impl Profile {
 
 	/// Get or create a [`Profile`] from a set of login claims
 	///
+	/// Existing profiles are only linked through their confirmed email, if
+	/// the email of the claims is still awaiting confirmation on some profile
+	/// an [`OAuthError::AccountExistsUnverified`] is returned instead.
+	/// Colliding usernames are retried with a random suffix at most
+	/// [`SSO_USERNAME_ATTEMPTS`] times
+	///
 	/// # Panics
 	/// Panics if the user has a *very* weird email
 	#[instrument(skip(conn))]
@@ -375,15 +649,23 @@ impl Profile {
 		let query = Self::query();
 		let user_email_ = claims.email.clone();
 
-		let profile: Option<Self> = conn
+		let (profile, unverified): (Option<Self>, bool) = conn
 			.interact(move |conn| {
 				use self::profile::dsl::*;
 
-				query
-					.filter(email.eq(user_email_))
+				let linked = query
+					.filter(email.eq(user_email_.clone()))
 					.select(Self::as_select())
 					.first(conn)
-					.optional()
+					.optional()?;
+
+				let unverified = linked.is_none()
+					&& diesel::select(diesel::dsl::exists(
+						profile.filter(pending_email.eq(user_email_)),
+					))
+					.get_result::<bool>(conn)?;
+
+				Ok::<_, Error>((linked, unverified))
 			})
 			.await??;
 
@@ -391,21 +673,21 @@ impl Profile {
 			return Ok(profile);
 		}
 
+		if unverified {
+			return Err(OAuthError::AccountExistsUnverified.into());
+		}
+
+		let prefix = claims.email.split('@').next().unwrap().to_string();
+
 		let username = if let Some(n) = claims.username
 			&& !n.is_empty()
 		{
 			n
 		} else {
-			let prefix = claims.email.split('@').next().unwrap().to_string();
-
-			let mut rng = rand::rng();
-			let suffix: String =
-				(0..5).map(|_| rng.sample(Alphabetic) as char).collect();
-
-			format!("{prefix}.{suffix}")
+			random_username(&prefix)
 		};
 
-		let new_profile = NewProfileDirect {
+		let mut new_profile = NewProfileDirect {
 			username,
 			first_name: claims.first_name,
 			last_name: claims.last_name,
@@ -414,13 +696,48 @@ impl Profile {
 			state: ProfileState::Active,
 		};
 
-		let profile = new_profile.insert(conn).await?;
+		let profile = conn
+			.interact(move |conn| {
+				use self::profile::dsl::*;
+
+				let mut attempt = 1;
+
+				loop {
+					let res = diesel::insert_into(profile)
+						.values(new_profile.clone())
+						.returning(PrimitiveProfile::as_select())
+						.get_result::<PrimitiveProfile>(conn);
+
+					match res {
+						Err(diesel::result::Error::DatabaseError(
+							DatabaseErrorKind::UniqueViolation,
+							info,
+						)) if attempt < SSO_USERNAME_ATTEMPTS
+							&& info.constraint_name()
+								== Some(USERNAME_CONSTRAINT) =>
+						{
+							attempt += 1;
+							new_profile.username = random_username(&prefix);
+						},
+						res => break res,
+					}
+				}
+			})
+			.await??;
+
+		let profile = Profile::get(profile.id, conn).await?;
+
+		info!(
+			"created new profile with id {} from claims",
+			profile.primitive.id
+		);
 
 		if let Some(avatar_url) = claims.avatar_url {
 			let avatar = NewImage {
-				file_path:   None,
-				uploaded_by: profile.primitive.id,
-				image_url:   Some(avatar_url.clone()),
+				file_path:    None,
+				uploaded_by:  profile.primitive.id,
+				image_url:    Some(avatar_url.clone()),
+				content_hash: None,
 			};
 
 			avatar.insert_for_profile(profile.primitive.id, conn).await?;
@@ -564,76 +881,84 @@ pub struct ProfileStats {
 	pub total_reservations:      usize,
 	pub completed_reservations:  usize,
 	pub upcoming_reservations:   usize,
+	pub cancelled_reservations:  usize,
+	/// Percentage of reservations that were cancelled
+	pub cancellation_rate:       f64,
 	pub total_reservation_hours: usize,
 }
 
+#[derive(Debug, QueryableByName)]
+#[diesel(check_for_backend(Pg))]
+struct ProfileStatsRow {
+	#[diesel(sql_type = BigInt)]
+	total_reservations:      i64,
+	#[diesel(sql_type = BigInt)]
+	completed_reservations:  i64,
+	#[diesel(sql_type = BigInt)]
+	upcoming_reservations:   i64,
+	#[diesel(sql_type = BigInt)]
+	cancelled_reservations:  i64,
+	#[diesel(sql_type = BigInt)]
+	total_reservation_hours: i64,
+}
+
+/// Aggregate the reservations of a profile around the moment they end
+///
+/// Cancelled reservations are neither completed nor upcoming
+const PROFILE_STATS_QUERY: &str = r"
+SELECT
+	COUNT(*) AS total_reservations,
+	COUNT(*) FILTER (
+		WHERE reservation.state <> 'cancelled'
+			AND opening_time.day + opening_time.end_time <= $3
+	) AS completed_reservations,
+	COUNT(*) FILTER (
+		WHERE reservation.state <> 'cancelled'
+			AND opening_time.day + opening_time.end_time > $3
+	) AS upcoming_reservations,
+	COUNT(*) FILTER (
+		WHERE reservation.state = 'cancelled'
+	) AS cancelled_reservations,
+	(
+		COALESCE(SUM(reservation.block_count), 0) * $2 / 60
+	)::BIGINT AS total_reservation_hours
+FROM reservation
+INNER JOIN opening_time ON opening_time.id = reservation.opening_time_id
+WHERE reservation.profile_id = $1
+";
+
 impl ProfileStats {
 	/// Get reservation statistics for a profile
 	#[instrument(skip(conn))]
-	#[allow(clippy::cast_sign_loss)]
+	#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
 	pub async fn for_profile(
 		profile_id: i32,
 		conn: &DbConn,
 	) -> Result<Self, Error> {
-		let reservation_data = conn
+		let now = Utc::now().naive_utc();
+
+		let row = conn
 			.interact(move |c| {
-				use self::opening_time::dsl as ot_dsl;
-				use self::reservation::dsl as r_dsl;
-
-				r_dsl::reservation
-					.inner_join(
-						ot_dsl::opening_time
-							.on(r_dsl::opening_time_id.eq(ot_dsl::id)),
-					)
-					.filter(r_dsl::profile_id.eq(profile_id))
-					.select((
-						r_dsl::block_count,
-						ot_dsl::day,
-						ot_dsl::end_time,
-						r_dsl::state,
-					))
-					.load::<(
-						i32,
-						chrono::NaiveDate,
-						chrono::NaiveTime,
-						ReservationState,
-					)>(c)
+				diesel::sql_query(PROFILE_STATS_QUERY)
+					.bind::<Integer, _>(profile_id)
+					.bind::<Integer, _>(RESERVATION_BLOCK_SIZE_MINUTES)
+					.bind::<Timestamp, _>(now)
+					.get_result::<ProfileStatsRow>(c)
 			})
 			.await??;
 
-		let now = Utc::now().naive_utc();
-		let mut total_reservations: usize = 0;
-		let mut completed_reservations: usize = 0;
-		let mut upcoming_reservations: usize = 0;
-		let mut total_reservation_hours: usize = 0;
-
-		for data in reservation_data {
-			let (block_count, day, end_time, state) = data;
-
-			// Calculate total hours for this reservation
-			let reservation_minutes =
-				block_count * RESERVATION_BLOCK_SIZE_MINUTES;
-			total_reservation_hours += (reservation_minutes as usize) / 60;
-
-			// Determine if reservation is past or future
-			let reservation_end = day.and_time(end_time);
-
-			if reservation_end > now {
-				if state != ReservationState::Cancelled {
-					upcoming_reservations += 1;
-				}
-			} else {
-				completed_reservations += 1;
-			}
-
-			total_reservations += 1;
-		}
+		#[allow(clippy::cast_precision_loss)]
+		let cancellation_rate = row.cancelled_reservations as f64
+			/ row.total_reservations.max(1) as f64
+			* 100.0;
 
 		let stats = ProfileStats {
-			total_reservations,
-			completed_reservations,
-			upcoming_reservations,
-			total_reservation_hours,
+			total_reservations: row.total_reservations as usize,
+			completed_reservations: row.completed_reservations as usize,
+			upcoming_reservations: row.upcoming_reservations as usize,
+			cancelled_reservations: row.cancelled_reservations as usize,
+			cancellation_rate,
+			total_reservation_hours: row.total_reservation_hours as usize,
 		};
 
 		Ok(stats)
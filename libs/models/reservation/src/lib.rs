@@ -1,12 +1,30 @@
 #[macro_use]
 extern crate tracing;
 
-use base::{BoxedCondition, ToFilter};
-use chrono::NaiveDate;
-use common::{DbConn, Error};
+use std::collections::HashMap;
+use std::ops::Range;
+
+use base::{
+	BoxedCondition,
+	PaginatedData,
+	PaginationConfig,
+	QUERY_HARD_LIMIT,
+	RESERVATION_BLOCK_SIZE_MINUTES,
+	ToFilter,
+	manual_pagination,
+};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use common::{
+	CreateReservationError,
+	DbConn,
+	Error,
+	PaginationError,
+	ReservationTransitionError,
+};
 use db::{
 	ConfirmerAlias,
 	CreatorAlias,
+	ReservationState,
 	confirmer,
 	creator,
 	location,
@@ -14,10 +32,11 @@ use db::{
 	profile,
 	reservation,
 };
-use diesel::dsl::{AliasedFields, Nullable};
+use diesel::dsl::{AliasedFields, Nullable, sql, sum};
+use diesel::expression::SqlLiteral;
 use diesel::pg::Pg;
 use diesel::prelude::*;
-use diesel::sql_types::{Bool, Date};
+use diesel::sql_types::{Bool, Date, Timestamp};
 use primitives::{
 	PrimitiveLocation,
 	PrimitiveOpeningTime,
@@ -25,18 +44,69 @@ use primitives::{
 	PrimitiveReservation,
 };
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Whether a reservation lies in the future or in the past
+#[derive(
+	Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, ToSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum TimeRelation {
+	/// Reservations that have not ended yet, including ones in progress
+	Upcoming,
+	/// Reservations that have ended
+	Past,
+}
 
-#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, IntoParams, Serialize)]
+#[into_params(parameter_in = Query)]
 #[serde(rename_all = "camelCase")]
 pub struct ReservationFilter {
-	pub date:       Option<NaiveDate>,
-	pub in_week_of: Option<NaiveDate>,
+	pub date:            Option<NaiveDate>,
+	pub in_week_of:      Option<NaiveDate>,
+	/// Id of the profile that confirmed the reservations
+	pub confirmer_id:    Option<i32>,
+	pub opening_time_id: Option<i32>,
+	#[param(inline)]
+	pub state:           Option<ReservationState>,
+	#[param(inline)]
+	pub when:            Option<TimeRelation>,
+	/// The first and last day reservations are limited to, set through
+	/// [`ReservationFilter::with_default_window`] rather than by clients
+	#[serde(skip)]
+	pub window:          Option<(NaiveDate, NaiveDate)>,
+}
+
+impl ReservationFilter {
+	/// Limit this filter to the days within `window` of today, unless it is
+	/// already limited by a date, a week or an opening time
+	///
+	/// Keeps listings without any of those from running over every
+	/// reservation ever made
+	#[must_use]
+	pub fn with_default_window(mut self, window: TimeDelta) -> Self {
+		if self.date.is_some()
+			|| self.in_week_of.is_some()
+			|| self.opening_time_id.is_some()
+		{
+			return self;
+		}
+
+		let today = Utc::now().date_naive();
+
+		self.window = Some((today - window, today + window));
+
+		self
+	}
 }
 
 impl<S> ToFilter<S> for ReservationFilter
 where
 	S: 'static,
 	opening_time::day: SelectableExpression<S>,
+	reservation::confirmed_by: SelectableExpression<S>,
+	reservation::opening_time_id: SelectableExpression<S>,
+	reservation::state: SelectableExpression<S>,
 {
 	type SqlType = Bool;
 
@@ -60,17 +130,87 @@ where
 			);
 		}
 
+		if let Some((first_day, last_day)) = self.window {
+			filter = Box::new(
+				filter.and(opening_time::day.between(first_day, last_day)),
+			);
+		}
+
+		if let Some(confirmer_id) = self.confirmer_id {
+			filter = Box::new(filter.and(
+				reservation::confirmed_by.eq(confirmer_id).assume_not_null(),
+			));
+		}
+
+		if let Some(t_id) = self.opening_time_id {
+			filter =
+				Box::new(filter.and(reservation::opening_time_id.eq(t_id)));
+		}
+
+		if let Some(r_state) = self.state {
+			filter = Box::new(filter.and(reservation::state.eq(r_state)));
+		}
+
+		if let Some(when) = self.when {
+			let now = Utc::now().naive_utc();
+
+			filter = match when {
+				TimeRelation::Upcoming => {
+					Box::new(filter.and(end_fragment().gt(now)))
+				},
+				TimeRelation::Past => {
+					Box::new(filter.and(end_fragment().le(now)))
+				},
+			};
+		}
+
 		filter
 	}
 }
 
-#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+/// Whether a single block of an opening time still has a free seat
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockAvailability {
+	pub block_index: i32,
+	pub available:   bool,
+}
+
+/// The availability of every block of an opening time
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OpeningTimeBlocks {
+	pub block_size:   i32,
+	pub total_blocks: i32,
+	pub seat_count:   i32,
+	pub blocks:       Vec<BlockAvailability>,
+}
+
+/// Count the reservations occupying each of the first `total_blocks` blocks
+/// of an opening time
+///
+/// Spans are (base, count) pairs, parts of a span outside of the opening time
+/// are ignored
+#[must_use]
+pub fn block_occupation(total_blocks: usize, spans: &[(i32, i32)]) -> Vec<i32> {
+	let mut occupation = vec![0; total_blocks];
+
+	for &(base, count) in spans {
+		let start = usize::try_from(base).unwrap_or(0);
+		let end = usize::try_from(base.saturating_add(count)).unwrap_or(0);
+
+		for block in occupation.iter_mut().take(end).skip(start) {
+			*block += 1;
+		}
+	}
+
+	occupation
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, IntoParams, Serialize)]
 #[allow(clippy::struct_excessive_bools)]
-#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+#[serde(default, rename_all = "camelCase")]
 pub struct ReservationIncludes {
-	#[serde(default)]
 	pub profile:      bool,
-	#[serde(default)]
 	pub confirmed_by: bool,
 }
 
@@ -105,6 +245,26 @@ fn confirmed_by_fragment() -> confirmed_by_fragment {
 	confirmer.fields(profile::all_columns).nullable()
 }
 
+/// The moment a reservation starts, computed from the day and start time of
+/// its opening time and its base block index
+fn start_fragment() -> SqlLiteral<Timestamp> {
+	sql::<Timestamp>(&format!(
+		"(opening_time.day + opening_time.start_time + \
+		 reservation.base_block_index * INTERVAL \
+		 '{RESERVATION_BLOCK_SIZE_MINUTES} minutes')"
+	))
+}
+
+/// The moment a reservation ends, computed from the day and start time of
+/// its opening time and the end of its block range
+fn end_fragment() -> SqlLiteral<Timestamp> {
+	sql::<Timestamp>(&format!(
+		"(opening_time.day + opening_time.start_time + \
+		 (reservation.base_block_index + reservation.block_count) * INTERVAL \
+		 '{RESERVATION_BLOCK_SIZE_MINUTES} minutes')"
+	))
+}
+
 impl Reservation {
 	/// Build a query with all required (dynamic) joins to select a full
 	/// reservation data tuple
@@ -157,28 +317,114 @@ impl Reservation {
 		Ok(reservation)
 	}
 
-	/// Get all the reservations for a specific [`Location`](crate::Location)
+	/// Get a page of the reservations for a specific
+	/// [`Location`](crate::Location), ordered by their opening time
 	#[instrument(skip(conn))]
-	pub async fn for_location(
+	pub async fn for_location_paginated(
 		loc_id: i32,
 		filter: ReservationFilter,
 		includes: ReservationIncludes,
+		p_cfg: PaginationConfig,
 		conn: &DbConn,
-	) -> Result<Vec<Self>, Error> {
+	) -> Result<PaginatedData<Vec<Self>>, Error> {
+		let count_filter = filter.to_filter();
 		let filter = filter.to_filter();
+		let count_query = Self::query(includes);
 		let query = Self::query(includes);
 
-		let reservations = conn
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.offset as i64;
+
+		let (total, reservations) = conn
 			.interact(move |conn| {
-				query
+				let total = count_query
+					.filter(location::id.eq(loc_id))
+					.filter(count_filter)
+					.count()
+					.get_result::<i64>(conn)?;
+
+				let reservations = query
 					.filter(location::id.eq(loc_id))
 					.filter(filter)
+					.order((
+						opening_time::day.asc(),
+						opening_time::start_time.asc(),
+						reservation::id.asc(),
+					))
 					.select(Self::as_select())
-					.get_results(conn)
+					.limit(limit)
+					.offset(offset)
+					.get_results(conn)?;
+
+				Ok::<_, Error>((total, reservations))
 			})
 			.await??;
 
-		Ok(reservations)
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		if total > 0 && p_cfg.offset >= total {
+			return Err(PaginationError::OffsetTooLarge.into());
+		}
+
+		Ok((total, false, reservations))
+	}
+
+	/// Get a page of the reservations for all the locations of a specific
+	/// [`Authority`](crate::Authority), ordered by their opening time
+	#[instrument(skip(conn))]
+	pub async fn get_for_authority(
+		auth_id: i32,
+		filter: ReservationFilter,
+		includes: ReservationIncludes,
+		p_cfg: PaginationConfig,
+		conn: &DbConn,
+	) -> Result<PaginatedData<Vec<Self>>, Error> {
+		let count_filter = filter.to_filter();
+		let filter = filter.to_filter();
+		let count_query = Self::query(includes);
+		let query = Self::query(includes);
+
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.offset as i64;
+
+		let (total, reservations) = conn
+			.interact(move |conn| {
+				let total = count_query
+					.filter(location::authority_id.eq(auth_id))
+					.filter(count_filter)
+					.count()
+					.get_result::<i64>(conn)?;
+
+				let reservations = query
+					.filter(location::authority_id.eq(auth_id))
+					.filter(filter)
+					.order((
+						opening_time::day.asc(),
+						opening_time::start_time.asc(),
+						reservation::id.asc(),
+					))
+					.select(Self::as_select())
+					.limit(limit)
+					.offset(offset)
+					.get_results(conn)?;
+
+				Ok::<_, Error>((total, reservations))
+			})
+			.await??;
+
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		if total > 0 && p_cfg.offset >= total {
+			return Err(PaginationError::OffsetTooLarge.into());
+		}
+
+		Ok((total, false, reservations))
 	}
 
 	/// Get all the reservations for a specific
@@ -189,45 +435,160 @@ impl Reservation {
 		includes: ReservationIncludes,
 		conn: &DbConn,
 	) -> Result<Vec<Self>, Error> {
+		let filter = ReservationFilter {
+			opening_time_id: Some(t_id),
+			..Default::default()
+		}
+		.to_filter();
+		let query = Self::query(includes);
+
+		let reservations = conn
+			.interact(move |conn| {
+				query.filter(filter).select(Self::as_select()).get_results(conn)
+			})
+			.await??;
+
+		Ok(reservations)
+	}
+
+	/// Get all [`Reservation`]s matching a given [`ReservationFilter`]
+	#[instrument(skip(conn))]
+	pub async fn get_all_paginated(
+		filter: ReservationFilter,
+		includes: ReservationIncludes,
+		p_cfg: PaginationConfig,
+		conn: &DbConn,
+	) -> Result<PaginatedData<Vec<Self>>, Error> {
+		let filter = filter.to_filter();
 		let query = Self::query(includes);
 
 		let reservations = conn
 			.interact(move |conn| {
 				query
-					.filter(opening_time::id.eq(t_id))
+					.filter(filter)
+					.order(reservation::id)
 					.select(Self::as_select())
+					.limit(QUERY_HARD_LIMIT)
 					.get_results(conn)
 			})
 			.await??;
 
-		Ok(reservations)
+		manual_pagination(reservations, p_cfg)
 	}
 
-	/// Get all the reservations for a specific [`Profile`](crate::Profile)
+	/// Get a page of the reservations for a specific
+	/// [`Profile`](crate::Profile), ordered by their opening time
 	#[instrument(skip(conn))]
 	pub async fn for_profile(
 		p_id: i32,
 		filter: ReservationFilter,
 		includes: ReservationIncludes,
+		p_cfg: PaginationConfig,
 		conn: &DbConn,
-	) -> Result<Vec<Self>, Error> {
+	) -> Result<PaginatedData<Vec<Self>>, Error> {
+		let count_filter = filter.to_filter();
 		let filter = filter.to_filter();
+		let count_query = Self::query(includes);
 		let query = Self::query(includes);
 
-		let reservations = conn
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.offset as i64;
+
+		let (total, reservations) = conn
 			.interact(move |conn| {
-				query
+				let total = count_query
+					.filter(reservation::profile_id.eq(p_id))
+					.filter(count_filter)
+					.count()
+					.get_result::<i64>(conn)?;
+
+				let reservations = query
 					.filter(reservation::profile_id.eq(p_id))
 					.filter(filter)
+					.order((
+						opening_time::day.asc(),
+						opening_time::start_time.asc(),
+						reservation::id.asc(),
+					))
 					.select(Self::as_select())
-					.get_results(conn)
+					.limit(limit)
+					.offset(offset)
+					.get_results(conn)?;
+
+				Ok::<_, Error>((total, reservations))
 			})
 			.await??;
 
-		Ok(reservations)
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		if total > 0 && p_cfg.offset >= total {
+			return Err(PaginationError::OffsetTooLarge.into());
+		}
+
+		Ok((total, false, reservations))
+	}
+
+	/// Get a page of the reservations confirmed by a specific
+	/// [`Profile`](crate::Profile), most recently confirmed first
+	#[instrument(skip(conn))]
+	pub async fn get_by_confirmed_by(
+		confirmer_id: i32,
+		filter: ReservationFilter,
+		includes: ReservationIncludes,
+		p_cfg: PaginationConfig,
+		conn: &DbConn,
+	) -> Result<PaginatedData<Vec<Self>>, Error> {
+		let filter =
+			ReservationFilter { confirmer_id: Some(confirmer_id), ..filter };
+
+		let count_filter = filter.to_filter();
+		let filter = filter.to_filter();
+		let count_query = Self::query(includes);
+		let query = Self::query(includes);
+
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.offset as i64;
+
+		let (total, reservations) = conn
+			.interact(move |conn| {
+				let total = count_query
+					.filter(count_filter)
+					.count()
+					.get_result::<i64>(conn)?;
+
+				let reservations = query
+					.filter(filter)
+					.order((
+						reservation::confirmed_at.desc(),
+						reservation::id.desc(),
+					))
+					.select(Self::as_select())
+					.limit(limit)
+					.offset(offset)
+					.get_results(conn)?;
+
+				Ok::<_, Error>((total, reservations))
+			})
+			.await??;
+
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		if total > 0 && p_cfg.offset >= total {
+			return Err(PaginationError::OffsetTooLarge.into());
+		}
+
+		Ok((total, false, reservations))
 	}
 
 	/// Get all the block (base, count) pairs a given opening time
+	///
+	/// Cancelled reservations are left out
 	#[instrument(skip(conn))]
 	pub async fn get_spans_for_opening_time(
 		t_id: i32,
@@ -242,6 +603,7 @@ impl Reservation {
 						reservation.on(opening_time_id.eq(opening_time::id)),
 					)
 					.filter(opening_time::id.eq(t_id))
+					.filter(state.ne(ReservationState::Cancelled))
 					.select((base_block_index, block_count))
 					.get_results(conn)
 			})
@@ -250,6 +612,644 @@ impl Reservation {
 		Ok(pairs)
 	}
 
+	/// Get the [`OpeningTimeBlocks`] of an opening time
+	///
+	/// The seat count of the opening time takes precedence over the one of
+	/// its location
+	#[instrument(skip(conn))]
+	pub async fn get_blocks_for_opening_time(
+		t_id: i32,
+		conn: &DbConn,
+	) -> Result<OpeningTimeBlocks, Error> {
+		let (time, loc_seats) = conn
+			.interact(move |conn| {
+				opening_time::table
+					.inner_join(
+						location::table
+							.on(opening_time::location_id.eq(location::id)),
+					)
+					.filter(opening_time::id.eq(t_id))
+					.select((
+						PrimitiveOpeningTime::as_select(),
+						location::seat_count,
+					))
+					.get_result::<(PrimitiveOpeningTime, i32)>(conn)
+			})
+			.await??;
+
+		let spans = Self::get_spans_for_opening_time(t_id, conn).await?;
+
+		let total_blocks =
+			i32::try_from(time.total_blocks(RESERVATION_BLOCK_SIZE_MINUTES))
+				.unwrap_or(0);
+		let seat_count = time.seat_count.unwrap_or(loc_seats);
+
+		let occupation = block_occupation(
+			usize::try_from(total_blocks).unwrap_or(0),
+			&spans,
+		);
+
+		let blocks = occupation
+			.into_iter()
+			.zip(0..)
+			.map(|(occupied, block_index)| {
+				BlockAvailability {
+					block_index,
+					available: occupied < seat_count,
+				}
+			})
+			.collect();
+
+		Ok(OpeningTimeBlocks {
+			block_size: RESERVATION_BLOCK_SIZE_MINUTES,
+			total_blocks,
+			seat_count,
+			blocks,
+		})
+	}
+
+	/// Get the total amount of blocks reserved for each of the given opening
+	/// times
+	///
+	/// Cancelled reservations are not counted, opening times without any
+	/// reservations are left out
+	#[instrument(skip(conn))]
+	pub async fn get_total_blocks_for_opening_times(
+		t_ids: Vec<i32>,
+		conn: &DbConn,
+	) -> Result<HashMap<i32, i64>, Error> {
+		let totals = conn
+			.interact(move |conn| {
+				use self::reservation::dsl::*;
+
+				reservation
+					.filter(opening_time_id.eq_any(t_ids))
+					.filter(state.ne(ReservationState::Cancelled))
+					.group_by(opening_time_id)
+					.select((opening_time_id, sum(block_count)))
+					.get_results::<(i32, Option<i64>)>(conn)
+			})
+			.await??;
+
+		let totals = totals
+			.into_iter()
+			.map(|(t_id, total)| (t_id, total.unwrap_or(0)))
+			.collect();
+
+		Ok(totals)
+	}
+
+	/// Shift the blocks of all reservations for an opening time whose start
+	/// time moves from `old_start` to `new_start`
+	///
+	/// Block indices are relative to the start time, so shifting them keeps
+	/// every active reservation at the same time of day. Cancelled
+	/// reservations no longer take up any blocks and are left untouched.
+	/// Fails if the start time moves by a partial block while there are
+	/// active reservations, or if one of them would no longer fit between
+	/// `new_start` and `new_end`
+	///
+	/// This should always be called from inside a transaction
+	pub fn recalculate_for_opening_time(
+		t_id: i32,
+		old_start: NaiveTime,
+		new_start: NaiveTime,
+		new_end: NaiveTime,
+		conn: &mut PgConnection,
+	) -> Result<(), Error> {
+		let active: i64 = reservation::table
+			.filter(reservation::opening_time_id.eq(t_id))
+			.filter(reservation::state.ne(ReservationState::Cancelled))
+			.count()
+			.get_result(conn)?;
+
+		if active == 0 {
+			return Ok(());
+		}
+
+		let block_size = i64::from(RESERVATION_BLOCK_SIZE_MINUTES);
+		let shift = (old_start - new_start).num_minutes();
+
+		if shift % block_size != 0 {
+			return Err(Error::Conflict(format!(
+				"start time can only move by multiples of {block_size} \
+				 minutes while it has reservations"
+			)));
+		}
+
+		#[allow(clippy::cast_possible_truncation)]
+		let delta = (shift / block_size) as i32;
+		#[allow(clippy::cast_possible_truncation)]
+		let total_blocks =
+			((new_end - new_start).num_minutes() / block_size) as i32;
+
+		let starts_before = (reservation::base_block_index + delta).lt(0);
+		let ends_after =
+			(reservation::base_block_index + reservation::block_count + delta)
+				.gt(total_blocks);
+
+		let out_of_bounds: i64 = reservation::table
+			.filter(reservation::opening_time_id.eq(t_id))
+			.filter(reservation::state.ne(ReservationState::Cancelled))
+			.filter(starts_before.or(ends_after))
+			.count()
+			.get_result(conn)?;
+
+		if out_of_bounds > 0 {
+			return Err(Error::Conflict(format!(
+				"{out_of_bounds} reservation(s) would fall outside of the \
+				 updated opening time"
+			)));
+		}
+
+		if delta != 0 {
+			diesel::update(
+				reservation::table
+					.filter(reservation::opening_time_id.eq(t_id))
+					.filter(reservation::state.ne(ReservationState::Cancelled)),
+			)
+			.set(
+				reservation::base_block_index
+					.eq(reservation::base_block_index + delta),
+			)
+			.execute(conn)?;
+
+			info!("shifted reservations for opening time {t_id} by {delta}");
+		}
+
+		Ok(())
+	}
+
+	/// Cancel all active reservations for an opening time that would no
+	/// longer fit once it runs from `new_start` to `new_end` instead of
+	/// starting at `old_start`
+	///
+	/// Reservations that lie entirely within the new bounds are kept. The
+	/// cancelled reservations are returned with their blocks still relative
+	/// to `old_start`
+	///
+	/// This should always be called from inside a transaction, before
+	/// [`Reservation::recalculate_for_opening_time`]
+	pub fn cancel_outside_of_opening_time(
+		t_id: i32,
+		old_start: NaiveTime,
+		new_start: NaiveTime,
+		new_end: NaiveTime,
+		conn: &mut PgConnection,
+	) -> Result<Vec<PrimitiveReservation>, Error> {
+		// Bounds are compared in minutes since the old start, so they do not
+		// have to line up with the blocks
+		#[allow(clippy::cast_possible_truncation)]
+		let lower = (new_start - old_start).num_minutes() as i32;
+		#[allow(clippy::cast_possible_truncation)]
+		let upper = (new_end - old_start).num_minutes() as i32;
+
+		let starts_before = (reservation::base_block_index
+			* RESERVATION_BLOCK_SIZE_MINUTES)
+			.lt(lower);
+		let ends_after = ((reservation::base_block_index
+			+ reservation::block_count)
+			* RESERVATION_BLOCK_SIZE_MINUTES)
+			.gt(upper);
+
+		let cancelled = diesel::update(
+			reservation::table
+				.filter(reservation::opening_time_id.eq(t_id))
+				.filter(reservation::state.eq(ReservationState::Created))
+				.filter(starts_before.or(ends_after)),
+		)
+		.set(reservation::state.eq(ReservationState::Cancelled))
+		.returning(PrimitiveReservation::as_returning())
+		.get_results(conn)?;
+
+		if !cancelled.is_empty() {
+			info!(
+				"cancelled {} reservations outside of opening time {t_id}",
+				cancelled.len()
+			);
+		}
+
+		Ok(cancelled)
+	}
+
+	/// Get all active reservations for an opening time and lock them until
+	/// the end of the transaction
+	///
+	/// This should always be called from inside a transaction
+	pub fn lock_active_for_opening_time(
+		t_id: i32,
+		conn: &mut PgConnection,
+	) -> QueryResult<Vec<PrimitiveReservation>> {
+		reservation::table
+			.filter(reservation::opening_time_id.eq(t_id))
+			.filter(reservation::state.eq(ReservationState::Created))
+			.select(PrimitiveReservation::as_select())
+			.for_update()
+			.get_results(conn)
+	}
+
+	/// Get all blocks in a given range of an opening time that have no seats
+	/// left, optionally ignoring a single reservation
+	///
+	/// Cancelled reservations do not take up any seats
+	pub fn get_full_blocks(
+		t_id: i32,
+		blocks: Range<i32>,
+		seats: i32,
+		exclude_id: Option<i32>,
+		conn: &mut PgConnection,
+	) -> QueryResult<Vec<i32>> {
+		let mut query = reservation::table
+			.filter(reservation::opening_time_id.eq(t_id))
+			.filter(reservation::state.ne(ReservationState::Cancelled))
+			.select((reservation::base_block_index, reservation::block_count))
+			.into_boxed();
+
+		if let Some(exclude_id) = exclude_id {
+			query = query.filter(reservation::id.ne(exclude_id));
+		}
+
+		let spans: Vec<(i32, i32)> = query.get_results(conn)?;
+		let seats = usize::try_from(seats).unwrap_or(0);
+
+		let full = blocks
+			.filter(|block| {
+				let occupation = spans
+					.iter()
+					.filter(|(base, count)| {
+						*base <= *block && *block < base + count
+					})
+					.count();
+
+				occupation >= seats
+			})
+			.collect();
+
+		Ok(full)
+	}
+
+	/// Check that the given blocks of an opening time can be reserved
+	///
+	/// The opening time has to be reservable right now, the profile, if any,
+	/// can not already hold any of the blocks and every block needs a free
+	/// seat. `exclude_id` leaves out a reservation that is being changed
+	///
+	/// This is the single definition of availability, location availability
+	/// checks call it without a profile. Reservations should always call it
+	/// from inside a transaction that locks the opening time
+	pub fn validate_blocks(
+		time: &PrimitiveOpeningTime,
+		seats: i32,
+		profile_id: Option<i32>,
+		blocks: Range<i32>,
+		exclude_id: Option<i32>,
+		conn: &mut PgConnection,
+	) -> Result<(), Error> {
+		NewReservation::validate_reservable(time, Utc::now().naive_utc())?;
+
+		// A profile can't be in two places at once
+		if let Some(profile_id) = profile_id {
+			let mut overlapping = reservation::table
+				.filter(reservation::opening_time_id.eq(time.id))
+				.filter(reservation::profile_id.eq(profile_id))
+				.filter(reservation::state.ne(ReservationState::Cancelled))
+				.filter(reservation::base_block_index.lt(blocks.end))
+				.filter(
+					(reservation::base_block_index + reservation::block_count)
+						.gt(blocks.start),
+				)
+				.select(reservation::id)
+				.into_boxed();
+
+			if let Some(exclude_id) = exclude_id {
+				overlapping =
+					overlapping.filter(reservation::id.ne(exclude_id));
+			}
+
+			if let Some(r_id) = overlapping.first::<i32>(conn).optional()? {
+				return Err(CreateReservationError::Overlap(r_id).into());
+			}
+		}
+
+		// Make sure every reserved block still has a free seat
+		let full =
+			Self::get_full_blocks(time.id, blocks, seats, exclude_id, conn)?;
+
+		if !full.is_empty() {
+			return Err(CreateReservationError::Full(full).into());
+		}
+
+		Ok(())
+	}
+
+	/// Extend a [`Reservation`] by a given amount of blocks
+	///
+	/// Only the profile that made the reservation can extend it, and only as
+	/// long as it has not been confirmed or cancelled
+	#[instrument(skip(conn))]
+	pub async fn extend(
+		r_id: i32,
+		additional_blocks: i32,
+		requesting_profile_id: i32,
+		includes: ReservationIncludes,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		conn.interact(move |conn| {
+			conn.transaction::<_, Error, _>(|conn| {
+				let (resv, time, loc) =
+					reservation::table
+						.inner_join(opening_time::table.on(
+							reservation::opening_time_id.eq(opening_time::id),
+						))
+						.inner_join(
+							location::table
+								.on(opening_time::location_id.eq(location::id)),
+						)
+						.filter(reservation::id.eq(r_id))
+						.select((
+							PrimitiveReservation::as_select(),
+							PrimitiveOpeningTime::as_select(),
+							PrimitiveLocation::as_select(),
+						))
+						.for_update()
+						.get_result::<(
+							PrimitiveReservation,
+							PrimitiveOpeningTime,
+							PrimitiveLocation,
+						)>(conn)?;
+
+				if resv.profile_id != requesting_profile_id {
+					return Err(Error::Forbidden);
+				}
+
+				if resv.state != ReservationState::Created
+					|| resv.confirmed_by.is_some()
+				{
+					return Err(CreateReservationError::NotExtendable.into());
+				}
+
+				if additional_blocks < 1 {
+					return Err(
+						CreateReservationError::ReservationTooShort(1).into()
+					);
+				}
+
+				let new_count = resv.block_count + additional_blocks;
+
+				let block_size = i64::from(RESERVATION_BLOCK_SIZE_MINUTES);
+				#[allow(clippy::cast_possible_truncation)]
+				let num_blocks = ((time.end_time - time.start_time)
+					.num_minutes() / block_size) as i32;
+
+				if resv.base_block_index + new_count > num_blocks {
+					return Err(CreateReservationError::OutOfBounds {
+						start: time.start_time,
+						end:   time.end_time,
+					}
+					.into());
+				}
+
+				let seats = time.seat_count.unwrap_or(loc.seat_count);
+				let first_new = resv.base_block_index + resv.block_count;
+				let last_new = resv.base_block_index + new_count;
+
+				Self::validate_blocks(
+					&time,
+					seats,
+					Some(resv.profile_id),
+					first_new..last_new,
+					Some(r_id),
+					conn,
+				)?;
+
+				// The maximum reservation length is expressed in minutes
+				if let Some(max) = loc.max_reservation_length
+					&& new_count * RESERVATION_BLOCK_SIZE_MINUTES > max
+				{
+					return Err(CreateReservationError::ReservationTooLong(
+						max,
+					)
+					.into());
+				}
+
+				diesel::update(reservation::table.find(r_id))
+					.set(reservation::block_count.eq(new_count))
+					.execute(conn)?;
+
+				Ok(())
+			})
+		})
+		.await??;
+
+		let reservation = Self::get_by_id(r_id, includes, conn).await?;
+
+		info!("extended reservation {r_id} by {additional_blocks} blocks");
+
+		Ok(reservation)
+	}
+
+	/// Cancel a [`Reservation`], freeing up its seats
+	///
+	/// Reservations can only be cancelled before they start
+	#[instrument(skip(conn))]
+	pub async fn cancel(
+		r_id: i32,
+		includes: ReservationIncludes,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		conn.interact(move |conn| {
+			Self::transition(r_id, ReservationState::Cancelled, None, conn)
+		})
+		.await??;
+
+		let reservation = Self::get_by_id(r_id, includes, conn).await?;
+
+		info!("cancelled reservation {r_id}");
+
+		Ok(reservation)
+	}
+
+	/// Confirm that the profile of a [`Reservation`] showed up
+	#[instrument(skip(conn))]
+	pub async fn confirm(
+		r_id: i32,
+		confirmed_by: i32,
+		includes: ReservationIncludes,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		conn.interact(move |conn| {
+			Self::transition(
+				r_id,
+				ReservationState::Present,
+				Some(confirmed_by),
+				conn,
+			)
+		})
+		.await??;
+
+		let reservation = Self::get_by_id(r_id, includes, conn).await?;
+
+		info!("confirmed reservation {r_id} by profile {confirmed_by}");
+
+		Ok(reservation)
+	}
+
+	/// Record that the profile of a [`Reservation`] did not show up
+	#[instrument(skip(conn))]
+	pub async fn mark_absent(
+		r_id: i32,
+		confirmed_by: i32,
+		includes: ReservationIncludes,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		conn.interact(move |conn| {
+			Self::transition(
+				r_id,
+				ReservationState::Absent,
+				Some(confirmed_by),
+				conn,
+			)
+		})
+		.await??;
+
+		let reservation = Self::get_by_id(r_id, includes, conn).await?;
+
+		info!("marked reservation {r_id} absent by profile {confirmed_by}");
+
+		Ok(reservation)
+	}
+
+	/// Move a reservation from the created state to a new state
+	///
+	/// Cancelled, present and absent are all final states. The profile
+	/// recording a presence or absence is stored as the confirmer
+	fn transition(
+		r_id: i32,
+		new_state: ReservationState,
+		confirmer_id: Option<i32>,
+		conn: &mut PgConnection,
+	) -> Result<(), Error> {
+		conn.transaction::<_, Error, _>(|conn| {
+			let (resv, time) = reservation::table
+				.inner_join(
+					opening_time::table
+						.on(reservation::opening_time_id.eq(opening_time::id)),
+				)
+				.filter(reservation::id.eq(r_id))
+				.select((
+					PrimitiveReservation::as_select(),
+					PrimitiveOpeningTime::as_select(),
+				))
+				.for_update()
+				.get_result::<(PrimitiveReservation, PrimitiveOpeningTime)>(
+					conn,
+				)?;
+
+			match resv.state {
+				ReservationState::Created => {},
+				ReservationState::Cancelled => {
+					return Err(
+						ReservationTransitionError::AlreadyCancelled.into()
+					);
+				},
+				ReservationState::Absent | ReservationState::Present => {
+					return Err(
+						ReservationTransitionError::AlreadyConfirmed.into()
+					);
+				},
+			}
+
+			let offset = resv.base_block_index * RESERVATION_BLOCK_SIZE_MINUTES;
+			let start = time.day.and_time(time.start_time)
+				+ TimeDelta::minutes(i64::from(offset));
+			let now = Utc::now().naive_utc();
+
+			if new_state == ReservationState::Cancelled && start <= now {
+				return Err(
+					ReservationTransitionError::AlreadyStarted(start).into()
+				);
+			}
+
+			diesel::update(reservation::table.find(r_id))
+				.set((
+					reservation::state.eq(new_state),
+					reservation::confirmed_at.eq(confirmer_id.map(|_| now)),
+					reservation::confirmed_by.eq(confirmer_id),
+				))
+				.execute(conn)?;
+
+			Ok(())
+		})
+	}
+
+	/// Get the moments this reservation starts and ends
+	#[must_use]
+	pub fn time_span(&self) -> (NaiveDateTime, NaiveDateTime) {
+		let block_size = i64::from(RESERVATION_BLOCK_SIZE_MINUTES);
+		let offset = i64::from(self.primitive.base_block_index) * block_size;
+		let length = i64::from(self.primitive.block_count) * block_size;
+
+		let start =
+			self.opening_time.day.and_time(self.opening_time.start_time)
+				+ TimeDelta::minutes(offset);
+		let end = start + TimeDelta::minutes(length);
+
+		(start, end)
+	}
+
+	/// Get all active reservations starting in `[from, until)` that have not
+	/// been sent a reminder yet, ordered by their start
+	#[instrument(skip(conn))]
+	pub async fn get_unreminded_starting_between(
+		from: NaiveDateTime,
+		until: NaiveDateTime,
+		conn: &DbConn,
+	) -> Result<Vec<Self>, Error> {
+		let query = Self::query(ReservationIncludes::default());
+
+		let reservations = conn
+			.interact(move |conn| {
+				query
+					.filter(reservation::state.eq(ReservationState::Created))
+					.filter(reservation::reminded_at.is_null())
+					.filter(start_fragment().ge(from))
+					.filter(start_fragment().lt(until))
+					.order((start_fragment().asc(), reservation::id.asc()))
+					.select(Self::as_select())
+					.get_results(conn)
+			})
+			.await??;
+
+		Ok(reservations)
+	}
+
+	/// Mark a [`Reservation`] as reminded, returning `false` if it already
+	/// was
+	///
+	/// Only the first call for a reservation succeeds, so a reminder should
+	/// only be sent after marking it
+	#[instrument(skip(conn))]
+	pub async fn mark_reminded(
+		r_id: i32,
+		conn: &DbConn,
+	) -> Result<bool, Error> {
+		let now = Utc::now().naive_utc();
+
+		let updated = conn
+			.interact(move |conn| {
+				use self::reservation::dsl::*;
+
+				diesel::update(
+					reservation.find(r_id).filter(reminded_at.is_null()),
+				)
+				.set(reminded_at.eq(now))
+				.execute(conn)
+			})
+			.await??;
+
+		Ok(updated > 0)
+	}
+
 	/// Delete a [`Reservation`] given its id
 	#[instrument(skip(conn))]
 	pub async fn delete_by_id(r_id: i32, conn: &DbConn) -> Result<(), Error> {
@@ -277,6 +1277,82 @@ pub struct NewReservation {
 }
 
 impl NewReservation {
+	/// Check that the reserved blocks fall within the blocks of the given
+	/// opening time, i.e. the first block is not negative and the last block
+	/// does not lie past the end of the opening time
+	pub fn validate_block_range(
+		&self,
+		opening_time: &PrimitiveOpeningTime,
+		block_size: i32,
+	) -> Result<(), Error> {
+		#[allow(clippy::cast_possible_truncation)]
+		let total_blocks = ((opening_time.end_time - opening_time.start_time)
+			.num_minutes()
+			/ i64::from(block_size)) as i32;
+
+		if self.base_block_index < 0
+			|| self.base_block_index + self.block_count > total_blocks
+		{
+			return Err(CreateReservationError::InvalidBlockRange {
+				min: 0,
+				max: total_blocks,
+			}
+			.into());
+		}
+
+		Ok(())
+	}
+
+	/// Check that the given opening time can still be reserved at `now`
+	///
+	/// Missing bounds of the reservable window are treated as unbounded, and
+	/// opening times that already ended can never be reserved
+	pub fn validate_reservable(
+		opening_time: &PrimitiveOpeningTime,
+		now: NaiveDateTime,
+	) -> Result<(), Error> {
+		if let Some(from) = opening_time.reservable_from
+			&& now < from
+		{
+			return Err(CreateReservationError::NotReservableYet(from).into());
+		}
+
+		if let Some(until) = opening_time.reservable_until
+			&& now > until
+		{
+			return Err(
+				CreateReservationError::NotReservableAnymore(until).into()
+			);
+		}
+
+		let end = opening_time.day.and_time(opening_time.end_time);
+
+		if now >= end {
+			return Err(CreateReservationError::OpeningTimeEnded(end).into());
+		}
+
+		Ok(())
+	}
+
+	/// Check that the reserved amount of time does not exceed the maximum
+	/// reservation length of a location, which is expressed in minutes
+	pub fn validate_length(
+		&self,
+		max_length: Option<i32>,
+	) -> Result<(), Error> {
+		if self.block_count < 1 {
+			return Err(CreateReservationError::ReservationTooShort(1).into());
+		}
+
+		if let Some(max) = max_length
+			&& self.block_count * RESERVATION_BLOCK_SIZE_MINUTES > max
+		{
+			return Err(CreateReservationError::ReservationTooLong(max).into());
+		}
+
+		Ok(())
+	}
+
 	/// Insert this [`NewReservation`]
 	#[instrument(skip(conn))]
 	pub async fn insert(
@@ -285,13 +1361,57 @@ impl NewReservation {
 		conn: &DbConn,
 	) -> Result<Reservation, Error> {
 		let reservation = conn
-			.interact(|conn| {
-				use self::reservation::dsl::*;
+			.interact(move |conn| {
+				conn.transaction::<_, Error, _>(|conn| {
+					// Lock the opening time so concurrent reservations can't
+					// both claim the last seat of a block
+					opening_time::table
+						.find(self.opening_time_id)
+						.select(opening_time::id)
+						.for_update()
+						.execute(conn)?;
 
-				diesel::insert_into(reservation)
-					.values(self)
-					.returning(PrimitiveReservation::as_returning())
-					.get_result(conn)
+					let (time, loc_seats, max_length) = opening_time::table
+						.inner_join(
+							location::table
+								.on(opening_time::location_id.eq(location::id)),
+						)
+						.filter(opening_time::id.eq(self.opening_time_id))
+						.select((
+							PrimitiveOpeningTime::as_select(),
+							location::seat_count,
+							location::max_reservation_length,
+						))
+						.get_result::<(PrimitiveOpeningTime, i32, Option<i32>)>(
+							conn,
+						)?;
+
+					self.validate_block_range(
+						&time,
+						RESERVATION_BLOCK_SIZE_MINUTES,
+					)?;
+
+					let first = self.base_block_index;
+					let last = self.base_block_index + self.block_count;
+
+					Reservation::validate_blocks(
+						&time,
+						time.seat_count.unwrap_or(loc_seats),
+						Some(self.profile_id),
+						first..last,
+						None,
+						conn,
+					)?;
+
+					self.validate_length(max_length)?;
+
+					let reservation = diesel::insert_into(reservation::table)
+						.values(self)
+						.returning(PrimitiveReservation::as_returning())
+						.get_result(conn)?;
+
+					Ok(reservation)
+				})
 			})
 			.await??;
 
@@ -0,0 +1,102 @@
+use common::{DbConn, Error};
+use db::{profile, review_flag};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::result::DatabaseErrorKind;
+use primitives::{PrimitiveProfile, PrimitiveReviewFlag};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Queryable, Selectable, Serialize)]
+#[diesel(check_for_backend(Pg))]
+pub struct ReviewFlag {
+	#[diesel(embed)]
+	pub primitive:  PrimitiveReviewFlag,
+	#[diesel(embed)]
+	pub flagged_by: PrimitiveProfile,
+}
+
+impl ReviewFlag {
+	/// Build a query with all required joins to select a full review flag
+	/// data tuple
+	#[diesel::dsl::auto_type(no_type_alias)]
+	fn query() -> _ {
+		review_flag::table.inner_join(
+			profile::table.on(profile::id.eq(review_flag::flagged_by)),
+		)
+	}
+
+	/// Flag the [`Review`](crate::Review) with the given id
+	///
+	/// A profile can only flag a single review once, flagging it a second
+	/// time results in an [`Error::Duplicate`]
+	#[instrument(skip(conn))]
+	pub async fn create(
+		r_id: i32,
+		p_id: i32,
+		flag_reason: String,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		let flag = conn
+			.interact(move |conn| {
+				conn.transaction::<_, Error, _>(|conn| {
+					use self::review_flag::dsl::*;
+
+					let f_id: i32 = diesel::insert_into(review_flag)
+						.values((
+							review_id.eq(r_id),
+							flagged_by.eq(p_id),
+							reason.eq(flag_reason),
+						))
+						.returning(id)
+						.get_result(conn)
+						.map_err(|e| {
+							match e {
+								diesel::result::Error::DatabaseError(
+									DatabaseErrorKind::UniqueViolation,
+									_,
+								) => {
+									Error::Duplicate {
+										message:     "review has already been \
+										              flagged by this profile"
+											.to_string(),
+										resource_id: Some(r_id),
+									}
+								},
+								e => e.into(),
+							}
+						})?;
+
+					let flag = Self::query()
+						.filter(id.eq(f_id))
+						.select(Self::as_select())
+						.get_result(conn)?;
+
+					Ok(flag)
+				})
+			})
+			.await??;
+
+		info!("profile {p_id} flagged review {r_id}");
+
+		Ok(flag)
+	}
+
+	/// Get all [`ReviewFlag`]s for the review with the given id
+	#[instrument(skip(conn))]
+	pub async fn get_for_review(
+		r_id: i32,
+		conn: &DbConn,
+	) -> Result<Vec<Self>, Error> {
+		let flags = conn
+			.interact(move |conn| {
+				Self::query()
+					.filter(review_flag::review_id.eq(r_id))
+					.order(review_flag::created_at.desc())
+					.select(Self::as_select())
+					.get_results(conn)
+			})
+			.await??;
+
+		Ok(flags)
+	}
+}
@@ -7,17 +7,34 @@ use base::{
 	PaginatedData,
 	PaginationConfig,
 	QUERY_HARD_LIMIT,
+	RESERVATION_BLOCK_SIZE_MINUTES,
 	manual_pagination,
 };
+use chrono::{NaiveDate, NaiveTime, TimeDelta, Utc};
 use common::{DbConn, Error};
-use db::{location, profile, review};
+use db::{
+	ReservationState,
+	location,
+	opening_time,
+	profile,
+	reservation,
+	review,
+};
+use diesel::dsl::{count_star, sql};
+use diesel::expression::SqlLiteral;
 use diesel::pg::Pg;
 use diesel::prelude::*;
-use diesel::sql_types::Bool;
+use diesel::sql_types::{BigInt, Bool, Double, Nullable};
 use primitives::{PrimitiveLocation, PrimitiveProfile, PrimitiveReview};
 use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+mod flag;
 
-#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub use flag::*;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, IntoParams, Serialize)]
+#[into_params(parameter_in = Query)]
 pub struct ReviewIncludes {
 	#[serde(default)]
 	pub location: bool,
@@ -33,6 +50,17 @@ pub struct Review {
 	pub created_by: PrimitiveProfile,
 	#[diesel(embed)]
 	pub location:   Option<PrimitiveLocation>,
+	#[diesel(select_expression = flag_count_fragment())]
+	pub flag_count: i64,
+}
+
+#[allow(non_camel_case_types)]
+type flag_count_fragment = SqlLiteral<BigInt>;
+fn flag_count_fragment() -> flag_count_fragment {
+	sql::<BigInt>(
+		"(SELECT COUNT(*) FROM review_flag WHERE review_flag.review_id = \
+		 review.id)",
+	)
 }
 
 impl Review {
@@ -59,17 +87,38 @@ impl Review {
 		p_cfg: PaginationConfig,
 		conn: &DbConn,
 	) -> Result<PaginatedData<Vec<Self>>, Error> {
-		let reviews = conn
+		let after_id = p_cfg.after_id.unwrap_or(i32::MIN);
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.sql_offset() as i64;
+
+		let (total, reviews) = conn
 			.interact(move |conn| {
-				Self::query(includes)
+				let total = review::table
 					.filter(review::location_id.eq(l_id))
+					.count()
+					.get_result::<i64>(conn)?;
+
+				let reviews = Self::query(includes)
+					.filter(review::location_id.eq(l_id))
+					.filter(review::id.gt(after_id))
+					.order(review::id)
+					.limit(limit)
+					.offset(offset)
 					.select(Self::as_select())
-					.limit(QUERY_HARD_LIMIT)
-					.get_results(conn)
+					.get_results(conn)?;
+
+				Ok::<_, Error>((total, reviews))
 			})
 			.await??;
 
-		manual_pagination(reviews, p_cfg)
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		p_cfg.check_offset(total)?;
+
+		Ok((total, false, reviews))
 	}
 
 	/// Get all [`Review`]s for a profile with the given ID
@@ -90,6 +139,55 @@ impl Review {
 
 		Ok(reviews)
 	}
+
+	/// Get all [`Review`]s that have been flagged at least once, most flagged
+	/// first
+	#[instrument(skip(conn))]
+	pub async fn get_flagged(
+		includes: ReviewIncludes,
+		p_cfg: PaginationConfig,
+		conn: &DbConn,
+	) -> Result<PaginatedData<Vec<Self>>, Error> {
+		let reviews = conn
+			.interact(move |conn| {
+				Self::query(includes)
+					.filter(flag_count_fragment().gt(0))
+					.order(flag_count_fragment().desc())
+					.select(Self::as_select())
+					.limit(QUERY_HARD_LIMIT)
+					.get_results(conn)
+			})
+			.await??;
+
+		manual_pagination(reviews, p_cfg)
+	}
+
+	/// Get the average rating and amount of reviews for a list of locations
+	///
+	/// Hidden reviews are not taken into account, locations without any
+	/// reviews are not included in the result
+	#[instrument(skip(conn))]
+	pub async fn get_aggregates_for_locations(
+		l_ids: Vec<i32>,
+		conn: &DbConn,
+	) -> Result<Vec<(i32, Option<f64>, i64)>, Error> {
+		let aggregates = conn
+			.interact(move |conn| {
+				review::table
+					.filter(review::location_id.eq_any(l_ids))
+					.filter(review::hidden_at.is_null())
+					.group_by(review::location_id)
+					.select((
+						review::location_id,
+						sql::<Nullable<Double>>("AVG(review.rating)::float8"),
+						count_star(),
+					))
+					.get_results(conn)
+			})
+			.await??;
+
+		Ok(aggregates)
+	}
 }
 
 #[derive(Clone, Debug, Deserialize, Insertable, Serialize)]
@@ -104,28 +202,107 @@ pub struct NewReview {
 
 impl NewReview {
 	/// Insert this [`NewReview`]
+	///
+	/// Profiles can only review a location they visited, unless
+	/// `skip_visit_check` is set, and can only review every location once.
+	/// A second review results in an [`Error::Duplicate`] pointing to the
+	/// existing review
 	#[instrument(skip(conn))]
-	pub async fn insert(self, conn: &DbConn) -> Result<Review, Error> {
+	pub async fn insert(
+		self,
+		skip_visit_check: bool,
+		conn: &DbConn,
+	) -> Result<Review, Error> {
 		let review = conn
 			.interact(move |conn| {
-				conn.transaction(|conn| {
+				conn.transaction::<_, Error, _>(|conn| {
 					use self::review::dsl::*;
 
+					let existing = review
+						.filter(profile_id.eq(self.profile_id))
+						.filter(location_id.eq(self.location_id))
+						.select(id)
+						.first::<i32>(conn)
+						.optional()?;
+
+					if let Some(r_id) = existing {
+						return Err(Error::Duplicate {
+							message:     "profile has already reviewed this \
+							              location"
+								.to_string(),
+							resource_id: Some(r_id),
+						});
+					}
+
+					if !skip_visit_check
+						&& !Self::has_visited(
+							self.profile_id,
+							self.location_id,
+							conn,
+						)? {
+						return Err(Error::ReviewNotAllowed);
+					}
+
 					let r_id: i32 = diesel::insert_into(review)
 						.values(self)
 						.returning(id)
 						.get_result(conn)?;
 
-					Review::query(ReviewIncludes::default())
+					let review = Review::query(ReviewIncludes::default())
 						.filter(id.eq(r_id))
 						.select(Review::as_select())
-						.get_result(conn)
+						.get_result(conn)?;
+
+					Ok(review)
 				})
 			})
 			.await??;
 
 		Ok(review)
 	}
+
+	/// Check whether a profile visited a location, meaning it has a
+	/// reservation there that has already ended and was neither cancelled
+	/// nor marked absent
+	fn has_visited(
+		p_id: i32,
+		l_id: i32,
+		conn: &mut PgConnection,
+	) -> Result<bool, Error> {
+		let now = Utc::now().naive_utc();
+
+		let reservations: Vec<(NaiveDate, NaiveTime, i32, i32)> =
+			reservation::table
+				.inner_join(
+					opening_time::table
+						.on(reservation::opening_time_id.eq(opening_time::id)),
+				)
+				.filter(reservation::profile_id.eq(p_id))
+				.filter(opening_time::location_id.eq(l_id))
+				.filter(opening_time::day.le(now.date()))
+				.filter(reservation::state.ne_all([
+					ReservationState::Cancelled,
+					ReservationState::Absent,
+				]))
+				.select((
+					opening_time::day,
+					opening_time::start_time,
+					reservation::base_block_index,
+					reservation::block_count,
+				))
+				.get_results(conn)?;
+
+		let visited =
+			reservations.into_iter().any(|(day, start, base, count)| {
+				let offset = (base + count) * RESERVATION_BLOCK_SIZE_MINUTES;
+				let end =
+					day.and_time(start) + TimeDelta::minutes(i64::from(offset));
+
+				end <= now
+			});
+
+		Ok(visited)
+	}
 }
 
 #[derive(AsChangeset, Clone, Debug, Deserialize, Serialize)]
@@ -1,5 +1,5 @@
 use common::{DbConn, Error};
-use db::{authority_role, creator, profile, updater};
+use db::{authority_member, authority_role, creator, profile, updater};
 use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::sql_types::Bool;
@@ -64,6 +64,9 @@ impl AuthorityRole {
 	}
 
 	/// Delete a [`AuthorityRole`] given its id
+	///
+	/// Fails with [`Error::RoleInUse`] if any members still hold this role,
+	/// these should be re-assigned first
 	#[instrument(skip(conn))]
 	pub async fn delete_by_id(
 		r_id: i32,
@@ -71,9 +74,22 @@ impl AuthorityRole {
 	) -> Result<PrimitiveAuthorityRole, Error> {
 		let role = conn
 			.interact(move |conn| {
-				diesel::delete(authority_role::table.find(r_id))
-					.returning(PrimitiveAuthorityRole::as_returning())
-					.get_result(conn)
+				conn.transaction::<_, Error, _>(|conn| {
+					let member_count = authority_member::table
+						.filter(authority_member::authority_role_id.eq(r_id))
+						.count()
+						.get_result::<i64>(conn)?;
+
+					if member_count > 0 {
+						return Err(Error::RoleInUse { member_count });
+					}
+
+					let role = diesel::delete(authority_role::table.find(r_id))
+						.returning(PrimitiveAuthorityRole::as_returning())
+						.get_result(conn)?;
+
+					Ok(role)
+				})
 			})
 			.await??;
 
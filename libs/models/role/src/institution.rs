@@ -1,5 +1,5 @@
 use common::{DbConn, Error};
-use db::{creator, institution_role, profile, updater};
+use db::{creator, institution_member, institution_role, profile, updater};
 use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::sql_types::Bool;
@@ -64,6 +64,9 @@ impl InstitutionRole {
 	}
 
 	/// Delete a [`InstitutionRole`] given its id
+	///
+	/// Fails with [`Error::RoleInUse`] if any members still hold this role,
+	/// these should be re-assigned first
 	#[instrument(skip(conn))]
 	pub async fn delete_by_id(
 		r_id: i32,
@@ -71,9 +74,25 @@ impl InstitutionRole {
 	) -> Result<PrimitiveInstitutionRole, Error> {
 		let role = conn
 			.interact(move |conn| {
-				diesel::delete(institution_role::table.find(r_id))
-					.returning(PrimitiveInstitutionRole::as_returning())
-					.get_result(conn)
+				conn.transaction::<_, Error, _>(|conn| {
+					let member_count = institution_member::table
+						.filter(
+							institution_member::institution_role_id.eq(r_id),
+						)
+						.count()
+						.get_result::<i64>(conn)?;
+
+					if member_count > 0 {
+						return Err(Error::RoleInUse { member_count });
+					}
+
+					let role =
+						diesel::delete(institution_role::table.find(r_id))
+							.returning(PrimitiveInstitutionRole::as_returning())
+							.get_result(conn)?;
+
+					Ok(role)
+				})
 			})
 			.await??;
 
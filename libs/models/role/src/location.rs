@@ -1,5 +1,5 @@
 use common::{DbConn, Error};
-use db::{creator, location_role, profile, updater};
+use db::{creator, location_member, location_role, profile, updater};
 use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::sql_types::Bool;
@@ -64,6 +64,9 @@ impl LocationRole {
 	}
 
 	/// Delete a [`LocationRole`] given its id
+	///
+	/// Fails with [`Error::RoleInUse`] if any members still hold this role,
+	/// these should be re-assigned first
 	#[instrument(skip(conn))]
 	pub async fn delete_by_id(
 		r_id: i32,
@@ -71,9 +74,22 @@ impl LocationRole {
 	) -> Result<PrimitiveLocationRole, Error> {
 		let role = conn
 			.interact(move |conn| {
-				diesel::delete(location_role::table.find(r_id))
-					.returning(PrimitiveLocationRole::as_returning())
-					.get_result(conn)
+				conn.transaction::<_, Error, _>(|conn| {
+					let member_count = location_member::table
+						.filter(location_member::location_role_id.eq(r_id))
+						.count()
+						.get_result::<i64>(conn)?;
+
+					if member_count > 0 {
+						return Err(Error::RoleInUse { member_count });
+					}
+
+					let role = diesel::delete(location_role::table.find(r_id))
+						.returning(PrimitiveLocationRole::as_returning())
+						.get_result(conn)?;
+
+					Ok(role)
+				})
 			})
 			.await??;
 
@@ -102,6 +118,74 @@ impl LocationRole {
 
 		Ok(role)
 	}
+
+	/// Get the default [`LocationRole`] for a given location, if any
+	///
+	/// This is the role explicitly marked as default, or otherwise a role
+	/// named "member" (case-insensitive)
+	#[instrument(skip(conn))]
+	pub async fn get_default_for_location(
+		l_id: i32,
+		conn: &DbConn,
+	) -> Result<Option<Self>, Error> {
+		let query = Self::query(RoleIncludes::default());
+
+		let role = conn
+			.interact(move |conn| {
+				use self::location_role::dsl::*;
+
+				query
+					.filter(location_id.eq(l_id))
+					.filter(is_default.or(name.ilike("member")))
+					.order((is_default.desc(), id))
+					.select(Self::as_select())
+					.first(conn)
+					.optional()
+			})
+			.await??;
+
+		Ok(role)
+	}
+
+	/// Mark a [`LocationRole`] as the default role of its location, which is
+	/// assigned to new members that are added without a role
+	#[instrument(skip(conn))]
+	pub async fn set_default(
+		l_id: i32,
+		r_id: i32,
+		includes: RoleIncludes,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		conn.interact(move |conn| {
+			conn.transaction::<_, Error, _>(|conn| {
+				use self::location_role::dsl::*;
+
+				// Return not found if the role doesn't belong to this location
+				location_role
+					.filter(id.eq(r_id))
+					.filter(location_id.eq(l_id))
+					.select(id)
+					.get_result::<i32>(conn)?;
+
+				diesel::update(location_role.filter(location_id.eq(l_id)))
+					.set(is_default.eq(false))
+					.execute(conn)?;
+
+				diesel::update(location_role.find(r_id))
+					.set(is_default.eq(true))
+					.execute(conn)?;
+
+				Ok(())
+			})
+		})
+		.await??;
+
+		let role = Self::get_by_id(r_id, includes, conn).await?;
+
+		info!("set role {r_id} as default for location {l_id}");
+
+		Ok(role)
+	}
 }
 
 #[derive(Clone, Debug, Deserialize, Insertable, Serialize)]
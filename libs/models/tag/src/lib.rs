@@ -1,11 +1,15 @@
 #[macro_use]
 extern crate tracing;
 
-use ::translation::{NewTranslation, TranslationUpdate};
-use common::{DbConn, Error};
+use std::collections::HashMap;
+
+use ::translation::{Language, NewTranslation, TranslationUpdate};
+use base::{PaginatedData, PaginationConfig};
+use common::{DbConn, Error, PaginationError};
 use db::{
 	CreatorAlias,
 	UpdaterAlias,
+	authority,
 	creator,
 	location,
 	location_tag,
@@ -14,12 +18,13 @@ use db::{
 	translation,
 	updater,
 };
-use diesel::dsl::{AliasedFields, Nullable};
+use diesel::dsl::{AliasedFields, Nullable, count};
 use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::sql_types::Bool;
 use primitives::{PrimitiveProfile, PrimitiveTag, PrimitiveTranslation};
 use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
 
 pub type JoinedTagData = (
 	PrimitiveTag,
@@ -28,12 +33,15 @@ pub type JoinedTagData = (
 	Option<PrimitiveProfile>,
 );
 
-#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, IntoParams, Serialize)]
+#[into_params(parameter_in = Query)]
+#[serde(default)]
 pub struct TagIncludes {
-	#[serde(default)]
 	pub created_by: bool,
-	#[serde(default)]
 	pub updated_by: bool,
+	/// Resolve translations to a single [`Language`] in responses
+	#[param(inline)]
+	pub lang:       Option<Language>,
 }
 
 #[derive(Clone, Debug, Deserialize, Queryable, Selectable, Serialize)]
@@ -107,33 +115,108 @@ impl Tag {
 		Ok(tag)
 	}
 
-	/// Get all [`Tag`]s from the database, optionally including related
-	/// profiles.
+	/// Get a page of all [`Tag`]s from the database, optionally including
+	/// related profiles.
+	///
+	/// Every tag comes with the amount of locations using it, counted in a
+	/// single grouped query over the whole page
 	#[instrument(skip(conn))]
-	pub async fn get_all(
+	pub async fn get_all_paginated(
 		includes: TagIncludes,
+		p_cfg: PaginationConfig,
 		conn: &DbConn,
-	) -> Result<Vec<Self>, Error> {
+	) -> Result<PaginatedData<Vec<(Self, i64)>>, Error> {
 		let query = Self::query(includes);
 
-		let tags = conn
-			.interact(move |c| query.select(Self::as_select()).load(c))
+		#[allow(clippy::cast_possible_wrap)]
+		let limit = p_cfg.limit as i64;
+		#[allow(clippy::cast_possible_wrap)]
+		let offset = p_cfg.offset as i64;
+
+		let (total, tags, usages) = conn
+			.interact(move |conn| {
+				let total = tag::table.count().get_result::<i64>(conn)?;
+
+				let tags = query
+					.order(tag::id.asc())
+					.select(Self::as_select())
+					.limit(limit)
+					.offset(offset)
+					.load::<Self>(conn)?;
+
+				let t_ids: Vec<_> =
+					tags.iter().map(|t| t.primitive.id).collect();
+
+				let usages = tag::table
+					.left_join(location_tag::table)
+					.filter(tag::id.eq_any(t_ids))
+					.group_by(tag::id)
+					.select((
+						tag::id,
+						count(location_tag::location_id.nullable()),
+					))
+					.get_results::<(i32, i64)>(conn)?;
+
+				Ok::<_, Error>((total, tags, usages))
+			})
 			.await??;
 
-		Ok(tags)
+		#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+		let total = total as usize;
+
+		if total > 0 && p_cfg.offset >= total {
+			return Err(PaginationError::OffsetTooLarge.into());
+		}
+
+		let usages: HashMap<i32, i64> = usages.into_iter().collect();
+		let tags = tags
+			.into_iter()
+			.map(|t| {
+				let usage_count =
+					usages.get(&t.primitive.id).copied().unwrap_or_default();
+
+				(t, usage_count)
+			})
+			.collect();
+
+		Ok((total, false, tags))
 	}
 
 	/// Delete a [`Tag`] given its id
+	///
+	/// Fails with [`Error::TagInUse`] if any locations still use this tag,
+	/// unless `force` is set, in which case the tag is removed from those
+	/// locations as well
 	#[instrument(skip(conn))]
-	pub async fn delete_by_id(tag_id: i32, conn: &DbConn) -> Result<(), Error> {
+	pub async fn delete_by_id(
+		t_id: i32,
+		force: bool,
+		conn: &DbConn,
+	) -> Result<(), Error> {
 		conn.interact(move |conn| {
-			use self::tag::dsl::*;
+			conn.transaction::<_, Error, _>(|conn| {
+				let location_count = location_tag::table
+					.filter(location_tag::tag_id.eq(t_id))
+					.count()
+					.get_result::<i64>(conn)?;
+
+				if location_count > 0 && !force {
+					return Err(Error::TagInUse { location_count });
+				}
+
+				diesel::delete(
+					location_tag::table.filter(location_tag::tag_id.eq(t_id)),
+				)
+				.execute(conn)?;
 
-			diesel::delete(tag.find(tag_id)).execute(conn)
+				diesel::delete(tag::table.find(t_id)).execute(conn)?;
+
+				Ok(())
+			})
 		})
 		.await??;
 
-		info!("deleted tag with id {tag_id}");
+		info!("deleted tag with id {t_id}");
 
 		Ok(())
 	}
@@ -165,32 +248,201 @@ impl Tag {
 		Ok(tags)
 	}
 
+	/// Get all [`Tag`]s with the given ids, mapped by their id
+	///
+	/// Duplicate ids are only fetched once
+	#[instrument(skip(conn))]
+	pub async fn get_by_ids(
+		tag_ids: Vec<i32>,
+		includes: TagIncludes,
+		conn: &DbConn,
+	) -> Result<HashMap<i32, Self>, Error> {
+		let query = Self::query(includes);
+
+		let tags = conn
+			.interact(move |conn| {
+				query
+					.filter(tag::id.eq_any(tag_ids))
+					.select(Self::as_select())
+					.get_results(conn)
+			})
+			.await??;
+
+		let tags = tags.into_iter().map(|t| (t.primitive.id, t)).collect();
+
+		Ok(tags)
+	}
+
 	/// Get all tags for a list of locations
+	///
+	/// Every distinct tag is only fetched once, regardless of how many
+	/// locations it is linked to
 	#[instrument(skip(conn))]
 	pub async fn get_for_locations(
 		l_ids: Vec<i32>,
 		includes: TagIncludes,
 		conn: &DbConn,
 	) -> Result<Vec<(i32, Self)>, Error> {
-		let query = Self::query(includes);
-
-		let tags = conn
+		let pairs = conn
 			.interact(move |conn| {
-				use self::location;
 				use self::location_tag::dsl::*;
-				use self::tag::dsl::*;
 
-				location::table
-					.filter(location::id.eq_any(l_ids))
-					.inner_join(location_tag.on(location_id.eq(location::id)))
-					.inner_join(query.on(tag_id.eq(id)))
-					.select((location::id, Self::as_select()))
-					.get_results(conn)
+				location_tag
+					.filter(location_id.eq_any(l_ids))
+					.select((location_id, tag_id))
+					.get_results::<(i32, i32)>(conn)
+			})
+			.await??;
+
+		let mut t_ids = pairs.iter().map(|(_, t_id)| *t_id).collect::<Vec<_>>();
+		t_ids.sort_unstable();
+		t_ids.dedup();
+
+		let tags = Self::get_by_ids(t_ids, includes, conn).await?;
+
+		let tags = pairs
+			.into_iter()
+			.filter_map(|(l_id, t_id)| {
+				tags.get(&t_id).map(|t| (l_id, t.to_owned()))
+			})
+			.collect();
+
+		Ok(tags)
+	}
+
+	/// Get all tags used by the locations of the authorities of an
+	/// institution, together with the amount of locations using them
+	///
+	/// The most used tags come first
+	#[instrument(skip(conn))]
+	pub async fn get_for_institution(
+		inst_id: i32,
+		conn: &DbConn,
+	) -> Result<Vec<(Self, i64)>, Error> {
+		let counts = conn
+			.interact(move |conn| {
+				location_tag::table
+					.inner_join(
+						location::table
+							.on(location_tag::location_id.eq(location::id)),
+					)
+					.inner_join(authority::table.on(
+						location::authority_id.eq(authority::id.nullable()),
+					))
+					.filter(authority::institution_id.eq(inst_id))
+					.group_by(location_tag::tag_id)
+					.select((
+						location_tag::tag_id,
+						count(location_tag::location_id),
+					))
+					.order((
+						count(location_tag::location_id).desc(),
+						location_tag::tag_id.asc(),
+					))
+					.get_results::<(i32, i64)>(conn)
 			})
 			.await??;
 
+		let t_ids = counts.iter().map(|(t_id, _)| *t_id).collect();
+		let tags =
+			Self::get_by_ids(t_ids, TagIncludes::default(), conn).await?;
+
+		let tags = counts
+			.into_iter()
+			.filter_map(|(t_id, usages)| {
+				tags.get(&t_id).map(|t| (t.to_owned(), usages))
+			})
+			.collect();
+
 		Ok(tags)
 	}
+
+	/// Get the amount of visible locations using a given [`Tag`], optionally
+	/// only counting those in a given country and/or province
+	#[instrument(skip(conn))]
+	pub async fn get_location_count(
+		t_id: i32,
+		country: Option<&str>,
+		province: Option<&str>,
+		conn: &DbConn,
+	) -> Result<i64, Error> {
+		let country = country.map(str::to_string);
+		let province = province.map(str::to_string);
+
+		let count = conn
+			.interact(move |conn| {
+				let mut query = location_tag::table
+					.inner_join(
+						location::table
+							.on(location_tag::location_id.eq(location::id)),
+					)
+					.filter(location_tag::tag_id.eq(t_id))
+					.filter(location::is_visible.eq(true))
+					.into_boxed();
+
+				if let Some(country) = country {
+					query = query.filter(location::country.eq(country));
+				}
+
+				if let Some(province) = province {
+					query = query.filter(location::province.eq(province));
+				}
+
+				query.count().get_result::<i64>(conn)
+			})
+			.await??;
+
+		Ok(count)
+	}
+
+	/// Get the amount of visible locations using a given [`Tag`], in total
+	/// and broken down per country and per province
+	#[instrument(skip(conn))]
+	pub async fn get_location_statistics(
+		t_id: i32,
+		conn: &DbConn,
+	) -> Result<TagLocationStatistics, Error> {
+		let (total, by_country, by_province) = tokio::join!(
+			Self::get_location_count(t_id, None, None, conn),
+			conn.interact(move |conn| {
+				location_tag::table
+					.inner_join(
+						location::table
+							.on(location_tag::location_id.eq(location::id)),
+					)
+					.filter(location_tag::tag_id.eq(t_id))
+					.filter(location::is_visible.eq(true))
+					.group_by(location::country)
+					.select((location::country, count(location::id)))
+					.get_results::<(String, i64)>(conn)
+			}),
+			conn.interact(move |conn| {
+				location_tag::table
+					.inner_join(
+						location::table
+							.on(location_tag::location_id.eq(location::id)),
+					)
+					.filter(location_tag::tag_id.eq(t_id))
+					.filter(location::is_visible.eq(true))
+					.group_by(location::province)
+					.select((location::province, count(location::id)))
+					.get_results::<(String, i64)>(conn)
+			}),
+		);
+
+		Ok(TagLocationStatistics {
+			total_locations: total?,
+			by_country:      by_country??.into_iter().collect(),
+			by_province:     by_province??.into_iter().collect(),
+		})
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TagLocationStatistics {
+	pub total_locations: i64,
+	pub by_country:      HashMap<String, i64>,
+	pub by_province:     HashMap<String, i64>,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Insertable, Serialize)]
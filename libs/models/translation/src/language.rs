@@ -0,0 +1,53 @@
+use primitives::PrimitiveTranslation;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::Translation;
+
+/// A language a [`Translation`] can be written in
+#[derive(
+	Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+	Nl,
+	En,
+	Fr,
+	De,
+}
+
+impl Language {
+	/// Get the text of a translation in exactly this language
+	#[must_use]
+	pub fn column(self, translation: &PrimitiveTranslation) -> Option<&str> {
+		match self {
+			Self::Nl => translation.nl.as_deref(),
+			Self::En => translation.en.as_deref(),
+			Self::Fr => translation.fr.as_deref(),
+			Self::De => translation.de.as_deref(),
+		}
+	}
+
+	/// Get the text of a translation in this language, falling back to other
+	/// languages if it is missing
+	///
+	/// The requested language is tried first, then English, then Dutch and
+	/// finally any other language that is set
+	#[must_use]
+	pub fn resolve(self, translation: &PrimitiveTranslation) -> Option<&str> {
+		[self, Self::En, Self::Nl, Self::Fr, Self::De]
+			.into_iter()
+			.find_map(|lang| lang.column(translation))
+	}
+}
+
+impl Translation {
+	/// Get the text of this [`Translation`] in a given [`Language`]
+	///
+	/// See [`Language::resolve`] for the fallback order, [`None`] is only
+	/// returned if no language is set at all
+	#[must_use]
+	pub fn resolve(&self, lang: Language) -> Option<&str> {
+		lang.resolve(&self.primitive)
+	}
+}
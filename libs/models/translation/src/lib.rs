@@ -2,7 +2,17 @@
 extern crate tracing;
 
 use common::{DbConn, Error};
-use db::{CreatorAlias, UpdaterAlias, creator, profile, translation, updater};
+use db::{
+	CreatorAlias,
+	UpdaterAlias,
+	creator,
+	institution,
+	location,
+	profile,
+	tag,
+	translation,
+	updater,
+};
 use diesel::dsl::{AliasedFields, Nullable};
 use diesel::pg::Pg;
 use diesel::prelude::*;
@@ -10,12 +20,19 @@ use diesel::sql_types::Bool;
 use primitives::{PrimitiveProfile, PrimitiveTranslation};
 use serde::{Deserialize, Serialize};
 
+mod language;
+
+pub use language::*;
+
 #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
 pub struct TranslationIncludes {
 	#[serde(default)]
 	pub created_by: bool,
 	#[serde(default)]
 	pub updated_by: bool,
+	/// Resolve the translation to a single [`Language`] in responses
+	#[serde(default)]
+	pub lang:       Option<Language>,
 }
 
 #[derive(Clone, Debug, Deserialize, Queryable, Selectable, Serialize)]
@@ -29,6 +46,25 @@ pub struct Translation {
 	pub updated_by: Option<PrimitiveProfile>,
 }
 
+/// All translations belonging to a single location
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LocationTranslations {
+	pub description: Translation,
+	pub excerpt:     Translation,
+}
+
+/// All translations belonging to a single tag
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TagTranslations {
+	pub name: Translation,
+}
+
+/// All translations belonging to a single institution
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InstitutionTranslations {
+	pub name: Translation,
+}
+
 #[allow(non_camel_case_types)]
 type created_by_fragment = Nullable<
 	AliasedFields<CreatorAlias, <profile::table as Table>::AllColumns>,
@@ -89,6 +125,103 @@ impl Translation {
 		Ok(translation)
 	}
 
+	/// Get the list of [`Translation`]s with the given ids
+	fn get_by_ids(
+		tr_ids: &[i32],
+		conn: &mut PgConnection,
+	) -> Result<Vec<Self>, Error> {
+		let translations = Self::query(TranslationIncludes::default())
+			.filter(translation::id.eq_any(tr_ids))
+			.select(Self::as_select())
+			.get_results(conn)?;
+
+		Ok(translations)
+	}
+
+	/// Find the [`Translation`] with the given id in a list of translations
+	fn take(translations: &mut Vec<Self>, tr_id: i32) -> Result<Self, Error> {
+		let idx = translations
+			.iter()
+			.position(|t| t.primitive.id == tr_id)
+			.ok_or(Error::NotFound(format!("translation with id {tr_id}")))?;
+
+		Ok(translations.swap_remove(idx))
+	}
+
+	/// Get all [`Translation`]s of the location with the given id
+	#[instrument(skip(conn))]
+	pub async fn get_for_location(
+		loc_id: i32,
+		conn: &DbConn,
+	) -> Result<LocationTranslations, Error> {
+		let translations = conn
+			.interact(move |conn| {
+				let (desc_id, exc_id) = location::table
+					.find(loc_id)
+					.select((location::description_id, location::excerpt_id))
+					.get_result::<(i32, i32)>(conn)?;
+
+				let mut translations =
+					Self::get_by_ids(&[desc_id, exc_id], conn)?;
+
+				Ok::<_, Error>(LocationTranslations {
+					description: Self::take(&mut translations, desc_id)?,
+					excerpt:     Self::take(&mut translations, exc_id)?,
+				})
+			})
+			.await??;
+
+		Ok(translations)
+	}
+
+	/// Get all [`Translation`]s of the tag with the given id
+	#[instrument(skip(conn))]
+	pub async fn get_for_tag(
+		tag_id: i32,
+		conn: &DbConn,
+	) -> Result<TagTranslations, Error> {
+		let translations = conn
+			.interact(move |conn| {
+				let name_id = tag::table
+					.find(tag_id)
+					.select(tag::name_translation_id)
+					.get_result::<i32>(conn)?;
+
+				let mut translations = Self::get_by_ids(&[name_id], conn)?;
+
+				Ok::<_, Error>(TagTranslations {
+					name: Self::take(&mut translations, name_id)?,
+				})
+			})
+			.await??;
+
+		Ok(translations)
+	}
+
+	/// Get all [`Translation`]s of the institution with the given id
+	#[instrument(skip(conn))]
+	pub async fn get_for_institution(
+		inst_id: i32,
+		conn: &DbConn,
+	) -> Result<InstitutionTranslations, Error> {
+		let translations = conn
+			.interact(move |conn| {
+				let name_id = institution::table
+					.find(inst_id)
+					.select(institution::name_translation_id)
+					.get_result::<i32>(conn)?;
+
+				let mut translations = Self::get_by_ids(&[name_id], conn)?;
+
+				Ok::<_, Error>(InstitutionTranslations {
+					name: Self::take(&mut translations, name_id)?,
+				})
+			})
+			.await??;
+
+		Ok(translations)
+	}
+
 	/// Delete a single [`Translation`] given its id
 	#[instrument(skip(conn))]
 	pub async fn delete_by_id(tr_id: i32, conn: &DbConn) -> Result<(), Error> {
@@ -103,6 +236,51 @@ impl Translation {
 
 		Ok(())
 	}
+
+	/// Apply a list of updates to the [`Translation`]s with the given ids in a
+	/// single transaction, returning the updated translations in the same
+	/// order
+	///
+	/// If any of the translations does not exist, none of them are updated
+	#[instrument(skip(updates, conn))]
+	pub async fn bulk_update(
+		updates: Vec<(i32, TranslationUpdate)>,
+		conn: &DbConn,
+	) -> Result<Vec<Self>, Error> {
+		let tr_ids: Vec<i32> = updates.iter().map(|(id, _)| *id).collect();
+		let updated_ids = tr_ids.clone();
+
+		let translations = conn
+			.interact(move |conn| {
+				conn.transaction::<_, Error, _>(|conn| {
+					for (tr_id, update) in updates {
+						diesel::update(translation::table.find(tr_id))
+							.set(update)
+							.execute(conn)?;
+					}
+
+					let translations = Self::get_by_ids(&tr_ids, conn)?;
+
+					tr_ids
+						.iter()
+						.map(|tr_id| {
+							translations
+								.iter()
+								.find(|t| t.primitive.id == *tr_id)
+								.cloned()
+								.ok_or(Error::NotFound(format!(
+									"translation with id {tr_id}"
+								)))
+						})
+						.collect::<Result<Vec<_>, _>>()
+				})
+			})
+			.await??;
+
+		debug!("bulk updated translations {updated_ids:?}");
+
+		Ok(translations)
+	}
 }
 
 #[derive(Clone, Debug, Deserialize, Insertable, Serialize)]
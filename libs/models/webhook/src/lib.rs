@@ -0,0 +1,256 @@
+#[macro_use]
+extern crate bitflags;
+#[macro_use]
+extern crate tracing;
+
+use chrono::{NaiveDateTime, Utc};
+use common::{DbConn, Error};
+use db::webhook;
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use primitives::PrimitiveWebhook;
+use serde::{Deserialize, Serialize};
+
+bitflags! {
+	/// All events a webhook can subscribe to
+	#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+	pub struct WebhookEvents: i64 {
+		/// A location was created under the authority
+		const LocationCreated = 1 << 0;
+		/// A location of the authority was approved
+		const LocationApproved = 1 << 1;
+		/// A location of the authority was rejected
+		const LocationRejected = 1 << 2;
+	}
+}
+
+impl WebhookEvents {
+	/// Get the names of all the events that are set
+	#[must_use]
+	pub fn events_labels(&self) -> Vec<String> {
+		self.iter_names().map(|(name, _)| name.to_string()).collect()
+	}
+}
+
+/// A single event that is sent to the subscribed webhooks
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookEvent {
+	LocationCreated,
+	LocationApproved,
+	LocationRejected,
+}
+
+impl WebhookEvent {
+	/// Get the flag subscribing a webhook to this event
+	#[must_use]
+	pub fn flag(self) -> WebhookEvents {
+		match self {
+			Self::LocationCreated => WebhookEvents::LocationCreated,
+			Self::LocationApproved => WebhookEvents::LocationApproved,
+			Self::LocationRejected => WebhookEvents::LocationRejected,
+		}
+	}
+
+	/// Get the name of this event as it is sent to webhooks
+	#[must_use]
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::LocationCreated => "locationCreated",
+			Self::LocationApproved => "locationApproved",
+			Self::LocationRejected => "locationRejected",
+		}
+	}
+}
+
+/// An endpoint of an authority that is notified of events concerning its
+/// locations
+#[derive(Clone, Debug, Deserialize, Queryable, Selectable, Serialize)]
+#[diesel(check_for_backend(Pg))]
+pub struct Webhook {
+	#[diesel(embed)]
+	pub primitive: PrimitiveWebhook,
+}
+
+impl Webhook {
+	/// Whether this [`Webhook`] is enabled and subscribed to the given event
+	#[must_use]
+	pub fn is_subscribed_to(&self, event: WebhookEvent) -> bool {
+		self.primitive.enabled
+			&& WebhookEvents::from_bits_truncate(self.primitive.events)
+				.contains(event.flag())
+	}
+
+	/// Get a [`Webhook`] of an authority given its id
+	#[instrument(skip(conn))]
+	pub async fn get_by_id(
+		auth_id: i32,
+		w_id: i32,
+		conn: &DbConn,
+	) -> Result<Self, Error> {
+		let webhook = conn
+			.interact(move |conn| {
+				webhook::table
+					.filter(webhook::id.eq(w_id))
+					.filter(webhook::authority_id.eq(auth_id))
+					.select(Self::as_select())
+					.get_result(conn)
+			})
+			.await??;
+
+		Ok(webhook)
+	}
+
+	/// Get all [`Webhook`]s for a given authority
+	#[instrument(skip(conn))]
+	pub async fn get_for_authority(
+		auth_id: i32,
+		conn: &DbConn,
+	) -> Result<Vec<Self>, Error> {
+		let webhooks = conn
+			.interact(move |conn| {
+				webhook::table
+					.filter(webhook::authority_id.eq(auth_id))
+					.order(webhook::id)
+					.select(Self::as_select())
+					.get_results(conn)
+			})
+			.await??;
+
+		Ok(webhooks)
+	}
+
+	/// Get all enabled [`Webhook`]s of an authority that are subscribed to
+	/// the given event
+	#[instrument(skip(conn))]
+	pub async fn get_subscribed(
+		auth_id: i32,
+		event: WebhookEvent,
+		conn: &DbConn,
+	) -> Result<Vec<Self>, Error> {
+		let webhooks = Self::get_for_authority(auth_id, conn).await?;
+
+		Ok(webhooks.into_iter().filter(|w| w.is_subscribed_to(event)).collect())
+	}
+
+	/// Delete a [`Webhook`] of an authority given its id
+	#[instrument(skip(conn))]
+	pub async fn delete_by_id(
+		auth_id: i32,
+		w_id: i32,
+		conn: &DbConn,
+	) -> Result<(), Error> {
+		let deleted = conn
+			.interact(move |conn| {
+				diesel::delete(
+					webhook::table
+						.filter(webhook::id.eq(w_id))
+						.filter(webhook::authority_id.eq(auth_id)),
+				)
+				.execute(conn)
+			})
+			.await??;
+
+		if deleted == 0 {
+			return Err(Error::NotFound(format!("webhook {w_id}")));
+		}
+
+		Ok(())
+	}
+
+	/// Store the outcome of the latest delivery to a [`Webhook`]
+	#[instrument(skip(conn))]
+	pub async fn record_delivery(
+		w_id: i32,
+		delivery: WebhookDelivery,
+		conn: &DbConn,
+	) -> Result<(), Error> {
+		conn.interact(move |conn| {
+			diesel::update(webhook::table.find(w_id))
+				.set(delivery)
+				.execute(conn)
+		})
+		.await??;
+
+		Ok(())
+	}
+}
+
+/// The outcome of the latest delivery to a [`Webhook`]
+#[derive(AsChangeset, Clone, Debug, Deserialize, Serialize)]
+#[diesel(table_name = webhook)]
+#[diesel(treat_none_as_null = true)]
+pub struct WebhookDelivery {
+	pub last_delivery_at:     NaiveDateTime,
+	/// The status code of the response, absent if none was received
+	pub last_delivery_status: Option<i32>,
+	/// Why the delivery failed, absent if it succeeded
+	pub last_delivery_error:  Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Insertable, Serialize)]
+#[diesel(table_name = webhook)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewWebhook {
+	pub authority_id: i32,
+	pub url:          String,
+	pub secret:       String,
+	pub enabled:      bool,
+	pub events:       i64,
+	pub created_by:   i32,
+}
+
+impl NewWebhook {
+	/// Insert this [`NewWebhook`]
+	///
+	/// The webhook itself is left out of the trace, it holds the secret
+	#[instrument(skip(self, conn))]
+	pub async fn insert(self, conn: &DbConn) -> Result<Webhook, Error> {
+		let webhook = conn
+			.interact(move |conn| {
+				diesel::insert_into(webhook::table)
+					.values(self)
+					.returning(Webhook::as_returning())
+					.get_result(conn)
+			})
+			.await??;
+
+		Ok(webhook)
+	}
+}
+
+#[derive(AsChangeset, Clone, Debug, Deserialize, Serialize)]
+#[diesel(table_name = webhook)]
+pub struct WebhookUpdate {
+	pub url:        Option<String>,
+	pub secret:     Option<String>,
+	pub enabled:    Option<bool>,
+	pub events:     Option<i64>,
+	pub updated_by: i32,
+}
+
+impl WebhookUpdate {
+	/// Update a [`Webhook`] of an authority in the database
+	#[instrument(skip(self, conn))]
+	pub async fn apply_to(
+		self,
+		auth_id: i32,
+		w_id: i32,
+		conn: &DbConn,
+	) -> Result<Webhook, Error> {
+		let webhook = conn
+			.interact(move |conn| {
+				diesel::update(
+					webhook::table
+						.filter(webhook::id.eq(w_id))
+						.filter(webhook::authority_id.eq(auth_id)),
+				)
+				.set((self, webhook::updated_at.eq(Utc::now().naive_utc())))
+				.returning(Webhook::as_returning())
+				.get_result(conn)
+			})
+			.await??;
+
+		Ok(webhook)
+	}
+}
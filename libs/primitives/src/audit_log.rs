@@ -0,0 +1,20 @@
+use chrono::NaiveDateTime;
+use db::{AuditAction, AuditTarget, audit_log};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+	Clone, Debug, Deserialize, Identifiable, Queryable, Selectable, Serialize,
+)]
+#[diesel(table_name = audit_log)]
+#[diesel(check_for_backend(Pg))]
+pub struct PrimitiveAuditLog {
+	pub id:          i32,
+	pub actor_id:    Option<i32>,
+	pub action:      AuditAction,
+	pub target_type: AuditTarget,
+	pub target_id:   i32,
+	pub detail:      serde_json::Value,
+	pub created_at:  NaiveDateTime,
+}
@@ -10,9 +10,10 @@ use serde::{Deserialize, Serialize};
 #[diesel(table_name = image)]
 #[diesel(check_for_backend(Pg))]
 pub struct PrimitiveImage {
-	pub id:          i32,
-	pub file_path:   Option<String>,
-	pub image_url:   Option<String>,
-	pub uploaded_at: NaiveDateTime,
-	pub uploaded_by: Option<i32>,
+	pub id:           i32,
+	pub file_path:    Option<String>,
+	pub image_url:    Option<String>,
+	pub uploaded_at:  NaiveDateTime,
+	pub uploaded_by:  Option<i32>,
+	pub content_hash: Option<String>,
 }
@@ -1,3 +1,4 @@
+mod audit_log;
 mod authority;
 mod image;
 mod institution;
@@ -9,7 +10,9 @@ mod review;
 mod role;
 mod tag;
 mod translation;
+mod webhook;
 
+pub use audit_log::*;
 pub use authority::*;
 pub use image::*;
 pub use institution::*;
@@ -21,3 +24,4 @@ pub use review::*;
 pub use role::*;
 pub use tag::*;
 pub use translation::*;
+pub use webhook::*;
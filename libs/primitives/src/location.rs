@@ -3,6 +3,7 @@ use db::location;
 use diesel::pg::Pg;
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(
 	Clone, Debug, Deserialize, Identifiable, Queryable, Selectable, Serialize,
@@ -17,6 +18,7 @@ pub struct PrimitiveLocation {
 	pub excerpt_id:             i32,
 	pub seat_count:             i32,
 	pub is_reservable:          bool,
+	/// The longest a single reservation can last, in minutes
 	pub max_reservation_length: Option<i32>,
 	pub is_visible:             bool,
 	pub street:                 String,
@@ -36,4 +38,5 @@ pub struct PrimitiveLocation {
 	pub created_by:             Option<i32>,
 	pub updated_at:             NaiveDateTime,
 	pub updated_by:             Option<i32>,
+	pub public_id:              Uuid,
 }
@@ -1,5 +1,5 @@
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
-use db::opening_time;
+use db::{opening_time, opening_time_exception};
 use diesel::pg::Pg;
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -30,3 +30,34 @@ pub struct PrimitiveOpeningTime {
 	pub updated_at:       NaiveDateTime,
 	pub updated_by:       Option<i32>,
 }
+
+impl PrimitiveOpeningTime {
+	/// The amount of reservation blocks of the given size that fit in this
+	/// opening time
+	#[must_use]
+	pub fn total_blocks(&self, block_size_minutes: i32) -> i64 {
+		(self.end_time - self.start_time).num_minutes()
+			/ i64::from(block_size_minutes)
+	}
+
+	/// Whether the given moment lies within this opening time
+	#[must_use]
+	pub fn is_open_at(&self, at: NaiveDateTime) -> bool {
+		self.day == at.date()
+			&& self.start_time <= at.time()
+			&& at.time() < self.end_time
+	}
+}
+
+#[derive(
+	Clone, Debug, Deserialize, Identifiable, Queryable, Selectable, Serialize,
+)]
+#[diesel(table_name = opening_time_exception)]
+#[diesel(check_for_backend(Pg))]
+pub struct PrimitiveOpeningTimeException {
+	pub id:              i32,
+	pub opening_time_id: i32,
+	pub reason:          String,
+	pub created_at:      NaiveDateTime,
+	pub created_by:      Option<i32>,
+}
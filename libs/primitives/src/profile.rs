@@ -38,11 +38,20 @@ pub struct PrimitiveProfile {
 	#[serde(skip)]
 	pub email_confirmation_token_expiry: Option<NaiveDateTime>,
 	pub is_admin:                        bool,
-	pub block_reason:                    Option<String>,
+	#[diesel(treat_none_as_null = true)]
+	pub deactivation_reason:             Option<String>,
 	#[serde(skip)]
 	pub state:                           ProfileState,
 	pub created_at:                      NaiveDateTime,
 	pub updated_at:                      NaiveDateTime,
 	pub updated_by:                      Option<i32>,
 	pub last_login_at:                   NaiveDateTime,
+	#[diesel(treat_none_as_null = true)]
+	pub deactivated_by:                  Option<i32>,
+	#[serde(skip)]
+	pub email_revert_token:              Option<String>,
+	#[serde(skip)]
+	pub email_revert_token_expiry:       Option<NaiveDateTime>,
+	#[serde(skip)]
+	pub previous_email:                  Option<String>,
 }
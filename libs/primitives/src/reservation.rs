@@ -20,4 +20,5 @@ pub struct PrimitiveReservation {
 	pub updated_at:       NaiveDateTime,
 	pub confirmed_at:     Option<NaiveDateTime>,
 	pub confirmed_by:     Option<i32>,
+	pub reminded_at:      Option<NaiveDateTime>,
 }
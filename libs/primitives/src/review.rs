@@ -1,5 +1,5 @@
 use chrono::NaiveDateTime;
-use db::review;
+use db::{review, review_flag};
 use diesel::pg::Pg;
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -20,3 +20,16 @@ pub struct PrimitiveReview {
 	pub hidden_at:   Option<NaiveDateTime>,
 	pub hidden_by:   Option<i32>,
 }
+
+#[derive(
+	Clone, Debug, Deserialize, Identifiable, Queryable, Selectable, Serialize,
+)]
+#[diesel(table_name = review_flag)]
+#[diesel(check_for_backend(Pg))]
+pub struct PrimitiveReviewFlag {
+	pub id:         i32,
+	pub review_id:  i32,
+	pub flagged_by: i32,
+	pub reason:     String,
+	pub created_at: NaiveDateTime,
+}
@@ -53,4 +53,5 @@ pub struct PrimitiveLocationRole {
 	pub created_by:  Option<i32>,
 	pub updated_at:  NaiveDateTime,
 	pub updated_by:  Option<i32>,
+	pub is_default:  bool,
 }
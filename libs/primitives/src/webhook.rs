@@ -0,0 +1,26 @@
+use chrono::NaiveDateTime;
+use db::webhook;
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+	Clone, Debug, Deserialize, Identifiable, Queryable, Selectable, Serialize,
+)]
+#[diesel(table_name = webhook)]
+#[diesel(check_for_backend(Pg))]
+pub struct PrimitiveWebhook {
+	pub id:                   i32,
+	pub authority_id:         i32,
+	pub url:                  String,
+	pub secret:               String,
+	pub enabled:              bool,
+	pub events:               i64,
+	pub last_delivery_at:     Option<NaiveDateTime>,
+	pub last_delivery_status: Option<i32>,
+	pub last_delivery_error:  Option<String>,
+	pub created_at:           NaiveDateTime,
+	pub created_by:           Option<i32>,
+	pub updated_at:           NaiveDateTime,
+	pub updated_by:           Option<i32>,
+}
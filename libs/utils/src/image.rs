@@ -1,14 +1,23 @@
 use std::fs::File;
-use std::io::{BufWriter, Cursor, Write};
+use std::io::{BufWriter, Cursor, ErrorKind, Write};
 use std::path::{Path, PathBuf};
 
 use axum::body::Bytes;
-use common::{DbConn, Error};
+use blake2::{Blake2s256, Digest};
+use common::{DbConn, Error, ImageUploadError};
 use fast_image_resize::images::Image;
 use fast_image_resize::{IntoImageView, Resizer};
 use image::{Image as ImageModel, NewImage, OrderedImage};
 use image_processing::codecs::webp::WebPEncoder;
-use image_processing::{ColorType, ImageEncoder, ImageReader};
+use image_processing::{
+	ColorType,
+	DynamicImage,
+	ImageDecoder,
+	ImageEncoder,
+	ImageFormat,
+	ImageReader,
+	Limits,
+};
 use uuid::Uuid;
 
 /// This basically only exists to avoid circular imports, would be nice if it
@@ -25,17 +34,105 @@ pub struct OrderedImageVariant {
 	pub index: i32,
 }
 
+/// Image formats that are accepted for uploads
+const SUPPORTED_FORMATS: [ImageFormat; 3] =
+	[ImageFormat::Jpeg, ImageFormat::Png, ImageFormat::WebP];
+
+/// Limits an uploaded image has to respect before it is decoded
+#[derive(Clone, Copy, Debug)]
+pub struct ImageLimits {
+	/// Maximum size of the upload in bytes
+	pub max_bytes:     usize,
+	/// Maximum width and height of the image in pixels
+	pub max_dimension: u32,
+}
+
+impl ImageLimits {
+	/// Check an uploaded image against these limits and return its format
+	///
+	/// The format is sniffed from the contents of the upload rather than
+	/// trusting its file name or content type, and the dimensions are read
+	/// from the image header so oversized images are never decoded
+	pub fn check(self, bytes: &[u8]) -> Result<ImageFormat, Error> {
+		if bytes.len() > self.max_bytes {
+			return Err(ImageUploadError::TooLarge {
+				size: bytes.len(),
+				max:  self.max_bytes,
+			}
+			.into());
+		}
+
+		// SVG has no magic bytes, so it is recognized by its markup instead
+		if is_svg(bytes) {
+			return Err(ImageUploadError::UnsupportedFormat(
+				"image/svg+xml".to_string(),
+			)
+			.into());
+		}
+
+		let format = image_processing::guess_format(bytes)
+			.map_err(|_| ImageUploadError::UnrecognizedFormat)?;
+
+		if !SUPPORTED_FORMATS.contains(&format) {
+			return Err(ImageUploadError::UnsupportedFormat(
+				format.to_mime_type().to_string(),
+			)
+			.into());
+		}
+
+		let (width, height) =
+			ImageReader::with_format(Cursor::new(bytes), format)
+				.into_dimensions()?;
+
+		if width > self.max_dimension || height > self.max_dimension {
+			return Err(ImageUploadError::DimensionsTooLarge {
+				width,
+				height,
+				max: self.max_dimension,
+			}
+			.into());
+		}
+
+		Ok(format)
+	}
+
+	/// Decoder limits matching these image limits
+	fn decoder_limits(self) -> Limits {
+		let mut limits = Limits::default();
+		limits.max_image_width = Some(self.max_dimension);
+		limits.max_image_height = Some(self.max_dimension);
+
+		limits
+	}
+}
+
+/// Check whether the contents of an upload look like an SVG document
+fn is_svg(bytes: &[u8]) -> bool {
+	let head = &bytes[..bytes.len().min(1024)];
+	let head = String::from_utf8_lossy(head);
+	let head = head.trim_start_matches('\u{feff}').trim_start();
+
+	let is_markup = head.starts_with("<?xml") || head.starts_with("<!DOCTYPE");
+
+	head.starts_with("<svg") || (is_markup && head.contains("<svg"))
+}
+
 impl ImageVariant {
 	fn into_insertable(
 		self,
 		uploaded_by: i32,
 		owner_type: ImageOwner,
 		owner_id: i32,
+		limits: ImageLimits,
 	) -> Result<NewImage, Error> {
-		let (file_path, image_url) = match self {
-			ImageVariant::Url(url) => (None, Some(url)),
+		let (file_path, image_url, content_hash) = match self {
+			ImageVariant::Url(url) => (None, Some(url), None),
 			ImageVariant::Image(bytes) => {
-				let (image, color_type) = resize_image(&bytes)?;
+				let format = limits.check(&bytes)?;
+				let content_hash = hash_image(&bytes);
+
+				let src_image = decode_image(&bytes, format, limits)?;
+				let (image, color_type) = resize_image(&src_image)?;
 				let (abs_filepath, rel_filepath) =
 					generate_image_filepaths(owner_type, owner_id)?;
 
@@ -43,43 +140,100 @@ impl ImageVariant {
 
 				let image_url = rel_filepath.to_string_lossy().into_owned();
 
-				(Some(image_url), None)
+				(Some(image_url), None, Some(content_hash))
 			},
 		};
 
-		Ok(NewImage { file_path, uploaded_by, image_url })
+		Ok(NewImage { file_path, uploaded_by, image_url, content_hash })
 	}
 }
 
 /// Store an image for the given location
+///
+/// If the location already has an image with the same contents that image is
+/// returned instead and nothing is written, the returned flag tells whether a
+/// new image was stored
 pub async fn store_location_image(
 	uploader_id: i32,
 	location_id: i32,
 	ordered_image: OrderedImageVariant,
+	limits: ImageLimits,
 	conn: &DbConn,
-) -> Result<OrderedImage, Error> {
+) -> Result<(OrderedImage, bool), Error> {
+	if let ImageVariant::Image(bytes) = &ordered_image.image {
+		limits.check(bytes)?;
+
+		let content_hash = hash_image(bytes);
+
+		let duplicate = ImageModel::detect_duplicate_for_location(
+			location_id,
+			&content_hash,
+			conn,
+		)
+		.await?;
+
+		if let Some(duplicate) = duplicate {
+			return Ok((duplicate, false));
+		}
+	}
+
 	let new_image = ordered_image.image.into_insertable(
 		uploader_id,
 		ImageOwner::Location,
 		location_id,
+		limits,
 	)?;
 
 	let image = new_image
 		.insert_for_location(location_id, ordered_image.index, conn)
 		.await?;
 
-	Ok(image)
+	Ok((image, true))
+}
+
+/// Build the insertables for a set of images belonging to the given location
+///
+/// The images are resized and written to disk, but not yet inserted into the
+/// database
+pub fn build_location_images(
+	uploader_id: i32,
+	location_id: i32,
+	images: Vec<ImageVariant>,
+	limits: ImageLimits,
+) -> Result<Vec<NewImage>, Error> {
+	images
+		.into_iter()
+		.map(|i| {
+			i.into_insertable(
+				uploader_id,
+				ImageOwner::Location,
+				location_id,
+				limits,
+			)
+		})
+		.collect()
 }
 
 /// Store an image for the given profile
 pub async fn store_profile_image(
 	profile_id: i32,
 	image: ImageVariant,
+	limits: ImageLimits,
 	conn: &DbConn,
 ) -> Result<ImageModel, Error> {
-	let new_image =
-		image.into_insertable(profile_id, ImageOwner::Profile, profile_id)?;
-	let image = new_image.insert_for_profile(profile_id, conn).await?;
+	let new_image = image.into_insertable(
+		profile_id,
+		ImageOwner::Profile,
+		profile_id,
+		limits,
+	)?;
+	let (image, replaced) =
+		new_image.insert_for_profile(profile_id, conn).await?;
+
+	// The old avatar is only removed from disk once it is no longer referenced
+	if let Some(file_path) = replaced {
+		delete_image_file(&file_path)?;
+	}
 
 	Ok(image)
 }
@@ -90,14 +244,57 @@ pub async fn delete_image(id: i32, conn: &DbConn) -> Result<(), Error> {
 	let image = ImageModel::delete_by_id(id, conn).await?;
 
 	if let Some(file_path) = &image.file_path {
-		let filepath = PathBuf::from("/mnt/files").join(file_path);
-		std::fs::remove_file(filepath)?;
+		delete_image_file(file_path)?;
 	}
 
 	Ok(())
 }
 
+/// Delete a stored image file from disk
+///
+/// Files that are already gone are ignored
+pub fn delete_image_file(file_path: &str) -> Result<(), Error> {
+	let filepath = PathBuf::from("/mnt/files").join(file_path);
+
+	match std::fs::remove_file(filepath) {
+		Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+		_ => Ok(()),
+	}
+}
+
+/// Hash the raw contents of an uploaded image
+#[must_use]
+pub fn hash_image(bytes: &[u8]) -> String {
+	let hash = Blake2s256::digest(bytes);
+
+	format!("{hash:x}")
+}
+
+/// Decode an uploaded image that passed [`ImageLimits::check`]
+///
+/// The EXIF orientation of the image is applied to its pixels, as the image
+/// is re-encoded from those pixels alone and loses its metadata afterwards
+fn decode_image(
+	bytes: &[u8],
+	format: ImageFormat,
+	limits: ImageLimits,
+) -> Result<DynamicImage, Error> {
+	let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+	reader.limits(limits.decoder_limits());
+
+	let mut decoder = reader.into_decoder()?;
+	let orientation = decoder.orientation()?;
+
+	let mut image = DynamicImage::from_decoder(decoder)?;
+	image.apply_orientation(orientation);
+
+	Ok(image)
+}
+
 /// Save an image to a file
+///
+/// Only the raw pixels are written, so no metadata of the original upload
+/// (e.g. EXIF location data) ends up in the stored file
 fn save_image_file(
 	path: &Path,
 	image: &Image<'static>,
@@ -123,12 +320,9 @@ fn save_image_file(
 /// # Panics
 /// Panics if the decoder can't infer the images pixel type
 #[inline]
-fn resize_image(bytes: &Bytes) -> Result<(Image<'static>, ColorType), Error> {
-	let image_reader =
-		ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
-
-	let src_image = image_reader.decode()?;
-
+fn resize_image(
+	src_image: &DynamicImage,
+) -> Result<(Image<'static>, ColorType), Error> {
 	// Set width to 1024 but scale height to preserve aspect ratio
 	#[allow(clippy::cast_precision_loss)]
 	let src_ratio = src_image.height() as f32 / src_image.width() as f32;
@@ -141,7 +335,7 @@ fn resize_image(bytes: &Bytes) -> Result<(Image<'static>, ColorType), Error> {
 		Image::new(dst_width, dst_height, src_image.pixel_type().unwrap());
 
 	let mut resizer = Resizer::new();
-	resizer.resize(&src_image, &mut dst_image, None)?;
+	resizer.resize(src_image, &mut dst_image, None)?;
 
 	Ok((dst_image, src_image.color()))
 }
@@ -0,0 +1 @@
+ALTER TABLE location_image DROP COLUMN is_cover;
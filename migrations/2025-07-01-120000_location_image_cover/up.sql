@@ -0,0 +1,2 @@
+ALTER TABLE location_image
+ADD COLUMN is_cover BOOLEAN NOT NULL DEFAULT FALSE;
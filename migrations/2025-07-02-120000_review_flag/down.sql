@@ -0,0 +1,2 @@
+DROP INDEX idx__review_flag__review_id;
+DROP TABLE review_flag;
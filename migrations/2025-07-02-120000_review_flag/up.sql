@@ -0,0 +1,20 @@
+CREATE TABLE review_flag (
+	id         SERIAL    PRIMARY KEY,
+	review_id  INTEGER   NOT NULL,
+	flagged_by INTEGER   NOT NULL,
+	reason     TEXT      NOT NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+	CONSTRAINT unq__review_flag
+	UNIQUE (review_id, flagged_by),
+
+	CONSTRAINT fk__review_flag__review_id
+	FOREIGN KEY (review_id) REFERENCES review(id)
+	ON DELETE CASCADE,
+
+	CONSTRAINT fk__review_flag__flagged_by
+	FOREIGN KEY (flagged_by) REFERENCES profile(id)
+	ON DELETE CASCADE
+);
+
+CREATE INDEX idx__review_flag__review_id ON review_flag(review_id);
@@ -0,0 +1 @@
+DROP TABLE opening_time_exception;
@@ -0,0 +1,18 @@
+CREATE TABLE opening_time_exception (
+	id              SERIAL    PRIMARY KEY,
+	opening_time_id INTEGER   NOT NULL,
+	reason          TEXT      NOT NULL,
+	created_at      TIMESTAMP NOT NULL DEFAULT NOW(),
+	created_by      INTEGER,
+
+	CONSTRAINT unq__opening_time_exception__opening_time_id
+	UNIQUE (opening_time_id),
+
+	CONSTRAINT fk__opening_time_exception__opening_time_id
+	FOREIGN KEY (opening_time_id) REFERENCES opening_time(id)
+	ON DELETE CASCADE,
+
+	CONSTRAINT fk__opening_time_exception__created_by
+	FOREIGN KEY (created_by) REFERENCES profile(id)
+	ON DELETE SET NULL
+);
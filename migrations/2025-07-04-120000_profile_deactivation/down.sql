@@ -0,0 +1,5 @@
+ALTER TABLE profile DROP CONSTRAINT fk__profile__deactivated_by;
+
+ALTER TABLE profile DROP COLUMN deactivated_by;
+
+ALTER TABLE profile RENAME COLUMN deactivation_reason TO block_reason;
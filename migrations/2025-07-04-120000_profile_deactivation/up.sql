@@ -0,0 +1,8 @@
+ALTER TABLE profile RENAME COLUMN block_reason TO deactivation_reason;
+
+ALTER TABLE profile ADD COLUMN deactivated_by INTEGER;
+
+ALTER TABLE profile
+ADD CONSTRAINT fk__profile__deactivated_by
+FOREIGN KEY (deactivated_by) REFERENCES profile(id)
+ON DELETE SET NULL;
@@ -0,0 +1 @@
+DROP INDEX idx__authority__name;
@@ -0,0 +1 @@
+CREATE INDEX idx__authority__name ON authority USING GIN (name gin_trgm_ops);
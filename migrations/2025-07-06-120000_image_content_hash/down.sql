@@ -0,0 +1,3 @@
+DROP INDEX idx__image__content_hash;
+
+ALTER TABLE image DROP COLUMN content_hash;
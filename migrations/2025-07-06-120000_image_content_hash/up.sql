@@ -0,0 +1,3 @@
+ALTER TABLE image ADD COLUMN content_hash TEXT;
+
+CREATE INDEX idx__image__content_hash ON image (content_hash);
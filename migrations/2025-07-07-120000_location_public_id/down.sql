@@ -0,0 +1,3 @@
+DROP INDEX idx__location__public_id;
+
+ALTER TABLE location DROP COLUMN public_id;
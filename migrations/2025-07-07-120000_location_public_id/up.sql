@@ -0,0 +1,5 @@
+-- The default is evaluated per row, so existing locations get their own uuid
+ALTER TABLE location
+ADD COLUMN public_id UUID NOT NULL DEFAULT gen_random_uuid();
+
+CREATE UNIQUE INDEX idx__location__public_id ON location (public_id);
@@ -0,0 +1,3 @@
+DROP INDEX idx__location_role__default;
+
+ALTER TABLE location_role DROP COLUMN is_default;
@@ -0,0 +1,7 @@
+ALTER TABLE location_role
+ADD COLUMN is_default BOOLEAN NOT NULL DEFAULT false;
+
+-- A location can have at most one default role
+CREATE UNIQUE INDEX idx__location_role__default
+ON location_role (location_id)
+WHERE is_default;
@@ -0,0 +1 @@
+DROP INDEX idx__location__visible_approved;
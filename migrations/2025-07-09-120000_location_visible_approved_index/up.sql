@@ -0,0 +1,3 @@
+-- Backs the default public listing of visible, approved locations
+CREATE INDEX idx__location__visible_approved
+ON location (is_visible, approved_at);
@@ -0,0 +1,2 @@
+DROP INDEX idx__location_favourite__location_id;
+DROP TABLE location_favourite;
@@ -0,0 +1,19 @@
+CREATE TABLE location_favourite (
+	profile_id  INTEGER   NOT NULL,
+	location_id INTEGER   NOT NULL,
+	created_at  TIMESTAMP NOT NULL DEFAULT NOW(),
+
+	CONSTRAINT pk__location_favourite
+	PRIMARY KEY (profile_id, location_id),
+
+	CONSTRAINT fk__location_favourite__profile_id
+	FOREIGN KEY (profile_id) REFERENCES profile(id)
+	ON DELETE CASCADE,
+
+	CONSTRAINT fk__location_favourite__location_id
+	FOREIGN KEY (location_id) REFERENCES location(id)
+	ON DELETE CASCADE
+);
+
+CREATE INDEX idx__location_favourite__location_id
+ON location_favourite(location_id);
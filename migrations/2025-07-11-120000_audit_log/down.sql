@@ -0,0 +1,5 @@
+DROP INDEX idx__audit_log__target;
+DROP INDEX idx__audit_log__actor_id;
+DROP TABLE audit_log;
+DROP TYPE AUDIT_TARGET;
+DROP TYPE AUDIT_ACTION;
@@ -0,0 +1,34 @@
+CREATE TYPE AUDIT_ACTION AS ENUM (
+	'location_approved',
+	'location_rejected',
+	'profile_disabled',
+	'profile_activated',
+	'member_role_changed',
+	'authority_deleted',
+	'institution_deleted'
+);
+
+CREATE TYPE AUDIT_TARGET AS ENUM (
+	'location',
+	'profile',
+	'authority',
+	'institution'
+);
+
+CREATE TABLE audit_log (
+	id          SERIAL       PRIMARY KEY,
+	actor_id    INTEGER,
+	action      AUDIT_ACTION NOT NULL,
+	target_type AUDIT_TARGET NOT NULL,
+	target_id   INTEGER      NOT NULL,
+	detail      JSONB        NOT NULL DEFAULT '{}',
+	created_at  TIMESTAMP    NOT NULL DEFAULT NOW(),
+
+	-- Entries outlive the profile that made them
+	CONSTRAINT fk__audit_log__actor_id
+	FOREIGN KEY (actor_id) REFERENCES profile(id)
+	ON DELETE SET NULL
+);
+
+CREATE INDEX idx__audit_log__actor_id ON audit_log(actor_id);
+CREATE INDEX idx__audit_log__target ON audit_log(target_type, target_id);
@@ -0,0 +1,3 @@
+DROP INDEX idx__reservation__unreminded;
+
+ALTER TABLE reservation DROP COLUMN reminded_at;
@@ -0,0 +1,6 @@
+ALTER TABLE reservation
+ADD COLUMN reminded_at TIMESTAMP;
+
+CREATE INDEX idx__reservation__unreminded
+ON reservation(opening_time_id)
+WHERE reminded_at IS NULL AND state = 'created';
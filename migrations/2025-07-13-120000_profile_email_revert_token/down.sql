@@ -0,0 +1,4 @@
+ALTER TABLE profile
+DROP COLUMN email_revert_token,
+DROP COLUMN email_revert_token_expiry,
+DROP COLUMN previous_email;
@@ -0,0 +1,4 @@
+ALTER TABLE profile
+ADD COLUMN email_revert_token TEXT,
+ADD COLUMN email_revert_token_expiry TIMESTAMP,
+ADD COLUMN previous_email TEXT;
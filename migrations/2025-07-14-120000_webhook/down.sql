@@ -0,0 +1,2 @@
+DROP INDEX idx__webhook__authority_id;
+DROP TABLE webhook;
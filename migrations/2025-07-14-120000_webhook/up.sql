@@ -0,0 +1,32 @@
+CREATE TABLE webhook (
+	id                   SERIAL    PRIMARY KEY,
+	authority_id         INTEGER   NOT NULL,
+	url                  TEXT      NOT NULL,
+	secret               TEXT      NOT NULL,
+	enabled              BOOLEAN   NOT NULL DEFAULT TRUE,
+	events               BIGINT    NOT NULL DEFAULT 0,
+	last_delivery_at     TIMESTAMP,
+	last_delivery_status INTEGER,
+	last_delivery_error  TEXT,
+	created_at           TIMESTAMP NOT NULL DEFAULT NOW(),
+	created_by           INTEGER,
+	updated_at           TIMESTAMP NOT NULL DEFAULT NOW(),
+	updated_by           INTEGER,
+
+	CONSTRAINT fk__webhook__authority_id
+	FOREIGN KEY (authority_id) REFERENCES authority(id)
+	ON DELETE CASCADE,
+
+	CONSTRAINT fk__webhook__created_by
+	FOREIGN KEY (created_by) REFERENCES profile(id)
+	ON DELETE SET NULL,
+
+	CONSTRAINT fk__webhook__updated_by
+	FOREIGN KEY (updated_by) REFERENCES profile(id)
+	ON DELETE SET NULL
+);
+
+-- updated_at is set by the application, recording a delivery is not an
+-- update of the webhook itself
+
+CREATE INDEX idx__webhook__authority_id ON webhook(authority_id);
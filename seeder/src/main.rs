@@ -235,7 +235,9 @@ async fn seed_locations(conn: &DbConn, count: usize) -> Result<usize, Error> {
 			let excerpt_id = excerpts[i % excerpts.len()];
 			let seat_count = (10..100).fake_with_rng(&mut rng);
 			let is_reservable = rng.random_bool(0.4);
-			let max_reservation_length = (2..24).fake_with_rng(&mut rng);
+			let max_reservation_length = (2..24)
+				.fake_with_rng::<Option<i32>, _>(&mut rng)
+				.map(|hours| hours * 60);
 			let street = StreetName(EN).fake::<String>();
 			let number = (1..200).fake_with_rng::<u32, _>(&mut rng).to_string();
 			let zip = ZipCode(EN).fake::<String>();
@@ -4,6 +4,7 @@ use chrono::Duration;
 use deadpool_diesel::postgres::{Manager, Pool};
 use lettre::Address;
 use url::Url;
+use utils::image::ImageLimits;
 
 use crate::RedisConn;
 use crate::mailer::StubMailbox;
@@ -22,6 +23,26 @@ fn get_env_default(var: &str, default: impl Into<String>) -> String {
 	})
 }
 
+/// Check that a URL uses one of the given schemes and has a host
+fn check_url(
+	name: &str,
+	url: &Url,
+	schemes: &[&str],
+	errors: &mut Vec<String>,
+) {
+	if !schemes.contains(&url.scheme()) {
+		errors.push(format!(
+			"{name} must use one of the schemes {}, got '{}'",
+			schemes.join("/"),
+			url.scheme(),
+		));
+	}
+
+	if url.host().is_none() {
+		errors.push(format!("{name} must have a host"));
+	}
+}
+
 /// Configuration settings for the application
 #[derive(Clone, Debug)]
 pub struct Config {
@@ -37,13 +58,31 @@ pub struct Config {
 
 	pub email_confirmation_token_lifetime: Duration,
 	pub password_reset_token_lifetime:     Duration,
+	pub email_revert_token_lifetime:       Duration,
 
 	pub claims_cookie_name:     String,
 	pub access_cookie_name:     String,
 	pub access_cookie_lifetime: time::Duration,
 
+	pub login_rate_limit_per_minute:          u32,
+	pub register_rate_limit_per_minute:       u32,
+	pub password_reset_rate_limit_per_minute: u32,
+
+	pub member_import_max_rows: usize,
+
+	pub image_max_bytes:     usize,
+	pub image_max_dimension: u32,
+
+	pub reservation_reminder_interval_minutes: u64,
+	pub reservation_list_window:               Duration,
+
+	/// Allow webhooks to target loopback, private and link-local hosts over
+	/// plain http, only meant for local development and tests
+	pub webhook_allow_private_hosts: bool,
+
 	pub email_address:       Address,
 	pub email_queue_size:    usize,
+	pub email_max_retries:   u32,
 	pub email_smtp_server:   String,
 	pub email_smtp_password: String,
 }
@@ -81,6 +120,11 @@ impl Config {
 				.parse::<i64>()
 				.unwrap(),
 		);
+		let email_revert_token_lifetime = Duration::minutes(
+			get_env_default("EMAIL_REVERT_TOKEN_LIFETIME", "10080")
+				.parse::<i64>()
+				.unwrap(),
+		);
 
 		let claims_cookie_name =
 			get_env_default("CLAIMS_COOKIE_NAME", "blokmap_login_claims");
@@ -94,6 +138,47 @@ impl Config {
 				.unwrap(),
 		);
 
+		let login_rate_limit_per_minute =
+			get_env_default("LOGIN_RATE_LIMIT_PER_MINUTE", "10")
+				.parse::<u32>()
+				.expect("INVALID LOGIN RATE LIMIT");
+		let register_rate_limit_per_minute =
+			get_env_default("REGISTER_RATE_LIMIT_PER_MINUTE", "5")
+				.parse::<u32>()
+				.expect("INVALID REGISTER RATE LIMIT");
+		let password_reset_rate_limit_per_minute =
+			get_env_default("PASSWORD_RESET_RATE_LIMIT_PER_MINUTE", "5")
+				.parse::<u32>()
+				.expect("INVALID PASSWORD RESET RATE LIMIT");
+
+		let member_import_max_rows =
+			get_env_default("MEMBER_IMPORT_MAX_ROWS", "500")
+				.parse::<usize>()
+				.expect("INVALID MEMBER IMPORT MAX ROWS");
+
+		let image_max_bytes = get_env_default("IMAGE_MAX_BYTES", "5242880")
+			.parse::<usize>()
+			.expect("INVALID IMAGE MAX BYTES");
+		let image_max_dimension =
+			get_env_default("IMAGE_MAX_DIMENSION", "10000")
+				.parse::<u32>()
+				.expect("INVALID IMAGE MAX DIMENSION");
+
+		let reservation_reminder_interval_minutes =
+			get_env_default("RESERVATION_REMINDER_INTERVAL_MINUTES", "15")
+				.parse::<u64>()
+				.expect("INVALID RESERVATION REMINDER INTERVAL");
+		let reservation_list_window = Duration::days(
+			get_env_default("RESERVATION_LIST_WINDOW_DAYS", "31")
+				.parse::<i64>()
+				.expect("INVALID RESERVATION LIST WINDOW"),
+		);
+
+		let webhook_allow_private_hosts =
+			get_env_default("WEBHOOK_ALLOW_PRIVATE_HOSTS", "false")
+				.parse::<bool>()
+				.expect("INVALID WEBHOOK ALLOW PRIVATE HOSTS");
+
 		let email_address =
 			get_env_default("EMAIL_ADDRESS", "blokmap@gmail.com")
 				.parse::<Address>()
@@ -103,6 +188,10 @@ impl Config {
 			.parse::<usize>()
 			.expect("INVALID EMAIL QUEUE SIZE");
 
+		let email_max_retries = get_env_default("EMAIL_MAX_RETRIES", "5")
+			.parse::<u32>()
+			.expect("INVALID EMAIL MAX RETRIES");
+
 		let email_smtp_server = get_env_default("EMAIL_SMTP_SERVER", "stub");
 
 		let email_smtp_password =
@@ -123,16 +212,162 @@ impl Config {
 			static_url,
 			email_confirmation_token_lifetime,
 			password_reset_token_lifetime,
+			email_revert_token_lifetime,
 			claims_cookie_name,
 			access_cookie_name,
 			access_cookie_lifetime,
+			login_rate_limit_per_minute,
+			register_rate_limit_per_minute,
+			password_reset_rate_limit_per_minute,
+			member_import_max_rows,
+			image_max_bytes,
+			image_max_dimension,
+			reservation_reminder_interval_minutes,
+			reservation_list_window,
+			webhook_allow_private_hosts,
 			email_address,
 			email_queue_size,
+			email_max_retries,
 			email_smtp_server,
 			email_smtp_password,
 		}
 	}
 
+	/// Check the values of this [`Config`] for semantic errors
+	///
+	/// All problems are collected so they can be reported at once
+	pub fn validate(&self) -> Result<(), Vec<String>> {
+		let mut errors = vec![];
+
+		match self.database_url.parse::<Url>() {
+			Ok(url) => {
+				check_url(
+					"DATABASE_URL",
+					&url,
+					&["postgres", "postgresql"],
+					&mut errors,
+				);
+			},
+			Err(e) => errors.push(format!("DATABASE_URL is not a url: {e}")),
+		}
+
+		match self.redis_url.parse::<Url>() {
+			Ok(url) => {
+				check_url("REDIS_URL", &url, &["redis", "rediss"], &mut errors);
+			},
+			Err(e) => errors.push(format!("REDIS_URL is not a url: {e}")),
+		}
+
+		for (name, url) in [
+			("BACKEND_URL", &self.backend_url),
+			("FRONTEND_URL", &self.frontend_url),
+			("STATIC_URL", &self.static_url),
+		] {
+			check_url(name, url, &["http", "https"], &mut errors);
+		}
+
+		if self.email_confirmation_token_lifetime <= Duration::zero() {
+			errors.push(
+				"EMAIL_CONFIRMATION_TOKEN_LIFETIME must be positive"
+					.to_string(),
+			);
+		}
+
+		if self.password_reset_token_lifetime <= Duration::zero() {
+			errors.push(
+				"PASSWORD_RESET_TOKEN_LIFETIME must be positive".to_string(),
+			);
+		}
+
+		if self.email_revert_token_lifetime <= Duration::zero() {
+			errors.push(
+				"EMAIL_REVERT_TOKEN_LIFETIME must be positive".to_string(),
+			);
+		}
+
+		if !self.access_cookie_lifetime.is_positive() {
+			errors.push(
+				"ACCESS_COOKIE_LIFETIME_MINUTES must be positive".to_string(),
+			);
+		}
+
+		if self.claims_cookie_name.is_empty() {
+			errors.push("CLAIMS_COOKIE_NAME must not be empty".to_string());
+		}
+
+		if self.access_cookie_name.is_empty() {
+			errors.push("ACCESS_COOKIE_NAME must not be empty".to_string());
+		}
+
+		if self.claims_cookie_name == self.access_cookie_name {
+			errors.push(
+				"CLAIMS_COOKIE_NAME and ACCESS_COOKIE_NAME must differ"
+					.to_string(),
+			);
+		}
+
+		for (name, limit) in [
+			("LOGIN_RATE_LIMIT_PER_MINUTE", self.login_rate_limit_per_minute),
+			(
+				"REGISTER_RATE_LIMIT_PER_MINUTE",
+				self.register_rate_limit_per_minute,
+			),
+			(
+				"PASSWORD_RESET_RATE_LIMIT_PER_MINUTE",
+				self.password_reset_rate_limit_per_minute,
+			),
+		] {
+			if limit == 0 {
+				errors.push(format!("{name} must be at least 1"));
+			}
+		}
+
+		if self.member_import_max_rows == 0 {
+			errors
+				.push("MEMBER_IMPORT_MAX_ROWS must be at least 1".to_string());
+		}
+
+		if self.image_max_bytes == 0 {
+			errors.push("IMAGE_MAX_BYTES must be at least 1".to_string());
+		}
+
+		if self.image_max_dimension == 0 {
+			errors.push("IMAGE_MAX_DIMENSION must be at least 1".to_string());
+		}
+
+		if self.reservation_reminder_interval_minutes == 0 {
+			errors.push(
+				"RESERVATION_REMINDER_INTERVAL_MINUTES must be at least 1"
+					.to_string(),
+			);
+		}
+
+		if self.reservation_list_window <= Duration::zero() {
+			errors.push(
+				"RESERVATION_LIST_WINDOW_DAYS must be positive".to_string(),
+			);
+		}
+
+		if self.email_queue_size == 0 {
+			errors.push("EMAIL_QUEUE_SIZE must be at least 1".to_string());
+		}
+
+		if self.email_smtp_server.is_empty() {
+			errors.push("EMAIL_SMTP_SERVER must not be empty".to_string());
+		}
+
+		if errors.is_empty() { Ok(()) } else { Err(errors) }
+	}
+
+	/// Get the [`ImageLimits`] uploaded images are checked against
+	#[must_use]
+	pub fn image_limits(&self) -> ImageLimits {
+		ImageLimits {
+			max_bytes:     self.image_max_bytes,
+			max_dimension: self.image_max_dimension,
+		}
+	}
+
 	/// Create a database pool for the given config
 	///
 	/// # Panics
@@ -0,0 +1,32 @@
+//! Controllers for the [`AuditLog`]
+
+use audit_log::{AuditLog, AuditLogFilter};
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use common::{DbPool, Error};
+
+use crate::AdminSession;
+use crate::schemas::audit_log::AuditLogResponse;
+use crate::schemas::pagination::PaginationOptions;
+
+/// Get the audit log entries matching a filter, newest first
+#[instrument(skip(pool))]
+pub(crate) async fn get_audit_log(
+	State(pool): State<DbPool>,
+	session: AdminSession,
+	Query(filter): Query<AuditLogFilter>,
+	Query(p_opts): Query<PaginationOptions>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let (total, truncated, entries) =
+		AuditLog::get_all(filter, p_opts.into(), &conn).await?;
+	let response: Vec<_> =
+		entries.into_iter().map(AuditLogResponse::from).collect();
+
+	let response = p_opts.paginate(total, truncated, response);
+
+	Ok((StatusCode::OK, Json(response)))
+}
@@ -8,7 +8,7 @@ use axum::response::{IntoResponse, NoContent};
 use axum_extra::extract::PrivateCookieJar;
 use axum_extra::extract::cookie::Cookie;
 use chrono::Utc;
-use common::{DbPool, Error, LoginError, RedisConn, TokenError};
+use common::{DbPool, Error, ErrorResponse, LoginError, RedisConn, TokenError};
 use db::ProfileState;
 use profile::{NewProfile, Profile};
 use time::Duration;
@@ -24,8 +24,24 @@ use crate::schemas::auth::{
 	RegisterRequest,
 };
 use crate::schemas::profile::ProfileResponse;
-use crate::{Config, Session};
-
+use crate::{Config, Session, SessionClient};
+
+#[utoipa::path(
+	post,
+	path = "/auth/register",
+	tag = "auth",
+	request_body = RegisterRequest,
+	responses(
+		(status = 201, description = "The new profile", body = ProfileResponse),
+		(
+			status = 409,
+			description = "Name or email taken",
+			body = ErrorResponse
+		),
+		(status = 422, description = "Invalid data", body = ErrorResponse),
+		(status = 429, description = "Rate limited", body = ErrorResponse)
+	)
+)]
 #[instrument(skip(pool, r_conn, config, mailer, jar))]
 pub(crate) async fn register_profile(
 	State(pool): State<DbPool>,
@@ -33,6 +49,7 @@ pub(crate) async fn register_profile(
 	State(config): State<Config>,
 	State(mailer): State<Mailer>,
 	jar: PrivateCookieJar,
+	client: SessionClient,
 	Json(register_data): Json<RegisterRequest>,
 ) -> Result<impl IntoResponse, Error> {
 	register_data.validate()?;
@@ -60,6 +77,7 @@ pub(crate) async fn register_profile(
 		let session = Session::create(
 			config.access_cookie_lifetime,
 			&profile,
+			client,
 			&mut r_conn,
 		)
 		.await?;
@@ -107,6 +125,16 @@ pub(crate) async fn register_profile(
 	}
 }
 
+#[utoipa::path(
+	post,
+	path = "/auth/resend_confirmation_email/{token}",
+	tag = "auth",
+	params(("token" = i32, Path, description = "Id of the profile")),
+	responses(
+		(status = 204, description = "Sent a new confirmation email"),
+		(status = 404, description = "Unknown profile", body = ErrorResponse)
+	)
+)]
 pub(crate) async fn resend_confirmation_email(
 	State(pool): State<DbPool>,
 	State(config): State<Config>,
@@ -137,12 +165,24 @@ pub(crate) async fn resend_confirmation_email(
 	Ok(NoContent)
 }
 
+#[utoipa::path(
+	post,
+	path = "/auth/confirm_email/{token}",
+	tag = "auth",
+	params(("token" = String, Path, description = "Email confirmation token")),
+	responses(
+		(status = 204, description = "Confirmed the email and logged in"),
+		(status = 403, description = "Token expired", body = ErrorResponse),
+		(status = 404, description = "Unknown token", body = ErrorResponse)
+	)
+)]
 #[instrument(skip(pool, r_conn, config, jar))]
 pub(crate) async fn confirm_email(
 	State(pool): State<DbPool>,
 	State(mut r_conn): State<RedisConn>,
 	State(config): State<Config>,
 	jar: PrivateCookieJar,
+	client: SessionClient,
 	Path(token): Path<String>,
 ) -> Result<(PrivateCookieJar, NoContent), Error> {
 	let conn = pool.get().await?;
@@ -156,11 +196,15 @@ pub(crate) async fn confirm_email(
 		return Err(TokenError::ExpiredEmailToken.into());
 	}
 
-	profile.confirm_email(&conn).await?;
+	let profile = profile.confirm_email(&conn).await?;
 
-	let session =
-		Session::create(config.access_cookie_lifetime, &profile, &mut r_conn)
-			.await?;
+	let session = Session::create(
+		config.access_cookie_lifetime,
+		&profile,
+		client,
+		&mut r_conn,
+	)
+	.await?;
 
 	let access_token_cookie = session.to_access_token_cookie(
 		config.access_cookie_name,
@@ -177,6 +221,64 @@ pub(crate) async fn confirm_email(
 	Ok((jar, NoContent))
 }
 
+/// Undo an email change from the link sent to the previous email
+///
+/// The previous email is restored even if the new one was already confirmed.
+/// All sessions of the profile are revoked, as the change may have been made
+/// from a stolen session
+#[utoipa::path(
+	post,
+	path = "/auth/revert-email/{token}",
+	tag = "auth",
+	params(("token" = String, Path, description = "Email revert token")),
+	responses(
+		(status = 204, description = "Reverted the email change"),
+		(status = 403, description = "Token expired", body = ErrorResponse),
+		(status = 404, description = "Unknown token", body = ErrorResponse)
+	)
+)]
+#[instrument(skip(pool, r_conn, config, jar))]
+pub(crate) async fn revert_email(
+	State(pool): State<DbPool>,
+	State(mut r_conn): State<RedisConn>,
+	State(config): State<Config>,
+	jar: PrivateCookieJar,
+	Path(token): Path<String>,
+) -> Result<(PrivateCookieJar, NoContent), Error> {
+	let conn = pool.get().await?;
+	let profile = Profile::get_by_email_revert_token(token, &conn).await?;
+
+	// Unwrap is safe because profiles with a revert token will always have a
+	// token expiry
+	let expiry = profile.primitive.email_revert_token_expiry.unwrap();
+	if Utc::now().naive_utc() > expiry {
+		return Err(TokenError::ExpiredRevertToken.into());
+	}
+
+	let profile = profile.revert_pending_email(&conn).await?;
+
+	Session::delete_for_profile(profile.primitive.id, None, &mut r_conn)
+		.await?;
+
+	let access_token = Cookie::build(config.access_cookie_name).path("/");
+	let jar = jar.remove(access_token);
+
+	info!("reverted email change for profile {}", profile.primitive.id);
+
+	Ok((jar, NoContent))
+}
+
+#[utoipa::path(
+	post,
+	path = "/auth/request_password_reset",
+	tag = "auth",
+	request_body = PasswordResetRequest,
+	responses(
+		(status = 204, description = "Sent a password reset email"),
+		(status = 404, description = "Unknown profile", body = ErrorResponse),
+		(status = 429, description = "Rate limited", body = ErrorResponse)
+	)
+)]
 #[instrument(skip(pool, config, mailer, request))]
 pub(crate) async fn request_password_reset(
 	State(pool): State<DbPool>,
@@ -208,12 +310,26 @@ pub(crate) async fn request_password_reset(
 	Ok(NoContent)
 }
 
+#[utoipa::path(
+	post,
+	path = "/auth/reset_password",
+	tag = "auth",
+	request_body = PasswordResetData,
+	responses(
+		(status = 204, description = "Changed the password and logged in"),
+		(status = 403, description = "Token expired", body = ErrorResponse),
+		(status = 404, description = "Unknown token", body = ErrorResponse),
+		(status = 422, description = "Invalid data", body = ErrorResponse),
+		(status = 429, description = "Rate limited", body = ErrorResponse)
+	)
+)]
 #[instrument(skip_all)]
 pub(crate) async fn reset_password(
 	State(pool): State<DbPool>,
 	State(config): State<Config>,
 	State(mut r_conn): State<RedisConn>,
 	jar: PrivateCookieJar,
+	client: SessionClient,
 	Json(request): Json<PasswordResetData>,
 ) -> Result<(PrivateCookieJar, NoContent), Error> {
 	request.validate()?;
@@ -231,9 +347,13 @@ pub(crate) async fn reset_password(
 
 	let profile = profile.change_password(&request.password, &conn).await?;
 
-	let session =
-		Session::create(config.access_cookie_lifetime, &profile, &mut r_conn)
-			.await?;
+	let session = Session::create(
+		config.access_cookie_lifetime,
+		&profile,
+		client,
+		&mut r_conn,
+	)
+	.await?;
 
 	let access_token_cookie = session.to_access_token_cookie(
 		config.access_cookie_name,
@@ -250,12 +370,25 @@ pub(crate) async fn reset_password(
 	Ok((jar, NoContent))
 }
 
+#[utoipa::path(
+	post,
+	path = "/auth/login",
+	tag = "auth",
+	request_body = LoginRequest,
+	responses(
+		(status = 204, description = "Logged in, sets the access token cookie"),
+		(status = 403, description = "Login failed", body = ErrorResponse),
+		(status = 404, description = "Unknown profile", body = ErrorResponse),
+		(status = 429, description = "Rate limited", body = ErrorResponse)
+	)
+)]
 #[instrument(skip_all)]
 pub(crate) async fn login_profile(
 	State(pool): State<DbPool>,
 	State(mut r_conn): State<RedisConn>,
 	State(config): State<Config>,
 	jar: PrivateCookieJar,
+	client: SessionClient,
 	Json(login_data): Json<LoginRequest>,
 ) -> Result<(PrivateCookieJar, NoContent), Error> {
 	let conn = pool.get().await?;
@@ -282,7 +415,8 @@ pub(crate) async fn login_profile(
 	};
 
 	let session =
-		Session::create(access_token_lifetime, &profile, &mut r_conn).await?;
+		Session::create(access_token_lifetime, &profile, client, &mut r_conn)
+			.await?;
 
 	let access_token_cookie = session.to_access_token_cookie(
 		config.access_cookie_name,
@@ -299,6 +433,16 @@ pub(crate) async fn login_profile(
 	Ok((jar, NoContent))
 }
 
+#[utoipa::path(
+	post,
+	path = "/auth/logout",
+	tag = "auth",
+	responses(
+		(status = 204, description = "Logged out"),
+		(status = 401, description = "Not logged in", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(config, jar))]
 pub(crate) async fn logout_profile(
 	State(config): State<Config>,
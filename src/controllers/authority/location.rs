@@ -1,25 +1,42 @@
-use ::location::{Location, LocationIncludes};
+use std::io;
+
+use ::location::{Location, LocationExportRow, LocationIncludes};
 use axum::Json;
+use axum::body::Body;
 use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
+use axum::http::{StatusCode, header};
 use axum::response::IntoResponse;
 use common::{DbPool, Error};
+use futures::{StreamExt, stream};
 use permissions::{
 	AuthorityPermissions,
 	InstitutionPermissions,
 	check_authority_perms,
 };
+use webhook::WebhookEvent;
 
 use crate::schemas::BuildResponse;
-use crate::schemas::location::{CreateLocationRequest, LocationResponse};
+use crate::schemas::includes::Includes;
+use crate::schemas::location::{
+	CreateLocationRequest,
+	LOCATION_EXPORT_CSV_HEADER,
+	LocationExportOptions,
+	LocationResponse,
+	location_export_csv_line,
+};
+use crate::webhooks::WebhookDispatcher;
 use crate::{Config, Session};
 
-#[instrument(skip(pool))]
+/// The amount of rows a location export buffers for slow clients
+const EXPORT_BUFFER_ROWS: usize = 64;
+
+#[instrument(skip(pool, webhooks))]
 pub(crate) async fn add_authority_location(
 	State(pool): State<DbPool>,
 	State(config): State<Config>,
+	State(webhooks): State<WebhookDispatcher>,
 	session: Session,
-	Query(includes): Query<LocationIncludes>,
+	Includes(includes): Includes<LocationIncludes>,
 	Path(id): Path<i32>,
 	Json(request): Json<CreateLocationRequest>,
 ) -> Result<impl IntoResponse, Error> {
@@ -40,6 +57,12 @@ pub(crate) async fn add_authority_location(
 	let records = new_location.insert(includes, &conn).await?;
 	let response = records.build_response(includes, &config)?;
 
+	webhooks.dispatch_location_event(
+		id,
+		WebhookEvent::LocationCreated,
+		response.clone(),
+	);
+
 	Ok((StatusCode::CREATED, Json(response)))
 }
 
@@ -47,7 +70,7 @@ pub(crate) async fn add_authority_location(
 pub(crate) async fn get_authority_locations(
 	State(pool): State<DbPool>,
 	State(config): State<Config>,
-	Query(includes): Query<LocationIncludes>,
+	Includes(includes): Includes<LocationIncludes>,
 	Path(id): Path<i32>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
@@ -60,3 +83,70 @@ pub(crate) async fn get_authority_locations(
 
 	Ok((StatusCode::OK, Json(response)))
 }
+
+/// Export the locations of an authority as a CSV file
+///
+/// The file is streamed to the client while the rows are read from the
+/// database. An error halfway through aborts the response, as the status has
+/// been sent already
+#[instrument(skip(pool))]
+pub(crate) async fn export_authority_locations(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+	Query(opts): Query<LocationExportOptions>,
+) -> Result<impl IntoResponse, Error> {
+	check_authority_perms(
+		id,
+		session.data.profile_id,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	let (sender, receiver) = tokio::sync::mpsc::channel(EXPORT_BUFFER_ROWS);
+
+	tokio::spawn(async move {
+		let error_sender = sender.clone();
+
+		if let Err(e) = LocationExportRow::stream_for_authority(
+			id, opts.from, opts.to, sender, &conn,
+		)
+		.await
+		{
+			let _ = error_sender.send(Err(e)).await;
+		}
+	});
+
+	let lines = stream::unfold(receiver, |mut receiver| {
+		async move {
+			let line = match receiver.recv().await? {
+				Ok(row) => Ok(location_export_csv_line(&row)),
+				Err(e) => {
+					error!(
+						"error exporting locations of authority {id}: {e:?}"
+					);
+
+					Err(io::Error::other(e.to_string()))
+				},
+			};
+
+			Some((line, receiver))
+		}
+	});
+	let csv_header =
+		stream::once(async { Ok(LOCATION_EXPORT_CSV_HEADER.to_string()) });
+
+	let headers = [
+		(header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+		(
+			header::CONTENT_DISPOSITION,
+			format!("attachment; filename=\"authority-{id}-locations.csv\""),
+		),
+	];
+
+	Ok((StatusCode::OK, headers, Body::from_stream(csv_header.chain(lines))))
+}
@@ -1,21 +1,28 @@
+use audit_log::AuditLog;
 use authority::Authority;
 use axum::Json;
-use axum::extract::{Path, Query, State};
+use axum::extract::{Multipart, Path, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use common::{DbPool, Error};
+use db::{AuditAction, AuditTarget};
 use location::LocationIncludes;
 use permissions::{
 	AuthorityPermissions,
 	InstitutionPermissions,
 	check_authority_perms,
+	check_authority_role_grant,
 };
+use serde_json::json;
 
 use crate::schemas::BuildResponse;
 use crate::schemas::authority::{
 	AuthorityMemberUpdateRequest,
 	CreateAuthorityMemberRequest,
+	ImportAuthorityMembersRequest,
+	MemberImportResponse,
 };
+use crate::schemas::includes::Includes;
 use crate::schemas::profile::ProfileResponse;
 use crate::{Config, Session};
 
@@ -24,7 +31,7 @@ pub(crate) async fn add_authority_member(
 	State(pool): State<DbPool>,
 	State(config): State<Config>,
 	session: Session,
-	Query(includes): Query<LocationIncludes>,
+	Includes(includes): Includes<LocationIncludes>,
 	Path(id): Path<i32>,
 	Json(request): Json<CreateAuthorityMemberRequest>,
 ) -> Result<impl IntoResponse, Error> {
@@ -38,6 +45,14 @@ pub(crate) async fn add_authority_member(
 	)
 	.await?;
 
+	check_authority_role_grant(
+		id,
+		session.data.profile_id,
+		request.authority_role_id,
+		&pool,
+	)
+	.await?;
+
 	let conn = pool.get().await?;
 
 	let new_auth_profile = request.to_insertable(id, session.data.profile_id);
@@ -47,6 +62,47 @@ pub(crate) async fn add_authority_member(
 	Ok((StatusCode::CREATED, Json(response)))
 }
 
+#[instrument(skip(pool, data))]
+pub(crate) async fn import_authority_members(
+	State(pool): State<DbPool>,
+	State(config): State<Config>,
+	session: Session,
+	Path(id): Path<i32>,
+	mut data: Multipart,
+) -> Result<impl IntoResponse, Error> {
+	check_authority_perms(
+		id,
+		session.data.profile_id,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let request = ImportAuthorityMembersRequest::parse(
+		&mut data,
+		config.member_import_max_rows,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	let mut summary = Authority::import_members(
+		id,
+		request.rows,
+		session.data.profile_id,
+		&conn,
+	)
+	.await?;
+
+	summary.errors.extend(request.malformed);
+	summary.errors.sort_by_key(|issue| issue.line);
+
+	let response = MemberImportResponse::from(summary);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
 #[instrument(skip(pool))]
 pub(crate) async fn get_authority_members(
 	State(pool): State<DbPool>,
@@ -83,21 +139,46 @@ pub async fn update_authority_member(
 	Path((auth_id, prof_id)): Path<(i32, i32)>,
 	Json(request): Json<AuthorityMemberUpdateRequest>,
 ) -> Result<impl IntoResponse, Error> {
-	check_authority_perms(
-		auth_id,
-		session.data.profile_id,
-		AuthorityPermissions::Administrator
-			| AuthorityPermissions::ManageMembers,
-		InstitutionPermissions::Administrator,
-		&pool,
-	)
-	.await?;
+	// Platform admins can grant any role
+	if !session.data.is_admin {
+		check_authority_perms(
+			auth_id,
+			session.data.profile_id,
+			AuthorityPermissions::Administrator
+				| AuthorityPermissions::ManageMembers,
+			InstitutionPermissions::Administrator,
+			&pool,
+		)
+		.await?;
+
+		check_authority_role_grant(
+			auth_id,
+			session.data.profile_id,
+			request.authority_role_id,
+			&pool,
+		)
+		.await?;
+	}
 
 	let conn = pool.get().await?;
 
+	let detail =
+		json!({ "profileId": prof_id, "roleId": request.authority_role_id });
+
 	let member_update = request.to_insertable(session.data.profile_id);
 	let updated_member =
 		member_update.apply_to(auth_id, prof_id, &conn).await?;
+
+	AuditLog::record(
+		session.data.profile_id,
+		AuditAction::MemberRoleChanged,
+		AuditTarget::Authority,
+		auth_id,
+		detail,
+		&conn,
+	)
+	.await;
+
 	let response = updated_member.build_response((), &config)?;
 
 	Ok((StatusCode::CREATED, Json(response)))
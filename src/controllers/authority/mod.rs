@@ -1,41 +1,80 @@
+use audit_log::AuditLog;
 use authority::{Authority, AuthorityIncludes};
 use axum::Json;
 use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
-use axum::response::IntoResponse;
-use common::{DbPool, Error};
+use axum::response::{IntoResponse, NoContent};
+use common::{DbConn, DbPool, Error};
+use db::{AuditAction, AuditTarget};
 use permissions::{
 	AuthorityPermissions,
+	EffectivePermissions,
 	InstitutionPermissions,
 	check_authority_perms,
 };
+use reservation::{Reservation, ReservationFilter, ReservationIncludes};
+use serde_json::json;
+use validator::Validate;
 
 use crate::schemas::BuildResponse;
 use crate::schemas::authority::{
 	AuthorityResponse,
+	AuthoritySearchOptions,
+	AuthorityStatisticsResponse,
 	CreateAuthorityRequest,
+	NameCheckOptions,
+	NameCheckResponse,
 	UpdateAuthorityRequest,
 };
+use crate::schemas::includes::Includes;
+use crate::schemas::pagination::PaginationOptions;
+use crate::schemas::permissions::AuthorityPermissionsResponse;
+use crate::schemas::reservation::ReservationResponse;
 use crate::{Config, Session};
 
 mod location;
 mod member;
 mod role;
+mod webhook;
 
 pub(crate) use location::*;
 pub(crate) use member::*;
 pub(crate) use role::*;
+pub(crate) use webhook::*;
+
+/// Validate a [`CreateAuthorityRequest`] and make sure its name is not in use
+/// by another authority yet
+pub(crate) async fn check_new_authority(
+	request: &CreateAuthorityRequest,
+	conn: &DbConn,
+) -> Result<(), Error> {
+	request.validate()?;
+
+	if !Authority::name_is_available(&request.name, conn).await? {
+		return Err(Error::Duplicate {
+			message:     format!(
+				"an authority named '{}' already exists",
+				request.name
+			),
+			resource_id: None,
+		});
+	}
+
+	Ok(())
+}
 
 #[instrument(skip(pool))]
 pub async fn create_authority(
 	State(config): State<Config>,
 	State(pool): State<DbPool>,
 	session: Session,
-	Query(includes): Query<AuthorityIncludes>,
+	Includes(includes): Includes<AuthorityIncludes>,
 	Json(request): Json<CreateAuthorityRequest>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
 
+	check_new_authority(&request, &conn).await?;
+
 	let new_auth = request.to_insertable(session.data.profile_id);
 	let auth = new_auth.insert(includes, &conn).await?;
 	let response = auth.build_response(includes, &config)?;
@@ -47,7 +86,7 @@ pub async fn create_authority(
 pub async fn get_all_authorities(
 	State(config): State<Config>,
 	State(pool): State<DbPool>,
-	Query(includes): Query<AuthorityIncludes>,
+	Includes(includes): Includes<AuthorityIncludes>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
 
@@ -60,11 +99,43 @@ pub async fn get_all_authorities(
 	Ok((StatusCode::OK, Json(response)))
 }
 
+#[instrument(skip(pool))]
+pub async fn search_authorities(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	Query(options): Query<AuthoritySearchOptions>,
+) -> Result<impl IntoResponse, Error> {
+	options.validate()?;
+
+	let conn = pool.get().await?;
+
+	let authorities =
+		Authority::get_by_name(&options.q, options.limit, &conn).await?;
+	let response: Vec<AuthorityResponse> = authorities
+		.into_iter()
+		.map(|a| a.build_response(AuthorityIncludes::default(), &config))
+		.collect::<Result<_, _>>()?;
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+#[instrument(skip(pool))]
+pub async fn check_authority_name(
+	State(pool): State<DbPool>,
+	Query(options): Query<NameCheckOptions>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let available = Authority::name_is_available(&options.name, &conn).await?;
+
+	Ok((StatusCode::OK, Json(NameCheckResponse { available })))
+}
+
 #[instrument(skip(pool))]
 pub async fn get_authority(
 	State(config): State<Config>,
 	State(pool): State<DbPool>,
-	Query(includes): Query<AuthorityIncludes>,
+	Includes(includes): Includes<AuthorityIncludes>,
 	Path(id): Path<i32>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
@@ -80,7 +151,7 @@ pub async fn update_authority(
 	State(config): State<Config>,
 	State(pool): State<DbPool>,
 	session: Session,
-	Query(includes): Query<AuthorityIncludes>,
+	Includes(includes): Includes<AuthorityIncludes>,
 	Path(id): Path<i32>,
 	Json(request): Json<UpdateAuthorityRequest>,
 ) -> Result<impl IntoResponse, Error> {
@@ -101,3 +172,116 @@ pub async fn update_authority(
 
 	Ok((StatusCode::OK, Json(response)))
 }
+
+/// Delete an [`Authority`]
+#[instrument(skip(pool))]
+pub async fn delete_authority(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+) -> Result<NoContent, Error> {
+	check_authority_perms(
+		id,
+		session.data.profile_id,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	Authority::delete_by_id(id, &conn).await?;
+
+	AuditLog::record(
+		session.data.profile_id,
+		AuditAction::AuthorityDeleted,
+		AuditTarget::Authority,
+		id,
+		json!({}),
+		&conn,
+	)
+	.await;
+
+	Ok(NoContent)
+}
+
+/// Get the dashboard statistics of an [`Authority`]
+#[instrument(skip(pool))]
+pub async fn get_authority_statistics(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	check_authority_perms(
+		id,
+		session.data.profile_id,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	let statistics = Authority::get_statistics(id, &conn).await?;
+	let response = AuthorityStatisticsResponse::from(statistics);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Get a page of the reservations for all locations of an [`Authority`]
+#[instrument(skip(pool))]
+pub async fn get_authority_reservations(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+	Query(filter): Query<ReservationFilter>,
+	Includes(includes): Includes<ReservationIncludes>,
+	Query(p_opts): Query<PaginationOptions>,
+) -> Result<impl IntoResponse, Error> {
+	check_authority_perms(
+		id,
+		session.data.profile_id,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	let (total, truncated, reservations) = Reservation::get_for_authority(
+		id,
+		filter,
+		includes,
+		p_opts.into(),
+		&conn,
+	)
+	.await?;
+	let reservations: Vec<ReservationResponse> = reservations
+		.into_iter()
+		.map(|r| r.build_response(includes, &config))
+		.collect::<Result<_, _>>()?;
+
+	let paginated = p_opts.paginate(total, truncated, reservations);
+
+	Ok((StatusCode::OK, Json(paginated)))
+}
+
+/// Get the effective permissions of the current profile for an authority,
+/// including those it holds through the institution
+#[instrument(skip(pool))]
+pub(crate) async fn get_authority_permissions(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	let permissions =
+		EffectivePermissions::for_authority(id, session.data.profile_id, &pool)
+			.await?;
+	let response = AuthorityPermissionsResponse::from(permissions);
+
+	Ok((StatusCode::OK, Json(response)))
+}
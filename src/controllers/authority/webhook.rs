@@ -0,0 +1,201 @@
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, NoContent};
+use common::{DbPool, Error};
+use permissions::{
+	AuthorityPermissions,
+	InstitutionPermissions,
+	check_authority_perms,
+};
+use url::Url;
+use uuid::Uuid;
+use validator::Validate;
+use webhook::{Webhook, WebhookEvents};
+
+use crate::schemas::webhook::{
+	CreateWebhookRequest,
+	UpdateWebhookRequest,
+	WebhookResponse,
+};
+use crate::webhooks::check_webhook_host;
+use crate::{Config, Session};
+
+/// Generate a new secret to sign the deliveries to a webhook with
+fn generate_webhook_secret() -> String {
+	format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Check the parts of a webhook request that can not be validated on their
+/// own
+///
+/// Production deployments only deliver to `https` urls on public hosts,
+/// unless private hosts are explicitly allowed
+fn check_webhook(
+	url: Option<&str>,
+	events: Option<i64>,
+	config: &Config,
+) -> Result<(), Error> {
+	if let Some(url) = url {
+		let url = url.parse::<Url>().map_err(|e| {
+			Error::BadRequest(format!("invalid webhook url: {e}"))
+		})?;
+		let scheme = url.scheme();
+		let allow_http =
+			!config.production || config.webhook_allow_private_hosts;
+
+		if scheme != "https" && !(allow_http && scheme == "http") {
+			return Err(Error::BadRequest(format!(
+				"webhooks can not be delivered over '{scheme}'"
+			)));
+		}
+
+		check_webhook_host(&url, config.webhook_allow_private_hosts)?;
+	}
+
+	if events.is_some_and(|e| WebhookEvents::from_bits(e).is_none()) {
+		return Err(Error::BadRequest("unknown webhook events".to_string()));
+	}
+
+	Ok(())
+}
+
+#[instrument(skip(pool))]
+pub(crate) async fn create_authority_webhook(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(auth_id): Path<i32>,
+	Json(request): Json<CreateWebhookRequest>,
+) -> Result<impl IntoResponse, Error> {
+	check_authority_perms(
+		auth_id,
+		session.data.profile_id,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	request.validate()?;
+	check_webhook(Some(&request.url), Some(request.events), &config)?;
+
+	let conn = pool.get().await?;
+
+	let new_webhook = request.to_insertable(
+		auth_id,
+		generate_webhook_secret(),
+		session.data.profile_id,
+	);
+	let webhook = new_webhook.insert(&conn).await?;
+	let response = WebhookResponse::with_secret(webhook);
+
+	Ok((StatusCode::CREATED, Json(response)))
+}
+
+#[instrument(skip(pool))]
+pub(crate) async fn get_authority_webhooks(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(auth_id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	check_authority_perms(
+		auth_id,
+		session.data.profile_id,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	let webhooks = Webhook::get_for_authority(auth_id, &conn).await?;
+	let response: Vec<WebhookResponse> =
+		webhooks.into_iter().map(Into::into).collect();
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+#[instrument(skip(pool))]
+pub(crate) async fn get_authority_webhook(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path((auth_id, webhook_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, Error> {
+	check_authority_perms(
+		auth_id,
+		session.data.profile_id,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	let webhook = Webhook::get_by_id(auth_id, webhook_id, &conn).await?;
+	let response = WebhookResponse::from(webhook);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+#[instrument(skip(pool))]
+pub(crate) async fn update_authority_webhook(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: Session,
+	Path((auth_id, webhook_id)): Path<(i32, i32)>,
+	Json(request): Json<UpdateWebhookRequest>,
+) -> Result<impl IntoResponse, Error> {
+	check_authority_perms(
+		auth_id,
+		session.data.profile_id,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	request.validate()?;
+	check_webhook(request.url.as_deref(), request.events, &config)?;
+
+	let conn = pool.get().await?;
+
+	let rotate_secret = request.rotate_secret;
+	let secret = rotate_secret.then(generate_webhook_secret);
+
+	let webhook_update = request.to_insertable(secret, session.data.profile_id);
+	let webhook = webhook_update.apply_to(auth_id, webhook_id, &conn).await?;
+
+	// The secret is only shown again when it changes
+	let response = if rotate_secret {
+		WebhookResponse::with_secret(webhook)
+	} else {
+		WebhookResponse::from(webhook)
+	};
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+#[instrument(skip(pool))]
+pub(crate) async fn delete_authority_webhook(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path((auth_id, webhook_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, Error> {
+	check_authority_perms(
+		auth_id,
+		session.data.profile_id,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	Webhook::delete_by_id(auth_id, webhook_id, &conn).await?;
+
+	Ok((StatusCode::NO_CONTENT, NoContent))
+}
@@ -1,13 +1,15 @@
 use ::authority::{AuthorityIncludes, AuthorityUpdate};
 use axum::Json;
-use axum::extract::{Path, Query, State};
+use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use common::{DbPool, Error};
 use permissions::{InstitutionPermissions, check_institution_perms};
 
+use crate::controllers::authority::check_new_authority;
 use crate::schemas::BuildResponse;
 use crate::schemas::authority::CreateAuthorityRequest;
+use crate::schemas::includes::Includes;
 use crate::{Config, Session};
 
 #[instrument(skip(pool))]
@@ -16,7 +18,7 @@ pub async fn create_institution_authority(
 	State(pool): State<DbPool>,
 	session: Session,
 	Path(i_id): Path<i32>,
-	Query(includes): Query<AuthorityIncludes>,
+	Includes(includes): Includes<AuthorityIncludes>,
 	Json(request): Json<CreateAuthorityRequest>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
@@ -30,6 +32,8 @@ pub async fn create_institution_authority(
 	)
 	.await?;
 
+	check_new_authority(&request, &conn).await?;
+
 	let mut new_authority = request.to_insertable(session.data.profile_id);
 	new_authority.institution_id = Some(i_id);
 	let new_authority = new_authority.insert(includes, &conn).await?;
@@ -44,7 +48,7 @@ pub async fn link_authority(
 	State(pool): State<DbPool>,
 	session: Session,
 	Path((i_id, a_id)): Path<(i32, i32)>,
-	Query(includes): Query<AuthorityIncludes>,
+	Includes(includes): Includes<AuthorityIncludes>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
 
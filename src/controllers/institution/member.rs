@@ -1,10 +1,17 @@
+use audit_log::AuditLog;
 use axum::Json;
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use common::{DbPool, Error};
+use db::{AuditAction, AuditTarget};
 use institution::Institution;
-use permissions::{InstitutionPermissions, check_institution_perms};
+use permissions::{
+	InstitutionPermissions,
+	check_institution_perms,
+	check_institution_role_grant,
+};
+use serde_json::json;
 
 use crate::schemas::BuildResponse;
 use crate::schemas::institution::{
@@ -33,6 +40,14 @@ pub(crate) async fn add_institution_member(
 	)
 	.await?;
 
+	check_institution_role_grant(
+		id,
+		session.data.profile_id,
+		request.institution_role_id,
+		&conn,
+	)
+	.await?;
+
 	let new_inst_profile = request.to_insertable(id, session.data.profile_id);
 	let member = new_inst_profile.insert(&conn).await?;
 	let response = member.build_response((), &config)?;
@@ -86,9 +101,31 @@ pub async fn update_insitution_member(
 	)
 	.await?;
 
+	check_institution_role_grant(
+		inst_id,
+		session.data.profile_id,
+		request.institution_role_id,
+		&conn,
+	)
+	.await?;
+
+	let detail =
+		json!({ "profileId": prof_id, "roleId": request.institution_role_id });
+
 	let member_update = request.to_insertable(session.data.profile_id);
 	let updated_member =
 		member_update.apply_to(inst_id, prof_id, &conn).await?;
+
+	AuditLog::record(
+		session.data.profile_id,
+		AuditAction::MemberRoleChanged,
+		AuditTarget::Institution,
+		inst_id,
+		detail,
+		&conn,
+	)
+	.await;
+
 	let response = updated_member.build_response((), &config)?;
 
 	Ok((StatusCode::CREATED, Json(response)))
@@ -1,18 +1,32 @@
 use ::authority::AuthorityIncludes;
+use audit_log::AuditLog;
 use axum::Json;
 use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use axum::response::{IntoResponse, NoContent};
 use common::{DbPool, Error};
-use db::InstitutionCategory;
-use institution::{Institution, InstitutionIncludes};
+use db::{AuditAction, AuditTarget, InstitutionCategory};
+use institution::{Institution, InstitutionFilter, InstitutionIncludes};
+use permissions::{
+	EffectivePermissions,
+	InstitutionPermissions,
+	check_institution_perms,
+};
+use serde_json::json;
+use tag::{Tag, TagIncludes};
+use translation::Translation;
 
+use crate::controllers::authority::check_new_authority;
 use crate::schemas::BuildResponse;
 use crate::schemas::institution::{
 	CreateInstitutionRequest,
 	InstitutionResponse,
+	InstitutionStatisticsResponse,
+	UpdateInstitutionRequest,
 };
 use crate::schemas::pagination::PaginationOptions;
+use crate::schemas::permissions::InstitutionPermissionsResponse;
+use crate::schemas::tag::TagUsageResponse;
 use crate::{Config, Session};
 
 mod authority;
@@ -33,6 +47,33 @@ pub async fn create_institution(
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
 
+	let names = [
+		&request.name_translation.nl,
+		&request.name_translation.en,
+		&request.name_translation.fr,
+		&request.name_translation.de,
+	];
+
+	for name in names.into_iter().flatten() {
+		let available =
+			Institution::name_is_available(name, request.category, &conn)
+				.await?;
+
+		if !available {
+			return Err(Error::Duplicate {
+				message:     format!(
+					"an institution named '{name}' already exists in this \
+					 category"
+				),
+				resource_id: None,
+			});
+		}
+	}
+
+	if let Some(authority_request) = &request.authority {
+		check_new_authority(authority_request, &conn).await?;
+	}
+
 	let (new_institution, authority_request) =
 		request.to_insertable(session.data.profile_id);
 	let institution = new_institution.insert(includes, &conn).await?;
@@ -58,19 +99,21 @@ pub async fn create_institution(
 pub async fn get_all_institutions(
 	State(config): State<Config>,
 	State(pool): State<DbPool>,
+	Query(filter): Query<InstitutionFilter>,
 	Query(includes): Query<InstitutionIncludes>,
 	Query(p_opts): Query<PaginationOptions>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
 
 	let (total, truncated, institutions) =
-		Institution::get_all(includes, p_opts.into(), &conn).await?;
+		Institution::get_all(filter, includes, p_opts.into(), &conn).await?;
 	let institutions: Vec<InstitutionResponse> = institutions
 		.into_iter()
 		.map(|i| i.build_response(includes, &config))
 		.collect::<Result<_, _>>()?;
 
-	let response = p_opts.paginate(total, truncated, institutions);
+	let response =
+		p_opts.paginate_with_cursor(total, truncated, institutions, |i| i.id);
 
 	Ok((StatusCode::OK, Json(response)))
 }
@@ -90,7 +133,206 @@ pub async fn get_institution(
 	Ok((StatusCode::OK, Json(response)))
 }
 
+/// Update an [`Institution`] and its name translation
+///
+/// Like on creation, the resulting names must not be used by any other
+/// institution in the resulting category
+#[instrument(skip(pool))]
+pub async fn update_institution(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: Session,
+	Query(includes): Query<InstitutionIncludes>,
+	Path(id): Path<i32>,
+	Json(request): Json<UpdateInstitutionRequest>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	check_institution_perms(
+		id,
+		session.data.profile_id,
+		InstitutionPermissions::Administrator,
+		&conn,
+	)
+	.await?;
+
+	let current =
+		Institution::get_by_id(id, InstitutionIncludes::default(), &conn)
+			.await?;
+
+	let category = request.category.unwrap_or(current.primitive.category);
+	let current_names = [
+		current.name.nl.as_ref(),
+		current.name.en.as_ref(),
+		current.name.fr.as_ref(),
+		current.name.de.as_ref(),
+	];
+
+	let name_update = request.name_translation.as_ref();
+	let names = [
+		name_update.and_then(|n| n.nl.as_ref()).or(current_names[0]),
+		name_update.and_then(|n| n.en.as_ref()).or(current_names[1]),
+		name_update.and_then(|n| n.fr.as_ref()).or(current_names[2]),
+		name_update.and_then(|n| n.de.as_ref()).or(current_names[3]),
+	];
+
+	for name in names.into_iter().flatten() {
+		// Names this institution already uses in its category are not
+		// taken by another institution
+		let own_name = category == current.primitive.category
+			&& current_names.contains(&Some(name));
+
+		if own_name {
+			continue;
+		}
+
+		if !Institution::name_is_available(name, category, &conn).await? {
+			return Err(Error::Duplicate {
+				message:     format!(
+					"an institution named '{name}' already exists in this \
+					 category"
+				),
+				resource_id: None,
+			});
+		}
+	}
+
+	let institution_update = request.to_insertable(session.data.profile_id);
+	let institution = institution_update.apply_to(id, includes, &conn).await?;
+	let response = institution.build_response(includes, &config)?;
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Delete an [`Institution`]
+///
+/// Institutions that still have linked authorities can not be deleted
+#[instrument(skip(pool))]
+pub async fn delete_institution(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+) -> Result<NoContent, Error> {
+	let conn = pool.get().await?;
+
+	check_institution_perms(
+		id,
+		session.data.profile_id,
+		InstitutionPermissions::Administrator,
+		&conn,
+	)
+	.await?;
+
+	Institution::delete_by_id(id, &conn).await?;
+
+	AuditLog::record(
+		session.data.profile_id,
+		AuditAction::InstitutionDeleted,
+		AuditTarget::Institution,
+		id,
+		json!({}),
+		&conn,
+	)
+	.await;
+
+	Ok(NoContent)
+}
+
+#[instrument(skip(pool))]
+pub async fn get_institution_translations(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	check_institution_perms(
+		id,
+		session.data.profile_id,
+		InstitutionPermissions::Administrator,
+		&conn,
+	)
+	.await?;
+
+	let translations = Translation::get_for_institution(id, &conn).await?;
+	let response = translations.build_response((), &config)?;
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Get all tags used by the locations of an institution together with the
+/// amount of locations using them
+#[instrument(skip(pool))]
+pub async fn get_institution_tags(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let tags = Tag::get_for_institution(id, &conn).await?;
+	let response: Vec<TagUsageResponse> = tags
+		.into_iter()
+		.map(|(t, usage_count)| {
+			let tag = t.build_response(TagIncludes::default(), &config)?;
+
+			Ok(TagUsageResponse { tag, usage_count })
+		})
+		.collect::<Result<_, Error>>()?;
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
 #[instrument]
 pub async fn get_categories() -> impl IntoResponse {
 	(StatusCode::OK, Json(InstitutionCategory::get_variants()))
 }
+
+/// Get the dashboard statistics of an [`Institution`]
+///
+/// Any member of the institution holding a role can view these
+#[instrument(skip(pool))]
+pub async fn get_institution_statistics(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	check_institution_perms(
+		id,
+		session.data.profile_id,
+		InstitutionPermissions::all(),
+		&conn,
+	)
+	.await?;
+
+	let statistics = Institution::get_statistics(id, &conn).await?;
+	let response = InstitutionStatisticsResponse::from(statistics);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Get the effective permissions of the current profile for an institution
+#[instrument(skip(pool))]
+pub async fn get_institution_permissions(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	// Return not found if the institution doesn't exist
+	Institution::get_by_id(id, InstitutionIncludes::default(), &conn).await?;
+
+	let permissions = EffectivePermissions::for_institution(
+		id,
+		session.data.profile_id,
+		&conn,
+	)
+	.await?;
+	let response = InstitutionPermissionsResponse::from(permissions);
+
+	Ok((StatusCode::OK, Json(response)))
+}
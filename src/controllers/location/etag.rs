@@ -0,0 +1,42 @@
+//! Conditional request support for [`Location`] responses
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use axum::http::{HeaderMap, header};
+use common::{DbConn, Error};
+use location::Location;
+use serde::Serialize;
+
+/// Compute the weak ETag of a response about a location
+///
+/// The ETag is derived from the [`LocationVersion`](location::LocationVersion)
+/// of the location and the query parameters that shape the response
+pub(crate) async fn location_etag(
+	loc_id: i32,
+	params: &impl Serialize,
+	conn: &DbConn,
+) -> Result<String, Error> {
+	let version = Location::get_version(loc_id, conn).await?;
+	let params = serde_json::to_string(params)?;
+
+	let mut hasher = DefaultHasher::new();
+	version.hash(&mut hasher);
+	params.hash(&mut hasher);
+
+	Ok(format!("W/\"{:x}\"", hasher.finish()))
+}
+
+/// Check whether the `If-None-Match` header of a request matches an ETag
+///
+/// ETags are compared weakly, so the `W/` prefix is ignored
+pub(crate) fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+	let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+	let etag = opaque(etag);
+
+	headers
+		.get_all(header::IF_NONE_MATCH)
+		.iter()
+		.filter_map(|value| value.to_str().ok())
+		.flat_map(|value| value.split(','))
+		.any(|tag| tag.trim() == "*" || opaque(tag) == etag)
+}
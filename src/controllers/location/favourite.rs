@@ -0,0 +1,33 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, NoContent};
+use common::{DbPool, Error};
+use location::LocationFavourite;
+
+use crate::Session;
+
+#[instrument(skip(pool))]
+pub(crate) async fn add_location_favourite(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	LocationFavourite::add(id, session.data.profile_id, &conn).await?;
+
+	Ok(StatusCode::OK)
+}
+
+#[instrument(skip(pool))]
+pub(crate) async fn remove_location_favourite(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	LocationFavourite::remove(id, session.data.profile_id, &conn).await?;
+
+	Ok((StatusCode::NO_CONTENT, NoContent))
+}
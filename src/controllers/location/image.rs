@@ -4,16 +4,22 @@ use axum::http::StatusCode;
 use axum::response::{IntoResponse, NoContent};
 use common::{DbPool, Error};
 use image::{Image, ImageIncludes};
+use location::{Location, LocationIncludes};
 use permissions::{
 	AuthorityPermissions,
 	InstitutionPermissions,
 	LocationPermissions,
+	check_authority_perms,
 	check_location_perms,
 };
-use utils::image::{delete_image, store_location_image};
+use utils::image::{delete_image_file, store_location_image};
 
 use crate::schemas::BuildResponse;
-use crate::schemas::image::{CreateOrderedImageRequest, ImageResponse};
+use crate::schemas::image::{
+	ApprovedImagesResponse,
+	CreateOrderedImageRequest,
+	ImageResponse,
+};
 use crate::schemas::location::LocationImageOrderUpdate;
 use crate::{Config, Session};
 
@@ -38,12 +44,21 @@ pub async fn upload_location_image(
 	let conn = pool.get().await?;
 
 	let image = CreateOrderedImageRequest::parse(&mut data).await?.into();
-	let inserted_image =
-		store_location_image(session.data.profile_id, id, image, &conn).await?;
+	let (stored_image, created) = store_location_image(
+		session.data.profile_id,
+		id,
+		image,
+		config.image_limits(),
+		&conn,
+	)
+	.await?;
 	let response =
-		inserted_image.build_response(ImageIncludes::default(), &config)?;
+		stored_image.build_response(ImageIncludes::default(), &config)?;
+
+	// Uploading an image the location already has returns the existing one
+	let status = if created { StatusCode::CREATED } else { StatusCode::OK };
 
-	Ok((StatusCode::CREATED, Json(response)))
+	Ok((status, Json(response)))
 }
 
 pub async fn reorder_location_images(
@@ -82,16 +97,118 @@ pub async fn reorder_location_images(
 	Ok((StatusCode::OK, Json(response)))
 }
 
+/// Check whether the given profile can review the images of a location
+///
+/// Platform admins and authority members that can approve locations can
+/// review the images of any location, other profiles need the given location
+/// permissions
+async fn check_image_review_perms(
+	l_id: i32,
+	session: &Session,
+	loc_perms: LocationPermissions,
+	pool: &DbPool,
+) -> Result<(), Error> {
+	if session.data.is_admin {
+		return Ok(());
+	}
+
+	check_location_perms(
+		l_id,
+		session.data.profile_id,
+		loc_perms,
+		AuthorityPermissions::ApproveLocations
+			| AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		pool,
+	)
+	.await
+}
+
+/// Get the images of a location that are still pending approval
+#[instrument(skip(pool))]
+pub async fn get_pending_location_images(
+	State(pool): State<DbPool>,
+	State(config): State<Config>,
+	session: Session,
+	Query(includes): Query<ImageIncludes>,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	check_image_review_perms(
+		id,
+		&session,
+		LocationPermissions::ManageImages | LocationPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	let images = Image::get_pending_for_location(id, includes, &conn).await?;
+	let response: Vec<ImageResponse> = images
+		.into_iter()
+		.map(|i| i.build_response(includes, &config))
+		.collect::<Result<_, _>>()?;
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Approve a single image of a location, making it publicly visible
+#[instrument(skip(pool))]
+pub async fn approve_location_image(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path((l_id, img_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, Error> {
+	check_image_review_perms(
+		l_id,
+		&session,
+		LocationPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+	Image::approve_for_location(l_id, img_id, session.data.profile_id, &conn)
+		.await?;
+
+	Ok((StatusCode::NO_CONTENT, NoContent))
+}
+
+/// Reject an image of a location, removing it and its stored file
 #[instrument(skip(pool))]
 pub async fn delete_location_image(
 	State(pool): State<DbPool>,
 	session: Session,
 	Path((l_id, img_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, Error> {
+	check_image_review_perms(
+		l_id,
+		&session,
+		LocationPermissions::ManageImages | LocationPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+	let file_path = Image::reject_for_location(l_id, img_id, &conn).await?;
+
+	if let Some(file_path) = file_path {
+		delete_image_file(&file_path)?;
+	}
+
+	Ok((StatusCode::NO_CONTENT, NoContent))
+}
+
+#[instrument(skip(pool))]
+pub async fn set_location_cover_image(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path((l_id, img_id)): Path<(i32, i32)>,
 ) -> Result<impl IntoResponse, Error> {
 	check_location_perms(
 		l_id,
 		session.data.profile_id,
-		LocationPermissions::ManageImages | LocationPermissions::Administrator,
+		LocationPermissions::Administrator,
 		AuthorityPermissions::Administrator,
 		InstitutionPermissions::Administrator,
 		&pool,
@@ -99,7 +216,44 @@ pub async fn delete_location_image(
 	.await?;
 
 	let conn = pool.get().await?;
-	delete_image(img_id, &conn).await?;
+	Image::set_as_cover(l_id, img_id, &conn).await?;
 
 	Ok((StatusCode::NO_CONTENT, NoContent))
 }
+
+/// Approve all images of a location that are still pending approval
+#[instrument(skip(pool))]
+pub async fn approve_all_location_images(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let location =
+		Location::get_by_id(id, LocationIncludes::default(), &conn).await?;
+	let location = location.0.primitive;
+
+	if let Some(auth_id) = location.authority_id {
+		check_authority_perms(
+			auth_id,
+			session.data.profile_id,
+			AuthorityPermissions::ApproveLocations
+				| AuthorityPermissions::Administrator,
+			InstitutionPermissions::Administrator,
+			&pool,
+		)
+		.await?;
+	} else if !session.data.is_admin {
+		return Err(Error::Forbidden);
+	}
+
+	let approved_images = Image::approve_all_pending_for_location(
+		id,
+		session.data.profile_id,
+		&conn,
+	)
+	.await?;
+
+	Ok((StatusCode::OK, Json(ApprovedImagesResponse { approved_images })))
+}
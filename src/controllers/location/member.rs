@@ -1,15 +1,21 @@
+use audit_log::AuditLog;
 use axum::Json;
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, NoContent};
 use common::{DbPool, Error};
+use db::{AuditAction, AuditTarget};
 use location::Location;
 use permissions::{
 	AuthorityPermissions,
 	InstitutionPermissions,
 	LocationPermissions,
+	check_location_member_change,
 	check_location_perms,
+	check_location_role_grant,
 };
+use role::LocationRole;
+use serde_json::json;
 
 use crate::schemas::BuildResponse;
 use crate::schemas::location::{
@@ -25,7 +31,7 @@ pub async fn add_location_member(
 	State(config): State<Config>,
 	session: Session,
 	Path(id): Path<i32>,
-	Json(request): Json<CreateLocationMemberRequest>,
+	Json(mut request): Json<CreateLocationMemberRequest>,
 ) -> Result<impl IntoResponse, Error> {
 	check_location_perms(
 		id,
@@ -39,6 +45,23 @@ pub async fn add_location_member(
 
 	let conn = pool.get().await?;
 
+	// Members added without a role get the default role of the location,
+	// which has to pass the same grant check as an explicit role
+	if request.location_role_id.is_none() {
+		let default_role =
+			LocationRole::get_default_for_location(id, &conn).await?;
+
+		request.location_role_id = default_role.map(|r| r.primitive.id);
+	}
+
+	check_location_role_grant(
+		id,
+		session.data.profile_id,
+		request.location_role_id,
+		&pool,
+	)
+	.await?;
+
 	let new_loc_profile = request.to_insertable(id, session.data.profile_id);
 	let member = new_loc_profile.insert(&conn).await?;
 	let response = member.build_response((), &config)?;
@@ -82,23 +105,57 @@ pub async fn update_location_member(
 	Path((loc_id, prof_id)): Path<(i32, i32)>,
 	Json(request): Json<LocationMemberUpdateRequest>,
 ) -> Result<impl IntoResponse, Error> {
-	check_location_perms(
-		loc_id,
-		session.data.profile_id,
-		LocationPermissions::ManageMembers | LocationPermissions::Administrator,
-		AuthorityPermissions::Administrator,
-		InstitutionPermissions::Administrator,
-		&pool,
-	)
-	.await?;
+	// Platform admins can grant any role to any member
+	if !session.data.is_admin {
+		check_location_perms(
+			loc_id,
+			session.data.profile_id,
+			LocationPermissions::ManageMembers
+				| LocationPermissions::Administrator,
+			AuthorityPermissions::Administrator,
+			InstitutionPermissions::Administrator,
+			&pool,
+		)
+		.await?;
+
+		check_location_member_change(
+			loc_id,
+			session.data.profile_id,
+			prof_id,
+			&pool,
+		)
+		.await?;
+
+		check_location_role_grant(
+			loc_id,
+			session.data.profile_id,
+			request.location_role_id,
+			&pool,
+		)
+		.await?;
+	}
 
 	let conn = pool.get().await?;
 
+	let detail =
+		json!({ "profileId": prof_id, "roleId": request.location_role_id });
+
 	let member_update = request.to_insertable(session.data.profile_id);
 	let updated_member = member_update.apply_to(loc_id, prof_id, &conn).await?;
+
+	AuditLog::record(
+		session.data.profile_id,
+		AuditAction::MemberRoleChanged,
+		AuditTarget::Location,
+		loc_id,
+		detail,
+		&conn,
+	)
+	.await;
+
 	let response = updated_member.build_response((), &config)?;
 
-	Ok((StatusCode::CREATED, Json(response)))
+	Ok((StatusCode::OK, Json(response)))
 }
 
 #[instrument(skip(pool))]
@@ -1,12 +1,33 @@
 //! Controllers for [`Location`]s
 
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
 use ::image::{Image, ImageIncludes};
+use ::review::Review;
+use audit_log::AuditLog;
 use axum::Json;
-use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
-use axum::response::{IntoResponse, NoContent};
-use common::{DbPool, Error};
-use location::{Location, LocationFilter, LocationIncludes, Point};
+use axum::extract::{Multipart, Path, Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{AppendHeaders, IntoResponse, NoContent};
+use common::{
+	DbConn,
+	DbPool,
+	Error,
+	ErrorResponse,
+	InternalServerError,
+	RedisConn,
+};
+use db::{AuditAction, AuditTarget};
+use location::{
+	Location,
+	LocationFavourite,
+	LocationFilter,
+	LocationIncludes,
+	LocationStats,
+	PendingFilter,
+	Point,
+};
 use opening_time::{
 	OpeningTime,
 	OpeningTimeIncludes,
@@ -15,46 +36,132 @@ use opening_time::{
 };
 use permissions::{
 	AuthorityPermissions,
+	EffectivePermissions,
 	InstitutionPermissions,
 	LocationPermissions,
 	check_authority_perms,
 	check_location_perms,
 };
+use redis::AsyncCommands;
 use reservation::{Reservation, ReservationFilter, ReservationIncludes};
+use serde_json::json;
 use tag::{Tag, TagIncludes};
+use translation::Translation;
+use utils::image::{ImageVariant, build_location_images, delete_image_file};
+use uuid::Uuid;
 use validator::Validate;
+use webhook::WebhookEvent;
 
 use crate::schemas::BuildResponse;
+use crate::schemas::includes::Includes;
 use crate::schemas::location::{
+	CreateLocationMultipartRequest,
 	CreateLocationRequest,
+	GEOJSON_CONTENT_TYPE,
+	LocationAvailabilityOptions,
+	LocationAvailabilityResponse,
+	LocationDeletionResponse,
+	LocationFeatureCollectionResponse,
+	LocationGeoJsonResponse,
+	LocationMergeResponse,
+	LocationReservationCountResponse,
 	LocationResponse,
+	LocationSearchFormatOptions,
+	LocationStatsOptions,
+	LocationStatsResponse,
+	MergeLocationRequest,
+	NearbyLocationsOptions,
 	NearestLocationResponse,
 	RejectLocationRequest,
+	ReservationCountOptions,
 	UpdateLocationRequest,
+	ZipLocationCountResponse,
+};
+use crate::schemas::opening_time::{
+	OpeningTimeAvailabilityResponse,
+	OpeningTimeResponse,
 };
-use crate::schemas::opening_time::OpeningTimeResponse;
-use crate::schemas::pagination::PaginationOptions;
+use crate::schemas::pagination::{PaginatedResponse, PaginationOptions};
+use crate::schemas::permissions::LocationPermissionsResponse;
 use crate::schemas::reservation::ReservationResponse;
 use crate::schemas::tag::SetLocationTagsRequest;
-use crate::{Config, Session};
+use crate::schemas::translation::{
+	LocationTranslationsResponse,
+	UpdateTranslationRequest,
+};
+use crate::webhooks::WebhookDispatcher;
+use crate::{AdminSession, Config, Session};
 
+mod etag;
+mod favourite;
 mod image;
 mod member;
 mod review;
 mod role;
 
+pub(crate) use etag::*;
+pub(crate) use favourite::*;
 pub(crate) use image::*;
 pub(crate) use member::*;
 pub(crate) use review::*;
 pub(crate) use role::*;
 
+/// Notify the webhooks of an authority of an event concerning one of its
+/// locations, sending the location as it is after the event
+///
+/// Webhooks should never get in the way of the change they describe, so
+/// failures are logged instead of returned
+async fn notify_location_webhooks(
+	loc_id: i32,
+	auth_id: i32,
+	event: WebhookEvent,
+	webhooks: &WebhookDispatcher,
+	config: &Config,
+	conn: &DbConn,
+) {
+	let includes = LocationIncludes::default();
+
+	let response = Location::get_by_id(loc_id, includes, conn)
+		.await
+		.and_then(|l| l.build_response(includes, config));
+
+	match response {
+		Ok(response) => {
+			webhooks.dispatch_location_event(auth_id, event, response);
+		},
+		Err(e) => {
+			error!(
+				"could not notify webhooks of {event:?} of location {loc_id}: \
+				 {e:?}"
+			);
+		},
+	}
+}
+
 /// Create a new location in the database.
+#[utoipa::path(
+	post,
+	path = "/locations",
+	tag = "locations",
+	params(LocationIncludes),
+	request_body = CreateLocationRequest,
+	responses(
+		(
+			status = 201,
+			description = "The new location",
+			body = LocationResponse
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 422, description = "Invalid data", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub(crate) async fn create_location(
 	State(pool): State<DbPool>,
 	State(config): State<Config>,
 	session: Session,
-	Query(includes): Query<LocationIncludes>,
+	Includes(includes): Includes<LocationIncludes>,
 	Json(request): Json<CreateLocationRequest>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
@@ -68,35 +175,257 @@ pub(crate) async fn create_location(
 	Ok((StatusCode::CREATED, Json(response)))
 }
 
+/// Create a new location from a `multipart/form-data` request, storing any
+/// attached images along with it.
+#[utoipa::path(
+	post,
+	path = "/locations/multipart",
+	tag = "locations",
+	params(LocationIncludes),
+	responses(
+		(
+			status = 201,
+			description = "The new location",
+			body = LocationResponse
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 422, description = "Invalid data", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool, data))]
+pub(crate) async fn create_location_multipart(
+	State(pool): State<DbPool>,
+	State(config): State<Config>,
+	session: Session,
+	Includes(includes): Includes<LocationIncludes>,
+	mut data: Multipart,
+) -> Result<impl IntoResponse, Error> {
+	let request = CreateLocationMultipartRequest::parse(&mut data).await?;
+
+	request.location.validate()?;
+
+	let conn = pool.get().await?;
+
+	let profile_id = session.data.profile_id;
+	let images: Vec<ImageVariant> =
+		request.images.into_iter().map(Into::into).collect();
+
+	let limits = config.image_limits();
+
+	let new_location = request.location.to_insertable(profile_id);
+	let records = new_location
+		.insert_with_images(
+			move |loc_id| {
+				build_location_images(profile_id, loc_id, images, limits)
+			},
+			includes,
+			&conn,
+		)
+		.await?;
+	let response = records.build_response(includes, &config)?;
+
+	Ok((StatusCode::CREATED, Json(response)))
+}
+
 /// Get a location from the database.
-#[instrument(skip(pool))]
+///
+/// Responds with `304 Not Modified` if the `If-None-Match` header matches
+/// the current ETag of the location.
+#[utoipa::path(
+	get,
+	path = "/locations/{id}",
+	tag = "locations",
+	params(
+		("id" = i32, Path, description = "Id of the location"),
+		LocationIncludes
+	),
+	responses(
+		(
+			status = 200,
+			description = "The location",
+			body = LocationResponse,
+			headers(
+				("ETag" = String, description = "Current ETag of the location")
+			)
+		),
+		(
+			status = 304,
+			description = "Not modified",
+			headers(
+				("ETag" = String, description = "Current ETag of the location")
+			)
+		),
+		(status = 404, description = "Unknown location", body = ErrorResponse)
+	)
+)]
+#[instrument(skip(pool, headers))]
 pub(crate) async fn get_location(
 	State(pool): State<DbPool>,
 	State(config): State<Config>,
+	headers: HeaderMap,
 	Path(id): Path<i32>,
-	Query(includes): Query<LocationIncludes>,
+	Includes(includes): Includes<LocationIncludes>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
 
+	let etag = location_etag(id, &includes, &conn).await?;
+	let etag_header = [(header::ETAG, etag.clone())];
+
+	if etag_matches(&headers, &etag) {
+		return Ok((StatusCode::NOT_MODIFIED, etag_header).into_response());
+	}
+
 	let result = Location::get_by_id(id, includes, &conn).await?;
-	let response = result.build_response(includes, &config)?;
+	let mut response = result.build_response(includes, &config)?;
 
-	Ok((StatusCode::OK, Json(response)))
+	response.favourite_count =
+		Some(LocationFavourite::count_for_location(id, &conn).await?);
+
+	Ok((StatusCode::OK, etag_header, Json(response)).into_response())
 }
 
+/// Get a location from the database by its public id.
+#[utoipa::path(
+	get,
+	path = "/locations/p/{public_id}",
+	tag = "locations",
+	params(
+		("public_id" = Uuid, Path, description = "Public id of the location"),
+		LocationIncludes
+	),
+	responses(
+		(status = 200, description = "The location", body = LocationResponse),
+		(status = 404, description = "Unknown location", body = ErrorResponse)
+	)
+)]
 #[instrument(skip(pool))]
+pub(crate) async fn get_location_by_public_id(
+	State(pool): State<DbPool>,
+	State(config): State<Config>,
+	Path(public_id): Path<Uuid>,
+	Includes(includes): Includes<LocationIncludes>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let result = Location::get_by_public_id(public_id, includes, &conn).await?;
+	let mut response = result.build_response(includes, &config)?;
+
+	response.favourite_count =
+		Some(LocationFavourite::count_for_location(response.id, &conn).await?);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Get the opening times of a location.
+///
+/// Without reservations, responds with `304 Not Modified` if the
+/// `If-None-Match` header matches the current ETag of the location.
+/// Reservations change independently of the location, so responses that
+/// include them are never cached.
+#[utoipa::path(
+	get,
+	path = "/locations/{id}/opening-times",
+	tag = "locations",
+	params(
+		("id" = i32, Path, description = "Id of the location"),
+		TimeBoundsFilter,
+		OpeningTimeIncludes
+	),
+	responses(
+		(
+			status = 200,
+			description = "The opening times",
+			body = Vec<OpeningTimeResponse>
+		),
+		(status = 304, description = "Not modified"),
+		(status = 401, description = "Not logged in", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool, headers))]
 pub async fn get_location_opening_times(
 	State(config): State<Config>,
 	State(pool): State<DbPool>,
+	headers: HeaderMap,
 	Path(id): Path<i32>,
 	Query(filter): Query<TimeBoundsFilter>,
 	Query(includes): Query<OpeningTimeIncludes>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
 
-	let times =
-		OpeningTime::get_for_location(id, filter, includes, &conn).await?;
-	let times: Vec<OpeningTimeResponse> = times
+	let etag = if includes.reservations {
+		None
+	} else {
+		Some(location_etag(id, &(&filter, includes), &conn).await?)
+	};
+
+	let etag_header = AppendHeaders(etag.clone().map(|e| (header::ETAG, e)));
+
+	if etag.as_deref().is_some_and(|etag| etag_matches(&headers, etag)) {
+		return Ok((StatusCode::NOT_MODIFIED, etag_header).into_response());
+	}
+
+	let times: Vec<OpeningTimeResponse> = if includes.reservations {
+		let times = OpeningTime::get_with_availability_for_location(
+			id, filter, includes, &conn,
+		)
+		.await?;
+
+		times
+			.into_iter()
+			.map(|t| {
+				let availability: OpeningTimeAvailabilityResponse =
+					t.build_response(includes, &config)?;
+
+				Ok(availability.into())
+			})
+			.collect::<Result<_, Error>>()?
+	} else {
+		let times =
+			OpeningTime::get_for_location(id, filter, includes, &conn).await?;
+
+		times
+			.into_iter()
+			.map(|t| t.build_response(includes, &config))
+			.collect::<Result<_, _>>()?
+	};
+
+	Ok((StatusCode::OK, etag_header, Json(times)).into_response())
+}
+
+#[utoipa::path(
+	get,
+	path = "/locations/{id}/opening-times/availability",
+	tag = "locations",
+	params(
+		("id" = i32, Path, description = "Id of the location"),
+		TimeBoundsFilter,
+		OpeningTimeIncludes
+	),
+	responses(
+		(
+			status = 200,
+			description = "The opening times",
+			body = Vec<OpeningTimeAvailabilityResponse>
+		)
+	)
+)]
+#[instrument(skip(pool))]
+pub async fn get_location_opening_times_availability(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	Path(id): Path<i32>,
+	Query(filter): Query<TimeBoundsFilter>,
+	Query(includes): Query<OpeningTimeIncludes>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let times = OpeningTime::get_with_availability_for_location(
+		id, filter, includes, &conn,
+	)
+	.await?;
+	let times: Vec<OpeningTimeAvailabilityResponse> = times
 		.into_iter()
 		.map(|t| t.build_response(includes, &config))
 		.collect::<Result<_, _>>()?;
@@ -104,6 +433,27 @@ pub async fn get_location_opening_times(
 	Ok((StatusCode::OK, Json(times)))
 }
 
+#[utoipa::path(
+	get,
+	path = "/locations/{l_id}/reservations",
+	tag = "locations",
+	params(
+		("l_id" = i32, Path, description = "Id of the location"),
+		ReservationFilter,
+		ReservationIncludes,
+		PaginationOptions
+	),
+	responses(
+		(
+			status = 200,
+			description = "A page of reservations",
+			body = PaginatedResponse<Vec<ReservationResponse>>
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub async fn get_location_reservations(
 	State(config): State<Config>,
@@ -111,7 +461,8 @@ pub async fn get_location_reservations(
 	session: Session,
 	Path(loc_id): Path<i32>,
 	Query(filter): Query<ReservationFilter>,
-	Query(includes): Query<ReservationIncludes>,
+	Includes(includes): Includes<ReservationIncludes>,
+	Query(p_opts): Query<PaginationOptions>,
 ) -> Result<impl IntoResponse, Error> {
 	check_location_perms(
 		loc_id,
@@ -125,23 +476,98 @@ pub async fn get_location_reservations(
 
 	let conn = pool.get().await?;
 
-	let reservations =
-		Reservation::for_location(loc_id, filter, includes, &conn).await?;
-	let response: Vec<ReservationResponse> = reservations
+	let filter = filter.with_default_window(config.reservation_list_window);
+
+	let (total, truncated, reservations) = Reservation::for_location_paginated(
+		loc_id,
+		filter,
+		includes,
+		p_opts.into(),
+		&conn,
+	)
+	.await?;
+	let reservations: Vec<ReservationResponse> = reservations
 		.into_iter()
 		.map(|r| r.build_response(includes, &config))
 		.collect::<Result<_, _>>()?;
 
+	let paginated = p_opts.paginate(total, truncated, reservations);
+
+	Ok((StatusCode::OK, Json(paginated)))
+}
+
+#[utoipa::path(
+	get,
+	path = "/locations/{id}/stats",
+	tag = "locations",
+	params(
+		("id" = i32, Path, description = "Id of the location"),
+		LocationStatsOptions
+	),
+	responses(
+		(
+			status = 200,
+			description = "The statistics",
+			body = LocationStatsResponse
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub async fn get_location_stats(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+	Query(opts): Query<LocationStatsOptions>,
+) -> Result<impl IntoResponse, Error> {
+	check_location_perms(
+		id,
+		session.data.profile_id,
+		LocationPermissions::Administrator,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	let stats =
+		LocationStats::for_location(id, opts.from, opts.to, &conn).await?;
+	let response: LocationStatsResponse = stats.into();
+
 	Ok((StatusCode::OK, Json(response)))
 }
 
+#[utoipa::path(
+	get,
+	path = "/locations/{l_id}/opening-times/{t_id}/reservations",
+	tag = "locations",
+	params(
+		("l_id" = i32, Path, description = "Id of the location"),
+		("t_id" = i32, Path, description = "Id of the opening time"),
+		ReservationIncludes
+	),
+	responses(
+		(
+			status = 200,
+			description = "The reservations",
+			body = Vec<ReservationResponse>
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub async fn get_location_opening_time_reservations(
 	State(config): State<Config>,
 	State(pool): State<DbPool>,
 	session: Session,
 	Path((l_id, t_id)): Path<(i32, i32)>,
-	Query(includes): Query<ReservationIncludes>,
+	Includes(includes): Includes<ReservationIncludes>,
 ) -> Result<impl IntoResponse, Error> {
 	check_location_perms(
 		l_id,
@@ -165,6 +591,20 @@ pub async fn get_location_opening_time_reservations(
 	Ok((StatusCode::OK, Json(response)))
 }
 
+#[utoipa::path(
+	get,
+	path = "/locations/nearest",
+	tag = "locations",
+	params(Point),
+	responses(
+		(
+			status = 200,
+			description = "The nearest location",
+			body = NearestLocationResponse
+		),
+		(status = 404, description = "Unknown location", body = ErrorResponse)
+	)
+)]
 #[instrument(skip(pool))]
 pub(crate) async fn get_nearest_location(
 	State(pool): State<DbPool>,
@@ -178,66 +618,363 @@ pub(crate) async fn get_nearest_location(
 	Ok((StatusCode::OK, Json(res)))
 }
 
+/// Get the visible locations closest to a given point, sorted by their
+/// distance to it.
+#[utoipa::path(
+	get,
+	path = "/locations/nearby",
+	tag = "locations",
+	params(NearbyLocationsOptions, LocationIncludes),
+	responses(
+		(
+			status = 200,
+			description = "The nearby locations",
+			body = Vec<LocationResponse>
+		),
+		(status = 400, description = "Invalid request", body = ErrorResponse)
+	)
+)]
+#[instrument(skip(pool))]
+pub(crate) async fn get_nearby_locations(
+	State(pool): State<DbPool>,
+	State(config): State<Config>,
+	Query(options): Query<NearbyLocationsOptions>,
+	Includes(includes): Includes<LocationIncludes>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let locations =
+		Location::get_nearby(options.into(), options.limit, includes, &conn)
+			.await?;
+
+	let response: Vec<LocationResponse> = locations
+		.into_iter()
+		.map(|(l, distance)| {
+			let mut response = l.build_response(includes, &config)?;
+			response.distance_km = Some(distance);
+
+			Ok(response)
+		})
+		.collect::<Result<_, Error>>()?;
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Check if a location can be reserved at a given point in time.
+#[utoipa::path(
+	get,
+	path = "/locations/{id}/available",
+	tag = "locations",
+	params(
+		("id" = i32, Path, description = "Id of the location"),
+		LocationAvailabilityOptions
+	),
+	responses(
+		(
+			status = 200,
+			description = "The availability",
+			body = LocationAvailabilityResponse
+		),
+		(status = 404, description = "Unknown location", body = ErrorResponse)
+	)
+)]
+#[instrument(skip(pool))]
+pub(crate) async fn get_location_availability(
+	State(pool): State<DbPool>,
+	Path(id): Path<i32>,
+	Query(options): Query<LocationAvailabilityOptions>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let availability =
+		Location::get_availability_at(id, options.at, &conn).await?;
+	let response: LocationAvailabilityResponse = availability.into();
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Amount of seconds a page of newest locations stays cached
+const NEWEST_LOCATIONS_CACHE_SECONDS: u64 = 60;
+
+/// Build the cache key for a page of newest locations matching the given
+/// search parameters
+fn newest_locations_cache_key(
+	loc_filter: &LocationFilter,
+	time_filter: &TimeFilter,
+	includes: LocationIncludes,
+	p_opts: PaginationOptions,
+) -> Result<String, Error> {
+	let params = serde_json::to_string(&(loc_filter, time_filter, includes))?;
+
+	let mut hasher = DefaultHasher::new();
+	params.hash(&mut hasher);
+	p_opts.page.hash(&mut hasher);
+	p_opts.per_page.hash(&mut hasher);
+
+	Ok(format!("locations:newest:{:x}", hasher.finish()))
+}
+
 /// Search all locations from the database on given latlng bounds.
 /// The latlng bounds include the southwestern and northeastern corners.
 /// The southwestern corner is the minimum latitude and longitude, and the
 /// northeastern corner is the maximum latitude and longitude.
-#[instrument(skip(pool))]
+///
+/// Without latlng bounds, only approved locations are listed and the results
+/// are paginated by the database.
+///
+/// A search query is matched against the name, description and excerpt of
+/// the locations, locations whose name matches are listed first.
+///
+/// Searches for the newest locations are cached for a short while, as they
+/// back the frequently visited "newly listed" section of the homepage.
+///
+/// The results are returned as a GeoJSON `FeatureCollection` instead if
+/// requested through `?format=geojson` or the `Accept` header.
+#[utoipa::path(
+	get,
+	path = "/locations",
+	tag = "locations",
+	params(
+		TimeFilter,
+		LocationFilter,
+		LocationIncludes,
+		PaginationOptions,
+		LocationSearchFormatOptions
+	),
+	responses(
+		(
+			status = 200,
+			description = "A page of locations",
+			content(
+				(PaginatedResponse<Vec<LocationResponse>> = "application/json"),
+				(LocationFeatureCollectionResponse = "application/geo+json")
+			)
+		),
+		(status = 400, description = "Invalid request", body = ErrorResponse)
+	)
+)]
+#[instrument(skip(pool, r_conn, headers))]
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn search_locations(
 	State(pool): State<DbPool>,
 	State(config): State<Config>,
+	State(mut r_conn): State<RedisConn>,
+	headers: HeaderMap,
 	Query(time_filter): Query<TimeFilter>,
 	Query(loc_filter): Query<LocationFilter>,
-	Query(includes): Query<LocationIncludes>,
+	Includes(includes): Includes<LocationIncludes>,
 	Query(p_opts): Query<PaginationOptions>,
+	Query(format): Query<LocationSearchFormatOptions>,
 ) -> Result<impl IntoResponse, Error> {
+	let geojson = format.wants_geojson(&headers);
+
+	let cache_key = if !geojson && loc_filter.sorts_by_newest() {
+		Some(newest_locations_cache_key(
+			&loc_filter,
+			&time_filter,
+			includes,
+			p_opts,
+		)?)
+	} else {
+		None
+	};
+
+	if let Some(key) = &cache_key {
+		let cached: Option<String> = r_conn.get(key).await?;
+
+		if let Some(cached) = cached {
+			let headers = [(header::CONTENT_TYPE, "application/json")];
+
+			return Ok((StatusCode::OK, headers, cached).into_response());
+		}
+	}
+
 	let conn = pool.get().await?;
 
-	let (total, truncated, locations) = Location::search(
-		loc_filter,
-		time_filter,
-		includes,
-		p_opts.into(),
-		&conn,
-	)
-	.await?;
+	let (total, truncated, locations) = if loc_filter.has_bounds() {
+		Location::search(
+			loc_filter,
+			time_filter,
+			includes,
+			p_opts.into(),
+			&conn,
+		)
+		.await?
+	} else {
+		Location::get_visible(
+			loc_filter,
+			time_filter,
+			includes,
+			p_opts.into(),
+			&conn,
+		)
+		.await?
+	};
 
 	let l_ids = locations.iter().map(|l| l.primitive.id).collect::<Vec<_>>();
 
-	let (times, tags, imgs) = tokio::join!(
+	if geojson {
+		let (times, ratings) = tokio::join!(
+			OpeningTime::get_for_locations(
+				l_ids.clone(),
+				OpeningTimeIncludes::default(),
+				&conn
+			),
+			Review::get_aggregates_for_locations(l_ids, &conn),
+		);
+
+		let locations =
+			Location::group(locations, &times?, &[], &[], &ratings?);
+
+		let features: Vec<LocationGeoJsonResponse> = locations
+			.into_iter()
+			.map(|l| l.build_response(includes, &config))
+			.collect::<Result<_, Error>>()?;
+
+		let collection = LocationFeatureCollectionResponse::from(
+			p_opts.paginate(total, truncated, features),
+		);
+		let headers = [(header::CONTENT_TYPE, GEOJSON_CONTENT_TYPE)];
+
+		return Ok((StatusCode::OK, headers, Json(collection)).into_response());
+	}
+
+	let summary_pool = pool.clone();
+	let summary_ids = l_ids.clone();
+	let summaries = tokio::spawn(async move {
+		let conn = summary_pool.get().await?;
+
+		Location::get_upcoming_availability_summary_for_ids(summary_ids, &conn)
+			.await
+	});
+
+	let (times, tags, ratings, imgs) = tokio::join!(
 		OpeningTime::get_for_locations(
 			l_ids.clone(),
 			OpeningTimeIncludes::default(),
 			&conn
 		),
 		Tag::get_for_locations(l_ids.clone(), TagIncludes::default(), &conn),
+		Review::get_aggregates_for_locations(l_ids.clone(), &conn),
 		Image::get_for_locations(l_ids, ImageIncludes::default(), &conn),
 	);
 
 	let times = times?;
 	let tags = tags?;
+	let ratings = ratings?;
 	let imgs = imgs?;
 
-	let locations = Location::group(locations, &times, &tags, &imgs);
+	let mut summaries = summaries
+		.await
+		.map_err(InternalServerError::JoinError)??
+		.into_iter()
+		.map(|s| (s.location_id, s))
+		.collect::<HashMap<_, _>>();
+
+	let locations = Location::group(locations, &times, &tags, &imgs, &ratings);
 
 	let locations: Vec<LocationResponse> = locations
 		.into_iter()
-		.map(|l| l.build_response(includes, &config))
-		.collect::<Result<_, _>>()?;
+		.map(|l| {
+			let mut response = l.build_response(includes, &config)?;
+
+			response.availability_summary =
+				summaries.remove(&response.id).map(Into::into);
+
+			Ok(response)
+		})
+		.collect::<Result<_, Error>>()?;
+
+	let paginated = p_opts.paginate(total, truncated, locations);
+
+	if let Some(key) = cache_key {
+		let cached = serde_json::to_string(&paginated)?;
+
+		let _: () =
+			r_conn.set_ex(key, cached, NEWEST_LOCATIONS_CACHE_SECONDS).await?;
+	}
+
+	Ok((StatusCode::OK, Json(paginated)).into_response())
+}
+
+/// Get all locations sorted by their amount of (non-cancelled) reservations.
+#[instrument(skip(pool))]
+pub(crate) async fn get_locations_by_reservation_count(
+	State(pool): State<DbPool>,
+	State(config): State<Config>,
+	session: AdminSession,
+	Query(options): Query<ReservationCountOptions>,
+	Query(p_opts): Query<PaginationOptions>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let (total, truncated, locations) = Location::get_by_reservation_count(
+		options.direction,
+		p_opts.into(),
+		&conn,
+	)
+	.await?;
+
+	let locations: Vec<LocationReservationCountResponse> = locations
+		.into_iter()
+		.map(|(l, reservation_count)| {
+			let location =
+				l.build_response(LocationIncludes::default(), &config)?;
+
+			Ok(LocationReservationCountResponse { location, reservation_count })
+		})
+		.collect::<Result<_, Error>>()?;
 
 	let paginated = p_opts.paginate(total, truncated, locations);
 
 	Ok((StatusCode::OK, Json(paginated)))
 }
 
+/// Get the amount of locations per postcode
+#[instrument(skip(pool))]
+pub(crate) async fn get_location_count_by_zip(
+	State(pool): State<DbPool>,
+	session: AdminSession,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let counts = Location::get_count_by_zip(&conn).await?;
+	let response: Vec<ZipLocationCountResponse> =
+		counts.into_iter().map(Into::into).collect();
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
 /// Update a location in the database.
+#[utoipa::path(
+	patch,
+	path = "/locations/{id}",
+	tag = "locations",
+	params(
+		("id" = i32, Path, description = "Id of the location"),
+		LocationIncludes
+	),
+	request_body = UpdateLocationRequest,
+	responses(
+		(
+			status = 200,
+			description = "The updated location",
+			body = LocationResponse
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown location", body = ErrorResponse),
+		(status = 422, description = "Invalid data", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub(crate) async fn update_location(
 	State(pool): State<DbPool>,
 	State(config): State<Config>,
 	session: Session,
 	Path(id): Path<i32>,
-	Query(includes): Query<LocationIncludes>,
+	Includes(includes): Includes<LocationIncludes>,
 	Json(request): Json<UpdateLocationRequest>,
 ) -> Result<impl IntoResponse, Error> {
 	check_location_perms(
@@ -259,10 +996,243 @@ pub(crate) async fn update_location(
 	Ok((StatusCode::OK, Json(response)))
 }
 
-/// Approve a location in the database.
+/// Update the description of a location.
+#[utoipa::path(
+	patch,
+	path = "/locations/{id}/description",
+	tag = "locations",
+	params(("id" = i32, Path, description = "Id of the location")),
+	request_body = UpdateTranslationRequest,
+	responses(
+		(status = 204, description = "Updated the description"),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown location", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub(crate) async fn update_location_description(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+	Json(request): Json<UpdateTranslationRequest>,
+) -> Result<impl IntoResponse, Error> {
+	check_location_perms(
+		id,
+		session.data.profile_id,
+		LocationPermissions::Administrator,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	let update = request.to_insertable(session.data.profile_id);
+	Location::update_description(id, update, session.data.profile_id, &conn)
+		.await?;
+
+	Ok((StatusCode::NO_CONTENT, NoContent))
+}
+
+/// Update the excerpt of a location.
+#[utoipa::path(
+	patch,
+	path = "/locations/{id}/excerpt",
+	tag = "locations",
+	params(("id" = i32, Path, description = "Id of the location")),
+	request_body = UpdateTranslationRequest,
+	responses(
+		(status = 204, description = "Updated the excerpt"),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown location", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub(crate) async fn update_location_excerpt(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+	Json(request): Json<UpdateTranslationRequest>,
+) -> Result<impl IntoResponse, Error> {
+	check_location_perms(
+		id,
+		session.data.profile_id,
+		LocationPermissions::Administrator,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	let update = request.to_insertable(session.data.profile_id);
+	Location::update_excerpt(id, update, session.data.profile_id, &conn)
+		.await?;
+
+	Ok((StatusCode::NO_CONTENT, NoContent))
+}
+
+/// Get all translations of a location.
+#[utoipa::path(
+	get,
+	path = "/locations/{id}/translations",
+	tag = "locations",
+	params(("id" = i32, Path, description = "Id of the location")),
+	responses(
+		(
+			status = 200,
+			description = "The translations",
+			body = LocationTranslationsResponse
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
+pub(crate) async fn get_location_translations(
+	State(pool): State<DbPool>,
+	State(config): State<Config>,
+	session: Session,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	check_location_perms(
+		id,
+		session.data.profile_id,
+		LocationPermissions::Administrator,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	let translations = Translation::get_for_location(id, &conn).await?;
+	let response = translations.build_response((), &config)?;
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Get the effective permissions of the current profile for a location,
+/// including those it holds through the authority and institution
+#[utoipa::path(
+	get,
+	path = "/locations/{id}/permissions",
+	tag = "locations",
+	params(("id" = i32, Path, description = "Id of the location")),
+	responses(
+		(
+			status = 200,
+			description = "The effective permissions",
+			body = LocationPermissionsResponse
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 404, description = "Unknown location", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub(crate) async fn get_location_permissions(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	let permissions =
+		EffectivePermissions::for_location(id, session.data.profile_id, &pool)
+			.await?;
+	let response = LocationPermissionsResponse::from(permissions);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Get a page of the locations that are still awaiting approval.
+///
+/// Without an authority only admins can see the pending locations, otherwise
+/// members of that authority that can approve locations can see them too.
+/// The creator of each location is always included.
+#[utoipa::path(
+	get,
+	path = "/locations/pending",
+	tag = "locations",
+	params(PendingFilter, LocationIncludes, PaginationOptions),
+	responses(
+		(
+			status = 200,
+			description = "A page of pending locations",
+			body = PaginatedResponse<Vec<LocationResponse>>
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub(crate) async fn get_pending_locations(
+	State(pool): State<DbPool>,
+	State(config): State<Config>,
+	session: Session,
+	Query(filter): Query<PendingFilter>,
+	Includes(includes): Includes<LocationIncludes>,
+	Query(p_opts): Query<PaginationOptions>,
+) -> Result<impl IntoResponse, Error> {
+	if !session.data.is_admin {
+		let Some(auth_id) = filter.authority_id else {
+			return Err(Error::Forbidden);
+		};
+
+		check_authority_perms(
+			auth_id,
+			session.data.profile_id,
+			AuthorityPermissions::ApproveLocations
+				| AuthorityPermissions::Administrator,
+			InstitutionPermissions::Administrator,
+			&pool,
+		)
+		.await?;
+	}
+
+	let includes = LocationIncludes { created_by: true, ..includes };
+
+	let conn = pool.get().await?;
+
+	let (total, truncated, locations) =
+		Location::get_pending(filter, includes, p_opts.into(), &conn).await?;
+	let locations: Vec<LocationResponse> = locations
+		.into_iter()
+		.map(|l| l.build_response(includes, &config))
+		.collect::<Result<_, _>>()?;
+
+	let paginated = p_opts.paginate(total, truncated, locations);
+
+	Ok((StatusCode::OK, Json(paginated)))
+}
+
+/// Approve a location in the database.
+#[utoipa::path(
+	post,
+	path = "/locations/{id}/approve",
+	tag = "locations",
+	params(("id" = i32, Path, description = "Id of the location")),
+	responses(
+		(status = 204, description = "Approved the location"),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown location", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool, webhooks))]
 pub(crate) async fn approve_location(
+	State(config): State<Config>,
 	State(pool): State<DbPool>,
+	State(webhooks): State<WebhookDispatcher>,
 	session: Session,
 	Path(id): Path<i32>,
 ) -> Result<impl IntoResponse, Error> {
@@ -288,13 +1258,125 @@ pub(crate) async fn approve_location(
 
 	Location::approve_by(id, session.data.profile_id, &conn).await?;
 
+	AuditLog::record(
+		session.data.profile_id,
+		AuditAction::LocationApproved,
+		AuditTarget::Location,
+		id,
+		json!({ "authorityId": location.authority_id }),
+		&conn,
+	)
+	.await;
+
+	if let Some(auth_id) = location.authority_id {
+		notify_location_webhooks(
+			id,
+			auth_id,
+			WebhookEvent::LocationApproved,
+			&webhooks,
+			&config,
+			&conn,
+		)
+		.await;
+	}
+
 	Ok((StatusCode::NO_CONTENT, NoContent))
 }
 
-/// Reject a location in the database.
+/// Make a location publicly visible.
+#[utoipa::path(
+	post,
+	path = "/locations/{id}/publish",
+	tag = "locations",
+	params(("id" = i32, Path, description = "Id of the location")),
+	responses(
+		(status = 204, description = "Published the location"),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown location", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
+pub(crate) async fn publish_location(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	check_location_perms(
+		id,
+		session.data.profile_id,
+		LocationPermissions::Administrator,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	Location::set_visibility(id, true, session.data.profile_id, &conn).await?;
+
+	Ok((StatusCode::NO_CONTENT, NoContent))
+}
+
+/// Hide a location from the public.
+#[utoipa::path(
+	post,
+	path = "/locations/{id}/unpublish",
+	tag = "locations",
+	params(("id" = i32, Path, description = "Id of the location")),
+	responses(
+		(status = 204, description = "Unpublished the location"),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown location", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub(crate) async fn unpublish_location(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	check_location_perms(
+		id,
+		session.data.profile_id,
+		LocationPermissions::Administrator,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	Location::set_visibility(id, false, session.data.profile_id, &conn).await?;
+
+	Ok((StatusCode::NO_CONTENT, NoContent))
+}
+
+/// Reject a location in the database.
+#[utoipa::path(
+	post,
+	path = "/locations/{id}/reject",
+	tag = "locations",
+	params(("id" = i32, Path, description = "Id of the location")),
+	request_body = RejectLocationRequest,
+	responses(
+		(status = 204, description = "Rejected the location"),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown location", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool, webhooks))]
 pub(crate) async fn reject_location(
+	State(config): State<Config>,
 	State(pool): State<DbPool>,
+	State(webhooks): State<WebhookDispatcher>,
 	session: Session,
 	Path(id): Path<i32>,
 	Json(request): Json<RejectLocationRequest>,
@@ -319,13 +1401,53 @@ pub(crate) async fn reject_location(
 		return Err(Error::Forbidden);
 	}
 
+	let detail = json!({
+		"authorityId": location.authority_id,
+		"reason": request.reason,
+	});
+
 	Location::reject_by(id, session.data.profile_id, request.reason, &conn)
 		.await?;
 
+	AuditLog::record(
+		session.data.profile_id,
+		AuditAction::LocationRejected,
+		AuditTarget::Location,
+		id,
+		detail,
+		&conn,
+	)
+	.await;
+
+	if let Some(auth_id) = location.authority_id {
+		notify_location_webhooks(
+			id,
+			auth_id,
+			WebhookEvent::LocationRejected,
+			&webhooks,
+			&config,
+			&conn,
+		)
+		.await;
+	}
+
 	Ok((StatusCode::NO_CONTENT, NoContent))
 }
 
 /// Delete a location from the database.
+#[utoipa::path(
+	delete,
+	path = "/locations/{id}",
+	tag = "locations",
+	params(("id" = i32, Path, description = "Id of the location")),
+	responses(
+		(status = 204, description = "Deleted the location"),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown location", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub(crate) async fn delete_location(
 	State(pool): State<DbPool>,
@@ -366,6 +1488,80 @@ pub(crate) async fn delete_location(
 	Ok((StatusCode::NO_CONTENT, NoContent))
 }
 
+/// Permanently delete a location and everything related to it.
+#[instrument(skip(pool))]
+pub(crate) async fn hard_delete_location(
+	State(pool): State<DbPool>,
+	session: AdminSession,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let deletion = Location::hard_delete(id, &conn).await?;
+
+	// The records are gone at this point, a leftover file should not fail the
+	// request
+	for file_path in &deletion.image_files {
+		if let Err(e) = delete_image_file(file_path) {
+			warn!("could not delete image file {file_path}: {e:?}");
+		}
+	}
+
+	let response = LocationDeletionResponse::from(&deletion);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Merge a duplicate location into the location with the given id.
+#[utoipa::path(
+	post,
+	path = "/locations/{id}/merge",
+	tag = "locations",
+	params(("id" = i32, Path, description = "Id of the location")),
+	request_body = MergeLocationRequest,
+	responses(
+		(
+			status = 200,
+			description = "The merged records",
+			body = LocationMergeResponse
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown location", body = ErrorResponse),
+		(status = 422, description = "Invalid data", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub(crate) async fn merge_location(
+	State(pool): State<DbPool>,
+	session: AdminSession,
+	Path(id): Path<i32>,
+	Json(request): Json<MergeLocationRequest>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let merge = Location::merge(request.source_id, id, &conn).await?;
+
+	let response = LocationMergeResponse::from(&merge);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+#[utoipa::path(
+	post,
+	path = "/locations/{id}/tags",
+	tag = "locations",
+	params(("id" = i32, Path, description = "Id of the location")),
+	request_body = SetLocationTagsRequest,
+	responses(
+		(status = 204, description = "Set the tags"),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown location", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub async fn set_location_tags(
 	State(pool): State<DbPool>,
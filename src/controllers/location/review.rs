@@ -22,8 +22,9 @@ pub async fn create_location_review(
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
 
+	// Admins can review locations without having visited them
 	let new_review = request.to_insertable(session.data.profile_id, id)?;
-	let review = new_review.insert(&conn).await?;
+	let review = new_review.insert(session.data.is_admin, &conn).await?;
 	let response: ReviewResponse = review.into();
 
 	Ok((StatusCode::OK, Json(response)))
@@ -43,7 +44,8 @@ pub async fn get_location_reviews(
 	let response: Vec<_> =
 		reviews.into_iter().map(ReviewResponse::from).collect();
 
-	let response = p_opts.paginate(total, truncated, response);
+	let response =
+		p_opts.paginate_with_cursor(total, truncated, response, |r| r.id);
 
 	Ok((StatusCode::OK, Json(response)))
 }
@@ -8,6 +8,7 @@ use permissions::{
 	InstitutionPermissions,
 	LocationPermissions,
 	check_location_perms,
+	check_location_role_grant,
 };
 use role::{LocationRole, RoleIncludes};
 
@@ -15,6 +16,7 @@ use crate::schemas::BuildResponse;
 use crate::schemas::role::{
 	CreateRoleRequest,
 	RoleResponse,
+	SetDefaultRoleRequest,
 	UpdateRoleRequest,
 };
 use crate::{Config, Session};
@@ -137,3 +139,43 @@ pub(crate) async fn delete_location_role(
 
 	Ok((StatusCode::NO_CONTENT, NoContent))
 }
+
+/// Mark a [`LocationRole`] as the default role of its location
+#[instrument(skip(pool))]
+pub(crate) async fn set_default_location_role(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(loc_id): Path<i32>,
+	Query(includes): Query<RoleIncludes>,
+	Json(request): Json<SetDefaultRoleRequest>,
+) -> Result<impl IntoResponse, Error> {
+	check_location_perms(
+		loc_id,
+		session.data.profile_id,
+		LocationPermissions::ManageMembers | LocationPermissions::Administrator,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	// The default role is granted to new members, so it may not exceed the
+	// permissions of whoever sets it
+	check_location_role_grant(
+		loc_id,
+		session.data.profile_id,
+		Some(request.role_id),
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	let role =
+		LocationRole::set_default(loc_id, request.role_id, includes, &conn)
+			.await?;
+	let response = role.build_response(includes, &config)?;
+
+	Ok((StatusCode::OK, Json(response)))
+}
@@ -1,29 +1,119 @@
 //! Defines controller functions that correspond to individual routes
 
-use axum::extract::State;
-use axum::response::NoContent;
-use common::Error;
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, NoContent, Response};
+use common::{Error, RedisConn};
 use diesel::{RunQueryDsl, sql_query};
 
-use crate::DbPool;
+use crate::mailer::Mailer;
+use crate::schemas::health::{
+	HealthResponse,
+	HealthStatus,
+	HealthcheckOptions,
+	SubsystemHealth,
+};
+use crate::{Config, DbPool};
 
+pub mod audit_log;
 pub mod auth;
 pub mod authority;
 pub mod institution;
 pub mod location;
+pub mod openapi;
 pub mod opening_time;
 pub mod profile;
 pub mod reservation;
+pub mod review;
 pub mod tag;
 pub mod translation;
 
+/// How long a single subsystem of a deep healthcheck may take
+const HEALTHCHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
 /// Check if the database connection and webserver are functional
+///
+/// A deep healthcheck also checks Redis and the SMTP server, and reports the
+/// status of every subsystem
 pub(crate) async fn healthcheck(
+	State(config): State<Config>,
 	State(pool): State<DbPool>,
-) -> Result<NoContent, Error> {
+	State(r_conn): State<RedisConn>,
+	State(mailer): State<Mailer>,
+	Query(options): Query<HealthcheckOptions>,
+) -> Result<Response, Error> {
+	if !options.deep {
+		check_database(&pool).await?;
+
+		return Ok(NoContent.into_response());
+	}
+
+	let (database, redis, mailer) = tokio::join!(
+		check_subsystem("database", check_database(&pool)),
+		check_subsystem("redis", check_redis(r_conn)),
+		async {
+			if mailer.is_stub() {
+				SubsystemHealth::skipped()
+			} else {
+				check_subsystem(
+					"mailer",
+					Mailer::test_smtp_connection(&config, HEALTHCHECK_TIMEOUT),
+				)
+				.await
+			}
+		},
+	);
+
+	let response = HealthResponse { database, redis, mailer };
+	let status = if response.is_healthy() {
+		StatusCode::OK
+	} else {
+		StatusCode::SERVICE_UNAVAILABLE
+	};
+
+	Ok((status, Json(response)).into_response())
+}
+
+/// Run a single check of a deep healthcheck and time it
+async fn check_subsystem(
+	name: &str,
+	check: impl Future<Output = Result<(), Error>>,
+) -> SubsystemHealth {
+	let start = Instant::now();
+
+	let result = tokio::time::timeout(HEALTHCHECK_TIMEOUT, check).await;
+	let latency_ms = u64::try_from(start.elapsed().as_millis()).ok();
+
+	let status = match result {
+		Ok(Ok(())) => HealthStatus::Ok,
+		Ok(Err(e)) => {
+			warn!("healthcheck of {name} failed: {e:?}");
+
+			HealthStatus::Failing
+		},
+		Err(_) => {
+			warn!("healthcheck of {name} timed out");
+
+			HealthStatus::Failing
+		},
+	};
+
+	SubsystemHealth { status, latency_ms }
+}
+
+async fn check_database(pool: &DbPool) -> Result<(), Error> {
 	let conn = pool.get().await?;
 
 	conn.interact(|conn| sql_query("SELECT 1").execute(conn)).await??;
 
-	Ok(NoContent)
+	Ok(())
+}
+
+async fn check_redis(mut r_conn: RedisConn) -> Result<(), Error> {
+	let _: String = redis::cmd("PING").query_async(&mut r_conn).await?;
+
+	Ok(())
 }
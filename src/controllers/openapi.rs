@@ -0,0 +1,45 @@
+//! Controllers serving the OpenAPI document
+
+use axum::Json;
+use axum::extract::State;
+use axum::response::{Html, IntoResponse};
+
+use crate::Config;
+use crate::openapi::openapi;
+
+/// Swagger UI page rendering the document at `/openapi.json`
+const SWAGGER_UI_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+	<meta charset="utf-8" />
+	<title>Blokmap API</title>
+	<link
+		rel="stylesheet"
+		href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css"
+	/>
+</head>
+<body>
+	<div id="swagger-ui"></div>
+	<script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js">
+	</script>
+	<script>
+		window.ui = SwaggerUIBundle({
+			url: "/openapi.json",
+			dom_id: "#swagger-ui",
+		});
+	</script>
+</body>
+</html>
+"#;
+
+/// Get the OpenAPI document describing the api
+pub(crate) async fn get_openapi_document(
+	State(config): State<Config>,
+) -> impl IntoResponse {
+	Json(openapi(&config))
+}
+
+/// Get a Swagger UI page to browse the OpenAPI document
+pub(crate) async fn get_api_docs() -> impl IntoResponse {
+	Html(SWAGGER_UI_PAGE)
+}
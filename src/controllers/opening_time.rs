@@ -1,18 +1,139 @@
+use std::collections::HashMap;
+
 use axum::Json;
 use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
-use common::{DbPool, Error};
-use opening_time::{NewOpeningTime, OpeningTime, OpeningTimeIncludes};
+use base::RESERVATION_BLOCK_SIZE_MINUTES;
+use chrono::TimeDelta;
+use common::{DbConn, DbPool, Error, ErrorResponse};
+use location::{Location, LocationIncludes};
+use opening_time::{
+	NewOpeningTime,
+	OpeningTime,
+	OpeningTimeIncludes,
+	PublicHolidayService,
+};
+use permissions::{
+	AuthorityPermissions,
+	InstitutionPermissions,
+	LocationPermissions,
+	check_location_perms,
+};
+use primitives::PrimitiveReservation;
+use profile::Profile;
+use reservation::Reservation;
+use validator::Validate;
 
+use crate::mailer::Mailer;
 use crate::schemas::BuildResponse;
+use crate::schemas::includes::Includes;
 use crate::schemas::opening_time::{
 	CreateOpeningTimeRequest,
+	CreateOpeningTimeSeriesRequest,
+	DeletedOpeningTimeResponse,
+	GeneratedHolidayExceptionsResponse,
+	HolidayExceptionOptions,
+	NextAvailableOpeningTimeResponse,
+	NextAvailableOpeningTimesRequest,
+	OpeningTimeBlocksResponse,
 	OpeningTimeResponse,
 	UpdateOpeningTimeRequest,
 };
 use crate::{Config, Session};
 
+#[instrument(skip(pool))]
+pub async fn get_opening_time_location(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	Path(id): Path<i32>,
+	Includes(includes): Includes<LocationIncludes>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let location =
+		Location::get_by_opening_time_id(id, includes, &conn).await?;
+	let response = location.build_response(includes, &config)?;
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+#[instrument(skip(pool))]
+pub async fn get_next_available_opening_times(
+	State(pool): State<DbPool>,
+	Json(request): Json<NextAvailableOpeningTimesRequest>,
+) -> Result<impl IntoResponse, Error> {
+	request.validate()?;
+
+	let conn = pool.get().await?;
+
+	let next_available = OpeningTime::get_next_available_for_locations(
+		request.location_ids,
+		&conn,
+	)
+	.await?;
+	let response: HashMap<i32, Option<NextAvailableOpeningTimeResponse>> =
+		next_available
+			.into_iter()
+			.map(|(id, time)| (id, time.map(Into::into)))
+			.collect();
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Get whether each block of an opening time still has a free seat
+#[utoipa::path(
+	get,
+	path = "/opening-times/{id}/availability",
+	tag = "locations",
+	params(("id" = i32, Path, description = "Id of the opening time")),
+	responses(
+		(
+			status = 200,
+			description = "The availability of every block",
+			body = OpeningTimeBlocksResponse
+		),
+		(
+			status = 404,
+			description = "Unknown opening time",
+			body = ErrorResponse
+		)
+	)
+)]
+#[instrument(skip(pool))]
+pub async fn get_opening_time_availability(
+	State(pool): State<DbPool>,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let blocks = Reservation::get_blocks_for_opening_time(id, &conn).await?;
+	let response = OpeningTimeBlocksResponse::from(blocks);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+#[utoipa::path(
+	post,
+	path = "/locations/{id}/opening-times",
+	tag = "locations",
+	params(
+		("id" = i32, Path, description = "Id of the location"),
+		OpeningTimeIncludes
+	),
+	request_body = Vec<CreateOpeningTimeRequest>,
+	responses(
+		(
+			status = 201,
+			description = "The new opening times",
+			body = Vec<OpeningTimeResponse>
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 422, description = "Invalid data", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub async fn create_location_opening_times(
 	State(pool): State<DbPool>,
@@ -35,32 +156,297 @@ pub async fn create_location_opening_times(
 	Ok((StatusCode::CREATED, Json(response)))
 }
 
+/// Create an opening time on every selected weekday between two dates
+#[utoipa::path(
+	post,
+	path = "/locations/{id}/opening-times/series",
+	tag = "locations",
+	params(("id" = i32, Path, description = "Id of the location")),
+	request_body = CreateOpeningTimeSeriesRequest,
+	responses(
+		(
+			status = 201,
+			description = "The new opening times",
+			body = Vec<OpeningTimeResponse>
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 422, description = "Invalid data", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
+pub async fn create_location_opening_time_series(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+	Json(request): Json<CreateOpeningTimeSeriesRequest>,
+) -> Result<impl IntoResponse, Error> {
+	check_location_perms(
+		id,
+		session.data.profile_id,
+		LocationPermissions::ManageOpeningTimes
+			| LocationPermissions::Administrator,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	let series = request.to_insertable(id, session.data.profile_id);
+	let new_times = series.insert(&conn).await?;
+	let response: Vec<OpeningTimeResponse> =
+		new_times.into_iter().map(Into::into).collect();
+
+	Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Email the profiles of reservations that were cancelled because the
+/// opening time they belonged to changed
+///
+/// The opening time should be given as it was before the change
+async fn notify_cancelled_reservations(
+	l_id: i32,
+	time: &OpeningTime,
+	cancelled: &[PrimitiveReservation],
+	mailer: &Mailer,
+	conn: &DbConn,
+) -> Result<(), Error> {
+	if cancelled.is_empty() {
+		return Ok(());
+	}
+
+	let (location, _) =
+		Location::get_by_id(l_id, LocationIncludes::default(), conn).await?;
+
+	let block_size = i64::from(RESERVATION_BLOCK_SIZE_MINUTES);
+	let opening = time.primitive.day.and_time(time.primitive.start_time);
+
+	for reservation in cancelled {
+		let profile = Profile::get(reservation.profile_id, conn).await?;
+
+		let offset = i64::from(reservation.base_block_index) * block_size;
+		let length = i64::from(reservation.block_count) * block_size;
+
+		let start = opening + TimeDelta::minutes(offset);
+		let end = start + TimeDelta::minutes(length);
+
+		mailer
+			.send_reservation_cancelled(
+				&profile,
+				&location.primitive.name,
+				start,
+				end,
+			)
+			.await?;
+	}
+
+	Ok(())
+}
+
+#[utoipa::path(
+	patch,
+	path = "/locations/{id}/opening-times/{time_id}",
+	tag = "locations",
+	params(
+		("id" = i32, Path, description = "Id of the location"),
+		("time_id" = i32, Path, description = "Id of the opening time"),
+		OpeningTimeIncludes
+	),
+	request_body = UpdateOpeningTimeRequest,
+	responses(
+		(
+			status = 200,
+			description = "The updated opening time",
+			body = OpeningTimeResponse
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(
+			status = 404,
+			description = "Unknown opening time",
+			body = ErrorResponse
+		),
+		(status = 422, description = "Invalid data", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool, mailer))]
 pub async fn update_location_opening_time(
 	State(config): State<Config>,
 	State(pool): State<DbPool>,
+	State(mailer): State<Mailer>,
 	session: Session,
 	Path((id, time_id)): Path<(i32, i32)>,
 	Query(includes): Query<OpeningTimeIncludes>,
 	Json(request): Json<UpdateOpeningTimeRequest>,
 ) -> Result<impl IntoResponse, Error> {
+	check_location_perms(
+		id,
+		session.data.profile_id,
+		LocationPermissions::ManageOpeningTimes
+			| LocationPermissions::Administrator,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
 	let conn = pool.get().await?;
 
+	// Return not found if the opening time doesn't belong to this location
+	let old_time = OpeningTime::get_by_id_and_location(
+		time_id,
+		id,
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await?;
+
 	let time_update = request.to_insertable(session.data.profile_id);
-	let updated_time = time_update.apply_to(time_id, includes, &conn).await?;
-	let response = updated_time.build_response(includes, &config)?;
+	let (updated_time, cancelled) =
+		time_update.apply_to(time_id, includes, &conn).await?;
+
+	notify_cancelled_reservations(id, &old_time, &cancelled, &mailer, &conn)
+		.await?;
+
+	let mut response = updated_time.build_response(includes, &config)?;
+	response.cancelled_reservations = Some(cancelled.len());
 
 	Ok((StatusCode::OK, Json(response)))
 }
 
-#[instrument(skip(pool))]
+#[utoipa::path(
+	delete,
+	path = "/locations/{id}/opening-times/{time_id}",
+	tag = "locations",
+	params(
+		("id" = i32, Path, description = "Id of the location"),
+		("time_id" = i32, Path, description = "Id of the opening time")
+	),
+	responses(
+		(
+			status = 200,
+			description = "The number of cancelled reservations",
+			body = DeletedOpeningTimeResponse
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(
+			status = 404,
+			description = "Unknown opening time",
+			body = ErrorResponse
+		)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool, mailer))]
 pub async fn delete_location_opening_time(
 	State(pool): State<DbPool>,
+	State(mailer): State<Mailer>,
+	session: Session,
 	Path((id, time_id)): Path<(i32, i32)>,
 ) -> Result<impl IntoResponse, Error> {
+	check_location_perms(
+		id,
+		session.data.profile_id,
+		LocationPermissions::ManageOpeningTimes
+			| LocationPermissions::Administrator,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	let conn = pool.get().await?;
+
+	// Return not found if the opening time doesn't belong to this location
+	let time = OpeningTime::get_by_id_and_location(
+		time_id,
+		id,
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await?;
+
+	let cancelled = OpeningTime::delete_by_id(time_id, &conn).await?;
+
+	notify_cancelled_reservations(id, &time, &cancelled, &mailer, &conn)
+		.await?;
+
+	let response =
+		DeletedOpeningTimeResponse { cancelled_reservations: cancelled.len() };
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Record an exception for every opening time of a location that falls on a
+/// public holiday of a given country and year
+///
+/// Exceptions only mark the opening times, they are still shown and can
+/// still be reserved
+#[utoipa::path(
+	post,
+	path = "/locations/{id}/opening-times/generate-holiday-exceptions",
+	tag = "locations",
+	params(
+		("id" = i32, Path, description = "Id of the location"),
+		HolidayExceptionOptions
+	),
+	responses(
+		(
+			status = 201,
+			description = "The new exceptions",
+			body = GeneratedHolidayExceptionsResponse
+		),
+		(status = 400, description = "Unknown country", body = ErrorResponse),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown location", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub async fn generate_location_holiday_exceptions(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+	Query(options): Query<HolidayExceptionOptions>,
+) -> Result<impl IntoResponse, Error> {
+	check_location_perms(
+		id,
+		session.data.profile_id,
+		LocationPermissions::Administrator,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
+
+	if !PublicHolidayService::has_country(&options.country) {
+		return Err(Error::BadRequest(format!(
+			"no public holidays are known for country '{}'",
+			options.country
+		)));
+	}
+
 	let conn = pool.get().await?;
 
-	OpeningTime::delete_by_id(time_id, &conn).await?;
+	let holidays =
+		PublicHolidayService::get_holidays(&options.country, options.year);
+	let exceptions = OpeningTime::generate_exceptions_from_public_holidays(
+		id,
+		holidays,
+		session.data.profile_id,
+		&conn,
+	)
+	.await?;
 
-	Ok(StatusCode::NO_CONTENT)
+	let response = GeneratedHolidayExceptionsResponse {
+		created_exceptions: exceptions.into_iter().map(Into::into).collect(),
+	};
+
+	Ok((StatusCode::CREATED, Json(response)))
 }
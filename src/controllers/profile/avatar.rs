@@ -2,16 +2,35 @@ use axum::Json;
 use axum::extract::{Multipart, Path, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, NoContent};
-use common::{DbPool, Error};
-use profile::Profile;
-use utils::image::{delete_image, store_profile_image};
+use common::{DbPool, Error, ErrorResponse};
+use image::Image;
+use utils::image::{delete_image_file, store_profile_image};
 
-use crate::Session;
-use crate::schemas::image::CreateImageRequest;
+use crate::schemas::image::{CreateImageRequest, ImageUploadForm};
+use crate::{Config, Session};
 
-#[instrument(skip(pool, data))]
+#[utoipa::path(
+	post,
+	path = "/profiles/{profile_id}/avatar",
+	tag = "profiles",
+	params(("profile_id" = i32, Path, description = "Id of the profile")),
+	request_body(
+		content = ImageUploadForm,
+		content_type = "multipart/form-data"
+	),
+	responses(
+		(status = 201, description = "Stored the new avatar"),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 413, description = "Image too large", body = ErrorResponse),
+		(status = 422, description = "Invalid data", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool, config, data))]
 pub async fn upload_profile_avatar(
 	State(pool): State<DbPool>,
+	State(config): State<Config>,
 	session: Session,
 	Path(p_id): Path<i32>,
 	mut data: Multipart,
@@ -22,17 +41,47 @@ pub async fn upload_profile_avatar(
 
 	let conn = pool.get().await?;
 
-	let profile = Profile::get(p_id, &conn).await?;
-	if let Some(img_id) = profile.primitive.avatar_image_id {
-		delete_image(img_id, &conn).await?;
-	}
-
 	let image_request = CreateImageRequest::parse(&mut data).await?;
-	let image = store_profile_image(p_id, image_request.into(), &conn).await?;
+	let image = store_profile_image(
+		p_id,
+		image_request.into(),
+		config.image_limits(),
+		&conn,
+	)
+	.await?;
 
 	Ok((StatusCode::CREATED, Json(image)))
 }
 
+/// Delete the avatar of a profile, together with its stored file
+async fn delete_avatar(p_id: i32, pool: &DbPool) -> Result<(), Error> {
+	let conn = pool.get().await?;
+
+	let file_path = Image::delete_avatar_for_profile(p_id, &conn).await?;
+
+	if let Some(file_path) = file_path {
+		tokio::spawn(async move {
+			if let Err(e) = delete_image_file(&file_path) {
+				warn!("failed to remove avatar file {file_path}: {e}");
+			}
+		});
+	}
+
+	Ok(())
+}
+
+#[utoipa::path(
+	delete,
+	path = "/profiles/{profile_id}/avatar",
+	tag = "profiles",
+	params(("profile_id" = i32, Path, description = "Id of the profile")),
+	responses(
+		(status = 204, description = "Deleted the avatar"),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub async fn delete_profile_avatar(
 	State(pool): State<DbPool>,
@@ -43,14 +92,27 @@ pub async fn delete_profile_avatar(
 		return Err(Error::Forbidden);
 	}
 
-	let conn = pool.get().await?;
+	delete_avatar(p_id, &pool).await?;
 
-	let profile = Profile::get(p_id, &conn).await?;
-	let Some(img_id) = profile.primitive.avatar_image_id else {
-		return Ok((StatusCode::NO_CONTENT, NoContent));
-	};
+	Ok((StatusCode::NO_CONTENT, NoContent))
+}
 
-	delete_image(img_id, &conn).await?;
+#[utoipa::path(
+	delete,
+	path = "/profiles/me/avatar",
+	tag = "profiles",
+	responses(
+		(status = 204, description = "Deleted the avatar"),
+		(status = 401, description = "Not logged in", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub async fn delete_current_profile_avatar(
+	State(pool): State<DbPool>,
+	session: Session,
+) -> Result<impl IntoResponse, Error> {
+	delete_avatar(session.data.profile_id, &pool).await?;
 
 	Ok((StatusCode::NO_CONTENT, NoContent))
 }
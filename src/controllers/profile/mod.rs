@@ -1,27 +1,43 @@
 //! Controllers for [`Profile`]s
 
+use audit_log::AuditLog;
 use authority::{Authority, AuthorityIncludes};
 use axum::extract::{Path, Query, Request, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, NoContent};
 use axum::{Json, RequestExt};
 use axum_extra::extract::PrivateCookieJar;
-use common::{DbPool, Error, RedisConn};
-use db::ProfileState;
-use location::{Location, LocationIncludes};
+use common::{DbConn, DbPool, Error, ErrorResponse, RedisConn};
+use db::{AuditAction, AuditTarget};
+use image::{Image, ImageIncludes};
+use location::{Location, LocationFavourite, LocationIncludes};
 use profile::{Profile, ProfileStats, UpdateProfile};
 use reservation::{Reservation, ReservationFilter, ReservationIncludes};
 use review::{Review, ReviewIncludes};
+use serde_json::json;
 use uuid::Uuid;
+use validator::Validate;
 
+use crate::controllers::auth::resend_confirmation_email;
+use crate::jobs::cleanup_pending_profiles;
 use crate::mailer::Mailer;
 use crate::schemas::BuildResponse;
-use crate::schemas::authority::AuthorityResponse;
+use crate::schemas::authority::{
+	AuthorityResponse,
+	ProfileAuthorityMembershipResponse,
+};
+use crate::schemas::image::ImageWithLocationResponse;
+use crate::schemas::includes::Includes;
 use crate::schemas::location::LocationResponse;
 use crate::schemas::pagination::{PaginatedResponse, PaginationOptions};
 use crate::schemas::profile::{
+	CleanupPendingResponse,
+	DeactivationReasonResponse,
+	DisableProfileRequest,
+	PendingVerificationResponse,
 	ProfileResponse,
 	ProfileStatsResponse,
+	UnlinkSsoRequest,
 	UpdateProfileRequest,
 };
 use crate::schemas::reservation::ReservationResponse;
@@ -29,10 +45,27 @@ use crate::schemas::review::ReviewResponse;
 use crate::{AdminSession, AppState, Config, Session};
 
 mod avatar;
+mod session;
 
 pub(crate) use avatar::*;
+pub(crate) use session::*;
 
 /// Get all [`Profile`]s
+#[utoipa::path(
+	get,
+	path = "/profiles",
+	tag = "profiles",
+	params(PaginationOptions),
+	responses(
+		(
+			status = 200,
+			description = "A page of profiles",
+			body = PaginatedResponse<Vec<ProfileResponse>>
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool, config))]
 pub async fn get_all_profiles(
 	State(pool): State<DbPool>,
@@ -49,13 +82,26 @@ pub async fn get_all_profiles(
 		.map(|data| data.build_response((), &config))
 		.collect::<Result<_, _>>()?;
 
-	let paginated = p_opts.paginate(total, truncated, profiles);
+	let paginated =
+		p_opts.paginate_with_cursor(total, truncated, profiles, |p| p.id);
 
 	Ok(Json(paginated))
 }
 
 /// # Panics
 /// Panics if the request doesn't have a valid cookie jar
+#[utoipa::path(
+	get,
+	path = "/profiles/me",
+	tag = "profiles",
+	responses(
+		(
+			status = 200,
+			description = "The logged in profile, `null` if logged out",
+			body = Option<ProfileResponse>
+		)
+	)
+)]
 #[instrument(skip(state, config, pool))]
 pub async fn get_current_profile(
 	State(state): State<AppState>,
@@ -89,6 +135,19 @@ pub async fn get_current_profile(
 	Ok((StatusCode::OK, Json(Some(response))))
 }
 
+#[utoipa::path(
+	get,
+	path = "/profiles/{profile_id}",
+	tag = "profiles",
+	params(("profile_id" = i32, Path, description = "Id of the profile")),
+	responses(
+		(status = 200, description = "The profile", body = ProfileResponse),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown profile", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool, config))]
 pub async fn get_profile(
 	State(pool): State<DbPool>,
@@ -108,6 +167,75 @@ pub async fn get_profile(
 	Ok((StatusCode::OK, Json(response)))
 }
 
+/// Send out the mails for a new pending email of a [`Profile`]
+///
+/// The pending email gets a confirmation link, the confirmed email (if any)
+/// gets a link to revert the change in case it was not requested by its owner
+async fn start_email_change(
+	profile: Profile,
+	config: &Config,
+	mailer: &Mailer,
+	conn: &DbConn,
+) -> Result<Profile, Error> {
+	let email_confirmation_token = Uuid::new_v4().to_string();
+
+	let mut profile = profile
+		.set_email_confirmation_token(
+			&email_confirmation_token,
+			config.email_confirmation_token_lifetime,
+			conn,
+		)
+		.await?;
+
+	mailer
+		.send_confirm_email(
+			&profile,
+			&email_confirmation_token,
+			&config.frontend_url,
+		)
+		.await?;
+
+	if profile.primitive.email.is_some() {
+		let email_revert_token = Uuid::new_v4().to_string();
+
+		profile = profile
+			.set_email_revert_token(
+				&email_revert_token,
+				config.email_revert_token_lifetime,
+				conn,
+			)
+			.await?;
+
+		mailer
+			.send_email_change_notice(
+				&profile,
+				&email_revert_token,
+				&config.frontend_url,
+			)
+			.await?;
+	}
+
+	info!("set new pending email for profile {}", profile.primitive.id);
+
+	Ok(profile)
+}
+
+#[utoipa::path(
+	patch,
+	path = "/profiles/me",
+	tag = "profiles",
+	request_body = UpdateProfileRequest,
+	responses(
+		(
+			status = 200,
+			description = "The updated profile",
+			body = ProfileResponse
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 409, description = "Conflict", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool, config, mailer))]
 pub async fn update_current_profile(
 	State(pool): State<DbPool>,
@@ -127,35 +255,72 @@ pub async fn update_current_profile(
 	if old_profile.primitive.pending_email
 		!= updated_profile.primitive.pending_email
 	{
-		let email_confirmation_token = Uuid::new_v4().to_string();
+		updated_profile =
+			start_email_change(updated_profile, &config, &mailer, &conn)
+				.await?;
+	}
 
-		updated_profile = updated_profile
-			.set_email_confirmation_token(
-				&email_confirmation_token,
-				config.email_confirmation_token_lifetime,
-				&conn,
-			)
-			.await?;
+	let response = updated_profile.build_response((), &config)?;
 
-		mailer
-			.send_confirm_email(
-				&updated_profile,
-				&email_confirmation_token,
-				&config.frontend_url,
-			)
-			.await?;
+	Ok((StatusCode::OK, Json(response)))
+}
 
-		info!(
-			"set new pending email for profile {}",
-			updated_profile.primitive.id
-		);
-	}
+/// Switch the current SSO-linked [`Profile`] over to password authentication
+#[utoipa::path(
+	post,
+	path = "/profiles/me/unlink-sso",
+	tag = "profiles",
+	request_body = UnlinkSsoRequest,
+	responses(
+		(
+			status = 200,
+			description = "The updated profile",
+			body = ProfileResponse
+		),
+		(status = 400, description = "Invalid request", body = ErrorResponse),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 422, description = "Invalid data", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip_all)]
+pub async fn unlink_current_profile_sso(
+	State(pool): State<DbPool>,
+	State(config): State<Config>,
+	session: Session,
+	Json(request): Json<UnlinkSsoRequest>,
+) -> Result<impl IntoResponse, Error> {
+	request.validate()?;
 
-	let response = updated_profile.build_response((), &config)?;
+	let conn = pool.get().await?;
+
+	let profile = Profile::get(session.data.profile_id, &conn).await?;
+	let profile = profile.unlink_sso(&request.password, &conn).await?;
+
+	let response = profile.build_response((), &config)?;
 
 	Ok((StatusCode::OK, Json(response)))
 }
 
+#[utoipa::path(
+	patch,
+	path = "/profiles/{profile_id}",
+	tag = "profiles",
+	params(("profile_id" = i32, Path, description = "Id of the profile")),
+	request_body = UpdateProfileRequest,
+	responses(
+		(
+			status = 200,
+			description = "The updated profile",
+			body = ProfileResponse
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown profile", body = ErrorResponse),
+		(status = 409, description = "Conflict", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool, config, mailer))]
 pub async fn update_profile(
 	State(pool): State<DbPool>,
@@ -179,28 +344,9 @@ pub async fn update_profile(
 	if old_profile.primitive.pending_email
 		!= updated_profile.primitive.pending_email
 	{
-		let email_confirmation_token = Uuid::new_v4().to_string();
-
-		updated_profile = updated_profile
-			.set_email_confirmation_token(
-				&email_confirmation_token,
-				config.email_confirmation_token_lifetime,
-				&conn,
-			)
-			.await?;
-
-		mailer
-			.send_confirm_email(
-				&updated_profile,
-				&email_confirmation_token,
-				&config.frontend_url,
-			)
-			.await?;
-
-		info!(
-			"set new pending email for profile {}",
-			updated_profile.primitive.id
-		);
+		updated_profile =
+			start_email_change(updated_profile, &config, &mailer, &conn)
+				.await?;
 	}
 
 	let response = updated_profile.build_response((), &config)?;
@@ -208,26 +354,68 @@ pub async fn update_profile(
 	Ok((StatusCode::OK, Json(response)))
 }
 
+#[utoipa::path(
+	post,
+	path = "/profiles/{profile_id}/block",
+	tag = "profiles",
+	params(("profile_id" = i32, Path, description = "Id of the profile")),
+	request_body = Option<DisableProfileRequest>,
+	responses(
+		(status = 204, description = "Disabled the profile"),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown profile", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub async fn disable_profile(
 	State(pool): State<DbPool>,
 	State(mut r_conn): State<RedisConn>,
 	session: AdminSession,
 	Path(profile_id): Path<i32>,
+	request: Option<Json<DisableProfileRequest>>,
 ) -> Result<NoContent, Error> {
 	let conn = pool.get().await?;
-	let mut profile = Profile::get(profile_id, &conn).await?;
+	let profile = Profile::get(profile_id, &conn).await?;
+
+	let reason = request.and_then(|Json(r)| r.reason);
+	let detail = json!({ "reason": reason });
 
-	profile.primitive.state = ProfileState::Disabled;
-	profile.update(&conn).await?;
+	profile
+		.deactivate_with_reason(reason, session.data.profile_id, &conn)
+		.await?;
 
-	Session::delete(profile_id, &mut r_conn).await?;
+	AuditLog::record(
+		session.data.profile_id,
+		AuditAction::ProfileDisabled,
+		AuditTarget::Profile,
+		profile_id,
+		detail,
+		&conn,
+	)
+	.await;
+
+	Session::delete_for_profile(profile_id, None, &mut r_conn).await?;
 
 	info!("disabled profile {profile_id}");
 
 	Ok(NoContent)
 }
 
+#[utoipa::path(
+	post,
+	path = "/profiles/{profile_id}/unblock",
+	tag = "profiles",
+	params(("profile_id" = i32, Path, description = "Id of the profile")),
+	responses(
+		(status = 204, description = "Activated the profile"),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown profile", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub async fn activate_profile(
 	State(pool): State<DbPool>,
@@ -235,60 +423,283 @@ pub async fn activate_profile(
 	Path(profile_id): Path<i32>,
 ) -> Result<NoContent, Error> {
 	let conn = pool.get().await?;
-	let mut profile = Profile::get(profile_id, &conn).await?;
+	let profile = Profile::get(profile_id, &conn).await?;
+
+	profile.activate(&conn).await?;
 
-	profile.primitive.state = ProfileState::Active;
-	profile.update(&conn).await?;
+	AuditLog::record(
+		session.data.profile_id,
+		AuditAction::ProfileActivated,
+		AuditTarget::Profile,
+		profile_id,
+		json!({}),
+		&conn,
+	)
+	.await;
 
 	info!("activated profile {profile_id}");
 
 	Ok(NoContent)
 }
 
+#[utoipa::path(
+	get,
+	path = "/profiles/{profile_id}/deactivation-reason",
+	tag = "profiles",
+	params(("profile_id" = i32, Path, description = "Id of the profile")),
+	responses(
+		(
+			status = 200,
+			description = "Why the profile was disabled",
+			body = DeactivationReasonResponse
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown profile", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub async fn get_deactivation_reason(
+	State(pool): State<DbPool>,
+	session: AdminSession,
+	Path(profile_id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+	let profile = Profile::get(profile_id, &conn).await?;
+
+	let response: DeactivationReasonResponse = profile.into();
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Get all [`Profile`]s that are still awaiting email verification
+#[instrument(skip(pool, config))]
+pub async fn get_pending_verification_profiles(
+	State(pool): State<DbPool>,
+	State(config): State<Config>,
+	session: AdminSession,
+	Query(p_opts): Query<PaginationOptions>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let (total, truncated, profiles) =
+		Profile::get_pending_email_verification(p_opts.into(), &conn).await?;
+
+	let profiles: Vec<PendingVerificationResponse> = profiles
+		.into_iter()
+		.map(|p| PendingVerificationResponse::from_profile(p, &config))
+		.collect::<Result<_, _>>()?;
+
+	let paginated = p_opts.paginate(total, truncated, profiles);
+
+	Ok((StatusCode::OK, Json(paginated)))
+}
+
+/// Resend the confirmation email of a [`Profile`] on behalf of an admin
+#[instrument(skip(pool, config, mailer))]
+pub async fn admin_resend_confirmation_email(
+	State(pool): State<DbPool>,
+	State(config): State<Config>,
+	State(mailer): State<Mailer>,
+	session: AdminSession,
+	Path(profile_id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	resend_confirmation_email(
+		State(pool),
+		State(config),
+		State(mailer),
+		Path(profile_id),
+	)
+	.await
+}
+
+/// Delete all unverified [`Profile`]s with an expired confirmation token
+#[instrument(skip(pool))]
+pub async fn admin_cleanup_pending_profiles(
+	State(pool): State<DbPool>,
+	session: AdminSession,
+) -> Result<impl IntoResponse, Error> {
+	let deleted_profiles = cleanup_pending_profiles(&pool).await?;
+
+	let response = CleanupPendingResponse { deleted_profiles };
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+#[utoipa::path(
+	get,
+	path = "/profiles/{profile_id}/favourites",
+	tag = "profiles",
+	params(
+		("profile_id" = i32, Path, description = "Id of the profile"),
+		LocationIncludes,
+		PaginationOptions
+	),
+	responses(
+		(
+			status = 200,
+			description = "A page of favourite locations",
+			body = PaginatedResponse<Vec<LocationResponse>>
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub async fn get_profile_favourites(
+	State(pool): State<DbPool>,
+	State(config): State<Config>,
+	session: Session,
+	Includes(includes): Includes<LocationIncludes>,
+	Query(p_opts): Query<PaginationOptions>,
+	Path(profile_id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	if !session.data.is_admin && profile_id != session.data.profile_id {
+		return Err(Error::Forbidden);
+	}
+
+	let conn = pool.get().await?;
+
+	let (total, truncated, locations) = LocationFavourite::list_for_profile(
+		profile_id,
+		includes,
+		p_opts.into(),
+		&conn,
+	)
+	.await?;
+	let locations: Vec<LocationResponse> = locations
+		.into_iter()
+		.map(|l| l.build_response(includes, &config))
+		.collect::<Result<_, _>>()?;
+
+	let paginated = p_opts.paginate(total, truncated, locations);
+
+	Ok((StatusCode::OK, Json(paginated)))
+}
+
+#[utoipa::path(
+	get,
+	path = "/profiles/{profile_id}/locations",
+	tag = "profiles",
+	params(
+		("profile_id" = i32, Path, description = "Id of the profile"),
+		LocationIncludes,
+		PaginationOptions
+	),
+	responses(
+		(
+			status = 200,
+			description = "A page of created locations",
+			body = PaginatedResponse<Vec<LocationResponse>>
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub async fn get_profile_locations(
 	State(pool): State<DbPool>,
 	State(config): State<Config>,
-	Query(includes): Query<LocationIncludes>,
+	Includes(includes): Includes<LocationIncludes>,
+	Query(p_opts): Query<PaginationOptions>,
 	Path(profile_id): Path<i32>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
 
-	let locations =
-		Location::get_by_profile_id(profile_id, includes, &conn).await?;
-	let response: Vec<LocationResponse> = locations
+	let (total, truncated, locations) = Location::created_by_profile_paginated(
+		profile_id,
+		includes,
+		p_opts.into(),
+		&conn,
+	)
+	.await?;
+	let locations: Vec<LocationResponse> = locations
 		.into_iter()
 		.map(|l| l.build_response(includes, &config))
 		.collect::<Result<_, _>>()?;
 
-	Ok((StatusCode::OK, Json(response)))
+	let paginated = p_opts.paginate(total, truncated, locations);
+
+	Ok((StatusCode::OK, Json(paginated)))
 }
 
+#[utoipa::path(
+	get,
+	path = "/profiles/{profile_id}/reservations",
+	tag = "profiles",
+	params(
+		("profile_id" = i32, Path, description = "Id of the profile"),
+		ReservationFilter,
+		ReservationIncludes,
+		PaginationOptions
+	),
+	responses(
+		(
+			status = 200,
+			description = "A page of the reservations of the profile",
+			body = PaginatedResponse<Vec<ReservationResponse>>
+		),
+		(status = 400, description = "Invalid request", body = ErrorResponse),
+		(status = 401, description = "Not logged in", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub async fn get_profile_reservations(
 	State(config): State<Config>,
 	State(pool): State<DbPool>,
 	Query(filter): Query<ReservationFilter>,
-	Query(includes): Query<ReservationIncludes>,
+	Includes(includes): Includes<ReservationIncludes>,
+	Query(p_opts): Query<PaginationOptions>,
 	Path(profile_id): Path<i32>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
 
-	let reservations =
-		Reservation::for_profile(profile_id, filter, includes, &conn).await?;
-	let response: Vec<ReservationResponse> = reservations
+	let filter = filter.with_default_window(config.reservation_list_window);
+
+	let (total, truncated, reservations) = Reservation::for_profile(
+		profile_id,
+		filter,
+		includes,
+		p_opts.into(),
+		&conn,
+	)
+	.await?;
+	let reservations: Vec<ReservationResponse> = reservations
 		.into_iter()
 		.map(|r| r.build_response(includes, &config))
 		.collect::<Result<_, _>>()?;
 
-	Ok((StatusCode::OK, Json(response)))
+	let paginated = p_opts.paginate(total, truncated, reservations);
+
+	Ok((StatusCode::OK, Json(paginated)))
 }
 
+#[utoipa::path(
+	get,
+	path = "/profiles/{profile_id}/authorities",
+	tag = "profiles",
+	params(
+		("profile_id" = i32, Path, description = "Id of the profile"),
+		AuthorityIncludes
+	),
+	responses(
+		(
+			status = 200,
+			description = "The authorities of the profile",
+			body = Vec<AuthorityResponse>
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub async fn get_profile_authorities(
 	State(config): State<Config>,
 	State(pool): State<DbPool>,
-	Query(includes): Query<AuthorityIncludes>,
+	Includes(includes): Includes<AuthorityIncludes>,
 	Path(p_id): Path<i32>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
@@ -302,6 +713,150 @@ pub async fn get_profile_authorities(
 	Ok((StatusCode::OK, Json(response)))
 }
 
+/// Get the [`Authority`]s created by the current profile
+#[utoipa::path(
+	get,
+	path = "/profiles/me/created-authorities",
+	tag = "profiles",
+	params(AuthorityIncludes, PaginationOptions),
+	responses(
+		(
+			status = 200,
+			description = "A page of created authorities",
+			body = PaginatedResponse<Vec<AuthorityResponse>>
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub async fn get_current_profile_created_authorities(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: Session,
+	Includes(includes): Includes<AuthorityIncludes>,
+	Query(p_opts): Query<PaginationOptions>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let (total, truncated, authorities) = Authority::created_by_profile(
+		session.data.profile_id,
+		includes,
+		p_opts.into(),
+		&conn,
+	)
+	.await?;
+	let authorities: Vec<AuthorityResponse> = authorities
+		.into_iter()
+		.map(|a| a.build_response(includes, &config))
+		.collect::<Result<_, _>>()?;
+
+	let paginated = p_opts.paginate(total, truncated, authorities);
+
+	Ok((StatusCode::OK, Json(paginated)))
+}
+
+/// Get the [`Authority`]s the current profile is a member of, together
+/// with its role in each of them
+#[utoipa::path(
+	get,
+	path = "/profiles/me/authority-memberships",
+	tag = "profiles",
+	params(AuthorityIncludes),
+	responses(
+		(
+			status = 200,
+			description = "The memberships of the profile",
+			body = Vec<ProfileAuthorityMembershipResponse>
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub async fn get_current_profile_authority_memberships(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: Session,
+	Includes(includes): Includes<AuthorityIncludes>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let memberships = Authority::for_profile_with_role(
+		session.data.profile_id,
+		includes,
+		&conn,
+	)
+	.await?;
+	let response: Vec<ProfileAuthorityMembershipResponse> = memberships
+		.into_iter()
+		.map(|m| m.build_response(includes, &config))
+		.collect::<Result<_, _>>()?;
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Get the [`Image`]s uploaded by the current profile, newest first
+#[utoipa::path(
+	get,
+	path = "/profiles/me/uploaded-images",
+	tag = "profiles",
+	params(ImageIncludes, PaginationOptions),
+	responses(
+		(
+			status = 200,
+			description = "A page of uploaded images",
+			body = PaginatedResponse<Vec<ImageWithLocationResponse>>
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub async fn get_current_profile_uploaded_images(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: Session,
+	Query(includes): Query<ImageIncludes>,
+	Query(p_opts): Query<PaginationOptions>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let (total, truncated, images) = Image::get_by_uploaded_by(
+		session.data.profile_id,
+		includes,
+		p_opts.into(),
+		&conn,
+	)
+	.await?;
+	let images: Vec<ImageWithLocationResponse> = images
+		.into_iter()
+		.map(|i| i.build_response(includes, &config))
+		.collect::<Result<_, _>>()?;
+
+	let paginated = p_opts.paginate(total, truncated, images);
+
+	Ok((StatusCode::OK, Json(paginated)))
+}
+
+#[utoipa::path(
+	get,
+	path = "/profiles/{profile_id}/reviews",
+	tag = "profiles",
+	params(
+		("profile_id" = i32, Path, description = "Id of the profile"),
+		ReviewIncludes
+	),
+	responses(
+		(
+			status = 200,
+			description = "The reviews of the profile",
+			body = Vec<ReviewResponse>
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub async fn get_profile_reviews(
 	State(pool): State<DbPool>,
@@ -317,6 +872,21 @@ pub async fn get_profile_reviews(
 	Ok((StatusCode::OK, Json(response)))
 }
 
+#[utoipa::path(
+	get,
+	path = "/profiles/{profile_id}/stats",
+	tag = "profiles",
+	params(("profile_id" = i32, Path, description = "Id of the profile")),
+	responses(
+		(
+			status = 200,
+			description = "Reservation statistics",
+			body = ProfileStatsResponse
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub async fn get_profile_stats(
 	State(pool): State<DbPool>,
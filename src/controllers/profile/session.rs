@@ -0,0 +1,116 @@
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, NoContent};
+use common::{Error, ErrorResponse, RedisConn};
+
+use crate::Session;
+use crate::schemas::session::SessionResponse;
+
+/// Check whether a session may manage the sessions of a profile
+fn check_session_access(session: &Session, p_id: i32) -> Result<(), Error> {
+	if session.data.profile_id != p_id && !session.data.is_admin {
+		return Err(Error::Forbidden);
+	}
+
+	Ok(())
+}
+
+/// Get all active sessions of a profile
+#[utoipa::path(
+	get,
+	path = "/profiles/{profile_id}/sessions",
+	tag = "profiles",
+	params(("profile_id" = i32, Path, description = "Id of the profile")),
+	responses(
+		(
+			status = 200,
+			description = "The active sessions",
+			body = Vec<SessionResponse>
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(r_conn))]
+pub async fn get_profile_sessions(
+	State(mut r_conn): State<RedisConn>,
+	session: Session,
+	Path(p_id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	check_session_access(&session, p_id)?;
+
+	let sessions = Session::get_all_for_profile(p_id, &mut r_conn).await?;
+
+	let response: Vec<SessionResponse> = sessions
+		.into_iter()
+		.map(|s| SessionResponse::new(s, session.id))
+		.collect();
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Revoke a single session of a profile
+#[utoipa::path(
+	delete,
+	path = "/profiles/{profile_id}/sessions/{session_id}",
+	tag = "profiles",
+	params(
+		("profile_id" = i32, Path, description = "Id of the profile"),
+		("session_id" = i32, Path, description = "Id of the session")
+	),
+	responses(
+		(status = 204, description = "Revoked the session"),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(status = 404, description = "Unknown session", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(r_conn))]
+pub async fn delete_profile_session(
+	State(mut r_conn): State<RedisConn>,
+	session: Session,
+	Path((p_id, session_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, Error> {
+	check_session_access(&session, p_id)?;
+
+	let target = Session::get(session_id, &mut r_conn).await?;
+
+	if !target.is_some_and(|s| s.data.profile_id == p_id) {
+		return Err(Error::NotFound(format!("session with id {session_id}")));
+	}
+
+	Session::delete(session_id, &mut r_conn).await?;
+
+	info!("revoked session {session_id} of profile {p_id}");
+
+	Ok((StatusCode::NO_CONTENT, NoContent))
+}
+
+/// Revoke all sessions of a profile, except for the current one
+#[utoipa::path(
+	delete,
+	path = "/profiles/{profile_id}/sessions",
+	tag = "profiles",
+	params(("profile_id" = i32, Path, description = "Id of the profile")),
+	responses(
+		(status = 204, description = "Revoked all other sessions"),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(r_conn))]
+pub async fn delete_other_profile_sessions(
+	State(mut r_conn): State<RedisConn>,
+	session: Session,
+	Path(p_id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	check_session_access(&session, p_id)?;
+
+	Session::delete_for_profile(p_id, Some(session.id), &mut r_conn).await?;
+
+	Ok((StatusCode::NO_CONTENT, NoContent))
+}
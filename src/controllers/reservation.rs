@@ -1,12 +1,10 @@
-use std::collections::HashMap;
-
 use axum::Json;
 use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use base::RESERVATION_BLOCK_SIZE_MINUTES;
-use chrono::{NaiveDateTime, NaiveTime, Utc};
-use common::{CreateReservationError, DbPool, Error};
+use chrono::NaiveTime;
+use common::{CreateReservationError, DbPool, Error, ErrorResponse};
 use location::{Location, LocationIncludes};
 use opening_time::{OpeningTime, OpeningTimeIncludes};
 use permissions::{
@@ -15,19 +13,139 @@ use permissions::{
 	LocationPermissions,
 	check_location_perms,
 };
-use reservation::{NewReservation, Reservation, ReservationIncludes};
+use reservation::{
+	NewReservation,
+	Reservation,
+	ReservationFilter,
+	ReservationIncludes,
+};
 
 use crate::schemas::BuildResponse;
-use crate::schemas::reservation::CreateReservationRequest;
-use crate::{Config, Session};
+use crate::schemas::includes::Includes;
+use crate::schemas::pagination::{PaginatedResponse, PaginationOptions};
+use crate::schemas::reservation::{
+	CreateReservationRequest,
+	ExtendReservationRequest,
+	ReservationResponse,
+};
+use crate::{AdminSession, Config, Session};
+
+/// Get all reservations confirmed by a given profile
+#[instrument(skip(pool))]
+pub async fn get_admin_reservations(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: AdminSession,
+	Query(filter): Query<ReservationFilter>,
+	Includes(includes): Includes<ReservationIncludes>,
+	Query(p_opts): Query<PaginationOptions>,
+) -> Result<impl IntoResponse, Error> {
+	let Some(confirmer_id) = filter.confirmer_id else {
+		return Err(Error::MissingRequestData(
+			"missing confirmerId query parameter".to_string(),
+		));
+	};
+
+	let conn = pool.get().await?;
+
+	let (total, truncated, reservations) = Reservation::get_by_confirmed_by(
+		confirmer_id,
+		filter,
+		includes,
+		p_opts.into(),
+		&conn,
+	)
+	.await?;
+	let reservations: Vec<ReservationResponse> = reservations
+		.into_iter()
+		.map(|r| r.build_response(includes, &config))
+		.collect::<Result<_, _>>()?;
+
+	let response = p_opts.paginate(total, truncated, reservations);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Get all reservations matching the given filter
+#[utoipa::path(
+	get,
+	path = "/reservations",
+	tag = "reservations",
+	params(ReservationFilter, ReservationIncludes, PaginationOptions),
+	responses(
+		(
+			status = 200,
+			description = "A page of reservations",
+			body = PaginatedResponse<Vec<ReservationResponse>>
+		),
+		(status = 400, description = "Invalid request", body = ErrorResponse),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub async fn get_all_reservations(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: AdminSession,
+	Query(filter): Query<ReservationFilter>,
+	Includes(includes): Includes<ReservationIncludes>,
+	Query(p_opts): Query<PaginationOptions>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let (total, truncated, reservations) =
+		Reservation::get_all_paginated(filter, includes, p_opts.into(), &conn)
+			.await?;
+	let reservations: Vec<ReservationResponse> = reservations
+		.into_iter()
+		.map(|r| r.build_response(includes, &config))
+		.collect::<Result<_, _>>()?;
 
+	let response = p_opts.paginate(total, truncated, reservations);
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+#[utoipa::path(
+	post,
+	path = "/locations/{l_id}/opening-times/{t_id}/reservations",
+	tag = "reservations",
+	params(
+		("l_id" = i32, Path, description = "Id of the location"),
+		("t_id" = i32, Path, description = "Id of the opening time"),
+		ReservationIncludes
+	),
+	request_body = CreateReservationRequest,
+	responses(
+		(
+			status = 201,
+			description = "The new reservation",
+			body = ReservationResponse
+		),
+		(status = 400, description = "Not reservable", body = ErrorResponse),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(
+			status = 404,
+			description = "Unknown opening time",
+			body = ErrorResponse
+		),
+		(
+			status = 409,
+			description = "Overlaps a reservation or exceeds the seat count",
+			body = ErrorResponse
+		)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub async fn create_reservation(
 	State(config): State<Config>,
 	State(pool): State<DbPool>,
 	session: Session,
 	Path((l_id, t_id)): Path<(i32, i32)>,
-	Query(includes): Query<ReservationIncludes>,
+	Includes(includes): Includes<ReservationIncludes>,
 	Json(request): Json<CreateReservationRequest>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
@@ -44,11 +162,9 @@ pub async fn create_reservation(
 		request.end_time,
 	)?;
 
-	check_reservation_period(time.reservable_from, time.reservable_until)?;
-
-	let loc =
-		Location::get_simple_by_id(l_id, LocationIncludes::default(), &conn)
-			.await?;
+	// Make sure the location exists
+	Location::get_simple_by_id(l_id, LocationIncludes::default(), &conn)
+		.await?;
 
 	let block_size = i64::from(RESERVATION_BLOCK_SIZE_MINUTES);
 
@@ -60,22 +176,6 @@ pub async fn create_reservation(
 	#[allow(clippy::cast_possible_truncation)]
 	let block_count = (span / block_size) as i32;
 
-	check_reservation_length(
-		loc.primitive.max_reservation_length,
-		block_count,
-	)?;
-
-	#[allow(clippy::cast_possible_truncation)]
-	let num_blocks =
-		((time.end_time - time.start_time).num_minutes() / block_size) as i32;
-	let spans = Reservation::get_spans_for_opening_time(t_id, &conn).await?;
-
-	check_reservation_occupation(
-		num_blocks,
-		&spans,
-		time.seat_count.unwrap_or(loc.primitive.seat_count),
-	)?;
-
 	let new_reservation = NewReservation {
 		profile_id: session.data.profile_id,
 		opening_time_id: t_id,
@@ -83,6 +183,7 @@ pub async fn create_reservation(
 		block_count,
 	};
 
+	// Every other check runs while the opening time is locked
 	let new_reservation = new_reservation.insert(includes, &conn).await?;
 	let response = new_reservation.build_response(includes, &config)?;
 
@@ -106,77 +207,189 @@ fn check_reservation_bounds(
 	Ok(())
 }
 
-fn check_reservation_period(
-	from: Option<NaiveDateTime>,
-	until: Option<NaiveDateTime>,
-) -> Result<(), Error> {
-	#[allow(clippy::collapsible_if)]
-	if let Some(from) = from {
-		if Utc::now().naive_utc() < from {
-			return Err(CreateReservationError::NotReservableYet(from).into());
-		}
-	}
-
-	#[allow(clippy::collapsible_if)]
-	if let Some(until) = until {
-		if Utc::now().naive_utc() > until {
-			return Err(
-				CreateReservationError::NotReservableAnymore(until).into()
-			);
-		}
-	}
+/// Extend a reservation by a number of blocks
+#[utoipa::path(
+	post,
+	path = "/reservations/{id}/extend",
+	tag = "reservations",
+	params(
+		("id" = i32, Path, description = "Id of the reservation"),
+		ReservationIncludes
+	),
+	request_body = ExtendReservationRequest,
+	responses(
+		(
+			status = 200,
+			description = "The extended reservation",
+			body = ReservationResponse
+		),
+		(status = 400, description = "Invalid request", body = ErrorResponse),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(
+			status = 404,
+			description = "Unknown reservation",
+			body = ErrorResponse
+		),
+		(status = 409, description = "Conflict", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub async fn extend_reservation(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(r_id): Path<i32>,
+	Includes(includes): Includes<ReservationIncludes>,
+	Json(request): Json<ExtendReservationRequest>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
 
-	Ok(())
+	let reservation = Reservation::extend(
+		r_id,
+		request.additional_blocks,
+		session.data.profile_id,
+		includes,
+		&conn,
+	)
+	.await?;
+	let response = reservation.build_response(includes, &config)?;
+
+	Ok((StatusCode::OK, Json(response)))
 }
 
-fn check_reservation_length(max: Option<i32>, len: i32) -> Result<(), Error> {
-	if len < 1 {
-		return Err(CreateReservationError::ReservationTooShort(1).into());
-	}
+/// Cancel a reservation, only the profile that made it can cancel it
+#[utoipa::path(
+	post,
+	path = "/reservations/{id}/cancel",
+	tag = "reservations",
+	params(
+		("id" = i32, Path, description = "Id of the reservation"),
+		ReservationIncludes
+	),
+	responses(
+		(
+			status = 200,
+			description = "The cancelled reservation",
+			body = ReservationResponse
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(
+			status = 404,
+			description = "Unknown reservation",
+			body = ErrorResponse
+		),
+		(status = 409, description = "Conflict", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub async fn cancel_reservation(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(r_id): Path<i32>,
+	Includes(includes): Includes<ReservationIncludes>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let reservation =
+		Reservation::get_by_id(r_id, ReservationIncludes::default(), &conn)
+			.await?
+			.primitive;
 
-	if let Some(max) = max
-		&& len > max
-	{
-		return Err(CreateReservationError::ReservationTooLong(max).into());
+	if reservation.profile_id != session.data.profile_id {
+		return Err(Error::Forbidden);
 	}
 
-	Ok(())
-}
+	let reservation = Reservation::cancel(r_id, includes, &conn).await?;
+	let response = reservation.build_response(includes, &config)?;
 
-fn check_reservation_occupation(
-	blocks: i32,
-	spans: &[(i32, i32)],
-	seats: i32,
-) -> Result<(), Error> {
-	let mut occupation = HashMap::<i32, i32>::new();
-
-	for i in 0..blocks {
-		let entry = occupation.entry(i).or_insert(0);
+	Ok((StatusCode::OK, Json(response)))
+}
 
-		for span in spans {
-			if span.0 <= i && (span.0 + span.1) >= i {
-				*entry += 1;
-			}
-		}
-	}
+/// Confirm that the profile of a reservation showed up
+#[utoipa::path(
+	post,
+	path = "/reservations/{id}/confirm",
+	tag = "reservations",
+	params(
+		("id" = i32, Path, description = "Id of the reservation"),
+		ReservationIncludes
+	),
+	responses(
+		(
+			status = 200,
+			description = "The confirmed reservation",
+			body = ReservationResponse
+		),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(
+			status = 404,
+			description = "Unknown reservation",
+			body = ErrorResponse
+		),
+		(status = 409, description = "Conflict", body = ErrorResponse)
+	),
+	security(("access_token" = []))
+)]
+#[instrument(skip(pool))]
+pub async fn confirm_reservation(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(r_id): Path<i32>,
+	Includes(includes): Includes<ReservationIncludes>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
 
-	let mut full = vec![];
+	let reservation =
+		Reservation::get_by_id(r_id, ReservationIncludes::default(), &conn)
+			.await?;
 
-	for (block, reservations) in occupation {
-		// + 1 because we want to know if adding another reservation will
-		// overflow
-		if reservations + 1 > seats {
-			full.push(block);
-		}
-	}
+	check_location_perms(
+		reservation.location.id,
+		session.data.profile_id,
+		LocationPermissions::ConfirmReservations
+			| LocationPermissions::Administrator,
+		AuthorityPermissions::Administrator,
+		InstitutionPermissions::Administrator,
+		&pool,
+	)
+	.await?;
 
-	if !full.is_empty() {
-		return Err(CreateReservationError::Full(full).into());
-	}
+	let reservation =
+		Reservation::confirm(r_id, session.data.profile_id, includes, &conn)
+			.await?;
+	let response = reservation.build_response(includes, &config)?;
 
-	Ok(())
+	Ok((StatusCode::OK, Json(response)))
 }
 
+#[utoipa::path(
+	delete,
+	path = "/locations/{l_id}/opening-times/{t_id}/reservations/{r_id}",
+	tag = "reservations",
+	params(
+		("l_id" = i32, Path, description = "Id of the location"),
+		("t_id" = i32, Path, description = "Id of the opening time"),
+		("r_id" = i32, Path, description = "Id of the reservation")
+	),
+	responses(
+		(status = 204, description = "Deleted the reservation"),
+		(status = 401, description = "Not logged in", body = ErrorResponse),
+		(status = 403, description = "Not allowed", body = ErrorResponse),
+		(
+			status = 404,
+			description = "Unknown reservation",
+			body = ErrorResponse
+		)
+	),
+	security(("access_token" = []))
+)]
 #[instrument(skip(pool))]
 pub async fn delete_reservation(
 	State(pool): State<DbPool>,
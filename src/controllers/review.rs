@@ -0,0 +1,57 @@
+//! Controllers for [`Review`]s
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use common::{DbPool, Error};
+use review::{Review, ReviewFlag, ReviewIncludes};
+use validator::Validate;
+
+use crate::schemas::pagination::PaginationOptions;
+use crate::schemas::review::{
+	FlagReviewRequest,
+	ReviewFlagResponse,
+	ReviewResponse,
+};
+use crate::{AdminSession, Session};
+
+/// Flag a review as inappropriate
+#[instrument(skip(pool))]
+pub(crate) async fn flag_review(
+	State(pool): State<DbPool>,
+	session: Session,
+	Path(id): Path<i32>,
+	Json(request): Json<FlagReviewRequest>,
+) -> Result<impl IntoResponse, Error> {
+	request.validate()?;
+
+	let conn = pool.get().await?;
+
+	let flag =
+		ReviewFlag::create(id, session.data.profile_id, request.reason, &conn)
+			.await?;
+	let response: ReviewFlagResponse = flag.into();
+
+	Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Get all flagged reviews, most flagged first
+#[instrument(skip(pool))]
+pub(crate) async fn get_flagged_reviews(
+	State(pool): State<DbPool>,
+	session: AdminSession,
+	Query(includes): Query<ReviewIncludes>,
+	Query(p_opts): Query<PaginationOptions>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let (total, truncated, reviews) =
+		Review::get_flagged(includes, p_opts.into(), &conn).await?;
+	let response: Vec<_> =
+		reviews.into_iter().map(ReviewResponse::from).collect();
+
+	let response = p_opts.paginate(total, truncated, response);
+
+	Ok((StatusCode::OK, Json(response)))
+}
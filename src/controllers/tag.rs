@@ -4,9 +4,20 @@ use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use common::{DbPool, Error};
 use tag::{Tag, TagIncludes};
+use translation::Translation;
+use validator::Validate;
 
 use crate::schemas::BuildResponse;
-use crate::schemas::tag::{CreateTagRequest, TagResponse, UpdateTagRequest};
+use crate::schemas::includes::Includes;
+use crate::schemas::pagination::PaginationOptions;
+use crate::schemas::tag::{
+	CreateTagRequest,
+	DeleteTagOptions,
+	GetTagsBatchRequest,
+	TagResponse,
+	TagStatisticsResponse,
+	UpdateTagRequest,
+};
 use crate::{AdminSession, Config};
 
 #[instrument(skip(pool))]
@@ -14,7 +25,7 @@ pub async fn create_tag(
 	State(config): State<Config>,
 	State(pool): State<DbPool>,
 	session: AdminSession,
-	Query(includes): Query<TagIncludes>,
+	Includes(includes): Includes<TagIncludes>,
 	Json(request): Json<CreateTagRequest>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
@@ -30,16 +41,40 @@ pub async fn create_tag(
 pub async fn get_all_tags(
 	State(config): State<Config>,
 	State(pool): State<DbPool>,
-	Query(includes): Query<TagIncludes>,
+	Includes(includes): Includes<TagIncludes>,
+	Query(p_opts): Query<PaginationOptions>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
 
-	let tags = Tag::get_all(includes, &conn).await?;
-	let response: Vec<TagResponse> = tags
+	let (total, truncated, tags) =
+		Tag::get_all_paginated(includes, p_opts.into(), &conn).await?;
+	let tags: Vec<TagResponse> = tags
 		.into_iter()
 		.map(|t| t.build_response(includes, &config))
 		.collect::<Result<_, _>>()?;
 
+	let paginated = p_opts.paginate(total, truncated, tags);
+
+	Ok((StatusCode::OK, Json(paginated)))
+}
+
+#[instrument(skip(pool))]
+pub async fn get_tags_batch(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	Includes(includes): Includes<TagIncludes>,
+	Json(request): Json<GetTagsBatchRequest>,
+) -> Result<impl IntoResponse, Error> {
+	request.validate()?;
+
+	let conn = pool.get().await?;
+
+	let tags = Tag::get_by_ids(request.ids, includes, &conn).await?;
+	let response: Vec<TagResponse> = tags
+		.into_values()
+		.map(|t| t.build_response(includes, &config))
+		.collect::<Result<_, _>>()?;
+
 	Ok((StatusCode::OK, Json(response)))
 }
 
@@ -48,7 +83,7 @@ pub async fn update_tag(
 	State(config): State<Config>,
 	State(pool): State<DbPool>,
 	session: AdminSession,
-	Query(includes): Query<TagIncludes>,
+	Includes(includes): Includes<TagIncludes>,
 	Path(id): Path<i32>,
 	Json(request): Json<UpdateTagRequest>,
 ) -> Result<impl IntoResponse, Error> {
@@ -62,15 +97,51 @@ pub async fn update_tag(
 	Ok((StatusCode::OK, Json(response)))
 }
 
+/// Delete a [`Tag`], refusing to do so while locations still use it unless
+/// the deletion is forced
 #[instrument(skip(pool))]
 pub async fn delete_tag(
 	State(pool): State<DbPool>,
 	session: AdminSession,
 	Path(id): Path<i32>,
+	Query(options): Query<DeleteTagOptions>,
 ) -> Result<impl IntoResponse, Error> {
 	let conn = pool.get().await?;
 
-	Tag::delete_by_id(id, &conn).await?;
+	Tag::delete_by_id(id, options.force, &conn).await?;
 
 	Ok(StatusCode::NO_CONTENT)
 }
+
+#[instrument(skip(pool))]
+pub async fn get_tag_translations(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: AdminSession,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	let translations = Translation::get_for_tag(id, &conn).await?;
+	let response = translations.build_response((), &config)?;
+
+	Ok((StatusCode::OK, Json(response)))
+}
+
+/// Get the amount of visible locations using a [`Tag`], in total and per
+/// country and province
+#[instrument(skip(pool))]
+pub async fn get_tag_statistics(
+	State(pool): State<DbPool>,
+	Path(id): Path<i32>,
+) -> Result<impl IntoResponse, Error> {
+	let conn = pool.get().await?;
+
+	// Return not found if the tag doesn't exist
+	Tag::get_by_id(id, TagIncludes::default(), &conn).await?;
+
+	let statistics = Tag::get_location_statistics(id, &conn).await?;
+	let response = TagStatisticsResponse::from(statistics);
+
+	Ok((StatusCode::OK, Json(response)))
+}
@@ -6,13 +6,16 @@ use axum::http::StatusCode;
 use axum::response::{IntoResponse, NoContent};
 use common::{DbPool, Error};
 use translation::{Translation, TranslationIncludes};
+use validator::Validate;
 
 use crate::schemas::BuildResponse;
 use crate::schemas::translation::{
+	BulkTranslationUpdateRequest,
 	CreateTranslationRequest,
+	TranslationResponse,
 	UpdateTranslationRequest,
 };
-use crate::{Config, Session};
+use crate::{AdminSession, Config, Session};
 
 /// Create and store a single translation in the database.
 #[instrument(skip(pool))]
@@ -80,3 +83,25 @@ pub(crate) async fn delete_translation(
 
 	Ok((StatusCode::NO_CONTENT, NoContent))
 }
+
+/// Update multiple translations at once, returning them in the same order
+#[instrument(skip(pool))]
+pub(crate) async fn admin_bulk_update_translations(
+	State(config): State<Config>,
+	State(pool): State<DbPool>,
+	session: AdminSession,
+	Json(request): Json<BulkTranslationUpdateRequest>,
+) -> Result<impl IntoResponse, Error> {
+	request.validate()?;
+
+	let conn = pool.get().await?;
+
+	let updates = request.to_insertable(session.data.profile_id);
+	let translations = Translation::bulk_update(updates, &conn).await?;
+	let response: Vec<TranslationResponse> = translations
+		.into_iter()
+		.map(|t| t.build_response(TranslationIncludes::default(), &config))
+		.collect::<Result<_, _>>()?;
+
+	Ok((StatusCode::OK, Json(response)))
+}
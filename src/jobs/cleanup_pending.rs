@@ -0,0 +1,51 @@
+//! Cleanup of unverified profiles with expired confirmation tokens
+
+use std::time::Duration;
+
+use common::{DbPool, Error};
+use profile::Profile;
+
+use super::ShutdownSignal;
+
+/// Interval between two runs of the pending profile cleanup
+pub const CLEANUP_PENDING_INTERVAL: Duration =
+	Duration::from_secs(24 * 60 * 60);
+
+/// Delete all unverified [`Profile`]s with an expired confirmation token,
+/// returning the amount of deleted profiles
+///
+/// # Errors
+/// Fails if no database connection could be acquired or the delete fails
+#[instrument(skip(pool))]
+pub async fn cleanup_pending_profiles(pool: &DbPool) -> Result<usize, Error> {
+	let conn = pool.get().await?;
+
+	let count = Profile::cleanup_expired_pending(&conn).await?;
+
+	info!("deleted {count} expired pending profiles");
+
+	Ok(count)
+}
+
+/// Start a job that cleans up expired pending profiles once a day until the
+/// server shuts down
+#[instrument(skip_all)]
+pub(super) async fn cleanup_pending_profiles_job(
+	pool: DbPool,
+	mut shutdown: ShutdownSignal,
+) {
+	let mut interval = tokio::time::interval(CLEANUP_PENDING_INTERVAL);
+
+	loop {
+		tokio::select! {
+			_ = interval.tick() => {},
+			_ = shutdown.changed() => break,
+		}
+
+		if let Err(e) = cleanup_pending_profiles(&pool).await {
+			error!("error cleaning up expired pending profiles: {e:?}");
+		}
+	}
+
+	info!("stopped pending profile cleanup job");
+}
@@ -0,0 +1,48 @@
+//! Periodic background jobs
+
+use std::time::Duration;
+
+use common::DbPool;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::Config;
+use crate::mailer::Mailer;
+
+mod cleanup_pending;
+mod reservation_reminder;
+
+pub use cleanup_pending::*;
+pub use reservation_reminder::*;
+
+/// Receiver that is notified once the server starts shutting down
+pub type ShutdownSignal = watch::Receiver<()>;
+
+/// Spawn all periodic background jobs onto the runtime
+///
+/// The jobs stop once `shutdown` is notified, any run that is in progress at
+/// that time is finished first
+pub fn spawn_jobs(
+	config: &Config,
+	pool: &DbPool,
+	mailer: &Mailer,
+	shutdown: &ShutdownSignal,
+) -> Vec<JoinHandle<()>> {
+	info!("spawning background jobs");
+
+	let reminder_interval =
+		Duration::from_secs(config.reservation_reminder_interval_minutes * 60);
+
+	vec![
+		tokio::spawn(cleanup_pending::cleanup_pending_profiles_job(
+			pool.clone(),
+			shutdown.clone(),
+		)),
+		tokio::spawn(reservation_reminder::send_reservation_reminders_job(
+			pool.clone(),
+			mailer.clone(),
+			reminder_interval,
+			shutdown.clone(),
+		)),
+	]
+}
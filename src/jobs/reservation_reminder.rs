@@ -0,0 +1,109 @@
+//! Reminder emails for upcoming reservations
+
+use std::time::Duration;
+
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use common::{DbPool, Error};
+use profile::Profile;
+use reservation::Reservation;
+
+use super::ShutdownSignal;
+use crate::mailer::Mailer;
+
+/// How long before the start of a reservation its reminder is sent
+pub const RESERVATION_REMINDER_WINDOW: TimeDelta = TimeDelta::hours(24);
+
+/// Send a reminder email for every active reservation starting within
+/// [`RESERVATION_REMINDER_WINDOW`] of `now` that has not been reminded yet,
+/// returning the amount of sent reminders
+///
+/// Reservations are marked as reminded before their email is sent, so a
+/// profile is never mailed twice for the same reservation, even when runs
+/// overlap
+///
+/// # Errors
+/// Fails if no database connection could be acquired or the reservations
+/// could not be loaded
+#[instrument(skip(pool, mailer))]
+pub async fn send_reservation_reminders(
+	pool: &DbPool,
+	mailer: &Mailer,
+	now: NaiveDateTime,
+) -> Result<usize, Error> {
+	let conn = pool.get().await?;
+
+	let until = now + RESERVATION_REMINDER_WINDOW;
+	let reservations =
+		Reservation::get_unreminded_starting_between(now, until, &conn).await?;
+
+	let mut sent = 0;
+
+	for reservation in reservations {
+		let r_id = reservation.primitive.id;
+
+		if !Reservation::mark_reminded(r_id, &conn).await? {
+			continue;
+		}
+
+		let profile =
+			match Profile::get(reservation.primitive.profile_id, &conn).await {
+				Ok(profile) => profile,
+				Err(e) => {
+					error!(
+						"error loading profile for reservation {r_id}: {e:?}"
+					);
+
+					continue;
+				},
+			};
+
+		let (start, end) = reservation.time_span();
+
+		let res = mailer
+			.send_reservation_reminder(
+				&profile,
+				&reservation.location,
+				start,
+				end,
+			)
+			.await;
+
+		match res {
+			Ok(()) => sent += 1,
+			Err(e) => {
+				error!("error sending reminder for reservation {r_id}: {e:?}");
+			},
+		}
+	}
+
+	info!("sent {sent} reservation reminders");
+
+	Ok(sent)
+}
+
+/// Start a job that sends reservation reminders every `interval` until the
+/// server shuts down
+#[instrument(skip_all)]
+pub(super) async fn send_reservation_reminders_job(
+	pool: DbPool,
+	mailer: Mailer,
+	interval: Duration,
+	mut shutdown: ShutdownSignal,
+) {
+	let mut interval = tokio::time::interval(interval);
+
+	loop {
+		tokio::select! {
+			_ = interval.tick() => {},
+			_ = shutdown.changed() => break,
+		}
+
+		let now = Utc::now().naive_utc();
+
+		if let Err(e) = send_reservation_reminders(&pool, &mailer, now).await {
+			error!("error sending reservation reminders: {e:?}");
+		}
+	}
+
+	info!("stopped reservation reminder job");
+}
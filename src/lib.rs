@@ -9,16 +9,20 @@ use axum::extract::FromRef;
 use axum_extra::extract::cookie::Key;
 use common::{DbPool, RedisConn};
 use mailer::Mailer;
+use webhooks::WebhookDispatcher;
 
 mod config;
 mod seeder;
 mod session;
 
 pub mod controllers;
+pub mod jobs;
 pub mod mailer;
 pub mod middleware;
+pub mod openapi;
 pub mod routes;
 pub mod schemas;
+pub mod webhooks;
 
 pub use config::*;
 pub use seeder::*;
@@ -32,6 +36,7 @@ pub struct AppState {
 	pub redis_connection: RedisConn,
 	pub cookie_jar_key:   Key,
 	pub mailer:           Mailer,
+	pub webhooks:         WebhookDispatcher,
 }
 
 impl FromRef<AppState> for Config {
@@ -53,3 +58,7 @@ impl FromRef<AppState> for Key {
 impl FromRef<AppState> for Mailer {
 	fn from_ref(input: &AppState) -> Self { input.mailer.clone() }
 }
+
+impl FromRef<AppState> for WebhookDispatcher {
+	fn from_ref(input: &AppState) -> Self { input.webhooks.clone() }
+}
@@ -1,21 +1,52 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
-use common::Error;
+use chrono::NaiveDateTime;
+use common::{Error, InternalServerError, RedisConn};
+use lettre::address::Envelope;
 use lettre::message::Mailbox;
+use lettre::transport::smtp::SmtpTransportBuilder;
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::{Address, Message, SmtpTransport, Transport};
 use parking_lot::{Condvar, Mutex};
+use primitives::PrimitiveLocation;
 use profile::Profile;
-use tokio::sync::mpsc;
+use redis::{AsyncCommands, Direction};
+use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::Config;
 
+/// Redis list holding the emails waiting to be delivered
+pub const MAIL_QUEUE_KEY: &str = "mail:queue";
+/// Redis list holding the emails the SMTP worker is currently delivering,
+/// they are only removed once they were sent or given up on
+pub const MAIL_PROCESSING_KEY: &str = "mail:processing";
+/// Redis list holding the emails that could not be delivered after all
+/// retries
+const MAIL_FAILED_KEY: &str = "mail:failed";
+/// Delay before the first retry of a failed delivery, doubled on every retry
+const MAIL_RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+/// Upper bound for the delay between two retries
+const MAIL_RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+
 /// A basic interface to send email messages
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct Mailer {
-	from:       Address,
-	send_queue: mpsc::Sender<Message>,
+	from:        Address,
+	max_queued:  usize,
+	max_retries: u32,
+	backend:     MailerBackend,
+}
+
+/// Where a [`Mailer`] delivers its messages to
+#[derive(Clone)]
+pub enum MailerBackend {
+	/// Push messages onto a Redis queue that is drained by the SMTP worker
+	Queue(RedisConn),
+	/// Deliver messages straight into a [`StubMailbox`]
+	Stub(Arc<StubMailbox>),
 }
 
 /// A fake mailbox to keep track of mails sent in tests
@@ -23,32 +54,198 @@ pub struct Mailer {
 pub struct StubMailbox {
 	pub mailbox:     Mutex<Vec<Message>>,
 	pub mail_signal: Condvar,
+	/// Amount of upcoming deliveries that should fail, used to simulate
+	/// transient failures
+	pub failures:    AtomicUsize,
+}
+
+/// A message as it is stored in the mail queue
+#[derive(Debug, Deserialize, Serialize)]
+struct QueuedMail {
+	from: Option<String>,
+	to:   Vec<String>,
+	raw:  Vec<u8>,
+}
+
+impl QueuedMail {
+	/// Deliver this mail over the given SMTP transport
+	fn send(&self, transport: &SmtpTransport) -> Result<(), Error> {
+		let from = self.from.as_deref().map(str::parse).transpose()?;
+		let to = self
+			.to
+			.iter()
+			.map(String::as_str)
+			.map(str::parse)
+			.collect::<Result<Vec<Address>, _>>()?;
+
+		let envelope = Envelope::new(from, to)?;
+
+		let res = transport.send_raw(&envelope, &self.raw)?;
+
+		info!("sent email: {res:?}");
+
+		Ok(())
+	}
+}
+
+impl From<&Message> for QueuedMail {
+	fn from(message: &Message) -> Self {
+		let envelope = message.envelope();
+
+		Self {
+			from: envelope.from().map(ToString::to_string),
+			to:   envelope.to().iter().map(ToString::to_string).collect(),
+			raw:  message.formatted(),
+		}
+	}
+}
+
+impl StubMailbox {
+	/// Put a message in this mailbox, unless a failure is still pending
+	fn deliver(&self, mail: &Message) -> Result<(), Error> {
+		let failed = self
+			.failures
+			.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |f| {
+				f.checked_sub(1)
+			})
+			.is_ok();
+
+		if failed {
+			return Err(std::io::Error::other("simulated stub failure").into());
+		}
+
+		let mail_pretty =
+			String::from_utf8_lossy(&mail.formatted()).to_string();
+
+		{
+			let mut mailbox = self.mailbox.lock();
+			mailbox.push(mail.clone());
+			self.mail_signal.notify_all();
+		}
+
+		info!(
+			target: "[STUB_MAILER]",
+			"sent email:\n{}\n",
+			mail_pretty
+		);
+
+		Ok(())
+	}
+}
+
+/// Start building an SMTP transport to the configured server
+fn smtp_transport(config: &Config) -> Result<SmtpTransportBuilder, Error> {
+	Ok(SmtpTransport::starttls_relay(&config.email_smtp_server)?.credentials(
+		Credentials::new(
+			config.email_address.to_string(),
+			config.email_smtp_password.clone(),
+		),
+	))
+}
+
+/// Try to deliver a mail, retrying with exponential backoff on failure
+///
+/// Returns the error of the last attempt if all retries failed
+async fn deliver_with_retries<F>(
+	max_retries: u32,
+	mut deliver: F,
+) -> Result<(), Error>
+where
+	F: AsyncFnMut() -> Result<(), Error>,
+{
+	let mut delay = MAIL_RETRY_BASE_DELAY;
+	let mut attempt = 0;
+
+	loop {
+		match deliver().await {
+			Ok(()) => return Ok(()),
+			Err(e) if attempt < max_retries => {
+				attempt += 1;
+
+				warn!(
+					"error delivering email, retry {attempt}/{max_retries} in \
+					 {delay:?}: {e:?}"
+				);
+
+				tokio::time::sleep(delay).await;
+				delay = (delay * 2).min(MAIL_RETRY_MAX_DELAY);
+			},
+			Err(e) => return Err(e),
+		}
+	}
 }
 
 impl Mailer {
 	/// Create a new mailer
 	///
+	/// Messages are queued in redis unless a stub mailbox is used
+	///
 	/// # Panics
 	/// Panics if a stub mailer is expected but not provied
 	#[must_use]
-	pub fn new(config: &Config, stub_mailer: Option<Arc<StubMailbox>>) -> Self {
-		let (tx, rx) = mpsc::channel(config.email_queue_size);
-
-		if config.email_smtp_server == "stub" {
+	pub fn new(
+		config: &Config,
+		redis_connection: RedisConn,
+		stub_mailer: Option<Arc<StubMailbox>>,
+	) -> Self {
+		let backend = if config.email_smtp_server == "stub" {
 			assert!(stub_mailer.is_some(), "MISSING STUB MAILER");
 
-			info!("spawning stub thread");
-			tokio::spawn(Self::start_stub_sender(rx, stub_mailer.unwrap()));
+			MailerBackend::Stub(stub_mailer.unwrap())
 		} else {
-			tokio::spawn(Self::start_smtp_sender(
-				rx,
-				config.email_address.clone(),
-				config.email_smtp_server.clone(),
-				config.email_smtp_password.clone(),
-			));
+			MailerBackend::Queue(redis_connection)
+		};
+
+		Self {
+			from: config.email_address.clone(),
+			max_queued: config.email_queue_size,
+			max_retries: config.email_max_retries,
+			backend,
+		}
+	}
+
+	/// Spawn the worker that delivers queued messages over SMTP
+	///
+	/// Does nothing if the stub mailer is used, as stub messages are
+	/// delivered immediately
+	pub fn spawn_queue_worker(config: &Config) {
+		if config.email_smtp_server == "stub" {
+			return;
+		}
+
+		tokio::spawn(Self::start_smtp_sender(config.clone()));
+	}
+
+	/// Whether this mailer delivers into a [`StubMailbox`]
+	#[must_use]
+	pub fn is_stub(&self) -> bool {
+		matches!(self.backend, MailerBackend::Stub(_))
+	}
+
+	/// Check whether the configured SMTP server accepts a connection and
+	/// responds to a NOOP
+	///
+	/// # Errors
+	/// Fails if the server can not be reached within `timeout` or does not
+	/// respond as expected
+	pub async fn test_smtp_connection(
+		config: &Config,
+		timeout: Duration,
+	) -> Result<(), Error> {
+		let transport = smtp_transport(config)?.timeout(Some(timeout)).build();
+
+		let connected =
+			tokio::task::spawn_blocking(move || transport.test_connection())
+				.await
+				.map_err(InternalServerError::JoinError)??;
+
+		if !connected {
+			let e = std::io::Error::other("SMTP server did not answer NOOP");
+
+			return Err(e.into());
 		}
 
-		Self { from: config.email_address.clone(), send_queue: tx }
+		Ok(())
 	}
 
 	/// Try to build an email [`Message`]
@@ -68,78 +265,174 @@ impl Mailer {
 			.body(body.to_string())?)
 	}
 
-	/// Try to send a message
+	/// Send a message
+	///
+	/// Queued messages are delivered by the SMTP worker, stub messages are
+	/// delivered before returning
 	///
 	/// # Errors
-	/// Fails if the mail queue is full
+	/// Fails if the mail queue is full, or if a stub message could not be
+	/// delivered after all retries
 	#[instrument(skip_all)]
-	pub fn try_send(&self, message: Message) -> Result<(), Error> {
-		Ok(self.send_queue.try_send(message)?)
+	pub async fn send(&self, message: Message) -> Result<(), Error> {
+		match &self.backend {
+			MailerBackend::Queue(r_conn) => {
+				let mut r_conn = r_conn.clone();
+
+				let queued: usize = r_conn.llen(MAIL_QUEUE_KEY).await?;
+				if queued >= self.max_queued {
+					return Err(
+						InternalServerError::MailQueueFull(queued).into()
+					);
+				}
+
+				let payload =
+					serde_json::to_string(&QueuedMail::from(&message))?;
+
+				let _: () = r_conn.rpush(MAIL_QUEUE_KEY, payload).await?;
+
+				Ok(())
+			},
+			MailerBackend::Stub(stub) => {
+				deliver_with_retries(self.max_retries, async || {
+					stub.deliver(&message)
+				})
+				.await
+			},
+		}
 	}
 
-	/// Send a message and block if the mail queue is full
+	/// Move the emails a previous SMTP worker was still delivering back to
+	/// the front of the mail queue
+	///
+	/// Returns the amount of requeued emails. As every instance shares the
+	/// same lists, an email that another worker is still delivering may be
+	/// sent twice
 	///
 	/// # Errors
-	/// Fails if the other end of the mail queue was unexpectedly closed
-	#[instrument(skip_all)]
-	pub async fn send(&self, message: Message) -> Result<(), Error> {
-		Ok(self.send_queue.send(message).await?)
-	}
+	/// Fails if the emails could not be moved
+	pub async fn requeue_unfinished_mails(
+		r_conn: &mut RedisConn,
+	) -> Result<usize, Error> {
+		let mut requeued = 0;
 
-	/// Start an infinitely looping stub sender thread
-	#[instrument(skip_all)]
-	async fn start_stub_sender(
-		mut rx: mpsc::Receiver<Message>,
-		stub_mailer: Arc<StubMailbox>,
-	) {
-		while let Some(mail) = rx.recv().await {
-			let mail_pretty =
-				String::from_utf8_lossy(&mail.formatted()).to_string();
+		loop {
+			let moved: Option<String> = r_conn
+				.lmove(
+					MAIL_PROCESSING_KEY,
+					MAIL_QUEUE_KEY,
+					Direction::Right,
+					Direction::Left,
+				)
+				.await?;
 
-			{
-				let mut mailbox = stub_mailer.mailbox.lock();
-				mailbox.push(mail);
-				stub_mailer.mail_signal.notify_all();
+			if moved.is_none() {
+				return Ok(requeued);
 			}
 
-			info!(
-				target: "[STUB_MAILER]",
-				"sent email:\n{}\n",
-				mail_pretty
-			);
-
-			tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+			requeued += 1;
 		}
 	}
 
 	/// Start an infinitely looping smtp sender thread
+	///
+	/// Every email is kept in a processing list until it was delivered, so
+	/// emails survive a restart of the worker. Mails that still fail after
+	/// all retries are moved to a separate list so they are not lost
 	#[instrument(skip_all)]
-	async fn start_smtp_sender(
-		mut rx: mpsc::Receiver<Message>,
-		address: Address,
-		server: String,
-		password: String,
-	) {
-		let transport = SmtpTransport::starttls_relay(&server)
-			.expect("STARTTLS ERROR")
-			.credentials(Credentials::new(address.to_string(), password))
-			.build();
+	async fn start_smtp_sender(config: Config) {
+		let transport =
+			smtp_transport(&config).expect("STARTTLS ERROR").build();
 
 		match transport.test_connection() {
 			Ok(_) => (),
 			Err(e) => panic!("SMTP CONNECTION FAILED: {e:?}"),
 		}
 
-		while let Some(mail) = rx.recv().await {
-			match transport.send(&mail) {
-				Ok(res) => info!("sent email: {res:?}"),
-				Err(e) => error!("error sending email: {e:?}"),
+		// BLMOVE blocks the connection it runs on, so the worker gets its own
+		let mut r_conn = config.create_redis_connection().await;
+
+		match Self::requeue_unfinished_mails(&mut r_conn).await {
+			Ok(0) => (),
+			Ok(requeued) => warn!("requeued {requeued} unfinished emails"),
+			Err(e) => error!("error requeueing unfinished emails: {e:?}"),
+		}
+
+		loop {
+			let moved: Option<String> = match r_conn
+				.blmove(
+					MAIL_QUEUE_KEY,
+					MAIL_PROCESSING_KEY,
+					Direction::Left,
+					Direction::Right,
+					0.0,
+				)
+				.await
+			{
+				Ok(moved) => moved,
+				Err(e) => {
+					error!("error reading mail queue: {e:?}");
+
+					tokio::time::sleep(MAIL_RETRY_BASE_DELAY).await;
+
+					continue;
+				},
+			};
+
+			let Some(payload) = moved else { continue };
+
+			match serde_json::from_str::<QueuedMail>(&payload) {
+				Ok(mail) => {
+					let to = mail.to.clone();
+					let max_retries = config.email_max_retries;
+
+					let res =
+						Self::send_queued(mail, &transport, max_retries).await;
+
+					if let Err(e) = res {
+						error!("giving up on email to {to:?}: {e:?}");
+
+						let res: Result<(), _> =
+							r_conn.rpush(MAIL_FAILED_KEY, &payload).await;
+
+						if let Err(e) = res {
+							error!("error storing failed email: {e:?}");
+						}
+					}
+				},
+				Err(e) => error!("dropping malformed queued email: {e:?}"),
 			}
 
-			tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+			let res: Result<(), _> =
+				r_conn.lrem(MAIL_PROCESSING_KEY, 1, &payload).await;
+
+			if let Err(e) = res {
+				error!("error removing processed email: {e:?}");
+			}
 		}
 	}
 
+	/// Deliver a queued email over SMTP, retrying on failure
+	///
+	/// SMTP calls block, so every attempt runs on the blocking thread pool
+	async fn send_queued(
+		mail: QueuedMail,
+		transport: &SmtpTransport,
+		max_retries: u32,
+	) -> Result<(), Error> {
+		let mail = Arc::new(mail);
+
+		deliver_with_retries(max_retries, async || {
+			let mail = mail.clone();
+			let transport = transport.clone();
+
+			tokio::task::spawn_blocking(move || mail.send(&transport))
+				.await
+				.map_err(InternalServerError::JoinError)?
+		})
+		.await
+	}
+
 	/// Send out an email confirmation email
 	#[instrument(skip(self))]
 	pub(crate) async fn send_confirm_email(
@@ -169,6 +462,38 @@ impl Mailer {
 		Ok(())
 	}
 
+	/// Let the confirmed email of a profile know a new email was requested,
+	/// with a link to revert the change
+	#[instrument(skip(self))]
+	pub(crate) async fn send_email_change_notice(
+		&self,
+		profile: &Profile,
+		revert_token: &str,
+		frontend_url: &Url,
+	) -> Result<(), Error> {
+		let revert_url = format!("{frontend_url}/revert_email/{revert_token}");
+
+		// Address the confirmed email instead of the pending one
+		let mut receiver = profile.clone();
+		receiver.primitive.pending_email = None;
+
+		let mail = self.try_build_message(
+			&receiver,
+			"Your email is being changed",
+			&format!(
+				"A new email was requested for your account. If this was not \
+				 you, you can cancel the change and log out everywhere by \
+				 going to {revert_url}"
+			),
+		)?;
+
+		self.send(mail).await?;
+
+		info!("sent email change notice for profile {}", profile.primitive.id);
+
+		Ok(())
+	}
+
 	/// Send out a password reset email
 	#[instrument(skip(self))]
 	pub(crate) async fn send_reset_password(
@@ -191,4 +516,73 @@ impl Mailer {
 
 		Ok(())
 	}
+
+	/// Let a profile know its reservation was cancelled because the opening
+	/// time it belonged to changed
+	#[instrument(skip(self))]
+	pub(crate) async fn send_reservation_cancelled(
+		&self,
+		profile: &Profile,
+		location_name: &str,
+		start: NaiveDateTime,
+		end: NaiveDateTime,
+	) -> Result<(), Error> {
+		let mail = self.try_build_message(
+			profile,
+			"Your reservation was cancelled",
+			&format!(
+				"Your reservation at {location_name} on {} from {} until {} \
+				 was cancelled because the opening times of the location \
+				 changed",
+				start.date(),
+				start.time(),
+				end.time(),
+			),
+		)?;
+
+		self.send(mail).await?;
+
+		info!(
+			"sent reservation cancelled email for profile {}",
+			profile.primitive.id
+		);
+
+		Ok(())
+	}
+
+	/// Remind a profile of an upcoming reservation
+	#[instrument(skip(self, location))]
+	pub(crate) async fn send_reservation_reminder(
+		&self,
+		profile: &Profile,
+		location: &PrimitiveLocation,
+		start: NaiveDateTime,
+		end: NaiveDateTime,
+	) -> Result<(), Error> {
+		let mail = self.try_build_message(
+			profile,
+			"Reminder of your upcoming reservation",
+			&format!(
+				"This is a reminder of your reservation at {} on {} from {} \
+				 until {}.\n\nAddress: {} {}, {} {}",
+				location.name,
+				start.date(),
+				start.time(),
+				end.time(),
+				location.street,
+				location.number,
+				location.zip,
+				location.city,
+			),
+		)?;
+
+		self.send(mail).await?;
+
+		info!(
+			"sent reservation reminder email for profile {}",
+			profile.primitive.id
+		);
+
+		Ok(())
+	}
 }
@@ -1,12 +1,16 @@
 #[macro_use]
 extern crate tracing;
 
+use std::net::SocketAddr;
+
 use axum_extra::extract::cookie::Key;
 use blokmap::mailer::Mailer;
-use blokmap::{AppState, Config, routes};
+use blokmap::webhooks::WebhookDispatcher;
+use blokmap::{AppState, Config, jobs, routes};
 use tokio::net::TcpListener;
 use tokio::signal;
 use tokio::signal::unix::SignalKind;
+use tokio::sync::watch;
 use tracing::Level;
 
 #[tokio::main]
@@ -22,6 +26,10 @@ async fn main() {
 	// Load the configuration from the environment,
 	// and create a database pool.
 	let config = Config::from_env();
+	config
+		.validate()
+		.unwrap_or_else(|errs| panic!("invalid config: {}", errs.join(", ")));
+
 	let database_pool = config.create_database_pool();
 	let redis_connection = config.create_redis_connection().await;
 
@@ -32,7 +40,21 @@ async fn main() {
 
 	let stub_mailbox = config.create_stub_mailbox();
 
-	let mailer = Mailer::new(&config, stub_mailbox);
+	let mailer = Mailer::new(&config, redis_connection.clone(), stub_mailbox);
+
+	// Start delivering queued emails.
+	Mailer::spawn_queue_worker(&config);
+
+	let webhooks = WebhookDispatcher::new(
+		database_pool.clone(),
+		config.webhook_allow_private_hosts,
+	);
+
+	// Start the periodic background jobs, they are stopped by the shutdown
+	// handler.
+	let (shutdown_sender, shutdown_signal) = watch::channel(());
+	let background_jobs =
+		jobs::spawn_jobs(&config, &database_pool, &mailer, &shutdown_signal);
 
 	// Create the app router and listener.
 	let router = routes::get_app_router(AppState {
@@ -41,20 +63,31 @@ async fn main() {
 		redis_connection,
 		cookie_jar_key,
 		mailer,
+		webhooks,
 	});
 
 	let listener = TcpListener::bind("0.0.0.0:80").await.unwrap();
 
 	// Start the server.
 	debug!("listening on {}", listener.local_addr().unwrap());
-	axum::serve(listener, router)
-		.with_graceful_shutdown(shutdown_handler())
-		.await
-		.unwrap();
+	axum::serve(
+		listener,
+		router.into_make_service_with_connect_info::<SocketAddr>(),
+	)
+	.with_graceful_shutdown(shutdown_handler(shutdown_sender))
+	.await
+	.unwrap();
+
+	// Let the background jobs finish their current run.
+	for job in background_jobs {
+		if let Err(e) = job.await {
+			error!("background job failed: {e:?}");
+		}
+	}
 }
 
-/// Gracefully shutdown the server on SIGINT or SIGTERM.
-async fn shutdown_handler() {
+/// Gracefully shutdown the server and background jobs on SIGINT or SIGTERM.
+async fn shutdown_handler(shutdown_sender: watch::Sender<()>) {
 	let ctrl_c = async {
 		signal::ctrl_c().await.expect("COULD NOT INSTALL CTRL+C HANDLER");
 	};
@@ -70,4 +103,6 @@ async fn shutdown_handler() {
 		() = ctrl_c => {},
 		() = terminate => {},
 	}
+
+	shutdown_sender.send_replace(());
 }
@@ -14,7 +14,7 @@ use profile::{Profile, ProfileClaims};
 use tower::{Layer, Service};
 
 use crate::AppState;
-use crate::session::Session;
+use crate::session::{Session, SessionClient};
 
 /// Middleware layer that guarantees a request has a valid access token and
 /// associated session
@@ -111,9 +111,15 @@ where
 					},
 				};
 
+				let client = SessionClient::from_request(
+					req.headers(),
+					req.extensions(),
+				);
+
 				let session = Session::create(
 					state.config.access_cookie_lifetime,
 					&profile,
+					client,
 					&mut r_conn,
 				)
 				.await;
@@ -1,5 +1,7 @@
 //! Custom middleware definitions
 
 mod auth;
+mod rate_limit;
 
 pub use auth::AuthLayer;
+pub use rate_limit::{RATE_LIMIT_WINDOW_SECONDS, RateLimitLayer};
@@ -0,0 +1,150 @@
+//! Middleware to limit the amount of requests a single client can make
+
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Request};
+use axum::http::Response;
+use axum::response::IntoResponse;
+use common::{Error, RedisConn};
+use redis::{ExistenceCheck, SetExpiry, SetOptions};
+use tower::{Layer, Service};
+
+/// Length of a single rate limiting window in seconds
+pub const RATE_LIMIT_WINDOW_SECONDS: u64 = 60;
+
+/// Middleware layer that limits how often a single client can call a route
+///
+/// Requests are counted per client IP in fixed windows of
+/// [`RATE_LIMIT_WINDOW_SECONDS`], routes sharing a scope share their counter
+///
+/// Once the limit of a window is exceeded, requests are rejected with
+/// [`Error::TooManyRequests`] until the window expires
+#[derive(Clone)]
+pub struct RateLimitLayer {
+	redis_connection: RedisConn,
+	scope:            &'static str,
+	limit:            u32,
+}
+
+impl RateLimitLayer {
+	#[must_use]
+	pub fn new(
+		redis_connection: RedisConn,
+		scope: &'static str,
+		limit: u32,
+	) -> Self {
+		Self { redis_connection, scope, limit }
+	}
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+	type Service = RateLimitMiddleware<S>;
+
+	fn layer(&self, inner: S) -> Self::Service {
+		RateLimitMiddleware {
+			inner,
+			redis_connection: self.redis_connection.clone(),
+			scope: self.scope,
+			limit: self.limit,
+		}
+	}
+}
+
+#[derive(Clone)]
+pub struct RateLimitMiddleware<S> {
+	inner:            S,
+	redis_connection: RedisConn,
+	scope:            &'static str,
+	limit:            u32,
+}
+
+impl<S> Service<Request<Body>> for RateLimitMiddleware<S>
+where
+	S: Service<Request, Response = Response<Body>> + Clone + Send + 'static,
+	S::Future: Send + 'static,
+{
+	type Error = S::Error;
+	type Future = Pin<
+		Box<
+			dyn Future<Output = Result<Self::Response, Self::Error>>
+				+ Send
+				+ 'static,
+		>,
+	>;
+	type Response = S::Response;
+
+	fn poll_ready(
+		&mut self,
+		cx: &mut Context<'_>,
+	) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(cx)
+	}
+
+	#[instrument(skip_all)]
+	fn call(&mut self, req: Request<Body>) -> Self::Future {
+		let cloned_inner = self.inner.clone();
+		let mut inner = std::mem::replace(&mut self.inner, cloned_inner);
+
+		let mut r_conn = self.redis_connection.clone();
+		let scope = self.scope;
+		let limit = self.limit;
+
+		Box::pin(async move {
+			let key = format!("ratelimit:{scope}:{}", client_ip(&req));
+
+			if let Err(e) = count_request(&key, limit, &mut r_conn).await {
+				return Ok(e.into_response());
+			}
+
+			inner.call(req).await
+		})
+	}
+}
+
+/// Get the IP address of the client that made a request
+///
+/// Requests without connection info, like the ones made in tests, all share
+/// a single address
+fn client_ip(req: &Request<Body>) -> String {
+	req.extensions().get::<ConnectInfo<SocketAddr>>().map_or_else(
+		|| "unknown".to_string(),
+		|ConnectInfo(addr)| addr.ip().to_string(),
+	)
+}
+
+/// Count a request against the current window of the given key
+///
+/// The window is started by the first request made in it, so the counter
+/// resets [`RATE_LIMIT_WINDOW_SECONDS`] after that request
+async fn count_request(
+	key: &str,
+	limit: u32,
+	conn: &mut RedisConn,
+) -> Result<(), Error> {
+	let window = SetOptions::default()
+		.conditional_set(ExistenceCheck::NX)
+		.with_expiration(SetExpiry::EX(RATE_LIMIT_WINDOW_SECONDS));
+
+	let (count, ttl): (u32, i64) = redis::pipe()
+		.atomic()
+		.set_options(key, 0, window)
+		.ignore()
+		.incr(key, 1)
+		.ttl(key)
+		.query_async(conn)
+		.await?;
+
+	if count > limit {
+		warn!("rate limit of {limit} requests exceeded for {key}");
+
+		let retry_after =
+			u64::try_from(ttl).unwrap_or(RATE_LIMIT_WINDOW_SECONDS);
+
+		return Err(Error::TooManyRequests { retry_after });
+	}
+
+	Ok(())
+}
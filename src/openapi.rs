@@ -0,0 +1,117 @@
+//! OpenAPI description of the public api
+
+use utoipa::OpenApi;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+
+use crate::Config;
+use crate::controllers::{auth, location, opening_time, profile, reservation};
+
+/// The generated OpenAPI document
+///
+/// Only the routes of the tags below are described so far, the remaining
+/// routes are added as they get annotated
+#[derive(OpenApi)]
+#[openapi(
+	info(
+		title = "Blokmap API",
+		description = "The api behind Blokmap, a map of study locations"
+	),
+	paths(
+		auth::register_profile,
+		auth::resend_confirmation_email,
+		auth::confirm_email,
+		auth::revert_email,
+		auth::request_password_reset,
+		auth::reset_password,
+		auth::login_profile,
+		auth::logout_profile,
+		profile::get_all_profiles,
+		profile::get_current_profile,
+		profile::get_profile,
+		profile::update_current_profile,
+		profile::unlink_current_profile_sso,
+		profile::update_profile,
+		profile::disable_profile,
+		profile::activate_profile,
+		profile::get_deactivation_reason,
+		profile::get_profile_favourites,
+		profile::get_profile_locations,
+		profile::get_profile_reservations,
+		profile::get_profile_authorities,
+		profile::get_current_profile_created_authorities,
+		profile::get_current_profile_authority_memberships,
+		profile::get_current_profile_uploaded_images,
+		profile::get_profile_reviews,
+		profile::get_profile_stats,
+		profile::get_profile_sessions,
+		profile::delete_profile_session,
+		profile::delete_other_profile_sessions,
+		profile::upload_profile_avatar,
+		profile::delete_profile_avatar,
+		profile::delete_current_profile_avatar,
+		location::search_locations,
+		location::create_location,
+		location::create_location_multipart,
+		location::get_location,
+		location::get_location_by_public_id,
+		location::get_nearest_location,
+		location::get_nearby_locations,
+		location::get_location_availability,
+		location::get_pending_locations,
+		location::update_location,
+		location::update_location_description,
+		location::update_location_excerpt,
+		location::get_location_translations,
+		location::approve_location,
+		location::reject_location,
+		location::publish_location,
+		location::unpublish_location,
+		location::delete_location,
+		location::merge_location,
+		location::set_location_tags,
+		location::get_location_stats,
+		location::get_location_permissions,
+		location::get_location_opening_times,
+		location::get_location_opening_times_availability,
+		location::get_location_reservations,
+		location::get_location_opening_time_reservations,
+		opening_time::create_location_opening_times,
+		opening_time::create_location_opening_time_series,
+		opening_time::generate_location_holiday_exceptions,
+		opening_time::get_opening_time_availability,
+		opening_time::update_location_opening_time,
+		opening_time::delete_location_opening_time,
+		reservation::get_all_reservations,
+		reservation::create_reservation,
+		reservation::extend_reservation,
+		reservation::cancel_reservation,
+		reservation::confirm_reservation,
+		reservation::delete_reservation
+	),
+	tags(
+		(name = "auth", description = "Registering and logging in"),
+		(name = "profiles", description = "Profiles and their sessions"),
+		(name = "locations", description = "Locations and their opening times"),
+		(name = "reservations", description = "Reservations of opening times")
+	)
+)]
+struct ApiDoc;
+
+/// Build the OpenAPI document for the given configuration
+///
+/// The access token cookie is named after the configured cookie name, so the
+/// security scheme can only be added at runtime
+#[must_use]
+pub fn openapi(config: &Config) -> utoipa::openapi::OpenApi {
+	let mut doc = ApiDoc::openapi();
+
+	let scheme = SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new(
+		&config.access_cookie_name,
+	)));
+
+	doc.components
+		.get_or_insert_with(Default::default)
+		.add_security_scheme("access_token", scheme);
+
+	doc
+}
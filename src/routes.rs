@@ -1,6 +1,7 @@
 use std::time::Duration;
 
 use axum::Router;
+use axum::extract::DefaultBodyLimit;
 use axum::routing::{delete, get, patch, post};
 use tower::ServiceBuilder;
 use tower_http::compression::CompressionLayer;
@@ -9,6 +10,7 @@ use tower_http::timeout::TimeoutLayer;
 use tower_http::trace::TraceLayer;
 
 use crate::AppState;
+use crate::controllers::audit_log::get_audit_log;
 use crate::controllers::auth::{
 	confirm_email,
 	login_profile,
@@ -17,22 +19,36 @@ use crate::controllers::auth::{
 	request_password_reset,
 	resend_confirmation_email,
 	reset_password,
+	revert_email,
 };
 use crate::controllers::authority::{
 	add_authority_location,
 	add_authority_member,
+	check_authority_name,
 	create_authority,
 	create_authority_role,
+	create_authority_webhook,
+	delete_authority,
 	delete_authority_member,
 	delete_authority_role,
+	delete_authority_webhook,
+	export_authority_locations,
 	get_all_authorities,
 	get_authority,
 	get_authority_locations,
 	get_authority_members,
+	get_authority_permissions,
+	get_authority_reservations,
 	get_authority_roles,
+	get_authority_statistics,
+	get_authority_webhook,
+	get_authority_webhooks,
+	import_authority_members,
+	search_authorities,
 	update_authority,
 	update_authority_member,
 	update_authority_role,
+	update_authority_webhook,
 };
 use crate::controllers::healthcheck;
 use crate::controllers::institution::{
@@ -40,21 +56,31 @@ use crate::controllers::institution::{
 	create_institution,
 	create_institution_authority,
 	create_institution_role,
+	delete_institution,
 	delete_institution_member,
 	delete_institution_role,
 	get_all_institutions,
 	get_categories,
 	get_institution,
 	get_institution_members,
+	get_institution_permissions,
 	get_institution_roles,
+	get_institution_statistics,
+	get_institution_tags,
+	get_institution_translations,
 	link_authority,
 	update_insitution_member,
+	update_institution,
 	update_institution_role,
 };
 use crate::controllers::location::{
+	add_location_favourite,
 	add_location_member,
+	approve_all_location_images,
 	approve_location,
+	approve_location_image,
 	create_location,
+	create_location_multipart,
 	create_location_review,
 	create_location_role,
 	delete_location,
@@ -62,70 +88,134 @@ use crate::controllers::location::{
 	delete_location_member,
 	delete_location_role,
 	get_location,
+	get_location_availability,
+	get_location_by_public_id,
+	get_location_count_by_zip,
 	get_location_members,
 	get_location_opening_time_reservations,
 	get_location_opening_times,
+	get_location_opening_times_availability,
+	get_location_permissions,
 	get_location_reservations,
 	get_location_reviews,
 	get_location_roles,
+	get_location_stats,
+	get_location_translations,
+	get_locations_by_reservation_count,
+	get_nearby_locations,
 	get_nearest_location,
+	get_pending_location_images,
+	get_pending_locations,
+	hard_delete_location,
+	merge_location,
+	publish_location,
 	reject_location,
+	remove_location_favourite,
 	reorder_location_images,
 	search_locations,
+	set_default_location_role,
+	set_location_cover_image,
 	set_location_tags,
+	unpublish_location,
 	update_location,
+	update_location_description,
+	update_location_excerpt,
 	update_location_member,
 	update_location_review,
 	update_location_role,
 	upload_location_image,
 };
+use crate::controllers::openapi::{get_api_docs, get_openapi_document};
 use crate::controllers::opening_time::{
+	create_location_opening_time_series,
 	create_location_opening_times,
 	delete_location_opening_time,
+	generate_location_holiday_exceptions,
+	get_next_available_opening_times,
+	get_opening_time_availability,
+	get_opening_time_location,
 	update_location_opening_time,
 };
 use crate::controllers::profile::{
 	activate_profile,
+	admin_cleanup_pending_profiles,
+	admin_resend_confirmation_email,
+	delete_current_profile_avatar,
+	delete_other_profile_sessions,
 	delete_profile_avatar,
+	delete_profile_session,
 	disable_profile,
 	get_all_profiles,
 	get_current_profile,
+	get_current_profile_authority_memberships,
+	get_current_profile_created_authorities,
+	get_current_profile_uploaded_images,
+	get_deactivation_reason,
+	get_pending_verification_profiles,
 	get_profile,
 	get_profile_authorities,
+	get_profile_favourites,
 	get_profile_locations,
 	get_profile_reservations,
 	get_profile_reviews,
+	get_profile_sessions,
 	get_profile_stats,
+	unlink_current_profile_sso,
 	update_current_profile,
 	update_profile,
 	upload_profile_avatar,
 };
-use crate::controllers::reservation::{create_reservation, delete_reservation};
+use crate::controllers::reservation::{
+	cancel_reservation,
+	confirm_reservation,
+	create_reservation,
+	delete_reservation,
+	extend_reservation,
+	get_admin_reservations,
+	get_all_reservations,
+};
+use crate::controllers::review::{flag_review, get_flagged_reviews};
 use crate::controllers::tag::{
 	create_tag,
 	delete_tag,
 	get_all_tags,
+	get_tag_statistics,
+	get_tag_translations,
+	get_tags_batch,
 	update_tag,
 };
 use crate::controllers::translation::{
+	admin_bulk_update_translations,
 	create_translation,
 	delete_translation,
 	get_translation,
 	update_translation,
 };
-use crate::middleware::AuthLayer;
+use crate::middleware::{AuthLayer, RateLimitLayer};
 
 /// Get the app router
 pub fn get_app_router(state: AppState) -> Router {
-	let api_routes = Router::new()
+	let mut api_routes = Router::new()
 		.route("/healthcheck", get(healthcheck))
+		.route("/healthz", get(healthcheck))
+		.route("/openapi.json", get(get_openapi_document))
 		.nest("/auth", auth_routes(&state))
 		.nest("/profiles", profile_routes(&state))
 		.nest("/authorities", authority_routes(&state))
 		.nest("/locations", location_routes(&state))
+		.nest("/opening-times", opening_time_routes())
 		.nest("/translations", translation_routes(&state))
 		.nest("/tags", tag_routes(&state))
-		.nest("/institutions", institution_routes(&state));
+		.nest("/institutions", institution_routes(&state))
+		.nest("/reviews", review_routes(&state))
+		.nest("/reservations", reservation_routes(&state))
+		.nest("/admin", admin_routes(&state));
+
+	// The Swagger UI loads its assets from a CDN, so it is left out in
+	// production
+	if !state.config.production {
+		api_routes = api_routes.route("/docs", get(get_api_docs));
+	}
 
 	Router::new()
 		.merge(api_routes)
@@ -139,18 +229,58 @@ pub fn get_app_router(state: AppState) -> Router {
 		.with_state(state)
 }
 
+/// Room for the multipart framing and other fields around an uploaded image
+const IMAGE_UPLOAD_OVERHEAD_BYTES: usize = 64 * 1024;
+
+/// Body limit for routes uploading a single image
+///
+/// The image itself is checked against the configured limit, this only makes
+/// sure such an image is not refused by the default body limit first
+fn image_upload_limit(state: &AppState) -> DefaultBodyLimit {
+	DefaultBodyLimit::max(
+		state.config.image_max_bytes + IMAGE_UPLOAD_OVERHEAD_BYTES,
+	)
+}
+
 /// Authentication routes
 fn auth_routes(state: &AppState) -> Router<AppState> {
+	let r_conn = &state.redis_connection;
+	let config = &state.config;
+
+	let register_limit = RateLimitLayer::new(
+		r_conn.clone(),
+		"register",
+		config.register_rate_limit_per_minute,
+	);
+	let password_reset_limit = RateLimitLayer::new(
+		r_conn.clone(),
+		"password_reset",
+		config.password_reset_rate_limit_per_minute,
+	);
+	let login_limit = RateLimitLayer::new(
+		r_conn.clone(),
+		"login",
+		config.login_rate_limit_per_minute,
+	);
+
 	Router::new()
-		.route("/register", post(register_profile))
+		.route("/register", post(register_profile).route_layer(register_limit))
 		.route("/confirm_email/{token}", post(confirm_email))
+		.route("/revert-email/{token}", post(revert_email))
 		.route(
 			"/resend_confirmation_email/{token}",
 			post(resend_confirmation_email),
 		)
-		.route("/request_password_reset", post(request_password_reset))
-		.route("/reset_password", post(reset_password))
-		.route("/login", post(login_profile))
+		.route(
+			"/request_password_reset",
+			post(request_password_reset)
+				.route_layer(password_reset_limit.clone()),
+		)
+		.route(
+			"/reset_password",
+			post(reset_password).route_layer(password_reset_limit),
+		)
+		.route("/login", post(login_profile).route_layer(login_limit))
 		.route(
 			"/logout",
 			post(logout_profile).route_layer(AuthLayer::new(state.clone())),
@@ -162,17 +292,43 @@ fn profile_routes(state: &AppState) -> Router<AppState> {
 	let protected = Router::new()
 		.route("/", get(get_all_profiles))
 		.route("/me", patch(update_current_profile))
+		.route(
+			"/me/authority-memberships",
+			get(get_current_profile_authority_memberships),
+		)
+		.route("/me/avatar", delete(delete_current_profile_avatar))
+		.route(
+			"/me/created-authorities",
+			get(get_current_profile_created_authorities),
+		)
+		.route("/me/unlink-sso", post(unlink_current_profile_sso))
+		.route("/me/uploaded-images", get(get_current_profile_uploaded_images))
 		.route("/{profile_id}", get(get_profile).patch(update_profile))
 		.route(
 			"/{profile_id}/avatar",
-			post(upload_profile_avatar).delete(delete_profile_avatar),
+			post(upload_profile_avatar)
+				.layer(image_upload_limit(state))
+				.delete(delete_profile_avatar),
 		)
 		.route("/{profile_id}/block", post(disable_profile))
 		.route("/{profile_id}/unblock", post(activate_profile))
+		.route(
+			"/{profile_id}/deactivation-reason",
+			get(get_deactivation_reason),
+		)
 		.route("/{profile_id}/authorities", get(get_profile_authorities))
+		.route("/{profile_id}/favourites", get(get_profile_favourites))
 		.route("/{profile_id}/locations", get(get_profile_locations))
 		.route("/{profile_id}/reservations", get(get_profile_reservations))
 		.route("/{profile_id}/reviews", get(get_profile_reviews))
+		.route(
+			"/{profile_id}/sessions",
+			get(get_profile_sessions).delete(delete_other_profile_sessions),
+		)
+		.route(
+			"/{profile_id}/sessions/{session_id}",
+			delete(delete_profile_session),
+		)
 		.route("/{profile_id}/stats", get(get_profile_stats))
 		.route_layer(AuthLayer::new(state.clone()));
 
@@ -183,10 +339,22 @@ fn profile_routes(state: &AppState) -> Router<AppState> {
 fn location_routes(state: &AppState) -> Router<AppState> {
 	let protected = Router::new()
 		.route("/", post(create_location))
+		.route("/multipart", post(create_location_multipart))
+		.route("/pending", get(get_pending_locations))
 		.route("/{id}", patch(update_location).delete(delete_location))
+		.route("/{id}/description", patch(update_location_description))
+		.route("/{id}/excerpt", patch(update_location_excerpt))
+		.route("/{id}/translations", get(get_location_translations))
 		.route("/{id}/approve", post(approve_location))
 		.route("/{id}/reject", post(reject_location))
+		.route("/{id}/publish", post(publish_location))
+		.route("/{id}/unpublish", post(unpublish_location))
+		.route("/{id}/merge", post(merge_location))
 		.route("/{id}/tags", post(set_location_tags))
+		.route(
+			"/{id}/favourite",
+			post(add_location_favourite).delete(remove_location_favourite),
+		)
 		.route(
 			"/{id}/members",
 			get(get_location_members).post(add_location_member),
@@ -199,23 +367,44 @@ fn location_routes(state: &AppState) -> Router<AppState> {
 			"/{id}/roles",
 			get(get_location_roles).post(create_location_role),
 		)
+		.route("/{id}/roles/set-default", post(set_default_location_role))
 		.route(
 			"/{loc_id}/roles/{role_id}",
 			patch(update_location_role).delete(delete_location_role),
 		)
-		.route("/{id}/images", post(upload_location_image))
+		.route(
+			"/{id}/images",
+			post(upload_location_image).layer(image_upload_limit(state)),
+		)
 		.route("/{id}/images/{image_id}", delete(delete_location_image))
+		.route("/{id}/images/pending", get(get_pending_location_images))
+		.route("/{id}/images/{image_id}/approve", post(approve_location_image))
 		.route("/{id}/images/reorder", post(reorder_location_images))
+		.route("/{id}/images/approve-all", post(approve_all_location_images))
+		.route(
+			"/{id}/images/{image_id}/set-cover",
+			post(set_location_cover_image),
+		)
 		.route(
 			"/{id}/opening-times",
 			get(get_location_opening_times).post(create_location_opening_times),
 		)
+		.route(
+			"/{id}/opening-times/series",
+			post(create_location_opening_time_series),
+		)
+		.route(
+			"/{id}/opening-times/generate-holiday-exceptions",
+			post(generate_location_holiday_exceptions),
+		)
 		.route(
 			"/{id}/opening-times/{time_id}",
 			patch(update_location_opening_time)
 				.delete(delete_location_opening_time),
 		)
 		.route("/{l_id}/reservations", get(get_location_reservations))
+		.route("/{id}/stats", get(get_location_stats))
+		.route("/{id}/permissions", get(get_location_permissions))
 		.route(
 			"/{l_id}/opening-times/{t_id}/reservations",
 			get(get_location_opening_time_reservations)
@@ -235,22 +424,34 @@ fn location_routes(state: &AppState) -> Router<AppState> {
 	Router::new()
 		.route("/", get(search_locations))
 		.route("/{id}", get(get_location))
+		.route("/p/{public_id}", get(get_location_by_public_id))
+		.route("/{id}/available", get(get_location_availability))
+		.route(
+			"/{id}/opening-times/availability",
+			get(get_location_opening_times_availability),
+		)
 		.route("/nearest", get(get_nearest_location))
+		.route("/nearby", get(get_nearby_locations))
 		.merge(protected)
 }
 
 fn authority_routes(state: &AppState) -> Router<AppState> {
-	Router::new()
+	let protected = Router::new()
 		.route("/", get(get_all_authorities).post(create_authority))
-		.route("/{id}", get(get_authority).patch(update_authority))
+		.route(
+			"/{id}",
+			get(get_authority).patch(update_authority).delete(delete_authority),
+		)
 		.route(
 			"/{id}/locations",
 			get(get_authority_locations).post(add_authority_location),
 		)
+		.route("/{id}/locations/export", get(export_authority_locations))
 		.route(
 			"/{id}/members",
 			get(get_authority_members).post(add_authority_member),
 		)
+		.route("/{id}/members/import", post(import_authority_members))
 		.route(
 			"/{a_id}/members/{p_id}",
 			patch(update_authority_member).delete(delete_authority_member),
@@ -263,7 +464,33 @@ fn authority_routes(state: &AppState) -> Router<AppState> {
 			"/{auth_id}/roles/{role_id}",
 			patch(update_authority_role).delete(delete_authority_role),
 		)
-		.route_layer(AuthLayer::new(state.clone()))
+		.route(
+			"/{id}/webhooks",
+			get(get_authority_webhooks).post(create_authority_webhook),
+		)
+		.route(
+			"/{auth_id}/webhooks/{webhook_id}",
+			get(get_authority_webhook)
+				.patch(update_authority_webhook)
+				.delete(delete_authority_webhook),
+		)
+		.route("/{id}/reservations", get(get_authority_reservations))
+		.route("/{id}/statistics", get(get_authority_statistics))
+		.route("/{id}/permissions", get(get_authority_permissions))
+		.route_layer(AuthLayer::new(state.clone()));
+
+	Router::new()
+		.route("/search", get(search_authorities))
+		.route("/name-check", get(check_authority_name))
+		.merge(protected)
+}
+
+/// Public opening time routes
+fn opening_time_routes() -> Router<AppState> {
+	Router::new()
+		.route("/next-available", post(get_next_available_opening_times))
+		.route("/{id}/location", get(get_opening_time_location))
+		.route("/{id}/availability", get(get_opening_time_availability))
 }
 
 /// Translation routes with auth protection
@@ -283,16 +510,71 @@ fn tag_routes(state: &AppState) -> Router<AppState> {
 	let protected = Router::new()
 		.route("/", post(create_tag))
 		.route("/{id}", patch(update_tag).delete(delete_tag))
+		.route("/{id}/translations", get(get_tag_translations))
 		.route_layer(AuthLayer::new(state.clone()));
 
-	Router::new().route("/", get(get_all_tags)).merge(protected)
+	Router::new()
+		.route("/", get(get_all_tags))
+		.route("/batch", post(get_tags_batch))
+		.route("/{id}/stats", get(get_tag_statistics))
+		.merge(protected)
 }
 
-fn institution_routes(state: &AppState) -> Router<AppState> {
+fn review_routes(state: &AppState) -> Router<AppState> {
+	Router::new()
+		.route("/{id}/flag", post(flag_review))
+		.route_layer(AuthLayer::new(state.clone()))
+}
+
+/// Reservation routes
+fn reservation_routes(state: &AppState) -> Router<AppState> {
 	Router::new()
+		.route("/", get(get_all_reservations))
+		.route("/{id}/cancel", post(cancel_reservation))
+		.route("/{id}/confirm", post(confirm_reservation))
+		.route("/{id}/extend", post(extend_reservation))
+		.route_layer(AuthLayer::new(state.clone()))
+}
+
+/// Admin-only routes, guarded by an [`AdminSession`](crate::AdminSession)
+fn admin_routes(state: &AppState) -> Router<AppState> {
+	Router::new()
+		.route("/audit-log", get(get_audit_log))
+		.route(
+			"/locations/by-reservation-count",
+			get(get_locations_by_reservation_count),
+		)
+		.route("/locations/stats/by-zip", get(get_location_count_by_zip))
+		.route("/locations/{id}/hard", delete(hard_delete_location))
+		.route(
+			"/profiles/pending-verification",
+			get(get_pending_verification_profiles),
+		)
+		.route(
+			"/profiles/cleanup-pending",
+			post(admin_cleanup_pending_profiles),
+		)
+		.route(
+			"/profiles/{id}/resend-confirmation",
+			post(admin_resend_confirmation_email),
+		)
+		.route("/reservations", get(get_admin_reservations))
+		.route("/reviews/flagged", get(get_flagged_reviews))
+		.route("/translations/bulk", patch(admin_bulk_update_translations))
+		.route_layer(AuthLayer::new(state.clone()))
+}
+
+fn institution_routes(state: &AppState) -> Router<AppState> {
+	let protected = Router::new()
 		.route("/", get(get_all_institutions).post(create_institution))
 		.route("/categories", get(get_categories))
-		.route("/{id}", get(get_institution))
+		.route(
+			"/{id}",
+			get(get_institution)
+				.patch(update_institution)
+				.delete(delete_institution),
+		)
+		.route("/{id}/translations", get(get_institution_translations))
 		.route("/{id}/authority", post(create_institution_authority))
 		.route("/{i_id}/link/{a_id}", post(link_authority))
 		.route(
@@ -311,5 +593,11 @@ fn institution_routes(state: &AppState) -> Router<AppState> {
 			"/{inst_id}/roles/{role_id}",
 			patch(update_institution_role).delete(delete_institution_role),
 		)
-		.route_layer(AuthLayer::new(state.clone()))
+		.route("/{id}/statistics", get(get_institution_statistics))
+		.route("/{id}/permissions", get(get_institution_permissions))
+		.route_layer(AuthLayer::new(state.clone()));
+
+	Router::new()
+		.route("/{id}/tags", get(get_institution_tags))
+		.merge(protected)
 }
@@ -0,0 +1,32 @@
+use audit_log::AuditLog;
+use chrono::NaiveDateTime;
+use db::{AuditAction, AuditTarget};
+use serde::{Deserialize, Serialize};
+
+use crate::schemas::profile::ProfileResponse;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogResponse {
+	pub id:          i32,
+	pub actor:       Option<ProfileResponse>,
+	pub action:      AuditAction,
+	pub target_type: AuditTarget,
+	pub target_id:   i32,
+	pub detail:      serde_json::Value,
+	pub created_at:  NaiveDateTime,
+}
+
+impl From<AuditLog> for AuditLogResponse {
+	fn from(value: AuditLog) -> Self {
+		Self {
+			id:          value.primitive.id,
+			actor:       value.actor.map(Into::into),
+			action:      value.primitive.action,
+			target_type: value.primitive.target_type,
+			target_id:   value.primitive.target_id,
+			detail:      value.primitive.detail,
+			created_at:  value.primitive.created_at,
+		}
+	}
+}
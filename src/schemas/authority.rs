@@ -1,29 +1,47 @@
+use std::collections::HashMap;
+
 use authority::{
 	Authority,
+	AuthorityCounts,
 	AuthorityIncludes,
 	AuthorityMemberUpdate,
+	AuthorityStatistics,
 	AuthorityUpdate,
+	MemberImportIssue,
+	MemberImportIssueReason,
+	MemberImportRow,
+	MemberImportSummary,
 	NewAuthority,
 	NewAuthorityMember,
 };
+use axum::extract::Multipart;
 use chrono::NaiveDateTime;
+use common::{Error, MultipartParseError};
 use primitives::PrimitiveAuthority;
+use role::{AuthorityRole, RoleIncludes};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator_derive::Validate;
 
 use crate::schemas::BuildResponse;
 use crate::schemas::profile::ProfileResponse;
+use crate::schemas::role::RoleResponse;
 
 #[skip_serializing_none]
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthorityResponse {
-	pub id:          i32,
-	pub name:        String,
-	pub description: Option<String>,
-	pub created_at:  NaiveDateTime,
-	pub created_by:  Option<Option<ProfileResponse>>,
-	pub updated_at:  NaiveDateTime,
-	pub updated_by:  Option<Option<ProfileResponse>>,
+	pub id:             i32,
+	pub name:           String,
+	pub description:    Option<String>,
+	pub created_at:     NaiveDateTime,
+	#[schema(value_type = Option<ProfileResponse>)]
+	pub created_by:     Option<Option<ProfileResponse>>,
+	pub updated_at:     NaiveDateTime,
+	#[schema(value_type = Option<ProfileResponse>)]
+	pub updated_by:     Option<Option<ProfileResponse>>,
+	pub member_count:   Option<i64>,
+	pub location_count: Option<i64>,
 }
 
 impl BuildResponse<AuthorityResponse> for Authority {
@@ -38,42 +56,146 @@ impl BuildResponse<AuthorityResponse> for Authority {
 		let updated_by = self.updated_by.map(Into::into);
 
 		Ok(AuthorityResponse {
-			id:          self.primitive.id,
-			name:        self.primitive.name,
-			description: self.primitive.description,
-			created_at:  self.primitive.created_at,
-			created_by:  if includes.created_by {
+			id:             self.primitive.id,
+			name:           self.primitive.name,
+			description:    self.primitive.description,
+			created_at:     self.primitive.created_at,
+			created_by:     if includes.created_by {
 				Some(created_by)
 			} else {
 				None
 			},
-			updated_at:  self.primitive.updated_at,
-			updated_by:  if includes.updated_by {
+			updated_at:     self.primitive.updated_at,
+			updated_by:     if includes.updated_by {
 				Some(updated_by)
 			} else {
 				None
 			},
+			member_count:   None,
+			location_count: None,
 		})
 	}
 }
 
+impl BuildResponse<AuthorityResponse> for (Authority, Option<AuthorityCounts>) {
+	type Includes = AuthorityIncludes;
+
+	fn build_response(
+		self,
+		includes: Self::Includes,
+		config: &crate::Config,
+	) -> Result<AuthorityResponse, common::Error> {
+		let (authority, counts) = self;
+
+		let mut response = authority.build_response(includes, config)?;
+
+		if let Some(counts) = counts {
+			response.member_count = Some(counts.member_count);
+			response.location_count = Some(counts.location_count);
+		}
+
+		Ok(response)
+	}
+}
+
 impl From<PrimitiveAuthority> for AuthorityResponse {
 	fn from(value: PrimitiveAuthority) -> Self {
 		Self {
-			id:          value.id,
-			name:        value.name,
-			description: value.description,
-			created_at:  value.created_at,
-			created_by:  None,
-			updated_at:  value.updated_at,
-			updated_by:  None,
+			id:             value.id,
+			name:           value.name,
+			description:    value.description,
+			created_at:     value.created_at,
+			created_by:     None,
+			updated_at:     value.updated_at,
+			updated_by:     None,
+			member_count:   None,
+			location_count: None,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileAuthorityMembershipResponse {
+	pub authority: AuthorityResponse,
+	pub role:      Option<RoleResponse>,
+}
+
+impl BuildResponse<ProfileAuthorityMembershipResponse>
+	for (Authority, Option<AuthorityRole>)
+{
+	type Includes = AuthorityIncludes;
+
+	fn build_response(
+		self,
+		includes: Self::Includes,
+		config: &crate::Config,
+	) -> Result<ProfileAuthorityMembershipResponse, common::Error> {
+		let (authority, role) = self;
+
+		let authority = authority.build_response(includes, config)?;
+		let role = role
+			.map(|r| r.build_response(RoleIncludes::default(), config))
+			.transpose()?;
+
+		Ok(ProfileAuthorityMembershipResponse { authority, role })
+	}
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorityStatisticsResponse {
+	pub location_count:               i64,
+	pub member_count:                 i64,
+	pub unique_location_member_count: i64,
+}
+
+impl From<AuthorityStatistics> for AuthorityStatisticsResponse {
+	fn from(value: AuthorityStatistics) -> Self {
+		Self {
+			location_count:               value.location_count,
+			member_count:                 value.member_count,
+			unique_location_member_count: value.unique_location_member_count,
 		}
 	}
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthoritySearchOptions {
+	#[validate(length(
+		min = 2,
+		message = "search query must be at least 2 characters long",
+		code = "q-length"
+	))]
+	pub q:     String,
+	#[serde(default = "default_search_limit")]
+	pub limit: usize,
+}
+
+fn default_search_limit() -> usize { 20 }
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
+pub struct NameCheckOptions {
+	pub name: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NameCheckResponse {
+	pub available: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+#[serde(rename_all = "camelCase")]
 pub struct CreateAuthorityRequest {
+	#[validate(length(
+		min = 2,
+		max = 255,
+		message = "name must be between 2 and 255 characters long",
+		code = "name-length"
+	))]
 	pub name:        String,
 	pub description: Option<String>,
 }
@@ -135,6 +257,7 @@ impl CreateAuthorityMemberRequest {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthorityMemberUpdateRequest {
+	#[serde(alias = "roleId")]
 	pub authority_role_id: Option<i32>,
 }
 
@@ -147,3 +270,118 @@ impl AuthorityMemberUpdateRequest {
 		}
 	}
 }
+
+/// A CSV file of members to add to an authority
+///
+/// Every row holds an email and an optional role name, a leading
+/// `email,role` header is skipped
+#[derive(Clone, Debug)]
+pub struct ImportAuthorityMembersRequest {
+	pub rows:      Vec<MemberImportRow>,
+	/// Rows that could not be parsed at all
+	pub malformed: Vec<MemberImportIssue>,
+}
+
+impl ImportAuthorityMembersRequest {
+	pub async fn parse(
+		multipart: &mut Multipart,
+		max_rows: usize,
+	) -> Result<Self, Error> {
+		let Some(field) = multipart.next_field().await? else {
+			return Err(MultipartParseError::MissingField {
+				expected_field: "file".to_string(),
+			}
+			.into());
+		};
+
+		let Some(name) = field.name() else {
+			return Err(MultipartParseError::NamelessField.into());
+		};
+
+		if name != "file" {
+			return Err(MultipartParseError::UnknownField {
+				field_name: name.to_string(),
+			}
+			.into());
+		}
+
+		let text = field.text().await?;
+
+		let mut rows = vec![];
+		let mut malformed = vec![];
+
+		let lines = text
+			.trim_start_matches('\u{feff}')
+			.lines()
+			.enumerate()
+			.map(|(i, line)| (i + 1, line.trim()))
+			.filter(|(_, line)| !line.is_empty());
+
+		for (line, content) in lines {
+			let columns: Vec<&str> = content
+				.split(',')
+				.map(|c| c.trim().trim_matches('"').trim())
+				.collect();
+
+			let is_header = rows.is_empty()
+				&& malformed.is_empty()
+				&& columns[0].eq_ignore_ascii_case("email");
+
+			if is_header {
+				continue;
+			}
+
+			let (email, role) = match columns.as_slice() {
+				[email] => (*email, ""),
+				[email, role] => (*email, *role),
+				_ => ("", ""),
+			};
+
+			if email.is_empty() {
+				malformed.push(MemberImportIssue {
+					line,
+					email: columns[0].to_string(),
+					reason: MemberImportIssueReason::MalformedRow,
+				});
+
+				continue;
+			}
+
+			rows.push(MemberImportRow {
+				line,
+				email: email.to_string(),
+				role: (!role.is_empty()).then(|| role.to_string()),
+			});
+		}
+
+		if rows.len() + malformed.len() > max_rows {
+			let message =
+				format!("at most {max_rows} members can be imported at once");
+
+			return Err(Error::ValidationError {
+				fields: HashMap::from([("file".to_string(), message.clone())]),
+				message,
+			});
+		}
+
+		Ok(Self { rows, malformed })
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberImportResponse {
+	pub added:   usize,
+	pub skipped: Vec<MemberImportIssue>,
+	pub errors:  Vec<MemberImportIssue>,
+}
+
+impl From<MemberImportSummary> for MemberImportResponse {
+	fn from(value: MemberImportSummary) -> Self {
+		Self {
+			added:   value.added,
+			skipped: value.skipped,
+			errors:  value.errors,
+		}
+	}
+}
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HealthcheckOptions {
+	/// Also check Redis and the mailer, instead of only the database
+	pub deep: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+	Ok,
+	Failing,
+	/// The subsystem is not used in this deployment
+	Skipped,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubsystemHealth {
+	pub status:     HealthStatus,
+	/// How long the check took, absent for skipped checks
+	pub latency_ms: Option<u64>,
+}
+
+impl SubsystemHealth {
+	#[must_use]
+	pub fn skipped() -> Self {
+		Self { status: HealthStatus::Skipped, latency_ms: None }
+	}
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthResponse {
+	pub database: SubsystemHealth,
+	pub redis:    SubsystemHealth,
+	pub mailer:   SubsystemHealth,
+}
+
+impl HealthResponse {
+	/// Whether none of the checked subsystems are failing
+	#[must_use]
+	pub fn is_healthy(&self) -> bool {
+		[self.database, self.redis, self.mailer]
+			.iter()
+			.all(|s| s.status != HealthStatus::Failing)
+	}
+}
@@ -2,20 +2,28 @@ use axum::body::Bytes;
 use axum::extract::Multipart;
 use axum::extract::multipart::Field;
 use common::{Error, MultipartParseError};
-use image::{Image, ImageIncludes, OrderedImage};
+use image::{Image, ImageIncludes, ImageWithLocation, OrderedImage};
 use primitives::PrimitiveImage;
 use serde::{Deserialize, Serialize};
 use utils::image::{ImageVariant, OrderedImageVariant};
+use utoipa::ToSchema;
 
 use crate::Config;
 use crate::schemas::BuildResponse;
 use crate::schemas::profile::ProfileResponse;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 pub struct ImageResponse {
 	pub id:          i32,
 	pub url:         String,
 	pub index:       Option<i32>,
+	#[serde(rename = "isCover")]
+	pub is_cover:    bool,
+	#[schema(
+		value_type = Option<ProfileResponse>,
+		required = true,
+		no_recursion
+	)]
 	pub uploaded_by: Option<Option<Box<ProfileResponse>>>,
 }
 
@@ -43,6 +51,7 @@ impl BuildResponse<ImageResponse> for PrimitiveImage {
 			id:          self.id,
 			url:         url.to_string(),
 			index:       None,
+			is_cover:    false,
 			uploaded_by: None,
 		};
 
@@ -80,11 +89,55 @@ impl BuildResponse<ImageResponse> for OrderedImage {
 	) -> Result<ImageResponse, common::Error> {
 		let mut response = self.image.build_response(includes, config)?;
 		response.index = Some(self.index);
+		response.is_cover = self.is_cover;
 
 		Ok(response)
 	}
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovedImagesResponse {
+	pub approved_images: usize,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageWithLocationResponse {
+	pub image:         ImageResponse,
+	pub location_id:   Option<i32>,
+	pub location_name: Option<String>,
+}
+
+impl BuildResponse<ImageWithLocationResponse> for ImageWithLocation {
+	type Includes = ImageIncludes;
+
+	fn build_response(
+		self,
+		includes: Self::Includes,
+		config: &Config,
+	) -> Result<ImageWithLocationResponse, Error> {
+		let image = self.image.build_response(includes, config)?;
+
+		Ok(ImageWithLocationResponse {
+			image,
+			location_id: self.location_id,
+			location_name: self.location_name,
+		})
+	}
+}
+
+/// The `multipart/form-data` fields of a [`CreateImageRequest`], only
+/// used to describe the request in the OpenAPI document
+#[derive(Clone, Debug, ToSchema)]
+pub struct ImageUploadForm {
+	/// The image file, if no url is given
+	#[schema(value_type = Option<String>, format = Binary)]
+	pub image: Option<Vec<u8>>,
+	/// The url of an image, if no file is given
+	pub url:   Option<String>,
+}
+
 #[derive(Clone, Debug)]
 pub enum CreateImageRequest {
 	Image(Bytes),
@@ -0,0 +1,130 @@
+//! Shared extractor for the includes of an endpoint
+
+use std::borrow::Cow;
+use std::fmt::Debug;
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use common::Error;
+use serde::de::DeserializeOwned;
+use utoipa::IntoParams;
+use utoipa::openapi::path::ParameterIn;
+
+/// Query parameters selecting which related records to include in a
+/// response, like `?created_by=true`
+///
+/// Flags accept `true`, `false`, `1`, `0`, `yes` and `no` in any case. Keys
+/// that are not valid for `T` are left to the other extractors of the
+/// endpoint, unless they look like a misspelled include, those are rejected
+/// together with a list of the valid keys
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Includes<T>(pub T);
+
+impl<S, T> FromRequestParts<S> for Includes<T>
+where
+	S: Send + Sync,
+	T: Debug + DeserializeOwned + IntoParams,
+{
+	type Rejection = Error;
+
+	async fn from_request_parts(
+		parts: &mut Parts,
+		_state: &S,
+	) -> Result<Self, Self::Rejection> {
+		let includes = parse_includes(parts.uri.query().unwrap_or_default())?;
+
+		debug!("parsed includes {includes:?}");
+
+		Ok(Self(includes))
+	}
+}
+
+/// Parse the includes of type `T` from a query string
+///
+/// # Errors
+/// Fails if a key looks like a misspelled include or a flag has a value
+/// that is not understood
+pub fn parse_includes<T>(query: &str) -> Result<T, Error>
+where
+	T: DeserializeOwned + IntoParams,
+{
+	let valid: Vec<String> = T::into_params(|| Some(ParameterIn::Query))
+		.into_iter()
+		.map(|p| p.name)
+		.collect();
+
+	let mut pairs = vec![];
+	let mut unknown = vec![];
+
+	for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+		if valid.iter().any(|k| *k == key) {
+			pairs.push((key, normalize_flag(&value).map_or(value, Cow::Owned)));
+		} else if valid.iter().any(|k| is_misspelling(&key, k)) {
+			unknown.push(key.into_owned());
+		}
+	}
+
+	if !unknown.is_empty() {
+		return Err(Error::UnknownIncludes { keys: unknown, valid });
+	}
+
+	let query = url::form_urlencoded::Serializer::new(String::new())
+		.extend_pairs(pairs)
+		.finish();
+
+	serde_urlencoded::from_str(&query).map_err(|e| {
+		Error::BadRequest(format!(
+			"invalid includes: {e}, flags are one of true, false, 1, 0, yes \
+			 or no"
+		))
+	})
+}
+
+/// Map any accepted spelling of a flag onto `true` or `false`
+fn normalize_flag(value: &str) -> Option<String> {
+	match value.to_ascii_lowercase().as_str() {
+		"true" | "1" | "yes" => Some("true".to_string()),
+		"false" | "0" | "no" => Some("false".to_string()),
+		_ => None,
+	}
+}
+
+/// Whether `key` is most likely a misspelling of the include `valid`
+///
+/// Case and underscores are ignored, and one edit is allowed for every five
+/// characters of the valid key. This keeps short unrelated parameters like
+/// `lng` from being mistaken for `lang`
+fn is_misspelling(key: &str, valid: &str) -> bool {
+	let normalize = |s: &str| -> Vec<char> {
+		s.chars()
+			.filter(|c| *c != '_')
+			.map(|c| c.to_ascii_lowercase())
+			.collect()
+	};
+
+	let key = normalize(key);
+	let valid = normalize(valid);
+
+	edit_distance(&key, &valid) * 5 <= valid.len()
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+	let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+	for (i, ca) in a.iter().enumerate() {
+		let mut current = vec![i + 1];
+
+		for (j, cb) in b.iter().enumerate() {
+			let substitution = previous[j] + usize::from(ca != cb);
+			let deletion = previous[j + 1] + 1;
+			let insertion = current[j] + 1;
+
+			current.push(substitution.min(deletion).min(insertion));
+		}
+
+		previous = current;
+	}
+
+	previous[b.len()]
+}
@@ -2,8 +2,11 @@ use chrono::NaiveDateTime;
 use db::InstitutionCategory;
 use institution::{
 	Institution,
+	InstitutionCounts,
 	InstitutionIncludes,
 	InstitutionMemberUpdate,
+	InstitutionStatistics,
+	InstitutionUpdate,
 	NewInstitution,
 	NewInstitutionMember,
 };
@@ -14,6 +17,7 @@ use crate::schemas::profile::ProfileResponse;
 use crate::schemas::translation::{
 	CreateTranslationRequest,
 	TranslationResponse,
+	UpdateTranslationRequest,
 };
 use crate::schemas::{BuildResponse, ser_includes};
 
@@ -39,6 +43,8 @@ pub struct InstitutionResponse {
 	pub category:         InstitutionCategory,
 	pub slug:             String,
 	pub authority:        Option<AuthorityResponse>,
+	pub member_count:     Option<i64>,
+	pub location_count:   Option<i64>,
 }
 
 impl BuildResponse<InstitutionResponse> for Institution {
@@ -54,7 +60,10 @@ impl BuildResponse<InstitutionResponse> for Institution {
 
 		Ok(InstitutionResponse {
 			id:               self.primitive.id,
-			name_translation: self.name.into(),
+			name_translation: TranslationResponse::localized(
+				self.name,
+				includes.lang,
+			),
 			email:            self.primitive.email,
 			phone_number:     self.primitive.phone_number,
 			street:           self.primitive.street,
@@ -78,10 +87,35 @@ impl BuildResponse<InstitutionResponse> for Institution {
 			category:         self.primitive.category,
 			slug:             self.primitive.slug,
 			authority:        None,
+			member_count:     None,
+			location_count:   None,
 		})
 	}
 }
 
+impl BuildResponse<InstitutionResponse>
+	for (Institution, Option<InstitutionCounts>)
+{
+	type Includes = InstitutionIncludes;
+
+	fn build_response(
+		self,
+		includes: Self::Includes,
+		config: &crate::Config,
+	) -> Result<InstitutionResponse, common::Error> {
+		let (institution, counts) = self;
+
+		let mut response = institution.build_response(includes, config)?;
+
+		if let Some(counts) = counts {
+			response.member_count = Some(counts.member_count);
+			response.location_count = Some(counts.location_count);
+		}
+
+		Ok(response)
+	}
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateInstitutionRequest {
@@ -127,6 +161,44 @@ impl CreateInstitutionRequest {
 	}
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInstitutionRequest {
+	pub name_translation: Option<UpdateTranslationRequest>,
+	pub email:            Option<String>,
+	pub phone_number:     Option<String>,
+	pub street:           Option<String>,
+	pub number:           Option<String>,
+	pub zip:              Option<String>,
+	pub city:             Option<String>,
+	pub province:         Option<String>,
+	pub country:          Option<String>,
+	pub category:         Option<InstitutionCategory>,
+	pub slug:             Option<String>,
+}
+
+impl UpdateInstitutionRequest {
+	#[must_use]
+	pub fn to_insertable(self, updated_by: i32) -> InstitutionUpdate {
+		InstitutionUpdate {
+			name_translation: self
+				.name_translation
+				.map(|n| n.to_insertable(updated_by)),
+			email: self.email,
+			phone_number: self.phone_number,
+			street: self.street,
+			number: self.number,
+			zip: self.zip,
+			city: self.city,
+			province: self.province,
+			country: self.country,
+			category: self.category,
+			slug: self.slug,
+			updated_by,
+		}
+	}
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateInstitutionMemberRequest {
@@ -153,6 +225,7 @@ impl CreateInstitutionMemberRequest {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InstitutionMemberUpdateRequest {
+	#[serde(alias = "roleId")]
 	pub institution_role_id: Option<i32>,
 }
 
@@ -165,3 +238,25 @@ impl InstitutionMemberUpdateRequest {
 		}
 	}
 }
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstitutionStatisticsResponse {
+	pub authority_count:         i64,
+	pub total_location_count:    i64,
+	pub pending_location_count:  i64,
+	pub total_member_count:      i64,
+	pub total_reservation_count: i64,
+}
+
+impl From<InstitutionStatistics> for InstitutionStatisticsResponse {
+	fn from(value: InstitutionStatistics) -> Self {
+		Self {
+			authority_count:         value.authority_count,
+			total_location_count:    value.total_location_count,
+			pending_location_count:  value.pending_location_count,
+			total_member_count:      value.total_member_count,
+			total_reservation_count: value.total_reservation_count,
+		}
+	}
+}
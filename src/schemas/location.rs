@@ -1,24 +1,45 @@
-use chrono::NaiveDateTime;
-use common::Error;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use axum::extract::Multipart;
+use axum::http::{HeaderMap, header};
+use base::SortDirection;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use common::{Error, MultipartParseError};
 use image::{ImageIncludes, NewLocationImage};
 use location::{
+	Availability,
 	FullLocationData,
+	Location,
+	LocationAvailabilitySummary,
+	LocationDayStats,
+	LocationDeletion,
+	LocationExportRow,
 	LocationIncludes,
 	LocationMemberUpdate,
+	LocationMerge,
+	LocationStats,
 	LocationUpdate,
+	MergedRecords,
 	NewLocation,
 	NewLocationMember,
+	Point,
 };
 use opening_time::OpeningTimeIncludes;
 use primitives::PrimitiveLocation;
 use serde::{Deserialize, Serialize};
 use tag::TagIncludes;
+use translation::Language;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
 use validator_derive::Validate;
 
 use crate::Config;
 use crate::schemas::authority::AuthorityResponse;
-use crate::schemas::image::ImageResponse;
+use crate::schemas::image::{CreateImageRequest, ImageResponse};
 use crate::schemas::opening_time::OpeningTimeResponse;
+use crate::schemas::pagination::PaginatedResponse;
 use crate::schemas::profile::ProfileResponse;
 use crate::schemas::tag::TagResponse;
 use crate::schemas::translation::{
@@ -28,7 +49,7 @@ use crate::schemas::translation::{
 use crate::schemas::{BuildResponse, ser_includes};
 
 #[skip_serializing_none]
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct NearestLocationResponse {
 	pub id:        i32,
@@ -42,18 +63,78 @@ impl From<(i32, f64, f64)> for NearestLocationResponse {
 	}
 }
 
+const fn nearby_limit_default() -> usize { 10 }
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct NearbyLocationsOptions {
+	pub lat:   f64,
+	pub lng:   f64,
+	#[serde(default = "nearby_limit_default")]
+	pub limit: usize,
+}
+
+impl From<NearbyLocationsOptions> for Point {
+	fn from(value: NearbyLocationsOptions) -> Self {
+		Self { center_lat: value.lat, center_lng: value.lng }
+	}
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationAvailabilityOptions {
+	pub at: NaiveDateTime,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationAvailabilityResponse {
+	pub available:       bool,
+	pub opening_time_id: Option<i32>,
+}
+
+impl From<Availability> for LocationAvailabilityResponse {
+	fn from(value: Availability) -> Self {
+		Self {
+			available:       value.available,
+			opening_time_id: value.opening_time_id,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationAvailabilitySummaryResponse {
+	pub next_date:       Option<NaiveDate>,
+	pub next_start_time: Option<NaiveTime>,
+	pub free_blocks:     i32,
+}
+
+impl From<LocationAvailabilitySummary> for LocationAvailabilitySummaryResponse {
+	fn from(value: LocationAvailabilitySummary) -> Self {
+		Self {
+			next_date:       value.next_date,
+			next_start_time: value.next_start_time,
+			free_blocks:     value.free_blocks,
+		}
+	}
+}
+
 #[skip_serializing_none]
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct LocationResponse {
 	pub id:                     i32,
+	pub public_id:              Uuid,
 	pub name:                   String,
+	#[schema(value_type = Option<AuthorityResponse>)]
 	#[serde(serialize_with = "ser_includes")]
 	pub authority:              Option<Option<AuthorityResponse>>,
 	pub description:            Option<TranslationResponse>,
 	pub excerpt:                Option<TranslationResponse>,
 	pub seat_count:             i32,
 	pub is_reservable:          bool,
+	/// The longest a single reservation can last, in minutes
 	pub max_reservation_length: Option<i32>,
 	pub is_visible:             bool,
 	pub street:                 String,
@@ -65,28 +146,41 @@ pub struct LocationResponse {
 	pub latitude:               f64,
 	pub longitude:              f64,
 	pub approved_at:            Option<NaiveDateTime>,
+	#[schema(value_type = Option<ProfileResponse>)]
 	#[serde(serialize_with = "ser_includes")]
 	pub approved_by:            Option<Option<ProfileResponse>>,
 	pub rejected_at:            Option<NaiveDateTime>,
+	#[schema(value_type = Option<ProfileResponse>)]
 	#[serde(serialize_with = "ser_includes")]
 	pub rejected_by:            Option<Option<ProfileResponse>>,
 	pub rejected_reason:        Option<String>,
 	pub created_at:             NaiveDateTime,
+	#[schema(value_type = Option<ProfileResponse>)]
 	#[serde(serialize_with = "ser_includes")]
 	pub created_by:             Option<Option<ProfileResponse>>,
 	pub updated_at:             NaiveDateTime,
+	#[schema(value_type = Option<ProfileResponse>)]
 	#[serde(serialize_with = "ser_includes")]
 	pub updated_by:             Option<Option<ProfileResponse>>,
 
-	pub images:        Vec<ImageResponse>,
-	pub opening_times: Vec<OpeningTimeResponse>,
-	pub tags:          Vec<TagResponse>,
+	pub images:               Vec<ImageResponse>,
+	pub opening_times:        Vec<OpeningTimeResponse>,
+	pub tags:                 Vec<TagResponse>,
+	pub availability_summary: Option<LocationAvailabilitySummaryResponse>,
+	pub distance_km:          Option<f64>,
+	pub pending_image_count:  Option<i64>,
+	#[schema(required = true)]
+	#[serialize_always]
+	pub average_rating:       Option<f64>,
+	pub review_count:         i64,
+	pub favourite_count:      Option<i64>,
 }
 
 impl From<PrimitiveLocation> for LocationResponse {
 	fn from(value: PrimitiveLocation) -> Self {
 		Self {
 			id:                     value.id,
+			public_id:              value.public_id,
 			name:                   value.name,
 			authority:              None,
 			description:            None,
@@ -113,22 +207,28 @@ impl From<PrimitiveLocation> for LocationResponse {
 			updated_at:             value.updated_at,
 			updated_by:             None,
 
-			opening_times: vec![],
-			tags:          vec![],
-			images:        vec![],
+			opening_times:        vec![],
+			tags:                 vec![],
+			images:               vec![],
+			availability_summary: None,
+			distance_km:          None,
+			pending_image_count:  None,
+			average_rating:       None,
+			review_count:         0,
+			favourite_count:      None,
 		}
 	}
 }
 
-impl BuildResponse<LocationResponse> for FullLocationData {
+impl BuildResponse<LocationResponse> for Location {
 	type Includes = LocationIncludes;
 
 	fn build_response(
 		self,
 		includes: Self::Includes,
-		config: &Config,
+		_config: &Config,
 	) -> Result<LocationResponse, Error> {
-		let (location, (opening_times, tags, images)) = self;
+		let location = self;
 
 		let authority = location.authority.map(Into::into);
 		let approved_by = location.approved_by.map(Into::into);
@@ -138,14 +238,21 @@ impl BuildResponse<LocationResponse> for FullLocationData {
 
 		Ok(LocationResponse {
 			id:                     location.primitive.id,
+			public_id:              location.primitive.public_id,
 			name:                   location.primitive.name,
 			authority:              if includes.authority {
 				Some(authority)
 			} else {
 				None
 			},
-			description:            Some(location.description.into()),
-			excerpt:                Some(location.excerpt.into()),
+			description:            Some(TranslationResponse::localized(
+				location.description,
+				includes.lang,
+			)),
+			excerpt:                Some(TranslationResponse::localized(
+				location.excerpt,
+				includes.lang,
+			)),
 			seat_count:             location.primitive.seat_count,
 			is_reservable:          location.primitive.is_reservable,
 			max_reservation_length: location.primitive.max_reservation_length,
@@ -184,24 +291,381 @@ impl BuildResponse<LocationResponse> for FullLocationData {
 				None
 			},
 
-			opening_times: opening_times
-				.into_iter()
-				.map(|t| {
-					t.build_response(OpeningTimeIncludes::default(), config)
-				})
-				.collect::<Result<_, _>>()?,
-			tags:          tags
-				.into_iter()
-				.map(|t| t.build_response(TagIncludes::default(), config))
-				.collect::<Result<_, _>>()?,
-			images:        images
-				.into_iter()
-				.map(|i| i.build_response(ImageIncludes::default(), config))
-				.collect::<Result<_, _>>()?,
+			opening_times:        vec![],
+			tags:                 vec![],
+			images:               vec![],
+			availability_summary: None,
+			distance_km:          None,
+			pending_image_count:  Some(location.pending_image_count),
+			average_rating:       None,
+			review_count:         0,
+			favourite_count:      None,
+		})
+	}
+}
+
+impl BuildResponse<LocationResponse> for FullLocationData {
+	type Includes = LocationIncludes;
+
+	fn build_response(
+		self,
+		includes: Self::Includes,
+		config: &Config,
+	) -> Result<LocationResponse, Error> {
+		let (location, (opening_times, tags, images, rating)) = self;
+
+		let mut response = location.build_response(includes, config)?;
+
+		(response.average_rating, response.review_count) = rating;
+
+		response.opening_times = opening_times
+			.into_iter()
+			.map(|t| t.build_response(OpeningTimeIncludes::default(), config))
+			.collect::<Result<_, _>>()?;
+		let tag_includes =
+			TagIncludes { lang: includes.lang, ..TagIncludes::default() };
+
+		response.tags = tags
+			.into_iter()
+			.map(|t| t.build_response(tag_includes, config))
+			.collect::<Result<_, _>>()?;
+		response.images = images
+			.into_iter()
+			.map(|i| i.build_response(ImageIncludes::default(), config))
+			.collect::<Result<_, _>>()?;
+
+		Ok(response)
+	}
+}
+
+/// Media type of GeoJSON documents
+pub const GEOJSON_CONTENT_TYPE: &str = "application/geo+json";
+
+/// The formats location search results can be returned in
+#[derive(
+	Clone,
+	Copy,
+	Debug,
+	Default,
+	Deserialize,
+	PartialEq,
+	Eq,
+	Serialize,
+	ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LocationSearchFormat {
+	#[default]
+	Json,
+	GeoJson,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationSearchFormatOptions {
+	#[serde(default)]
+	pub format: LocationSearchFormat,
+}
+
+impl LocationSearchFormatOptions {
+	/// Whether search results should be returned as GeoJSON, either through
+	/// `?format=geojson` or an `Accept: application/geo+json` header
+	#[must_use]
+	pub fn wants_geojson(self, headers: &HeaderMap) -> bool {
+		if self.format == LocationSearchFormat::GeoJson {
+			return true;
+		}
+
+		headers
+			.get_all(header::ACCEPT)
+			.iter()
+			.filter_map(|value| value.to_str().ok())
+			.flat_map(|value| value.split(','))
+			.filter_map(|media| media.split(';').next())
+			.any(|media| media.trim() == GEOJSON_CONTENT_TYPE)
+	}
+}
+
+/// The `type` member of a GeoJSON object
+#[derive(
+	Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize, ToSchema,
+)]
+pub enum GeoJsonType {
+	Point,
+	Feature,
+	FeatureCollection,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema)]
+pub struct GeoJsonPoint {
+	#[serde(rename = "type")]
+	pub kind:        GeoJsonType,
+	/// Longitude first, then latitude, as required by GeoJSON
+	#[schema(value_type = Vec<f64>, min_items = 2, max_items = 2)]
+	pub coordinates: [f64; 2],
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationGeoJsonProperties {
+	pub id:             i32,
+	pub name:           String,
+	/// The excerpt in the requested language, English if none was requested
+	pub excerpt:        Option<String>,
+	pub average_rating: Option<f64>,
+	pub review_count:   i64,
+	/// Whether one of the opening times of the location covers the current
+	/// moment
+	pub is_open:        bool,
+}
+
+/// A location as a GeoJSON `Feature` with a `Point` geometry
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct LocationGeoJsonResponse {
+	#[serde(rename = "type")]
+	pub kind:       GeoJsonType,
+	pub id:         i32,
+	pub geometry:   GeoJsonPoint,
+	pub properties: LocationGeoJsonProperties,
+}
+
+impl BuildResponse<LocationGeoJsonResponse> for FullLocationData {
+	type Includes = LocationIncludes;
+
+	fn build_response(
+		self,
+		includes: Self::Includes,
+		_config: &Config,
+	) -> Result<LocationGeoJsonResponse, Error> {
+		let (location, (opening_times, _, _, rating)) = self;
+		let (average_rating, review_count) = rating;
+
+		let lang = includes.lang.unwrap_or(Language::En);
+		let excerpt = lang.resolve(&location.excerpt).map(ToString::to_string);
+
+		let now = Utc::now().naive_utc();
+		let is_open = opening_times.iter().any(|t| t.primitive.is_open_at(now));
+
+		let primitive = location.primitive;
+
+		Ok(LocationGeoJsonResponse {
+			kind:       GeoJsonType::Feature,
+			id:         primitive.id,
+			geometry:   GeoJsonPoint {
+				kind:        GeoJsonType::Point,
+				coordinates: [primitive.longitude, primitive.latitude],
+			},
+			properties: LocationGeoJsonProperties {
+				id: primitive.id,
+				name: primitive.name,
+				excerpt,
+				average_rating,
+				review_count,
+				is_open,
+			},
 		})
 	}
 }
 
+/// A page of locations as a GeoJSON `FeatureCollection`
+///
+/// The pagination metadata is kept in foreign members next to the features
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationFeatureCollectionResponse {
+	#[serde(rename = "type")]
+	pub kind:      GeoJsonType,
+	pub features:  Vec<LocationGeoJsonResponse>,
+	pub page:      u32,
+	pub per_page:  u32,
+	pub total:     usize,
+	pub truncated: bool,
+}
+
+impl From<PaginatedResponse<Vec<LocationGeoJsonResponse>>>
+	for LocationFeatureCollectionResponse
+{
+	fn from(value: PaginatedResponse<Vec<LocationGeoJsonResponse>>) -> Self {
+		Self {
+			kind:      GeoJsonType::FeatureCollection,
+			features:  value.data,
+			page:      value.page,
+			per_page:  value.per_page,
+			total:     value.total,
+			truncated: value.truncated,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationCountOptions {
+	#[serde(default)]
+	pub direction: SortDirection,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationReservationCountResponse {
+	pub location:          LocationResponse,
+	pub reservation_count: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZipLocationCountResponse {
+	pub zip:            String,
+	pub location_count: i64,
+}
+
+impl From<(String, i64)> for ZipLocationCountResponse {
+	fn from((zip, location_count): (String, i64)) -> Self {
+		Self { zip, location_count }
+	}
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationStatsOptions {
+	pub from: NaiveDate,
+	pub to:   NaiveDate,
+}
+
+/// Fraction of the offered seat minutes that was reserved
+#[allow(clippy::cast_precision_loss)]
+fn occupancy(reserved_minutes: i64, capacity_minutes: i64) -> f64 {
+	if capacity_minutes == 0 {
+		return 0.0;
+	}
+
+	reserved_minutes as f64 / capacity_minutes as f64
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationDayStatsResponse {
+	pub day:               NaiveDate,
+	pub reservation_count: i64,
+	pub cancelled_count:   i64,
+	pub reserved_minutes:  i64,
+	pub capacity_minutes:  i64,
+	pub occupancy:         f64,
+}
+
+impl From<LocationDayStats> for LocationDayStatsResponse {
+	fn from(value: LocationDayStats) -> Self {
+		Self {
+			day:               value.day,
+			reservation_count: value.reservation_count,
+			cancelled_count:   value.cancelled_count,
+			reserved_minutes:  value.reserved_minutes,
+			capacity_minutes:  value.capacity_minutes,
+			occupancy:         occupancy(
+				value.reserved_minutes,
+				value.capacity_minutes,
+			),
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationStatsTotalsResponse {
+	pub reservation_count: i64,
+	pub cancelled_count:   i64,
+	pub reserved_minutes:  i64,
+	pub capacity_minutes:  i64,
+	pub occupancy:         f64,
+	pub unique_visitors:   i64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationStatsResponse {
+	pub from:   NaiveDate,
+	pub to:     NaiveDate,
+	pub days:   Vec<LocationDayStatsResponse>,
+	pub totals: LocationStatsTotalsResponse,
+}
+
+impl From<LocationStats> for LocationStatsResponse {
+	fn from(value: LocationStats) -> Self {
+		let days: Vec<LocationDayStatsResponse> =
+			value.days.into_iter().map(Into::into).collect();
+
+		let reserved_minutes = days.iter().map(|d| d.reserved_minutes).sum();
+		let capacity_minutes = days.iter().map(|d| d.capacity_minutes).sum();
+
+		let totals = LocationStatsTotalsResponse {
+			reservation_count: days.iter().map(|d| d.reservation_count).sum(),
+			cancelled_count: days.iter().map(|d| d.cancelled_count).sum(),
+			reserved_minutes,
+			capacity_minutes,
+			occupancy: occupancy(reserved_minutes, capacity_minutes),
+			unique_visitors: value.unique_visitors,
+		};
+
+		Self { from: value.from, to: value.to, days, totals }
+	}
+}
+
+/// Optional range of days, both inclusive, to total reservations over in a
+/// location export
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationExportOptions {
+	pub from: Option<NaiveDate>,
+	pub to:   Option<NaiveDate>,
+}
+
+/// Header line of a CSV location export
+pub const LOCATION_EXPORT_CSV_HEADER: &str =
+	"id,name,street,number,zip,city,province,country,seat_count,\
+	 approval_state,is_reservable,tags,reservation_count\r\n";
+
+/// Escape a single CSV field
+///
+/// Fields starting with a character a spreadsheet would read as the start of
+/// a formula are prefixed with `'`, fields holding a separator, quote or line
+/// break are wrapped in quotes with any quotes inside doubled
+#[must_use]
+pub fn csv_field(value: &str) -> Cow<'_, str> {
+	let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+		Cow::Owned(format!("'{value}"))
+	} else {
+		Cow::Borrowed(value)
+	};
+
+	if value.contains([',', '"', '\n', '\r']) {
+		Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+	} else {
+		value
+	}
+}
+
+/// Format a [`LocationExportRow`] as a line of a CSV location export, tag
+/// names are joined with `;`
+#[must_use]
+pub fn location_export_csv_line(row: &LocationExportRow) -> String {
+	let fields = [
+		row.id.to_string(),
+		csv_field(&row.name).into_owned(),
+		csv_field(&row.street).into_owned(),
+		csv_field(&row.number).into_owned(),
+		csv_field(&row.zip).into_owned(),
+		csv_field(&row.city).into_owned(),
+		csv_field(&row.province).into_owned(),
+		csv_field(&row.country).into_owned(),
+		row.seat_count.to_string(),
+		row.approval_state.clone(),
+		row.is_reservable.to_string(),
+		csv_field(&row.tag_names.join(";")).into_owned(),
+		row.reservation_count.to_string(),
+	];
+
+	format!("{}\r\n", fields.join(","))
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LocationImageOrderUpdate {
@@ -220,7 +684,7 @@ impl LocationImageOrderUpdate {
 	}
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateLocationRequest {
 	pub name:                   String,
@@ -229,6 +693,7 @@ pub struct CreateLocationRequest {
 	pub seat_count:             i32,
 	pub is_reservable:          bool,
 	pub is_visible:             bool,
+	/// The longest a single reservation can last, in minutes
 	pub max_reservation_length: Option<i32>,
 	pub street:                 String,
 	pub number:                 String,
@@ -293,6 +758,120 @@ impl CreateLocationRequest {
 	}
 }
 
+/// A [`CreateLocationRequest`] submitted as `multipart/form-data`, together
+/// with any number of image files
+#[derive(Clone, Debug)]
+pub struct CreateLocationMultipartRequest {
+	pub location: CreateLocationRequest,
+	pub images:   Vec<CreateImageRequest>,
+}
+
+impl CreateLocationMultipartRequest {
+	pub async fn parse(multipart: &mut Multipart) -> Result<Self, Error> {
+		let mut fields = FormFields::default();
+		let mut images = vec![];
+
+		while let Some(field) = multipart.next_field().await? {
+			let Some(name) = field.name() else {
+				return Err(MultipartParseError::NamelessField.into());
+			};
+
+			let name = name.to_string();
+
+			if name == "images" || name == "images[]" {
+				let bytes = field.bytes().await?;
+
+				images.push(CreateImageRequest::Image(bytes));
+			} else {
+				let text = field.text().await?;
+
+				fields.0.insert(name, text);
+			}
+		}
+
+		let location = CreateLocationRequest {
+			name:                   fields.required("name")?,
+			description:            fields.translation("description"),
+			excerpt:                fields.translation("excerpt"),
+			seat_count:             fields.parse("seatCount", "number")?,
+			is_reservable:          fields.parse("isReservable", "boolean")?,
+			is_visible:             fields.parse("isVisible", "boolean")?,
+			max_reservation_length: fields
+				.parse_optional("maxReservationLength", "number")?,
+			street:                 fields.required("street")?,
+			number:                 fields.required("number")?,
+			zip:                    fields.required("zip")?,
+			city:                   fields.required("city")?,
+			province:               fields.required("province")?,
+			country:                fields.required("country")?,
+			latitude:               fields.parse("latitude", "number")?,
+			longitude:              fields.parse("longitude", "number")?,
+		};
+
+		// Every known field has been taken out by now
+		if let Some(field_name) = fields.0.into_keys().next() {
+			return Err(MultipartParseError::UnknownField { field_name }.into());
+		}
+
+		Ok(Self { location, images })
+	}
+}
+
+/// The text fields of a `multipart/form-data` request
+#[derive(Debug, Default)]
+struct FormFields(HashMap<String, String>);
+
+impl FormFields {
+	fn optional(&mut self, name: &str) -> Option<String> { self.0.remove(name) }
+
+	fn required(&mut self, name: &str) -> Result<String, MultipartParseError> {
+		self.optional(name).ok_or_else(|| {
+			MultipartParseError::MissingField {
+				expected_field: name.to_string(),
+			}
+		})
+	}
+
+	fn parse_optional<T: FromStr>(
+		&mut self,
+		name: &str,
+		expected_ty: &str,
+	) -> Result<Option<T>, MultipartParseError> {
+		self.optional(name)
+			.map(|value| {
+				value.parse().map_err(|_| {
+					MultipartParseError::WrongFieldType {
+						field_name:  name.to_string(),
+						expected_ty: expected_ty.to_string(),
+					}
+				})
+			})
+			.transpose()
+	}
+
+	fn parse<T: FromStr>(
+		&mut self,
+		name: &str,
+		expected_ty: &str,
+	) -> Result<T, MultipartParseError> {
+		self.parse_optional(name, expected_ty)?.ok_or_else(|| {
+			MultipartParseError::MissingField {
+				expected_field: name.to_string(),
+			}
+		})
+	}
+
+	/// Collect the `{prefix}Nl`, `{prefix}En`, ... fields into a translation
+	fn translation(&mut self, prefix: &str) -> CreateTranslationRequest {
+		CreateTranslationRequest {
+			nl: self.optional(&format!("{prefix}Nl")),
+			en: self.optional(&format!("{prefix}En")),
+			fr: self.optional(&format!("{prefix}Fr")),
+			de: self.optional(&format!("{prefix}De")),
+		}
+	}
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateLocationMemberRequest {
@@ -319,6 +898,7 @@ impl CreateLocationMemberRequest {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LocationMemberUpdateRequest {
+	#[serde(alias = "roleId")]
 	pub location_role_id: Option<i32>,
 }
 
@@ -332,7 +912,7 @@ impl LocationMemberUpdateRequest {
 	}
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateLocationRequest {
 	pub name:          Option<String>,
@@ -368,7 +948,90 @@ impl UpdateLocationRequest {
 	}
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 pub struct RejectLocationRequest {
 	pub reason: Option<String>,
 }
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationDeletionResponse {
+	pub opening_times: i64,
+	pub reservations:  i64,
+	pub images:        i64,
+	pub tags:          i64,
+	pub members:       i64,
+	pub roles:         i64,
+	pub reviews:       i64,
+	pub translations:  i64,
+}
+
+impl From<&LocationDeletion> for LocationDeletionResponse {
+	fn from(value: &LocationDeletion) -> Self {
+		Self {
+			opening_times: value.opening_times,
+			reservations:  value.reservations,
+			images:        value.images,
+			tags:          value.tags,
+			members:       value.members,
+			roles:         value.roles,
+			reviews:       value.reviews,
+			translations:  value.translations,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeLocationRequest {
+	/// The duplicate location that is merged into the target and deleted
+	pub source_id: i32,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MergedRecordsResponse {
+	pub opening_times: i64,
+	pub reservations:  i64,
+	pub reviews:       i64,
+	pub images:        i64,
+	pub tags:          i64,
+	pub members:       i64,
+	pub favourites:    i64,
+}
+
+impl From<MergedRecords> for MergedRecordsResponse {
+	fn from(value: MergedRecords) -> Self {
+		Self {
+			opening_times: value.opening_times,
+			reservations:  value.reservations,
+			reviews:       value.reviews,
+			images:        value.images,
+			tags:          value.tags,
+			members:       value.members,
+			favourites:    value.favourites,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationMergeResponse {
+	pub source_id:    i32,
+	pub target_id:    i32,
+	pub moved:        MergedRecordsResponse,
+	pub skipped:      MergedRecordsResponse,
+	pub translations: i64,
+}
+
+impl From<&LocationMerge> for LocationMergeResponse {
+	fn from(value: &LocationMerge) -> Self {
+		Self {
+			source_id:    value.source_id,
+			target_id:    value.target_id,
+			moved:        value.moved.into(),
+			skipped:      value.skipped.into(),
+			translations: value.translations,
+		}
+	}
+}
@@ -2,19 +2,25 @@ use serde::de::Visitor;
 
 use crate::Config;
 
+pub mod audit_log;
 pub mod auth;
 pub mod authority;
+pub mod health;
 pub mod image;
+pub mod includes;
 pub mod institution;
 pub mod location;
 pub mod opening_time;
 pub mod pagination;
+pub mod permissions;
 pub mod profile;
 pub mod reservation;
 pub mod review;
 pub mod role;
+pub mod session;
 pub mod tag;
 pub mod translation;
+pub mod webhook;
 
 pub trait BuildResponse<R> {
 	type Includes;
@@ -58,6 +64,9 @@ impl Visitor<'_> for BoundedU32Visitor {
 
 /// Serialize an `Option<Option<T>>` value.
 /// Used for dynamic relationship includes in the API.
+///
+/// These fields are described as `#[schema(value_type = Option<T>)]` in the
+/// OpenAPI document, meaning they can be left out as well as be `null`.
 pub fn ser_includes<S, T>(
 	value: &Option<Option<T>>,
 	serializer: S,
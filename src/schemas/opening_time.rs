@@ -1,34 +1,49 @@
-use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use base::RESERVATION_BLOCK_SIZE_MINUTES;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Weekday};
 use opening_time::{
 	NewOpeningTime,
+	NewOpeningTimeSeries,
 	OpeningTime,
 	OpeningTimeIncludes,
 	OpeningTimeUpdate,
+	OpeningTimeWithAvailability,
 };
-use primitives::PrimitiveOpeningTime;
+use primitives::{PrimitiveOpeningTime, PrimitiveOpeningTimeException};
+use reservation::{BlockAvailability, OpeningTimeBlocks};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator_derive::Validate;
 
 use crate::schemas::profile::ProfileResponse;
 use crate::schemas::{BuildResponse, ser_includes};
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct OpeningTimeResponse {
-	pub id:               i32,
-	pub day:              NaiveDate,
-	pub start_time:       NaiveTime,
-	pub end_time:         NaiveTime,
+	pub id:                     i32,
+	pub day:                    NaiveDate,
+	pub start_time:             NaiveTime,
+	pub end_time:               NaiveTime,
 	#[serde(skip_serializing_if = "Option::is_none")]
-	pub seat_occupancy:   Option<i32>,
-	pub seat_count:       Option<i32>,
-	pub reservable_from:  Option<NaiveDateTime>,
-	pub reservable_until: Option<NaiveDateTime>,
-	pub created_at:       NaiveDateTime,
+	pub seat_occupancy:         Option<i32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub booked_blocks:          Option<i64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub total_blocks:           Option<i64>,
+	pub seat_count:             Option<i32>,
+	pub reservable_from:        Option<NaiveDateTime>,
+	pub reservable_until:       Option<NaiveDateTime>,
+	pub created_at:             NaiveDateTime,
+	#[schema(value_type = Option<ProfileResponse>, required = true)]
 	#[serde(serialize_with = "ser_includes")]
-	pub created_by:       Option<Option<ProfileResponse>>,
-	pub updated_at:       NaiveDateTime,
+	pub created_by:             Option<Option<ProfileResponse>>,
+	pub updated_at:             NaiveDateTime,
+	#[schema(value_type = Option<ProfileResponse>, required = true)]
 	#[serde(serialize_with = "ser_includes")]
-	pub updated_by:       Option<Option<ProfileResponse>>,
+	pub updated_by:             Option<Option<ProfileResponse>>,
+	/// The amount of reservations cancelled by an update
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub cancelled_reservations: Option<usize>,
 }
 
 impl BuildResponse<OpeningTimeResponse> for OpeningTime {
@@ -43,26 +58,29 @@ impl BuildResponse<OpeningTimeResponse> for OpeningTime {
 		let updated_by = self.updated_by.map(Into::into);
 
 		Ok(OpeningTimeResponse {
-			id:               self.primitive.id,
-			day:              self.primitive.day,
-			start_time:       self.primitive.start_time,
-			end_time:         self.primitive.end_time,
-			seat_occupancy:   None,
-			seat_count:       self.primitive.seat_count,
-			reservable_from:  self.primitive.reservable_from,
-			reservable_until: self.primitive.reservable_until,
-			created_at:       self.primitive.created_at,
-			created_by:       if includes.created_by {
+			id:                     self.primitive.id,
+			day:                    self.primitive.day,
+			start_time:             self.primitive.start_time,
+			end_time:               self.primitive.end_time,
+			seat_occupancy:         None,
+			booked_blocks:          None,
+			total_blocks:           None,
+			seat_count:             self.primitive.seat_count,
+			reservable_from:        self.primitive.reservable_from,
+			reservable_until:       self.primitive.reservable_until,
+			created_at:             self.primitive.created_at,
+			created_by:             if includes.created_by {
 				Some(created_by)
 			} else {
 				None
 			},
-			updated_at:       self.primitive.updated_at,
-			updated_by:       if includes.updated_by {
+			updated_at:             self.primitive.updated_at,
+			updated_by:             if includes.updated_by {
 				Some(updated_by)
 			} else {
 				None
 			},
+			cancelled_reservations: None,
 		})
 	}
 }
@@ -70,23 +88,26 @@ impl BuildResponse<OpeningTimeResponse> for OpeningTime {
 impl From<PrimitiveOpeningTime> for OpeningTimeResponse {
 	fn from(value: PrimitiveOpeningTime) -> Self {
 		Self {
-			id:               value.id,
-			seat_occupancy:   None,
-			day:              value.day,
-			start_time:       value.start_time,
-			end_time:         value.end_time,
-			seat_count:       value.seat_count,
-			reservable_from:  value.reservable_from,
-			reservable_until: value.reservable_until,
-			created_at:       value.created_at,
-			created_by:       None,
-			updated_at:       value.updated_at,
-			updated_by:       None,
+			id:                     value.id,
+			seat_occupancy:         None,
+			booked_blocks:          None,
+			total_blocks:           None,
+			day:                    value.day,
+			start_time:             value.start_time,
+			end_time:               value.end_time,
+			seat_count:             value.seat_count,
+			reservable_from:        value.reservable_from,
+			reservable_until:       value.reservable_until,
+			created_at:             value.created_at,
+			created_by:             None,
+			updated_at:             value.updated_at,
+			updated_by:             None,
+			cancelled_reservations: None,
 		}
 	}
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateOpeningTimeRequest {
 	pub day:              NaiveDate,
@@ -117,7 +138,43 @@ impl CreateOpeningTimeRequest {
 	}
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateOpeningTimeSeriesRequest {
+	pub start_date:       NaiveDate,
+	pub end_date:         NaiveDate,
+	#[schema(value_type = Vec<String>, example = json!(["Mon", "Wed"]))]
+	pub weekdays:         Vec<Weekday>,
+	pub start_time:       NaiveTime,
+	pub end_time:         NaiveTime,
+	pub seat_count:       Option<i32>,
+	pub reservable_from:  Option<NaiveDateTime>,
+	pub reservable_until: Option<NaiveDateTime>,
+}
+
+impl CreateOpeningTimeSeriesRequest {
+	#[must_use]
+	pub fn to_insertable(
+		self,
+		location_id: i32,
+		created_by: i32,
+	) -> NewOpeningTimeSeries {
+		NewOpeningTimeSeries {
+			location_id,
+			start_date: self.start_date,
+			end_date: self.end_date,
+			weekdays: NewOpeningTimeSeries::weekday_mask(&self.weekdays),
+			start_time: self.start_time,
+			end_time: self.end_time,
+			seat_count: self.seat_count,
+			reservable_from: self.reservable_from,
+			reservable_until: self.reservable_until,
+			created_by,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateOpeningTimeRequest {
 	pub day:              Option<NaiveDate>,
@@ -142,3 +199,139 @@ impl UpdateOpeningTimeRequest {
 		}
 	}
 }
+
+#[derive(Clone, Debug, Deserialize, Serialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct HolidayExceptionOptions {
+	pub country: String,
+	pub year:    i32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HolidayExceptionResponse {
+	pub date:         NaiveDate,
+	pub holiday_name: String,
+}
+
+impl From<(NaiveDate, PrimitiveOpeningTimeException)>
+	for HolidayExceptionResponse
+{
+	fn from(value: (NaiveDate, PrimitiveOpeningTimeException)) -> Self {
+		Self { date: value.0, holiday_name: value.1.reason }
+	}
+}
+
+/// Exceptions recorded for public holidays, opening times that already had
+/// an exception are left out
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedHolidayExceptionsResponse {
+	pub created_exceptions: Vec<HolidayExceptionResponse>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletedOpeningTimeResponse {
+	pub cancelled_reservations: usize,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct NextAvailableOpeningTimesRequest {
+	#[validate(length(
+		max = 100,
+		message = "at most 100 locations can be requested at once",
+		code = "location-ids-length"
+	))]
+	pub location_ids: Vec<i32>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NextAvailableOpeningTimeResponse {
+	pub opening_time: OpeningTimeResponse,
+	pub free_blocks:  i64,
+}
+
+impl From<OpeningTimeWithAvailability> for NextAvailableOpeningTimeResponse {
+	fn from(value: OpeningTimeWithAvailability) -> Self {
+		Self {
+			opening_time: value.opening_time.into(),
+			free_blocks:  value.free_blocks,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OpeningTimeAvailabilityResponse {
+	pub opening_time:  OpeningTimeResponse,
+	pub booked_blocks: i64,
+	pub total_blocks:  i64,
+}
+
+impl BuildResponse<OpeningTimeAvailabilityResponse> for (OpeningTime, i64) {
+	type Includes = OpeningTimeIncludes;
+
+	fn build_response(
+		self,
+		includes: Self::Includes,
+		config: &crate::Config,
+	) -> Result<OpeningTimeAvailabilityResponse, common::Error> {
+		let (time, booked_blocks) = self;
+
+		let total_blocks =
+			time.primitive.total_blocks(RESERVATION_BLOCK_SIZE_MINUTES);
+		let opening_time = time.build_response(includes, config)?;
+
+		Ok(OpeningTimeAvailabilityResponse {
+			opening_time,
+			booked_blocks,
+			total_blocks,
+		})
+	}
+}
+
+impl From<OpeningTimeAvailabilityResponse> for OpeningTimeResponse {
+	fn from(value: OpeningTimeAvailabilityResponse) -> Self {
+		Self {
+			booked_blocks: Some(value.booked_blocks),
+			total_blocks: Some(value.total_blocks),
+			..value.opening_time
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockAvailabilityResponse {
+	pub block_index: i32,
+	pub available:   bool,
+}
+
+impl From<BlockAvailability> for BlockAvailabilityResponse {
+	fn from(value: BlockAvailability) -> Self {
+		Self { block_index: value.block_index, available: value.available }
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OpeningTimeBlocksResponse {
+	pub block_size:   i32,
+	pub total_blocks: i32,
+	pub seat_count:   i32,
+	pub blocks:       Vec<BlockAvailabilityResponse>,
+}
+
+impl From<OpeningTimeBlocks> for OpeningTimeBlocksResponse {
+	fn from(value: OpeningTimeBlocks) -> Self {
+		Self {
+			block_size:   value.block_size,
+			total_blocks: value.total_blocks,
+			seat_count:   value.seat_count,
+			blocks:       value.blocks.into_iter().map(Into::into).collect(),
+		}
+	}
+}
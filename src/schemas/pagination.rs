@@ -1,5 +1,6 @@
 use base::PaginationConfig;
 use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::schemas::BoundedU32Visitor;
 
@@ -8,7 +9,7 @@ const fn page_default() -> u32 { 1 }
 const fn per_page_default() -> u32 { 12 }
 
 /// Pagination request parameters.
-#[derive(Clone, Copy, Debug, Deserialize)]
+#[derive(Clone, Copy, Debug, Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
 pub struct PaginationOptions {
 	#[serde(default = "page_default", deserialize_with = "ds_page_bounds")]
@@ -18,26 +19,36 @@ pub struct PaginationOptions {
 		deserialize_with = "ds_per_page_bounds"
 	)]
 	pub per_page: u32,
+	/// Cursor returned by a previous page, takes precedence over `page` on
+	/// endpoints that support it
+	pub after_id: Option<i32>,
 }
 
 impl From<PaginationOptions> for PaginationConfig {
 	fn from(value: PaginationOptions) -> Self {
-		Self { limit: value.limit(), offset: value.offset() }
+		Self {
+			limit:    value.limit(),
+			offset:   value.offset(),
+			after_id: value.after_id,
+		}
 	}
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PaginatedResponse<T> {
-	pub page:      u32,
-	pub per_page:  u32,
-	pub total:     usize,
-	pub truncated: bool,
-	pub data:      T,
+	pub page:        u32,
+	pub per_page:    u32,
+	pub total:       usize,
+	pub truncated:   bool,
+	/// Cursor to pass as `afterId` to get the next page, only set by
+	/// endpoints that support cursors and if this page is full
+	pub next_cursor: Option<i32>,
+	pub data:        T,
 }
 
 impl Default for PaginationOptions {
-	fn default() -> Self { Self { page: 1, per_page: 12 } }
+	fn default() -> Self { Self { page: 1, per_page: 12, after_id: None } }
 }
 
 impl PaginationOptions {
@@ -54,10 +65,32 @@ impl PaginationOptions {
 			per_page: self.per_page,
 			total,
 			truncated,
+			next_cursor: None,
 			data,
 		}
 	}
 
+	/// Create a new [`Paginated`] struct like [`Self::paginate`], with a
+	/// cursor pointing to the last item if this page is full
+	pub fn paginate_with_cursor<T>(
+		&self,
+		total: usize,
+		truncated: bool,
+		data: Vec<T>,
+		cursor: impl Fn(&T) -> i32,
+	) -> PaginatedResponse<Vec<T>> {
+		let next_cursor = if data.len() == self.limit() {
+			data.last().map(cursor)
+		} else {
+			None
+		};
+
+		PaginatedResponse {
+			next_cursor,
+			..self.paginate(total, truncated, data)
+		}
+	}
+
 	/// Calculate the SQL LIMIT value of these parameters
 	#[inline]
 	#[must_use]
@@ -0,0 +1,145 @@
+use permissions::{
+	AuthorityPermissions,
+	EffectivePermissions,
+	InstitutionPermissions,
+	LocationPermissions,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// The names of all permissions held on any level
+fn effective_labels(perms: &EffectivePermissions) -> Vec<String> {
+	let mut labels = perms.institution.permissions_labels();
+
+	labels.extend(perms.authority.permissions_labels());
+	labels.extend(perms.location.permissions_labels());
+
+	labels
+}
+
+/// The permissions of the current profile for a location
+///
+/// The derived flags follow the checks of the matching endpoints, global
+/// admins can do more than these flags tell
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationPermissionsResponse {
+	pub institution_permissions:  i64,
+	pub authority_permissions:    i64,
+	pub location_permissions:     i64,
+	pub permissions_labels:       Vec<String>,
+	pub can_edit:                 bool,
+	pub can_approve:              bool,
+	pub can_manage_members:       bool,
+	pub can_manage_images:        bool,
+	pub can_confirm_reservations: bool,
+}
+
+impl From<EffectivePermissions> for LocationPermissionsResponse {
+	fn from(value: EffectivePermissions) -> Self {
+		let inst_admin = InstitutionPermissions::Administrator;
+		let auth_admin = AuthorityPermissions::Administrator;
+		let loc_admin = LocationPermissions::Administrator;
+
+		Self {
+			institution_permissions:  value.institution.bits(),
+			authority_permissions:    value.authority.bits(),
+			location_permissions:     value.location.bits(),
+			permissions_labels:       effective_labels(&value),
+			can_edit:                 value.is_administrator(),
+			can_approve:              value.intersects(
+				LocationPermissions::empty(),
+				AuthorityPermissions::ApproveLocations | auth_admin,
+				inst_admin,
+			),
+			can_manage_members:       value.intersects(
+				LocationPermissions::ManageMembers | loc_admin,
+				auth_admin,
+				inst_admin,
+			),
+			can_manage_images:        value.intersects(
+				LocationPermissions::ManageImages | loc_admin,
+				auth_admin,
+				inst_admin,
+			),
+			can_confirm_reservations: value.intersects(
+				LocationPermissions::ConfirmReservations | loc_admin,
+				auth_admin,
+				inst_admin,
+			),
+		}
+	}
+}
+
+/// The permissions of the current profile for an authority
+///
+/// The derived flags follow the checks of the matching endpoints, global
+/// admins can do more than these flags tell
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorityPermissionsResponse {
+	pub institution_permissions: i64,
+	pub authority_permissions:   i64,
+	pub permissions_labels:      Vec<String>,
+	pub can_edit:                bool,
+	pub can_add_locations:       bool,
+	pub can_approve:             bool,
+	pub can_delete_locations:    bool,
+	pub can_manage_members:      bool,
+}
+
+impl From<EffectivePermissions> for AuthorityPermissionsResponse {
+	fn from(value: EffectivePermissions) -> Self {
+		let inst_admin = InstitutionPermissions::Administrator;
+		let auth_admin = AuthorityPermissions::Administrator;
+		let can = |perms: AuthorityPermissions| {
+			value.intersects(
+				LocationPermissions::empty(),
+				perms | auth_admin,
+				inst_admin,
+			)
+		};
+
+		Self {
+			institution_permissions: value.institution.bits(),
+			authority_permissions:   value.authority.bits(),
+			permissions_labels:      effective_labels(&value),
+			can_edit:                value.is_administrator(),
+			can_add_locations:       can(AuthorityPermissions::AddLocations),
+			can_approve:             can(
+				AuthorityPermissions::ApproveLocations,
+			),
+			can_delete_locations:    can(AuthorityPermissions::DeleteLocations),
+			can_manage_members:      can(AuthorityPermissions::ManageMembers),
+		}
+	}
+}
+
+/// The permissions of the current profile for an institution
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InstitutionPermissionsResponse {
+	pub institution_permissions: i64,
+	pub permissions_labels:      Vec<String>,
+	pub can_edit:                bool,
+	pub can_add_authorities:     bool,
+	pub can_manage_members:      bool,
+}
+
+impl From<EffectivePermissions> for InstitutionPermissionsResponse {
+	fn from(value: EffectivePermissions) -> Self {
+		let inst_admin = InstitutionPermissions::Administrator;
+
+		Self {
+			institution_permissions: value.institution.bits(),
+			permissions_labels:      effective_labels(&value),
+			can_edit:                value.is_administrator(),
+			can_add_authorities:     value
+				.institution
+				.intersects(InstitutionPermissions::AddAuthority | inst_admin),
+			can_manage_members:      value
+				.institution
+				.intersects(InstitutionPermissions::ManageMembers | inst_admin),
+		}
+	}
+}
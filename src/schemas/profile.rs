@@ -3,12 +3,14 @@ use common::Error;
 use primitives::PrimitiveProfile;
 use profile::{Profile, ProfileStats, UpdateProfile};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator_derive::Validate;
 
 use crate::Config;
 use crate::schemas::BuildResponse;
 use crate::schemas::image::ImageResponse;
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProfileResponse {
 	pub id:            i32,
@@ -63,8 +65,44 @@ impl BuildResponse<ProfileResponse> for Profile {
 	}
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingVerificationResponse {
+	pub profile:          ProfileResponse,
+	pub pending_email:    String,
+	pub token_expires_at: NaiveDateTime,
+}
+
+impl PendingVerificationResponse {
+	/// Build a [`PendingVerificationResponse`] for a profile that is still
+	/// awaiting email verification
+	pub fn from_profile(
+		profile: Profile,
+		config: &Config,
+	) -> Result<Self, Error> {
+		let pending_email =
+			profile.primitive.pending_email.clone().unwrap_or_default();
+		let token_expires_at = profile
+			.primitive
+			.email_confirmation_token_expiry
+			.unwrap_or_default();
+
+		Ok(Self {
+			profile: profile.build_response((), config)?,
+			pending_email,
+			token_expires_at,
+		})
+	}
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
+pub struct CleanupPendingResponse {
+	pub deleted_profiles: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct UpdateProfileRequest {
 	pub username:      Option<String>,
 	pub first_name:    Option<String>,
@@ -83,12 +121,17 @@ impl From<UpdateProfileRequest> for UpdateProfile {
 	}
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProfileStatsResponse {
 	pub total_reservations:      usize,
+	/// Reservations that ended and were not cancelled
 	pub completed_reservations:  usize,
+	/// Reservations that did not end yet and were not cancelled
 	pub upcoming_reservations:   usize,
+	pub cancelled_reservations:  usize,
+	/// Percentage of reservations that were cancelled
+	pub cancellation_rate:       f64,
 	pub total_reservation_hours: usize,
 }
 
@@ -98,7 +141,42 @@ impl From<ProfileStats> for ProfileStatsResponse {
 			total_reservations:      stats.total_reservations,
 			completed_reservations:  stats.completed_reservations,
 			upcoming_reservations:   stats.upcoming_reservations,
+			cancelled_reservations:  stats.cancelled_reservations,
+			cancellation_rate:       stats.cancellation_rate,
 			total_reservation_hours: stats.total_reservation_hours,
 		}
 	}
 }
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DisableProfileRequest {
+	pub reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeactivationReasonResponse {
+	pub reason:         Option<String>,
+	pub deactivated_by: Option<i32>,
+}
+
+impl From<Profile> for DeactivationReasonResponse {
+	fn from(profile: Profile) -> Self {
+		Self {
+			reason:         profile.primitive.deactivation_reason,
+			deactivated_by: profile.primitive.deactivated_by,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct UnlinkSsoRequest {
+	#[validate(length(
+		min = 16,
+		message = "password must be at least 16 characters long",
+		code = "password-length"
+	))]
+	pub password: String,
+}
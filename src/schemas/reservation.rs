@@ -3,6 +3,7 @@ use chrono::{Duration, NaiveDateTime, NaiveTime};
 use db::ReservationState;
 use reservation::{Reservation, ReservationIncludes};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::schemas::location::LocationResponse;
 use crate::schemas::opening_time::OpeningTimeResponse;
@@ -10,7 +11,7 @@ use crate::schemas::profile::ProfileResponse;
 use crate::schemas::{BuildResponse, ser_includes};
 
 #[skip_serializing_none]
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ReservationResponse {
 	pub id:               i32,
@@ -24,6 +25,7 @@ pub struct ReservationResponse {
 	pub created_by:       Option<ProfileResponse>,
 	pub updated_at:       NaiveDateTime,
 	pub confirmed_at:     Option<NaiveDateTime>,
+	#[schema(value_type = Option<ProfileResponse>)]
 	#[serde(serialize_with = "ser_includes")]
 	pub confirmed_by:     Option<Option<ProfileResponse>>,
 
@@ -86,9 +88,15 @@ impl BuildResponse<ReservationResponse> for Reservation {
 	}
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateReservationRequest {
 	pub start_time: NaiveTime,
 	pub end_time:   NaiveTime,
 }
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtendReservationRequest {
+	pub additional_blocks: i32,
+}
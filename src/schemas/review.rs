@@ -1,14 +1,15 @@
 use chrono::NaiveDateTime;
 use common::Error;
-use review::{NewReview, Review, ReviewUpdate};
+use review::{NewReview, Review, ReviewFlag, ReviewUpdate};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use validator::Validate;
 use validator_derive::Validate;
 
 use crate::schemas::location::LocationResponse;
 use crate::schemas::profile::ProfileResponse;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ReviewResponse {
 	pub id:         i32,
@@ -18,6 +19,7 @@ pub struct ReviewResponse {
 	pub created_at: NaiveDateTime,
 	pub updated_at: NaiveDateTime,
 	pub location:   Option<LocationResponse>,
+	pub flag_count: i64,
 }
 
 impl From<Review> for ReviewResponse {
@@ -30,6 +32,7 @@ impl From<Review> for ReviewResponse {
 			created_at: value.primitive.created_at,
 			updated_at: value.primitive.updated_at,
 			location:   value.location.map(Into::into),
+			flag_count: value.flag_count,
 		}
 	}
 }
@@ -74,3 +77,32 @@ impl UpdateReviewRequest {
 		Ok(ReviewUpdate { rating: self.rating, body: self.body })
 	}
 }
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewFlagResponse {
+	pub id:         i32,
+	pub review_id:  i32,
+	pub flagged_by: ProfileResponse,
+	pub reason:     String,
+	pub created_at: NaiveDateTime,
+}
+
+impl From<ReviewFlag> for ReviewFlagResponse {
+	fn from(value: ReviewFlag) -> Self {
+		Self {
+			id:         value.primitive.id,
+			review_id:  value.primitive.review_id,
+			flagged_by: value.flagged_by.into(),
+			reason:     value.primitive.reason,
+			created_at: value.primitive.created_at,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct FlagReviewRequest {
+	#[validate(length(min = 1))]
+	pub reason: String,
+}
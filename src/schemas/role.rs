@@ -1,4 +1,9 @@
 use chrono::NaiveDateTime;
+use permissions::{
+	AuthorityPermissions,
+	InstitutionPermissions,
+	LocationPermissions,
+};
 use role::{
 	AuthorityRole,
 	AuthorityRoleUpdate,
@@ -13,24 +18,48 @@ use role::{
 	RoleIncludes,
 };
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::Config;
 use crate::schemas::profile::ProfileResponse;
 use crate::schemas::{BuildResponse, ser_includes};
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct RoleResponse {
-	pub id:          i32,
-	pub name:        String,
-	pub colour:      String,
-	pub permissions: i64,
-	pub created_at:  NaiveDateTime,
+	pub id:                 i32,
+	pub name:               String,
+	pub colour:             String,
+	pub permissions:        i64,
+	pub permissions_labels: Vec<String>,
+	pub created_at:         NaiveDateTime,
+	#[schema(value_type = Option<ProfileResponse>, required = true)]
 	#[serde(serialize_with = "ser_includes")]
-	pub created_by:  Option<Option<ProfileResponse>>,
-	pub updated_at:  NaiveDateTime,
+	pub created_by:         Option<Option<ProfileResponse>>,
+	pub updated_at:         NaiveDateTime,
+	#[schema(value_type = Option<ProfileResponse>, required = true)]
 	#[serde(serialize_with = "ser_includes")]
-	pub updated_by:  Option<Option<ProfileResponse>>,
+	pub updated_by:         Option<Option<ProfileResponse>>,
+}
+
+/// Get the names of all permissions set in the given bits
+///
+/// The bits of institution, authority and location permissions never
+/// overlap, so the labels of any role can be derived without knowing what
+/// kind of role it is
+#[must_use]
+pub fn permissions_labels(bits: i64) -> Vec<String> {
+	let mut labels =
+		InstitutionPermissions::from_bits_truncate(bits).permissions_labels();
+
+	labels.extend(
+		AuthorityPermissions::from_bits_truncate(bits).permissions_labels(),
+	);
+	labels.extend(
+		LocationPermissions::from_bits_truncate(bits).permissions_labels(),
+	);
+
+	labels
 }
 
 impl BuildResponse<RoleResponse> for OpaqueRole {
@@ -45,18 +74,19 @@ impl BuildResponse<RoleResponse> for OpaqueRole {
 		let updated_by = self.updated_by.map(Into::into);
 
 		Ok(RoleResponse {
-			id:          self.id,
-			name:        self.name,
-			colour:      self.colour,
-			permissions: self.permissions,
-			created_at:  self.created_at,
-			created_by:  if includes.created_by {
+			id:                 self.id,
+			name:               self.name,
+			colour:             self.colour,
+			permissions:        self.permissions,
+			permissions_labels: permissions_labels(self.permissions),
+			created_at:         self.created_at,
+			created_by:         if includes.created_by {
 				Some(created_by)
 			} else {
 				None
 			},
-			updated_at:  self.updated_at,
-			updated_by:  if includes.updated_by {
+			updated_at:         self.updated_at,
+			updated_by:         if includes.updated_by {
 				Some(updated_by)
 			} else {
 				None
@@ -204,3 +234,9 @@ impl UpdateRoleRequest {
 		}
 	}
 }
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetDefaultRoleRequest {
+	pub role_id: i32,
+}
@@ -0,0 +1,30 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::Session;
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionResponse {
+	pub id:         i32,
+	pub created_at: NaiveDateTime,
+	pub user_agent: Option<String>,
+	pub ip:         Option<String>,
+	/// Whether this is the session the request was made with
+	pub is_current: bool,
+}
+
+impl SessionResponse {
+	/// Build a response for a session, given the id of the current session
+	#[must_use]
+	pub fn new(session: Session, current_id: i32) -> Self {
+		Self {
+			id:         session.id,
+			created_at: session.data.created_at,
+			user_agent: session.data.user_agent,
+			ip:         session.data.ip,
+			is_current: session.id == current_id,
+		}
+	}
+}
@@ -1,6 +1,10 @@
+use std::collections::HashMap;
+
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use tag::{NewTag, Tag, TagIncludes, TagUpdate};
+use tag::{NewTag, Tag, TagIncludes, TagLocationStatistics, TagUpdate};
+use utoipa::ToSchema;
+use validator_derive::Validate;
 
 use crate::schemas::BuildResponse;
 use crate::schemas::profile::ProfileResponse;
@@ -10,15 +14,19 @@ use crate::schemas::translation::{
 	UpdateTranslationRequest,
 };
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TagResponse {
-	pub id:         i32,
-	pub name:       TranslationResponse,
-	pub created_at: NaiveDateTime,
-	pub created_by: Option<Option<ProfileResponse>>,
-	pub updated_at: NaiveDateTime,
-	pub updated_by: Option<Option<ProfileResponse>>,
+	pub id:          i32,
+	pub name:        TranslationResponse,
+	pub created_at:  NaiveDateTime,
+	#[schema(value_type = Option<ProfileResponse>, required = true)]
+	pub created_by:  Option<Option<ProfileResponse>>,
+	pub updated_at:  NaiveDateTime,
+	#[schema(value_type = Option<ProfileResponse>, required = true)]
+	pub updated_by:  Option<Option<ProfileResponse>>,
+	/// The amount of locations using this tag, only set when listing tags
+	pub usage_count: Option<i64>,
 }
 
 impl BuildResponse<TagResponse> for Tag {
@@ -33,30 +41,95 @@ impl BuildResponse<TagResponse> for Tag {
 		let updated_by = self.updated_by.map(Into::into);
 
 		Ok(TagResponse {
-			id:         self.primitive.id,
-			name:       self.name.into(),
-			created_at: self.primitive.created_at,
-			created_by: if includes.created_by {
+			id:          self.primitive.id,
+			name:        TranslationResponse::localized(
+				self.name,
+				includes.lang,
+			),
+			created_at:  self.primitive.created_at,
+			created_by:  if includes.created_by {
 				Some(created_by)
 			} else {
 				None
 			},
-			updated_at: self.primitive.updated_at,
-			updated_by: if includes.updated_by {
+			updated_at:  self.primitive.updated_at,
+			updated_by:  if includes.updated_by {
 				Some(updated_by)
 			} else {
 				None
 			},
+			usage_count: None,
 		})
 	}
 }
 
+impl BuildResponse<TagResponse> for (Tag, i64) {
+	type Includes = TagIncludes;
+
+	fn build_response(
+		self,
+		includes: Self::Includes,
+		config: &crate::Config,
+	) -> Result<TagResponse, common::Error> {
+		let (tag, usage_count) = self;
+
+		let mut response = tag.build_response(includes, config)?;
+		response.usage_count = Some(usage_count);
+
+		Ok(response)
+	}
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
+pub struct TagUsageResponse {
+	pub tag:         TagResponse,
+	pub usage_count: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagStatisticsResponse {
+	pub total_locations: i64,
+	pub by_country:      HashMap<String, i64>,
+	pub by_province:     HashMap<String, i64>,
+}
+
+impl From<TagLocationStatistics> for TagStatisticsResponse {
+	fn from(value: TagLocationStatistics) -> Self {
+		Self {
+			total_locations: value.total_locations,
+			by_country:      value.by_country,
+			by_province:     value.by_province,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct SetLocationTagsRequest {
 	pub tags: Vec<i32>,
 }
 
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteTagOptions {
+	/// Also remove the tag from every location still using it
+	#[serde(default)]
+	pub force: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct GetTagsBatchRequest {
+	#[validate(length(
+		max = 100,
+		message = "at most 100 tags can be requested at once",
+		code = "ids-length"
+	))]
+	pub ids: Vec<i32>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateTagRequest {
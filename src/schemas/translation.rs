@@ -2,32 +2,100 @@ use chrono::NaiveDateTime;
 use primitives::PrimitiveTranslation;
 use serde::{Deserialize, Serialize};
 use translation::{
+	InstitutionTranslations,
+	Language,
+	LocationTranslations,
 	NewTranslation,
+	TagTranslations,
 	Translation,
 	TranslationIncludes,
 	TranslationUpdate,
 };
+use utoipa::ToSchema;
+use validator_derive::Validate;
 
 use crate::schemas::profile::ProfileResponse;
 use crate::schemas::{BuildResponse, ser_includes};
 
 /// The data returned when making a new [`Translation`]
-#[derive(Clone, Debug, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
+///
+/// If a [`Language`] was requested only the resolved `text` is serialized,
+/// otherwise all languages are
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase", into = "TranslationResponseRepr")]
 pub struct TranslationResponse {
 	pub id:         i32,
 	pub nl:         Option<String>,
 	pub en:         Option<String>,
 	pub fr:         Option<String>,
 	pub de:         Option<String>,
+	/// The text in the requested [`Language`], [`None`] if no language was
+	/// requested
+	#[schema(value_type = Option<String>)]
+	#[serde(default)]
+	pub text:       Option<Option<String>>,
 	pub created_at: NaiveDateTime,
-	#[serde(serialize_with = "ser_includes")]
+	#[schema(value_type = Option<ProfileResponse>, required = true)]
 	pub created_by: Option<Option<ProfileResponse>>,
 	pub updated_at: NaiveDateTime,
-	#[serde(serialize_with = "ser_includes")]
+	#[schema(value_type = Option<ProfileResponse>, required = true)]
 	pub updated_by: Option<Option<ProfileResponse>>,
 }
 
+/// The serialized form of a [`TranslationResponse`]
+#[skip_serializing_none]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslationResponseRepr {
+	id:         i32,
+	nl:         Option<Option<String>>,
+	en:         Option<Option<String>>,
+	fr:         Option<Option<String>>,
+	de:         Option<Option<String>>,
+	text:       Option<Option<String>>,
+	created_at: NaiveDateTime,
+	#[serialize_always]
+	#[serde(serialize_with = "ser_includes")]
+	created_by: Option<Option<ProfileResponse>>,
+	updated_at: NaiveDateTime,
+	#[serialize_always]
+	#[serde(serialize_with = "ser_includes")]
+	updated_by: Option<Option<ProfileResponse>>,
+}
+
+impl From<TranslationResponse> for TranslationResponseRepr {
+	fn from(value: TranslationResponse) -> Self {
+		let resolved = value.text.is_some();
+
+		Self {
+			id:         value.id,
+			nl:         (!resolved).then_some(value.nl),
+			en:         (!resolved).then_some(value.en),
+			fr:         (!resolved).then_some(value.fr),
+			de:         (!resolved).then_some(value.de),
+			text:       value.text,
+			created_at: value.created_at,
+			created_by: value.created_by,
+			updated_at: value.updated_at,
+			updated_by: value.updated_by,
+		}
+	}
+}
+
+impl TranslationResponse {
+	/// Build a response for a translation, resolved to a single [`Language`]
+	/// if one was requested
+	#[must_use]
+	pub fn localized(
+		value: PrimitiveTranslation,
+		lang: Option<Language>,
+	) -> Self {
+		let text = lang.map(|l| l.resolve(&value).map(ToString::to_string));
+
+		Self { text, ..value.into() }
+	}
+}
+
 impl BuildResponse<TranslationResponse> for Translation {
 	type Includes = TranslationIncludes;
 
@@ -36,15 +104,18 @@ impl BuildResponse<TranslationResponse> for Translation {
 		includes: Self::Includes,
 		_config: &crate::Config,
 	) -> Result<TranslationResponse, common::Error> {
+		let text =
+			includes.lang.map(|l| self.resolve(l).map(ToString::to_string));
 		let created_by = self.created_by.map(Into::into);
 		let updated_by = self.updated_by.map(Into::into);
 
 		Ok(TranslationResponse {
-			id:         self.primitive.id,
-			nl:         self.primitive.nl,
-			en:         self.primitive.en,
-			fr:         self.primitive.fr,
-			de:         self.primitive.de,
+			id: self.primitive.id,
+			nl: self.primitive.nl,
+			en: self.primitive.en,
+			fr: self.primitive.fr,
+			de: self.primitive.de,
+			text,
 			created_at: self.primitive.created_at,
 			created_by: if includes.created_by {
 				Some(created_by)
@@ -69,6 +140,7 @@ impl From<PrimitiveTranslation> for TranslationResponse {
 			en:         value.en,
 			fr:         value.fr,
 			de:         value.de,
+			text:       None,
 			created_at: value.created_at,
 			created_by: None,
 			updated_at: value.updated_at,
@@ -77,9 +149,73 @@ impl From<PrimitiveTranslation> for TranslationResponse {
 	}
 }
 
-/// The data needed to make a new [`Translation`].
+/// All translations belonging to a single location
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationTranslationsResponse {
+	pub description: TranslationResponse,
+	pub excerpt:     TranslationResponse,
+}
+
+impl BuildResponse<LocationTranslationsResponse> for LocationTranslations {
+	type Includes = ();
+
+	fn build_response(
+		self,
+		_includes: Self::Includes,
+		config: &crate::Config,
+	) -> Result<LocationTranslationsResponse, common::Error> {
+		let includes = TranslationIncludes::default();
+
+		Ok(LocationTranslationsResponse {
+			description: self.description.build_response(includes, config)?,
+			excerpt:     self.excerpt.build_response(includes, config)?,
+		})
+	}
+}
+
+/// All translations belonging to a single tag or institution
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
+pub struct NameTranslationsResponse {
+	pub name: TranslationResponse,
+}
+
+impl BuildResponse<NameTranslationsResponse> for TagTranslations {
+	type Includes = ();
+
+	fn build_response(
+		self,
+		_includes: Self::Includes,
+		config: &crate::Config,
+	) -> Result<NameTranslationsResponse, common::Error> {
+		let includes = TranslationIncludes::default();
+
+		Ok(NameTranslationsResponse {
+			name: self.name.build_response(includes, config)?,
+		})
+	}
+}
+
+impl BuildResponse<NameTranslationsResponse> for InstitutionTranslations {
+	type Includes = ();
+
+	fn build_response(
+		self,
+		_includes: Self::Includes,
+		config: &crate::Config,
+	) -> Result<NameTranslationsResponse, common::Error> {
+		let includes = TranslationIncludes::default();
+
+		Ok(NameTranslationsResponse {
+			name: self.name.build_response(includes, config)?,
+		})
+	}
+}
+
+/// The data needed to make a new [`Translation`].
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct CreateTranslationRequest {
 	pub nl: Option<String>,
 	pub en: Option<String>,
@@ -101,7 +237,7 @@ impl CreateTranslationRequest {
 }
 
 /// The data needed to update a [`Translation`].
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateTranslationRequest {
 	pub nl: Option<String>,
@@ -122,3 +258,36 @@ impl UpdateTranslationRequest {
 		}
 	}
 }
+
+/// A single entry of a [`BulkTranslationUpdateRequest`]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkTranslationUpdateItem {
+	pub id:     i32,
+	pub update: UpdateTranslationRequest,
+}
+
+/// The data needed to update multiple [`Translation`]s at once
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+#[serde(transparent)]
+pub struct BulkTranslationUpdateRequest {
+	#[validate(length(
+		max = 100,
+		message = "at most 100 translations can be updated at once",
+		code = "translation-updates-length"
+	))]
+	pub updates: Vec<BulkTranslationUpdateItem>,
+}
+
+impl BulkTranslationUpdateRequest {
+	#[must_use]
+	pub fn to_insertable(
+		self,
+		updated_by: i32,
+	) -> Vec<(i32, TranslationUpdate)> {
+		self.updates
+			.into_iter()
+			.map(|item| (item.id, item.update.to_insertable(updated_by)))
+			.collect()
+	}
+}
@@ -0,0 +1,132 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use validator_derive::Validate;
+use webhook::{
+	NewWebhook,
+	Webhook,
+	WebhookEvent,
+	WebhookEvents,
+	WebhookUpdate,
+};
+
+use crate::schemas::location::LocationResponse;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookResponse {
+	pub id:                   i32,
+	pub authority_id:         i32,
+	pub url:                  String,
+	/// Key of the HMAC-SHA256 signature sent along with every delivery, only
+	/// returned when the webhook is created or its secret is rotated
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub secret:               Option<String>,
+	pub enabled:              bool,
+	pub events:               i64,
+	pub events_labels:        Vec<String>,
+	pub last_delivery_at:     Option<NaiveDateTime>,
+	pub last_delivery_status: Option<i32>,
+	pub last_delivery_error:  Option<String>,
+	pub created_at:           NaiveDateTime,
+	pub updated_at:           NaiveDateTime,
+}
+
+impl From<Webhook> for WebhookResponse {
+	fn from(value: Webhook) -> Self {
+		let events = WebhookEvents::from_bits_truncate(value.primitive.events);
+
+		Self {
+			id:                   value.primitive.id,
+			authority_id:         value.primitive.authority_id,
+			url:                  value.primitive.url,
+			secret:               None,
+			enabled:              value.primitive.enabled,
+			events:               value.primitive.events,
+			events_labels:        events.events_labels(),
+			last_delivery_at:     value.primitive.last_delivery_at,
+			last_delivery_status: value.primitive.last_delivery_status,
+			last_delivery_error:  value.primitive.last_delivery_error,
+			created_at:           value.primitive.created_at,
+			updated_at:           value.primitive.updated_at,
+		}
+	}
+}
+
+impl WebhookResponse {
+	/// Build a response that includes the secret of the webhook
+	#[must_use]
+	pub fn with_secret(value: Webhook) -> Self {
+		let secret = value.primitive.secret.clone();
+
+		Self { secret: Some(secret), ..value.into() }
+	}
+}
+
+const fn default_enabled() -> bool { true }
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateWebhookRequest {
+	#[validate(url(message = "url must be a valid url", code = "url"))]
+	pub url:     String,
+	#[serde(default = "default_enabled")]
+	pub enabled: bool,
+	pub events:  i64,
+}
+
+impl CreateWebhookRequest {
+	#[must_use]
+	pub fn to_insertable(
+		self,
+		authority_id: i32,
+		secret: String,
+		created_by: i32,
+	) -> NewWebhook {
+		NewWebhook {
+			authority_id,
+			url: self.url,
+			secret,
+			enabled: self.enabled,
+			events: self.events,
+			created_by,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateWebhookRequest {
+	#[validate(url(message = "url must be a valid url", code = "url"))]
+	pub url:           Option<String>,
+	pub enabled:       Option<bool>,
+	pub events:        Option<i64>,
+	/// Replace the secret with a newly generated one
+	#[serde(default)]
+	pub rotate_secret: bool,
+}
+
+impl UpdateWebhookRequest {
+	#[must_use]
+	pub fn to_insertable(
+		self,
+		secret: Option<String>,
+		updated_by: i32,
+	) -> WebhookUpdate {
+		WebhookUpdate {
+			url: self.url,
+			secret,
+			enabled: self.enabled,
+			events: self.events,
+			updated_by,
+		}
+	}
+}
+
+/// The body posted to webhooks for events concerning a location
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationWebhookPayload {
+	pub event:       WebhookEvent,
+	pub occurred_at: NaiveDateTime,
+	pub location:    LocationResponse,
+}
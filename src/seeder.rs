@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use common::{DbConn, Error};
 use db::ProfileState;
 use diesel::prelude::*;
+use diesel::sql_types::Bool;
 use serde::Deserialize;
 use serde::de::DeserializeOwned;
 
@@ -59,6 +60,56 @@ impl<'c> Seeder<'c> {
 
 		self
 	}
+
+	/// Load a file and populate the database with it, unless
+	/// `table_has_rows` reports that the data is already present
+	///
+	/// # Panics
+	/// Panics if reading the file or interacting with the database fails
+	pub async fn populate_if_empty<'s, T, P, F>(
+		&'s self,
+		filename: &str,
+		table_has_rows: P,
+		loader: F,
+	) -> &'s Self
+	where
+		T: DeserializeOwned,
+		P: AsyncFnOnce(&DbConn) -> bool,
+		F: AsyncFnOnce(&DbConn, Vec<T>) -> Result<(), Error>,
+	{
+		if table_has_rows(self.connection).await {
+			info!("skipped seeding from {filename}, data already present");
+
+			return self;
+		}
+
+		self.populate(filename, loader).await
+	}
+
+	/// Check if the given table contains at least one row
+	///
+	/// # Panics
+	/// Panics if interacting with the database fails
+	pub async fn table_has_rows(conn: &DbConn, table: &'static str) -> bool {
+		#[derive(QueryableByName)]
+		struct HasRows {
+			#[diesel(sql_type = Bool)]
+			has_rows: bool,
+		}
+
+		let query =
+			format!("SELECT EXISTS(SELECT 1 FROM {table} LIMIT 1) AS has_rows");
+
+		let result = conn
+			.interact(move |conn| {
+				diesel::sql_query(query).get_result::<HasRows>(conn)
+			})
+			.await
+			.unwrap_or_else(|e| panic!("COULD NOT CHECK TABLE {table}\n{e:?}"))
+			.unwrap_or_else(|e| panic!("COULD NOT CHECK TABLE {table}\n{e:?}"));
+
+		result.has_rows
+	}
 }
 
 #[derive(Clone, Debug, Deserialize, Insertable, AsChangeset)]
@@ -1,12 +1,17 @@
 //! User sessions and tokens
 
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
 use axum::RequestPartsExt;
-use axum::extract::{FromRequestParts, State};
+use axum::extract::{ConnectInfo, FromRequestParts, State};
 use axum::http::request::Parts;
+use axum::http::{Extensions, HeaderMap, header};
 use axum_extra::extract::cookie::{Cookie, SameSite};
+use chrono::{NaiveDateTime, Utc};
 use common::{Error, InternalServerError, RedisConn};
 use profile::Profile;
-use redis::AsyncCommands;
+use redis::{AsyncCommands, ExistenceCheck, SetExpiry, SetOptions};
 use serde::{Deserialize, Serialize};
 use time::Duration;
 
@@ -21,7 +26,7 @@ use crate::AppState;
 ///     ()
 /// }
 /// ```
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Session {
 	pub id:   i32,
 	pub data: SessionData,
@@ -36,16 +41,68 @@ pub struct Session {
 ///     ()
 /// }
 /// ```
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct AdminSession {
 	pub id:   i32,
 	pub data: SessionData,
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+/// Redis key of the counter new session ids are taken from
+const SESSION_ID_COUNTER_KEY: &str = "session:next_id";
+
+/// Redis key of the set holding the ids of all sessions of a profile
+fn profile_sessions_key(profile_id: i32) -> String {
+	format!("sessions:profile:{profile_id}")
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SessionData {
 	pub profile_id: i32,
 	pub is_admin:   bool,
+	/// Sessions stored before their creation was tracked default to the epoch
+	#[serde(default)]
+	pub created_at: NaiveDateTime,
+	#[serde(default)]
+	pub user_agent: Option<String>,
+	#[serde(default)]
+	pub ip:         Option<String>,
+}
+
+/// The client a [`Session`] is created for
+#[derive(Clone, Debug, Default)]
+pub struct SessionClient {
+	pub user_agent: Option<String>,
+	pub ip:         Option<String>,
+}
+
+impl SessionClient {
+	/// Read the client details from the headers and extensions of a request
+	///
+	/// The IP is only known if the server was started with connection info
+	#[must_use]
+	pub fn from_request(headers: &HeaderMap, extensions: &Extensions) -> Self {
+		let user_agent = headers
+			.get(header::USER_AGENT)
+			.and_then(|value| value.to_str().ok())
+			.map(ToString::to_string);
+
+		let ip = extensions
+			.get::<ConnectInfo<SocketAddr>>()
+			.map(|ConnectInfo(addr)| addr.ip().to_string());
+
+		Self { user_agent, ip }
+	}
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for SessionClient {
+	type Rejection = Infallible;
+
+	async fn from_request_parts(
+		parts: &mut Parts,
+		_state: &S,
+	) -> Result<Self, Self::Rejection> {
+		Ok(Self::from_request(&parts.headers, &parts.extensions))
+	}
 }
 
 impl FromRequestParts<AppState> for Session {
@@ -94,7 +151,9 @@ impl FromRequestParts<AppState> for AdminSession {
 			return Err(Error::Forbidden);
 		}
 
-		let admin_session = Self { id: session.id, data: session.data };
+		let Session { id, data } = session;
+
+		let admin_session = Self { id, data };
 
 		Ok(admin_session)
 	}
@@ -102,36 +161,62 @@ impl FromRequestParts<AppState> for AdminSession {
 
 impl Session {
 	/// Create and store a new [`Session`] for a given [`Profile`]
+	///
+	/// The session is added to the index of its profile, so it can be listed
+	/// and revoked later on
 	#[instrument(skip(conn))]
 	pub async fn create(
 		lifetime: Duration,
 		profile: &Profile,
+		client: SessionClient,
 		conn: &mut RedisConn,
 	) -> Result<Self, Error> {
-		let id = profile.primitive.id;
 		let profile_id = profile.primitive.id;
 
-		let data =
-			SessionData { profile_id, is_admin: profile.primitive.is_admin };
-
-		let session = Self { id, data };
+		let data = SessionData {
+			profile_id,
+			is_admin: profile.primitive.is_admin,
+			created_at: Utc::now().naive_utc(),
+			user_agent: client.user_agent,
+			ip: client.ip,
+		};
 
 		// Add a buffer of 10 seconds to ensure the cached session doesn't
 		// expire before the session cookie does
 		let expiry = lifetime.whole_seconds() + 10;
 
-		let data = serde_json::to_string(&data)
+		let value = serde_json::to_string(&data)
 			.map_err(InternalServerError::SerdeJsonError)?;
 
-		let _: bool = conn.set(id, &data).await?;
-		let _: bool = conn.expire(id, expiry).await?;
+		// Never overwrite an existing session, e.g. one that was stored under
+		// the id of its profile before sessions got their own ids
+		let id = loop {
+			let id: i32 = conn.incr(SESSION_ID_COUNTER_KEY, 1).await?;
 
-		debug!(
-			"stored session {} in cache for profile {}",
-			id, profile.primitive.id
-		);
+			let options = SetOptions::default()
+				.conditional_set(ExistenceCheck::NX)
+				.with_expiration(SetExpiry::EX(expiry.unsigned_abs()));
 
-		Ok(session)
+			let stored: Option<String> =
+				conn.set_options(id, &value, options).await?;
+
+			if stored.is_some() {
+				break id;
+			}
+		};
+
+		// The index lives as long as the longest lived session in it
+		let index_key = profile_sessions_key(profile_id);
+		let _: i32 = conn.sadd(&index_key, id).await?;
+
+		let index_ttl: i64 = conn.ttl(&index_key).await?;
+		if index_ttl < expiry {
+			let _: bool = conn.expire(&index_key, expiry).await?;
+		}
+
+		debug!("stored session {id} in cache for profile {profile_id}");
+
+		Ok(Self { id, data })
 	}
 
 	/// Get a session from the cache
@@ -154,14 +239,79 @@ impl Session {
 		Ok(Some(session))
 	}
 
+	/// Get all sessions of a profile, oldest first
+	///
+	/// Expired sessions are removed from the index of the profile along the
+	/// way
+	#[instrument(skip(conn))]
+	pub async fn get_all_for_profile(
+		profile_id: i32,
+		conn: &mut RedisConn,
+	) -> Result<Vec<Self>, Error> {
+		let index_key = profile_sessions_key(profile_id);
+		let ids: Vec<i32> = conn.smembers(&index_key).await?;
+
+		let mut sessions = vec![];
+		let mut expired = vec![];
+
+		for id in ids {
+			match Self::get(id, conn).await? {
+				Some(session) => sessions.push(session),
+				None => expired.push(id),
+			}
+		}
+
+		if !expired.is_empty() {
+			let _: i32 = conn.srem(&index_key, expired).await?;
+		}
+
+		sessions.sort_by_key(|s| (s.data.created_at, s.id));
+
+		Ok(sessions)
+	}
+
 	/// Remove a session given its id
 	#[instrument(skip(conn))]
 	pub async fn delete(id: i32, conn: &mut RedisConn) -> Result<(), Error> {
+		if let Some(session) = Self::get(id, conn).await? {
+			let index_key = profile_sessions_key(session.data.profile_id);
+			let _: i32 = conn.srem(index_key, id).await?;
+		}
+
 		let _: i32 = conn.del(id).await?;
 
 		Ok(())
 	}
 
+	/// Remove all sessions of a profile, except for the given one if any
+	///
+	/// Returns the amount of sessions that were removed
+	#[instrument(skip(conn))]
+	pub async fn delete_for_profile(
+		profile_id: i32,
+		except: Option<i32>,
+		conn: &mut RedisConn,
+	) -> Result<usize, Error> {
+		let index_key = profile_sessions_key(profile_id);
+		let ids: Vec<i32> = conn.smembers(&index_key).await?;
+
+		let ids = ids
+			.into_iter()
+			.filter(|id| Some(*id) != except)
+			.collect::<Vec<_>>();
+
+		if ids.is_empty() {
+			return Ok(0);
+		}
+
+		let deleted: usize = conn.del(&ids).await?;
+		let _: i32 = conn.srem(&index_key, &ids).await?;
+
+		info!("revoked {deleted} sessions of profile {profile_id}");
+
+		Ok(deleted)
+	}
+
 	/// Check if a session with this id exists
 	#[instrument(skip(conn))]
 	pub async fn exists(id: i32, conn: &mut RedisConn) -> Result<bool, Error> {
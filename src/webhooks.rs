@@ -0,0 +1,322 @@
+//! Delivery of webhook notifications to the systems of authorities
+
+use std::fmt::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use common::{DbPool, Error};
+use hmac::{Hmac, Mac};
+use primitives::PrimitiveWebhook;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::redirect::Policy;
+use sha2::Sha256;
+use url::{Host, Url};
+use webhook::{Webhook, WebhookDelivery, WebhookEvent};
+
+use crate::schemas::location::LocationResponse;
+use crate::schemas::webhook::LocationWebhookPayload;
+
+/// Header holding the HMAC-SHA256 signature of the body, as `sha256=<hex>`
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Blokmap-Signature";
+/// Header holding the name of the event that is delivered
+pub const WEBHOOK_EVENT_HEADER: &str = "X-Blokmap-Event";
+/// How long a single delivery attempt may take
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Amount of times a failed delivery is retried
+const WEBHOOK_MAX_RETRIES: u32 = 2;
+/// Delay before the first retry of a failed delivery, doubled on every retry
+const WEBHOOK_RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Sends events to the webhooks subscribed to them
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+	client:              reqwest::Client,
+	pool:                DbPool,
+	allow_private_hosts: bool,
+}
+
+/// Whether an address can be reached from the public internet
+///
+/// Webhooks are never delivered to loopback, private, link-local or
+/// otherwise reserved addresses, so authorities can't use them to reach
+/// services on the internal network of the server
+#[must_use]
+pub fn is_public_ip(ip: IpAddr) -> bool {
+	match ip {
+		IpAddr::V4(ip) => {
+			let [a, b, ..] = ip.octets();
+
+			!(ip.is_loopback()
+				|| ip.is_private()
+				|| ip.is_link_local()
+				|| ip.is_unspecified()
+				|| ip.is_broadcast()
+				|| ip.is_documentation()
+				// Shared address space used for carrier-grade NAT
+				|| (a == 100 && (64..128).contains(&b)))
+		},
+		IpAddr::V6(ip) => {
+			if let Some(ip) = ip.to_ipv4_mapped() {
+				return is_public_ip(IpAddr::V4(ip));
+			}
+
+			!(ip.is_loopback()
+				|| ip.is_unspecified()
+				|| ip.is_unique_local()
+				|| ip.is_unicast_link_local())
+		},
+	}
+}
+
+/// Check that a webhook url does not point at the internal network of the
+/// server
+///
+/// Only literal addresses and `localhost` can be checked up front, the
+/// addresses of other hosts are checked once they are resolved for a
+/// delivery
+pub fn check_webhook_host(
+	url: &Url,
+	allow_private_hosts: bool,
+) -> Result<(), Error> {
+	if allow_private_hosts {
+		return Ok(());
+	}
+
+	let is_public = match url.host() {
+		Some(Host::Domain(domain)) => {
+			let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+
+			domain != "localhost" && !domain.ends_with(".localhost")
+		},
+		Some(Host::Ipv4(ip)) => is_public_ip(IpAddr::V4(ip)),
+		Some(Host::Ipv6(ip)) => is_public_ip(IpAddr::V6(ip)),
+		None => false,
+	};
+
+	if !is_public {
+		return Err(Error::BadRequest(
+			"webhooks can only be delivered to public hosts".to_string(),
+		));
+	}
+
+	Ok(())
+}
+
+/// Resolves the hosts of webhooks, leaving out every address that is not
+/// [public](is_public_ip)
+///
+/// Checking the resolved addresses rather than the host name keeps domains
+/// that point at internal addresses from being used
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+	fn resolve(&self, name: Name) -> Resolving {
+		Box::pin(async move {
+			let addrs = tokio::net::lookup_host((name.as_str(), 0)).await?;
+			let addrs: Vec<SocketAddr> =
+				addrs.filter(|a| is_public_ip(a.ip())).collect();
+
+			if addrs.is_empty() {
+				return Err(format!(
+					"{} does not resolve to a public address",
+					name.as_str()
+				)
+				.into());
+			}
+
+			Ok(Box::new(addrs.into_iter()) as Addrs)
+		})
+	}
+}
+
+/// Sign a body with the secret of a webhook
+///
+/// Receivers can check the signature by computing the HMAC-SHA256 of the raw
+/// body themselves
+///
+/// # Panics
+/// Never panics, HMAC accepts keys of any length
+#[must_use]
+pub fn webhook_signature(secret: &str, body: &[u8]) -> String {
+	let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+		.expect("HMAC ACCEPTS KEYS OF ANY LENGTH");
+
+	mac.update(body);
+
+	mac.finalize().into_bytes().iter().fold(
+		String::from("sha256="),
+		|mut signature, byte| {
+			let _ = write!(signature, "{byte:02x}");
+
+			signature
+		},
+	)
+}
+
+impl WebhookDispatcher {
+	/// Create a new dispatcher
+	///
+	/// Redirects are never followed, as they could lead to internal hosts.
+	/// Unless `allow_private_hosts` is set, webhooks are only delivered to
+	/// [public](is_public_ip) addresses
+	///
+	/// # Panics
+	/// Panics if the HTTP client can not be created
+	#[must_use]
+	pub fn new(database_pool: DbPool, allow_private_hosts: bool) -> Self {
+		let mut builder = reqwest::Client::builder()
+			.timeout(WEBHOOK_TIMEOUT)
+			.redirect(Policy::none())
+			.user_agent(concat!("blokmap/", env!("CARGO_PKG_VERSION")));
+
+		if !allow_private_hosts {
+			builder = builder.dns_resolver(Arc::new(PublicResolver));
+		}
+
+		let client = builder.build().expect("COULD NOT CREATE WEBHOOK CLIENT");
+
+		Self { client, pool: database_pool, allow_private_hosts }
+	}
+
+	/// Notify the webhooks of an authority that are subscribed to an event
+	/// concerning one of its locations
+	///
+	/// Delivery happens on a background task, so the request that caused
+	/// the event never waits for the receiving systems
+	pub fn dispatch_location_event(
+		&self,
+		auth_id: i32,
+		event: WebhookEvent,
+		location: LocationResponse,
+	) {
+		let dispatcher = self.clone();
+
+		tokio::spawn(async move {
+			let payload = LocationWebhookPayload {
+				event,
+				occurred_at: Utc::now().naive_utc(),
+				location,
+			};
+
+			let result = dispatcher.dispatch(auth_id, event, &payload).await;
+
+			if let Err(e) = result {
+				error!(
+					"could not dispatch {event:?} to the webhooks of \
+					 authority {auth_id}: {e:?}"
+				);
+			}
+		});
+	}
+
+	/// Deliver a payload to all subscribed webhooks and record the outcome
+	/// of every delivery
+	async fn dispatch(
+		&self,
+		auth_id: i32,
+		event: WebhookEvent,
+		payload: &LocationWebhookPayload,
+	) -> Result<(), Error> {
+		let conn = self.pool.get().await?;
+
+		let webhooks = Webhook::get_subscribed(auth_id, event, &conn).await?;
+
+		if webhooks.is_empty() {
+			return Ok(());
+		}
+
+		let body = serde_json::to_vec(payload)?;
+
+		let deliveries = futures::future::join_all(
+			webhooks.iter().map(|w| self.deliver(&w.primitive, event, &body)),
+		)
+		.await;
+
+		for (webhook, delivery) in webhooks.into_iter().zip(deliveries) {
+			Webhook::record_delivery(webhook.primitive.id, delivery, &conn)
+				.await?;
+		}
+
+		Ok(())
+	}
+
+	/// Post a body to a single webhook, retrying with exponential backoff
+	/// on failure
+	///
+	/// Returns the outcome of the last attempt
+	async fn deliver(
+		&self,
+		webhook: &PrimitiveWebhook,
+		event: WebhookEvent,
+		body: &[u8],
+	) -> WebhookDelivery {
+		// Webhooks stored before a host was refused are never delivered
+		let checked = webhook
+			.url
+			.parse::<Url>()
+			.map_err(|e| Error::BadRequest(e.to_string()))
+			.and_then(|url| check_webhook_host(&url, self.allow_private_hosts));
+
+		if let Err(e) = checked {
+			return WebhookDelivery {
+				last_delivery_at:     Utc::now().naive_utc(),
+				last_delivery_status: None,
+				last_delivery_error:  Some(e.to_string()),
+			};
+		}
+
+		let signature = webhook_signature(&webhook.secret, body);
+
+		let mut delay = WEBHOOK_RETRY_BASE_DELAY;
+		let mut attempt = 0;
+
+		loop {
+			let result = self
+				.client
+				.post(&webhook.url)
+				.header(CONTENT_TYPE, "application/json")
+				.header(WEBHOOK_EVENT_HEADER, event.name())
+				.header(WEBHOOK_SIGNATURE_HEADER, &signature)
+				.body(body.to_vec())
+				.send()
+				.await;
+
+			let (status, error) = match result {
+				Ok(response) if response.status().is_success() => {
+					(Some(response.status().as_u16()), None)
+				},
+				Ok(response) => {
+					let status = response.status();
+
+					(
+						Some(status.as_u16()),
+						Some(format!("unexpected status {status}")),
+					)
+				},
+				Err(e) => (e.status().map(|s| s.as_u16()), Some(e.to_string())),
+			};
+
+			if error.is_none() || attempt >= WEBHOOK_MAX_RETRIES {
+				return WebhookDelivery {
+					last_delivery_at:     Utc::now().naive_utc(),
+					last_delivery_status: status.map(i32::from),
+					last_delivery_error:  error,
+				};
+			}
+
+			attempt += 1;
+
+			warn!(
+				"error delivering {event:?} to webhook {}, retry \
+				 {attempt}/{WEBHOOK_MAX_RETRIES} in {delay:?}: {error:?}",
+				webhook.id,
+			);
+
+			tokio::time::sleep(delay).await;
+			delay *= 2;
+		}
+	}
+}
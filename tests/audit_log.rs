@@ -0,0 +1,74 @@
+use axum::http::StatusCode;
+use blokmap::schemas::audit_log::AuditLogResponse;
+use blokmap::schemas::pagination::PaginatedResponse;
+use db::{AuditAction, AuditTarget};
+
+mod common;
+
+use common::TestEnv;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_audit_log_test() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let admin = env.get_admin_profile().await.unwrap();
+	let test = env.get_profile("test").await.unwrap();
+
+	let response = env.app.post("/locations/1/approve").await;
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let response = env.app.post(&format!("/profiles/{}/block", test.id)).await;
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let response = env.app.get("/admin/audit-log").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	// Newest entries come first
+	let body = response.json::<PaginatedResponse<Vec<AuditLogResponse>>>();
+	assert_eq!(body.total, 2);
+	assert_eq!(body.data[0].action, AuditAction::ProfileDisabled);
+	assert_eq!(body.data[0].actor.as_ref().unwrap().id, admin.id);
+	assert_eq!(body.data[1].action, AuditAction::LocationApproved);
+
+	let response = env
+		.app
+		.get("/admin/audit-log")
+		.add_query_param("action", "LocationApproved")
+		.await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<AuditLogResponse>>>();
+	assert_eq!(body.total, 1);
+	assert_eq!(body.data[0].target_type, AuditTarget::Location);
+	assert_eq!(body.data[0].target_id, 1);
+
+	let response = env
+		.app
+		.get("/admin/audit-log")
+		.add_query_param("targetType", "Profile")
+		.add_query_param("targetId", test.id)
+		.await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<AuditLogResponse>>>();
+	assert_eq!(body.total, 1);
+	assert_eq!(body.data[0].action, AuditAction::ProfileDisabled);
+
+	let response = env
+		.app
+		.get("/admin/audit-log")
+		.add_query_param("actorId", test.id)
+		.await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<AuditLogResponse>>>();
+	assert_eq!(body.total, 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_audit_log_requires_admin_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env.app.get("/admin/audit-log").await;
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+}
@@ -1,4 +1,9 @@
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
 use axum::http::StatusCode;
+use blokmap::Config;
+use blokmap::middleware::RATE_LIMIT_WINDOW_SECONDS;
 use blokmap::schemas::auth::{
 	LoginRequest,
 	PasswordResetData,
@@ -6,6 +11,7 @@ use blokmap::schemas::auth::{
 	RegisterRequest,
 };
 use primitives::PrimitiveProfile;
+use redis::AsyncCommands;
 
 mod common;
 
@@ -582,6 +588,26 @@ async fn reset_password_expired_token() {
 	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn reset_password_mail_retried() {
+	let env = TestEnv::new().await;
+
+	// Make the first two delivery attempts fail
+	env.stub_mailbox.failures.store(2, Ordering::SeqCst);
+
+	let response = env
+		.expect_mail_to(&["test@example.com"], async || {
+			env.app
+				.post("/auth/request_password_reset")
+				.json(&PasswordResetRequest { username: "test".to_string() })
+				.await
+		})
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+	assert_eq!(env.stub_mailbox.failures.load(Ordering::SeqCst), 0);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn login_username() {
 	let env = TestEnv::new().await;
@@ -683,3 +709,49 @@ async fn logout() {
 	assert_eq!(access_token.max_age(), Some(time::Duration::ZERO));
 	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn login_rate_limit() {
+	let env = TestEnv::new().await;
+
+	let limit = Config::from_env().login_rate_limit_per_minute;
+
+	let login = || {
+		env.app.post("/auth/login").json(&LoginRequest {
+			username: "test".to_string(),
+			password: "foo".to_string(),
+			remember: false,
+		})
+	};
+
+	for _ in 0..limit {
+		let response = login().await;
+
+		assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+	}
+
+	let response = login().await;
+
+	assert_eq!(response.status_code(), StatusCode::TOO_MANY_REQUESTS);
+
+	let retry_after = response
+		.header("retry-after")
+		.to_str()
+		.unwrap()
+		.parse::<u64>()
+		.unwrap();
+	assert!(retry_after > 0);
+	assert!(retry_after <= RATE_LIMIT_WINDOW_SECONDS);
+
+	// Let the current window expire
+	let mut r_conn = env.redis_guard.connect().await;
+	let keys: Vec<String> = r_conn.keys("ratelimit:login:*").await.unwrap();
+	assert_eq!(keys.len(), 1);
+
+	let _: bool = r_conn.pexpire(&keys[0], 1).await.unwrap();
+	tokio::time::sleep(Duration::from_millis(50)).await;
+
+	let response = login().await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+}
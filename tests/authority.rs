@@ -0,0 +1,847 @@
+use authority::{
+	Authority,
+	AuthorityIncludes,
+	MemberImportIssueReason,
+	NewAuthority,
+};
+use axum::http::StatusCode;
+use axum_test::TestResponse;
+use axum_test::multipart::{MultipartForm, Part};
+use blokmap::schemas::authority::{
+	AuthorityResponse,
+	AuthorityStatisticsResponse,
+	MemberImportResponse,
+	NameCheckResponse,
+};
+use blokmap::schemas::location::csv_field;
+use blokmap::schemas::pagination::PaginatedResponse;
+use blokmap::schemas::reservation::ReservationResponse;
+use chrono::{Duration, NaiveTime, Utc};
+use db::AuditAction;
+use location::NewLocationMember;
+use opening_time::{NewOpeningTime, OpeningTimeIncludes};
+use reservation::{NewReservation, ReservationIncludes};
+
+mod common;
+
+use common::TestEnv;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn search_authorities_test() {
+	let env = TestEnv::new().await;
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let names =
+		["Faculteit Wetenschappen", "Faculteit Letteren", "Stadsbibliotheek"];
+
+	for name in names {
+		NewAuthority {
+			name:           name.to_string(),
+			description:    None,
+			created_by:     profile.id,
+			institution_id: None,
+		}
+		.insert(AuthorityIncludes::default(), &conn)
+		.await
+		.unwrap();
+	}
+
+	let response = env
+		.app
+		.get("/authorities/search")
+		.add_query_param("q", "faculteit")
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let mut names = response
+		.json::<Vec<AuthorityResponse>>()
+		.into_iter()
+		.map(|a| a.name)
+		.collect::<Vec<_>>();
+	names.sort();
+
+	assert_eq!(names, ["Faculteit Letteren", "Faculteit Wetenschappen"]);
+
+	let response =
+		env.app.get("/authorities/search").add_query_param("q", "biblio").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<Vec<AuthorityResponse>>();
+
+	assert_eq!(body.len(), 1);
+	assert_eq!(body[0].name, "Stadsbibliotheek");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn search_authorities_short_query_test() {
+	let env = TestEnv::new().await;
+
+	let response =
+		env.app.get("/authorities/search").add_query_param("q", "f").await;
+
+	assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_authority_duplicate_name_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env
+		.app
+		.post("/authorities")
+		.json(&serde_json::json!({
+			"name": "Faculteit Wetenschappen",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let response = env
+		.app
+		.post("/authorities")
+		.json(&serde_json::json!({
+			"name": "Faculteit Wetenschappen",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["code"], "CONFLICT");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn authority_name_check_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env
+		.app
+		.get("/authorities/name-check")
+		.add_query_param("name", "Faculteit Letteren")
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+	assert!(response.json::<NameCheckResponse>().available);
+
+	env.app
+		.post("/authorities")
+		.json(&serde_json::json!({
+			"name": "Faculteit Letteren",
+		}))
+		.await;
+
+	let response = env
+		.app
+		.get("/authorities/name-check")
+		.add_query_param("name", "Faculteit Letteren")
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+	assert!(!response.json::<NameCheckResponse>().available);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_authority_empty_name_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env
+		.app
+		.post("/authorities")
+		.json(&serde_json::json!({
+			"name": "",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["code"], "VALIDATION_FAILED");
+	assert!(body["details"]["fields"]["name"].is_string());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_authority_statistics_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	let profile = env.get_profile("test").await.unwrap();
+	let other = env.get_profile("test2").await.unwrap();
+	let admin = env.get_admin_profile().await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let authority = NewAuthority {
+		name:           "Faculteit Wetenschappen".to_string(),
+		description:    None,
+		created_by:     profile.id,
+		institution_id: None,
+	}
+	.insert(AuthorityIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let a_id = authority.primitive.id;
+
+	conn.interact(move |conn| {
+		use db::location::dsl::*;
+
+		diesel::update(location.filter(id.eq_any([1, 2])))
+			.set(authority_id.eq(Some(a_id)))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	// The second profile is a member of both locations
+	let members =
+		[(1, profile.id), (1, other.id), (2, other.id), (2, admin.id)];
+
+	for (location_id, profile_id) in members {
+		NewLocationMember {
+			location_id,
+			profile_id,
+			location_role_id: None,
+			added_by: admin.id,
+		}
+		.insert(&conn)
+		.await
+		.unwrap();
+	}
+
+	let response =
+		env.app.get(&format!("/authorities/{a_id}/statistics")).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let statistics = response.json::<AuthorityStatisticsResponse>();
+
+	assert_eq!(statistics.location_count, 2);
+	assert_eq!(statistics.member_count, 1);
+	assert_eq!(statistics.unique_location_member_count, 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_authority_reservations_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let authority = NewAuthority {
+		name:           "Faculteit Wetenschappen".to_string(),
+		description:    None,
+		created_by:     profile.id,
+		institution_id: None,
+	}
+	.insert(AuthorityIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let a_id = authority.primitive.id;
+
+	conn.interact(move |conn| {
+		use db::location::dsl::*;
+
+		diesel::update(location.filter(id.eq_any([1, 2])))
+			.set(authority_id.eq(Some(a_id)))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let day = (Utc::now() + Duration::days(7)).date_naive();
+	let times = [1, 2].map(|location_id| {
+		NewOpeningTime {
+			location_id,
+			day,
+			start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+			seat_count: None,
+			reservable_from: None,
+			reservable_until: None,
+			created_by: profile.id,
+		}
+	});
+
+	let times = NewOpeningTime::bulk_insert(
+		Vec::from(times),
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	let mut r_ids = vec![];
+	for time in &times {
+		for base_block_index in [0, 8, 16, 24, 32] {
+			let reservation = NewReservation {
+				profile_id: profile.id,
+				opening_time_id: time.id,
+				base_block_index,
+				block_count: 4,
+			}
+			.insert(ReservationIncludes::default(), &conn)
+			.await
+			.unwrap();
+
+			r_ids.push(reservation.primitive.id);
+		}
+	}
+
+	// Filter on the day to leave out the seeded reservation of location 1
+	let response = env
+		.app
+		.get(&format!("/authorities/{a_id}/reservations"))
+		.add_query_param("date", day)
+		.add_query_param("page", 1)
+		.add_query_param("perPage", 2)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<ReservationResponse>>>();
+	let ids: Vec<_> = body.data.iter().map(|r| r.id).collect();
+
+	assert_eq!(body.total, 10);
+	assert_eq!(ids, r_ids[..2]);
+
+	let response = env
+		.app
+		.get(&format!("/authorities/{a_id}/reservations"))
+		.add_query_param("date", day)
+		.add_query_param("page", 5)
+		.add_query_param("perPage", 2)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<ReservationResponse>>>();
+	let ids: Vec<_> = body.data.iter().map(|r| r.id).collect();
+
+	assert_eq!(ids, r_ids[8..]);
+
+	// Profiles outside of the authority can't see its reservations
+	let env = env.login("test2").await;
+
+	let response =
+		env.app.get(&format!("/authorities/{a_id}/reservations")).await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+}
+
+/// Create an authority owned by the given profile
+async fn create_authority(env: &TestEnv, username: &str) -> i32 {
+	let profile = env.get_profile(username).await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let authority = NewAuthority {
+		name:           "Faculteit Wetenschappen".to_string(),
+		description:    None,
+		created_by:     profile.id,
+		institution_id: None,
+	}
+	.insert(AuthorityIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	authority.primitive.id
+}
+
+/// Upload a CSV file of members to an authority
+async fn import_members(env: &TestEnv, a_id: i32, csv: &str) -> TestResponse {
+	let form = MultipartForm::new().add_part(
+		"file",
+		Part::text(csv.to_string())
+			.file_name("members.csv")
+			.mime_type("text/csv"),
+	);
+
+	env.app
+		.post(&format!("/authorities/{a_id}/members/import"))
+		.multipart(form)
+		.await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn delete_authority_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let a_id = create_authority(&env, "test").await;
+	let url = format!("/authorities/{a_id}");
+
+	let env = env.login("test2").await;
+
+	let response = env.app.delete(&url).await;
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let env = env.login("test").await;
+
+	let response = env.app.delete(&url).await;
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let response = env.app.get(&url).await;
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+	let entries = env.get_audit_entries(AuditAction::AuthorityDeleted).await;
+	assert_eq!(entries.len(), 1);
+	assert_eq!(entries[0].target_id, a_id);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_authority_member_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let a_id = create_authority(&env, "test").await;
+	let test2 = env.get_profile("test2").await.unwrap();
+
+	let response = import_members(&env, a_id, "test2@example.com\n").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let response = env
+		.app
+		.patch(&format!("/authorities/{a_id}/members/{}", test2.id))
+		.json(&serde_json::json!({ "roleId": null }))
+		.await;
+
+	assert!(response.status_code().is_success());
+
+	let entries = env.get_audit_entries(AuditAction::MemberRoleChanged).await;
+	assert_eq!(entries.len(), 1);
+	assert_eq!(entries[0].target_id, a_id);
+	assert_eq!(entries[0].detail["profileId"], test2.id);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn last_authority_administrator_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let a_id = create_authority(&env, "test").await;
+	let test = env.get_profile("test").await.unwrap();
+	let test2 = env.get_profile("test2").await.unwrap();
+
+	let response = import_members(&env, a_id, "test2@example.com\n").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+	let owner_id: i32 = conn
+		.interact(move |conn| {
+			use db::authority_role::dsl::*;
+			use diesel::prelude::*;
+
+			authority_role
+				.filter(authority_id.eq(a_id))
+				.select(id)
+				.get_result(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+
+	let own_url = format!("/authorities/{a_id}/members/{}", test.id);
+
+	// The creator is the only administrator, so it can neither drop its role
+	// nor leave the authority
+	let response = env
+		.app
+		.patch(&own_url)
+		.json(&serde_json::json!({ "roleId": null }))
+		.await;
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["details"]["reason"], "last_administrator");
+
+	let response = env.app.delete(&own_url).await;
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+	assert_eq!(member_count(&env, a_id).await, 2);
+
+	// Once another member is an administrator it can
+	let response = env
+		.app
+		.patch(&format!("/authorities/{a_id}/members/{}", test2.id))
+		.json(&serde_json::json!({ "roleId": owner_id }))
+		.await;
+	assert!(response.status_code().is_success());
+
+	let response = env.app.delete(&own_url).await;
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+	assert_eq!(member_count(&env, a_id).await, 1);
+}
+
+/// Count the members of an authority
+async fn member_count(env: &TestEnv, a_id: i32) -> usize {
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	Authority::get_members(a_id, &conn).await.unwrap().len()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn import_authority_members_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let a_id = create_authority(&env, "test").await;
+
+	let csv = "email,role
+test2@example.com,owner
+test-admin@example.com
+
+test2@example.com
+unknown@example.com,owner
+test-disabled@example.com,janitor
+test@example.com
+,owner
+";
+
+	let response = import_members(&env, a_id, csv).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<MemberImportResponse>();
+
+	assert_eq!(body.added, 2);
+
+	let skipped: Vec<_> =
+		body.skipped.iter().map(|i| (i.line, i.reason)).collect();
+	assert_eq!(
+		skipped,
+		[
+			(5, MemberImportIssueReason::DuplicateRow),
+			(8, MemberImportIssueReason::AlreadyMember),
+		]
+	);
+
+	let errors: Vec<_> =
+		body.errors.iter().map(|i| (i.line, i.reason)).collect();
+	assert_eq!(
+		errors,
+		[
+			(6, MemberImportIssueReason::UnknownEmail),
+			(7, MemberImportIssueReason::UnknownRole),
+			(9, MemberImportIssueReason::MalformedRow),
+		]
+	);
+	assert_eq!(body.errors[0].email, "unknown@example.com");
+
+	assert_eq!(member_count(&env, a_id).await, 3);
+
+	// Importing the same file again doesn't add anyone
+	let response = import_members(&env, a_id, csv).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<MemberImportResponse>();
+
+	assert_eq!(body.added, 0);
+	assert_eq!(body.skipped.len(), 4);
+	assert_eq!(member_count(&env, a_id).await, 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn import_authority_members_forbidden_test() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	let a_id = create_authority(&env, "test").await;
+
+	let response = import_members(&env, a_id, "test2@example.com\n").await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+	assert_eq!(member_count(&env, a_id).await, 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn import_authority_members_too_many_rows_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let a_id = create_authority(&env, "test").await;
+
+	let csv: String =
+		(0..=500).map(|i| format!("member{i}@example.com\n")).collect();
+
+	let response = import_members(&env, a_id, &csv).await;
+
+	assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+	let body = response.json::<serde_json::Value>();
+	assert!(body["details"]["fields"]["file"].is_string());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn import_authority_members_all_or_nothing_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	let a_id = create_authority(&env, "test").await;
+	let admin = env.get_admin_profile().await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	// Make the insert fail on the last row of the import
+	conn.interact(move |conn| {
+		diesel::sql_query(
+			"CREATE FUNCTION reject_member() RETURNS trigger AS $$
+			BEGIN
+				RAISE EXCEPTION 'rejected member';
+			END;
+			$$ LANGUAGE plpgsql",
+		)
+		.execute(conn)?;
+
+		diesel::sql_query(format!(
+			"CREATE TRIGGER reject_member
+			BEFORE INSERT ON authority_member
+			FOR EACH ROW
+			WHEN (NEW.profile_id = {})
+			EXECUTE FUNCTION reject_member()",
+			admin.id
+		))
+		.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let csv = "test2@example.com\ntest-admin@example.com\n";
+	let response = import_members(&env, a_id, csv).await;
+
+	assert_eq!(response.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+	assert_eq!(member_count(&env, a_id).await, 1);
+}
+
+/// Get the member and location count of an authority from the listing of all
+/// authorities
+async fn listed_authority_counts(
+	env: &TestEnv,
+	a_id: i32,
+) -> (Option<i64>, Option<i64>) {
+	let response =
+		env.app.get("/authorities").add_query_param("counts", true).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let authority = response
+		.json::<Vec<AuthorityResponse>>()
+		.into_iter()
+		.find(|a| a.id == a_id)
+		.unwrap();
+
+	(authority.member_count, authority.location_count)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_all_authorities_counts_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	let a_id = create_authority(&env, "test").await;
+	let test2 = env.get_profile("test2").await.unwrap();
+
+	let response = import_members(&env, a_id, "test2@example.com\n").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	conn.interact(move |conn| {
+		use db::location::dsl::*;
+
+		diesel::update(location.find(2))
+			.set(authority_id.eq(a_id))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	assert_eq!(listed_authority_counts(&env, a_id).await, (Some(2), Some(1)));
+
+	let response = env
+		.app
+		.delete(&format!("/authorities/{a_id}/members/{}", test2.id))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	assert_eq!(listed_authority_counts(&env, a_id).await, (Some(1), Some(1)));
+
+	// Without the flag the counts are left out of the response entirely
+	let response = env.app.get("/authorities").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<serde_json::Value>();
+	let authority =
+		body.as_array().unwrap().iter().find(|a| a["id"] == a_id).unwrap();
+
+	assert!(authority.get("memberCount").is_none());
+	assert!(authority.get("locationCount").is_none());
+}
+
+#[test]
+fn csv_field_escaping_test() {
+	assert_eq!(csv_field("Gent"), "Gent");
+	assert_eq!(csv_field("Gent, Belgium"), "\"Gent, Belgium\"");
+	assert_eq!(csv_field("Bib \"De Krook\""), "\"Bib \"\"De Krook\"\"\"");
+	assert_eq!(csv_field("line\nbreak"), "\"line\nbreak\"");
+}
+
+#[test]
+fn csv_field_formula_test() {
+	assert_eq!(csv_field("=1+1"), "'=1+1");
+	assert_eq!(csv_field("+32 9 123 45 67"), "'+32 9 123 45 67");
+	assert_eq!(csv_field("-2"), "'-2");
+	assert_eq!(csv_field("@SUM(A1:A2)"), "'@SUM(A1:A2)");
+	assert_eq!(csv_field("\tcmd"), "'\tcmd");
+	assert_eq!(csv_field("\rcmd"), "\"'\rcmd\"");
+	assert_eq!(
+		csv_field("=HYPERLINK(\"x\", \"y\")"),
+		"\"'=HYPERLINK(\"\"x\"\", \"\"y\"\")\""
+	);
+	assert_eq!(csv_field("Gent-Zuid"), "Gent-Zuid");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn export_authority_locations_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	let profile = env.get_profile("test").await.unwrap();
+	let a_id = create_authority(&env, "test").await;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	conn.interact(move |conn| {
+		use db::location::dsl::*;
+
+		diesel::update(location.filter(id.eq_any([1, 2])))
+			.set(authority_id.eq(Some(a_id)))
+			.execute(conn)?;
+
+		diesel::update(location.find(1))
+			.set(name.eq("Bib \"De Krook\", Gent"))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let in_range = (Utc::now() + Duration::days(7)).date_naive();
+	let out_of_range = (Utc::now() + Duration::days(14)).date_naive();
+
+	let times = [in_range, out_of_range].map(|day| {
+		NewOpeningTime {
+			location_id: 1,
+			day,
+			start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+			seat_count: None,
+			reservable_from: None,
+			reservable_until: None,
+			created_by: profile.id,
+		}
+	});
+
+	let times = NewOpeningTime::bulk_insert(
+		Vec::from(times),
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	// Two reservations in range, one out of range and one cancelled
+	let reservations = [
+		(times[0].id, 0),
+		(times[0].id, 8),
+		(times[0].id, 16),
+		(times[1].id, 0),
+	];
+
+	let mut r_ids = vec![];
+	for (opening_time_id, base_block_index) in reservations {
+		let reservation = NewReservation {
+			profile_id: profile.id,
+			opening_time_id,
+			base_block_index,
+			block_count: 4,
+		}
+		.insert(ReservationIncludes::default(), &conn)
+		.await
+		.unwrap();
+
+		r_ids.push(reservation.primitive.id);
+	}
+
+	let cancelled = r_ids[2];
+
+	conn.interact(move |conn| {
+		use db::reservation::dsl::*;
+
+		diesel::update(reservation.find(cancelled))
+			.set(state.eq(db::ReservationState::Cancelled))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let response = env
+		.app
+		.get(&format!("/authorities/{a_id}/locations/export"))
+		.add_query_param("from", in_range)
+		.add_query_param("to", in_range)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+	assert_eq!(
+		response.header("content-disposition"),
+		format!("attachment; filename=\"authority-{a_id}-locations.csv\""),
+	);
+
+	let body = response.text();
+	let lines: Vec<_> = body.lines().collect();
+
+	assert_eq!(lines.len(), 3);
+	assert!(lines[0].starts_with("id,name,"));
+	assert!(lines[1].starts_with("1,\"Bib \"\"De Krook\"\", Gent\","));
+	assert!(lines[1].ends_with(",2"));
+	assert!(lines[2].starts_with("2,"));
+	assert!(lines[2].ends_with(",0"));
+
+	let response = env
+		.app
+		.get(&format!("/authorities/{a_id}/locations/export"))
+		.add_query_param("from", out_of_range)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.text();
+	let lines: Vec<_> = body.lines().collect();
+
+	assert!(lines[1].ends_with(",1"));
+
+	// Profiles outside of the authority can't export its locations
+	let env = env.login("test2").await;
+
+	let response =
+		env.app.get(&format!("/authorities/{a_id}/locations/export")).await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+}
@@ -4,14 +4,18 @@ use axum_extra::extract::cookie::Key;
 use axum_test::TestServer;
 use blokmap::mailer::{Mailer, StubMailbox};
 use blokmap::schemas::auth::LoginRequest;
+use blokmap::webhooks::WebhookDispatcher;
 use blokmap::{AppState, Config, SeedProfile, Seeder, routes};
-use common::Error;
+use common::{Error, RedisConn};
+use db::AuditAction;
+use institution::{InstitutionIncludes, NewInstitution};
 use location::{Location, LocationIncludes, NewLocation};
 use mock_redis::{RedisUrlGuard, RedisUrlProvider};
 use opening_time::{NewOpeningTime, OpeningTime, OpeningTimeIncludes};
-use primitives::PrimitiveProfile;
+use primitives::{PrimitiveAuditLog, PrimitiveProfile};
 use profile::Profile;
-use reservation::NewReservation;
+use redis::cmd;
+use reservation::{NewReservation, Reservation, ReservationIncludes};
 use tag::{NewTag, TagIncludes};
 use translation::{NewTranslation, Translation, TranslationIncludes};
 
@@ -23,10 +27,12 @@ use mock_db::{DATABASE_PROVIDER, DatabaseGuard};
 
 #[allow(dead_code)]
 pub struct TestEnv {
-	pub app:          TestServer,
-	pub db_guard:     DatabaseGuard,
-	pub redis_guard:  RedisUrlGuard,
-	pub stub_mailbox: Arc<StubMailbox>,
+	pub app:              TestServer,
+	pub db_guard:         DatabaseGuard,
+	pub redis_guard:      RedisUrlGuard,
+	pub redis_connection: RedisConn,
+	pub stub_mailbox:     Arc<StubMailbox>,
+	pub mailer:           Mailer,
 }
 
 impl TestEnv {
@@ -41,6 +47,8 @@ impl TestEnv {
 
 		config.production = true;
 		config.skip_verify = false;
+		// Webhook tests deliver to a receiver on localhost
+		config.webhook_allow_private_hosts = true;
 
 		// Create a test database pool
 		tracing::info!("acquiring db guard");
@@ -59,8 +67,9 @@ impl TestEnv {
 
 			// Seed profiles
 			seeder
-				.populate(
+				.populate_if_empty(
 					"tests/seed/profiles.json",
+					async |conn| Seeder::table_has_rows(conn, "profile").await,
 					async |conn, records: Vec<SeedProfile>| {
 						conn.interact(move |conn| {
 							use db::profile::dsl::*;
@@ -80,8 +89,11 @@ impl TestEnv {
 
 			// Seed translations
 			seeder
-				.populate(
+				.populate_if_empty(
 					"tests/seed/translations.json",
+					async |conn| {
+						Seeder::table_has_rows(conn, "translation").await
+					},
 					async |conn, records: Vec<NewTranslation>| {
 						conn.interact(move |conn| {
 							use db::translation::dsl::*;
@@ -101,8 +113,9 @@ impl TestEnv {
 
 			// Seed locations
 			seeder
-				.populate(
+				.populate_if_empty(
 					"tests/seed/locations.json",
+					async |conn| Seeder::table_has_rows(conn, "location").await,
 					async |conn, locations: Vec<NewLocation>| {
 						for location in locations {
 							location
@@ -115,10 +128,32 @@ impl TestEnv {
 				)
 				.await;
 
+			// Seed institutions
+			seeder
+				.populate_if_empty(
+					"tests/seed/institutions.json",
+					async |conn| {
+						Seeder::table_has_rows(conn, "institution").await
+					},
+					async |conn, institutions: Vec<NewInstitution>| {
+						for institution in institutions {
+							institution
+								.insert(InstitutionIncludes::default(), conn)
+								.await?;
+						}
+
+						Ok(())
+					},
+				)
+				.await;
+
 			// Seed opening times
 			seeder
-				.populate(
+				.populate_if_empty(
 					"tests/seed/opening-times.json",
+					async |conn| {
+						Seeder::table_has_rows(conn, "opening_time").await
+					},
 					async |conn, records: Vec<NewOpeningTime>| {
 						conn.interact(move |conn| {
 							use db::opening_time::dsl::*;
@@ -138,8 +173,9 @@ impl TestEnv {
 
 			// Seed tags
 			seeder
-				.populate(
+				.populate_if_empty(
 					"tests/seed/tags.json",
+					async |conn| Seeder::table_has_rows(conn, "tag").await,
 					async |conn, tags: Vec<NewTag>| {
 						for tag in tags {
 							tag.insert(TagIncludes::default(), conn).await?;
@@ -152,8 +188,11 @@ impl TestEnv {
 
 			// Seed reservations
 			seeder
-				.populate(
+				.populate_if_empty(
 					"tests/seed/reservations.json",
+					async |conn| {
+						Seeder::table_has_rows(conn, "reservation").await
+					},
 					async |conn, records: Vec<NewReservation>| {
 						conn.interact(move |conn| {
 							use db::reservation::dsl::*;
@@ -183,25 +222,38 @@ impl TestEnv {
 		let stub_mailbox = config.create_stub_mailbox();
 
 		// Create a test Mailer
-		let mailer = Mailer::new(&config, stub_mailbox.clone());
+		let mailer = Mailer::new(
+			&config,
+			redis_connection.clone(),
+			stub_mailbox.clone(),
+		);
+
+		// Create a webhook dispatcher
+		let webhooks = WebhookDispatcher::new(
+			test_pool.clone(),
+			config.webhook_allow_private_hosts,
+		);
 
 		// Create the test app.
 		let app = routes::get_app_router(AppState {
 			config,
 			database_pool: test_pool.clone(),
-			redis_connection,
+			redis_connection: redis_connection.clone(),
 			cookie_jar_key,
-			mailer,
+			mailer: mailer.clone(),
+			webhooks,
 		});
 
 		let test_server =
 			TestServer::builder().save_cookies().build(app).unwrap();
 
 		TestEnv {
-			app:          test_server,
-			db_guard:     test_pool_guard,
-			redis_guard:  redis_url_guard,
+			app: test_server,
+			db_guard: test_pool_guard,
+			redis_guard: redis_url_guard,
+			redis_connection,
 			stub_mailbox: stub_mailbox.unwrap(),
+			mailer,
 		}
 	}
 
@@ -228,6 +280,24 @@ impl TestEnv {
 }
 
 impl TestEnv {
+	/// Kill the Redis connection of the app, as if Redis went down
+	#[allow(dead_code)]
+	pub async fn kill_redis_connection(&self) {
+		let mut conn = self.redis_connection.clone();
+		let id: i64 =
+			cmd("CLIENT").arg("ID").query_async(&mut conn).await.unwrap();
+
+		// A connection can not kill itself, so use a fresh one
+		let mut other = self.redis_guard.connect().await;
+		let _: i64 = cmd("CLIENT")
+			.arg("KILL")
+			.arg("ID")
+			.arg(id)
+			.query_async(&mut other)
+			.await
+			.unwrap();
+	}
+
 	/// Get a test user profile from the test database
 	#[allow(dead_code)]
 	pub async fn get_profile(
@@ -271,4 +341,59 @@ impl TestEnv {
 		let conn = self.db_guard.create_pool().get().await.unwrap();
 		OpeningTime::get_by_id(1, OpeningTimeIncludes::default(), &conn).await
 	}
+
+	/// Insert a reservation without the checks of [`NewReservation::insert`]
+	///
+	/// Opening times that already ended can't be reserved, this allows
+	/// giving profiles reservations in the past
+	#[allow(dead_code)]
+	pub async fn insert_reservation_unchecked(
+		&self,
+		new_reservation: NewReservation,
+	) -> Reservation {
+		use diesel::prelude::*;
+
+		let conn = self.db_guard.create_pool().get().await.unwrap();
+
+		let r_id = conn
+			.interact(move |conn| {
+				use db::reservation::dsl::*;
+
+				diesel::insert_into(reservation)
+					.values(new_reservation)
+					.returning(id)
+					.get_result::<i32>(conn)
+			})
+			.await
+			.unwrap()
+			.unwrap();
+
+		Reservation::get_by_id(r_id, ReservationIncludes::default(), &conn)
+			.await
+			.unwrap()
+	}
+
+	/// Get all audit log entries of an action, oldest first
+	#[allow(dead_code)]
+	pub async fn get_audit_entries(
+		&self,
+		audit_action: AuditAction,
+	) -> Vec<PrimitiveAuditLog> {
+		use diesel::prelude::*;
+
+		let conn = self.db_guard.create_pool().get().await.unwrap();
+
+		conn.interact(move |conn| {
+			use db::audit_log::dsl::*;
+
+			audit_log
+				.filter(action.eq(audit_action))
+				.order(id)
+				.select(PrimitiveAuditLog::as_select())
+				.get_results(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap()
+	}
 }
@@ -104,4 +104,46 @@ impl TestEnv {
 
 		result
 	}
+
+	/// Call a closure and check that it creates one email for each of the
+	/// given receivers, in the same order
+	#[allow(dead_code)]
+	pub async fn expect_mails_to<F, T>(&self, receivers: &[&str], f: F) -> T
+	where
+		F: AsyncFnOnce() -> T,
+	{
+		let outbox_size = { self.stub_mailbox.mailbox.lock().len() };
+
+		let result = f().await;
+
+		// Wait for up to 1 second per email or until all of them are received
+		let mut mailbox = self.stub_mailbox.mailbox.lock();
+
+		while mailbox.len() < outbox_size + receivers.len() {
+			let wait_res = self
+				.stub_mailbox
+				.mail_signal
+				.wait_for(&mut mailbox, Duration::from_secs(1));
+
+			assert!(!wait_res.timed_out(), "timed out waiting for email");
+		}
+
+		assert_eq!(
+			mailbox.len(),
+			outbox_size + receivers.len(),
+			"unexpected amount of emails"
+		);
+
+		for (mail, receiver) in mailbox[outbox_size..].iter().zip(receivers) {
+			let receiver = receiver.parse::<Address>().unwrap();
+
+			assert_eq!(
+				mail.envelope().to(),
+				[receiver],
+				"unexpected receivers"
+			);
+		}
+
+		result
+	}
 }
@@ -0,0 +1,114 @@
+use axum::http::StatusCode;
+use blokmap::schemas::auth::RegisterRequest;
+use blokmap::schemas::review::ReviewResponse;
+
+mod common;
+
+use common::TestEnv;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn not_found_error_code_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env.app.get("/locations/999999").await;
+
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["code"], "NOT_FOUND");
+	assert_eq!(body["details"]["reason"], "not_found");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn forbidden_error_code_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let location = env.get_location().await.unwrap();
+
+	let response = env
+		.app
+		.post(format!("/locations/{}/approve", location.primitive.id).as_str())
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["code"], "FORBIDDEN");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn unauthorized_error_code_test() {
+	let env = TestEnv::new().await;
+
+	let response = env.app.get("/profiles").await;
+
+	assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["code"], "UNAUTHORIZED");
+	assert_eq!(body["details"]["reason"], "missing_access_token");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn validation_error_code_test() {
+	let env = TestEnv::new().await;
+
+	let response = env
+		.expect_no_mail(async || {
+			env.app
+				.post("/auth/register")
+				.json(&RegisterRequest {
+					username:   "123".to_string(),
+					password:   "bobdebouwer1234!".to_string(),
+					email:      "bob@example.com".to_string(),
+					first_name: "Bob".to_string(),
+					last_name:  "De Bouwer".to_string(),
+				})
+				.await
+		})
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["code"], "VALIDATION_FAILED");
+	assert!(body["details"]["fields"]["username"].is_string());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn conflict_error_code_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let location = env.get_location().await.unwrap();
+
+	let review = env
+		.app
+		.post(format!("/locations/{}/reviews", location.primitive.id).as_str())
+		.json(&serde_json::json!({
+			"rating": 4,
+			"body": "test review",
+		}))
+		.await
+		.json::<ReviewResponse>();
+
+	env.app
+		.post(format!("/reviews/{}/flag", review.id).as_str())
+		.json(&serde_json::json!({
+			"reason": "inappropriate",
+		}))
+		.await;
+
+	let response = env
+		.app
+		.post(format!("/reviews/{}/flag", review.id).as_str())
+		.json(&serde_json::json!({
+			"reason": "inappropriate",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["code"], "CONFLICT");
+	assert_eq!(body["details"]["resourceId"], review.id);
+}
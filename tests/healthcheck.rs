@@ -0,0 +1,59 @@
+use axum::http::StatusCode;
+use blokmap::schemas::health::{HealthResponse, HealthStatus};
+
+mod common;
+
+use common::TestEnv;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shallow_healthcheck_test() {
+	let env = TestEnv::new().await;
+
+	for path in ["/healthcheck", "/healthz"] {
+		let response = env.app.get(path).await;
+
+		assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+	}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn deep_healthcheck_test() {
+	let env = TestEnv::new().await;
+
+	let response = env.app.get("/healthz").add_query_param("deep", true).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let health = response.json::<HealthResponse>();
+
+	assert_eq!(health.database.status, HealthStatus::Ok);
+	assert!(health.database.latency_ms.is_some());
+	assert_eq!(health.redis.status, HealthStatus::Ok);
+	assert!(health.redis.latency_ms.is_some());
+
+	// Tests deliver mails into a stub mailbox
+	assert_eq!(health.mailer.status, HealthStatus::Skipped);
+	assert!(health.mailer.latency_ms.is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn deep_healthcheck_redis_down_test() {
+	let env = TestEnv::new().await;
+
+	env.kill_redis_connection().await;
+
+	let response = env.app.get("/healthz").add_query_param("deep", true).await;
+
+	assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+
+	let health = response.json::<HealthResponse>();
+
+	assert_eq!(health.database.status, HealthStatus::Ok);
+	assert_eq!(health.redis.status, HealthStatus::Failing);
+	assert_eq!(health.mailer.status, HealthStatus::Skipped);
+
+	// Liveness probes only look at the database
+	let response = env.app.get("/healthz").await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+}
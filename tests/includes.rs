@@ -0,0 +1,94 @@
+use axum::http::StatusCode;
+use blokmap::schemas::includes::parse_includes;
+use blokmap::schemas::location::LocationResponse;
+use location::LocationIncludes;
+use reservation::ReservationIncludes;
+use serde_json::{Value, json};
+
+mod common;
+
+use common::TestEnv;
+
+#[test]
+fn parse_includes_flag_spellings_test() {
+	let includes = parse_includes::<LocationIncludes>(
+		"authority=1&approved_by=TRUE&rejected_by=Yes&created_by=no&\
+		 updated_by=0",
+	)
+	.unwrap();
+
+	assert!(includes.authority);
+	assert!(includes.approved_by);
+	assert!(includes.rejected_by);
+	assert!(!includes.created_by);
+	assert!(!includes.updated_by);
+}
+
+#[test]
+fn parse_includes_ignores_other_parameters_test() {
+	let includes = parse_includes::<ReservationIncludes>(
+		"profile=yes&page=1&perPage=10&date=2025-01-01",
+	)
+	.unwrap();
+
+	assert!(includes.profile);
+	assert!(!includes.confirmed_by);
+}
+
+#[test]
+fn parse_includes_invalid_flag_test() {
+	let result = parse_includes::<ReservationIncludes>("profile=maybe");
+
+	assert!(result.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn include_numeric_and_named_flags_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let mut bodies = vec![];
+	for value in ["1", "true"] {
+		let response = env
+			.app
+			.get("/locations/1")
+			.add_query_param("created_by", value)
+			.await;
+
+		assert_eq!(response.status_code(), StatusCode::OK);
+
+		let location = response.json::<LocationResponse>();
+
+		assert_eq!(location.created_by.unwrap().unwrap().id, 1);
+
+		bodies.push(response.json::<Value>());
+	}
+
+	assert_eq!(bodies[0], bodies[1]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn include_unknown_key_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response =
+		env.app.get("/locations/1").add_query_param("creatd_by", true).await;
+
+	assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+	let body = response.json::<Value>();
+
+	assert_eq!(body["code"], "BAD_REQUEST");
+	assert_eq!(body["details"]["reason"], "unknown_includes");
+	assert_eq!(body["details"]["unknownKeys"], json!(["creatd_by"]));
+
+	let valid = body["details"]["validKeys"].as_array().unwrap();
+
+	assert!(valid.contains(&Value::from("created_by")));
+	assert!(valid.contains(&Value::from("lang")));
+
+	// Keys that look nothing like an include are left to other parameters
+	let response =
+		env.app.get("/locations/1").add_query_param("unrelated", true).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+}
@@ -0,0 +1,698 @@
+use authority::{AuthorityIncludes, NewAuthority, NewAuthorityMember};
+use axum::http::StatusCode;
+use blokmap::schemas::institution::{
+	InstitutionResponse,
+	InstitutionStatisticsResponse,
+};
+use blokmap::schemas::location::LocationResponse;
+use blokmap::schemas::pagination::PaginatedResponse;
+use blokmap::schemas::tag::TagUsageResponse;
+use chrono::{Duration, NaiveTime, Utc};
+use db::{AuditAction, InstitutionCategory};
+use institution::NewInstitutionMember;
+use opening_time::{NewOpeningTime, OpeningTimeIncludes};
+use reservation::{NewReservation, ReservationIncludes};
+
+mod common;
+
+use common::TestEnv;
+
+async fn get_institutions_by_category(
+	env: &TestEnv,
+	category: &str,
+) -> Vec<InstitutionResponse> {
+	let response = env
+		.app
+		.get("/institutions")
+		.add_query_param("category", category)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	response.json::<PaginatedResponse<Vec<InstitutionResponse>>>().data
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_institutions_education_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let institutions = get_institutions_by_category(&env, "Education").await;
+
+	assert_eq!(institutions.len(), 1);
+	assert_eq!(institutions[0].slug, "ugent");
+	assert_eq!(institutions[0].category, InstitutionCategory::Education);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_institutions_organisation_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let institutions = get_institutions_by_category(&env, "Organisation").await;
+
+	assert_eq!(institutions.len(), 1);
+	assert_eq!(institutions[0].slug, "studentenvereniging-gent");
+	assert_eq!(institutions[0].category, InstitutionCategory::Organisation);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_institutions_government_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let institutions = get_institutions_by_category(&env, "Government").await;
+
+	assert_eq!(institutions.len(), 1);
+	assert_eq!(institutions[0].slug, "stad-gent");
+	assert_eq!(institutions[0].category, InstitutionCategory::Government);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_institutions_unknown_category_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env
+		.app
+		.get("/institutions")
+		.add_query_param("category", "University")
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_categories_match_filter_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env.app.get("/institutions/categories").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let categories = response.json::<Vec<String>>();
+	assert_eq!(categories.len(), 3);
+
+	// Every listed category should be usable as a filter
+	for category in categories {
+		let institutions = get_institutions_by_category(&env, &category).await;
+		assert!(!institutions.is_empty());
+	}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_institution_duplicate_name_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	// The seeded education institution already uses this name
+	let response = env
+		.app
+		.post("/institutions")
+		.json(&serde_json::json!({
+			"nameTranslation": {
+				"nl": "Universiteit Gent",
+			},
+			"category": "Education",
+			"slug": "ugent-2",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	// The same name is fine in another category
+	let response = env
+		.app
+		.post("/institutions")
+		.json(&serde_json::json!({
+			"nameTranslation": {
+				"nl": "Universiteit Gent",
+			},
+			"category": "Government",
+			"slug": "ugent-2",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+}
+
+/// Create a location through the API and return its id
+async fn create_location(env: &TestEnv, name: &str) -> i32 {
+	let response = env
+		.app
+		.post("/locations")
+		.json(&serde_json::json!({
+			"name": name,
+			"description": {
+				"nl": "test description",
+			},
+			"excerpt": {
+				"nl": "test excerpt",
+			},
+			"seatCount": 10,
+			"isReservable": true,
+			"isVisible": true,
+			"street": "Test Street",
+			"number": "123",
+			"zip": "9000",
+			"city": "Gent",
+			"province": "Oost-Vlaanderen",
+			"country": "BE",
+			"latitude": 51.0,
+			"longitude": 3.7
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	response.json::<LocationResponse>().id
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_institution_tags_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let inst_id = conn
+		.interact(|conn| {
+			use db::institution::dsl::*;
+
+			institution
+				.filter(slug.eq("ugent"))
+				.select(id)
+				.get_result::<i32>(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+
+	let third = create_location(&env, "Third Location").await;
+	let fourth = create_location(&env, "Fourth Location").await;
+
+	// Two authorities with two locations each
+	let authorities = [
+		("Faculteit Wetenschappen", [1, 2]),
+		("Faculteit Letteren", [third, fourth]),
+	];
+
+	for (name, l_ids) in authorities {
+		let authority = NewAuthority {
+			name:           name.to_string(),
+			description:    None,
+			created_by:     profile.id,
+			institution_id: Some(inst_id),
+		}
+		.insert(AuthorityIncludes::default(), &conn)
+		.await
+		.unwrap();
+
+		let a_id = authority.primitive.id;
+
+		conn.interact(move |conn| {
+			use db::location::dsl::*;
+
+			diesel::update(location.filter(id.eq_any(l_ids)))
+				.set(authority_id.eq(Some(a_id)))
+				.execute(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+	}
+
+	// Tag 1 is used by three locations, tag 2 by two of them
+	let location_tags = [(1, 1), (1, 2), (2, 1), (third, 1), (third, 2)];
+
+	conn.interact(move |conn| {
+		use db::location_tag::dsl::*;
+
+		let rows = location_tags
+			.into_iter()
+			.map(|(l_id, t_id)| (location_id.eq(l_id), tag_id.eq(t_id)))
+			.collect::<Vec<_>>();
+
+		diesel::insert_into(location_tag).values(rows).execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	// Tag usages are public
+	env.app.post("/auth/logout").await;
+
+	let response = env.app.get(&format!("/institutions/{inst_id}/tags")).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let usages = response.json::<Vec<TagUsageResponse>>();
+
+	assert_eq!(usages.len(), 2);
+	assert_eq!(usages[0].tag.id, 1);
+	assert_eq!(usages[0].usage_count, 3);
+	assert_eq!(usages[1].tag.id, 2);
+	assert_eq!(usages[1].usage_count, 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_institution_statistics_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	let profile = env.get_profile("test").await.unwrap();
+	let test2 = env.get_profile("test2").await.unwrap();
+	let admin = env.get_profile("test-admin").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let inst_id = conn
+		.interact(|conn| {
+			use db::institution::dsl::*;
+
+			institution
+				.filter(slug.eq("ugent"))
+				.select(id)
+				.get_result::<i32>(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+
+	let third = create_location(&env, "Third Location").await;
+	let fourth = create_location(&env, "Fourth Location").await;
+	let fifth = create_location(&env, "Fifth Location").await;
+
+	// Two authorities of the institution and one standalone authority, each
+	// with its own locations and extra members
+	let authorities = [
+		("Faculteit Wetenschappen", Some(inst_id), vec![1, 2], vec![test2.id]),
+		(
+			"Faculteit Letteren",
+			Some(inst_id),
+			vec![third, fourth],
+			vec![test2.id, admin.id],
+		),
+		("Stadsbibliotheek", None, vec![fifth], vec![]),
+	];
+
+	for (name, institution_id, l_ids, p_ids) in authorities {
+		let authority = NewAuthority {
+			name: name.to_string(),
+			description: None,
+			created_by: profile.id,
+			institution_id,
+		}
+		.insert(AuthorityIncludes::default(), &conn)
+		.await
+		.unwrap();
+
+		let a_id = authority.primitive.id;
+
+		conn.interact(move |conn| {
+			use db::location::dsl::*;
+
+			diesel::update(location.filter(id.eq_any(l_ids)))
+				.set(authority_id.eq(Some(a_id)))
+				.execute(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+
+		for p_id in p_ids {
+			NewAuthorityMember {
+				authority_id:      a_id,
+				profile_id:        p_id,
+				authority_role_id: None,
+				added_by:          profile.id,
+			}
+			.insert(&conn)
+			.await
+			.unwrap();
+		}
+	}
+
+	// Location 1 is approved and location 2 is rejected, leaving the third
+	// and fourth location pending
+	conn.interact(move |conn| {
+		use db::location::dsl::*;
+
+		diesel::update(location.filter(id.eq(1)))
+			.set((approved_at.eq(Utc::now().naive_utc()), approved_by.eq(1)))
+			.execute(conn)?;
+
+		diesel::update(location.filter(id.eq(2)))
+			.set((rejected_at.eq(Utc::now().naive_utc()), rejected_by.eq(1)))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	// Location 1 already has a seeded reservation, add two more to the third
+	// location and one to the standalone location
+	let day = (Utc::now() + Duration::days(7)).date_naive();
+	let times = [third, fifth].map(|location_id| {
+		NewOpeningTime {
+			location_id,
+			day,
+			start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+			seat_count: None,
+			reservable_from: None,
+			reservable_until: None,
+			created_by: profile.id,
+		}
+	});
+
+	let times = NewOpeningTime::bulk_insert(
+		Vec::from(times),
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	for (time_id, base_block_index) in
+		[(times[0].id, 0), (times[0].id, 8), (times[1].id, 0)]
+	{
+		NewReservation {
+			profile_id: profile.id,
+			opening_time_id: time_id,
+			base_block_index,
+			block_count: 4,
+		}
+		.insert(ReservationIncludes::default(), &conn)
+		.await
+		.unwrap();
+	}
+
+	let response =
+		env.app.get(&format!("/institutions/{inst_id}/statistics")).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let statistics = response.json::<InstitutionStatisticsResponse>();
+
+	assert_eq!(statistics.authority_count, 2);
+	assert_eq!(statistics.total_location_count, 4);
+	assert_eq!(statistics.pending_location_count, 2);
+	assert_eq!(statistics.total_member_count, 3);
+	assert_eq!(statistics.total_reservation_count, 3);
+
+	// Profiles outside of the institution can't see its statistics
+	let env = env.login("test2").await;
+
+	let response =
+		env.app.get(&format!("/institutions/{inst_id}/statistics")).await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+}
+
+/// Create an institution through the API as the logged in profile, making
+/// them its administrator
+async fn create_institution(env: &TestEnv, slug: &str) -> InstitutionResponse {
+	let response = env
+		.app
+		.post("/institutions")
+		.json(&serde_json::json!({
+			"nameTranslation": {
+				"nl": "Tijdelijke Instelling",
+			},
+			"category": "Organisation",
+			"slug": slug,
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	response.json::<InstitutionResponse>()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_institution_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let institution = create_institution(&env, "tijdelijk").await;
+	let url = format!("/institutions/{}", institution.id);
+
+	let env = env.login("test2").await;
+
+	let response = env
+		.app
+		.patch(&url)
+		.json(&serde_json::json!({ "email": "info@example.com" }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let env = env.login("test").await;
+
+	let response = env
+		.app
+		.patch(&url)
+		.json(&serde_json::json!({
+			"nameTranslation": {
+				"nl": "Permanente Instelling",
+			},
+			"email": "info@example.com",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<InstitutionResponse>();
+	assert_eq!(body.email, Some("info@example.com".to_string()));
+	assert_eq!(
+		body.name_translation.nl,
+		Some("Permanente Instelling".to_string())
+	);
+	assert_eq!(body.slug, "tijdelijk");
+
+	// Names of other institutions in the same category stay off limits
+	let response = env
+		.app
+		.patch(&url)
+		.json(&serde_json::json!({
+			"nameTranslation": {
+				"nl": "Universiteit Gent",
+			},
+			"category": "Education",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn delete_institution_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let profile = env.get_profile("test").await.unwrap();
+	let institution = create_institution(&env, "tijdelijk").await;
+	let url = format!("/institutions/{}", institution.id);
+
+	let env = env.login("test2").await;
+
+	let response = env.app.delete(&url).await;
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let env = env.login("test").await;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let authority = NewAuthority {
+		name:           "Tijdelijke Afdeling".to_string(),
+		description:    None,
+		created_by:     profile.id,
+		institution_id: Some(institution.id),
+	}
+	.insert(AuthorityIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let response = env.app.delete(&url).await;
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["details"]["reason"], "institution_in_use");
+	assert_eq!(
+		body["details"]["authorityIds"],
+		serde_json::json!([authority.primitive.id])
+	);
+
+	// Unlinking the authority unblocks the deletion
+	let a_id = authority.primitive.id;
+
+	conn.interact(move |conn| {
+		use db::authority::dsl::*;
+		use diesel::prelude::*;
+
+		diesel::update(authority.find(a_id))
+			.set(institution_id.eq(None::<i32>))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let response = env.app.delete(&url).await;
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let response = env.app.get(&url).await;
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+	// Only the deletion that went through is recorded
+	let entries = env.get_audit_entries(AuditAction::InstitutionDeleted).await;
+	assert_eq!(entries.len(), 1);
+	assert_eq!(entries[0].target_id, institution.id);
+	assert_eq!(entries[0].actor_id, Some(profile.id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_institution_member_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let profile = env.get_profile("test").await.unwrap();
+	let test2 = env.get_profile("test2").await.unwrap();
+	let institution = create_institution(&env, "tijdelijk").await;
+	let inst_id = institution.id;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	NewInstitutionMember {
+		institution_id:      inst_id,
+		profile_id:          test2.id,
+		institution_role_id: None,
+		added_by:            profile.id,
+	}
+	.insert(&conn)
+	.await
+	.unwrap();
+
+	let response = env
+		.app
+		.patch(&format!("/institutions/{inst_id}/members/{}", test2.id))
+		.json(&serde_json::json!({ "roleId": null }))
+		.await;
+
+	assert!(response.status_code().is_success());
+
+	let entries = env.get_audit_entries(AuditAction::MemberRoleChanged).await;
+	assert_eq!(entries.len(), 1);
+	assert_eq!(entries[0].target_id, inst_id);
+	assert_eq!(entries[0].detail["profileId"], test2.id);
+}
+
+/// Get the member and location count of an institution from the listing of
+/// all institutions
+async fn listed_institution_counts(
+	env: &TestEnv,
+	inst_id: i32,
+) -> (Option<i64>, Option<i64>) {
+	let response = env
+		.app
+		.get("/institutions")
+		.add_query_param("category", "Organisation")
+		.add_query_param("counts", true)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let institution = response
+		.json::<PaginatedResponse<Vec<InstitutionResponse>>>()
+		.data
+		.into_iter()
+		.find(|i| i.id == inst_id)
+		.unwrap();
+
+	(institution.member_count, institution.location_count)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_all_institutions_counts_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	let institution = create_institution(&env, "tijdelijk").await;
+	let inst_id = institution.id;
+
+	let profile = env.get_profile("test").await.unwrap();
+	let test2 = env.get_profile("test2").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	NewInstitutionMember {
+		institution_id:      inst_id,
+		profile_id:          test2.id,
+		institution_role_id: None,
+		added_by:            profile.id,
+	}
+	.insert(&conn)
+	.await
+	.unwrap();
+
+	// Locations are counted through the authorities of the institution
+	let authority = NewAuthority {
+		name:           "Tijdelijke Faculteit".to_string(),
+		description:    None,
+		created_by:     profile.id,
+		institution_id: Some(inst_id),
+	}
+	.insert(AuthorityIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let a_id = authority.primitive.id;
+
+	conn.interact(move |conn| {
+		use db::location::dsl::*;
+
+		diesel::update(location.filter(id.eq_any([1, 2])))
+			.set(authority_id.eq(a_id))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	assert_eq!(
+		listed_institution_counts(&env, inst_id).await,
+		(Some(2), Some(2))
+	);
+
+	let response = env
+		.app
+		.delete(&format!("/institutions/{inst_id}/members/{}", test2.id))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	assert_eq!(
+		listed_institution_counts(&env, inst_id).await,
+		(Some(1), Some(2))
+	);
+
+	// Without the flag the counts are left out of the response entirely
+	let response = env
+		.app
+		.get("/institutions")
+		.add_query_param("category", "Organisation")
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<serde_json::Value>();
+	let institution = body["data"]
+		.as_array()
+		.unwrap()
+		.iter()
+		.find(|i| i["id"] == inst_id)
+		.unwrap();
+
+	assert!(institution.get("memberCount").is_none());
+	assert!(institution.get("locationCount").is_none());
+}
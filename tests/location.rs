@@ -1,8 +1,64 @@
 mod common;
-use axum::http::StatusCode;
-use blokmap::schemas::location::LocationResponse;
+use authority::{AuthorityIncludes, NewAuthority};
+use axum::http::{HeaderValue, StatusCode, header};
+use axum_test::multipart::{MultipartForm, Part};
+use base::RESERVATION_BLOCK_SIZE_MINUTES;
+use blokmap::Config;
+use blokmap::schemas::image::{ApprovedImagesResponse, ImageResponse};
+use blokmap::schemas::location::{
+	GeoJsonType,
+	LocationAvailabilityResponse,
+	LocationDeletionResponse,
+	LocationFeatureCollectionResponse,
+	LocationMergeResponse,
+	LocationReservationCountResponse,
+	LocationResponse,
+	LocationStatsResponse,
+	ZipLocationCountResponse,
+};
 use blokmap::schemas::pagination::PaginatedResponse;
+use blokmap::schemas::permissions::{
+	AuthorityPermissionsResponse,
+	LocationPermissionsResponse,
+};
+use blokmap::schemas::profile::ProfileResponse;
+use blokmap::schemas::role::RoleResponse;
+use blokmap::schemas::translation::LocationTranslationsResponse;
+use chrono::{Duration, NaiveTime, Utc};
 use common::TestEnv;
+use db::AuditAction;
+use image::NewImage;
+use institution::NewInstitutionMember;
+use location::{
+	Location,
+	LocationIncludes,
+	LocationMemberUpdate,
+	LocationUpdate,
+	NewLocationMember,
+};
+use opening_time::{NewOpeningTime, OpeningTime, OpeningTimeIncludes};
+use permissions::{InstitutionPermissions, LocationPermissions};
+use reservation::{NewReservation, Reservation, ReservationIncludes};
+use review::NewReview;
+use role::{NewInstitutionRole, NewLocationRole, RoleIncludes};
+
+/// Approve the seeded locations, so they show up in the public listing
+async fn approve_seeded_locations(env: &TestEnv) {
+	use diesel::prelude::*;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	conn.interact(|conn| {
+		use db::location::dsl::*;
+
+		diesel::update(location.filter(id.eq_any([1, 2])))
+			.set(approved_at.eq(Utc::now().naive_utc()))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+}
 
 #[tokio::test(flavor = "multi_thread")]
 async fn create_location_test() {
@@ -46,6 +102,49 @@ async fn create_location_test() {
 	assert!(excerpt.nl.is_some());
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn create_location_multipart_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let form = MultipartForm::new()
+		.add_text("name", "Test Location")
+		.add_text("descriptionNl", "test beschrijving")
+		.add_text("descriptionEn", "test description")
+		.add_text("excerptNl", "test excerpt")
+		.add_text("seatCount", "10")
+		.add_text("isReservable", "true")
+		.add_text("isVisible", "true")
+		.add_text("street", "Test Street")
+		.add_text("number", "123")
+		.add_text("zip", "1234AB")
+		.add_text("city", "Test City")
+		.add_text("province", "Test Province")
+		.add_text("country", "BE")
+		.add_text("latitude", "52.0")
+		.add_text("longitude", "4.0");
+
+	let response = env.app.post("/locations/multipart").multipart(form).await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let location = response.json::<LocationResponse>();
+	assert_eq!(location.name, "Test Location");
+	assert_eq!(location.max_reservation_length, None);
+
+	let description = location.description.unwrap();
+	assert_eq!(description.nl.as_deref(), Some("test beschrijving"));
+	assert_eq!(description.en.as_deref(), Some("test description"));
+
+	// Missing the required seat count
+	let form = MultipartForm::new()
+		.add_text("name", "Test Location")
+		.add_text("isReservable", "true");
+
+	let response = env.app.post("/locations/multipart").multipart(form).await;
+
+	assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn get_location_test() {
 	let env = TestEnv::new().await;
@@ -73,6 +172,16 @@ async fn get_locations_test() {
 	// Get a test location in the database
 	let location = env.get_location().await.unwrap();
 
+	// Unapproved locations are not listed
+	let response = env.app.get("/locations").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let locations = response.json::<PaginatedResponse<Vec<LocationResponse>>>();
+	assert!(!locations.data.iter().any(|l| l.id == location.primitive.id));
+
+	approve_seeded_locations(&env).await;
+
 	let response = env.app.get("/locations").await;
 
 	assert_eq!(response.status_code(), StatusCode::OK);
@@ -111,6 +220,81 @@ async fn search_locations_test() {
 	assert!(locations.data.iter().any(|l| l.name == location.primitive.name));
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn search_locations_geojson_test() {
+	let env = TestEnv::new().await;
+
+	let location = env.get_location().await.unwrap();
+	let lat = location.primitive.latitude;
+	let lng = location.primitive.longitude;
+
+	let bounds = [
+		("northEastLat", lat + 1.0),
+		("northEastLng", lng + 1.0),
+		("southWestLat", lat - 1.0),
+		("southWestLng", lng - 1.0),
+	];
+
+	let response = env
+		.app
+		.get("/locations")
+		.add_query_params(bounds)
+		.add_query_param("format", "geojson")
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+	assert_eq!(response.header(header::CONTENT_TYPE), "application/geo+json");
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["type"], "FeatureCollection");
+	assert_eq!(body["page"], 1);
+	assert!(body["total"].is_u64());
+	assert!(body.get("data").is_none());
+
+	let features = body["features"].as_array().unwrap();
+	assert!(!features.is_empty());
+
+	for feature in features {
+		assert_eq!(feature["type"], "Feature");
+		assert_eq!(feature["geometry"]["type"], "Point");
+		assert!(feature["properties"].is_object());
+
+		let coordinates =
+			feature["geometry"]["coordinates"].as_array().unwrap();
+		assert_eq!(coordinates.len(), 2);
+		assert!(coordinates.iter().all(serde_json::Value::is_f64));
+	}
+
+	let feature =
+		features.iter().find(|f| f["id"] == location.primitive.id).unwrap();
+
+	// GeoJSON positions are ordered longitude first
+	assert_eq!(
+		feature["geometry"]["coordinates"],
+		serde_json::json!([lng, lat])
+	);
+	assert_eq!(feature["properties"]["id"], location.primitive.id);
+	assert_eq!(feature["properties"]["name"], location.primitive.name);
+	assert!(feature["properties"]["isOpen"].is_boolean());
+
+	// The accept header asks for the same output
+	let response = env
+		.app
+		.get("/locations")
+		.add_query_params(bounds)
+		.add_header(
+			header::ACCEPT,
+			HeaderValue::from_static("application/geo+json"),
+		)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let collection = response.json::<LocationFeatureCollectionResponse>();
+	assert_eq!(collection.kind, GeoJsonType::FeatureCollection);
+	assert_eq!(collection.features.len(), features.len());
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn update_location_test() {
 	let env = TestEnv::new().await.login("test").await;
@@ -187,6 +371,11 @@ async fn approve_location_test() {
 		.json::<LocationResponse>();
 
 	assert_eq!(updated_location.approved_by.unwrap().unwrap().id, profile.id);
+
+	let entries = env.get_audit_entries(AuditAction::LocationApproved).await;
+	assert_eq!(entries.len(), 1);
+	assert_eq!(entries[0].target_id, location.primitive.id);
+	assert_eq!(entries[0].actor_id, Some(profile.id));
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -203,6 +392,89 @@ async fn approve_location_unauthorized_test() {
 		.await;
 
 	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let entries = env.get_audit_entries(AuditAction::LocationApproved).await;
+	assert!(entries.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reject_location_test() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let response = env
+		.app
+		.post("/locations/2/reject")
+		.json(&serde_json::json!({ "reason": "duplicate" }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let entries = env.get_audit_entries(AuditAction::LocationRejected).await;
+	assert_eq!(entries.len(), 1);
+	assert_eq!(entries[0].target_id, 2);
+	assert_eq!(entries[0].detail["reason"], "duplicate");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_pending_locations_test() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	// Both seeded locations are pending, reject one of them
+	let response = env
+		.app
+		.post("/locations/2/reject")
+		.json(&serde_json::json!({ "reason": "duplicate" }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let response = env.app.get("/locations/pending").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let locations = response.json::<PaginatedResponse<Vec<LocationResponse>>>();
+
+	assert_eq!(locations.total, 1);
+	assert_eq!(locations.data.len(), 1);
+	assert_eq!(locations.data[0].id, 1);
+	assert_eq!(locations.data[0].created_by.clone().unwrap().unwrap().id, 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_pending_locations_unauthorized_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env.app.get("/locations/pending").await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let response = env
+		.app
+		.post("/authorities")
+		.json(&serde_json::json!({ "name": "Faculteit Wetenschappen" }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let auth_id = response.json::<serde_json::Value>()["id"].clone();
+
+	// The creator of an authority can approve its locations
+	let response =
+		env.app.get(&format!("/locations/pending?authorityId={auth_id}")).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let locations = response.json::<PaginatedResponse<Vec<LocationResponse>>>();
+
+	assert_eq!(locations.total, 0);
+
+	// Other profiles are not members of the authority
+	let env = env.login("test2").await;
+
+	let response =
+		env.app.get(&format!("/locations/pending?authorityId={auth_id}")).await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -227,3 +499,2731 @@ async fn delete_location_test() {
 
 	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_location_description_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	// Get a test location in the database
+	let location = env.get_location().await.unwrap();
+
+	// Update only the description
+	let response = env
+		.app
+		.patch(
+			format!("/locations/{}/description", location.primitive.id)
+				.as_str(),
+		)
+		.json(&serde_json::json!({
+			"nl": "updated description",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	// Check that only the description was changed
+	let updated_location = env
+		.app
+		.get(format!("/locations/{}", location.primitive.id).as_str())
+		.await
+		.json::<LocationResponse>();
+
+	let description = updated_location.description.unwrap();
+	assert_eq!(description.nl.as_deref(), Some("updated description"));
+
+	let excerpt = updated_location.excerpt.unwrap();
+	assert_eq!(excerpt.nl, location.excerpt.nl);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_location_excerpt_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	// Get a test location in the database
+	let location = env.get_location().await.unwrap();
+
+	// Update only the excerpt
+	let response = env
+		.app
+		.patch(format!("/locations/{}/excerpt", location.primitive.id).as_str())
+		.json(&serde_json::json!({
+			"nl": "updated excerpt",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	// Check that only the excerpt was changed
+	let updated_location = env
+		.app
+		.get(format!("/locations/{}", location.primitive.id).as_str())
+		.await
+		.json::<LocationResponse>();
+
+	let excerpt = updated_location.excerpt.unwrap();
+	assert_eq!(excerpt.nl.as_deref(), Some("updated excerpt"));
+
+	let description = updated_location.description.unwrap();
+	assert_eq!(description.nl, location.description.nl);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_location_translations_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	// Get a test location in the database
+	let location = env.get_location().await.unwrap();
+
+	let response = env
+		.app
+		.get(
+			format!("/locations/{}/translations", location.primitive.id)
+				.as_str(),
+		)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	// Check if both translations are returned
+	let translations = response.json::<LocationTranslationsResponse>();
+	assert_eq!(translations.description.id, location.description.id);
+	assert_eq!(translations.excerpt.id, location.excerpt.id);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn publish_location_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	approve_seeded_locations(&env).await;
+
+	// Get a test location in the database
+	let location = env.get_location().await.unwrap();
+
+	// Unpublish the location
+	let response = env
+		.app
+		.post(
+			format!("/locations/{}/unpublish", location.primitive.id).as_str(),
+		)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let updated_location = env
+		.app
+		.get(format!("/locations/{}", location.primitive.id).as_str())
+		.await
+		.json::<LocationResponse>();
+
+	assert!(!updated_location.is_visible);
+
+	// Unpublished locations should not show up in searches
+	let locations = env
+		.app
+		.get("/locations")
+		.await
+		.json::<PaginatedResponse<Vec<LocationResponse>>>();
+
+	assert!(!locations.data.iter().any(|l| l.id == location.primitive.id));
+
+	// Publish the location again
+	let response = env
+		.app
+		.post(format!("/locations/{}/publish", location.primitive.id).as_str())
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let updated_location = env
+		.app
+		.get(format!("/locations/{}", location.primitive.id).as_str())
+		.await
+		.json::<LocationResponse>();
+
+	assert!(updated_location.is_visible);
+
+	let locations = env
+		.app
+		.get("/locations")
+		.await
+		.json::<PaginatedResponse<Vec<LocationResponse>>>();
+
+	assert!(locations.data.iter().any(|l| l.id == location.primitive.id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn publish_location_unauthorized_test() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	// Get a test location in the database
+	let location = env.get_location().await.unwrap();
+
+	// Attempt to unpublish the location without admin privileges
+	let response = env
+		.app
+		.post(
+			format!("/locations/{}/unpublish", location.primitive.id).as_str(),
+		)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn location_availability_past_test() {
+	let env = TestEnv::new().await;
+
+	let location = env.get_location().await.unwrap();
+	let time = env.get_opening_time().await.unwrap();
+
+	// The seeded opening time lies in the past
+	let at = time.primitive.day.and_time(time.primitive.start_time);
+
+	let response = env
+		.app
+		.get(format!("/locations/{}/available", location.primitive.id).as_str())
+		.add_query_param("at", at)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<LocationAvailabilityResponse>();
+	assert!(!body.available);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn location_availability_no_opening_time_test() {
+	let env = TestEnv::new().await;
+
+	let location = env.get_location().await.unwrap();
+
+	let at = (Utc::now() + Duration::days(365)).naive_utc();
+
+	let response = env
+		.app
+		.get(format!("/locations/{}/available", location.primitive.id).as_str())
+		.add_query_param("at", at)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<LocationAvailabilityResponse>();
+	assert!(!body.available);
+	assert_eq!(body.opening_time_id, None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn location_availability_full_test() {
+	let env = TestEnv::new().await;
+
+	let location = env.get_location().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let day = (Utc::now() + Duration::days(7)).date_naive();
+
+	let times = NewOpeningTime::bulk_insert(
+		vec![NewOpeningTime {
+			location_id: location.primitive.id,
+			day,
+			start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+			seat_count: Some(1),
+			reservable_from: None,
+			reservable_until: None,
+			created_by: profile.id,
+		}],
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	let t_id = times[0].id;
+	let at = day.and_time(NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+
+	let response = env
+		.app
+		.get(format!("/locations/{}/available", location.primitive.id).as_str())
+		.add_query_param("at", at)
+		.await;
+
+	let body = response.json::<LocationAvailabilityResponse>();
+	assert!(body.available);
+	assert_eq!(body.opening_time_id, Some(t_id));
+
+	// Book the only seat from 08:00 until 10:00
+	NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  t_id,
+		base_block_index: 0,
+		block_count:      24,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let response = env
+		.app
+		.get(format!("/locations/{}/available", location.primitive.id).as_str())
+		.add_query_param("at", at)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<LocationAvailabilityResponse>();
+	assert!(!body.available);
+	assert_eq!(body.opening_time_id, Some(t_id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn location_availability_not_reservable_yet_test() {
+	let env = TestEnv::new().await;
+
+	let location = env.get_location().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let day = (Utc::now() + Duration::days(7)).date_naive();
+
+	// Free seats are not enough if reservations can't be made yet
+	let times = NewOpeningTime::bulk_insert(
+		vec![NewOpeningTime {
+			location_id: location.primitive.id,
+			day,
+			start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+			seat_count: None,
+			reservable_from: Some(day.and_hms_opt(0, 0, 0).unwrap()),
+			reservable_until: None,
+			created_by: profile.id,
+		}],
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	let at = day.and_time(NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+
+	let response = env
+		.app
+		.get(format!("/locations/{}/available", location.primitive.id).as_str())
+		.add_query_param("at", at)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<LocationAvailabilityResponse>();
+	assert!(!body.available);
+	assert_eq!(body.opening_time_id, Some(times[0].id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_locations_by_reservation_count_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login_admin().await;
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let third = env
+		.app
+		.post("/locations")
+		.json(&serde_json::json!({
+			"name": "Third Location",
+			"description": { "nl": "test description" },
+			"excerpt": { "nl": "test excerpt" },
+			"seatCount": 10,
+			"isReservable": true,
+			"isVisible": true,
+			"street": "Test Street",
+			"number": "123",
+			"zip": "1234AB",
+			"city": "Test City",
+			"province": "Test Province",
+			"country": "BE",
+			"latitude": 52.0,
+			"longitude": 4.0
+		}))
+		.await
+		.json::<LocationResponse>();
+
+	let day = (Utc::now() + Duration::days(7)).date_naive();
+
+	let new_time = |location_id| {
+		NewOpeningTime {
+			location_id,
+			day,
+			start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+			seat_count: None,
+			reservable_from: None,
+			reservable_until: None,
+			created_by: profile.id,
+		}
+	};
+
+	let times = NewOpeningTime::bulk_insert(
+		vec![new_time(2), new_time(third.id)],
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	// Location 1 has a single seeded reservation, give location 2 three
+	for base_block_index in [0, 10, 20] {
+		NewReservation {
+			profile_id: profile.id,
+			opening_time_id: times[0].id,
+			base_block_index,
+			block_count: 4,
+		}
+		.insert(ReservationIncludes::default(), &conn)
+		.await
+		.unwrap();
+	}
+
+	// The only reservation for the third location is cancelled
+	let cancelled = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  times[1].id,
+		base_block_index: 0,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	conn.interact(move |conn| {
+		use db::reservation::dsl::*;
+
+		diesel::update(reservation.find(cancelled.primitive.id))
+			.set(state.eq(db::ReservationState::Cancelled))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let response = env
+		.app
+		.get("/admin/locations/by-reservation-count")
+		.add_query_param("direction", "desc")
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response
+		.json::<PaginatedResponse<Vec<LocationReservationCountResponse>>>();
+	let order: Vec<_> = body
+		.data
+		.iter()
+		.map(|l| (l.location.id, l.reservation_count))
+		.collect();
+	assert_eq!(order, vec![(2, 3), (1, 1), (third.id, 0)]);
+
+	let response = env
+		.app
+		.get("/admin/locations/by-reservation-count")
+		.add_query_param("direction", "asc")
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response
+		.json::<PaginatedResponse<Vec<LocationReservationCountResponse>>>();
+	let order: Vec<_> = body
+		.data
+		.iter()
+		.map(|l| (l.location.id, l.reservation_count))
+		.collect();
+	assert_eq!(order, vec![(third.id, 0), (1, 1), (2, 3)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn hard_delete_location_test() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let profile = env.get_admin_profile().await.unwrap();
+
+	let ordered = NewImage {
+		file_path:    None,
+		uploaded_by:  profile.id,
+		image_url:    Some("https://example.com/image.png".to_string()),
+		content_hash: None,
+	}
+	.insert_for_location(1, 0, &conn)
+	.await
+	.unwrap();
+
+	let response = env.app.delete("/admin/locations/1/hard").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<LocationDeletionResponse>();
+	assert_eq!(body.opening_times, 1);
+	assert_eq!(body.reservations, 1);
+	assert_eq!(body.images, 1);
+	assert_eq!(body.translations, 2);
+
+	let location =
+		Location::get_by_id(1, LocationIncludes::default(), &conn).await;
+	assert!(location.is_err());
+
+	let time =
+		OpeningTime::get_by_id(1, OpeningTimeIncludes::default(), &conn).await;
+	assert!(time.is_err());
+
+	let image_id = ordered.image.primitive.id;
+	let image_exists = conn
+		.interact(move |conn| {
+			use db::image::dsl::*;
+			use diesel::prelude::*;
+
+			diesel::select(diesel::dsl::exists(image.filter(id.eq(image_id))))
+				.get_result::<bool>(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+	assert!(!image_exists);
+
+	let response = env.app.get("/locations/2").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let response = env.app.delete("/admin/locations/1/hard").await;
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn hard_delete_location_requires_admin_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env.app.delete("/admin/locations/1/hard").await;
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let response = env.app.get("/locations/1").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn merge_location_test() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let profile = env.get_admin_profile().await.unwrap();
+
+	// The target location already has a review by this profile
+	for location_id in [1, 2] {
+		NewReview {
+			profile_id: profile.id,
+			location_id,
+			rating: 4,
+			body: None,
+		}
+		.insert(true, &conn)
+		.await
+		.unwrap();
+	}
+
+	let response = env
+		.app
+		.post("/locations/2/merge")
+		.json(&serde_json::json!({ "sourceId": 1 }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<LocationMergeResponse>();
+	assert_eq!(body.source_id, 1);
+	assert_eq!(body.target_id, 2);
+	assert_eq!(body.moved.opening_times, 1);
+	assert_eq!(body.moved.reservations, 1);
+	assert_eq!(body.moved.reviews, 0);
+	assert_eq!(body.skipped.reviews, 1);
+	assert_eq!(body.translations, 2);
+
+	let reservation =
+		Reservation::get_by_id(1, ReservationIncludes::default(), &conn)
+			.await
+			.unwrap();
+	assert_eq!(reservation.opening_time.location_id, 2);
+	assert_eq!(reservation.location.id, 2);
+
+	let location =
+		Location::get_by_id(1, LocationIncludes::default(), &conn).await;
+	assert!(location.is_err());
+
+	let response = env.app.get("/locations/2").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn merge_location_invalid_test() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let response = env
+		.app
+		.post("/locations/1/merge")
+		.json(&serde_json::json!({ "sourceId": 1 }))
+		.await;
+	assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+	let response = env
+		.app
+		.post("/locations/999/merge")
+		.json(&serde_json::json!({ "sourceId": 1 }))
+		.await;
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+	// Nothing was merged or deleted by the failed requests
+	let response = env.app.get("/locations/1").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn merge_location_requires_admin_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env
+		.app
+		.post("/locations/2/merge")
+		.json(&serde_json::json!({ "sourceId": 1 }))
+		.await;
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let response = env.app.get("/locations/1").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+}
+
+/// Set up a location with a member managing role and an administrator role,
+/// and make `test` and `test2` members with the managing role
+///
+/// Returns the ids of the managing and administrator roles
+async fn setup_location_member_roles(env: &TestEnv) -> (i32, i32) {
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let admin = env.get_admin_profile().await.unwrap();
+
+	let manager = NewLocationRole {
+		location_id: 1,
+		name:        "manager".to_string(),
+		colour:      None,
+		permissions: LocationPermissions::ManageMembers.bits(),
+		created_by:  admin.id,
+	}
+	.insert(1, RoleIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let administrator = NewLocationRole {
+		location_id: 1,
+		name:        "administrator".to_string(),
+		colour:      None,
+		permissions: LocationPermissions::Administrator.bits(),
+		created_by:  admin.id,
+	}
+	.insert(1, RoleIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	for username in ["test", "test2"] {
+		let profile = env.get_profile(username).await.unwrap();
+
+		NewLocationMember {
+			location_id:      1,
+			profile_id:       profile.id,
+			location_role_id: Some(manager.primitive.id),
+			added_by:         admin.id,
+		}
+		.insert(&conn)
+		.await
+		.unwrap();
+	}
+
+	(manager.primitive.id, administrator.primitive.id)
+}
+
+/// Get the role of a profile within location 1
+async fn get_location_member_role(env: &TestEnv, p_id: i32) -> Option<i32> {
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	conn.interact(move |conn| {
+		use db::location_member::dsl::*;
+		use diesel::prelude::*;
+
+		location_member
+			.filter(location_id.eq(1).and(profile_id.eq(p_id)))
+			.select(location_role_id)
+			.get_result(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_location_member_role_escalation_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let (manager_id, administrator_id) =
+		setup_location_member_roles(&env).await;
+
+	let test2 = env.get_profile("test2").await.unwrap();
+
+	let response = env
+		.app
+		.patch(format!("/locations/1/members/{}", test2.id).as_str())
+		.json(&serde_json::json!({
+			"roleId": administrator_id,
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+	assert_eq!(
+		get_location_member_role(&env, test2.id).await,
+		Some(manager_id)
+	);
+
+	let entries = env.get_audit_entries(AuditAction::MemberRoleChanged).await;
+	assert!(entries.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_location_member_role_downgrade_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let (manager_id, _) = setup_location_member_roles(&env).await;
+
+	let test2 = env.get_profile("test2").await.unwrap();
+
+	let response = env
+		.app
+		.patch(format!("/locations/1/members/{}", test2.id).as_str())
+		.json(&serde_json::json!({
+			"roleId": manager_id,
+		}))
+		.await;
+
+	assert!(response.status_code().is_success());
+
+	let entries = env.get_audit_entries(AuditAction::MemberRoleChanged).await;
+	assert_eq!(entries.len(), 1);
+	assert_eq!(entries[0].target_id, 1);
+	assert_eq!(entries[0].detail["profileId"], test2.id);
+
+	let response = env
+		.app
+		.patch(format!("/locations/1/members/{}", test2.id).as_str())
+		.json(&serde_json::json!({
+			"roleId": null,
+		}))
+		.await;
+
+	assert!(response.status_code().is_success());
+	assert_eq!(get_location_member_role(&env, test2.id).await, None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_location_member_role_platform_admin_test() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let (_, administrator_id) = setup_location_member_roles(&env).await;
+
+	let test2 = env.get_profile("test2").await.unwrap();
+
+	// Platform admins are not members of the location, but can still grant
+	// any role
+	let response = env
+		.app
+		.patch(format!("/locations/1/members/{}", test2.id).as_str())
+		.json(&serde_json::json!({
+			"roleId": administrator_id,
+		}))
+		.await;
+
+	assert!(response.status_code().is_success());
+	assert_eq!(
+		get_location_member_role(&env, test2.id).await,
+		Some(administrator_id)
+	);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_location_member_demote_administrator_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let (manager_id, administrator_id) =
+		setup_location_member_roles(&env).await;
+
+	let test2 = env.get_profile("test2").await.unwrap();
+	let admin = env.get_admin_profile().await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	// Two administrators, so neither of them is the last one
+	LocationMemberUpdate {
+		location_role_id: Some(administrator_id),
+		updated_by:       admin.id,
+	}
+	.apply_to(1, test2.id, &conn)
+	.await
+	.unwrap();
+
+	NewLocationMember {
+		location_id:      1,
+		profile_id:       admin.id,
+		location_role_id: Some(administrator_id),
+		added_by:         admin.id,
+	}
+	.insert(&conn)
+	.await
+	.unwrap();
+
+	// Managing members does not allow demoting an administrator
+	for role_id in [Some(manager_id), None] {
+		let response = env
+			.app
+			.patch(format!("/locations/1/members/{}", test2.id).as_str())
+			.json(&serde_json::json!({
+				"roleId": role_id,
+			}))
+			.await;
+
+		assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+	}
+
+	assert_eq!(
+		get_location_member_role(&env, test2.id).await,
+		Some(administrator_id)
+	);
+
+	// An administrator can
+	let env = env.login("test2").await;
+
+	let response = env
+		.app
+		.patch(format!("/locations/1/members/{}", admin.id).as_str())
+		.json(&serde_json::json!({
+			"roleId": manager_id,
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+	assert_eq!(
+		get_location_member_role(&env, admin.id).await,
+		Some(manager_id)
+	);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn last_location_administrator_test() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	let (manager_id, administrator_id) =
+		setup_location_member_roles(&env).await;
+
+	let test = env.get_profile("test").await.unwrap();
+	let test2 = env.get_profile("test2").await.unwrap();
+	let admin = env.get_admin_profile().await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	LocationMemberUpdate {
+		location_role_id: Some(administrator_id),
+		updated_by:       admin.id,
+	}
+	.apply_to(1, test2.id, &conn)
+	.await
+	.unwrap();
+
+	let own_url = format!("/locations/1/members/{}", test2.id);
+
+	// The only administrator can neither step down nor leave the location
+	let response = env
+		.app
+		.patch(&own_url)
+		.json(&serde_json::json!({
+			"roleId": manager_id,
+		}))
+		.await;
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["details"]["reason"], "last_administrator");
+
+	let response = env.app.delete(&own_url).await;
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+	assert_eq!(
+		get_location_member_role(&env, test2.id).await,
+		Some(administrator_id)
+	);
+
+	// Once another member is an administrator it can
+	let response = env
+		.app
+		.patch(format!("/locations/1/members/{}", test.id).as_str())
+		.json(&serde_json::json!({
+			"roleId": administrator_id,
+		}))
+		.await;
+	assert!(response.status_code().is_success());
+
+	let response = env
+		.app
+		.patch(&own_url)
+		.json(&serde_json::json!({
+			"roleId": null,
+		}))
+		.await;
+	assert!(response.status_code().is_success());
+	assert_eq!(get_location_member_role(&env, test2.id).await, None);
+}
+
+/// Count the files stored for the images of a location
+fn count_location_image_files(loc_id: i32) -> usize {
+	std::fs::read_dir(format!("/mnt/files/location/{loc_id}"))
+		.map(Iterator::count)
+		.unwrap_or_default()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn upload_duplicate_location_image_test() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let mut bytes = vec![];
+	image_processing::RgbImage::new(16, 16)
+		.write_to(
+			&mut std::io::Cursor::new(&mut bytes),
+			image_processing::ImageFormat::Png,
+		)
+		.unwrap();
+
+	let upload = |bytes: Vec<u8>| {
+		let form = MultipartForm::new()
+			.add_part(
+				"image",
+				Part::bytes(bytes)
+					.file_name("image.png")
+					.mime_type("image/png"),
+			)
+			.add_text("index", "0");
+
+		env.app.post("/locations/1/images").multipart(form)
+	};
+
+	let files_before = count_location_image_files(1);
+
+	let response = upload(bytes.clone()).await;
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+	let first = response.json::<ImageResponse>();
+
+	let response = upload(bytes).await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+	let second = response.json::<ImageResponse>();
+
+	assert_eq!(first.id, second.id);
+	assert_eq!(count_location_image_files(1), files_before + 1);
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let image_count = conn
+		.interact(|conn| {
+			use db::location_image::dsl::*;
+			use diesel::prelude::*;
+
+			location_image
+				.filter(location_id.eq(1))
+				.count()
+				.get_result::<i64>(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(image_count, 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn upload_oversized_location_image_test() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let max_bytes = Config::from_env().image_max_bytes;
+
+	let form = MultipartForm::new()
+		.add_part(
+			"image",
+			Part::bytes(vec![0; max_bytes + 1])
+				.file_name("image.png")
+				.mime_type("image/png"),
+		)
+		.add_text("index", "0");
+
+	let files_before = count_location_image_files(1);
+
+	let response = env.app.post("/locations/1/images").multipart(form).await;
+	assert_eq!(response.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["details"]["reason"], "image_too_large");
+	assert_eq!(body["details"]["max"], max_bytes);
+
+	assert_eq!(count_location_image_files(1), files_before);
+}
+
+/// Encode a JPEG with an EXIF segment holding the given orientation
+fn jpeg_with_orientation(width: u32, height: u32, orientation: u8) -> Vec<u8> {
+	let mut jpeg = vec![];
+	image_processing::RgbImage::new(width, height)
+		.write_to(
+			&mut std::io::Cursor::new(&mut jpeg),
+			image_processing::ImageFormat::Jpeg,
+		)
+		.unwrap();
+
+	// Big endian TIFF header followed by a single IFD entry for the
+	// orientation tag
+	let mut exif = b"Exif\0\0MM\0\x2A\0\0\0\x08\0\x01".to_vec();
+	exif.extend_from_slice(&[0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01]);
+	exif.extend_from_slice(&[0x00, orientation, 0x00, 0x00]);
+	exif.extend_from_slice(&[0x00; 4]);
+
+	let length = u16::try_from(exif.len() + 2).unwrap();
+
+	// The APP1 segment goes right after the start of image marker
+	let mut segment = vec![0xFF, 0xE1];
+	segment.extend_from_slice(&length.to_be_bytes());
+	segment.extend_from_slice(&exif);
+
+	jpeg.splice(2..2, segment);
+
+	jpeg
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn upload_location_image_strips_exif_test() {
+	use image_processing::{ImageDecoder, ImageReader};
+
+	let env = TestEnv::new().await.login_admin().await;
+
+	// Orientation 6 means the image has to be rotated 90 degrees clockwise
+	let bytes = jpeg_with_orientation(64, 32, 6);
+
+	let form = MultipartForm::new()
+		.add_part(
+			"image",
+			Part::bytes(bytes).file_name("image.jpg").mime_type("image/jpeg"),
+		)
+		.add_text("index", "0");
+
+	let response = env.app.post("/locations/1/images").multipart(form).await;
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let image_id = response.json::<ImageResponse>().id;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+	let file_path = conn
+		.interact(move |conn| {
+			use db::image::dsl::*;
+			use diesel::prelude::*;
+
+			image
+				.find(image_id)
+				.select(file_path)
+				.get_result::<Option<String>>(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap()
+		.unwrap();
+
+	let mut decoder = ImageReader::open(format!("/mnt/files/{file_path}"))
+		.unwrap()
+		.with_guessed_format()
+		.unwrap()
+		.into_decoder()
+		.unwrap();
+
+	// The orientation is applied to the pixels before the metadata is dropped
+	assert_eq!(decoder.dimensions(), (1024, 2048));
+	assert!(decoder.exif_metadata().unwrap().is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_location_by_public_id_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env
+		.app
+		.post("/locations")
+		.json(&serde_json::json!({
+			"name": "Public Location",
+			"description": {
+				"nl": "test description",
+			},
+			"excerpt": {
+				"nl": "test excerpt",
+			},
+			"seatCount": 10,
+			"isReservable": true,
+			"isVisible": true,
+			"street": "Test Street",
+			"number": "123",
+			"zip": "1234AB",
+			"city": "Test City",
+			"province": "Test Province",
+			"country": "BE",
+			"latitude": 52.0,
+			"longitude": 4.0
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let created = response.json::<LocationResponse>();
+
+	let response = env.app.get(&format!("/locations/{}", created.id)).await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let by_id = response.json::<serde_json::Value>();
+	let public_id = by_id["publicId"].as_str().unwrap().to_string();
+	assert_eq!(public_id, created.public_id.to_string());
+
+	let response = env.app.get(&format!("/locations/p/{public_id}")).await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let by_public_id = response.json::<serde_json::Value>();
+	assert_eq!(by_id, by_public_id);
+
+	// Unknown public ids are not found, malformed ones are rejected
+	let unknown = uuid::Uuid::new_v4();
+	let response = env.app.get(&format!("/locations/p/{unknown}")).await;
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+	let response = env.app.get("/locations/p/not-a-uuid").await;
+	assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn search_locations_availability_summary_test() {
+	let env = TestEnv::new().await;
+
+	approve_seeded_locations(&env).await;
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let day = (Utc::now() + Duration::days(1)).date_naive();
+	let start_time = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+	let end_time = NaiveTime::from_hms_opt(10, 0, 0).unwrap();
+
+	let times = NewOpeningTime::bulk_insert(
+		vec![
+			NewOpeningTime {
+				location_id: 1,
+				day,
+				start_time,
+				end_time,
+				seat_count: None,
+				reservable_from: None,
+				reservable_until: None,
+				created_by: profile.id,
+			},
+			NewOpeningTime {
+				location_id: 2,
+				day,
+				start_time,
+				end_time,
+				seat_count: Some(1),
+				reservable_from: None,
+				reservable_until: None,
+				created_by: profile.id,
+			},
+		],
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	let full_time = times.iter().find(|t| t.location_id == 2).unwrap();
+
+	// Book the only seat of the second location for the whole opening time
+	NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  full_time.id,
+		base_block_index: 0,
+		block_count:      24,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let response = env.app.get("/locations").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let locations = response.json::<PaginatedResponse<Vec<LocationResponse>>>();
+
+	let free = locations.data.iter().find(|l| l.id == 1).unwrap();
+	let summary = free.availability_summary.unwrap();
+	assert_eq!(summary.next_date, Some(day));
+	assert_eq!(summary.next_start_time, Some(start_time));
+	assert_eq!(summary.free_blocks, 24);
+
+	let full = locations.data.iter().find(|l| l.id == 2).unwrap();
+	let summary = full.availability_summary.unwrap();
+	assert_eq!(summary.next_date, None);
+	assert_eq!(summary.next_start_time, None);
+	assert_eq!(summary.free_blocks, 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn location_role_permissions_labels_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	setup_location_member_roles(&env).await;
+
+	let permissions = LocationPermissions::ManageImages
+		| LocationPermissions::ManageMembers
+		| LocationPermissions::ConfirmReservations;
+
+	let response = env
+		.app
+		.post("/locations/1/roles")
+		.json(&serde_json::json!({
+			"name": "helper",
+			"permissions": permissions.bits(),
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let role = response.json::<RoleResponse>();
+
+	assert_eq!(role.permissions, permissions.bits());
+	assert_eq!(
+		role.permissions_labels,
+		vec!["ManageImages", "ManageMembers", "ConfirmReservations"]
+	);
+	assert_eq!(role.permissions_labels, permissions.permissions_labels());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_nearby_locations_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	// Only approve the two seeded locations
+	conn.interact(|conn| {
+		use db::location::dsl::*;
+
+		diesel::update(location.filter(id.eq_any([1, 2])))
+			.set(approved_at.eq(Utc::now().naive_utc()))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	// Search from the first location, the second one is about 2.35km away
+	let response = env
+		.app
+		.get("/locations/nearby")
+		.add_query_param("lat", 51.0425)
+		.add_query_param("lng", 3.7064)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let locations = response.json::<Vec<LocationResponse>>();
+
+	assert_eq!(locations.len(), 2);
+	assert_eq!(locations[0].id, 1);
+	assert!(locations[0].distance_km.unwrap() < 0.001);
+	assert_eq!(locations[1].id, 2);
+	assert!((locations[1].distance_km.unwrap() - 2.35).abs() < 0.01);
+
+	let response = env
+		.app
+		.get("/locations/nearby")
+		.add_query_param("lat", 51.0247)
+		.add_query_param("lng", 3.7244)
+		.add_query_param("limit", 1)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let locations = response.json::<Vec<LocationResponse>>();
+
+	assert_eq!(locations.len(), 1);
+	assert_eq!(locations[0].id, 2);
+}
+
+/// Get the ids of the listed locations matching a search query
+async fn search_location_ids(env: &TestEnv, query: &str) -> Vec<i32> {
+	let response =
+		env.app.get("/locations").add_query_param("query", query).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let locations = response.json::<PaginatedResponse<Vec<LocationResponse>>>();
+
+	locations.data.iter().map(|l| l.id).collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn search_locations_by_query_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let locations = [
+		("Reading Room", "A quiet library in Ghent", "Lots of books"),
+		("Quiet Study Hall", "Een studiezaal", "Stil"),
+		("100% Focus_Zone", "Een lawaaierige zaal", "Luid"),
+	];
+
+	let mut l_ids = vec![];
+	for (name, description, excerpt) in locations {
+		let response = env
+			.app
+			.post("/locations")
+			.json(&serde_json::json!({
+				"name": name,
+				"description": {
+					"en": description,
+				},
+				"excerpt": {
+					"nl": excerpt,
+				},
+				"seatCount": 10,
+				"isReservable": true,
+				"isVisible": true,
+				"street": "Test Street",
+				"number": "123",
+				"zip": "9000",
+				"city": "Gent",
+				"province": "Oost-Vlaanderen",
+				"country": "BE",
+				"latitude": 51.0,
+				"longitude": 3.7
+			}))
+			.await;
+
+		assert_eq!(response.status_code(), StatusCode::CREATED);
+
+		l_ids.push(response.json::<LocationResponse>().id);
+	}
+
+	let ids = l_ids.clone();
+	conn.interact(move |conn| {
+		use db::location::dsl::*;
+
+		diesel::update(location.filter(id.eq_any(ids)))
+			.set(approved_at.eq(Utc::now().naive_utc()))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	// Only locations with an opening time show up in searches
+	let day = (Utc::now() + Duration::days(1)).date_naive();
+	let times = l_ids
+		.iter()
+		.map(|&location_id| {
+			NewOpeningTime {
+				location_id,
+				day,
+				start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+				end_time: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+				seat_count: None,
+				reservable_from: None,
+				reservable_until: None,
+				created_by: profile.id,
+			}
+		})
+		.collect();
+
+	NewOpeningTime::bulk_insert(times, OpeningTimeIncludes::default(), &conn)
+		.await
+		.unwrap();
+
+	let [reading_room, study_hall, focus_zone] = l_ids[..] else {
+		unreachable!();
+	};
+
+	// Name matches come before description matches
+	assert_eq!(
+		search_location_ids(&env, "QUIET").await,
+		[study_hall, reading_room]
+	);
+	assert_eq!(
+		search_location_ids(&env, "quiet library").await,
+		[reading_room]
+	);
+	assert_eq!(search_location_ids(&env, "stil").await, [study_hall]);
+
+	// LIKE wildcards in the query are matched literally
+	assert_eq!(search_location_ids(&env, "%").await, [focus_zone]);
+	assert_eq!(search_location_ids(&env, "s_").await, [focus_zone]);
+	assert!(search_location_ids(&env, "\\").await.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn search_newest_locations_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let response = env
+		.app
+		.post("/locations")
+		.json(&serde_json::json!({
+			"name": "Third Location",
+			"description": {
+				"nl": "test description",
+			},
+			"excerpt": {
+				"nl": "test excerpt",
+			},
+			"seatCount": 10,
+			"isReservable": true,
+			"isVisible": true,
+			"street": "Test Street",
+			"number": "123",
+			"zip": "9000",
+			"city": "Gent",
+			"province": "Oost-Vlaanderen",
+			"country": "BE",
+			"latitude": 51.0,
+			"longitude": 3.7
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let third = response.json::<LocationResponse>().id;
+
+	// Only locations with an opening time show up in searches
+	let day = (Utc::now() + Duration::days(1)).date_naive();
+	let times = [2, third].map(|location_id| {
+		NewOpeningTime {
+			location_id,
+			day,
+			start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time: NaiveTime::from_hms_opt(10, 0, 0).unwrap(),
+			seat_count: None,
+			reservable_from: None,
+			reservable_until: None,
+			created_by: profile.id,
+		}
+	});
+
+	NewOpeningTime::bulk_insert(
+		Vec::from(times),
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	let now = Utc::now().naive_utc();
+	let approvals = [
+		(1, now - Duration::days(3)),
+		(2, now - Duration::days(1)),
+		(third, now - Duration::days(2)),
+	];
+
+	for (l_id, approved) in approvals {
+		conn.interact(move |conn| {
+			use db::location::dsl::*;
+
+			diesel::update(location.find(l_id))
+				.set(approved_at.eq(approved))
+				.execute(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+	}
+
+	let response =
+		env.app.get("/locations").add_query_param("newest", true).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let locations = response.json::<PaginatedResponse<Vec<LocationResponse>>>();
+	let ids: Vec<_> = locations.data.iter().map(|l| l.id).collect();
+
+	assert_eq!(ids, [2, third, 1]);
+
+	// Newest locations are cached, so a fresh approval is not visible yet
+	conn.interact(move |conn| {
+		use db::location::dsl::*;
+
+		diesel::update(location.find(1)).set(approved_at.eq(now)).execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let response =
+		env.app.get("/locations").add_query_param("newest", true).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let locations = response.json::<PaginatedResponse<Vec<LocationResponse>>>();
+	let ids: Vec<_> = locations.data.iter().map(|l| l.id).collect();
+
+	assert_eq!(ids, [2, third, 1]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn default_location_role_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let (_, administrator_id) = setup_location_member_roles(&env).await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let admin = env.get_admin_profile().await.unwrap();
+
+	let viewer = NewLocationRole {
+		location_id: 1,
+		name:        "viewer".to_string(),
+		colour:      None,
+		permissions: 0,
+		created_by:  admin.id,
+	}
+	.insert(1, RoleIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	// Managers cannot make a role with more permissions the default
+	let response = env
+		.app
+		.post("/locations/1/roles/set-default")
+		.json(&serde_json::json!({ "roleId": administrator_id }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let response = env
+		.app
+		.post("/locations/1/roles/set-default")
+		.json(&serde_json::json!({ "roleId": viewer.primitive.id }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+	assert_eq!(response.json::<RoleResponse>().id, viewer.primitive.id);
+
+	// Members added without a role get the default role
+	let response = env
+		.app
+		.post("/locations/1/members")
+		.json(&serde_json::json!({ "profileId": admin.id }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let role = get_location_member_role(&env, admin.id).await;
+
+	assert_eq!(role, Some(viewer.primitive.id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn default_location_role_escalation_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	setup_location_member_roles(&env).await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let admin = env.get_admin_profile().await.unwrap();
+
+	// Without an explicit default, any role named "member" is used
+	NewLocationRole {
+		location_id: 1,
+		name:        "Member".to_string(),
+		colour:      None,
+		permissions: LocationPermissions::Administrator.bits(),
+		created_by:  admin.id,
+	}
+	.insert(1, RoleIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	// Managers cannot hand out the default role if it grants more than they
+	// have themselves
+	let response = env
+		.app
+		.post("/locations/1/members")
+		.json(&serde_json::json!({ "profileId": admin.id }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let members = env.app.get("/locations/1/members").await;
+	let members = members.json::<Vec<ProfileResponse>>();
+
+	assert!(members.iter().all(|m| m.id != admin.id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn delete_location_role_in_use_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let (manager_id, administrator_id) =
+		setup_location_member_roles(&env).await;
+
+	// Both test profiles still hold the managing role
+	let response =
+		env.app.delete(&format!("/locations/1/roles/{manager_id}")).await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["details"]["reason"], "role_in_use");
+	assert_eq!(body["details"]["memberCount"], 2);
+
+	// Nobody holds the administrator role
+	let response =
+		env.app.delete(&format!("/locations/1/roles/{administrator_id}")).await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let test2 = env.get_profile("test2").await.unwrap();
+	assert_eq!(
+		get_location_member_role(&env, test2.id).await,
+		Some(manager_id)
+	);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_location_count_by_zip_test() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	// The seeded locations both have postcode 9000
+	for zip in ["1000", "1000", "1000", "2000"] {
+		let response = env
+			.app
+			.post("/locations")
+			.json(&serde_json::json!({
+				"name": format!("Location in {zip}"),
+				"description": { "nl": "test description" },
+				"excerpt": { "nl": "test excerpt" },
+				"seatCount": 10,
+				"isReservable": true,
+				"isVisible": true,
+				"street": "Test Street",
+				"number": "123",
+				"zip": zip,
+				"city": "Test City",
+				"province": "Test Province",
+				"country": "BE",
+				"latitude": 50.8,
+				"longitude": 4.3
+			}))
+			.await;
+
+		assert_eq!(response.status_code(), StatusCode::CREATED);
+	}
+
+	let response = env.app.get("/admin/locations/stats/by-zip").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let counts: Vec<_> = response
+		.json::<Vec<ZipLocationCountResponse>>()
+		.into_iter()
+		.map(|c| (c.zip, c.location_count))
+		.collect();
+
+	assert_eq!(
+		counts,
+		[
+			("1000".to_string(), 3),
+			("9000".to_string(), 2),
+			("2000".to_string(), 1),
+		]
+	);
+
+	// Searches can be narrowed down to a single postcode
+	approve_seeded_locations(&env).await;
+
+	let response =
+		env.app.get("/locations").add_query_param("zip", "9000").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let locations = response.json::<PaginatedResponse<Vec<LocationResponse>>>();
+
+	assert!(!locations.data.is_empty());
+	assert!(locations.data.iter().all(|l| l.zip == "9000"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_location_count_by_zip_unauthorized_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env.app.get("/admin/locations/stats/by-zip").await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_visible_locations_test() {
+	use diesel::prelude::*;
+	use diesel::sql_types::Text;
+
+	#[derive(QueryableByName)]
+	struct QueryPlanRow {
+		#[diesel(sql_type = Text, column_name = "QUERY PLAN")]
+		line: String,
+	}
+
+	let env = TestEnv::new().await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	// Half of the generated locations are approved, but only the even ones
+	// are visible, leaving 50 listed locations
+	conn.interact(|conn| {
+		diesel::sql_query(
+			"INSERT INTO location (
+				name, description_id, excerpt_id, seat_count, is_reservable,
+				is_visible, street, number, zip, city, province, country,
+				latitude, longitude, approved_at
+			)
+			SELECT
+				'Generated ' || n, description_id, excerpt_id, seat_count,
+				is_reservable, n % 2 = 0, street, number, zip, city, province,
+				country, latitude, longitude,
+				CASE WHEN n % 4 IN (0, 1) THEN now() END
+			FROM location, generate_series(1, 200) AS n
+			WHERE location.id = 1",
+		)
+		.execute(conn)?;
+
+		diesel::sql_query(
+			"INSERT INTO opening_time (location_id, day, start_time, end_time)
+			SELECT id, current_date + 1, '08:00', '10:00'
+			FROM location
+			WHERE name LIKE 'Generated %'",
+		)
+		.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let response = env
+		.app
+		.get("/locations")
+		.add_query_param("page", 5)
+		.add_query_param("perPage", 12)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let locations = response.json::<PaginatedResponse<Vec<LocationResponse>>>();
+
+	assert_eq!(locations.total, 50);
+	assert!(!locations.truncated);
+	assert_eq!(locations.data.len(), 2);
+	assert!(locations.data.iter().all(|l| l.is_visible));
+
+	// The visibility and approval filter can be answered from the index
+	let plan = conn
+		.interact(|conn| {
+			diesel::sql_query("SET enable_seqscan = off").execute(conn)?;
+
+			diesel::sql_query(
+				"EXPLAIN SELECT id FROM location
+				WHERE is_visible AND approved_at IS NOT NULL",
+			)
+			.load::<QueryPlanRow>(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+
+	assert!(
+		plan.iter().any(|r| r.line.contains("idx__location__visible_approved"))
+	);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn approve_all_location_images_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	for index in 0..3 {
+		NewImage {
+			file_path:    None,
+			uploaded_by:  profile.id,
+			image_url:    Some(format!("https://example.com/{index}.png")),
+			content_hash: None,
+		}
+		.insert_for_location(1, index, &conn)
+		.await
+		.unwrap();
+	}
+
+	let location = env.app.get("/locations/1").await.json::<LocationResponse>();
+	assert_eq!(location.pending_image_count, Some(3));
+
+	// Only admins can approve images of locations without an authority
+	let response = env.app.post("/locations/1/images/approve-all").await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let env = env.login_admin().await;
+
+	let response = env.app.post("/locations/1/images/approve-all").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<ApprovedImagesResponse>();
+	assert_eq!(body.approved_images, 3);
+
+	let location = env.app.get("/locations/1").await.json::<LocationResponse>();
+	assert_eq!(location.pending_image_count, Some(0));
+
+	// Approving again has nothing left to do
+	let response = env.app.post("/locations/1/images/approve-all").await;
+
+	assert_eq!(response.json::<ApprovedImagesResponse>().approved_images, 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn approve_location_image_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	approve_seeded_locations(&env).await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let admin = env.get_admin_profile().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let role = NewLocationRole {
+		location_id: 1,
+		name:        "image manager".to_string(),
+		colour:      None,
+		permissions: LocationPermissions::ManageImages.bits(),
+		created_by:  admin.id,
+	}
+	.insert(1, RoleIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	NewLocationMember {
+		location_id:      1,
+		profile_id:       profile.id,
+		location_role_id: Some(role.primitive.id),
+		added_by:         admin.id,
+	}
+	.insert(&conn)
+	.await
+	.unwrap();
+
+	let ordered = NewImage {
+		file_path:    None,
+		uploaded_by:  profile.id,
+		image_url:    Some("https://example.com/image.png".to_string()),
+		content_hash: None,
+	}
+	.insert_for_location(1, 0, &conn)
+	.await
+	.unwrap();
+
+	// Pending images are not shown publicly
+	let location = env.app.get("/locations/1").await.json::<LocationResponse>();
+	assert!(location.images.is_empty());
+
+	let response = env.app.get("/locations/1/images/pending").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let pending = response.json::<Vec<ImageResponse>>();
+	assert_eq!(pending.len(), 1);
+	assert_eq!(pending[0].id, ordered.image.id);
+
+	// Managing images does not allow approving them
+	let response = env
+		.app
+		.post(&format!("/locations/1/images/{}/approve", ordered.image.id))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let env = env.login_admin().await;
+
+	let response = env
+		.app
+		.post(&format!("/locations/1/images/{}/approve", ordered.image.id))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let location = env.app.get("/locations/1").await.json::<LocationResponse>();
+	assert_eq!(location.images.len(), 1);
+	assert_eq!(location.images[0].id, ordered.image.id);
+
+	let response = env.app.get("/locations/1/images/pending").await;
+	assert!(response.json::<Vec<ImageResponse>>().is_empty());
+
+	// The image does not belong to location 2
+	let response = env
+		.app
+		.post(&format!("/locations/2/images/{}/approve", ordered.image.id))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+}
+
+/// Get the id, index and cover flag of every image of location 1
+async fn get_cover_order(env: &TestEnv) -> Vec<(i32, Option<i32>, bool)> {
+	let location = env.app.get("/locations/1").await.json::<LocationResponse>();
+
+	location.images.into_iter().map(|i| (i.id, i.index, i.is_cover)).collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn set_location_cover_image_test() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	let mut ids = vec![];
+
+	for index in 0..3 {
+		let ordered = NewImage {
+			file_path:    None,
+			uploaded_by:  profile.id,
+			image_url:    Some(format!("https://example.com/{index}.png")),
+			content_hash: None,
+		}
+		.insert_for_location(1, index, &conn)
+		.await
+		.unwrap();
+
+		ids.push(ordered.image.id);
+	}
+
+	let response = env.app.post("/locations/1/images/approve-all").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	// Only administrators of the location can pick its cover
+	let env = env.login("test2").await;
+
+	let response = env
+		.app
+		.post(&format!("/locations/1/images/{}/set-cover", ids[2]))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let env = env.login("test").await;
+
+	let response = env
+		.app
+		.post(&format!("/locations/1/images/{}/set-cover", ids[2]))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	// The cover moves to the front, the images before it shift back
+	assert_eq!(
+		get_cover_order(&env).await,
+		[
+			(ids[2], Some(0), true),
+			(ids[0], Some(1), false),
+			(ids[1], Some(2), false),
+		]
+	);
+
+	// A location only has a single cover
+	let response = env
+		.app
+		.post(&format!("/locations/1/images/{}/set-cover", ids[1]))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	assert_eq!(
+		get_cover_order(&env).await,
+		[
+			(ids[1], Some(0), true),
+			(ids[2], Some(1), false),
+			(ids[0], Some(2), false),
+		]
+	);
+
+	// The image does not belong to location 2
+	let response = env
+		.app
+		.post(&format!("/locations/2/images/{}/set-cover", ids[0]))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+	let response = env.app.get("/locations/1").await;
+	let body = response.json::<serde_json::Value>();
+
+	assert_eq!(body["images"][0]["isCover"], true);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn location_cover_image_order_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login_admin().await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	let mut ids = vec![];
+
+	for index in 0..3 {
+		let ordered = NewImage {
+			file_path:    None,
+			uploaded_by:  profile.id,
+			image_url:    Some(format!("https://example.com/{index}.png")),
+			content_hash: None,
+		}
+		.insert_for_location(1, index, &conn)
+		.await
+		.unwrap();
+
+		ids.push(ordered.image.id);
+	}
+
+	env.app.post("/locations/1/images/approve-all").await;
+
+	// A cover that is not at the front of the gallery is still shown first
+	let cover_id = ids[1];
+
+	conn.interact(move |conn| {
+		use db::location_image::dsl::*;
+
+		diesel::update(location_image.find((1, cover_id)))
+			.set(is_cover.eq(true))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let location = env.app.get("/locations/1").await.json::<LocationResponse>();
+	let order: Vec<_> = location.images.iter().map(|i| i.id).collect();
+
+	assert_eq!(order, [ids[1], ids[0], ids[2]]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reject_location_image_test() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	let ordered = NewImage {
+		file_path:    None,
+		uploaded_by:  profile.id,
+		image_url:    Some("https://example.com/image.png".to_string()),
+		content_hash: None,
+	}
+	.insert_for_location(1, 0, &conn)
+	.await
+	.unwrap();
+
+	let response = env
+		.app
+		.delete(&format!("/locations/1/images/{}", ordered.image.id))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let env = env.login_admin().await;
+
+	let response = env
+		.app
+		.delete(&format!("/locations/1/images/{}", ordered.image.id))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let image_id = ordered.image.id;
+	let image_count = conn
+		.interact(move |conn| {
+			use db::image::dsl::*;
+			use diesel::prelude::*;
+
+			image.filter(id.eq(image_id)).count().get_result::<i64>(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+
+	assert_eq!(image_count, 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn search_locations_review_aggregates_test() {
+	let env = TestEnv::new().await;
+
+	approve_seeded_locations(&env).await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	// Only the first location gets reviewed
+	for (username, rating) in [("test", 4), ("test2", 5)] {
+		let profile = env.get_profile(username).await.unwrap();
+
+		NewReview {
+			profile_id: profile.id,
+			location_id: 1,
+			rating,
+			body: None,
+		}
+		.insert(true, &conn)
+		.await
+		.unwrap();
+	}
+
+	let response = env.app.get("/locations").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let locations = response.json::<PaginatedResponse<Vec<LocationResponse>>>();
+
+	let reviewed = locations.data.iter().find(|l| l.id == 1).unwrap();
+	assert_eq!(reviewed.average_rating, Some(4.5));
+	assert_eq!(reviewed.review_count, 2);
+
+	let unreviewed = locations.data.iter().find(|l| l.id == 2).unwrap();
+	assert_eq!(unreviewed.average_rating, None);
+	assert_eq!(unreviewed.review_count, 0);
+
+	// Locations without reviews still expose the rating fields
+	let body = response.json::<serde_json::Value>();
+	let unreviewed =
+		body["data"].as_array().unwrap().iter().find(|l| l["id"] == 2).unwrap();
+
+	assert_eq!(unreviewed.get("averageRating"), Some(&serde_json::Value::Null));
+	assert_eq!(unreviewed["reviewCount"], 0);
+
+	let location = env.app.get("/locations/1").await.json::<LocationResponse>();
+	assert_eq!(location.average_rating, Some(4.5));
+	assert_eq!(location.review_count, 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn favourite_location_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	let response = env.app.post("/locations/1/favourite").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	// Favouriting a location twice is not an error
+	let response = env.app.post("/locations/1/favourite").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let response = env.app.post("/locations/2/favourite").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let location = env.app.get("/locations/1").await.json::<LocationResponse>();
+	assert_eq!(location.favourite_count, Some(1));
+
+	let response =
+		env.app.get(&format!("/profiles/{}/favourites", profile.id)).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<LocationResponse>>>();
+	let ids: Vec<i32> = body.data.iter().map(|l| l.id).collect();
+	assert_eq!(ids, vec![2, 1]);
+
+	let response = env.app.delete("/locations/1/favourite").await;
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let body = env
+		.app
+		.get(&format!("/profiles/{}/favourites", profile.id))
+		.await
+		.json::<PaginatedResponse<Vec<LocationResponse>>>();
+	assert_eq!(body.data.len(), 1);
+	assert_eq!(body.data[0].id, 2);
+
+	let location = env.app.get("/locations/1").await.json::<LocationResponse>();
+	assert_eq!(location.favourite_count, Some(0));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_profile_favourites_unauthorized_test() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	let response =
+		env.app.get(&format!("/profiles/{}/favourites", profile.id)).await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn hard_delete_favourited_location_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env.app.post("/locations/1/favourite").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let env = env.login_admin().await;
+
+	let response = env.app.delete("/admin/locations/1/hard").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let favourite_count = conn
+		.interact(|conn| {
+			use db::location_favourite::dsl::*;
+			use diesel::prelude::*;
+
+			location_favourite
+				.filter(location_id.eq(1))
+				.count()
+				.get_result::<i64>(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+
+	assert_eq!(favourite_count, 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_location_etag_test() {
+	let env = TestEnv::new().await;
+
+	let response = env.app.get("/locations/1").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let etag = response.header(header::ETAG);
+	assert!(etag.to_str().unwrap().starts_with("W/\""));
+
+	let response = env
+		.app
+		.get("/locations/1")
+		.add_header(header::IF_NONE_MATCH, etag.clone())
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NOT_MODIFIED);
+	assert_eq!(response.header(header::ETAG), etag);
+	assert!(response.as_bytes().is_empty());
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	LocationUpdate {
+		name:          Some("Renamed location".to_string()),
+		seat_count:    None,
+		is_reservable: None,
+		is_visible:    None,
+		street:        None,
+		number:        None,
+		zip:           None,
+		city:          None,
+		province:      None,
+		latitude:      None,
+		longitude:     None,
+		updated_by:    1,
+	}
+	.apply_to(1, LocationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let response = env
+		.app
+		.get("/locations/1")
+		.add_header(header::IF_NONE_MATCH, etag.clone())
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+	assert_ne!(response.header(header::ETAG), etag);
+
+	let location = response.json::<LocationResponse>();
+	assert_eq!(location.name, "Renamed location");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_location_opening_times_etag_test() {
+	let env = TestEnv::new().await;
+
+	let response = env.app.get("/locations/1/opening-times").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let etag = response.header(header::ETAG);
+
+	let response = env
+		.app
+		.get("/locations/1/opening-times")
+		.add_header(header::IF_NONE_MATCH, etag.clone())
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NOT_MODIFIED);
+
+	// Different query parameters produce a different response
+	let response = env
+		.app
+		.get("/locations/1/opening-times?startDate=2025-07-03")
+		.add_header(header::IF_NONE_MATCH, etag.clone())
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+	assert_ne!(response.header(header::ETAG), etag);
+
+	// Responses including reservations are never cached
+	let response = env
+		.app
+		.get("/locations/1/opening-times?reservations=true")
+		.add_header(header::IF_NONE_MATCH, etag)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+	assert!(response.maybe_header(header::ETAG).is_none());
+}
+
+/// Give the second seeded location two opening times on the same day as the
+/// seeded opening time of the first location, which can no longer be
+/// reserved by then
+async fn add_split_opening_times(env: &TestEnv) {
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let day = chrono::NaiveDate::from_ymd_opt(2025, 7, 2).unwrap();
+	let reservable_until = chrono::NaiveDate::from_ymd_opt(2025, 7, 1)
+		.unwrap()
+		.and_hms_opt(12, 0, 0);
+
+	let times = [(9, 12), (13, 17)].map(|(start, end)| {
+		NewOpeningTime {
+			location_id: 2,
+			day,
+			start_time: NaiveTime::from_hms_opt(start, 0, 0).unwrap(),
+			end_time: NaiveTime::from_hms_opt(end, 0, 0).unwrap(),
+			seat_count: None,
+			reservable_from: None,
+			reservable_until,
+			created_by: 1,
+		}
+	});
+
+	NewOpeningTime::bulk_insert(
+		times.to_vec(),
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+}
+
+/// Search the locations with the given query parameters and return the ids
+/// of the results
+async fn search_location_ids(
+	env: &TestEnv,
+	params: &[(&str, &str)],
+) -> Vec<i32> {
+	let response = env.app.get("/locations").add_query_params(params).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	response
+		.json::<PaginatedResponse<Vec<LocationResponse>>>()
+		.data
+		.into_iter()
+		.map(|l| l.id)
+		.collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn search_locations_open_at_test() {
+	let env = TestEnv::new().await;
+
+	approve_seeded_locations(&env).await;
+	add_split_opening_times(&env).await;
+
+	// Multiple opening times on the same day do not duplicate a location
+	let ids = search_location_ids(
+		&env,
+		&[("openOn", "2025-07-02"), ("openAt", "10:00:00")],
+	)
+	.await;
+	assert_eq!(ids, vec![1, 2]);
+
+	let ids = search_location_ids(
+		&env,
+		&[("openOn", "2025-07-02"), ("openAt", "13:00:00")],
+	)
+	.await;
+	assert_eq!(ids, vec![1, 2]);
+
+	// Opening times do not cover the moment they end
+	let ids = search_location_ids(
+		&env,
+		&[("openOn", "2025-07-02"), ("openAt", "12:00:00")],
+	)
+	.await;
+	assert_eq!(ids, vec![1]);
+
+	let ids = search_location_ids(
+		&env,
+		&[("openOn", "2025-07-02"), ("openAt", "22:00:00")],
+	)
+	.await;
+	assert!(ids.is_empty());
+
+	let ids = search_location_ids(&env, &[("openOn", "2025-07-03")]).await;
+	assert!(ids.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn search_locations_reservable_at_test() {
+	let env = TestEnv::new().await;
+
+	approve_seeded_locations(&env).await;
+	add_split_opening_times(&env).await;
+
+	let ids = search_location_ids(
+		&env,
+		&[
+			("openOn", "2025-07-02"),
+			("openAt", "10:00:00"),
+			("isReservable", "true"),
+		],
+	)
+	.await;
+	assert_eq!(ids, vec![1]);
+
+	// Without a time of day, there is no moment to reserve at
+	let ids = search_location_ids(
+		&env,
+		&[("openOn", "2025-07-02"), ("isReservable", "true")],
+	)
+	.await;
+	assert_eq!(ids, vec![1, 2]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_location_stats_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login_admin().await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let profile = env.get_profile("test2").await.unwrap();
+
+	// Cancel a second reservation on the seeded opening time
+	let cancelled = env
+		.insert_reservation_unchecked(NewReservation {
+			profile_id:       profile.id,
+			opening_time_id:  1,
+			base_block_index: 10,
+			block_count:      8,
+		})
+		.await;
+
+	conn.interact(move |conn| {
+		use db::reservation::dsl::*;
+
+		diesel::update(reservation.find(cancelled.primitive.id))
+			.set(state.eq(db::ReservationState::Cancelled))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let response = env
+		.app
+		.get("/locations/1/stats")
+		.add_query_param("from", "2025-07-01")
+		.add_query_param("to", "2025-07-03")
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<LocationStatsResponse>();
+
+	let days: Vec<_> = body
+		.days
+		.iter()
+		.map(|d| (d.day.to_string(), d.reservation_count, d.cancelled_count))
+		.collect();
+	assert_eq!(
+		days,
+		vec![
+			("2025-07-01".to_string(), 0, 0),
+			("2025-07-02".to_string(), 1, 1),
+			("2025-07-03".to_string(), 0, 0),
+		]
+	);
+
+	// Only the seeded reservation of 4 blocks counts towards occupancy, the
+	// seeded opening time offers 100 seats from 08:00 until 22:00
+	let reserved_minutes = i64::from(4 * RESERVATION_BLOCK_SIZE_MINUTES);
+	let capacity_minutes = 14 * 60 * 100;
+
+	assert_eq!(body.days[1].reserved_minutes, reserved_minutes);
+	assert_eq!(body.days[1].capacity_minutes, capacity_minutes);
+	assert_eq!(body.days[0].capacity_minutes, 0);
+	assert!(body.days[0].occupancy.abs() < f64::EPSILON);
+
+	assert_eq!(body.totals.reservation_count, 1);
+	assert_eq!(body.totals.cancelled_count, 1);
+	assert_eq!(body.totals.reserved_minutes, reserved_minutes);
+	assert_eq!(body.totals.capacity_minutes, capacity_minutes);
+	assert_eq!(body.totals.unique_visitors, 1);
+	assert!(body.totals.occupancy > 0.0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_location_stats_invalid_range_test() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let response = env
+		.app
+		.get("/locations/1/stats")
+		.add_query_param("from", "2024-01-01")
+		.add_query_param("to", "2025-07-03")
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+	let response = env
+		.app
+		.get("/locations/1/stats")
+		.add_query_param("from", "2025-07-03")
+		.add_query_param("to", "2025-07-01")
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_location_stats_forbidden_test() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	let response = env
+		.app
+		.get("/locations/1/stats")
+		.add_query_param("from", "2025-07-01")
+		.add_query_param("to", "2025-07-03")
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_location_permissions_through_institution_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test2").await;
+
+	let admin = env.get_admin_profile().await.unwrap();
+	let test2 = env.get_profile("test2").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let inst_id = conn
+		.interact(|conn| {
+			use db::institution::dsl::*;
+
+			institution
+				.filter(slug.eq("ugent"))
+				.select(id)
+				.get_result::<i32>(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+
+	let authority = NewAuthority {
+		name:           "Faculteit Wetenschappen".to_string(),
+		description:    None,
+		created_by:     admin.id,
+		institution_id: Some(inst_id),
+	}
+	.insert(AuthorityIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let a_id = authority.primitive.id;
+
+	conn.interact(move |conn| {
+		use db::location::dsl::*;
+
+		diesel::update(location.find(1))
+			.set(authority_id.eq(Some(a_id)))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let role = NewInstitutionRole {
+		institution_id: inst_id,
+		name:           "administrator".to_string(),
+		colour:         None,
+		permissions:    InstitutionPermissions::Administrator.bits(),
+		created_by:     admin.id,
+	}
+	.insert(inst_id, RoleIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	NewInstitutionMember {
+		institution_id:      inst_id,
+		profile_id:          test2.id,
+		institution_role_id: Some(role.primitive.id),
+		added_by:            admin.id,
+	}
+	.insert(&conn)
+	.await
+	.unwrap();
+
+	let response = env.app.get("/locations/1/permissions").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<LocationPermissionsResponse>();
+
+	assert_eq!(
+		body.institution_permissions,
+		InstitutionPermissions::Administrator.bits()
+	);
+	assert_eq!(body.authority_permissions, 0);
+	assert_eq!(body.location_permissions, 0);
+	assert_eq!(body.permissions_labels, vec!["Administrator".to_string()]);
+	assert!(body.can_edit);
+	assert!(body.can_approve);
+	assert!(body.can_manage_members);
+	assert!(body.can_manage_images);
+	assert!(body.can_confirm_reservations);
+
+	// The permission checks of other endpoints agree
+	let response = env.app.get("/locations/1/translations").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	// The same rights apply to the authority
+	let response =
+		env.app.get(&format!("/authorities/{a_id}/permissions")).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<AuthorityPermissionsResponse>();
+
+	assert_eq!(body.authority_permissions, 0);
+	assert!(body.can_edit);
+	assert!(body.can_manage_members);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_location_permissions_member_without_role_test() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	let admin = env.get_admin_profile().await.unwrap();
+	let test2 = env.get_profile("test2").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	NewLocationMember {
+		location_id:      1,
+		profile_id:       test2.id,
+		location_role_id: None,
+		added_by:         admin.id,
+	}
+	.insert(&conn)
+	.await
+	.unwrap();
+
+	let response = env.app.get("/locations/1/permissions").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<LocationPermissionsResponse>();
+
+	assert_eq!(body.institution_permissions, 0);
+	assert_eq!(body.authority_permissions, 0);
+	assert_eq!(body.location_permissions, 0);
+	assert!(body.permissions_labels.is_empty());
+	assert!(!body.can_edit);
+	assert!(!body.can_approve);
+	assert!(!body.can_manage_members);
+	assert!(!body.can_manage_images);
+	assert!(!body.can_confirm_reservations);
+
+	let response = env.app.get("/locations/1/translations").await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let response = env.app.get("/locations/999/permissions").await;
+
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+}
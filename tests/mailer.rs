@@ -0,0 +1,75 @@
+use ::common::Error;
+use blokmap::Config;
+use blokmap::mailer::{MAIL_PROCESSING_KEY, MAIL_QUEUE_KEY, Mailer};
+use profile::Profile;
+use redis::AsyncCommands;
+
+mod common;
+
+use common::TestEnv;
+
+/// Get a mailer that queues its messages for the SMTP worker instead of
+/// delivering them into the stub mailbox
+fn queue_mailer(env: &TestEnv, queue_size: usize) -> Mailer {
+	let mut config = Config::from_env();
+
+	config.email_smtp_server = "smtp.example.com".to_string();
+	config.email_queue_size = queue_size;
+
+	Mailer::new(&config, env.redis_connection.clone(), None)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn queue_mail_test() {
+	let env = TestEnv::new().await;
+
+	let mailer = queue_mailer(&env, 2);
+	assert!(!mailer.is_stub());
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+	let p_id = env.get_profile("test").await.unwrap().id;
+	let profile = Profile::get(p_id, &conn).await.unwrap();
+
+	for _ in 0..2 {
+		let mail = mailer.try_build_message(&profile, "Hello", "Hi").unwrap();
+
+		mailer.send(mail).await.unwrap();
+	}
+
+	let mut r_conn = env.redis_connection.clone();
+
+	let queued: usize = r_conn.llen(MAIL_QUEUE_KEY).await.unwrap();
+	assert_eq!(queued, 2);
+
+	// A full queue refuses new mails
+	let mail = mailer.try_build_message(&profile, "Hello", "Hi").unwrap();
+
+	let res = mailer.send(mail).await;
+	assert!(matches!(res, Err(Error::InternalServerError)));
+
+	let queued: usize = r_conn.llen(MAIL_QUEUE_KEY).await.unwrap();
+	assert_eq!(queued, 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn requeue_unfinished_mails_test() {
+	let env = TestEnv::new().await;
+
+	let mut r_conn = env.redis_connection.clone();
+
+	// A worker stopped while delivering two mails, one more is waiting
+	let _: () =
+		r_conn.rpush(MAIL_PROCESSING_KEY, &["first", "second"]).await.unwrap();
+	let _: () = r_conn.rpush(MAIL_QUEUE_KEY, "third").await.unwrap();
+
+	let requeued = Mailer::requeue_unfinished_mails(&mut r_conn).await.unwrap();
+	assert_eq!(requeued, 2);
+
+	// The unfinished mails are delivered first, in their original order
+	let queue: Vec<String> =
+		r_conn.lrange(MAIL_QUEUE_KEY, 0, -1).await.unwrap();
+	assert_eq!(queue, ["first", "second", "third"]);
+
+	let processing: usize = r_conn.llen(MAIL_PROCESSING_KEY).await.unwrap();
+	assert_eq!(processing, 0);
+}
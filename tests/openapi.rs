@@ -0,0 +1,102 @@
+use axum::http::StatusCode;
+use serde_json::Value;
+use utoipa::openapi::OpenApi;
+
+mod common;
+
+use common::TestEnv;
+
+/// Collect every `$ref` in a JSON document
+fn collect_refs<'a>(value: &'a Value, refs: &mut Vec<&'a str>) {
+	match value {
+		Value::Object(map) => {
+			for (key, value) in map {
+				match (key.as_str(), value) {
+					("$ref", Value::String(r)) => refs.push(r),
+					_ => collect_refs(value, refs),
+				}
+			}
+		},
+		Value::Array(values) => {
+			for value in values {
+				collect_refs(value, refs);
+			}
+		},
+		_ => {},
+	}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_openapi_document() {
+	let env = TestEnv::new().await;
+
+	let response = env.app.get("/openapi.json").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let spec = serde_json::from_str::<OpenApi>(&response.text()).unwrap();
+	let reparsed =
+		serde_json::from_str::<OpenApi>(&serde_json::to_string(&spec).unwrap())
+			.unwrap();
+
+	assert!(spec == reparsed);
+
+	let paths = spec.paths.paths;
+
+	for path in [
+		"/auth/login",
+		"/profiles/{profile_id}",
+		"/locations",
+		"/locations/{id}",
+		"/reservations/{id}/cancel",
+	] {
+		assert!(paths.contains_key(path), "{path} is not documented");
+	}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_openapi_document_refs_resolve() {
+	let env = TestEnv::new().await;
+
+	let response = env.app.get("/openapi.json").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<Value>();
+	let schemas = body["components"]["schemas"].as_object().unwrap();
+
+	let mut refs = vec![];
+	collect_refs(&body, &mut refs);
+
+	assert!(!refs.is_empty());
+
+	for r in refs {
+		let name = r.strip_prefix("#/components/schemas/").unwrap();
+
+		assert!(schemas.contains_key(name), "{r} does not resolve");
+	}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_openapi_security_scheme() {
+	let env = TestEnv::new().await;
+
+	let response = env.app.get("/openapi.json").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<Value>();
+	let scheme = &body["components"]["securitySchemes"]["access_token"];
+
+	assert_eq!(scheme["type"], "apiKey");
+	assert_eq!(scheme["in"], "cookie");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_api_docs_hidden_in_production() {
+	let env = TestEnv::new().await;
+
+	let response = env.app.get("/docs").await;
+
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+}
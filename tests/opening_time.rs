@@ -1,5 +1,24 @@
+use std::collections::HashMap;
+
 use axum::http::StatusCode;
-use blokmap::schemas::opening_time::OpeningTimeResponse;
+use blokmap::schemas::location::LocationResponse;
+use blokmap::schemas::opening_time::{
+	DeletedOpeningTimeResponse,
+	GeneratedHolidayExceptionsResponse,
+	NextAvailableOpeningTimeResponse,
+	OpeningTimeAvailabilityResponse,
+	OpeningTimeBlocksResponse,
+	OpeningTimeResponse,
+};
+use chrono::{Datelike, Duration, NaiveTime, Utc, Weekday};
+use db::ReservationState;
+use opening_time::{NewOpeningTime, OpeningTimeIncludes};
+use reservation::{
+	NewReservation,
+	Reservation,
+	ReservationIncludes,
+	block_occupation,
+};
 
 mod common;
 
@@ -23,6 +42,52 @@ async fn test_get_location_opening_times() {
 	assert!(!body.is_empty());
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_location_opening_times_occupancy() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let test2 = env.get_profile("test2").await.unwrap();
+	let admin = env.get_admin_profile().await.unwrap();
+
+	// Both overlap with the seeded reservation of 4 blocks
+	for (profile_id, base_block_index, block_count) in
+		[(test2.id, 2, 6), (admin.id, 0, 10)]
+	{
+		env.insert_reservation_unchecked(NewReservation {
+			profile_id,
+			opening_time_id: time.primitive.id,
+			base_block_index,
+			block_count,
+		})
+		.await;
+	}
+
+	let response = env
+		.app
+		.get("/locations/1/opening-times")
+		.add_query_param("reservations", true)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<Vec<OpeningTimeResponse>>();
+	let occupied = body.iter().find(|t| t.id == time.primitive.id).unwrap();
+
+	assert_eq!(occupied.booked_blocks, Some(20));
+	assert_eq!(occupied.total_blocks, Some(168));
+
+	// Occupancy is left out unless explicitly requested
+	let response = env.app.get("/locations/1/opening-times").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<serde_json::Value>();
+
+	assert!(body[0].get("bookedBlocks").is_none());
+	assert!(body[0].get("totalBlocks").is_none());
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_create_opening_time() {
 	let env = TestEnv::new().await.login_admin().await;
@@ -58,7 +123,7 @@ async fn test_create_opening_time() {
 
 #[tokio::test(flavor = "multi_thread")]
 async fn test_update_opening_time() {
-	let env = TestEnv::new().await.login_admin().await;
+	let env = TestEnv::new().await.login("test").await;
 
 	let location = env.get_location().await.unwrap();
 
@@ -111,7 +176,7 @@ async fn test_update_opening_time() {
 
 #[tokio::test(flavor = "multi_thread")]
 async fn test_delete_location_time() {
-	let env = TestEnv::new().await.login_admin().await;
+	let env = TestEnv::new().await.login("test").await;
 
 	let location = env.get_location().await.unwrap();
 
@@ -143,5 +208,871 @@ async fn test_delete_location_time() {
 		))
 		.await;
 
-	assert_eq!(delete_response.status_code(), StatusCode::NO_CONTENT);
+	assert_eq!(delete_response.status_code(), StatusCode::OK);
+
+	let body = delete_response.json::<DeletedOpeningTimeResponse>();
+	assert_eq!(body.cancelled_reservations, 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_opening_time_wrong_location() {
+	let env = TestEnv::new().await.login("test").await;
+
+	// The seeded opening time belongs to location 1
+	let time = env.get_opening_time().await.unwrap();
+	let url = format!("/locations/2/opening-times/{}", time.primitive.id);
+
+	let update_response = env
+		.app
+		.patch(&url)
+		.json(&serde_json::json!({ "seatCount": 100 }))
+		.await;
+
+	assert_eq!(update_response.status_code(), StatusCode::NOT_FOUND);
+
+	let delete_response = env.app.delete(&url).await;
+
+	assert_eq!(delete_response.status_code(), StatusCode::NOT_FOUND);
+
+	// The opening time itself is left untouched
+	let response = env
+		.app
+		.get(format!("/opening-times/{}/location", time.primitive.id).as_str())
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+	assert_eq!(response.json::<LocationResponse>().id, 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_opening_time_unauthorized() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let url = format!(
+		"/locations/{}/opening-times/{}",
+		time.primitive.location_id, time.primitive.id
+	);
+
+	let update_response = env
+		.app
+		.patch(&url)
+		.json(&serde_json::json!({ "endTime": "09:00:00" }))
+		.await;
+
+	assert_eq!(update_response.status_code(), StatusCode::FORBIDDEN);
+
+	let delete_response = env.app.delete(&url).await;
+
+	assert_eq!(delete_response.status_code(), StatusCode::FORBIDDEN);
+
+	// The opening time and its reservations are left untouched
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let unchanged = env.get_opening_time().await.unwrap();
+	let reservation =
+		Reservation::get_by_id(1, ReservationIncludes::default(), &conn)
+			.await
+			.unwrap();
+
+	assert_eq!(unchanged.primitive.end_time, time.primitive.end_time);
+	assert_ne!(reservation.primitive.state, ReservationState::Cancelled);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_generate_holiday_exceptions() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let location = env.get_location().await.unwrap();
+
+	let create_request = serde_json::json!([{
+		"day":       "2025-01-01",
+		"startTime": "08:30:00",
+		"endTime":   "22:00:00",
+	}]);
+
+	let create_response = env
+		.app
+		.post(&format!("/locations/{}/opening-times", location.primitive.id))
+		.json(&create_request)
+		.await;
+
+	assert_eq!(create_response.status_code(), StatusCode::CREATED);
+
+	let response = env
+		.app
+		.post(&format!(
+			"/locations/{}/opening-times/generate-holiday-exceptions",
+			location.primitive.id
+		))
+		.add_query_params([("country", "BE"), ("year", "2025")])
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let body = response.json::<GeneratedHolidayExceptionsResponse>();
+
+	assert_eq!(body.created_exceptions.len(), 1);
+	assert_eq!(body.created_exceptions[0].date, "2025-01-01".parse().unwrap());
+	assert_eq!(body.created_exceptions[0].holiday_name, "Nieuwjaar");
+
+	// Countries outside of the embedded dataset are refused
+	let response = env
+		.app
+		.post(&format!(
+			"/locations/{}/opening-times/generate-holiday-exceptions",
+			location.primitive.id
+		))
+		.add_query_params([("country", "XX"), ("year", "2025")])
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_create_opening_time_series() {
+	let env = TestEnv::new().await.login("test").await;
+
+	// Daylight saving time starts on the last sunday of march
+	let create_request = serde_json::json!({
+		"startDate": "2025-03-01",
+		"endDate":   "2025-03-31",
+		"weekdays":  ["Mon", "Wed"],
+		"startTime": "08:00:00",
+		"endTime":   "18:00:00",
+		"seatCount": 20,
+	});
+
+	let response = env
+		.app
+		.post("/locations/1/opening-times/series")
+		.json(&create_request)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let body = response.json::<Vec<OpeningTimeResponse>>();
+
+	assert_eq!(body.len(), 9);
+	assert!(
+		body.iter()
+			.all(|t| matches!(t.day.weekday(), Weekday::Mon | Weekday::Wed))
+	);
+	assert!(body.iter().all(|t| {
+		t.start_time == NaiveTime::from_hms_opt(8, 0, 0).unwrap()
+			&& t.end_time == NaiveTime::from_hms_opt(18, 0, 0).unwrap()
+			&& t.seat_count == Some(20)
+	}));
+	assert_eq!(body.last().unwrap().day, "2025-03-31".parse().unwrap());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_create_opening_time_series_unauthorized() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	let create_request = serde_json::json!({
+		"startDate": "2025-03-01",
+		"endDate":   "2025-03-31",
+		"weekdays":  ["Mon", "Wed"],
+		"startTime": "08:00:00",
+		"endTime":   "18:00:00",
+	});
+
+	let response = env
+		.app
+		.post("/locations/1/opening-times/series")
+		.json(&create_request)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_create_opening_time_series_too_long() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let create_request = serde_json::json!({
+		"startDate": "2026-01-01",
+		"endDate":   "2027-01-02",
+		"weekdays":  ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+		"startTime": "08:00:00",
+		"endTime":   "18:00:00",
+	});
+
+	let response = env
+		.app
+		.post("/locations/1/opening-times/series")
+		.json(&create_request)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["details"]["reason"], "validation_error");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_create_opening_time_series_collision() {
+	let env = TestEnv::new().await.login("test").await;
+
+	// The seeded opening time of location 1 is on wednesday 2025-07-02
+	let create_request = serde_json::json!({
+		"startDate": "2025-06-30",
+		"endDate":   "2025-07-06",
+		"weekdays":  ["Mon", "Wed"],
+		"startTime": "08:00:00",
+		"endTime":   "18:00:00",
+	});
+
+	let response = env
+		.app
+		.post("/locations/1/opening-times/series")
+		.json(&create_request)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["details"]["reason"], "opening_time_collision");
+	assert_eq!(body["details"]["days"], serde_json::json!(["2025-07-02"]));
+
+	// Nothing of the colliding series should have been inserted
+	let create_request = serde_json::json!({
+		"startDate": "2025-06-30",
+		"endDate":   "2025-07-06",
+		"weekdays":  ["Mon"],
+		"startTime": "08:00:00",
+		"endTime":   "18:00:00",
+	});
+
+	let response = env
+		.app
+		.post("/locations/1/opening-times/series")
+		.json(&create_request)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+	assert_eq!(response.json::<Vec<OpeningTimeResponse>>().len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_next_available_opening_times() {
+	let env = TestEnv::new().await;
+
+	let location = env.get_location().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let day = (Utc::now() + Duration::days(7)).date_naive();
+
+	// A single seat for one hour, so twelve blocks in total
+	let times = NewOpeningTime::bulk_insert(
+		vec![NewOpeningTime {
+			location_id: location.primitive.id,
+			day,
+			start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+			seat_count: Some(1),
+			reservable_from: None,
+			reservable_until: None,
+			created_by: profile.id,
+		}],
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	let t_id = times[0].id;
+
+	let response = env
+		.app
+		.post("/opening-times/next-available")
+		.json(&serde_json::json!({
+			"locationIds": [location.primitive.id, 999_999],
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response
+		.json::<HashMap<i32, Option<NextAvailableOpeningTimeResponse>>>();
+	let next = body[&location.primitive.id].as_ref().unwrap();
+	assert_eq!(next.opening_time.id, t_id);
+	assert_eq!(next.free_blocks, 12);
+	assert!(body[&999_999].is_none());
+
+	// Book the only seat for the entire opening time
+	NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  t_id,
+		base_block_index: 0,
+		block_count:      12,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let response = env
+		.app
+		.post("/opening-times/next-available")
+		.json(&serde_json::json!({
+			"locationIds": [location.primitive.id],
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response
+		.json::<HashMap<i32, Option<NextAvailableOpeningTimeResponse>>>();
+	assert!(body[&location.primitive.id].is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_opening_time_location() {
+	let env = TestEnv::new().await;
+
+	let location = env.get_location().await.unwrap();
+	let time = env.get_opening_time().await.unwrap();
+
+	let response = env
+		.app
+		.get(format!("/opening-times/{}/location", time.primitive.id).as_str())
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<LocationResponse>();
+	assert_eq!(body.id, location.primitive.id);
+	assert_eq!(body.id, time.primitive.location_id);
+
+	let response = env.app.get("/opening-times/999999/location").await;
+
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_opening_times_availability() {
+	let env = TestEnv::new().await;
+
+	let location = env.get_location().await.unwrap();
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	// The seeded opening time already has a reservation of 4 blocks
+	env.insert_reservation_unchecked(NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 10,
+		block_count:      6,
+	})
+	.await;
+
+	let response = env
+		.app
+		.get(
+			format!(
+				"/locations/{}/opening-times/availability",
+				location.primitive.id
+			)
+			.as_str(),
+		)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<Vec<OpeningTimeAvailabilityResponse>>();
+	let availability =
+		body.iter().find(|t| t.opening_time.id == time.primitive.id).unwrap();
+
+	assert_eq!(availability.booked_blocks, 4 + 6);
+	// 08:00 until 22:00 in blocks of 5 minutes
+	assert_eq!(availability.total_blocks, 14 * 12);
+}
+
+#[test]
+fn test_block_occupation_overlapping_spans() {
+	let occupation = block_occupation(6, &[(0, 3), (2, 2), (1, 1)]);
+
+	assert_eq!(occupation, [1, 2, 2, 1, 0, 0]);
+}
+
+#[test]
+fn test_block_occupation_out_of_bounds_spans() {
+	// Spans are clamped to the opening time instead of overflowing it
+	let occupation = block_occupation(4, &[(-2, 3), (3, 5), (10, 2)]);
+
+	assert_eq!(occupation, [1, 0, 0, 1]);
+	assert!(block_occupation(0, &[(0, 2)]).is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_opening_time_block_availability() {
+	let env = TestEnv::new().await;
+
+	let location = env.get_location().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let day = (Utc::now() + Duration::days(7)).date_naive();
+
+	let times = NewOpeningTime::bulk_insert(
+		[Some(1), None]
+			.into_iter()
+			.map(|seat_count| {
+				NewOpeningTime {
+					location_id: location.primitive.id,
+					day,
+					start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+					end_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+					seat_count,
+					reservable_from: None,
+					reservable_until: None,
+					created_by: profile.id,
+				}
+			})
+			.collect(),
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	let t_id = times[0].id;
+
+	// Reserve 08:10 - 08:20 and 08:30 - 08:40, cancelling the latter
+	let mut r_ids = vec![];
+	for base_block_index in [2, 6] {
+		let reservation = env
+			.insert_reservation_unchecked(NewReservation {
+				profile_id: profile.id,
+				opening_time_id: t_id,
+				base_block_index,
+				block_count: 2,
+			})
+			.await;
+
+		r_ids.push(reservation.primitive.id);
+	}
+
+	Reservation::cancel(r_ids[1], ReservationIncludes::default(), &conn)
+		.await
+		.unwrap();
+
+	let response =
+		env.app.get(&format!("/opening-times/{t_id}/availability")).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<OpeningTimeBlocksResponse>();
+
+	assert_eq!(body.block_size, 5);
+	assert_eq!(body.total_blocks, 12);
+	assert_eq!(body.seat_count, 1);
+	assert_eq!(body.blocks.len(), 12);
+
+	for block in &body.blocks {
+		let full = (2..4).contains(&block.block_index);
+
+		assert_eq!(block.available, !full, "block {}", block.block_index);
+	}
+
+	// Without a seat count of its own the one of the location is used
+	let response = env
+		.app
+		.get(&format!("/opening-times/{}/availability", times[1].id))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<OpeningTimeBlocksResponse>();
+
+	assert_eq!(body.seat_count, location.primitive.seat_count);
+	assert!(body.blocks.iter().all(|b| b.available));
+
+	let response = env.app.get("/opening-times/999999/availability").await;
+
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_update_opening_time_shifts_reservations() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let location = env.get_location().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let day = (Utc::now() + Duration::days(7)).date_naive();
+
+	let times = NewOpeningTime::bulk_insert(
+		vec![NewOpeningTime {
+			location_id: location.primitive.id,
+			day,
+			start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+			seat_count: None,
+			reservable_from: None,
+			reservable_until: None,
+			created_by: profile.id,
+		}],
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	let t_id = times[0].id;
+
+	// Reserve 08:10 - 08:20
+	let reservation = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  t_id,
+		base_block_index: 2,
+		block_count:      2,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let r_id = reservation.primitive.id;
+	let l_id = location.primitive.id;
+	let url = format!("/locations/{l_id}/opening-times/{t_id}");
+
+	// Advancing the start time by one block moves the reservation one block
+	// closer to the start
+	let response = env
+		.app
+		.patch(&url)
+		.json(&serde_json::json!({ "startTime": "08:05:00" }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let reservation =
+		Reservation::get_by_id(r_id, ReservationIncludes::default(), &conn)
+			.await
+			.unwrap();
+	assert_eq!(reservation.primitive.base_block_index, 1);
+	assert_eq!(reservation.primitive.block_count, 2);
+
+	// Moving the start time by part of a block is not possible
+	let response = env
+		.app
+		.patch(&url)
+		.json(&serde_json::json!({ "startTime": "08:07:00" }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let reservation =
+		Reservation::get_by_id(r_id, ReservationIncludes::default(), &conn)
+			.await
+			.unwrap();
+	assert_eq!(reservation.primitive.base_block_index, 1);
+	assert_eq!(reservation.primitive.state, ReservationState::Created);
+	assert_eq!(
+		reservation.opening_time.start_time,
+		NaiveTime::from_hms_opt(8, 5, 0).unwrap()
+	);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_update_opening_time_partial_block() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	let location = env.get_location().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let times = NewOpeningTime::bulk_insert(
+		vec![NewOpeningTime {
+			location_id:      location.primitive.id,
+			day:              (Utc::now() + Duration::days(7)).date_naive(),
+			start_time:       NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time:         NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+			seat_count:       None,
+			reservable_from:  None,
+			reservable_until: None,
+			created_by:       profile.id,
+		}],
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	let t_id = times[0].id;
+	let l_id = location.primitive.id;
+	let url = format!("/locations/{l_id}/opening-times/{t_id}");
+
+	// Without reservations the start time can move freely
+	let response = env
+		.app
+		.patch(&url)
+		.json(&serde_json::json!({ "startTime": "08:02:00" }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let response = env
+		.app
+		.patch(&url)
+		.json(&serde_json::json!({ "startTime": "08:00:00" }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let cancelled = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  t_id,
+		base_block_index: 2,
+		block_count:      2,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let c_id = cancelled.primitive.id;
+
+	conn.interact(move |conn| {
+		use db::reservation::dsl::*;
+
+		diesel::update(reservation.find(c_id))
+			.set(state.eq(ReservationState::Cancelled))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	// Cancelled reservations don't hold on to any blocks
+	let response = env
+		.app
+		.patch(&url)
+		.json(&serde_json::json!({ "startTime": "08:02:00" }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let response = env
+		.app
+		.patch(&url)
+		.json(&serde_json::json!({ "startTime": "08:00:00" }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let active = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  t_id,
+		base_block_index: 4,
+		block_count:      2,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	// Once there is an active reservation, partial blocks are refused
+	let response = env
+		.app
+		.patch(&url)
+		.json(&serde_json::json!({ "startTime": "08:02:00" }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let response = env
+		.app
+		.patch(&url)
+		.json(&serde_json::json!({ "startTime": "08:05:00" }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	// Only the active reservation moves along with the start time
+	let active = Reservation::get_by_id(
+		active.primitive.id,
+		ReservationIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+	assert_eq!(active.primitive.base_block_index, 3);
+
+	let cancelled =
+		Reservation::get_by_id(c_id, ReservationIncludes::default(), &conn)
+			.await
+			.unwrap();
+	assert_eq!(cancelled.primitive.base_block_index, 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_update_opening_time_cancels_reservations() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let location = env.get_location().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+	let other = env.get_profile("test2").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let times = NewOpeningTime::bulk_insert(
+		vec![NewOpeningTime {
+			location_id:      location.primitive.id,
+			day:              (Utc::now() + Duration::days(7)).date_naive(),
+			start_time:       NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time:         NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+			seat_count:       None,
+			reservable_from:  None,
+			reservable_until: None,
+			created_by:       profile.id,
+		}],
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	let t_id = times[0].id;
+
+	// 08:30 - 09:30, 09:00 - 10:00 and 10:30 - 11:30
+	let mut r_ids = vec![];
+	for (profile_id, base_block_index) in
+		[(profile.id, 6), (other.id, 12), (other.id, 30)]
+	{
+		let reservation = NewReservation {
+			profile_id,
+			opening_time_id: t_id,
+			base_block_index,
+			block_count: 12,
+		}
+		.insert(ReservationIncludes::default(), &conn)
+		.await
+		.unwrap();
+
+		r_ids.push(reservation.primitive.id);
+	}
+
+	let outbox_size = env.stub_mailbox.mailbox.lock().len();
+
+	// Only the reservation between 09:00 and 10:00 still fits
+	let response = env
+		.app
+		.patch(&format!(
+			"/locations/{}/opening-times/{t_id}",
+			location.primitive.id
+		))
+		.json(&serde_json::json!({
+			"startTime": "09:00:00",
+			"endTime":   "11:00:00",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<OpeningTimeResponse>();
+	assert_eq!(body.cancelled_reservations, Some(2));
+
+	let mut states = vec![];
+	for r_id in &r_ids {
+		let reservation = Reservation::get_by_id(
+			*r_id,
+			ReservationIncludes::default(),
+			&conn,
+		)
+		.await
+		.unwrap();
+
+		states.push(reservation.primitive.state);
+	}
+
+	assert_eq!(
+		states,
+		[
+			ReservationState::Cancelled,
+			ReservationState::Created,
+			ReservationState::Cancelled,
+		]
+	);
+
+	let kept =
+		Reservation::get_by_id(r_ids[1], ReservationIncludes::default(), &conn)
+			.await
+			.unwrap();
+	assert_eq!(kept.primitive.base_block_index, 0);
+
+	// Both profiles are notified about their cancelled reservation
+	let mailbox = env.stub_mailbox.mailbox.lock();
+	let receivers: Vec<_> = mailbox[outbox_size..]
+		.iter()
+		.map(|m| m.envelope().to()[0].to_string())
+		.collect();
+
+	assert_eq!(receivers, ["test@example.com", "test2@example.com"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_delete_opening_time_cancels_reservations() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let location = env.get_location().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let times = NewOpeningTime::bulk_insert(
+		vec![NewOpeningTime {
+			location_id:      location.primitive.id,
+			day:              (Utc::now() + Duration::days(7)).date_naive(),
+			start_time:       NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time:         NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+			seat_count:       None,
+			reservable_from:  None,
+			reservable_until: None,
+			created_by:       profile.id,
+		}],
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	let t_id = times[0].id;
+
+	NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  t_id,
+		base_block_index: 0,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let url =
+		format!("/locations/{}/opening-times/{t_id}", location.primitive.id);
+
+	let response = env
+		.expect_mail_to(&["test@example.com"], async || {
+			env.app.delete(&url).await
+		})
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<DeletedOpeningTimeResponse>();
+	assert_eq!(body.cancelled_reservations, 1);
 }
@@ -1,15 +1,39 @@
+use ::common::{Error, OAuthError};
+use authority::{AuthorityIncludes, NewAuthority, NewAuthorityMember};
 use axum::http::StatusCode;
-use blokmap::schemas::auth::LoginRequest;
+use axum_test::multipart::{MultipartForm, Part};
+use blokmap::schemas::auth::{LoginRequest, RegisterRequest};
+use blokmap::schemas::authority::{
+	AuthorityResponse,
+	ProfileAuthorityMembershipResponse,
+};
+use blokmap::schemas::image::ImageWithLocationResponse;
 use blokmap::schemas::pagination::{PaginatedResponse, PaginationOptions};
 use blokmap::schemas::reservation::ReservationResponse;
-use db::ProfileState;
+use chrono::{Duration, DurationRound, NaiveTime, Utc};
+use db::{AuditAction, ProfileState, ReservationState};
+use image::NewImage;
+use opening_time::{NewOpeningTime, OpeningTimeIncludes};
+use permissions::AuthorityPermissions;
 use primitives::PrimitiveProfile;
-use profile::Profile;
+use profile::{NewProfile, Profile, ProfileClaims};
+use reservation::{NewReservation, Reservation, ReservationIncludes};
+use role::{NewAuthorityRole, RoleIncludes};
 
 mod common;
 
+use blokmap::Config;
 use blokmap::schemas::location::LocationResponse;
-use blokmap::schemas::profile::{ProfileResponse, UpdateProfileRequest};
+use blokmap::schemas::profile::{
+	CleanupPendingResponse,
+	DeactivationReasonResponse,
+	PendingVerificationResponse,
+	ProfileResponse,
+	ProfileStatsResponse,
+	UnlinkSsoRequest,
+	UpdateProfileRequest,
+};
+use blokmap::schemas::session::SessionResponse;
 use common::TestEnv;
 
 #[tokio::test(flavor = "multi_thread")]
@@ -33,6 +57,59 @@ async fn get_all_profiles() {
 	assert_eq!(response.status_code(), StatusCode::OK);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn get_all_profiles_cursor() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	// Go well past the hard limit on the amount of rows fetched at once
+	conn.interact(|conn| {
+		diesel::sql_query(
+			"INSERT INTO profile (username, password_hash)
+			SELECT 'generated-' || n, 'x'
+			FROM generate_series(1, 150) AS n",
+		)
+		.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let mut ids = vec![];
+	let mut cursor = None;
+
+	loop {
+		let mut request =
+			env.app.get("/profiles").add_query_param("perPage", 50);
+
+		if let Some(after_id) = cursor {
+			request = request.add_query_param("afterId", after_id);
+		}
+
+		let response = request.await;
+
+		assert_eq!(response.status_code(), StatusCode::OK);
+
+		let page = response.json::<PaginatedResponse<Vec<ProfileResponse>>>();
+
+		assert!(!page.truncated);
+		ids.extend(page.data.iter().map(|p| p.id));
+
+		cursor = page.next_cursor;
+		if cursor.is_none() {
+			assert_eq!(ids.len(), page.total);
+			break;
+		}
+	}
+
+	// Every profile is listed exactly once, in order
+	assert!(ids.len() > 150);
+	assert!(ids.windows(2).all(|w| w[0] < w[1]));
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn get_current_profile() {
 	let env = TestEnv::new().await.login("test").await;
@@ -89,18 +166,22 @@ async fn update_current_profile_pending_email() {
 		.unwrap()
 		.unwrap();
 
+	// The old email is told about the change as well
 	let response = env
-		.expect_mail_to(&["bobble@example.com"], async || {
-			env.app
-				.patch("/profiles/me")
-				.json(&UpdateProfileRequest {
-					username:      None,
-					first_name:    None,
-					last_name:     None,
-					pending_email: Some("bobble@example.com".to_string()),
-				})
-				.await
-		})
+		.expect_mails_to(
+			&["bobble@example.com", "test@example.com"],
+			async || {
+				env.app
+					.patch("/profiles/me")
+					.json(&UpdateProfileRequest {
+						username:      None,
+						first_name:    None,
+						last_name:     None,
+						pending_email: Some("bobble@example.com".to_string()),
+					})
+					.await
+			},
+		)
 		.await;
 	assert_eq!(response.status_code(), StatusCode::OK);
 
@@ -118,6 +199,141 @@ async fn update_current_profile_pending_email() {
 	assert_ne!(old_profile.pending_email, new_profile.pending_email);
 	assert!(new_profile.email_confirmation_token.is_some());
 	assert!(new_profile.email_confirmation_token_expiry.is_some());
+	assert!(new_profile.email_revert_token.is_some());
+	assert!(new_profile.email_revert_token_expiry.is_some());
+}
+
+/// Request a new email for the `test` profile and return its revert token
+async fn request_email_change(env: &TestEnv) -> String {
+	let response = env
+		.expect_mails_to(
+			&["bobble@example.com", "test@example.com"],
+			async || {
+				env.app
+					.patch("/profiles/me")
+					.json(&UpdateProfileRequest {
+						username:      None,
+						first_name:    None,
+						last_name:     None,
+						pending_email: Some("bobble@example.com".to_string()),
+					})
+					.await
+			},
+		)
+		.await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	profile.email_revert_token.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn revert_pending_email() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let cookie_name = Config::from_env().access_cookie_name;
+	let other_session = env
+		.app
+		.post("/auth/login")
+		.json(&LoginRequest {
+			username: "test".to_string(),
+			password: "foo".to_string(),
+			remember: false,
+		})
+		.await
+		.cookie(&cookie_name);
+
+	let token = request_email_change(&env).await;
+
+	let response = env.app.post(&format!("/auth/revert-email/{token}")).await;
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	assert_eq!(profile.email, Some("test@example.com".to_string()));
+	assert!(profile.pending_email.is_none());
+	assert!(profile.email_confirmation_token.is_none());
+	assert!(profile.email_confirmation_token_expiry.is_none());
+	assert!(profile.email_revert_token.is_none());
+	assert!(profile.email_revert_token_expiry.is_none());
+
+	// Every session of the profile is revoked
+	let response = env
+		.app
+		.get("/profiles/me")
+		.clear_cookies()
+		.add_cookie(other_session)
+		.await;
+	assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+	// The token can only be used once
+	let response = env.app.post(&format!("/auth/revert-email/{token}")).await;
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn revert_pending_email_expired_token() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let token = request_email_change(&env).await;
+
+	let expired_token = token.clone();
+	let new_expiry = Utc::now().naive_utc() - Duration::days(1);
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+	conn.interact(move |conn| {
+		use db::profile::dsl::*;
+		use diesel::prelude::*;
+
+		diesel::update(profile.filter(email_revert_token.eq(expired_token)))
+			.set(email_revert_token_expiry.eq(new_expiry))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let response = env.app.post(&format!("/auth/revert-email/{token}")).await;
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	// The change is left untouched
+	let response = env.app.get("/profiles/me").await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	assert_eq!(profile.pending_email, Some("bobble@example.com".to_string()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn revert_confirmed_email() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let token = request_email_change(&env).await;
+
+	// Whoever requested the change already confirmed the new email
+	let profile = env.get_profile("test").await.unwrap();
+	let confirmation_token = profile.email_confirmation_token.unwrap();
+
+	let response = env
+		.app
+		.post(&format!("/auth/confirm_email/{confirmation_token}"))
+		.await;
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let profile = env.get_profile("test").await.unwrap();
+	assert_eq!(profile.email, Some("bobble@example.com".to_string()));
+
+	let response = env.app.post(&format!("/auth/revert-email/{token}")).await;
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	assert_eq!(profile.email, Some("test@example.com".to_string()));
+	assert!(profile.previous_email.is_none());
+	assert!(profile.pending_email.is_none());
+	assert!(profile.email_revert_token.is_none());
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -142,6 +358,10 @@ async fn disable_profile() {
 	let bob = Profile::get(test_id, &conn).await.unwrap();
 
 	assert_eq!(bob.primitive.state, ProfileState::Disabled);
+
+	let entries = env.get_audit_entries(AuditAction::ProfileDisabled).await;
+	assert_eq!(entries.len(), 1);
+	assert_eq!(entries[0].target_id, test_id);
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -167,6 +387,9 @@ async fn disable_profile_not_admin() {
 	let bob = Profile::get(test_id, &conn).await.unwrap();
 
 	assert_eq!(bob.primitive.state, ProfileState::Active);
+
+	let entries = env.get_audit_entries(AuditAction::ProfileDisabled).await;
+	assert!(entries.is_empty());
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -193,6 +416,10 @@ async fn activate_profile() {
 	let bob = Profile::get(test_id, &conn).await.unwrap();
 
 	assert_eq!(bob.primitive.state, ProfileState::Active);
+
+	let entries = env.get_audit_entries(AuditAction::ProfileActivated).await;
+	assert_eq!(entries.len(), 1);
+	assert_eq!(entries[0].target_id, test_id);
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -228,9 +455,101 @@ async fn get_profile_locations() {
 	let env = TestEnv::new().await.login("test").await;
 
 	let response = env.app.get("/profiles/1/locations").await;
-	let _ = response.json::<Vec<LocationResponse>>();
+	let _ = response.json::<PaginatedResponse<Vec<LocationResponse>>>();
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_profile_locations_paginated() {
+	let env = TestEnv::new().await.login("test").await;
+
+	// The seeded locations are both created by `test`, add a third one
+	let response = env
+		.app
+		.post("/locations")
+		.json(&serde_json::json!({
+			"name": "Third Location",
+			"description": {
+				"nl": "test description",
+			},
+			"excerpt": {
+				"nl": "test excerpt",
+			},
+			"seatCount": 10,
+			"isReservable": true,
+			"isVisible": true,
+			"street": "Test Street",
+			"number": "123",
+			"zip": "9000",
+			"city": "Gent",
+			"province": "Oost-Vlaanderen",
+			"country": "BE",
+			"latitude": 51.0,
+			"longitude": 3.7
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let response = env
+		.app
+		.get("/profiles/1/locations")
+		.add_query_param("page", 1)
+		.add_query_param("perPage", 2)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<LocationResponse>>>();
+
+	assert_eq!(body.total, 3);
+	assert_eq!(body.data.len(), 2);
+
+	let response = env
+		.app
+		.get("/profiles/1/locations")
+		.add_query_param("page", 2)
+		.add_query_param("perPage", 2)
+		.await;
+
+	let body = response.json::<PaginatedResponse<Vec<LocationResponse>>>();
+
+	assert_eq!(body.data.len(), 1);
+	assert_eq!(body.data[0].name, "Third Location");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_current_profile_created_authorities() {
+	let env = TestEnv::new().await.login("test").await;
+
+	for name in ["Faculteit Wetenschappen", "Faculteit Letteren"] {
+		let response = env
+			.app
+			.post("/authorities")
+			.json(&serde_json::json!({ "name": name }))
+			.await;
+
+		assert_eq!(response.status_code(), StatusCode::CREATED);
+	}
+
+	let response = env.app.get("/profiles/me/created-authorities").await;
 
 	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<AuthorityResponse>>>();
+
+	assert_eq!(body.total, 2);
+	assert_eq!(body.data[0].name, "Faculteit Wetenschappen");
+	assert_eq!(body.data[1].name, "Faculteit Letteren");
+
+	// Authorities created by other profiles are not included
+	let env = env.login("test2").await;
+
+	let response = env.app.get("/profiles/me/created-authorities").await;
+	let body = response.json::<PaginatedResponse<Vec<AuthorityResponse>>>();
+
+	assert_eq!(body.total, 0);
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -238,7 +557,993 @@ async fn get_profile_reservations() {
 	let env = TestEnv::new().await.login("test").await;
 
 	let response = env.app.get("/profiles/1/reservations").await;
-	let _ = response.json::<Vec<ReservationResponse>>();
+	let _ = response.json::<PaginatedResponse<Vec<ReservationResponse>>>();
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_profile_reservations_default_window() {
+	let env = TestEnv::new().await.login("test").await;
+
+	// The seeded reservation lies too far in the past to be listed by default
+	let response = env.app.get("/profiles/1/reservations").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<ReservationResponse>>>();
+
+	assert_eq!(body.total, 0);
+	assert!(body.data.is_empty());
+
+	let response = env
+		.app
+		.get("/profiles/1/reservations")
+		.add_query_param("date", "2025-07-02")
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<ReservationResponse>>>();
+
+	assert_eq!(body.total, 1);
+	assert_eq!(body.data.len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_profile_reservations_by_time_and_state() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	let location = env.get_location().await.unwrap();
+	let profile = env.get_profile("test2").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let start = (Utc::now().naive_utc() - Duration::hours(1))
+		.duration_trunc(Duration::minutes(5))
+		.unwrap();
+
+	let times = NewOpeningTime::bulk_insert(
+		vec![NewOpeningTime {
+			location_id:      location.primitive.id,
+			day:              start.date(),
+			start_time:       start.time(),
+			end_time:         NaiveTime::from_hms_opt(23, 59, 59).unwrap(),
+			seat_count:       None,
+			reservable_from:  None,
+			reservable_until: None,
+			created_by:       profile.id,
+		}],
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	// Ended half an hour ago, in progress and starting in an hour and a half
+	let mut r_ids = vec![];
+	for (base_block_index, block_count) in [(0, 6), (6, 24), (30, 6)] {
+		let reservation = env
+			.insert_reservation_unchecked(NewReservation {
+				profile_id: profile.id,
+				opening_time_id: times[0].id,
+				base_block_index,
+				block_count,
+			})
+			.await;
+
+		r_ids.push(reservation.primitive.id);
+	}
+
+	Reservation::cancel(r_ids[2], ReservationIncludes::default(), &conn)
+		.await
+		.unwrap();
+
+	let fetch = async |query: &str| {
+		let response = env
+			.app
+			.get(&format!("/profiles/{}/reservations?{query}", profile.id))
+			.await;
+
+		assert_eq!(response.status_code(), StatusCode::OK);
+
+		let mut ids: Vec<_> = response
+			.json::<PaginatedResponse<Vec<ReservationResponse>>>()
+			.data
+			.into_iter()
+			.map(|r| r.id)
+			.collect();
+		ids.sort_unstable();
+
+		ids
+	};
+
+	assert_eq!(fetch("when=past").await, [r_ids[0]]);
+	assert_eq!(fetch("when=upcoming").await, [r_ids[1], r_ids[2]]);
+	assert_eq!(fetch("state=Cancelled").await, [r_ids[2]]);
+	assert_eq!(fetch("when=upcoming&state=Created").await, [r_ids[1]]);
+
+	let response = env
+		.app
+		.get(&format!("/profiles/{}/reservations?when=soon", profile.id))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn disable_profile_with_reason() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let admin = env.get_admin_profile().await.unwrap();
+	let test_id = env.get_profile("test").await.unwrap().id;
+
+	let response = env
+		.app
+		.post(&format!("/profiles/{test_id}/block"))
+		.json(&serde_json::json!({
+			"reason": "spamming reviews",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let response =
+		env.app.get(&format!("/profiles/{test_id}/deactivation-reason")).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<DeactivationReasonResponse>();
+
+	assert_eq!(body.reason.as_deref(), Some("spamming reviews"));
+	assert_eq!(body.deactivated_by, Some(admin.id));
+
+	let response = env.app.post(&format!("/profiles/{test_id}/unblock")).await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+	let bob = Profile::get(test_id, &conn).await.unwrap();
+
+	assert_eq!(bob.primitive.state, ProfileState::Active);
+	assert_eq!(bob.primitive.deactivation_reason, None);
+	assert_eq!(bob.primitive.deactivated_by, None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_pending_verification_profiles() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let response = env
+		.expect_mail_to(&["bob@example.com"], async || {
+			env.app
+				.post("/auth/register")
+				.json(&RegisterRequest {
+					username:   "bob".to_string(),
+					password:   "bobdebouwer1234!".to_string(),
+					email:      "bob@example.com".to_string(),
+					first_name: "Bob".to_string(),
+					last_name:  "De Bouwer".to_string(),
+				})
+				.await
+		})
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let response = env.app.get("/admin/profiles/pending-verification").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body =
+		response.json::<PaginatedResponse<Vec<PendingVerificationResponse>>>();
+
+	let pending = body
+		.data
+		.iter()
+		.find(|p| p.profile.username == "bob")
+		.expect("registered profile should be pending verification");
+
+	assert_eq!(pending.pending_email, "bob@example.com");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn unlink_sso() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let test_id = env.get_profile("test").await.unwrap().id;
+
+	// Turn the test profile into an SSO-linked profile
+	conn.interact(move |conn| {
+		use db::profile::dsl::*;
+		use diesel::prelude::*;
+
+		diesel::update(profile.find(test_id))
+			.set(password_hash.eq(String::new()))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	NewImage {
+		file_path:    None,
+		uploaded_by:  test_id,
+		image_url:    Some("https://example.com/avatar.png".to_string()),
+		content_hash: None,
+	}
+	.insert_for_profile(test_id, &conn)
+	.await
+	.unwrap();
+
+	let avatar_id =
+		Profile::get(test_id, &conn).await.unwrap().primitive.avatar_image_id;
+	assert!(avatar_id.is_some());
+
+	let response = env
+		.app
+		.post("/profiles/me/unlink-sso")
+		.json(&UnlinkSsoRequest {
+			password: "a-brand-new-password".to_string(),
+		})
+		.await;
 
 	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<ProfileResponse>();
+	assert!(body.avatar_url.is_none());
+
+	let test = Profile::get(test_id, &conn).await.unwrap();
+	assert!(!test.primitive.password_hash.is_empty());
+	assert!(test.primitive.avatar_image_id.is_none());
+	assert_eq!(test.primitive.state, ProfileState::Active);
+
+	// The avatar record is kept, only its external url is cleared
+	let image_url = conn
+		.interact(move |conn| {
+			use db::image::dsl::*;
+			use diesel::prelude::*;
+
+			image
+				.find(avatar_id.unwrap())
+				.select(image_url)
+				.get_result::<Option<String>>(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+	assert!(image_url.is_none());
+
+	let response = env
+		.app
+		.post("/auth/login")
+		.json(&LoginRequest {
+			username: "test".to_string(),
+			password: "a-brand-new-password".to_string(),
+			remember: false,
+		})
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn unlink_sso_password_profile() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env
+		.app
+		.post("/profiles/me/unlink-sso")
+		.json(&UnlinkSsoRequest {
+			password: "a-brand-new-password".to_string(),
+		})
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["details"]["reason"], "bad_request");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_profile_stats_cancellation_rate() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test2").await;
+
+	let profile = env.get_profile("test2").await.unwrap();
+	let past_time = env.get_opening_time().await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let future_time = NewOpeningTime::bulk_insert(
+		vec![NewOpeningTime {
+			location_id:      1,
+			day:              (Utc::now() + Duration::days(1)).date_naive(),
+			start_time:       NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time:         NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+			seat_count:       None,
+			reservable_from:  None,
+			reservable_until: None,
+			created_by:       profile.id,
+		}],
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap()
+	.remove(0);
+
+	// Three completed reservations, one upcoming and one cancelled
+	let slots = [
+		(past_time.primitive.id, 0),
+		(past_time.primitive.id, 12),
+		(past_time.primitive.id, 24),
+		(future_time.id, 0),
+		(future_time.id, 12),
+	];
+
+	let mut reservations = vec![];
+
+	for (t_id, base_block_index) in slots {
+		let reservation = env
+			.insert_reservation_unchecked(NewReservation {
+				profile_id: profile.id,
+				opening_time_id: t_id,
+				base_block_index,
+				block_count: 12,
+			})
+			.await;
+
+		reservations.push(reservation.primitive.id);
+	}
+
+	let cancelled_id = reservations[4];
+
+	conn.interact(move |conn| {
+		use db::reservation::dsl::*;
+
+		diesel::update(reservation.find(cancelled_id))
+			.set(state.eq(ReservationState::Cancelled))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let response =
+		env.app.get(&format!("/profiles/{}/stats", profile.id)).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let stats = response.json::<ProfileStatsResponse>();
+
+	assert_eq!(stats.total_reservations, 5);
+	assert_eq!(stats.completed_reservations, 3);
+	assert_eq!(stats.upcoming_reservations, 1);
+	assert_eq!(stats.cancelled_reservations, 1);
+	assert!((stats.cancellation_rate - 20.0).abs() < f64::EPSILON);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_profile_stats_cancelled_past_reservation() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test2").await;
+
+	let profile = env.get_profile("test2").await.unwrap();
+	let past_time = env.get_opening_time().await.unwrap();
+
+	let mut reservations = vec![];
+
+	for base_block_index in [0, 12, 24] {
+		let reservation = env
+			.insert_reservation_unchecked(NewReservation {
+				profile_id: profile.id,
+				opening_time_id: past_time.primitive.id,
+				base_block_index,
+				block_count: 12,
+			})
+			.await;
+
+		reservations.push(reservation.primitive.id);
+	}
+
+	let cancelled_id = reservations[0];
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	conn.interact(move |conn| {
+		use db::reservation::dsl::*;
+
+		diesel::update(reservation.find(cancelled_id))
+			.set(state.eq(ReservationState::Cancelled))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let response =
+		env.app.get(&format!("/profiles/{}/stats", profile.id)).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let stats = response.json::<ProfileStatsResponse>();
+
+	// A cancelled reservation never completes, even once its time has passed
+	assert_eq!(stats.total_reservations, 3);
+	assert_eq!(stats.completed_reservations, 2);
+	assert_eq!(stats.upcoming_reservations, 0);
+	assert_eq!(stats.cancelled_reservations, 1);
+	assert_eq!(stats.total_reservation_hours, 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cleanup_expired_pending_profiles() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let now = Utc::now().naive_utc();
+	let pending = [
+		("expired-1", now - Duration::days(2)),
+		("expired-2", now - Duration::minutes(5)),
+		("valid", now + Duration::days(1)),
+	];
+
+	for (username, expiry) in pending {
+		NewProfile {
+			username:                        username.to_string(),
+			password:                        "foo".to_string(),
+			pending_email:                   format!("{username}@example.com"),
+			email_confirmation_token:        format!("{username}-token"),
+			email_confirmation_token_expiry: expiry,
+			first_name:                      "Pending".to_string(),
+			last_name:                       "Profile".to_string(),
+		}
+		.insert(&conn)
+		.await
+		.unwrap();
+	}
+
+	let expired =
+		Profile::get_expired_pending_verification(&conn).await.unwrap();
+	let expired: Vec<_> =
+		expired.into_iter().map(|p| p.primitive.username).collect();
+
+	assert_eq!(expired, ["expired-1", "expired-2"]);
+
+	let response = env.app.post("/admin/profiles/cleanup-pending").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<CleanupPendingResponse>();
+
+	assert_eq!(body.deleted_profiles, 2);
+
+	for username in ["expired-1", "expired-2"] {
+		let profile =
+			Profile::get_by_username(username.to_string(), &conn).await;
+
+		assert!(profile.is_err());
+	}
+
+	let valid = Profile::get_by_username("valid".to_string(), &conn).await;
+
+	assert!(valid.is_ok());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_current_profile_authority_memberships() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let admin = env.get_admin_profile().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let memberships = [
+		("Wetenschappen", "editor", AuthorityPermissions::AddLocations),
+		("Letteren", "viewer", AuthorityPermissions::empty()),
+	];
+
+	for (name, role_name, permissions) in memberships {
+		let authority = NewAuthority {
+			name:           name.to_string(),
+			description:    None,
+			created_by:     admin.id,
+			institution_id: None,
+		}
+		.insert(AuthorityIncludes::default(), &conn)
+		.await
+		.unwrap();
+
+		let role = NewAuthorityRole {
+			authority_id: authority.primitive.id,
+			name:         role_name.to_string(),
+			colour:       None,
+			permissions:  permissions.bits(),
+			created_by:   admin.id,
+		}
+		.insert(authority.primitive.id, RoleIncludes::default(), &conn)
+		.await
+		.unwrap();
+
+		NewAuthorityMember {
+			authority_id:      authority.primitive.id,
+			profile_id:        profile.id,
+			authority_role_id: Some(role.primitive.id),
+			added_by:          admin.id,
+		}
+		.insert(&conn)
+		.await
+		.unwrap();
+	}
+
+	let response = env.app.get("/profiles/me/authority-memberships").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<Vec<ProfileAuthorityMembershipResponse>>();
+
+	assert_eq!(body.len(), 2);
+
+	for (membership, (name, role_name, _)) in body.iter().zip(memberships) {
+		let role = membership.role.as_ref().unwrap();
+
+		assert_eq!(membership.authority.name, name);
+		assert_eq!(role.name, role_name);
+	}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_current_profile_uploaded_images() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let test_id = env.get_profile("test").await.unwrap().id;
+
+	let (avatar, _) = NewImage {
+		file_path:    None,
+		uploaded_by:  test_id,
+		image_url:    Some("https://example.com/avatar.png".to_string()),
+		content_hash: None,
+	}
+	.insert_for_profile(test_id, &conn)
+	.await
+	.unwrap();
+
+	let location_image = NewImage {
+		file_path:    None,
+		uploaded_by:  test_id,
+		image_url:    Some("https://example.com/location.png".to_string()),
+		content_hash: None,
+	}
+	.insert_for_location(1, 0, &conn)
+	.await
+	.unwrap();
+
+	let response = env.app.get("/profiles/me/uploaded-images").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body =
+		response.json::<PaginatedResponse<Vec<ImageWithLocationResponse>>>();
+
+	assert_eq!(body.total, 2);
+
+	// Newest uploads come first
+	let location = &body.data[0];
+	assert_eq!(location.image.id, location_image.image.primitive.id);
+	assert_eq!(location.location_id, Some(1));
+	assert_eq!(
+		location.location_name.as_deref(),
+		Some("Bibliotheek S5 Sterre")
+	);
+
+	let profile = &body.data[1];
+	assert_eq!(profile.image.id, avatar.primitive.id);
+	assert_eq!(profile.location_id, None);
+	assert_eq!(profile.location_name, None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn delete_current_profile_avatar_test() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let test_id = env.get_profile("test").await.unwrap().id;
+
+	let (avatar, _) = NewImage {
+		file_path:    None,
+		uploaded_by:  test_id,
+		image_url:    Some("https://example.com/avatar.png".to_string()),
+		content_hash: None,
+	}
+	.insert_for_profile(test_id, &conn)
+	.await
+	.unwrap();
+
+	let avatar_id = avatar.primitive.id;
+
+	let response = env.app.delete("/profiles/me/avatar").await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let test = Profile::get(test_id, &conn).await.unwrap();
+	assert!(test.primitive.avatar_image_id.is_none());
+
+	let image_count = conn
+		.interact(move |conn| {
+			use db::image::dsl::*;
+
+			image.find(avatar_id).count().get_result::<i64>(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(image_count, 0);
+
+	// Deleting a missing avatar is a no-op
+	let response = env.app.delete("/profiles/me/avatar").await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn delete_profile_avatar_as_admin_test() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let test_id = env.get_profile("test").await.unwrap().id;
+
+	NewImage {
+		file_path:    None,
+		uploaded_by:  test_id,
+		image_url:    Some("https://example.com/avatar.png".to_string()),
+		content_hash: None,
+	}
+	.insert_for_profile(test_id, &conn)
+	.await
+	.unwrap();
+
+	let response = env.app.delete(&format!("/profiles/{test_id}/avatar")).await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let test = Profile::get(test_id, &conn).await.unwrap();
+	assert!(test.primitive.avatar_image_id.is_none());
+}
+
+/// Get the id and stored file of the avatar of a profile
+async fn get_avatar(env: &TestEnv, p_id: i32) -> Option<(i32, Option<String>)> {
+	use diesel::prelude::*;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	conn.interact(move |conn| {
+		use db::{image, profile};
+
+		profile::table
+			.find(p_id)
+			.inner_join(
+				image::table
+					.on(profile::avatar_image_id.eq(image::id.nullable())),
+			)
+			.select((image::id, image::file_path))
+			.get_result(conn)
+			.optional()
+	})
+	.await
+	.unwrap()
+	.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn replace_profile_avatar_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let test_id = env.get_profile("test").await.unwrap().id;
+
+	// Start out with an external avatar, as set when logging in through SSO
+	NewImage {
+		file_path:    None,
+		uploaded_by:  test_id,
+		image_url:    Some("https://example.com/avatar.png".to_string()),
+		content_hash: None,
+	}
+	.insert_for_profile(test_id, &conn)
+	.await
+	.unwrap();
+
+	let (sso_id, _) = get_avatar(&env, test_id).await.unwrap();
+
+	let mut bytes = vec![];
+	image_processing::RgbImage::new(16, 16)
+		.write_to(
+			&mut std::io::Cursor::new(&mut bytes),
+			image_processing::ImageFormat::Png,
+		)
+		.unwrap();
+
+	let upload = || {
+		let form = MultipartForm::new().add_part(
+			"image",
+			Part::bytes(bytes.clone())
+				.file_name("avatar.png")
+				.mime_type("image/png"),
+		);
+
+		env.app.post(&format!("/profiles/{test_id}/avatar")).multipart(form)
+	};
+
+	let response = upload().await;
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let (first_id, first_path) = get_avatar(&env, test_id).await.unwrap();
+	let first_path = format!("/mnt/files/{}", first_path.unwrap());
+
+	assert_ne!(first_id, sso_id);
+	assert!(std::fs::exists(&first_path).unwrap());
+
+	let response = upload().await;
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let (second_id, second_path) = get_avatar(&env, test_id).await.unwrap();
+	let second_path = format!("/mnt/files/{}", second_path.unwrap());
+
+	assert_ne!(second_id, first_id);
+	assert!(!std::fs::exists(&first_path).unwrap());
+	assert!(std::fs::exists(&second_path).unwrap());
+
+	// Replaced avatars don't leave any records behind
+	let image_count = conn
+		.interact(move |conn| {
+			use db::image::dsl::*;
+			use diesel::prelude::*;
+
+			image
+				.filter(id.eq_any([sso_id, first_id]))
+				.count()
+				.get_result::<i64>(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(image_count, 0);
+
+	// A file that is already gone doesn't stop the avatar from being deleted
+	std::fs::remove_file(&second_path).unwrap();
+
+	let response = env.app.delete(&format!("/profiles/{test_id}/avatar")).await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+	assert!(get_avatar(&env, test_id).await.is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn upload_invalid_profile_avatar_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let test_id = env.get_profile("test").await.unwrap().id;
+
+	let upload = |bytes: &[u8], file_name: &str, mime_type: &str| {
+		let form = MultipartForm::new().add_part(
+			"image",
+			Part::bytes(bytes.to_vec())
+				.file_name(file_name)
+				.mime_type(mime_type),
+		);
+
+		env.app.post(&format!("/profiles/{test_id}/avatar")).multipart(form)
+	};
+
+	// A text file does not become an image by renaming it
+	let response =
+		upload(b"definitely not an image", "avatar.png", "image/png").await;
+	assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["code"], "INVALID_IMAGE");
+	assert_eq!(body["details"]["reason"], "unrecognized_image_format");
+
+	let svg = br#"<?xml version="1.0"?>
+<svg xmlns="http://www.w3.org/2000/svg"><script>alert(1)</script></svg>"#;
+
+	let response = upload(svg, "avatar.svg", "image/svg+xml").await;
+	assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["details"]["reason"], "unsupported_image_format");
+	assert_eq!(body["details"]["format"], "image/svg+xml");
+
+	assert!(get_avatar(&env, test_id).await.is_none());
+}
+
+/// Build login claims as they would be received from an SSO provider
+fn sso_claims(email: &str, username: Option<&str>) -> ProfileClaims {
+	ProfileClaims {
+		issuer:     "https://sso.example.com".to_string(),
+		email:      email.to_string(),
+		username:   username.map(ToString::to_string),
+		first_name: None,
+		last_name:  None,
+		avatar_url: None,
+	}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sso_links_confirmed_profile() {
+	let env = TestEnv::new().await;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let test = env.get_profile("test").await.unwrap();
+
+	let profile =
+		Profile::from_claims(sso_claims("test@example.com", None), &conn)
+			.await
+			.unwrap();
+
+	assert_eq!(profile.primitive.id, test.id);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sso_retries_colliding_username() {
+	let env = TestEnv::new().await;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	// The seeded test profile already uses this username
+	let profile = Profile::from_claims(
+		sso_claims("sso@example.com", Some("test")),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	assert_ne!(profile.primitive.username, "test");
+	assert!(profile.primitive.username.starts_with("test."));
+	assert_eq!(profile.primitive.email, Some("sso@example.com".to_string()));
+	assert_eq!(profile.primitive.state, ProfileState::Active);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sso_rejects_unconfirmed_profile() {
+	let env = TestEnv::new().await;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let expiry = Utc::now().naive_utc() + Duration::days(1);
+
+	NewProfile {
+		username:                        "pending".to_string(),
+		password:                        "foo".to_string(),
+		pending_email:                   "pending@example.com".to_string(),
+		email_confirmation_token:        "pending-token".to_string(),
+		email_confirmation_token_expiry: expiry,
+		first_name:                      "Pending".to_string(),
+		last_name:                       "Profile".to_string(),
+	}
+	.insert(&conn)
+	.await
+	.unwrap();
+
+	let result =
+		Profile::from_claims(sso_claims("pending@example.com", None), &conn)
+			.await;
+
+	assert!(matches!(
+		result,
+		Err(Error::OAuthError(OAuthError::AccountExistsUnverified))
+	));
+
+	// No second profile was created for the email
+	let created = Profile::get_by_email_or_username(
+		"pending@example.com".to_string(),
+		&conn,
+	)
+	.await;
+
+	assert!(matches!(created, Err(Error::NotFound(_))));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn profile_sessions_test() {
+	let env = TestEnv::new().await;
+
+	let cookie_name = Config::from_env().access_cookie_name;
+	let test_id = env.get_profile("test").await.unwrap().id;
+	let sessions_url = format!("/profiles/{test_id}/sessions");
+
+	let login = async || {
+		env.app
+			.post("/auth/login")
+			.json(&LoginRequest {
+				username: "test".to_string(),
+				password: "foo".to_string(),
+				remember: false,
+			})
+			.await
+			.cookie(&cookie_name)
+	};
+
+	let first = login().await;
+	let second = login().await;
+
+	// The test server keeps using the cookie of the second login
+	let response = env.app.get(&sessions_url).await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let sessions = response.json::<Vec<SessionResponse>>();
+	assert_eq!(sessions.len(), 2);
+	assert_ne!(sessions[0].id, sessions[1].id);
+	assert!(!sessions[0].is_current);
+	assert!(sessions[1].is_current);
+
+	let response =
+		env.app.delete(&format!("{sessions_url}/{}", sessions[0].id)).await;
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	// The revoked cookie no longer authenticates, the other one still does
+	let response =
+		env.app.get(&sessions_url).clear_cookies().add_cookie(first).await;
+	assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+	let response = env
+		.app
+		.get(&sessions_url)
+		.clear_cookies()
+		.add_cookie(second.clone())
+		.await;
+	assert_eq!(response.status_code(), StatusCode::OK);
+	assert_eq!(response.json::<Vec<SessionResponse>>().len(), 1);
+
+	// Revoking all other sessions keeps the current one
+	login().await;
+
+	let response = env.app.delete(&sessions_url).await;
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let sessions =
+		env.app.get(&sessions_url).await.json::<Vec<SessionResponse>>();
+	assert_eq!(sessions.len(), 1);
+	assert!(sessions[0].is_current);
+
+	let response =
+		env.app.get(&sessions_url).clear_cookies().add_cookie(second).await;
+	assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn profile_sessions_forbidden_test() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	let test_id = env.get_profile("test").await.unwrap().id;
+
+	let response = env.app.get(&format!("/profiles/{test_id}/sessions")).await;
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let response =
+		env.app.delete(&format!("/profiles/{test_id}/sessions")).await;
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
 }
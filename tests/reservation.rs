@@ -8,18 +8,29 @@
 ///   - `create_reservation`
 ///       - check permissions if not authenticated
 ///       - check for out of bounds
-///       - check for reservable timeframe exceeded
 ///       - check for reservation length
 ///       - check for occupation exceeded
 ///   - `delete_reservation`
 ///       - check permissions if not authenticated
 ///       - check permissions if not a manager
 use axum::http::StatusCode;
+use axum::response::IntoResponse;
 
 mod common;
 
+use ::common::{CreateReservationError, Error};
+use base::RESERVATION_BLOCK_SIZE_MINUTES;
+use blokmap::jobs::{RESERVATION_REMINDER_WINDOW, send_reservation_reminders};
+use blokmap::schemas::pagination::PaginatedResponse;
 use blokmap::schemas::reservation::ReservationResponse;
+use chrono::{Duration, NaiveDateTime, NaiveTime, Utc};
 use common::TestEnv;
+use db::ReservationState;
+use location::NewLocationMember;
+use opening_time::{NewOpeningTime, OpeningTimeIncludes};
+use permissions::LocationPermissions;
+use reservation::{NewReservation, Reservation, ReservationIncludes};
+use role::{NewLocationRole, RoleIncludes};
 
 #[tokio::test(flavor = "multi_thread")]
 async fn get_reservations_for_location() {
@@ -30,13 +41,92 @@ async fn get_reservations_for_location() {
 	let response = env
 		.app
 		.get(&format!("/locations/{}/reservations", location.primitive.id))
+		.add_query_param("date", "2025-07-02")
 		.await;
 
 	assert_eq!(response.status_code(), StatusCode::OK);
 
-	let body = response.json::<Vec<ReservationResponse>>();
+	let body = response.json::<PaginatedResponse<Vec<ReservationResponse>>>();
 
-	assert!(!body.is_empty());
+	assert!(!body.data.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_reservations_for_location_default_window() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let location = env.get_location().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let today = Utc::now().date_naive();
+	let start_time = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+	let end_time = NaiveTime::from_hms_opt(18, 0, 0).unwrap();
+
+	let times = NewOpeningTime::bulk_insert(
+		[2, 90]
+			.into_iter()
+			.map(|days| {
+				NewOpeningTime {
+					location_id: location.primitive.id,
+					day: today + Duration::days(days),
+					start_time,
+					end_time,
+					seat_count: None,
+					reservable_from: None,
+					reservable_until: None,
+					created_by: profile.id,
+				}
+			})
+			.collect(),
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	for time in &times {
+		for base_block_index in [0, 4] {
+			env.insert_reservation_unchecked(NewReservation {
+				profile_id: profile.id,
+				opening_time_id: time.id,
+				base_block_index,
+				block_count: 4,
+			})
+			.await;
+		}
+	}
+
+	// Neither the seeded reservation from 2025 nor the ones in three months
+	// are counted without a filter
+	let response = env
+		.app
+		.get(&format!("/locations/{}/reservations", location.primitive.id))
+		.add_query_param("perPage", 1)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<ReservationResponse>>>();
+
+	assert_eq!(body.total, 2);
+	assert_eq!(body.data.len(), 1);
+	assert_eq!(body.data[0].opening_time_id, times[0].id);
+
+	// An explicit date lifts the window
+	let response = env
+		.app
+		.get(&format!("/locations/{}/reservations", location.primitive.id))
+		.add_query_param("date", times[1].day)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<ReservationResponse>>>();
+
+	assert_eq!(body.total, 2);
+	assert!(body.data.iter().all(|r| r.opening_time_id == times[1].id));
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -65,6 +155,8 @@ async fn get_reservations_for_opening_time() {
 async fn create_reservation() {
 	let env = TestEnv::new().await.login("test").await;
 
+	move_opening_time_to_tomorrow(&env).await;
+
 	let location = env.get_location().await.unwrap();
 	let time = env.get_opening_time().await.unwrap();
 
@@ -93,6 +185,8 @@ async fn create_reservation() {
 async fn delete_reservation() {
 	let env = TestEnv::new().await.login_admin().await;
 
+	move_opening_time_to_tomorrow(&env).await;
+
 	let location = env.get_location().await.unwrap();
 	let time = env.get_opening_time().await.unwrap();
 
@@ -123,3 +217,1417 @@ async fn delete_reservation() {
 
 	assert_eq!(delete_response.status_code(), StatusCode::NO_CONTENT);
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_reservations_by_confirmed_by() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login_admin().await;
+
+	move_opening_time_to_tomorrow(&env).await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let admin = env.get_admin_profile().await.unwrap();
+	let test2 = env.get_profile("test2").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	// Seed three reservations, two confirmed by the admin and one by test2
+	let confirmers = [admin.id, admin.id, test2.id];
+
+	for (idx, confirmer_id) in confirmers.into_iter().enumerate() {
+		let new_reservation = NewReservation {
+			profile_id:       test2.id,
+			opening_time_id:  time.primitive.id,
+			base_block_index: 10 * i32::try_from(idx).unwrap() + 10,
+			block_count:      4,
+		};
+
+		let r_id = new_reservation
+			.insert(ReservationIncludes::default(), &conn)
+			.await
+			.unwrap()
+			.primitive
+			.id;
+
+		conn.interact(move |conn| {
+			use db::reservation::dsl::*;
+
+			diesel::update(reservation.find(r_id))
+				.set(confirmed_by.eq(confirmer_id))
+				.execute(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+	}
+
+	let response = env
+		.app
+		.get("/admin/reservations")
+		.add_query_param("confirmerId", admin.id)
+		.add_query_param("confirmedBy", true)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<ReservationResponse>>>();
+
+	assert_eq!(body.total, 2);
+	assert_eq!(body.data.len(), 2);
+	assert!(!body.truncated);
+	assert!(body.data.iter().all(|r| {
+		matches!(&r.confirmed_by, Some(Some(p)) if p.id == admin.id)
+	}));
+
+	// Pages are cut in SQL, the total still covers every reservation
+	let response = env
+		.app
+		.get("/admin/reservations")
+		.add_query_param("confirmerId", admin.id)
+		.add_query_param("perPage", 1)
+		.add_query_param("page", 2)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<ReservationResponse>>>();
+
+	assert_eq!(body.total, 2);
+	assert_eq!(body.data.len(), 1);
+
+	let response = env
+		.app
+		.get("/admin/reservations")
+		.add_query_param("confirmerId", test2.id)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<ReservationResponse>>>();
+
+	assert_eq!(body.data.len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn extend_reservation() {
+	let env = TestEnv::new().await.login("test").await;
+
+	move_opening_time_to_tomorrow(&env).await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let created = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 20,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let response = env
+		.app
+		.post(&format!("/reservations/{}/extend", created.primitive.id))
+		.json(&serde_json::json!({ "additionalBlocks": 3 }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<ReservationResponse>();
+
+	assert_eq!(body.block_count, 7);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn extend_reservation_into_own() {
+	let env = TestEnv::new().await.login("test").await;
+
+	move_opening_time_to_tomorrow(&env).await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let mut reservations = vec![];
+
+	for base_block_index in [20, 26] {
+		let created = NewReservation {
+			profile_id: profile.id,
+			opening_time_id: time.primitive.id,
+			base_block_index,
+			block_count: 4,
+		}
+		.insert(ReservationIncludes::default(), &conn)
+		.await
+		.unwrap();
+
+		reservations.push(created.primitive.id);
+	}
+
+	// There are enough seats, but the profile already holds block 26
+	let response = env
+		.app
+		.post(&format!("/reservations/{}/extend", reservations[0]))
+		.json(&serde_json::json!({ "additionalBlocks": 3 }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let body = response.json::<serde_json::Value>();
+
+	assert_eq!(body["details"]["reason"], "overlap");
+
+	let response = env
+		.app
+		.post(&format!("/reservations/{}/extend", reservations[0]))
+		.json(&serde_json::json!({ "additionalBlocks": 2 }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn extend_reservation_after_end() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	// The seeded opening time lies in the past
+	let created = env
+		.insert_reservation_unchecked(NewReservation {
+			profile_id:       profile.id,
+			opening_time_id:  time.primitive.id,
+			base_block_index: 100,
+			block_count:      4,
+		})
+		.await;
+
+	let response = env
+		.app
+		.post(&format!("/reservations/{}/extend", created.primitive.id))
+		.json(&serde_json::json!({ "additionalBlocks": 2 }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+	let body = response.json::<serde_json::Value>();
+
+	assert_eq!(body["details"]["reason"], "opening_time_ended");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn extend_reservation_too_long() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	move_opening_time_to_tomorrow(&env).await;
+
+	let location = env.get_location().await.unwrap();
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let l_id = location.primitive.id;
+
+	conn.interact(move |conn| {
+		use db::location::dsl::*;
+
+		diesel::update(location.find(l_id))
+			.set(max_reservation_length.eq(Some(30)))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let created = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 20,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let response = env
+		.app
+		.post(&format!("/reservations/{}/extend", created.primitive.id))
+		.json(&serde_json::json!({ "additionalBlocks": 3 }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+	let body = response.json::<serde_json::Value>();
+
+	assert_eq!(body["details"]["reason"], "reservation_too_long");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn extend_reservation_overlap() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	move_opening_time_to_tomorrow(&env).await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+	let test2 = env.get_profile("test2").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let t_id = time.primitive.id;
+
+	// Only allow a single reservation per block
+	conn.interact(move |conn| {
+		use db::opening_time::dsl::*;
+
+		diesel::update(opening_time.find(t_id))
+			.set(seat_count.eq(Some(1)))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let created = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  t_id,
+		base_block_index: 20,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	// Block the slots directly after the reservation
+	NewReservation {
+		profile_id:       test2.id,
+		opening_time_id:  t_id,
+		base_block_index: 24,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let response = env
+		.app
+		.post(&format!("/reservations/{}/extend", created.primitive.id))
+		.json(&serde_json::json!({ "additionalBlocks": 2 }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let body = response.json::<serde_json::Value>();
+
+	assert_eq!(body["details"]["reason"], "full");
+	assert_eq!(body["details"]["blocks"], serde_json::json!([24, 25]));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_reservations_by_opening_time() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	move_opening_time_to_tomorrow(&env).await;
+
+	let location = env.get_location().await.unwrap();
+	let time = env.get_opening_time().await.unwrap();
+	let admin = env.get_admin_profile().await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	// Seed a second opening time on the same location
+	let other_time = NewOpeningTime::bulk_insert(
+		vec![NewOpeningTime {
+			location_id:      location.primitive.id,
+			day:              time.primitive.day.succ_opt().unwrap(),
+			start_time:       NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time:         NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+			seat_count:       None,
+			reservable_from:  None,
+			reservable_until: None,
+			created_by:       admin.id,
+		}],
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap()
+	.remove(0);
+
+	NewReservation {
+		profile_id:       admin.id,
+		opening_time_id:  other_time.id,
+		base_block_index: 0,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let response = env
+		.app
+		.get("/reservations")
+		.add_query_param("openingTimeId", other_time.id)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<ReservationResponse>>>();
+
+	assert_eq!(body.data.len(), 1);
+	assert_eq!(body.data[0].opening_time_id, other_time.id);
+
+	let response = env
+		.app
+		.get(&format!("/locations/{}/reservations", location.primitive.id))
+		.add_query_param("openingTimeId", time.primitive.id)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<ReservationResponse>>>();
+
+	assert!(!body.data.is_empty());
+	assert!(body.data.iter().all(|r| r.opening_time_id == time.primitive.id));
+}
+
+/// Move the seeded opening time to tomorrow, as opening times that already
+/// ended can't be reserved
+async fn move_opening_time_to_tomorrow(env: &TestEnv) {
+	use diesel::prelude::*;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+	let t_id = env.get_opening_time().await.unwrap().primitive.id;
+	let tomorrow = (Utc::now() + Duration::days(1)).date_naive();
+
+	conn.interact(move |conn| {
+		use db::opening_time::dsl::*;
+
+		diesel::update(opening_time.find(t_id))
+			.set(day.eq(tomorrow))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+}
+
+/// Set the maximum reservation length of the test location
+async fn set_max_reservation_length(env: &TestEnv, max: Option<i32>) {
+	use diesel::prelude::*;
+
+	let location = env.get_location().await.unwrap();
+	let l_id = location.primitive.id;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	conn.interact(move |conn| {
+		use db::location::dsl::*;
+
+		diesel::update(location.find(l_id))
+			.set(max_reservation_length.eq(max))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_reservation_max_length() {
+	let env = TestEnv::new().await;
+
+	move_opening_time_to_tomorrow(&env).await;
+	set_max_reservation_length(&env, Some(30)).await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let created = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 20,
+		block_count:      6,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	assert_eq!(created.primitive.block_count, 6);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_reservation_too_long() {
+	let env = TestEnv::new().await;
+
+	move_opening_time_to_tomorrow(&env).await;
+	set_max_reservation_length(&env, Some(30)).await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let result = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 20,
+		block_count:      7,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await;
+
+	assert!(matches!(
+		result,
+		Err(Error::CreateReservationError(
+			CreateReservationError::ReservationTooLong(30)
+		))
+	));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_reservation_too_long_overlap() {
+	let env = TestEnv::new().await;
+
+	move_opening_time_to_tomorrow(&env).await;
+	set_max_reservation_length(&env, Some(30)).await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	// Overlapping reservations are refused before their length is checked
+	let result = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 0,
+		block_count:      7,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await;
+
+	assert!(matches!(
+		result,
+		Err(Error::CreateReservationError(CreateReservationError::Overlap(_)))
+	));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn validate_reservation_block_range() {
+	let env = TestEnv::new().await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	// The seeded opening time from 08:00 until 22:00 spans 168 blocks
+	let cases = [(0, 4, true), (164, 4, true), (-1, 4, false), (165, 4, false)];
+
+	for (base_block_index, block_count, valid) in cases {
+		let result = NewReservation {
+			profile_id: profile.id,
+			opening_time_id: time.primitive.id,
+			base_block_index,
+			block_count,
+		}
+		.validate_block_range(&time.primitive, RESERVATION_BLOCK_SIZE_MINUTES);
+
+		if valid {
+			assert!(result.is_ok());
+		} else {
+			assert!(matches!(
+				result,
+				Err(Error::CreateReservationError(
+					CreateReservationError::InvalidBlockRange {
+						min: 0,
+						max: 168,
+					}
+				))
+			));
+		}
+	}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_reservation_invalid_block_range() {
+	let env = TestEnv::new().await;
+
+	move_opening_time_to_tomorrow(&env).await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let result = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 166,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await;
+
+	assert!(matches!(
+		result,
+		Err(Error::CreateReservationError(
+			CreateReservationError::InvalidBlockRange { .. }
+		))
+	));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_reservation_without_max_length() {
+	let env = TestEnv::new().await;
+
+	move_opening_time_to_tomorrow(&env).await;
+	set_max_reservation_length(&env, None).await;
+
+	let time = env.get_opening_time().await.unwrap();
+	// The seeded reservation of `test` would overlap
+	let profile = env.get_profile("test2").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	// Reserve the entire opening time from 08:00 until 22:00
+	let created = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 0,
+		block_count:      168,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	assert_eq!(created.primitive.block_count, 168);
+}
+
+/// Create an opening time for the test location tomorrow, reservable within
+/// the given window
+async fn create_opening_time_with_window(
+	env: &TestEnv,
+	reservable_from: Option<NaiveDateTime>,
+	reservable_until: Option<NaiveDateTime>,
+) -> i32 {
+	let profile = env.get_admin_profile().await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	NewOpeningTime::bulk_insert(
+		vec![NewOpeningTime {
+			location_id: 1,
+			day: (Utc::now() + Duration::days(1)).date_naive(),
+			start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+			seat_count: None,
+			reservable_from,
+			reservable_until,
+			created_by: profile.id,
+		}],
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap()
+	.remove(0)
+	.id
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn validate_reservable_window() {
+	let env = TestEnv::new().await;
+
+	let mut time = env.get_opening_time().await.unwrap().primitive;
+
+	let now = Utc::now().naive_utc();
+	let second = Duration::seconds(1);
+
+	time.day = (now + Duration::days(1)).date();
+	time.reservable_from = Some(now);
+	time.reservable_until = Some(now + Duration::hours(1));
+
+	// Both bounds of the window are inclusive
+	let from = now;
+	let until = now + Duration::hours(1);
+
+	assert!(NewReservation::validate_reservable(&time, from).is_ok());
+	assert!(NewReservation::validate_reservable(&time, until).is_ok());
+
+	assert!(matches!(
+		NewReservation::validate_reservable(&time, from - second),
+		Err(Error::CreateReservationError(
+			CreateReservationError::NotReservableYet(f)
+		)) if f == from
+	));
+	assert!(matches!(
+		NewReservation::validate_reservable(&time, until + second),
+		Err(Error::CreateReservationError(
+			CreateReservationError::NotReservableAnymore(u)
+		)) if u == until
+	));
+
+	// Without a window only the end of the opening time matters
+	time.reservable_from = None;
+	time.reservable_until = None;
+
+	let end = time.day.and_time(time.end_time);
+
+	assert!(NewReservation::validate_reservable(&time, end - second).is_ok());
+	assert!(matches!(
+		NewReservation::validate_reservable(&time, end),
+		Err(Error::CreateReservationError(
+			CreateReservationError::OpeningTimeEnded(e)
+		)) if e == end
+	));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_reservation_not_reservable_yet() {
+	let env = TestEnv::new().await;
+
+	let from = Utc::now().naive_utc() + Duration::hours(1);
+	let t_id = create_opening_time_with_window(&env, Some(from), None).await;
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let result = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  t_id,
+		base_block_index: 0,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await;
+
+	assert!(matches!(
+		result,
+		Err(Error::CreateReservationError(
+			CreateReservationError::NotReservableYet(_)
+		))
+	));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_reservation_not_reservable_anymore() {
+	let env = TestEnv::new().await;
+
+	let until = Utc::now().naive_utc() - Duration::hours(1);
+	let t_id = create_opening_time_with_window(&env, None, Some(until)).await;
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let result = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  t_id,
+		base_block_index: 0,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await;
+
+	assert!(matches!(
+		result,
+		Err(Error::CreateReservationError(
+			CreateReservationError::NotReservableAnymore(_)
+		))
+	));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_reservation_opening_time_ended() {
+	let env = TestEnv::new().await;
+
+	// The seeded opening time lies in the past
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test2").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let result = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 20,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await;
+
+	assert!(matches!(
+		result,
+		Err(Error::CreateReservationError(
+			CreateReservationError::OpeningTimeEnded(_)
+		))
+	));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_reservation_window_boundary() {
+	let env = TestEnv::new().await;
+
+	// The window opens right now and closes shortly after
+	let now = Utc::now().naive_utc();
+	let t_id = create_opening_time_with_window(
+		&env,
+		Some(now),
+		Some(now + Duration::minutes(1)),
+	)
+	.await;
+
+	let profile = env.get_profile("test").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let result = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  t_id,
+		base_block_index: 0,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await;
+
+	assert!(result.is_ok());
+}
+
+/// Only allow a single reservation per block of the seeded opening time
+async fn set_single_seat(env: &TestEnv) {
+	use diesel::prelude::*;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+	let t_id = env.get_opening_time().await.unwrap().primitive.id;
+
+	conn.interact(move |conn| {
+		use db::opening_time::dsl::*;
+
+		diesel::update(opening_time.find(t_id))
+			.set(seat_count.eq(Some(1)))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_reservation_adjacent() {
+	let env = TestEnv::new().await;
+
+	move_opening_time_to_tomorrow(&env).await;
+	set_single_seat(&env).await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+	let test2 = env.get_profile("test2").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 20,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	// Starting exactly where the other reservation ends is fine
+	let result = NewReservation {
+		profile_id:       test2.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 24,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await;
+
+	assert!(result.is_ok());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_reservation_adjacent() {
+	let env = TestEnv::new().await;
+
+	move_opening_time_to_tomorrow(&env).await;
+	set_single_seat(&env).await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	// Together with the seeded reservation, blocks 0 through 3 and 20
+	// through 23 have no seats left
+	NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 20,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let env = env.login("test2").await;
+
+	// Starting exactly where the other reservation ends is fine
+	let response = env
+		.app
+		.post(&format!(
+			"/locations/{}/opening-times/{}/reservations",
+			time.primitive.location_id, time.primitive.id
+		))
+		.json(&serde_json::json!({
+			"startTime": "10:00:00",
+			"endTime": "10:20:00",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let body = response.json::<ReservationResponse>();
+
+	assert_eq!(body.base_block_index, 24);
+	assert_eq!(body.block_count, 4);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_reservation_overlap_full() {
+	let env = TestEnv::new().await;
+
+	move_opening_time_to_tomorrow(&env).await;
+	set_single_seat(&env).await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+	let test2 = env.get_profile("test2").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 20,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	// Overlapping by a single block exceeds the capacity of that block
+	let result = NewReservation {
+		profile_id:       test2.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 23,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await;
+
+	assert!(matches!(
+		result,
+		Err(Error::CreateReservationError(CreateReservationError::Full(blocks)))
+			if blocks == [23]
+	));
+
+	// Through the API the exceeded capacity is reported as a conflict
+	let env = env.login("test2").await;
+
+	let response = env
+		.app
+		.post(&format!(
+			"/locations/{}/opening-times/{}/reservations",
+			time.primitive.location_id, time.primitive.id
+		))
+		.json(&serde_json::json!({
+			"startTime": "09:55:00",
+			"endTime": "10:15:00",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let body = response.json::<serde_json::Value>();
+
+	assert_eq!(body["details"]["reason"], "full");
+	assert_eq!(body["details"]["blocks"], serde_json::json!([23]));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_reservation_overlap_own() {
+	let env = TestEnv::new().await;
+
+	move_opening_time_to_tomorrow(&env).await;
+
+	let time = env.get_opening_time().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let created = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 20,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	// There are enough seats, but a profile can't book the same block twice
+	let result = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 23,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await;
+
+	let Err(error) = result else {
+		panic!("overlapping reservation was created");
+	};
+
+	assert!(matches!(
+		error,
+		Error::CreateReservationError(CreateReservationError::Overlap(id))
+			if id == created.primitive.id
+	));
+	assert_eq!(error.into_response().status(), StatusCode::CONFLICT);
+
+	let result = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  time.primitive.id,
+		base_block_index: 24,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await;
+
+	assert!(result.is_ok());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_reservations_for_location_paginated() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let admin = env.get_admin_profile().await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	// Only location administrators can list the reservations
+	let role = NewLocationRole {
+		location_id: 2,
+		name:        "administrator".to_string(),
+		colour:      None,
+		permissions: LocationPermissions::Administrator.bits(),
+		created_by:  admin.id,
+	}
+	.insert(2, RoleIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	NewLocationMember {
+		location_id:      2,
+		profile_id:       admin.id,
+		location_role_id: Some(role.primitive.id),
+		added_by:         admin.id,
+	}
+	.insert(&conn)
+	.await
+	.unwrap();
+
+	let today = Utc::now().date_naive();
+	let start_time = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+	let end_time = NaiveTime::from_hms_opt(22, 0, 0).unwrap();
+
+	// Insert the later days first so the ids don't match the expected order
+	let times = NewOpeningTime::bulk_insert(
+		(1..=3)
+			.rev()
+			.map(|days| {
+				NewOpeningTime {
+					location_id: 2,
+					day: today + Duration::days(days),
+					start_time,
+					end_time,
+					seat_count: None,
+					reservable_from: None,
+					reservable_until: None,
+					created_by: admin.id,
+				}
+			})
+			.collect(),
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	for time in &times {
+		for i in 0..5 {
+			NewReservation {
+				profile_id:       admin.id,
+				opening_time_id:  time.id,
+				base_block_index: i * 4,
+				block_count:      4,
+			}
+			.insert(ReservationIncludes::default(), &conn)
+			.await
+			.unwrap();
+		}
+	}
+
+	let response = env
+		.app
+		.get("/locations/2/reservations")
+		.add_query_param("page", 1)
+		.add_query_param("perPage", 10)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let first = response.json::<PaginatedResponse<Vec<ReservationResponse>>>();
+
+	assert_eq!(first.total, 15);
+	assert_eq!(first.data.len(), 10);
+
+	let response = env
+		.app
+		.get("/locations/2/reservations")
+		.add_query_param("page", 2)
+		.add_query_param("perPage", 10)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let second = response.json::<PaginatedResponse<Vec<ReservationResponse>>>();
+
+	assert_eq!(second.total, 15);
+	assert_eq!(second.data.len(), 5);
+
+	// Reservations are ordered by the day of their opening time
+	let days = first
+		.data
+		.iter()
+		.chain(&second.data)
+		.map(|r| times.iter().find(|t| t.id == r.opening_time_id).unwrap().day)
+		.collect::<Vec<_>>();
+
+	assert!(days.is_sorted());
+	assert!(second.data.iter().all(|r| r.opening_time_id == times[0].id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cancel_reservation() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let location = env.get_location().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let times = NewOpeningTime::bulk_insert(
+		vec![NewOpeningTime {
+			location_id:      location.primitive.id,
+			day:              Utc::now().date_naive() + Duration::days(7),
+			start_time:       NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time:         NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+			seat_count:       None,
+			reservable_from:  None,
+			reservable_until: None,
+			created_by:       profile.id,
+		}],
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	let created = NewReservation {
+		profile_id:       profile.id,
+		opening_time_id:  times[0].id,
+		base_block_index: 0,
+		block_count:      4,
+	}
+	.insert(ReservationIncludes::default(), &conn)
+	.await
+	.unwrap();
+
+	let response = env
+		.app
+		.post(&format!("/reservations/{}/cancel", created.primitive.id))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<ReservationResponse>();
+
+	assert_eq!(body.state, ReservationState::Cancelled);
+	assert!(body.confirmed_at.is_none());
+
+	// Cancelled reservations can not change state anymore
+	let response = env
+		.app
+		.post(&format!("/reservations/{}/cancel", created.primitive.id))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["details"]["reason"], "already_cancelled");
+
+	let response = env
+		.app
+		.post(&format!("/reservations/{}/confirm", created.primitive.id))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cancel_reservation_started() {
+	let env = TestEnv::new().await.login("test").await;
+
+	// The seeded reservation lies in the past
+	let response = env.app.post("/reservations/1/cancel").await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["details"]["reason"], "already_started");
+
+	// Only the owner can cancel a reservation
+	let env = env.login("test2").await;
+
+	let response = env.app.post("/reservations/1/cancel").await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn confirm_reservation() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	// Profiles without location permissions can't confirm reservations
+	let response = env.app.post("/reservations/1/confirm").await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let env = env.login("test").await;
+	let profile = env.get_profile("test").await.unwrap();
+
+	let response = env
+		.app
+		.post("/reservations/1/confirm")
+		.add_query_param("confirmedBy", true)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<ReservationResponse>();
+
+	assert_eq!(body.state, ReservationState::Present);
+	assert!(body.confirmed_at.is_some());
+	assert_eq!(body.confirmed_by.unwrap().unwrap().id, profile.id);
+
+	// Confirmed reservations can not be confirmed or cancelled again
+	let response = env.app.post("/reservations/1/confirm").await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["details"]["reason"], "already_confirmed");
+
+	let response = env.app.post("/reservations/1/cancel").await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+}
+
+/// Insert opening times from 08:00 until 20:00 on two consecutive days far
+/// enough in the future to not overlap with seeded reservations, returning
+/// their ids and the moment reminders are sent from
+async fn insert_reminder_opening_times(
+	env: &TestEnv,
+) -> (i32, i32, NaiveDateTime) {
+	let location = env.get_location().await.unwrap();
+	let profile = env.get_profile("test").await.unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let day = (Utc::now() + Duration::days(60)).date_naive();
+	let new_times = [day, day + Duration::days(1)]
+		.into_iter()
+		.map(|day| {
+			NewOpeningTime {
+				location_id: location.primitive.id,
+				day,
+				start_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+				end_time: NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+				seat_count: None,
+				reservable_from: None,
+				reservable_until: None,
+				created_by: profile.id,
+			}
+		})
+		.collect();
+
+	let times = NewOpeningTime::bulk_insert(
+		new_times,
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	let now = day.and_time(NaiveTime::from_hms_opt(10, 0, 0).unwrap());
+
+	(times[0].id, times[1].id, now)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reservation_reminder_window() {
+	let env = TestEnv::new().await;
+
+	let profile = env.get_profile("test").await.unwrap();
+	let (today, tomorrow, now) = insert_reminder_opening_times(&env).await;
+
+	// Block 24 starts at 10:00, the start of the window is inclusive and the
+	// end exclusive
+	let mut r_ids = vec![];
+	for (opening_time_id, base_block_index) in [
+		(today, 12),
+		(today, 24),
+		(today, 120),
+		(today, 36),
+		(tomorrow, 23),
+		(tomorrow, 24),
+	] {
+		let reservation = env
+			.insert_reservation_unchecked(NewReservation {
+				profile_id: profile.id,
+				opening_time_id,
+				base_block_index,
+				block_count: 1,
+			})
+			.await;
+
+		r_ids.push(reservation.primitive.id);
+	}
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	// Cancelled reservations are never reminded
+	Reservation::cancel(r_ids[3], ReservationIncludes::default(), &conn)
+		.await
+		.unwrap();
+
+	let until = now + RESERVATION_REMINDER_WINDOW;
+	let due = Reservation::get_unreminded_starting_between(now, until, &conn)
+		.await
+		.unwrap();
+
+	let due_ids: Vec<_> = due.iter().map(|r| r.primitive.id).collect();
+	assert_eq!(due_ids, [r_ids[1], r_ids[2], r_ids[4]]);
+
+	let (start, end) = due[2].time_span();
+	assert_eq!(start, until - Duration::minutes(5));
+	assert_eq!(end, until);
+
+	// Reminded reservations are no longer selected
+	assert!(Reservation::mark_reminded(r_ids[2], &conn).await.unwrap());
+
+	let due = Reservation::get_unreminded_starting_between(now, until, &conn)
+		.await
+		.unwrap();
+
+	let due_ids: Vec<_> = due.iter().map(|r| r.primitive.id).collect();
+	assert_eq!(due_ids, [r_ids[1], r_ids[4]]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reservation_reminder_sent_once() {
+	let env = TestEnv::new().await;
+
+	let profile = env.get_profile("test").await.unwrap();
+	let (today, _, now) = insert_reminder_opening_times(&env).await;
+
+	let reservation = env
+		.insert_reservation_unchecked(NewReservation {
+			profile_id:       profile.id,
+			opening_time_id:  today,
+			base_block_index: 36,
+			block_count:      12,
+		})
+		.await;
+
+	let pool = env.db_guard.create_pool();
+	let outbox_size = env.stub_mailbox.mailbox.lock().len();
+
+	let sent =
+		send_reservation_reminders(&pool, &env.mailer, now).await.unwrap();
+	assert_eq!(sent, 1);
+
+	{
+		let mailbox = env.stub_mailbox.mailbox.lock();
+		assert_eq!(mailbox.len(), outbox_size + 1);
+
+		let mail = &mailbox[outbox_size];
+		assert_eq!(mail.envelope().to()[0].to_string(), "test@example.com");
+
+		let body = String::from_utf8(mail.formatted()).unwrap();
+		assert!(body.contains("from 11:00:00 until 12:00:00"));
+	}
+
+	let conn = pool.get().await.unwrap();
+	let reminded = Reservation::get_by_id(
+		reservation.primitive.id,
+		ReservationIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+	assert!(reminded.primitive.reminded_at.is_some());
+
+	// Later runs and repeated markers don't mail the profile again
+	let sent = env
+		.expect_no_mail(async || {
+			send_reservation_reminders(&pool, &env.mailer, now).await
+		})
+		.await
+		.unwrap();
+	assert_eq!(sent, 0);
+
+	assert!(
+		!Reservation::mark_reminded(reservation.primitive.id, &conn)
+			.await
+			.unwrap()
+	);
+}
@@ -0,0 +1,236 @@
+mod common;
+use axum::http::StatusCode;
+use blokmap::schemas::review::{ReviewFlagResponse, ReviewResponse};
+use chrono::{Duration, NaiveTime, Utc};
+use common::TestEnv;
+use db::ReservationState;
+use opening_time::{NewOpeningTime, OpeningTimeIncludes};
+use reservation::NewReservation;
+
+/// Give `test2` a reservation at location 2, on a day relative to today
+async fn create_test2_reservation(
+	env: &TestEnv,
+	days: i64,
+	new_state: ReservationState,
+) {
+	use diesel::prelude::*;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let profile = env.get_profile("test2").await.unwrap();
+
+	let times = NewOpeningTime::bulk_insert(
+		vec![NewOpeningTime {
+			location_id:      2,
+			day:              (Utc::now() + Duration::days(days)).date_naive(),
+			start_time:       NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+			end_time:         NaiveTime::from_hms_opt(10, 0, 0).unwrap(),
+			seat_count:       None,
+			reservable_from:  None,
+			reservable_until: None,
+			created_by:       profile.id,
+		}],
+		OpeningTimeIncludes::default(),
+		&conn,
+	)
+	.await
+	.unwrap();
+
+	let r_id = env
+		.insert_reservation_unchecked(NewReservation {
+			profile_id:       profile.id,
+			opening_time_id:  times[0].id,
+			base_block_index: 0,
+			block_count:      4,
+		})
+		.await
+		.primitive
+		.id;
+
+	conn.interact(move |conn| {
+		use db::reservation::dsl::*;
+
+		diesel::update(reservation.find(r_id))
+			.set(state.eq(new_state))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn flag_review_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	// Get a test location in the database
+	let location = env.get_location().await.unwrap();
+
+	// Create a review to flag
+	let review = env
+		.app
+		.post(format!("/locations/{}/reviews", location.primitive.id).as_str())
+		.json(&serde_json::json!({
+			"rating": 4,
+			"body": "test review",
+		}))
+		.await
+		.json::<ReviewResponse>();
+
+	assert_eq!(review.flag_count, 0);
+
+	// Flag the review
+	let response = env
+		.app
+		.post(format!("/reviews/{}/flag", review.id).as_str())
+		.json(&serde_json::json!({
+			"reason": "inappropriate",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let flag = response.json::<ReviewFlagResponse>();
+	assert_eq!(flag.review_id, review.id);
+	assert_eq!(flag.reason, "inappropriate");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn flag_review_twice_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	// Get a test location in the database
+	let location = env.get_location().await.unwrap();
+
+	// Create a review to flag
+	let review = env
+		.app
+		.post(format!("/locations/{}/reviews", location.primitive.id).as_str())
+		.json(&serde_json::json!({
+			"rating": 4,
+			"body": "test review",
+		}))
+		.await
+		.json::<ReviewResponse>();
+
+	// Flag the review once
+	let response = env
+		.app
+		.post(format!("/reviews/{}/flag", review.id).as_str())
+		.json(&serde_json::json!({
+			"reason": "inappropriate",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	// Flagging the same review again should be rejected
+	let response = env
+		.app
+		.post(format!("/reviews/{}/flag", review.id).as_str())
+		.json(&serde_json::json!({
+			"reason": "still inappropriate",
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_flagged_reviews_unauthorized_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env.app.get("/admin/reviews/flagged").await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_review_without_reservation_test() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	let response = env
+		.app
+		.post("/locations/2/reviews")
+		.json(&serde_json::json!({ "rating": 4 }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["details"]["reason"], "review_not_allowed");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_review_upcoming_reservation_test() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	create_test2_reservation(&env, 1, ReservationState::Created).await;
+
+	let response = env
+		.app
+		.post("/locations/2/reviews")
+		.json(&serde_json::json!({ "rating": 4 }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_review_cancelled_reservation_test() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	create_test2_reservation(&env, -1, ReservationState::Cancelled).await;
+
+	let response = env
+		.app
+		.post("/locations/2/reviews")
+		.json(&serde_json::json!({ "rating": 4 }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_review_past_reservation_test() {
+	let env = TestEnv::new().await.login("test2").await;
+
+	create_test2_reservation(&env, -1, ReservationState::Created).await;
+
+	let response = env
+		.app
+		.post("/locations/2/reviews")
+		.json(&serde_json::json!({ "rating": 4 }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let review = response.json::<ReviewResponse>();
+	assert_eq!(review.rating, 4);
+
+	// A second review of the same location points to the existing one
+	let response = env
+		.app
+		.post("/locations/2/reviews")
+		.json(&serde_json::json!({ "rating": 5 }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["details"]["resourceId"], review.id);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn create_review_admin_test() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let response = env
+		.app
+		.post("/locations/2/reviews")
+		.json(&serde_json::json!({ "rating": 4 }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+}
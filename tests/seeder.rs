@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use blokmap::Seeder;
+use tag::NewTag;
+
+mod common;
+
+use common::TestEnv;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn populate_if_empty_test() {
+	let env = TestEnv::new().await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	let seeder = Seeder::new(&conn);
+	let calls = AtomicUsize::new(0);
+
+	// First run, the table is reported as empty
+	seeder
+		.populate_if_empty(
+			"tests/seed/tags.json",
+			async |_| false,
+			async |_, _: Vec<NewTag>| {
+				calls.fetch_add(1, Ordering::SeqCst);
+
+				Ok(())
+			},
+		)
+		.await;
+
+	assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+	// Second run, the table already contains data
+	seeder
+		.populate_if_empty(
+			"tests/seed/tags.json",
+			async |_| true,
+			async |_, _: Vec<NewTag>| {
+				calls.fetch_add(1, Ordering::SeqCst);
+
+				Ok(())
+			},
+		)
+		.await;
+
+	assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn table_has_rows_test() {
+	let env = TestEnv::new().await;
+
+	let pool = env.db_guard.create_pool();
+	let conn = pool.get().await.unwrap();
+
+	assert!(Seeder::table_has_rows(&conn, "profile").await);
+	assert!(!Seeder::table_has_rows(&conn, "review").await);
+}
@@ -1,5 +1,12 @@
 use axum::http::StatusCode;
-use blokmap::schemas::tag::{CreateTagRequest, TagResponse, UpdateTagRequest};
+use blokmap::schemas::location::LocationResponse;
+use blokmap::schemas::pagination::PaginatedResponse;
+use blokmap::schemas::tag::{
+	CreateTagRequest,
+	TagResponse,
+	TagStatisticsResponse,
+	UpdateTagRequest,
+};
 use blokmap::schemas::translation::{
 	CreateTranslationRequest,
 	UpdateTranslationRequest,
@@ -17,9 +24,25 @@ async fn test_get_all_tags() {
 
 	assert_eq!(response.status_code(), StatusCode::OK);
 
-	let body = response.json::<Vec<TagResponse>>();
+	let body = response.json::<PaginatedResponse<Vec<TagResponse>>>();
+
+	assert!(!body.data.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_all_tags_lang() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let response = env.app.get("/tags?lang=fr").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<TagResponse>>>();
+	let names: Vec<_> =
+		body.data.into_iter().filter_map(|t| t.name.text.flatten()).collect();
 
-	assert!(!body.is_empty());
+	assert!(names.contains(&"WiFi Gratuit".to_string()));
+	assert!(names.contains(&"Calme".to_string()));
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -188,3 +211,282 @@ async fn test_delete_tag_not_admin() {
 
 	assert_eq!(delete_response.status_code(), StatusCode::FORBIDDEN);
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_delete_tag_in_use() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login_admin().await;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	conn.interact(|conn| {
+		use db::location_tag::dsl::*;
+
+		diesel::insert_into(location_tag)
+			.values(vec![
+				(location_id.eq(1), tag_id.eq(2)),
+				(location_id.eq(2), tag_id.eq(2)),
+			])
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let response = env.app.delete("/tags/2").await;
+
+	assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+	let body = response.json::<serde_json::Value>();
+
+	assert_eq!(body["details"]["reason"], "tag_in_use");
+	assert_eq!(body["details"]["locationCount"], 2);
+
+	// The tag is left untouched
+	let response = env.app.get("/tags/2/stats").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+	assert_eq!(response.json::<TagStatisticsResponse>().total_locations, 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_delete_tag_in_use_forced() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login_admin().await;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	conn.interact(|conn| {
+		use db::location_tag::dsl::*;
+
+		diesel::insert_into(location_tag)
+			.values((location_id.eq(1), tag_id.eq(2)))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let response =
+		env.app.delete("/tags/2").add_query_param("force", true).await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let response = env.app.get("/tags/2/stats").await;
+
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+	let remaining = conn
+		.interact(|conn| {
+			use db::location_tag::dsl::*;
+
+			location_tag.filter(tag_id.eq(2)).count().get_result::<i64>(conn)
+		})
+		.await
+		.unwrap()
+		.unwrap();
+
+	assert_eq!(remaining, 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_all_tags_usage_count() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	conn.interact(|conn| {
+		use db::location_tag::dsl::*;
+
+		diesel::insert_into(location_tag)
+			.values(vec![
+				(location_id.eq(1), tag_id.eq(1)),
+				(location_id.eq(2), tag_id.eq(1)),
+			])
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	let response = env.app.get("/tags").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<PaginatedResponse<Vec<TagResponse>>>();
+
+	let used = body.data.iter().find(|t| t.id == 1).unwrap();
+	let unused = body.data.iter().find(|t| t.id == 2).unwrap();
+
+	assert_eq!(used.usage_count, Some(2));
+	assert_eq!(unused.usage_count, Some(0));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_tags_batch() {
+	let env = TestEnv::new().await;
+
+	let all_tags = env
+		.app
+		.get("/tags")
+		.await
+		.json::<PaginatedResponse<Vec<TagResponse>>>();
+	let tag_id = all_tags.data[0].id;
+
+	let response = env
+		.app
+		.post("/tags/batch")
+		.json(&serde_json::json!({ "ids": [tag_id, tag_id] }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<Vec<TagResponse>>();
+
+	assert_eq!(body.len(), 1);
+	assert_eq!(body[0].id, tag_id);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_tags_batch_too_many() {
+	let env = TestEnv::new().await;
+
+	let ids = (1..=101).collect::<Vec<i32>>();
+
+	let response = env
+		.app
+		.post("/tags/batch")
+		.json(&serde_json::json!({ "ids": ids }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_all_tags_paginated() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	// Add tags until there are 5 in total
+	for i in 3..=5 {
+		let create_req = CreateTagRequest {
+			name: CreateTranslationRequest {
+				nl: Some(format!("Tag {i}")),
+				en: None,
+				fr: None,
+				de: None,
+			},
+		};
+
+		let response = env.app.post("/tags").json(&create_req).await;
+
+		assert_eq!(response.status_code(), StatusCode::CREATED);
+	}
+
+	let response = env
+		.app
+		.get("/tags")
+		.add_query_param("page", 1)
+		.add_query_param("perPage", 2)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let first = response.json::<PaginatedResponse<Vec<TagResponse>>>();
+
+	assert_eq!(first.total, 5);
+	assert_eq!(first.data.len(), 2);
+	assert!(first.data[0].id < first.data[1].id);
+
+	let response = env
+		.app
+		.get("/tags")
+		.add_query_param("page", 3)
+		.add_query_param("perPage", 2)
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let last = response.json::<PaginatedResponse<Vec<TagResponse>>>();
+
+	assert_eq!(last.total, 5);
+	assert_eq!(last.data.len(), 1);
+	assert_eq!(last.data[0].name.nl, Some("Tag 5".to_string()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_tag_statistics() {
+	use diesel::prelude::*;
+
+	let env = TestEnv::new().await.login("test").await;
+
+	// The seeded locations are in Oost-Vlaanderen, add a visible one in the
+	// Netherlands and a hidden one that shouldn't be counted
+	let mut l_ids = vec![1, 2];
+	for is_visible in [true, false] {
+		let response = env
+			.app
+			.post("/locations")
+			.json(&serde_json::json!({
+				"name": format!("Amsterdam {is_visible}"),
+				"description": { "nl": "test description" },
+				"excerpt": { "nl": "test excerpt" },
+				"seatCount": 10,
+				"isReservable": true,
+				"isVisible": is_visible,
+				"street": "Dam",
+				"number": "1",
+				"zip": "1012",
+				"city": "Amsterdam",
+				"province": "Noord-Holland",
+				"country": "NL",
+				"latitude": 52.37,
+				"longitude": 4.89
+			}))
+			.await;
+
+		assert_eq!(response.status_code(), StatusCode::CREATED);
+
+		l_ids.push(response.json::<LocationResponse>().id);
+	}
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	conn.interact(move |conn| {
+		use db::location_tag::dsl::*;
+
+		let rows = l_ids
+			.into_iter()
+			.map(|l_id| (location_id.eq(l_id), tag_id.eq(1)))
+			.collect::<Vec<_>>();
+
+		diesel::insert_into(location_tag).values(rows).execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	// Tag statistics are public
+	env.app.post("/auth/logout").await;
+
+	let response = env.app.get("/tags/1/stats").await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let stats = response.json::<TagStatisticsResponse>();
+
+	assert_eq!(stats.total_locations, 3);
+	assert_eq!(stats.by_country.len(), 2);
+	assert_eq!(stats.by_country["BE"], 2);
+	assert_eq!(stats.by_country["NL"], 1);
+	assert_eq!(stats.by_province.len(), 2);
+	assert_eq!(stats.by_province["Oost-Vlaanderen"], 2);
+	assert_eq!(stats.by_province["Noord-Holland"], 1);
+
+	let response = env.app.get("/tags/999/stats").await;
+
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+}
@@ -1,9 +1,12 @@
 use axum::http::StatusCode;
 use blokmap::schemas::translation::{
+	BulkTranslationUpdateItem,
+	BulkTranslationUpdateRequest,
 	CreateTranslationRequest,
 	TranslationResponse,
 	UpdateTranslationRequest,
 };
+use translation::{Language, NewTranslation, Translation, TranslationIncludes};
 
 mod common;
 
@@ -138,3 +141,182 @@ async fn delete_translation_test() {
 
 	assert_eq!(get_response.status_code(), StatusCode::NOT_FOUND);
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn bulk_update_translations_test() {
+	let env = TestEnv::new().await.login_admin().await;
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let mut tr_ids = vec![];
+	for i in 0..5 {
+		let new_tr = NewTranslation {
+			nl:         Some(format!("vertaling {i}")),
+			en:         Some(format!("translation {i}")),
+			fr:         None,
+			de:         None,
+			created_by: 1,
+		};
+		let tr = new_tr.insert(TranslationIncludes::default(), &conn).await;
+		tr_ids.push(tr.unwrap().primitive.id);
+	}
+
+	// Update in reverse order to check the response order
+	let updates = tr_ids
+		.iter()
+		.rev()
+		.map(|id| {
+			BulkTranslationUpdateItem {
+				id:     *id,
+				update: UpdateTranslationRequest {
+					nl: None,
+					en: Some(format!("updated {id}")),
+					fr: Some(format!("mis à jour {id}")),
+					de: None,
+				},
+			}
+		})
+		.collect();
+	let request = BulkTranslationUpdateRequest { updates };
+
+	let response =
+		env.app.patch("/admin/translations/bulk").json(&request).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let body = response.json::<Vec<TranslationResponse>>();
+	let body_ids: Vec<i32> = body.iter().map(|t| t.id).collect();
+	let expected_ids: Vec<i32> = tr_ids.iter().rev().copied().collect();
+
+	assert_eq!(body_ids, expected_ids);
+
+	for id in tr_ids {
+		let tr =
+			Translation::get_by_id(id, TranslationIncludes::default(), &conn)
+				.await
+				.unwrap();
+
+		assert_eq!(tr.primitive.en, Some(format!("updated {id}")));
+		assert_eq!(tr.primitive.fr, Some(format!("mis à jour {id}")));
+		assert!(tr.primitive.nl.is_some());
+	}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn bulk_update_translations_too_many() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let updates = (1..=101)
+		.map(|id| {
+			BulkTranslationUpdateItem {
+				id,
+				update: UpdateTranslationRequest {
+					nl: None,
+					en: Some("too many".to_string()),
+					fr: None,
+					de: None,
+				},
+			}
+		})
+		.collect();
+	let request = BulkTranslationUpdateRequest { updates };
+
+	let response =
+		env.app.patch("/admin/translations/bulk").json(&request).await;
+
+	assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn resolve_translation_fallback_test() {
+	let env = TestEnv::new().await;
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let new_tr = NewTranslation {
+		nl:         Some("hallo".to_string()),
+		en:         None,
+		fr:         Some("bonjour".to_string()),
+		de:         None,
+		created_by: 1,
+	};
+	let tr =
+		new_tr.insert(TranslationIncludes::default(), &conn).await.unwrap();
+
+	// Set languages are returned as is
+	assert_eq!(tr.resolve(Language::Fr), Some("bonjour"));
+	assert_eq!(tr.resolve(Language::Nl), Some("hallo"));
+	// English is missing, so Dutch is preferred over any other language
+	assert_eq!(tr.resolve(Language::De), Some("hallo"));
+	assert_eq!(tr.resolve(Language::En), Some("hallo"));
+
+	let new_tr = NewTranslation {
+		nl:         Some("hallo".to_string()),
+		en:         Some("hello".to_string()),
+		fr:         None,
+		de:         None,
+		created_by: 1,
+	};
+	let tr =
+		new_tr.insert(TranslationIncludes::default(), &conn).await.unwrap();
+
+	// English is preferred over Dutch
+	assert_eq!(tr.resolve(Language::Fr), Some("hello"));
+	assert_eq!(tr.resolve(Language::De), Some("hello"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn resolve_translation_only_fr_test() {
+	let env = TestEnv::new().await;
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	let new_tr = NewTranslation {
+		nl:         None,
+		en:         None,
+		fr:         Some("bonjour".to_string()),
+		de:         None,
+		created_by: 1,
+	};
+	let tr =
+		new_tr.insert(TranslationIncludes::default(), &conn).await.unwrap();
+
+	for lang in [Language::Nl, Language::En, Language::Fr, Language::De] {
+		assert_eq!(tr.resolve(lang), Some("bonjour"));
+	}
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn get_translation_lang_test() {
+	let env = TestEnv::new().await.login_admin().await;
+
+	let create_req = CreateTranslationRequest {
+		nl: None,
+		en: None,
+		fr: Some("bonjour".to_string()),
+		de: None,
+	};
+
+	let response = env.app.post("/translations").json(&create_req).await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+	let created = response.json::<TranslationResponse>();
+
+	let response =
+		env.app.get(&format!("/translations/{}?lang=nl", created.id)).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	// Only the resolved text is returned when a language is requested
+	let body = response.json::<serde_json::Value>();
+	assert_eq!(body["text"], "bonjour");
+	assert!(body.get("nl").is_none());
+	assert!(body.get("fr").is_none());
+
+	let response = env.app.get(&format!("/translations/{}", created.id)).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	// The full translation is returned otherwise
+	let body = response.json::<serde_json::Value>();
+	assert!(body.get("text").is_none());
+	assert!(body["nl"].is_null());
+	assert_eq!(body["fr"], "bonjour");
+}
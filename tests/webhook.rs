@@ -0,0 +1,377 @@
+use std::time::Duration;
+
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use blokmap::schemas::webhook::{LocationWebhookPayload, WebhookResponse};
+use blokmap::webhooks::{
+	WEBHOOK_EVENT_HEADER,
+	WEBHOOK_SIGNATURE_HEADER,
+	check_webhook_host,
+};
+use hmac::{Hmac, Mac};
+use serde_json::{Value, json};
+use sha2::Sha256;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::{
+	UnboundedReceiver,
+	UnboundedSender,
+	unbounded_channel,
+};
+use url::Url;
+use webhook::WebhookEvent;
+
+mod common;
+
+use common::TestEnv;
+
+/// A request that was posted to a [`start_receiver`] server
+struct ReceivedHook {
+	headers: HeaderMap,
+	body:    Bytes,
+}
+
+#[derive(Clone)]
+struct ReceiverState {
+	sender: UnboundedSender<ReceivedHook>,
+	status: StatusCode,
+}
+
+async fn receive_hook(
+	State(state): State<ReceiverState>,
+	headers: HeaderMap,
+	body: Bytes,
+) -> StatusCode {
+	state.sender.send(ReceivedHook { headers, body }).unwrap();
+
+	state.status
+}
+
+/// Start a local server that passes on every request posted to `/hook` and
+/// answers them with `status`
+///
+/// Returns the url to point a webhook at
+async fn start_receiver(
+	status: StatusCode,
+) -> (String, UnboundedReceiver<ReceivedHook>) {
+	let (sender, receiver) = unbounded_channel();
+
+	let app = Router::new()
+		.route("/hook", post(receive_hook))
+		.with_state(ReceiverState { sender, status });
+
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+	tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+	(url, receiver)
+}
+
+/// Wait for the next request to a receiver
+async fn next_hook(
+	receiver: &mut UnboundedReceiver<ReceivedHook>,
+) -> ReceivedHook {
+	tokio::time::timeout(Duration::from_secs(10), receiver.recv())
+		.await
+		.expect("no webhook delivered in time")
+		.unwrap()
+}
+
+/// Create an authority administered by the logged in profile and move the
+/// seeded location into it
+async fn create_authority_with_location(env: &TestEnv) -> i32 {
+	use diesel::prelude::*;
+
+	let response = env
+		.app
+		.post("/authorities")
+		.json(&json!({ "name": "Faculteit Wetenschappen" }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	let a_id = response.json::<Value>()["id"].as_i64().unwrap();
+	let a_id = i32::try_from(a_id).unwrap();
+
+	let conn = env.db_guard.create_pool().get().await.unwrap();
+
+	conn.interact(move |conn| {
+		use db::location::dsl::*;
+
+		diesel::update(location.find(1))
+			.set(authority_id.eq(Some(a_id)))
+			.execute(conn)
+	})
+	.await
+	.unwrap()
+	.unwrap();
+
+	a_id
+}
+
+async fn create_webhook(
+	env: &TestEnv,
+	a_id: i32,
+	url: &str,
+	events: i64,
+) -> WebhookResponse {
+	let response = env
+		.app
+		.post(&format!("/authorities/{a_id}/webhooks"))
+		.json(&json!({ "url": url, "events": events }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	response.json::<WebhookResponse>()
+}
+
+/// Poll a webhook until its latest delivery has been recorded
+async fn wait_for_delivery(
+	env: &TestEnv,
+	a_id: i32,
+	w_id: i32,
+) -> WebhookResponse {
+	for _ in 0..50 {
+		let webhook = env
+			.app
+			.get(&format!("/authorities/{a_id}/webhooks/{w_id}"))
+			.await
+			.json::<WebhookResponse>();
+
+		if webhook.last_delivery_at.is_some() {
+			return webhook;
+		}
+
+		tokio::time::sleep(Duration::from_millis(100)).await;
+	}
+
+	panic!("delivery to webhook {w_id} was never recorded");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn webhook_crud_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let a_id = create_authority_with_location(&env).await;
+
+	let created =
+		create_webhook(&env, a_id, "https://example.com/blokmap", 6).await;
+
+	assert!(created.enabled);
+	assert_eq!(created.secret.as_ref().map(String::len), Some(64));
+	assert_eq!(created.events_labels, ["LocationApproved", "LocationRejected"]);
+	assert!(created.last_delivery_at.is_none());
+
+	let response = env.app.get(&format!("/authorities/{a_id}/webhooks")).await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	// The secret is only shown when it is created or rotated
+	let webhooks = response.json::<Vec<WebhookResponse>>();
+
+	assert_eq!(webhooks.len(), 1);
+	assert!(webhooks[0].secret.is_none());
+
+	let response = env
+		.app
+		.patch(&format!("/authorities/{a_id}/webhooks/{}", created.id))
+		.json(&json!({ "enabled": false }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let updated = response.json::<WebhookResponse>();
+
+	assert!(!updated.enabled);
+	assert!(updated.secret.is_none());
+
+	let response = env
+		.app
+		.patch(&format!("/authorities/{a_id}/webhooks/{}", created.id))
+		.json(&json!({ "rotateSecret": true }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::OK);
+
+	let updated = response.json::<WebhookResponse>();
+
+	assert!(updated.secret.is_some());
+	assert_ne!(updated.secret, created.secret);
+	assert_eq!(updated.url, created.url);
+
+	// Unknown events and schemes other than http(s) are refused
+	for body in [
+		json!({ "url": "https://example.com/blokmap", "events": 1 << 10 }),
+		json!({ "url": "ftp://example.com/blokmap", "events": 1 }),
+	] {
+		let response = env
+			.app
+			.post(&format!("/authorities/{a_id}/webhooks"))
+			.json(&body)
+			.await;
+
+		assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+	}
+
+	let response = env
+		.app
+		.delete(&format!("/authorities/{a_id}/webhooks/{}", created.id))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let response = env
+		.app
+		.get(&format!("/authorities/{a_id}/webhooks/{}", created.id))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+	// Only administrators of the authority can manage its webhooks
+	let env = env.login("test2").await;
+
+	let response = env.app.get(&format!("/authorities/{a_id}/webhooks")).await;
+
+	assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn webhook_approval_delivery_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let a_id = create_authority_with_location(&env).await;
+
+	let (url, mut receiver) = start_receiver(StatusCode::NO_CONTENT).await;
+	let webhook = create_webhook(&env, a_id, &url, 2).await;
+
+	let response = env.app.post("/locations/1/approve").await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let hook = next_hook(&mut receiver).await;
+
+	assert_eq!(hook.headers[WEBHOOK_EVENT_HEADER], "locationApproved");
+
+	// Check the signature the way a receiving system would
+	let signature = hook.headers[WEBHOOK_SIGNATURE_HEADER].to_str().unwrap();
+	let signature = signature.strip_prefix("sha256=").unwrap();
+	let signature: Vec<u8> = (0..signature.len())
+		.step_by(2)
+		.map(|i| u8::from_str_radix(&signature[i..i + 2], 16).unwrap())
+		.collect();
+
+	let secret = webhook.secret.unwrap();
+	let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+	mac.update(&hook.body);
+	mac.verify_slice(&signature).unwrap();
+
+	let payload =
+		serde_json::from_slice::<LocationWebhookPayload>(&hook.body).unwrap();
+
+	assert_eq!(payload.event, WebhookEvent::LocationApproved);
+	assert_eq!(payload.location.id, 1);
+	assert!(payload.location.approved_at.is_some());
+
+	let delivered = wait_for_delivery(&env, a_id, webhook.id).await;
+
+	assert_eq!(delivered.last_delivery_status, Some(204));
+	assert!(delivered.last_delivery_error.is_none());
+
+	// The webhook is not subscribed to rejections
+	let response = env
+		.app
+		.post("/locations/1/reject")
+		.json(&json!({ "reason": "closed" }))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+	let next =
+		tokio::time::timeout(Duration::from_secs(1), receiver.recv()).await;
+
+	assert!(next.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn webhook_failed_delivery_test() {
+	let env = TestEnv::new().await.login("test").await;
+
+	let a_id = create_authority_with_location(&env).await;
+
+	let (url, mut receiver) =
+		start_receiver(StatusCode::INTERNAL_SERVER_ERROR).await;
+	let webhook = create_webhook(&env, a_id, &url, 1).await;
+
+	let response = env
+		.app
+		.post(&format!("/authorities/{a_id}/locations"))
+		.json(&json!({
+			"name": "Test Location",
+			"seatCount": 10,
+			"isReservable": true,
+			"isVisible": true,
+			"street": "Test Street",
+			"number": "123",
+			"zip": "9000",
+			"city": "Gent",
+			"province": "Oost-Vlaanderen",
+			"country": "BE",
+			"latitude": 51.05,
+			"longitude": 3.72
+		}))
+		.await;
+
+	assert_eq!(response.status_code(), StatusCode::CREATED);
+
+	// The first attempt is retried twice
+	for _ in 0..3 {
+		let hook = next_hook(&mut receiver).await;
+
+		assert_eq!(hook.headers[WEBHOOK_EVENT_HEADER], "locationCreated");
+	}
+
+	let delivered = wait_for_delivery(&env, a_id, webhook.id).await;
+
+	assert_eq!(delivered.last_delivery_status, Some(500));
+	assert!(delivered.last_delivery_error.is_some());
+}
+
+#[test]
+fn webhook_host_check_test() {
+	let check = |url: &str, allow_private_hosts: bool| {
+		let url = url.parse::<Url>().unwrap();
+
+		check_webhook_host(&url, allow_private_hosts).is_ok()
+	};
+
+	for url in [
+		"https://example.com/hook",
+		"https://1.1.1.1/hook",
+		"https://[2606:4700:4700::1111]/hook",
+	] {
+		assert!(check(url, false), "{url} should be allowed");
+	}
+
+	for url in [
+		"http://localhost/hook",
+		"http://api.localhost/hook",
+		"http://127.0.0.1:8000/hook",
+		"http://10.0.0.1/hook",
+		"http://172.16.0.1/hook",
+		"http://192.168.1.1/hook",
+		"http://169.254.169.254/latest/meta-data",
+		"http://100.64.0.1/hook",
+		"http://0.0.0.0/hook",
+		"http://[::1]/hook",
+		"http://[fe80::1]/hook",
+		"http://[fd00::1]/hook",
+		"http://[::ffff:127.0.0.1]/hook",
+	] {
+		assert!(!check(url, false), "{url} should be refused");
+		assert!(check(url, true), "{url} should be allowed explicitly");
+	}
+}